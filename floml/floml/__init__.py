@@ -5,6 +5,6 @@ Statistical analysis and machine learning for flood prediction.
 
 __version__ = "0.1.0"
 
-from floml import db, regression, correlation, precursors
+from floml import db, regression, correlation, precursors, response_curves
 
-__all__ = ["db", "regression", "correlation", "precursors"]
+__all__ = ["db", "regression", "correlation", "precursors", "response_curves"]
@@ -0,0 +1,183 @@
+"""Empirical precipitation-to-rise response curves.
+
+Fits, per basin/gauge pair, how far the gauge rose after a given 24-hour
+basin rainfall and how long after the rain it crested, from the events in
+flood_analysis.events. The result is a short lookup table written to
+flood_analysis.precip_response_curves, which the Rust daemon reads to log
+an expected tributary response as soon as a rain event is under way
+(flomon_service/src/analysis/precip_response.rs).
+
+Basin precipitation follows the daemon's rollups: precip_1hr_in bucketed by
+clock hour (max per hour), summed over 24 hours per station, averaged over
+the basin's ASOS stations.
+"""
+
+import tomllib
+import logging
+from dataclasses import dataclass
+from datetime import timedelta
+from pathlib import Path
+from typing import Dict, List, Optional, Tuple
+
+import numpy as np
+import pandas as pd
+from sqlalchemy import text
+
+logger = logging.getLogger(__name__)
+
+# Fewer catalog events than this and no curve is written
+MIN_EVENTS = 5
+
+# Most lookup points per curve
+MAX_POINTS = 5
+
+# How far before an event's peak to look for the rain that caused it
+LOOKBACK_HOURS = 96
+
+
+@dataclass
+class ResponseCurve:
+    """A fitted lookup table for one basin/gauge pair."""
+    basin: str
+    site_code: str
+    precip_24h_in: List[float]
+    rise_ft: List[float]
+    lag_hours: float
+    event_count: int
+
+    def __str__(self) -> str:
+        points = ", ".join(f"{p:.2f} in -> {r:.1f} ft" for p, r in zip(self.precip_24h_in, self.rise_ft))
+        return (
+            f"{self.basin} -> {self.site_code} ({self.event_count} events)\n"
+            f"  {points}\n"
+            f"  Lag: {self.lag_hours:.0f} hours"
+        )
+
+
+def basin_gauges(asos_toml: Path) -> Dict[Tuple[str, str], List[str]]:
+    """Map (basin, upstream_gauge) to its ASOS station IDs from iem_asos.toml."""
+    with open(asos_toml, "rb") as f:
+        config = tomllib.load(f)
+
+    pairs: Dict[Tuple[str, str], List[str]] = {}
+    for station in config.get("stations", []):
+        basin = station.get("basin")
+        gauge = station.get("upstream_gauge")
+        if basin and gauge:
+            pairs.setdefault((basin, gauge), []).append(station["station_id"])
+    return pairs
+
+
+def basin_precip_24h(engine, station_ids: List[str], start, end) -> pd.Series:
+    """Hourly series of the basin-mean 24-hour precipitation total."""
+    hourly = pd.read_sql(
+        text("""
+            SELECT station_id, date_trunc('hour', observation_time) AS hour,
+                   MAX(precip_1hr_in) AS precip_in
+            FROM asos_observations
+            WHERE station_id = ANY(:stations)
+              AND observation_time BETWEEN :start AND :end
+              AND precip_1hr_in IS NOT NULL
+            GROUP BY station_id, hour
+        """),
+        engine,
+        params={"stations": station_ids, "start": start - timedelta(hours=24), "end": end},
+    )
+    if hourly.empty:
+        return pd.Series(dtype=float)
+
+    per_station = hourly.pivot(index="hour", columns="station_id", values="precip_in")
+    per_station = per_station.asfreq("1h").fillna(0.0)
+    return per_station.rolling(24, min_periods=1).sum().mean(axis=1)
+
+
+def event_samples(engine, basin: str, site_code: str, station_ids: List[str]) -> pd.DataFrame:
+    """One row per catalog event: wettest 24h before the peak, rise, lag."""
+    events = pd.read_sql(
+        text("""
+            SELECT id, event_start, event_peak, total_rise_ft
+            FROM flood_analysis.events
+            WHERE site_code = :site AND total_rise_ft IS NOT NULL
+            ORDER BY event_peak
+        """),
+        engine,
+        params={"site": site_code},
+    )
+
+    samples = []
+    for _, event in events.iterrows():
+        peak = pd.Timestamp(event["event_peak"])
+        precip = basin_precip_24h(engine, station_ids, peak - timedelta(hours=LOOKBACK_HOURS), peak)
+        if precip.empty or precip.max() <= 0:
+            logger.debug(f"Event {event['id']}: no {basin} precipitation on record")
+            continue
+        wettest = precip.idxmax()
+        samples.append({
+            "event_id": event["id"],
+            "precip_24h_in": float(precip.max()),
+            "rise_ft": float(event["total_rise_ft"]),
+            "lag_hours": (peak - wettest).total_seconds() / 3600.0,
+        })
+
+    return pd.DataFrame(samples)
+
+
+def fit_curve(basin: str, site_code: str, samples: pd.DataFrame) -> Optional[ResponseCurve]:
+    """Median rise per precipitation quantile bin, forced non-decreasing.
+
+    Returns None with fewer than MIN_EVENTS samples.
+    """
+    if len(samples) < MIN_EVENTS:
+        return None
+
+    samples = samples.sort_values("precip_24h_in")
+    bins = np.array_split(np.arange(len(samples)), min(MAX_POINTS, len(samples) // 2))
+
+    precip, rise = [], []
+    for rows in bins:
+        chunk = samples.iloc[rows]
+        p = float(chunk["precip_24h_in"].median())
+        r = float(chunk["rise_ft"].median())
+        # The daemon needs strictly ascending precipitation
+        if precip and p <= precip[-1]:
+            continue
+        precip.append(round(p, 3))
+        rise.append(round(max(r, rise[-1] if rise else 0.0), 2))
+
+    if len(precip) < 2:
+        return None
+
+    return ResponseCurve(
+        basin=basin,
+        site_code=site_code,
+        precip_24h_in=precip,
+        rise_ft=rise,
+        lag_hours=round(float(samples["lag_hours"].clip(lower=0).median()), 1),
+        event_count=len(samples),
+    )
+
+
+def store_curve(engine, curve: ResponseCurve) -> None:
+    """Replace the stored curve for this basin/gauge pair."""
+    with engine.begin() as conn:
+        conn.execute(
+            text("""
+                INSERT INTO flood_analysis.precip_response_curves
+                (basin, site_code, precip_24h_in, rise_ft, lag_hours, event_count, fitted_at)
+                VALUES (:basin, :site, :precip, :rise, :lag, :events, NOW())
+                ON CONFLICT (basin, site_code) DO UPDATE
+                SET precip_24h_in = EXCLUDED.precip_24h_in,
+                    rise_ft = EXCLUDED.rise_ft,
+                    lag_hours = EXCLUDED.lag_hours,
+                    event_count = EXCLUDED.event_count,
+                    fitted_at = EXCLUDED.fitted_at
+            """),
+            {
+                "basin": curve.basin,
+                "site": curve.site_code,
+                "precip": curve.precip_24h_in,
+                "rise": curve.rise_ft,
+                "lag": curve.lag_hours,
+                "events": curve.event_count,
+            },
+        )
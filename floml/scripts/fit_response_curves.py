@@ -0,0 +1,67 @@
+#!/usr/bin/env python3
+"""Refit precipitation-to-rise response curves from the event catalog.
+
+For every basin/gauge pair in iem_asos.toml (stations with both `basin`
+and `upstream_gauge`), fits a 24-hour basin precipitation -> gauge rise
+lookup table and stores it in flood_analysis.precip_response_curves, where
+the monitoring daemon picks it up on its next cycle.
+
+Run after the event catalog is updated, e.g. from cron:
+    python3 fit_response_curves.py
+    python3 fit_response_curves.py --dry-run
+"""
+
+import sys
+import logging
+import argparse
+from pathlib import Path
+
+from floml.db import get_engine, verify_schemas
+from floml.response_curves import basin_gauges, event_samples, fit_curve, store_curve, MIN_EVENTS
+
+logging.basicConfig(
+    level=logging.INFO,
+    format='%(asctime)s - %(name)s - %(levelname)s - %(message)s'
+)
+logger = logging.getLogger(__name__)
+
+DEFAULT_ASOS_TOML = Path(__file__).resolve().parents[2] / "flomon_service" / "iem_asos.toml"
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Fit precipitation-to-rise response curves')
+    parser.add_argument('--asos-toml', type=Path, default=DEFAULT_ASOS_TOML, help='Path to iem_asos.toml')
+    parser.add_argument('--dry-run', action='store_true', help='Print curves without storing them')
+    args = parser.parse_args()
+
+    try:
+        engine = get_engine()
+        verify_schemas(engine)
+
+        pairs = basin_gauges(args.asos_toml)
+        if not pairs:
+            print(f"No stations with basin and upstream_gauge in {args.asos_toml}")
+            return
+
+        stored = 0
+        for (basin, site_code), station_ids in sorted(pairs.items()):
+            samples = event_samples(engine, basin, site_code, station_ids)
+            curve = fit_curve(basin, site_code, samples)
+            if curve is None:
+                print(f"⚠ {basin} -> {site_code}: {len(samples)} usable events (need {MIN_EVENTS}), skipped")
+                continue
+
+            print(f"✓ {curve}")
+            if not args.dry_run:
+                store_curve(engine, curve)
+                stored += 1
+
+        print(f"\n{stored} curve(s) stored" + (" (dry run)" if args.dry_run else ""))
+
+    except Exception as e:
+        logger.error(f"Fitting failed: {e}")
+        sys.exit(1)
+
+
+if __name__ == "__main__":
+    main()
@@ -52,7 +52,7 @@ def format_value(value: float, unit: str) -> str:
         return "N/A"
     if unit == "ft":
         return f"{value:.2f} ft"
-    elif unit == "cfs":
+    elif unit in ("cfs", "ft3/s"):
         return f"{value:,.0f} cfs"
     elif unit == "in":
         return f"{value:.2f} in"
@@ -54,7 +54,13 @@ def fetch_all_zones() -> List[Dict[str, Any]]:
             if response.status_code == 200:
                 zones.append(response.json())
             else:
-                zones.append({"zone_id": zone_id, "error": True})
+                # request_id finds the failure in the flomon logs / api_errors
+                zones.append({
+                    "zone_id": zone_id,
+                    "error": True,
+                    "status": response.status_code,
+                    "request_id": response.headers.get("X-Request-Id"),
+                })
         except Exception:
             zones.append({"zone_id": zone_id, "error": True})
     return zones
@@ -183,6 +189,10 @@ def draw_zone_box(win, y: int, x: int, width: int, height: int, zone: Dict[str,
             title = f" Zone {zone_id} "
             win.addstr(y, x + 2, title, curses.A_BOLD | color_pair)
             win.addstr(y + 2, x + 2, "ERROR", (curses.color_pair(3) or 0) | curses.A_BOLD)
+            if zone.get('status') and height > 4:
+                win.addstr(y + 3, x + 2, f"HTTP {zone['status']}"[:width - 4], color_pair)
+            if zone.get('request_id') and height > 5:
+                win.addstr(y + 4, x + 2, f"id {zone['request_id']}"[:width - 4], color_pair)
         except curses.error:
             pass
         return
@@ -0,0 +1,241 @@
+//! Ingest and database throughput.
+//!
+//! ```text
+//! cargo bench                       # parsing + grouping
+//! cargo bench -- db_insert          # inserts only (needs DATABASE_URL)
+//! ```
+//!
+//! Payloads are synthetic but shaped like the real responses: IV JSON with
+//! stage and discharge series per site, and IEM ASOS CSV with the 21+
+//! columns `parse_asos_csv` expects.
+//!
+//! The insert group works on `flomon_bench.gauge_readings`, a copy of
+//! `usgs_raw.gauge_readings` (same indexes and conflict key) created for
+//! the run and dropped afterwards, so production tables are never touched.
+//! It is skipped when the database is not reachable.
+
+use chrono::{Duration, TimeZone, Utc};
+use criterion::{BatchSize, Criterion, Throughput, criterion_group, criterion_main};
+use postgres::Client;
+use std::hint::black_box;
+
+use flomon_service::analysis::groupings::{group_by_site, group_by_zone};
+use flomon_service::db::{self, bulk::{self, BulkTable}};
+use flomon_service::ingest::{iem, usgs};
+use flomon_service::model::GaugeReading;
+use flomon_service::zones;
+
+/// Values per series for the parsing benchmarks
+const POINTS: usize = 5000;
+
+/// Rows per run for the insert benchmarks
+const DB_ROWS: usize = 10_000;
+
+/// Scratch schema used by the insert benchmarks
+const BENCH_SCHEMA: &str = "flomon_bench";
+
+/// Site codes cycled through by the generators
+const SITES: [&str; 8] = [
+    "05568500", "05567500", "05568000", "05557000",
+    "05586100", "05552500", "05543010", "05536995",
+];
+
+// ============================================================================
+// Synthetic payloads
+// ============================================================================
+
+/// IV JSON with a stage and a discharge series per site, `points` values each
+fn synthetic_iv_json(sites: usize, points: usize) -> String {
+    let start = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+    let mut series = Vec::with_capacity(sites * 2);
+    for s in 0..sites {
+        let site = SITES[s % SITES.len()];
+        for (param, unit, base) in [("00065", "ft", 14.0), ("00060", "ft3/s", 12_000.0)] {
+            let values: Vec<serde_json::Value> = (0..points)
+                .map(|i| serde_json::json!({
+                    "value": format!("{:.2}", base + (i as f64 * 0.05).sin() * base * 0.05),
+                    "qualifiers": ["P"],
+                    "dateTime": (start + Duration::minutes(15 * i as i64)).to_rfc3339(),
+                }))
+                .collect();
+            series.push(serde_json::json!({
+                "sourceInfo": {
+                    "siteName": format!("ILLINOIS RIVER SITE {}", s),
+                    "siteCode": [{ "value": site }],
+                },
+                "variable": {
+                    "variableCode": [{ "value": param }],
+                    "unit": { "unitCode": unit },
+                    "noDataValue": -999999.0,
+                },
+                "values": [{ "value": values }],
+            }));
+        }
+    }
+    serde_json::json!({ "value": { "timeSeries": series } }).to_string()
+}
+
+/// IEM ASOS CSV with `rows` observations (header included)
+fn synthetic_asos_csv(rows: usize) -> String {
+    let start = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+    let mut csv = String::from(
+        "station,valid,tmpf,dwpf,relh,drct,sknt,p01i,alti,mslp,vsby,gust,\
+         skyc1,skyc2,skyc3,skyc4,skyl1,skyl2,skyl3,skyl4,wxcodes\n",
+    );
+    for i in 0..rows {
+        let time = start + Duration::minutes(i as i64);
+        let precip = if i % 7 == 0 { "0.02" } else { "0.00" };
+        csv.push_str(&format!(
+            "PIA,{},{:.1},{:.1},{:.1},{},{},{},29.92,1013.2,10.00,null,BKN,OVC,null,null,2500,8000,null,null,{}\n",
+            time.format("%Y-%m-%d %H:%M"),
+            60.0 + (i % 20) as f64 * 0.5,
+            50.0 + (i % 10) as f64 * 0.5,
+            70.0 + (i % 25) as f64,
+            (i * 10) % 360,
+            i % 25,
+            precip,
+            if i % 7 == 0 { "-RA" } else { "null" },
+        ));
+    }
+    csv
+}
+
+/// Flat readings: `points` 15-minute stage values for each of `sites` sites
+fn synthetic_readings(sites: usize, points: usize) -> Vec<GaugeReading> {
+    let start = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+    (0..sites)
+        .flat_map(|s| (0..points).map(move |i| (s, i)))
+        .map(|(s, i)| GaugeReading {
+            site_code: SITES[s % SITES.len()].to_string(),
+            site_name: format!("ILLINOIS RIVER SITE {}", s),
+            parameter_code: "00065".to_string(),
+            unit: "ft".to_string(),
+            value: 14.0 + (i as f64 * 0.05).sin(),
+            datetime: (start + Duration::minutes(15 * i as i64)).fixed_offset(),
+            qualifier: "P".to_string(),
+            original_unit: None,
+        })
+        .collect()
+}
+
+// ============================================================================
+// Ingest benchmarks (no database)
+// ============================================================================
+
+fn parse_benches(c: &mut Criterion) {
+    let sites = SITES.len();
+    let iv = synthetic_iv_json(sites, POINTS);
+    let asos = synthetic_asos_csv(POINTS);
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Elements((sites * 2 * POINTS) as u64));
+    group.bench_function("usgs::parse_iv_response", |b| b.iter(|| usgs::parse_iv_response(black_box(&iv))));
+    group.bench_function("usgs::parse_iv_response_all", |b| b.iter(|| usgs::parse_iv_response_all(black_box(&iv))));
+    group.throughput(Throughput::Elements(POINTS as u64));
+    group.bench_function("iem::parse_asos_csv", |b| b.iter(|| iem::parse_asos_csv(black_box(&asos), "PIA")));
+    group.finish();
+}
+
+fn grouping_benches(c: &mut Criterion) {
+    let readings = synthetic_readings(SITES.len(), POINTS);
+
+    let mut group = c.benchmark_group("groupings");
+    group.throughput(Throughput::Elements(readings.len() as u64));
+    group.bench_function("group_by_site", |b| {
+        b.iter_batched(|| readings.clone(), group_by_site, BatchSize::LargeInput)
+    });
+    if let Ok(zones_config) = zones::load_zones_default() {
+        group.bench_function("group_by_zone", |b| {
+            b.iter_batched(|| readings.clone(), |r| group_by_zone(r, &zones_config), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+// ============================================================================
+// Database benchmarks (scratch schema)
+// ============================================================================
+
+const BENCH_INSERT: &str =
+    "INSERT INTO flomon_bench.gauge_readings
+     (site_code, parameter_code, unit, value, reading_time, qualifier, original_unit)
+     VALUES ($1, $2, $3, $4, $5, $6, $7)
+     ON CONFLICT (site_code, parameter_code, reading_time) DO NOTHING";
+
+/// Insert `readings` one statement per row, as the daemon's warehouse path does
+fn insert_rows(client: &mut Client, readings: &[GaugeReading]) -> u64 {
+    let mut inserted = 0;
+    for reading in readings {
+        let value = rust_decimal::Decimal::from_f64_retain(reading.value).expect("finite value");
+        inserted += client.execute(BENCH_INSERT, &[
+            &reading.site_code,
+            &reading.parameter_code,
+            &reading.unit,
+            &value,
+            &reading.datetime,
+            &reading.qualifier,
+            &reading.original_unit,
+        ]).expect("bench insert");
+    }
+    inserted
+}
+
+/// `insert_rows` through `db::bulk`'s staged binary COPY instead
+fn copy_rows(client: &mut Client, readings: &[GaugeReading]) -> u64 {
+    let table = BulkTable { target: "flomon_bench.gauge_readings", ..bulk::GAUGE_READINGS };
+    bulk::insert_gauge_readings_into(client, &table, readings).expect("bench copy") as u64
+}
+
+fn truncate(client: &mut Client) {
+    client.batch_execute(&format!("TRUNCATE {}.gauge_readings", BENCH_SCHEMA))
+        .expect("truncate scratch table");
+}
+
+/// Insert throughput against a scratch copy of `usgs_raw.gauge_readings`.
+///
+/// Measures new rows (table truncated before each run, outside the timing)
+/// and the same rows again (every one hits the conflict key, the common
+/// case on a steady-state poll), row by row and through the bulk COPY path.
+fn db_benches(c: &mut Criterion) {
+    let Ok(mut client) = db::connect_simple() else {
+        eprintln!("db_insert: database not reachable, skipping");
+        return;
+    };
+    client.batch_execute(&format!(
+        "DROP SCHEMA IF EXISTS {schema} CASCADE;
+         CREATE SCHEMA {schema};
+         CREATE TABLE {schema}.gauge_readings (LIKE usgs_raw.gauge_readings INCLUDING ALL);",
+        schema = BENCH_SCHEMA,
+    )).expect("create scratch schema");
+
+    let points = DB_ROWS.div_ceil(SITES.len());
+    let readings = synthetic_readings(SITES.len(), points);
+    let client = std::cell::RefCell::new(client);
+
+    let mut group = c.benchmark_group("db_insert");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(readings.len() as u64));
+    for (name, insert) in [
+        ("rows", insert_rows as fn(&mut Client, &[GaugeReading]) -> u64),
+        ("copy", copy_rows),
+    ] {
+        group.bench_function(format!("{} (new rows)", name), |b| {
+            b.iter_batched(
+                || truncate(&mut client.borrow_mut()),
+                |()| insert(&mut client.borrow_mut(), &readings),
+                BatchSize::PerIteration,
+            )
+        });
+        group.bench_function(format!("{} (all conflicts)", name), |b| {
+            b.iter(|| insert(&mut client.borrow_mut(), &readings))
+        });
+    }
+    group.finish();
+
+    client.into_inner()
+        .batch_execute(&format!("DROP SCHEMA IF EXISTS {} CASCADE", BENCH_SCHEMA))
+        .expect("drop scratch schema");
+}
+
+criterion_group!(benches, parse_benches, grouping_benches, db_benches);
+criterion_main!(benches);
@@ -0,0 +1,53 @@
+-- Property Freeboard Timeseries
+-- Migration 007: Persist derived freeboard as a first-class timeseries
+--
+-- Purpose: Freeboard (critical property elevation minus water surface
+--          elevation) is computed by the daemon each time the source pool
+--          elevation is ingested. Storing it lets dashboards chart it,
+--          alerting query it directly (freeboard < 2 ft), and exports treat
+--          it like any other parameter.
+--
+-- Datum: water surface and critical elevations are both NGVD29 (feet).
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE SCHEMA IF NOT EXISTS flood_analysis;
+
+CREATE TABLE IF NOT EXISTS flood_analysis.freeboard (
+    point_name VARCHAR(100) NOT NULL,          -- Critical point label from flomon.toml
+    timestamp TIMESTAMPTZ NOT NULL,            -- Time of the source water surface sample
+    
+    water_surface_ft NUMERIC(8, 2) NOT NULL,   -- Pool elevation (NGVD29)
+    critical_elevation_ft NUMERIC(8, 2) NOT NULL,
+    freeboard_ft NUMERIC(8, 2) NOT NULL,       -- Negative when the point is underwater
+    
+    source_timeseries_id VARCHAR(200) NOT NULL, -- CWMS timeseries the surface came from
+    computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    
+    PRIMARY KEY (point_name, timestamp)
+);
+
+CREATE INDEX IF NOT EXISTS idx_freeboard_timestamp ON flood_analysis.freeboard(timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_freeboard_low ON flood_analysis.freeboard(point_name, timestamp DESC)
+    WHERE freeboard_ft < 2.0;
+
+COMMENT ON TABLE flood_analysis.freeboard IS
+    'Derived freeboard per critical property elevation. One row per point per source pool elevation sample.';
+
+-- Latest freeboard per point
+CREATE OR REPLACE VIEW flood_analysis.freeboard_latest AS
+SELECT DISTINCT ON (point_name)
+    point_name,
+    timestamp,
+    water_surface_ft,
+    critical_elevation_ft,
+    freeboard_ft
+FROM flood_analysis.freeboard
+ORDER BY point_name, timestamp DESC;
+
+GRANT ALL PRIVILEGES ON flood_analysis.freeboard TO flopro_admin;
+GRANT SELECT ON flood_analysis.freeboard_latest TO flopro_admin;
+
+COMMIT;
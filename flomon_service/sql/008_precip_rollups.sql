@@ -0,0 +1,54 @@
+-- Precipitation Accumulation Rollups
+-- Migration 008: Store rolling precipitation windows per station and basin
+--
+-- Purpose: The daemon computes 1/3/6/24/72-hour precipitation totals for
+--          every ASOS station and every basin once per polling cycle. The
+--          rules engine, risk score, digest and API read these rows instead
+--          of each running their own SUM over asos_observations.
+--
+-- Station totals bucket precip_1hr_in by clock hour (max per hour) so
+-- special observations don't double count. Basin totals are the mean of
+-- member station totals; max_in is the wettest member station.
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS asos_precip_rollups (
+    scope TEXT NOT NULL CHECK (scope IN ('station', 'basin')),
+    scope_id TEXT NOT NULL,                    -- Station ID (KPIA) or basin name
+    window_hours INTEGER NOT NULL,             -- 1, 3, 6, 24, 72
+    window_end TIMESTAMPTZ NOT NULL,           -- Cycle time the window ends at
+    
+    total_in DOUBLE PRECISION NOT NULL,        -- Station total or basin mean
+    max_in DOUBLE PRECISION NOT NULL,          -- Wettest member (basin) or total (station)
+    sample_count INTEGER NOT NULL,             -- Hourly buckets (station) or member stations (basin)
+    
+    computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    
+    PRIMARY KEY (scope, scope_id, window_hours, window_end)
+);
+
+CREATE INDEX IF NOT EXISTS idx_precip_rollups_latest
+    ON asos_precip_rollups(scope, scope_id, window_hours, window_end DESC);
+
+COMMENT ON TABLE asos_precip_rollups IS
+    'Rolling precipitation accumulations per ASOS station and basin, written once per daemon cycle';
+
+-- Latest rollup per station/basin and window
+CREATE OR REPLACE VIEW asos_precip_rollups_latest AS
+SELECT DISTINCT ON (scope, scope_id, window_hours)
+    scope,
+    scope_id,
+    window_hours,
+    window_end,
+    total_in,
+    max_in,
+    sample_count
+FROM asos_precip_rollups
+ORDER BY scope, scope_id, window_hours, window_end DESC;
+
+GRANT ALL PRIVILEGES ON asos_precip_rollups TO flopro_admin;
+GRANT SELECT ON asos_precip_rollups_latest TO flopro_admin;
+
+COMMIT;
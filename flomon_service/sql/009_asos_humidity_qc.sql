@@ -0,0 +1,39 @@
+-- ASOS Relative Humidity QC
+-- Migration 009: Record derived-field QC on asos_observations
+--
+-- Purpose: IEM relative humidity occasionally exceeds 100% or disagrees
+--          with the reported temperature/dewpoint. At ingest the daemon
+--          recomputes RH from temp/dewpoint and stores the computed value in
+--          relative_humidity. The value IEM reported is kept alongside it,
+--          with a flag describing how the two compared, so the melt and ET
+--          models read trustworthy humidity and the discrepancy stays
+--          auditable.
+--
+-- Apparent temperature (wind chill / heat index) is derived from these same
+-- fields and is deliberately not stored.
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+ALTER TABLE asos_observations
+    ADD COLUMN IF NOT EXISTS relative_humidity_reported DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS rh_qc_flag TEXT
+        CHECK (rh_qc_flag IN ('consistent', 'computed', 'inconsistent',
+                              'out_of_range', 'dewpoint_above_temp', 'unverifiable'));
+
+COMMENT ON COLUMN asos_observations.relative_humidity IS
+    'Relative humidity (%) after QC: computed from temp/dewpoint when both are present';
+
+COMMENT ON COLUMN asos_observations.relative_humidity_reported IS
+    'Relative humidity (%) as reported by IEM, before QC';
+
+COMMENT ON COLUMN asos_observations.rh_qc_flag IS
+    'How reported RH compared to RH computed from temp/dewpoint';
+
+-- Rows whose reported humidity could not be trusted
+CREATE INDEX IF NOT EXISTS idx_asos_obs_rh_flagged
+    ON asos_observations(station_id, observation_time DESC)
+    WHERE rh_qc_flag IN ('inconsistent', 'out_of_range', 'dewpoint_above_temp');
+
+COMMIT;
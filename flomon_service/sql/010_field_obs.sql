@@ -0,0 +1,53 @@
+-- Manual Field Observations
+-- Migration 010: High-water marks, crest stakes, photos, sandbagging actions
+--
+-- Purpose: Post-event documentation collected by hand is stored next to the
+--          sensor data it corroborates. Each row is tied to a station (or a
+--          coordinate) and optionally to a flood_analysis.events row.
+--
+-- Written by: flomon field-obs add, POST /field_obs
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE SCHEMA IF NOT EXISTS flood_analysis;
+
+CREATE TABLE IF NOT EXISTS flood_analysis.field_obs (
+    id BIGSERIAL PRIMARY KEY,
+    kind VARCHAR(20) NOT NULL
+        CHECK (kind IN ('high_water_mark', 'crest_stake', 'photo', 'sandbagging')),
+    observed_at TIMESTAMPTZ NOT NULL,
+    
+    -- Where: nearest station and/or surveyed coordinates
+    site_code VARCHAR(50),                     -- USGS site code or CWMS location
+    latitude DOUBLE PRECISION,
+    longitude DOUBLE PRECISION,
+    
+    -- Which event this documents, if known
+    event_id INTEGER REFERENCES flood_analysis.events(id),
+    
+    -- Water level (HWM elevation or crest stake reading)
+    value_ft DOUBLE PRECISION,
+    datum VARCHAR(20),                         -- NGVD29, NAVD88, gauge
+    
+    media_ref TEXT,                            -- Photo path or URL
+    notes TEXT,
+    observer VARCHAR(100),
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    
+    CHECK (site_code IS NOT NULL OR (latitude IS NOT NULL AND longitude IS NOT NULL)),
+    CHECK (kind NOT IN ('high_water_mark', 'crest_stake') OR (value_ft IS NOT NULL AND datum IS NOT NULL)),
+    CHECK (kind <> 'photo' OR media_ref IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_field_obs_site_time ON flood_analysis.field_obs(site_code, observed_at DESC);
+CREATE INDEX IF NOT EXISTS idx_field_obs_event ON flood_analysis.field_obs(event_id) WHERE event_id IS NOT NULL;
+
+COMMENT ON TABLE flood_analysis.field_obs IS
+    'Manual flood observations (high-water marks, crest stakes, photos, sandbagging) tied to stations and events';
+
+GRANT ALL PRIVILEGES ON flood_analysis.field_obs TO flopro_admin;
+GRANT USAGE, SELECT ON SEQUENCE flood_analysis.field_obs_id_seq TO flopro_admin;
+
+COMMIT;
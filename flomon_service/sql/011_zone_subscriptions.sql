@@ -0,0 +1,32 @@
+-- Zone Notification Subscriptions
+-- Migration 011: Per-recipient, per-zone subscriptions with severity floors
+--
+-- Purpose: Notification recipients follow individual zones (0-6, zones.toml)
+--          rather than the whole basin. Rows here are managed with
+--          `flomon subscribe` and merged with [[subscriptions]] entries from
+--          flomon.toml; a row overrides the config floor for the same
+--          recipient and zone.
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS zone_subscriptions (
+    recipient TEXT NOT NULL,                   -- Email, phone, or channel handle
+    zone_id SMALLINT NOT NULL CHECK (zone_id BETWEEN 0 AND 6),
+    min_severity TEXT NOT NULL
+        CHECK (min_severity IN ('action', 'flood', 'moderate', 'major')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    
+    PRIMARY KEY (recipient, zone_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_zone_subscriptions_zone ON zone_subscriptions(zone_id);
+
+COMMENT ON TABLE zone_subscriptions IS
+    'Zone-level notification subscriptions managed by flomon subscribe';
+
+GRANT ALL PRIVILEGES ON zone_subscriptions TO flopro_admin;
+
+COMMIT;
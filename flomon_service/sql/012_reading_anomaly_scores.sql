@@ -0,0 +1,41 @@
+-- Reading Anomaly Scores
+-- Migration 012: Median/MAD robust z-scores for USGS readings, computed at ingest
+--
+-- Purpose: The daemon scores each new gauge reading against the previous
+--          72 hours for the same site and parameter and stores the result
+--          here. `suspect` rows are values to double-check before trusting
+--          them in alerts; the heavier offline Python analysis is unchanged.
+--          Scores live beside the raw readings rather than in them, so the
+--          raw table stays exactly what USGS published.
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS usgs_raw.reading_anomaly_scores (
+    site_code VARCHAR(8) NOT NULL,
+    parameter_code VARCHAR(5) NOT NULL,
+    reading_time TIMESTAMPTZ NOT NULL,
+    
+    value DOUBLE PRECISION NOT NULL,           -- Reading that was scored
+    window_median DOUBLE PRECISION NOT NULL,   -- Median of the history window
+    window_mad DOUBLE PRECISION NOT NULL,      -- Median absolute deviation
+    robust_z DOUBLE PRECISION NOT NULL,        -- (value - median) / (1.4826 * MAD), floored scale
+    sample_count INTEGER NOT NULL,             -- History readings in the window
+    suspect BOOLEAN NOT NULL,                  -- |robust_z| >= threshold
+    
+    scored_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    
+    PRIMARY KEY (site_code, parameter_code, reading_time)
+);
+
+CREATE INDEX IF NOT EXISTS idx_reading_anomaly_suspect
+    ON usgs_raw.reading_anomaly_scores(reading_time DESC)
+    WHERE suspect;
+
+COMMENT ON TABLE usgs_raw.reading_anomaly_scores IS
+    'Real-time median/MAD anomaly scores for gauge readings (see analysis::anomaly)';
+
+GRANT ALL PRIVILEGES ON usgs_raw.reading_anomaly_scores TO flopro_admin;
+
+COMMIT;
@@ -0,0 +1,26 @@
+-- Unit Normalization
+-- Migration 013: Record the source unit of readings converted at ingest
+--
+-- Purpose: Parsers now convert every reading to one canonical unit per
+--          parameter (ft, ft3/s, in, deg C; see ingest::units) before it
+--          is stored, so `unit` is always canonical. When the source
+--          reported something else (cfs, kcfs, m, cms, ...), the original
+--          spelling is kept in `original_unit`; NULL means the source
+--          already used the canonical unit.
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+ALTER TABLE usgs_raw.gauge_readings
+    ADD COLUMN IF NOT EXISTS original_unit VARCHAR(16);
+
+ALTER TABLE usace.cwms_timeseries
+    ADD COLUMN IF NOT EXISTS original_unit VARCHAR(16);
+
+COMMENT ON COLUMN usgs_raw.gauge_readings.original_unit IS
+    'Unit reported by USGS when it differed from the canonical unit (value was converted)';
+COMMENT ON COLUMN usace.cwms_timeseries.original_unit IS
+    'Unit reported by CWMS when it differed from the canonical unit (value was converted)';
+
+COMMIT;
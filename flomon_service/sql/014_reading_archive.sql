@@ -0,0 +1,75 @@
+-- Long-Term Reading Archive
+-- Migration 014: Compressed day-bucketed archive for USGS readings
+--
+-- Purpose: Readings older than the hot retention window (365 days by
+--          default) are moved out of usgs_raw.gauge_readings by
+--          `flomon archive`. Each archive row holds one site/parameter/UTC
+--          day with the readings packed into arrays; arrays that size are
+--          TOASTed, so Postgres compresses them transparently.
+--
+--          ZSTD is not available for TOAST compression in Postgres; lz4
+--          (Postgres 14+, server built --with-lz4) is used when present and
+--          the default pglz otherwise. Either way the archive is several
+--          times smaller than the equivalent hot rows plus their indexes.
+--
+--          usgs_raw.gauge_readings_all unions hot and archived readings for
+--          ad hoc queries. Application code uses archive::readings_between,
+--          which also prunes archive rows by day.
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS usgs_raw.gauge_readings_archive (
+    site_code VARCHAR(8) NOT NULL,
+    parameter_code VARCHAR(5) NOT NULL,
+    day DATE NOT NULL,                          -- UTC day the readings fall in
+    unit VARCHAR(10) NOT NULL,
+    
+    -- Parallel arrays, one element per reading
+    reading_times TIMESTAMPTZ[] NOT NULL,
+    reading_values NUMERIC(12, 4)[] NOT NULL,
+    qualifiers TEXT[] NOT NULL,
+    reading_count INTEGER NOT NULL,
+    
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    
+    PRIMARY KEY (site_code, parameter_code, day)
+);
+
+-- Always move the arrays out of line so they are compressed as a unit
+ALTER TABLE usgs_raw.gauge_readings_archive ALTER COLUMN reading_times SET STORAGE EXTENDED;
+ALTER TABLE usgs_raw.gauge_readings_archive ALTER COLUMN reading_values SET STORAGE EXTENDED;
+ALTER TABLE usgs_raw.gauge_readings_archive ALTER COLUMN qualifiers SET STORAGE EXTENDED;
+ALTER TABLE usgs_raw.gauge_readings_archive SET (toast_tuple_target = 128);
+
+-- Prefer lz4 when the server supports it
+DO $$
+BEGIN
+    ALTER TABLE usgs_raw.gauge_readings_archive ALTER COLUMN reading_times SET COMPRESSION lz4;
+    ALTER TABLE usgs_raw.gauge_readings_archive ALTER COLUMN reading_values SET COMPRESSION lz4;
+    ALTER TABLE usgs_raw.gauge_readings_archive ALTER COLUMN qualifiers SET COMPRESSION lz4;
+EXCEPTION
+    WHEN feature_not_supported OR syntax_error OR undefined_object THEN
+        RAISE NOTICE 'lz4 TOAST compression unavailable; using pglz';
+END
+$$;
+
+CREATE OR REPLACE VIEW usgs_raw.gauge_readings_all AS
+SELECT site_code, parameter_code, reading_time, value, unit, qualifier::TEXT AS qualifier, FALSE AS archived
+FROM usgs_raw.gauge_readings
+UNION ALL
+SELECT a.site_code, a.parameter_code, r.reading_time, r.value, a.unit, r.qualifier, TRUE AS archived
+FROM usgs_raw.gauge_readings_archive a
+CROSS JOIN LATERAL unnest(a.reading_times, a.reading_values, a.qualifiers)
+    AS r(reading_time, value, qualifier);
+
+COMMENT ON TABLE usgs_raw.gauge_readings_archive IS
+    'Day-bucketed, TOAST-compressed USGS readings older than the hot retention window';
+COMMENT ON VIEW usgs_raw.gauge_readings_all IS
+    'Hot and archived USGS readings; prefer archive::readings_between in application code';
+
+GRANT ALL PRIVILEGES ON usgs_raw.gauge_readings_archive TO flopro_admin;
+GRANT SELECT ON usgs_raw.gauge_readings_all TO flopro_admin;
+
+COMMIT;
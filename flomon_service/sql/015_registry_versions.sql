@@ -0,0 +1,48 @@
+-- Station Registry Versions
+-- Migration 015: Versioned snapshots of the station registry and a field-level change log
+--
+-- Purpose: Post-event reviews need to know which thresholds were in effect
+--          when an alert fired. The daemon records a new version at startup
+--          whenever the registry (usgs_stations.toml / flomon.toml) differs
+--          from the latest stored snapshot, with one registry_changes row
+--          per changed field. Alerts and status responses carry the
+--          version number.
+--
+--          Supersedes the manually maintained nws.flood_threshold_history
+--          for thresholds sourced from the registry.
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS registry_versions (
+    version SERIAL PRIMARY KEY,
+    fingerprint TEXT NOT NULL,                 -- Hash of the snapshot, for change detection
+    snapshot JSONB NOT NULL,                   -- Site code -> audited station fields
+    station_count INTEGER NOT NULL,
+    changed_by TEXT NOT NULL,                  -- $FLOMON_CHANGED_BY or $USER of the daemon
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS registry_changes (
+    id BIGSERIAL PRIMARY KEY,
+    version INTEGER NOT NULL REFERENCES registry_versions(version),
+    site_code VARCHAR(8) NOT NULL,
+    field TEXT NOT NULL,                       -- e.g. flood_stage_ft, name
+    old_value TEXT,                            -- NULL when the station or field was added
+    new_value TEXT,                            -- NULL when the station or field was removed
+    changed_by TEXT NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_registry_changes_site ON registry_changes(site_code, version DESC);
+
+COMMENT ON TABLE registry_versions IS
+    'Station registry snapshots; a new version is recorded whenever thresholds or stations change';
+COMMENT ON TABLE registry_changes IS
+    'Field-level registry change log (who, when, old and new value)';
+
+GRANT ALL PRIVILEGES ON registry_versions, registry_changes TO flopro_admin;
+GRANT USAGE, SELECT ON SEQUENCE registry_versions_version_seq, registry_changes_id_seq TO flopro_admin;
+
+COMMIT;
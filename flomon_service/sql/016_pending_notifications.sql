@@ -0,0 +1,34 @@
+-- Pending Notifications
+-- Migration 016: Undelivered alert notifications carried across restarts
+--
+-- Purpose: On shutdown the daemon drains its notification queue with a
+--          deadline; anything still undelivered is written here and loaded
+--          back (and removed) at the next start, so an alert raised during
+--          a restart is delayed rather than lost.
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS pending_notifications (
+    id BIGSERIAL PRIMARY KEY,
+    recipient TEXT NOT NULL,
+    severity TEXT NOT NULL
+        CHECK (severity IN ('action', 'flood', 'moderate', 'major')),
+    message TEXT NOT NULL,
+    registry_version INTEGER,                  -- Registry version the alert was evaluated against
+    queued_at TIMESTAMPTZ NOT NULL,            -- When the alert was first queued
+    attempts INTEGER NOT NULL DEFAULT 0,       -- Failed delivery attempts so far
+    last_error TEXT,
+    persisted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_pending_notifications_queued ON pending_notifications(queued_at);
+
+COMMENT ON TABLE pending_notifications IS
+    'Alert notifications left undelivered at shutdown, redelivered at next start';
+
+GRANT ALL PRIVILEGES ON pending_notifications TO flopro_admin;
+GRANT USAGE, SELECT ON SEQUENCE pending_notifications_id_seq TO flopro_admin;
+
+COMMIT;
@@ -0,0 +1,38 @@
+-- Adaptive Polling Decisions
+-- Migration 017: Audit trail of poll-interval changes
+--
+-- Purpose: The daemon tightens a station's poll interval while it is at
+--          action stage or rising fast, and relaxes it afterwards (see
+--          monitor::adaptive). Each change is recorded here with its
+--          trigger and the stage/rise rate behind it, so polling cadence
+--          during an event can be audited afterwards.
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS usgs_raw.polling_decisions (
+    id BIGSERIAL PRIMARY KEY,
+    site_code VARCHAR(8) NOT NULL,
+    decided_at TIMESTAMPTZ NOT NULL,
+    trigger TEXT NOT NULL
+        CHECK (trigger IN ('action_stage', 'rapid_rise', 'receded')),
+    previous_minutes INTEGER NOT NULL,         -- Station interval before the change
+    interval_minutes INTEGER NOT NULL,         -- Station interval after the change
+    effective_minutes INTEGER NOT NULL,        -- Daemon-wide interval after the change
+    stage_ft DOUBLE PRECISION NOT NULL,        -- Stage that prompted the decision
+    action_stage_ft DOUBLE PRECISION,          -- NWS action stage, if known
+    rise_rate_ft_per_hour DOUBLE PRECISION,    -- Since the previous poll (NULL on first poll)
+    reason TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_polling_decisions_site_time
+    ON usgs_raw.polling_decisions(site_code, decided_at DESC);
+
+COMMENT ON TABLE usgs_raw.polling_decisions IS
+    'Adaptive poll-interval changes with the trigger and readings behind each';
+
+GRANT ALL PRIVILEGES ON usgs_raw.polling_decisions TO flopro_admin;
+GRANT USAGE, SELECT ON SEQUENCE usgs_raw.polling_decisions_id_seq TO flopro_admin;
+
+COMMIT;
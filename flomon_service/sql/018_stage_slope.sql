@@ -0,0 +1,37 @@
+-- Peoria-Kingston Mines Water-Surface Slope
+-- Migration 018: Persist the reach slope and its trend each cycle
+--
+-- Purpose: The daemon converts the latest Peoria pool and Kingston Mines
+--          stages to elevation (stage + gauge datum) and stores the fall
+--          per river mile here with its trend over the preceding hours
+--          (see analysis::slope). A flattening slope is the hydraulic
+--          signature of Mississippi backwater and raises the confidence of
+--          the backwater assessment served at /backwater and /status.
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE SCHEMA IF NOT EXISTS flood_analysis;
+
+CREATE TABLE IF NOT EXISTS flood_analysis.stage_slope (
+    timestamp TIMESTAMPTZ PRIMARY KEY,         -- Later of the two gauge reading times
+    upstream_site VARCHAR(8) NOT NULL,
+    downstream_site VARCHAR(8) NOT NULL,
+    upstream_elevation_ft DOUBLE PRECISION NOT NULL,
+    downstream_elevation_ft DOUBLE PRECISION NOT NULL,
+    datum TEXT NOT NULL,                       -- Vertical datum of both elevations
+    reach_miles DOUBLE PRECISION NOT NULL,
+    slope_ft_per_mile DOUBLE PRECISION NOT NULL,
+    trend TEXT                                 -- NULL until enough history exists
+        CHECK (trend IN ('steepening', 'steady', 'flattening')),
+    trend_ft_per_mile_per_hour DOUBLE PRECISION,
+    computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE flood_analysis.stage_slope IS
+    'Water-surface slope between Peoria pool and Kingston Mines with its recent trend';
+
+GRANT ALL PRIVILEGES ON flood_analysis.stage_slope TO flopro_admin;
+
+COMMIT;
@@ -0,0 +1,43 @@
+-- Webcam Snapshots
+-- Migration 019: Images captured when a station changes flood severity
+--
+-- Purpose: With a [cams] section in flomon.toml, the daemon fetches every
+--          webcam watching a station when its severity changes (see cams
+--          and alert::transitions) and stores the image here, tied to the
+--          transition and to the site's open flood event if any. Images
+--          are served at /cams/snapshot/{id} and linked from alerts so
+--          conditions can be confirmed visually.
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE SCHEMA IF NOT EXISTS flood_analysis;
+
+CREATE TABLE IF NOT EXISTS flood_analysis.cam_snapshots (
+    id BIGSERIAL PRIMARY KEY,
+    camera TEXT NOT NULL,                      -- [[cams.cameras]] name
+    url TEXT NOT NULL,                         -- URL the image was fetched from
+    site_code VARCHAR(8) NOT NULL,             -- Station whose transition triggered it
+    captured_at TIMESTAMPTZ NOT NULL,
+    severity_from TEXT NOT NULL,               -- 'normal' below action stage
+    severity_to TEXT NOT NULL,
+    stage_ft DOUBLE PRECISION NOT NULL,        -- Stage at the transition
+    event_id INTEGER REFERENCES flood_analysis.events(id),  -- Open event at capture, if any
+    content_type TEXT NOT NULL,
+    image BYTEA NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_cam_snapshots_site_time
+    ON flood_analysis.cam_snapshots(site_code, captured_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_cam_snapshots_event
+    ON flood_analysis.cam_snapshots(event_id) WHERE event_id IS NOT NULL;
+
+COMMENT ON TABLE flood_analysis.cam_snapshots IS
+    'Webcam images captured on flood severity transitions, linked to the flood event';
+
+GRANT ALL PRIVILEGES ON flood_analysis.cam_snapshots TO flopro_admin;
+GRANT USAGE, SELECT ON SEQUENCE flood_analysis.cam_snapshots_id_seq TO flopro_admin;
+
+COMMIT;
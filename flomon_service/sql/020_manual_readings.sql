@@ -0,0 +1,41 @@
+-- Manual Staff-Gauge Readings
+-- Migration 020: Stage readings reported by SMS/email during outages
+--
+-- Purpose: When gauges or telemetry are down, people text or email staff
+--          gauge readings; a forwarder POSTs them to /manual_readings (see
+--          manual_readings). They are kept out of usgs_raw.gauge_readings
+--          so the daemon and analyses never use them; the dashboard reads
+--          them from here explicitly.
+--
+-- Written by: POST /manual_readings
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS usgs_raw.manual_readings (
+    id BIGSERIAL PRIMARY KEY,
+    site_code VARCHAR(15) NOT NULL,
+    stage_ft DOUBLE PRECISION NOT NULL,
+    reading_time TIMESTAMPTZ NOT NULL,         -- Time on the staff gauge (receipt time if not given)
+    received_at TIMESTAMPTZ NOT NULL,          -- When the forwarder received the message
+    source VARCHAR(10) NOT NULL DEFAULT 'MANUAL'
+        CHECK (source = 'MANUAL'),
+    channel VARCHAR(10) NOT NULL
+        CHECK (channel IN ('sms', 'email')),
+    reporter VARCHAR(100) NOT NULL,            -- Phone number or email address
+    note TEXT,
+    raw_message TEXT NOT NULL,                 -- Message body as received
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_manual_readings_site_time
+    ON usgs_raw.manual_readings(site_code, reading_time DESC);
+
+COMMENT ON TABLE usgs_raw.manual_readings IS
+    'Manually reported staff gauge readings (source MANUAL); excluded from automated analysis';
+
+GRANT ALL PRIVILEGES ON usgs_raw.manual_readings TO flopro_admin;
+GRANT USAGE, SELECT ON SEQUENCE usgs_raw.manual_readings_id_seq TO flopro_admin;
+
+COMMIT;
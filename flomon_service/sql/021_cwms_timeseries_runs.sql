@@ -0,0 +1,65 @@
+-- Delta-Encoded CWMS Timeseries
+-- Migration 021: Run-length storage for CWMS rows past the hot window
+--
+-- Purpose: 15-minute CWMS pool elevations rarely change. `flomon archive
+--          --cwms` moves rows older than the hot window (30 days by
+--          default) out of usace.cwms_timeseries into runs: one row per
+--          value held over a regular sampling grid (see cwms_archive).
+--          The encoding is lossless; every original timestamp is
+--          regenerated from valid_from, step_seconds and sample_count.
+--
+--          usace.cwms_timeseries_all unions hot rows and expanded runs for
+--          ad hoc queries. Application code uses cwms_archive::series_between.
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS usace.cwms_timeseries_runs (
+    id BIGSERIAL PRIMARY KEY,
+    location_id TEXT NOT NULL,
+    timeseries_id TEXT NOT NULL,
+    parameter_id TEXT NOT NULL,
+    unit TEXT NOT NULL,
+    original_unit VARCHAR(16),
+    
+    -- The value and quality shared by every sample in the run
+    value NUMERIC(12, 4) NOT NULL,
+    quality_code INT,
+    
+    -- Sampling grid: valid_from + k * step_seconds, k = 0 .. sample_count - 1
+    valid_from TIMESTAMPTZ NOT NULL,
+    valid_to TIMESTAMPTZ NOT NULL,
+    step_seconds INTEGER NOT NULL CHECK (step_seconds >= 0),
+    sample_count INTEGER NOT NULL CHECK (sample_count >= 1),
+    
+    compacted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    
+    CHECK (valid_to = valid_from + make_interval(secs => step_seconds * (sample_count - 1))),
+    CONSTRAINT unique_cwms_run UNIQUE (timeseries_id, parameter_id, unit, valid_from)
+);
+
+CREATE INDEX IF NOT EXISTS idx_cwms_runs_location_time
+    ON usace.cwms_timeseries_runs(location_id, parameter_id, valid_to DESC);
+
+CREATE OR REPLACE VIEW usace.cwms_timeseries_all AS
+SELECT location_id, timeseries_id, parameter_id, timestamp, value, unit, original_unit, quality_code,
+       FALSE AS compacted
+FROM usace.cwms_timeseries
+UNION ALL
+SELECT r.location_id, r.timeseries_id, r.parameter_id, t AS timestamp, r.value, r.unit, r.original_unit,
+       r.quality_code, TRUE AS compacted
+FROM usace.cwms_timeseries_runs r
+CROSS JOIN LATERAL generate_series(
+    r.valid_from, r.valid_to, make_interval(secs => GREATEST(r.step_seconds, 1))) AS t;
+
+COMMENT ON TABLE usace.cwms_timeseries_runs IS
+    'Run-length (delta) encoded CWMS samples older than the hot window';
+COMMENT ON VIEW usace.cwms_timeseries_all IS
+    'Hot and compacted CWMS samples; prefer cwms_archive::series_between in application code';
+
+GRANT ALL PRIVILEGES ON usace.cwms_timeseries_runs TO flopro_admin;
+GRANT USAGE, SELECT ON SEQUENCE usace.cwms_timeseries_runs_id_seq TO flopro_admin;
+GRANT SELECT ON usace.cwms_timeseries_all TO flopro_admin;
+
+COMMIT;
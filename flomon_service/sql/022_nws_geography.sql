@@ -0,0 +1,44 @@
+-- NWS Geography
+-- Migration 022: NWS forecast zone and county FIPS per station and zone
+--
+-- Purpose: NWS CAP alerts are addressed by UGC forecast zone / county and
+--          SAME (county FIPS) codes. This mapping lets alert ingestion keep
+--          only warnings covering a monitored station or zone, instead of
+--          every Flash Flood Warning in the state.
+--
+-- Written by: flomon geo resolve (replaces all rows each run)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS nws.station_geography (
+    site_code VARCHAR(15) PRIMARY KEY,
+    forecast_zone VARCHAR(6),                  -- UGC forecast zone, e.g. ILZ031
+    county_fips CHAR(5),                       -- e.g. 17143 (Peoria County)
+    county_ugc VARCHAR(6),                     -- e.g. ILC143
+    source VARCHAR(10) NOT NULL
+        CHECK (source IN ('config', 'api')),   -- usgs_stations.toml or api.weather.gov/points
+    resolved_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS nws.zone_geography (
+    zone_id INTEGER PRIMARY KEY
+        CHECK (zone_id BETWEEN 0 AND 6),
+    forecast_zones TEXT[] NOT NULL,            -- Union over the zone's sensors
+    county_fips TEXT[] NOT NULL,
+    resolved_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_station_geography_county
+    ON nws.station_geography(county_fips);
+
+COMMENT ON TABLE nws.station_geography IS
+    'NWS forecast zone and county of each monitored station, for CAP alert relevance';
+COMMENT ON TABLE nws.zone_geography IS
+    'NWS forecast zones and counties covered by each hydrological zone';
+
+GRANT ALL PRIVILEGES ON nws.station_geography TO flopro_admin;
+GRANT ALL PRIVILEGES ON nws.zone_geography TO flopro_admin;
+
+COMMIT;
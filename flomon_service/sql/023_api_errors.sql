@@ -0,0 +1,35 @@
+-- API Errors
+-- Migration 023: Server errors returned by the HTTP API, keyed by request ID
+--
+-- Purpose: Every API request gets an ID (X-Request-Id header, request_id in
+--          JSON error bodies, and the request log line). Each 5xx response
+--          is recorded here with the underlying error, so an ID copied from
+--          the dashboard finds the failing query without grepping logs.
+--
+-- Written by: flomon serve (endpoint)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS api_errors (
+    id BIGSERIAL PRIMARY KEY,
+    request_id VARCHAR(64) NOT NULL,
+    method VARCHAR(10) NOT NULL,
+    path TEXT NOT NULL,                        -- Without the query string
+    status INTEGER NOT NULL,
+    error TEXT NOT NULL,                       -- Message returned to the client
+    client_ip TEXT,                            -- Resolved client (after trusted proxies)
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_errors_request_id ON api_errors(request_id);
+CREATE INDEX IF NOT EXISTS idx_api_errors_occurred ON api_errors(occurred_at DESC);
+
+COMMENT ON TABLE api_errors IS
+    'HTTP API 5xx responses with request ID, path and error message';
+
+GRANT ALL PRIVILEGES ON api_errors TO flopro_admin;
+GRANT USAGE, SELECT ON SEQUENCE api_errors_id_seq TO flopro_admin;
+
+COMMIT;
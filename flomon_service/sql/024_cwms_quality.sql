@@ -0,0 +1,22 @@
+-- CWMS Quality Codes
+-- Migration 024: Document the CWMS quality bitfield and index unusable rows
+--
+-- Purpose: quality_code is the raw CWMS 32-bit quality word, not an enum
+--          (the comment from 004 was wrong). flomon decodes it (see
+--          ingest::cwms_quality) and keeps rejected (bit 4) and missing
+--          (bit 2) values in storage but out of analysis and alerting.
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+COMMENT ON COLUMN usace.cwms_timeseries.quality_code IS
+    'CWMS quality bitfield: bit 0 screened; bits 1-4 okay/missing/questionable/rejected; '
+    'bit 7 different; bits 8-10 replacement cause; bits 11-14 replacement method; bit 31 protected';
+
+-- Rejected/missing rows are rare; a partial index makes auditing them cheap
+CREATE INDEX IF NOT EXISTS idx_cwms_timeseries_unusable
+    ON usace.cwms_timeseries(location_id, timestamp DESC)
+    WHERE (quality_code & 20) <> 0;
+
+COMMIT;
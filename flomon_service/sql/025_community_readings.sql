@@ -0,0 +1,40 @@
+-- Community Data Source Readings
+-- Migration 025: Default destination for data source plugins
+--
+-- Purpose: Private and community gauges (LoRa staff gauges, township rain
+--          buckets) are polled through ingest::plugin. Their readings are
+--          stored here, keyed by plugin instance name (source), and never
+--          mixed into usgs_raw.gauge_readings. A plugin that names its own
+--          destination table must give it this same shape.
+--
+-- Written by: flomon daemon (one row per plugin reading)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE SCHEMA IF NOT EXISTS community;
+
+CREATE TABLE IF NOT EXISTS community.readings (
+    id BIGSERIAL PRIMARY KEY,
+    source VARCHAR(64) NOT NULL,               -- [[plugins]] name
+    site_id VARCHAR(64) NOT NULL,
+    parameter VARCHAR(32) NOT NULL,
+    reading_time TIMESTAMPTZ NOT NULL,
+    value DOUBLE PRECISION NOT NULL,
+    unit VARCHAR(16) NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (source, site_id, parameter, reading_time)
+);
+
+CREATE INDEX IF NOT EXISTS idx_community_readings_site_time
+    ON community.readings(site_id, parameter, reading_time DESC);
+
+COMMENT ON TABLE community.readings IS
+    'Readings from data source plugins; excluded from automated analysis and alerting';
+
+GRANT USAGE ON SCHEMA community TO flopro_admin;
+GRANT ALL PRIVILEGES ON community.readings TO flopro_admin;
+GRANT USAGE, SELECT ON SEQUENCE community.readings_id_seq TO flopro_admin;
+
+COMMIT;
@@ -0,0 +1,42 @@
+-- Data-Quality Alerts
+-- Migration 026: Detected data problems awaiting manual review
+--
+-- Purpose: Some problems can be detected but not corrected automatically,
+--          e.g. a stage datum shift (step in stage not matched by
+--          discharge; see analysis::datum_shift). The daemon opens one row
+--          per detected event; `flomon data-quality review` confirms or
+--          dismisses it. Raw readings are never modified.
+--
+-- Written by: flomon daemon (open), flomon data-quality review (status)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS usgs_raw.data_quality_alerts (
+    id BIGSERIAL PRIMARY KEY,
+    site_code VARCHAR(15) NOT NULL,
+    kind VARCHAR(30) NOT NULL,                 -- e.g. 'datum_shift'
+    event_time TIMESTAMPTZ NOT NULL,           -- Where the problem starts in the data
+    detected_at TIMESTAMPTZ NOT NULL,
+    summary TEXT NOT NULL,                     -- Human-readable description
+    details JSONB NOT NULL DEFAULT '{}',       -- Detector measurements
+    status VARCHAR(10) NOT NULL DEFAULT 'open'
+        CHECK (status IN ('open', 'confirmed', 'dismissed')),
+    reviewed_by VARCHAR(100),
+    reviewed_at TIMESTAMPTZ,
+    review_note TEXT,
+    UNIQUE (site_code, kind, event_time)
+);
+
+CREATE INDEX IF NOT EXISTS idx_data_quality_alerts_open
+    ON usgs_raw.data_quality_alerts(event_time DESC)
+    WHERE status = 'open';
+
+COMMENT ON TABLE usgs_raw.data_quality_alerts IS
+    'Detected data problems (e.g. stage datum shifts) awaiting manual review';
+
+GRANT ALL PRIVILEGES ON usgs_raw.data_quality_alerts TO flopro_admin;
+GRANT USAGE, SELECT ON SEQUENCE usgs_raw.data_quality_alerts_id_seq TO flopro_admin;
+
+COMMIT;
@@ -0,0 +1,38 @@
+-- Precipitation-to-Rise Response Curves
+-- Migration 027: Empirical lookup tables from the event catalog
+--
+-- Purpose: For a basin/gauge pair, how far does the gauge rise after a
+--          given 24-hour basin rainfall, and how long after the rain? The
+--          floml layer fits these from flood_analysis.events and the ASOS
+--          rollups (floml/scripts/fit_response_curves.py) and replaces the
+--          row on each refit. The daemon reads them to log an expected
+--          tributary response as soon as a rain event is under way (see
+--          analysis::precip_response).
+--
+-- Written by: floml (fit_response_curves.py). Read by: flomon daemon.
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE SCHEMA IF NOT EXISTS flood_analysis;
+
+CREATE TABLE IF NOT EXISTS flood_analysis.precip_response_curves (
+    basin TEXT NOT NULL,                       -- iem_asos.toml basin name
+    site_code VARCHAR(15) NOT NULL,            -- Responding USGS gauge
+    precip_24h_in DOUBLE PRECISION[] NOT NULL, -- Lookup points, ascending
+    rise_ft DOUBLE PRECISION[] NOT NULL,       -- Typical rise at each point
+    lag_hours DOUBLE PRECISION NOT NULL,       -- Rain window end to crest, median
+    event_count INTEGER NOT NULL,              -- Catalog events behind the fit
+    fitted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (basin, site_code),
+    CHECK (cardinality(precip_24h_in) = cardinality(rise_ft)),
+    CHECK (cardinality(precip_24h_in) >= 2)
+);
+
+COMMENT ON TABLE flood_analysis.precip_response_curves IS
+    'Empirical 24h basin precipitation to gauge rise lookup tables, refit by floml';
+
+GRANT ALL PRIVILEGES ON flood_analysis.precip_response_curves TO flopro_admin;
+
+COMMIT;
@@ -0,0 +1,30 @@
+-- Daemon Heartbeats
+-- Migration 028: Liveness of every daemon sharing this database
+--
+-- Purpose: In the HA setup a warm standby daemon runs on a second box.
+--          Each daemon with [failover] configured upserts its row once per
+--          poll cycle; the dispatch leader treats a standby whose
+--          last_seen is too old as down and logs that failover readiness
+--          has degraded (see monitor::failover).
+--
+-- Written by: flomon daemon (every instance). Read by: flomon daemon (leader).
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS daemon_heartbeats (
+    instance TEXT PRIMARY KEY,                 -- [failover] instance, $FLOMON_INSTANCE or host name
+    host TEXT NOT NULL,
+    role TEXT NOT NULL CHECK (role IN ('leader', 'standby')),
+    registry_version INTEGER,                  -- Station registry the daemon is running with
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE daemon_heartbeats IS
+    'Latest heartbeat of each flomon daemon, for warm standby readiness checks';
+
+GRANT ALL PRIVILEGES ON daemon_heartbeats TO flopro_admin;
+
+COMMIT;
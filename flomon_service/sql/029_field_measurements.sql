@@ -0,0 +1,39 @@
+-- USGS Field Measurements
+-- Migration 029: Direct discharge measurements from USGS site visits
+--
+-- Purpose: Rated IV discharge is only as good as the station's rating.
+--          Field measurements are the independent check; the daemon
+--          compares them with rated discharge and opens a 'rating_drift'
+--          data-quality alert when recent measurements consistently miss
+--          (see analysis::rating_drift).
+--
+-- Source: https://waterdata.usgs.gov/nwis/measurements?site_no={site}&format=rdb
+-- Written by: flomon daemon (daily refresh)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS usgs_raw.field_measurements (
+    id BIGSERIAL PRIMARY KEY,
+    site_code VARCHAR(15) NOT NULL,
+    measurement_number VARCHAR(20) NOT NULL,   -- USGS measurement_nu, unique per site
+    measured_at TIMESTAMPTZ NOT NULL,
+    gage_height_ft DOUBLE PRECISION,
+    discharge_cfs DOUBLE PRECISION,
+    rating VARCHAR(15),                        -- Excellent/Good/Fair/Poor/Unspecified
+    used BOOLEAN NOT NULL DEFAULT FALSE,       -- Used by USGS for the rating
+    ingested_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (site_code, measurement_number)
+);
+
+CREATE INDEX IF NOT EXISTS idx_field_measurements_site_time
+    ON usgs_raw.field_measurements(site_code, measured_at DESC);
+
+COMMENT ON TABLE usgs_raw.field_measurements IS
+    'USGS field (site visit) discharge measurements, checked against rated discharge';
+
+GRANT ALL PRIVILEGES ON usgs_raw.field_measurements TO flopro_admin;
+GRANT USAGE, SELECT ON SEQUENCE usgs_raw.field_measurements_id_seq TO flopro_admin;
+
+COMMIT;
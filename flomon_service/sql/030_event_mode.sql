@@ -0,0 +1,34 @@
+-- Event Mode History
+-- Migration 030: System-wide Normal/Watch/Event/Recovery transitions
+--
+-- Purpose: The daemon derives one event mode from station severities,
+--          rapid-rise triggers and NWS products (see monitor::event_mode)
+--          and records every change here. The latest row is the current
+--          mode: the API banner reads it and the daemon resumes from it
+--          after a restart.
+--
+-- Written by: flomon daemon
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS usgs_raw.event_mode_history (
+    id BIGSERIAL PRIMARY KEY,
+    mode VARCHAR(10) NOT NULL
+        CHECK (mode IN ('normal', 'watch', 'event', 'recovery')),
+    previous_mode VARCHAR(10) NOT NULL,
+    entered_at TIMESTAMPTZ NOT NULL,
+    reason TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_mode_history_entered
+    ON usgs_raw.event_mode_history(entered_at DESC);
+
+COMMENT ON TABLE usgs_raw.event_mode_history IS
+    'System-wide event mode changes; the latest row is the current mode';
+
+GRANT ALL PRIVILEGES ON usgs_raw.event_mode_history TO flopro_admin;
+GRANT USAGE, SELECT ON SEQUENCE usgs_raw.event_mode_history_id_seq TO flopro_admin;
+
+COMMIT;
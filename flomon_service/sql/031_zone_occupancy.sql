@@ -0,0 +1,34 @@
+-- Zone Occupancy
+-- Migration 031: Occupancy flags per zone and quiet notifications
+--
+-- Purpose: A zone listed under [occupancy] in flomon.toml is occupied when
+--          its flag here says so (set by POST /api/occupancy or a presence
+--          sensor over MQTT) or, without a flag, when its schedule covers
+--          the current time. Alerts for zones nobody is at are queued with
+--          urgency 'quiet' (see alert::occupancy), which channels deliver
+--          at reduced priority; pending_notifications keeps that across
+--          restarts.
+--
+-- Written by: flomon daemon, endpoint and MQTT presence subscriber
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS zone_occupancy (
+    zone_id SMALLINT PRIMARY KEY CHECK (zone_id BETWEEN 0 AND 6),
+    occupied BOOLEAN NOT NULL,
+    source TEXT NOT NULL,                      -- 'api' or 'mqtt'
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE zone_occupancy IS
+    'Occupancy flags per zone; a flag overrides the zone''s configured schedule';
+
+ALTER TABLE pending_notifications
+    ADD COLUMN IF NOT EXISTS urgency TEXT NOT NULL DEFAULT 'normal'
+        CHECK (urgency IN ('normal', 'quiet'));
+
+GRANT ALL PRIVILEGES ON zone_occupancy TO flopro_admin;
+
+COMMIT;
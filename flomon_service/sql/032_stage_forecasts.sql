@@ -0,0 +1,65 @@
+-- Stage Forecasts
+-- Migration 032: Forecast stages from every agency, and their verification
+--
+-- Purpose: NWS, the Corps and the Illinois State Water Survey each forecast
+--          the Peoria pool reach. Each source's adapter (ingest::isws, ...)
+--          normalizes its product to one row per station and valid time,
+--          attributed by `source`. A reissued product is a new issued_at;
+--          re-reading the same issue replaces its values.
+--
+--          forecast_verification pairs every forecast value whose valid
+--          time has passed with the USGS stage observed nearest to it
+--          (within 30 minutes), for error by source and lead time.
+--
+-- Written by: flomon daemon (when a forecast source is configured)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE SCHEMA IF NOT EXISTS flood_analysis;
+
+CREATE TABLE IF NOT EXISTS flood_analysis.stage_forecasts (
+    source TEXT NOT NULL CHECK (source IN ('nws', 'cwms', 'isws')),
+    site_code VARCHAR(8) NOT NULL,             -- USGS site forecast
+    issued_at TIMESTAMPTZ NOT NULL,
+    valid_at TIMESTAMPTZ NOT NULL,
+    stage_ft DOUBLE PRECISION NOT NULL,        -- Above the station's gauge datum
+    product_location TEXT NOT NULL,            -- Location as the product names it
+    fetched_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (source, site_code, issued_at, valid_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_stage_forecasts_valid
+    ON flood_analysis.stage_forecasts (site_code, valid_at);
+
+COMMENT ON TABLE flood_analysis.stage_forecasts IS
+    'Forecast stages by issuing agency (nws, cwms, isws), one row per station, issue and valid time';
+
+CREATE OR REPLACE VIEW flood_analysis.forecast_verification AS
+SELECT f.source,
+       f.site_code,
+       f.issued_at,
+       f.valid_at,
+       f.valid_at - f.issued_at AS lead_time,
+       f.stage_ft AS forecast_ft,
+       o.observed_ft,
+       f.stage_ft - o.observed_ft AS error_ft
+FROM flood_analysis.stage_forecasts f
+CROSS JOIN LATERAL (
+    SELECT g.value::DOUBLE PRECISION AS observed_ft
+    FROM usgs_raw.gauge_readings g
+    WHERE g.site_code = f.site_code
+      AND g.parameter_code = '00065'
+      AND g.reading_time BETWEEN f.valid_at - INTERVAL '30 minutes' AND f.valid_at + INTERVAL '30 minutes'
+    ORDER BY abs(extract(epoch FROM g.reading_time - f.valid_at))
+    LIMIT 1
+) o;
+
+COMMENT ON VIEW flood_analysis.forecast_verification IS
+    'Forecast stage against the nearest observed USGS stage (within 30 minutes) at the valid time';
+
+GRANT ALL PRIVILEGES ON flood_analysis.stage_forecasts TO flopro_admin;
+GRANT SELECT ON flood_analysis.forecast_verification TO flopro_admin;
+
+COMMIT;
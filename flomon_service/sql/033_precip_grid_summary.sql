@@ -0,0 +1,39 @@
+-- Radar QPE Summaries
+-- Migration 033: Hourly MRMS precipitation averaged over the basin and zones
+--
+-- Purpose: ASOS gauges miss convective cells that fall between stations.
+--          The daemon reads each hour's MRMS multi-sensor QPE grid (see
+--          ingest::mrms) and stores the mean and wettest cell over the
+--          Illinois basin bounding box and over each zone's footprint.
+--
+-- Written by: flomon daemon, once per hour of QPE (re-written if the hour
+--             is fetched again)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS precip_grid_summary (
+    scope TEXT NOT NULL CHECK (scope IN ('basin', 'zone')),
+    scope_id TEXT NOT NULL,                    -- 'illinois' or zone id
+    hour_end TIMESTAMPTZ NOT NULL,             -- End of the 1-hour accumulation
+    
+    mean_in DOUBLE PRECISION NOT NULL,         -- Mean over cells in the area
+    max_in DOUBLE PRECISION NOT NULL,          -- Wettest cell
+    cell_count INTEGER NOT NULL,               -- Cells with a valid value
+    product TEXT NOT NULL,                     -- MultiSensor_QPE_01H_Pass2
+    
+    computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    
+    PRIMARY KEY (scope, scope_id, hour_end)
+);
+
+CREATE INDEX IF NOT EXISTS idx_precip_grid_summary_latest
+    ON precip_grid_summary(scope, scope_id, hour_end DESC);
+
+COMMENT ON TABLE precip_grid_summary IS
+    'Hourly MRMS radar QPE averaged over the Illinois basin and each zone footprint';
+
+GRANT ALL PRIVILEGES ON precip_grid_summary TO flopro_admin;
+
+COMMIT;
@@ -0,0 +1,30 @@
+-- USGS Rating Tables
+-- Migration 034: Cached stage-discharge ratings per station
+--
+-- Purpose: Flood thresholds are stages. Stations that report only
+--          discharge get a stage estimate through the station's rating
+--          (see ingest::rating); the table is cached here so estimates
+--          keep working while the NWIS rating service is down.
+--
+-- Source: https://waterdata.usgs.gov/nwisweb/get_ratings?site_no={site}&file_type=exsa
+-- Written by: flomon daemon (daily refresh, replaced in place)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS usgs_raw.rating_tables (
+    site_code VARCHAR(15) PRIMARY KEY,
+    rating_id VARCHAR(20),                     -- USGS rating number
+    stage_ft DOUBLE PRECISION[] NOT NULL,      -- Expanded table, ascending
+    discharge_cfs DOUBLE PRECISION[] NOT NULL, -- Rated discharge at each stage
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (cardinality(stage_ft) = cardinality(discharge_cfs))
+);
+
+COMMENT ON TABLE usgs_raw.rating_tables IS
+    'Current shift-adjusted USGS stage-discharge rating per station';
+
+GRANT ALL PRIVILEGES ON usgs_raw.rating_tables TO flopro_admin;
+
+COMMIT;
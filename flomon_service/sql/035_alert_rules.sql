@@ -0,0 +1,47 @@
+-- Composite Alert Rules
+-- Migration 035: Per-rule evaluation counters and firings
+--
+-- Purpose: [[alert_rules]] in flomon.toml combine stage, rise, severity and
+--          basin precipitation conditions (see alert::rules). Every cycle
+--          each rule's evaluation is counted here; a rule in shadow mode is
+--          evaluated and recorded like an active one but notifies nobody,
+--          so a new rule can be trialled for a season and its firings
+--          reviewed before it is promoted to active.
+--
+-- Written by: flomon daemon (every poll cycle)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS nws.alert_rule_stats (
+    rule_name TEXT PRIMARY KEY,
+    mode TEXT NOT NULL CHECK (mode IN ('active', 'shadow')),  -- mode at last evaluation
+    evaluations BIGINT NOT NULL DEFAULT 0,
+    matches BIGINT NOT NULL DEFAULT 0,          -- cycles on which every condition held
+    firings BIGINT NOT NULL DEFAULT 0,          -- cycles on which the rule started matching
+    last_matched_at TIMESTAMPTZ,
+    last_fired_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS nws.alert_rule_firings (
+    id SERIAL PRIMARY KEY,
+    rule_name TEXT NOT NULL,
+    mode TEXT NOT NULL CHECK (mode IN ('active', 'shadow')),
+    fired_at TIMESTAMPTZ NOT NULL,
+    site_code VARCHAR(15) NOT NULL,
+    severity TEXT NOT NULL,
+    message TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_alert_rule_firings_rule
+    ON nws.alert_rule_firings(rule_name, fired_at DESC);
+
+COMMENT ON TABLE nws.alert_rule_firings IS
+    'Composite rule firings; shadow-mode firings were logged but not dispatched';
+
+GRANT ALL PRIVILEGES ON nws.alert_rule_stats, nws.alert_rule_firings TO flopro_admin;
+GRANT USAGE, SELECT ON SEQUENCE nws.alert_rule_firings_id_seq TO flopro_admin;
+
+COMMIT;
@@ -0,0 +1,37 @@
+-- Flood Event Detection
+-- Migration 036: Events segmented from stored stage series
+--
+-- Purpose: analysis::events scans each gauge's stage history for events
+--          (rise above action stage -> crest -> recession below) and
+--          writes them to flood_analysis.events, where the event report,
+--          volume account, cams and field observations already look.
+--          Events that never passed action stage are recorded with
+--          severity 'action'. Rows written by the scan are marked
+--          detected_by = 'stage_scan' and are refreshed on every rescan;
+--          catalogued events (detected_by NULL) are never touched.
+--
+-- Written by: flomon event detect
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+ALTER TABLE flood_analysis.events DROP CONSTRAINT IF EXISTS events_severity_check;
+ALTER TABLE flood_analysis.events ADD CONSTRAINT events_severity_check
+    CHECK (severity IN ('action', 'minor', 'moderate', 'major', 'extreme'));
+
+ALTER TABLE flood_analysis.events
+    ADD COLUMN IF NOT EXISTS detected_by TEXT,
+    ADD COLUMN IF NOT EXISTS duration_hours NUMERIC(10, 2) GENERATED ALWAYS AS
+        (EXTRACT(EPOCH FROM (event_end - event_start)) / 3600) STORED;
+
+-- A rescan finds the same start for the same event; its crest and end may move
+CREATE UNIQUE INDEX IF NOT EXISTS idx_events_detected_start
+    ON flood_analysis.events(site_code, event_start) WHERE detected_by = 'stage_scan';
+
+COMMENT ON COLUMN flood_analysis.events.detected_by IS
+    'stage_scan when segmented from gauge readings by analysis::events; NULL for catalogued events';
+COMMENT ON COLUMN flood_analysis.events.duration_hours IS
+    'Hours from event_start to event_end; NULL while the event is open';
+
+COMMIT;
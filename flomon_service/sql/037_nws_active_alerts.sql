@@ -0,0 +1,43 @@
+-- NWS Active Alerts
+-- Migration 037: Flood watches and warnings from api.weather.gov
+--
+-- Purpose: ingest::nws_alerts polls the api.weather.gov active alerts for
+--          the counties around the property (Peoria, Tazewell, Woodford by
+--          default) and keeps the flood watches and warnings here. The
+--          table holds what is in effect: an alert that drops out of the
+--          feed (expired, cancelled or superseded by an update) is deleted
+--          on the next poll. first_seen_at is when it was dispatched.
+--
+-- Written by: flomon daemon (every poll cycle, when [nws_alerts] is set)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS nws.active_alerts (
+    alert_id TEXT PRIMARY KEY,                  -- CAP identifier (urn:oid:...)
+    event TEXT NOT NULL,                        -- 'Flood Warning', 'Flash Flood Watch', ...
+    message_type TEXT NOT NULL,                 -- 'Alert' or 'Update'
+    cap_severity TEXT,                          -- CAP severity: Minor, Moderate, Severe, Extreme
+    urgency TEXT,
+    headline TEXT,
+    area_desc TEXT NOT NULL,
+    ugc_codes TEXT[] NOT NULL DEFAULT '{}',
+    same_codes TEXT[] NOT NULL DEFAULT '{}',
+    sent_at TIMESTAMPTZ NOT NULL,
+    onset_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ,
+    ends_at TIMESTAMPTZ,
+    first_seen_at TIMESTAMPTZ NOT NULL,
+    last_seen_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_active_alerts_sent
+    ON nws.active_alerts(sent_at DESC);
+
+COMMENT ON TABLE nws.active_alerts IS
+    'NWS flood watches and warnings in effect for the monitored counties, as of the last poll';
+
+GRANT ALL PRIVILEGES ON nws.active_alerts TO flopro_admin;
+
+COMMIT;
@@ -0,0 +1,35 @@
+-- Upstream Lag Estimates
+-- Migration 038: Travel time from upstream gauges, per flow regime
+--
+-- Purpose: analysis::lag cross-correlates hourly stage rises at each
+--          upstream gauge with those at Kingston Mines and keeps the lag
+--          with the highest correlation, fitted separately for low,
+--          elevated (action to flood stage) and flood flow. Replaces the
+--          hand-estimated travel_time_to_peoria_hours for forecast-style
+--          alerts once a season of data has been fitted.
+--
+-- Written by: flomon lag fit
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS flood_analysis.lag_estimates (
+    upstream_site VARCHAR(15) NOT NULL,
+    downstream_site VARCHAR(15) NOT NULL,
+    regime TEXT NOT NULL CHECK (regime IN ('low', 'elevated', 'flood')),
+    lag_hours INTEGER NOT NULL CHECK (lag_hours >= 0),
+    correlation DOUBLE PRECISION NOT NULL,       -- of hourly rises at lag_hours
+    pairs INTEGER NOT NULL,                      -- hourly pairs the fit rests on
+    window_start TIMESTAMPTZ NOT NULL,
+    window_end TIMESTAMPTZ NOT NULL,
+    fitted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (upstream_site, downstream_site, regime)
+);
+
+COMMENT ON TABLE flood_analysis.lag_estimates IS
+    'Fitted upstream-to-downstream travel time per flow regime (regime from the downstream stage)';
+
+GRANT ALL PRIVILEGES ON flood_analysis.lag_estimates TO flopro_admin;
+
+COMMIT;
@@ -0,0 +1,31 @@
+-- Backfill Checkpoints
+-- Migration 039: Completed chunks of historical backfill runs
+--
+-- Purpose: flomon backfill splits a multi-year range into yearly daily
+--          value requests and monthly instantaneous value requests. Each
+--          chunk that is fetched and warehoused is recorded here, so a run
+--          interrupted by a shutdown, a network failure or a USGS outage
+--          resumes at the first missing chunk instead of starting over.
+--
+-- Written by: flomon backfill (backfill::store_checkpoint)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS usgs_raw.backfill_checkpoints (
+    site_code VARCHAR(15) NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('dv', 'iv')),
+    chunk_start DATE NOT NULL,
+    chunk_end DATE NOT NULL,                     -- inclusive
+    readings INTEGER NOT NULL,                   -- rows warehoused for the chunk
+    completed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (site_code, kind, chunk_start)
+);
+
+COMMENT ON TABLE usgs_raw.backfill_checkpoints IS
+    'Backfill chunks already fetched and warehoused (resumed runs skip them)';
+
+GRANT ALL PRIVILEGES ON usgs_raw.backfill_checkpoints TO flopro_admin;
+
+COMMIT;
@@ -0,0 +1,42 @@
+-- Backwater Status
+-- Migration 040: One backwater assessment per daemon cycle
+--
+-- Purpose: The daemon compares the LaGrange and Peoria pool and tailwater
+--          elevations with the Grafton stage and the Peoria-Kingston Mines
+--          slope every cycle (see analysis::backwater) and records the
+--          result here. A row with affecting_peoria set after one without
+--          is when the Peoria zones were alerted.
+--
+-- Written by: flomon daemon (every poll cycle)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE SCHEMA IF NOT EXISTS flood_analysis;
+
+CREATE TABLE IF NOT EXISTS flood_analysis.backwater_status (
+    timestamp TIMESTAMPTZ PRIMARY KEY,         -- Cycle time
+    grafton_stage_ft DOUBLE PRECISION,
+    lagrange_pool_ft DOUBLE PRECISION,
+    lagrange_tailwater_ft DOUBLE PRECISION,
+    peoria_pool_ft DOUBLE PRECISION,
+    peoria_tailwater_ft DOUBLE PRECISION,
+    slope_ft_per_mile DOUBLE PRECISION,
+    slope_trend TEXT,
+    risk_level TEXT NOT NULL
+        CHECK (risk_level IN ('UNKNOWN', 'LOW', 'MODERATE', 'HIGH', 'CRITICAL')),
+    confidence TEXT NOT NULL
+        CHECK (confidence IN ('LOW', 'MEDIUM', 'HIGH')),
+    affecting_peoria BOOLEAN NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_backwater_status_affecting
+    ON flood_analysis.backwater_status(timestamp DESC) WHERE affecting_peoria;
+
+COMMENT ON TABLE flood_analysis.backwater_status IS
+    'Per-cycle Mississippi backwater assessment for the LaGrange and Peoria pools';
+
+GRANT ALL PRIVILEGES ON flood_analysis.backwater_status TO flopro_admin;
+
+COMMIT;
@@ -0,0 +1,39 @@
+-- USGS Daily Statistics
+-- Migration 041: Day-of-year climatology per station and parameter
+--
+-- Purpose: USGS publishes, for every calendar day, the minimum, mean,
+--          maximum and 5th-95th percentiles of that day's mean value over
+--          the period of record. The API and escalation alerts compare
+--          the latest discharge with the row for its date
+--          (see ingest::usgs_stats).
+--
+-- Source: https://waterservices.usgs.gov/nwis/stat/?format=rdb&statReportType=daily&statTypeCd=all
+-- Written by: flomon daemon (weekly refresh)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS usgs_raw.daily_statistics (
+    site_code VARCHAR(15) NOT NULL,
+    parameter_code VARCHAR(5) NOT NULL,        -- 00060 discharge, 00065 stage
+    month SMALLINT NOT NULL CHECK (month BETWEEN 1 AND 12),
+    day SMALLINT NOT NULL CHECK (day BETWEEN 1 AND 31),
+    begin_year INTEGER,
+    end_year INTEGER,
+    year_count INTEGER,                        -- Years with data for this day
+    min_value DOUBLE PRECISION,
+    mean_value DOUBLE PRECISION,
+    max_value DOUBLE PRECISION,
+    percentiles DOUBLE PRECISION[] NOT NULL,   -- e.g. {5,10,20,25,50,75,80,90,95}
+    percentile_values DOUBLE PRECISION[] NOT NULL,
+    fetched_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (site_code, parameter_code, month, day)
+);
+
+COMMENT ON TABLE usgs_raw.daily_statistics IS
+    'USGS daily statistics: distribution of each calendar day''s mean over the period of record';
+
+GRANT ALL PRIVILEGES ON usgs_raw.daily_statistics TO flopro_admin;
+
+COMMIT;
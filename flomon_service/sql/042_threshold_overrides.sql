@@ -0,0 +1,38 @@
+-- Threshold Overrides
+-- Migration 042: Per-station flood thresholds that override the registry
+--
+-- Purpose: FloML-derived or site-specific flood stages, changed at runtime
+--          without editing usgs_stations.toml. A NULL level falls back to
+--          the registry value. updated_by records who or what set the row.
+--          The daemon re-reads this table every cycle and records each
+--          change in the effective thresholds as a new registry version
+--          (see threshold_overrides).
+--
+-- Written by: FloML, operators
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE SCHEMA IF NOT EXISTS flood_analysis;
+
+CREATE TABLE IF NOT EXISTS flood_analysis.thresholds (
+    site_code VARCHAR(15) PRIMARY KEY,
+    action_stage_ft DOUBLE PRECISION,
+    flood_stage_ft DOUBLE PRECISION,
+    moderate_flood_stage_ft DOUBLE PRECISION,
+    major_flood_stage_ft DOUBLE PRECISION,
+    updated_by TEXT NOT NULL,                  -- e.g. 'floml segmented-regression v3', an operator
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    reason TEXT,
+    CONSTRAINT thresholds_override_something CHECK (
+        COALESCE(action_stage_ft, flood_stage_ft, moderate_flood_stage_ft, major_flood_stage_ft) IS NOT NULL
+    )
+);
+
+COMMENT ON TABLE flood_analysis.thresholds IS
+    'Per-station flood stage overrides applied on top of the station registry';
+
+GRANT ALL PRIVILEGES ON flood_analysis.thresholds TO flopro_admin;
+
+COMMIT;
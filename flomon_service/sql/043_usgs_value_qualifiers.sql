@@ -0,0 +1,83 @@
+-- USGS Value Qualifiers
+-- Migration 043: Keep every NWIS qualifier on a reading, not just the first
+--
+-- Purpose: NWIS attaches several qualifiers to a value ("A" plus "e" for
+--          an estimate, "P" plus "Ice"), and the parsers now store them all,
+--          colon-joined ("A:e", "P:Ice"). The quality module maps them onto
+--          one scale, and alerts ignore estimated and ice-affected stage.
+--          The VARCHAR(1) column held only the approval code.
+--
+--          The column type cannot change under the views that select it,
+--          so public.latest_readings and usgs_raw.gauge_readings_all are
+--          dropped and recreated unchanged around the ALTER.
+--
+-- Written by: flomon daemon (USGS ingest)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+DROP VIEW IF EXISTS usgs_raw.gauge_readings_all;
+DROP MATERIALIZED VIEW IF EXISTS public.latest_readings;
+
+ALTER TABLE usgs_raw.gauge_readings ALTER COLUMN qualifier TYPE VARCHAR(32);
+
+COMMENT ON COLUMN usgs_raw.gauge_readings.qualifier IS
+    'NWIS qualifiers, colon-joined: P=Provisional, A=Approved, e=Estimated, Ice, Eqp, ... (see quality)';
+
+CREATE MATERIALIZED VIEW public.latest_readings AS
+WITH ranked_readings AS (
+    SELECT 
+        gr.site_code,
+        gr.parameter_code,
+        gr.value,
+        gr.unit,
+        gr.qualifier,
+        gr.reading_time,
+        s.site_name,
+        s.latitude,
+        s.longitude,
+        ROW_NUMBER() OVER (
+            PARTITION BY gr.site_code, gr.parameter_code 
+            ORDER BY gr.reading_time DESC
+        ) as rn
+    FROM usgs_raw.gauge_readings gr
+    INNER JOIN usgs_raw.sites s ON gr.site_code = s.site_code
+    WHERE s.active = true
+      AND gr.reading_time > NOW() - INTERVAL '6 hours'  -- Only recent data
+)
+SELECT 
+    site_code,
+    site_name,
+    latitude,
+    longitude,
+    parameter_code,
+    value,
+    unit,
+    qualifier,
+    reading_time,
+    NOW() - reading_time AS data_age
+FROM ranked_readings
+WHERE rn = 1;
+
+CREATE UNIQUE INDEX idx_latest_readings_site_param 
+    ON public.latest_readings(site_code, parameter_code);
+
+COMMENT ON MATERIALIZED VIEW public.latest_readings IS 'Most recent reading per site/parameter (refresh every 15 min)';
+
+CREATE VIEW usgs_raw.gauge_readings_all AS
+SELECT site_code, parameter_code, reading_time, value, unit, qualifier::TEXT AS qualifier, FALSE AS archived
+FROM usgs_raw.gauge_readings
+UNION ALL
+SELECT a.site_code, a.parameter_code, r.reading_time, r.value, a.unit, r.qualifier, TRUE AS archived
+FROM usgs_raw.gauge_readings_archive a
+CROSS JOIN LATERAL unnest(a.reading_times, a.reading_values, a.qualifiers)
+    AS r(reading_time, value, qualifier);
+
+COMMENT ON VIEW usgs_raw.gauge_readings_all IS
+    'Hot and archived USGS readings; prefer archive::readings_between in application code';
+
+GRANT SELECT ON public.latest_readings TO flopro_admin;
+GRANT SELECT ON usgs_raw.gauge_readings_all TO flopro_admin;
+
+COMMIT;
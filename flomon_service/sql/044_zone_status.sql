@@ -0,0 +1,36 @@
+-- Zone Status
+-- Migration 044: One rolled-up status per zone per daemon cycle
+--
+-- Purpose: The daemon reduces each zone's sensors (zones.toml) to its worst
+--          current severity, fastest stage rise, stale sensors and ASOS
+--          precipitation totals every cycle (see zones::status) and records
+--          the result here. GET /api/zones/{id}/status serves the latest row.
+--
+-- Written by: flomon daemon (every poll cycle)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE SCHEMA IF NOT EXISTS flood_analysis;
+
+CREATE TABLE IF NOT EXISTS flood_analysis.zone_status (
+    zone_id SMALLINT NOT NULL,
+    computed_at TIMESTAMPTZ NOT NULL,              -- Cycle time
+    severity TEXT
+        CHECK (severity IN ('action', 'flood', 'moderate', 'major')),
+    severity_site VARCHAR(15),                     -- Station at that severity
+    max_rise_ft_per_hour DOUBLE PRECISION,         -- Over the last 3 hours
+    max_rise_site VARCHAR(15),
+    sensor_count INTEGER NOT NULL,                 -- USGS, CWMS and ASOS sensors
+    stale_sensors TEXT[] NOT NULL DEFAULT '{}',
+    precip JSONB NOT NULL DEFAULT '[]',            -- [{window_hours, mean_in, max_in, station_count}]
+    PRIMARY KEY (zone_id, computed_at)
+);
+
+COMMENT ON TABLE flood_analysis.zone_status IS
+    'Per-cycle rollup of each zone''s sensors: worst severity, fastest rise, staleness, precipitation';
+
+GRANT ALL PRIVILEGES ON flood_analysis.zone_status TO flopro_admin;
+
+COMMIT;
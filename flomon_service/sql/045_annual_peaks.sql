@@ -0,0 +1,35 @@
+-- USGS Annual Peaks
+-- Migration 045: Annual peak stage and discharge per station and water year
+--
+-- Purpose: The USGS peak-flow file lists each water year's highest
+--          discharge with the stage at that time (and the year's highest
+--          stage when it came apart from the discharge peak). Escalation
+--          alerts rank the current stage among them (see ingest::usgs_peaks).
+--
+-- Source: https://nwis.waterdata.usgs.gov/nwis/peak?site_no={site}&agency_cd=USGS&format=rdb
+-- Written by: flomon daemon (monthly refresh)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS usgs_raw.annual_peaks (
+    site_code VARCHAR(15) NOT NULL,
+    water_year INTEGER NOT NULL,               -- Oct 1 - Sep 30, named for the ending year
+    peak_date DATE NOT NULL,
+    peak_time TIME,                            -- Often unknown for older peaks
+    peak_discharge_cfs DOUBLE PRECISION,
+    peak_codes TEXT[] NOT NULL DEFAULT '{}',   -- USGS peak_cd qualification codes
+    gage_height_ft DOUBLE PRECISION,           -- Stage at the peak discharge
+    gage_height_codes TEXT[] NOT NULL DEFAULT '{}',
+    alternate_gage_height_ft DOUBLE PRECISION, -- Year's highest stage, if not at the peak
+    fetched_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (site_code, water_year)
+);
+
+COMMENT ON TABLE usgs_raw.annual_peaks IS
+    'USGS annual peak streamflow: each water year''s peak discharge and stage over the period of record';
+
+GRANT ALL PRIVILEGES ON usgs_raw.annual_peaks TO flopro_admin;
+
+COMMIT;
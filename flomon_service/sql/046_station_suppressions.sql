@@ -0,0 +1,39 @@
+-- Station Suppressions
+-- Migration 046: Stations or sensors marked down until a time, with a reason
+--
+-- Purpose: A station pulled for maintenance fails every poll and goes stale.
+--          A row here (written by POST /api/suppressions) marks the station,
+--          or one parameter of it, down until `until`. Meanwhile the daemon
+--          logs its failures as expected and skips its staleness and
+--          severity alerts (see monitor::suppression). Rows are kept after
+--          they lapse or are ended early (ended_at).
+--
+-- Written by: flomon endpoint (operators)
+
+\set ON_ERROR_STOP on
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS station_suppressions (
+    id BIGSERIAL PRIMARY KEY,
+    source TEXT NOT NULL CHECK (source IN ('usgs', 'cwms', 'asos')),
+    station_id VARCHAR(64) NOT NULL,           -- Site code, CWMS location or ASOS id
+    parameter_code VARCHAR(16),                -- One sensor; NULL for the whole station
+    until TIMESTAMPTZ NOT NULL,
+    reason TEXT NOT NULL,
+    created_by TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ended_at TIMESTAMPTZ,                      -- Ended early
+    CHECK (until > created_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_station_suppressions_until
+    ON station_suppressions (until) WHERE ended_at IS NULL;
+
+COMMENT ON TABLE station_suppressions IS
+    'Stations or sensors down for maintenance: failures expected, staleness and severity alerts skipped until `until`';
+
+GRANT ALL PRIVILEGES ON station_suppressions TO flopro_admin;
+GRANT USAGE, SELECT ON SEQUENCE station_suppressions_id_seq TO flopro_admin;
+
+COMMIT;
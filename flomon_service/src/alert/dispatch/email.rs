@@ -0,0 +1,414 @@
+//! Email channel: a minimal SMTP client.
+//!
+//! Enough of RFC 5321 to hand one plain-text message to a relay: EHLO,
+//! optional STARTTLS (or implicit TLS on port 465), AUTH PLAIN, MAIL/RCPT/
+//! DATA, QUIT. TLS uses the same rustls and Mozilla root set as the HTTP
+//! client. Delivery beyond the relay (queueing, bounces) is the relay's job;
+//! a relay rejecting the message is a delivery failure and the notification
+//! stays queued.
+//!
+//! A bare `email` recipient goes to the configured `to` list; an email
+//! address as recipient (or `email:someone@example.com`) goes to that
+//! address.
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::alert::dispatch::Notifier;
+use crate::alert::queue::{Notification, Urgency};
+
+pub const KIND: &str = "email";
+
+/// How the connection is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Security {
+    /// Plain connection upgraded with STARTTLS (port 587)
+    Starttls,
+    /// TLS from the first byte (port 465)
+    Tls,
+    /// No encryption; only for a relay on localhost
+    None,
+}
+
+fn default_port() -> u16 {
+    587
+}
+
+fn default_security() -> Security {
+    Security::Starttls
+}
+
+fn default_timeout_secs() -> u64 {
+    20
+}
+
+/// `kind = "email"` settings
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct EmailSettings {
+    pub smtp_host: String,
+    #[serde(default = "default_port")]
+    pub smtp_port: u16,
+    #[serde(default = "default_security")]
+    pub security: Security,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Envelope and header sender
+    pub from: String,
+    /// Recipients of a bare `email` notification
+    #[serde(default)]
+    pub to: Vec<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl EmailSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.smtp_host.trim().is_empty() {
+            return Err("smtp_host must not be empty".to_string());
+        }
+        if let Some(bad) = std::iter::once(&self.from).chain(&self.to).find(|a| !valid_address(a)) {
+            return Err(format!("'{}' is not an email address", bad));
+        }
+        if self.username.is_some() != self.password.is_some() {
+            return Err("username and password must be set together".to_string());
+        }
+        if self.username.is_some() && self.security == Security::None {
+            return Err("refusing to send SMTP credentials without TLS (security = \"none\")".to_string());
+        }
+        if self.timeout_secs == 0 {
+            return Err("timeout_secs must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Plausible mailbox: one '@', something on both sides, no whitespace or
+/// angle brackets (it goes into SMTP commands verbatim)
+fn valid_address(address: &str) -> bool {
+    address.split_once('@').is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'))
+        && !address.contains(|c: char| c.is_whitespace() || c == '<' || c == '>' || c.is_control())
+        && address.matches('@').count() == 1
+}
+
+impl Notifier for EmailSettings {
+    fn kind(&self) -> &'static str {
+        KIND
+    }
+
+    fn send(&self, address: Option<&str>, notification: &Notification) -> Result<(), String> {
+        let recipients: Vec<&str> = match address {
+            Some(address) if valid_address(address) => vec![address],
+            Some(address) => return Err(format!("'{}' is not an email address", address)),
+            None if self.to.is_empty() => return Err("email channel has no default 'to' recipients".to_string()),
+            None => self.to.iter().map(String::as_str).collect(),
+        };
+        let message = format_message(&self.from, &recipients, notification, Utc::now());
+        let mut session = self.connect()?;
+        session.deliver(self, &recipients, &message)
+            .map_err(|e| format!("SMTP delivery via {} failed: {}", self.smtp_host, e))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Message
+// ---------------------------------------------------------------------------
+
+/// RFC 5322 message with CRLF line endings, dot-stuffed for DATA
+pub fn format_message(from: &str, to: &[&str], notification: &Notification, now: DateTime<Utc>) -> String {
+    let alert = &notification.alert;
+    let subject = format!("[flomon] {} alert", alert.severity.as_str().to_uppercase());
+    let mut body = alert.message.clone();
+    if let Some(version) = alert.registry_version {
+        body.push_str(&format!("\n\nThresholds: station registry v{}", version));
+    }
+    body.push_str(&format!("\nQueued: {}", notification.queued_at.to_rfc3339()));
+
+    let priority = match notification.urgency {
+        Urgency::Normal => "",
+        Urgency::Quiet => "Importance: low\r\nX-Priority: 5\r\n",
+    };
+    let mut message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\n{}MIME-Version: 1.0\r\n\
+         Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n",
+        from, to.join(", "), subject, now.to_rfc2822(), priority,
+    );
+    for line in body.lines() {
+        if line.starts_with('.') {
+            message.push('.');
+        }
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message
+}
+
+// ---------------------------------------------------------------------------
+// SMTP session
+// ---------------------------------------------------------------------------
+
+/// Byte stream the session runs over (TCP, TLS, or a test double)
+pub trait Stream: Read + Write + Send {}
+impl<T: Read + Write + Send> Stream for T {}
+
+impl EmailSettings {
+    /// Session ready for `deliver`, upgraded to TLS if configured
+    fn connect(&self) -> Result<Session<Box<dyn Stream>>, String> {
+        let timeout = Duration::from_secs(self.timeout_secs);
+        let addr = (self.smtp_host.as_str(), self.smtp_port).to_socket_addrs()
+            .map_err(|e| format!("Failed to resolve {}: {}", self.smtp_host, e))?
+            .next()
+            .ok_or_else(|| format!("{} did not resolve", self.smtp_host))?;
+        let tcp = TcpStream::connect_timeout(&addr, timeout)
+            .map_err(|e| format!("Failed to connect to {}:{}: {}", self.smtp_host, self.smtp_port, e))?;
+        tcp.set_read_timeout(Some(timeout)).and_then(|_| tcp.set_write_timeout(Some(timeout)))
+            .map_err(|e| format!("Failed to set SMTP timeouts: {}", e))?;
+
+        match self.security {
+            Security::Tls => wrap_tls(&self.smtp_host, Box::new(tcp)).map(Session::new),
+            Security::Starttls => {
+                let mut session = Session::new(Box::new(tcp) as Box<dyn Stream>);
+                session.starttls()?;
+                wrap_tls(&self.smtp_host, session.into_inner()).map(Session::upgraded)
+            }
+            Security::None => Ok(Session::new(Box::new(tcp))),
+        }
+    }
+}
+
+fn wrap_tls(host: &str, stream: Box<dyn Stream>) -> Result<Box<dyn Stream>, String> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+        rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)
+    }));
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let server_name = rustls::ServerName::try_from(host)
+        .map_err(|e| format!("Invalid SMTP host name {}: {}", host, e))?;
+    let connection = rustls::ClientConnection::new(Arc::new(config), server_name)
+        .map_err(|e| format!("Failed to start TLS with {}: {}", host, e))?;
+    Ok(Box::new(rustls::StreamOwned::new(connection, stream)))
+}
+
+/// Name sent with EHLO
+fn hello_name() -> String {
+    crate::monitor::failover::host_name()
+}
+
+/// One SMTP conversation
+pub struct Session<S: Read + Write> {
+    reader: BufReader<S>,
+    /// The server's 220 greeting was already read on this connection
+    greeted: bool,
+}
+
+impl<S: Read + Write> Session<S> {
+    pub fn new(stream: S) -> Self {
+        Self { reader: BufReader::new(stream), greeted: false }
+    }
+
+    /// Session over a stream just upgraded by STARTTLS. The server sends
+    /// no new greeting after the handshake (RFC 3207 section 4.2), so the
+    /// conversation resumes with EHLO.
+    pub fn upgraded(stream: S) -> Self {
+        Self { reader: BufReader::new(stream), greeted: true }
+    }
+
+    fn into_inner(self) -> S {
+        self.reader.into_inner()
+    }
+
+    /// Read one (possibly multi-line) reply and check its code
+    fn expect(&mut self, code: u16) -> Result<String, String> {
+        let mut reply = String::new();
+        loop {
+            let mut line = String::new();
+            let read = self.reader.read_line(&mut line).map_err(|e| format!("read failed: {}", e))?;
+            if read == 0 {
+                return Err("connection closed".to_string());
+            }
+            reply.push_str(&line);
+            // "250-more follows" vs "250 last line"
+            if line.as_bytes().get(3) != Some(&b'-') {
+                break;
+            }
+        }
+        match reply.get(..3).and_then(|c| c.parse::<u16>().ok()) {
+            Some(got) if got == code => Ok(reply),
+            _ => Err(format!("expected {}, got: {}", code, reply.trim())),
+        }
+    }
+
+    fn command(&mut self, command: &str, code: u16) -> Result<String, String> {
+        let stream = self.reader.get_mut();
+        stream.write_all(command.as_bytes())
+            .and_then(|_| stream.write_all(b"\r\n"))
+            .and_then(|_| stream.flush())
+            .map_err(|e| format!("write failed: {}", e))?;
+        self.expect(code)
+    }
+
+    /// Greeting, EHLO and STARTTLS on the plain connection; the caller
+    /// then wraps the stream in TLS
+    pub fn starttls(&mut self) -> Result<(), String> {
+        self.expect(220)?;
+        self.command(&format!("EHLO {}", hello_name()), 250)?;
+        self.command("STARTTLS", 220)?;
+        Ok(())
+    }
+
+    /// Greeting (unless already read) through QUIT for one message
+    pub fn deliver(&mut self, settings: &EmailSettings, to: &[&str], message: &str) -> Result<(), String> {
+        if !self.greeted {
+            self.expect(220)?;
+            self.greeted = true;
+        }
+        self.command(&format!("EHLO {}", hello_name()), 250)?;
+        if let (Some(username), Some(password)) = (&settings.username, &settings.password) {
+            let credentials = base64::engine::general_purpose::STANDARD
+                .encode(format!("\0{}\0{}", username, password));
+            self.command(&format!("AUTH PLAIN {}", credentials), 235)?;
+        }
+        self.command(&format!("MAIL FROM:<{}>", settings.from), 250)?;
+        for recipient in to {
+            self.command(&format!("RCPT TO:<{}>", recipient), 250)?;
+        }
+        self.command("DATA", 354)?;
+        self.command(&format!("{}.", message), 250)?;
+        // The message is accepted; a failed QUIT doesn't change that
+        let _ = self.command("QUIT", 221);
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alert::thresholds::{FloodAlert, FloodSeverity};
+    use chrono::TimeZone;
+    use std::io::Cursor;
+
+    /// Scripted server replies in, client commands out
+    struct Script {
+        replies: Cursor<Vec<u8>>,
+        written: Vec<u8>,
+    }
+
+    impl Read for Script {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.replies.read(buf)
+        }
+    }
+
+    impl Write for Script {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn settings() -> EmailSettings {
+        EmailSettings {
+            smtp_host: "smtp.example.org".to_string(),
+            smtp_port: 587,
+            security: Security::Starttls,
+            username: Some("flomon".to_string()),
+            password: Some("secret".to_string()),
+            from: "flomon@example.org".to_string(),
+            to: vec!["family@example.org".to_string()],
+            timeout_secs: 20,
+        }
+    }
+
+    fn notification() -> Notification {
+        Notification {
+            recipient: "email".to_string(),
+            urgency: Default::default(),
+            alert: FloodAlert {
+                severity: FloodSeverity::Flood,
+                message: "FLOOD at Kingston Mines: 16.40 ft\n.dot line".to_string(),
+                registry_version: Some(4),
+            },
+            queued_at: Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            attempts: 0,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn test_message_headers_and_dot_stuffing() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 5, 0).unwrap();
+        let message = format_message("flomon@example.org", &["a@example.org", "b@example.org"], &notification(), now);
+        assert!(message.starts_with("From: flomon@example.org\r\nTo: a@example.org, b@example.org\r\nSubject: [flomon] FLOOD alert\r\n"));
+        assert!(message.contains("\r\n\r\nFLOOD at Kingston Mines: 16.40 ft\r\n..dot line\r\n"));
+        assert!(message.contains("station registry v4"));
+    }
+
+    #[test]
+    fn test_session_conversation() {
+        let replies = "220 smtp.example.org ESMTP\r\n250-smtp.example.org\r\n250 AUTH PLAIN\r\n235 ok\r\n\
+                       250 sender ok\r\n250 rcpt ok\r\n354 go ahead\r\n250 queued\r\n221 bye\r\n";
+        let mut session = Session::new(Script { replies: Cursor::new(replies.into()), written: Vec::new() });
+        session.deliver(&settings(), &["family@example.org"], "Subject: x\r\n\r\nhi\r\n").unwrap();
+
+        let written = String::from_utf8(session.into_inner().written).unwrap();
+        let commands: Vec<&str> = written.split("\r\n").collect();
+        assert!(commands[0].starts_with("EHLO "));
+        assert_eq!(commands[1], format!("AUTH PLAIN {}", base64::engine::general_purpose::STANDARD.encode("\0flomon\0secret")));
+        assert_eq!(&commands[2..5], ["MAIL FROM:<flomon@example.org>", "RCPT TO:<family@example.org>", "DATA"]);
+        assert!(written.ends_with("hi\r\n.\r\nQUIT\r\n"));
+    }
+
+    #[test]
+    fn test_starttls_conversation_resumes_with_ehlo() {
+        let plain = "220 smtp.example.org ESMTP\r\n250-smtp.example.org\r\n250 STARTTLS\r\n220 ready for TLS\r\n";
+        let mut session = Session::new(Script { replies: Cursor::new(plain.into()), written: Vec::new() });
+        session.starttls().unwrap();
+        let written = String::from_utf8(session.into_inner().written).unwrap();
+        let commands: Vec<&str> = written.split("\r\n").collect();
+        assert!(commands[0].starts_with("EHLO "));
+        assert_eq!(commands[1], "STARTTLS");
+
+        // After the handshake the server waits for EHLO; there is no greeting
+        let tls = "250-smtp.example.org\r\n250 AUTH PLAIN\r\n235 ok\r\n\
+                   250 sender ok\r\n250 rcpt ok\r\n354 go ahead\r\n250 queued\r\n221 bye\r\n";
+        let mut session = Session::upgraded(Script { replies: Cursor::new(tls.into()), written: Vec::new() });
+        session.deliver(&settings(), &["family@example.org"], "Subject: x\r\n\r\nhi\r\n").unwrap();
+        let written = String::from_utf8(session.into_inner().written).unwrap();
+        assert!(written.starts_with("EHLO "));
+        assert!(written.contains("\r\nAUTH PLAIN ") && written.ends_with("hi\r\n.\r\nQUIT\r\n"));
+    }
+
+    #[test]
+    fn test_rejected_recipient_fails_delivery() {
+        let replies = "220 hi\r\n250 ok\r\n235 ok\r\n250 ok\r\n550 no such user\r\n";
+        let mut session = Session::new(Script { replies: Cursor::new(replies.into()), written: Vec::new() });
+        let err = session.deliver(&settings(), &["nobody@example.org"], "x\r\n").unwrap_err();
+        assert!(err.contains("550 no such user"), "got {}", err);
+    }
+
+    #[test]
+    fn test_settings_validation() {
+        assert!(settings().validate().is_ok());
+        assert!(EmailSettings { security: Security::None, ..settings() }.validate().is_err());
+        assert!(EmailSettings { to: vec!["not an address".to_string()], ..settings() }.validate().is_err());
+        assert!(EmailSettings { password: None, ..settings() }.validate().is_err());
+    }
+}
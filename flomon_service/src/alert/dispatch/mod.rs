@@ -0,0 +1,327 @@
+//! Notification dispatch: pluggable delivery channels.
+//!
+//! When a station escalates, the daemon queues the alert for every
+//! recipient subscribed to the station's zones (`alert::subscriptions`), and
+//! each poll cycle the queue (`alert::queue`) hands the notifications to the
+//! `Dispatcher`. The dispatcher picks a channel by recipient:
+//!
+//! - `name` — the channel called `name`, with its default destination
+//! - `name:address` — the channel called `name`, sent to `address` (a room,
+//!   topic or email address, depending on the channel)
+//! - `someone@example.com` — the first email channel
+//!
+//! Channels are `[[notifiers]]` entries in flomon.toml (plus `[matrix]`, see
+//! `alert::matrix`), each a `Notifier` implementation with its own severity
+//! floor, so ntfy can page on everything from action stage while email only
+//! goes out at flood stage. A notification below its channel's floor is
+//! dropped as delivered; one no channel claims falls through to the log.
+//!
+//! Built-in channels:
+//! - `email` — SMTP with STARTTLS, implicit TLS or plain (see `email`)
+//! - `webhook` — JSON POST of `flomon_types::webhook::AlertWebhookPayload`
+//! - `ntfy` — ntfy.sh (or a self-hosted ntfy) topic
+//!
+//! A channel can also limit the alert text's length and character set
+//! (`max_length`, `charset`); the dispatcher shortens the message to fit
+//! before the channel sees it, keeping station, severity and value (see
+//! `text`).
+//!
+//! `Urgency::Quiet` notifications (zones nobody is at, see
+//! `alert::occupancy`) are still delivered, but without a ping: ntfy
+//! priority 2, a Matrix `m.notice`, low-importance email headers, and an
+//! `X-Flomon-Urgency: quiet` header on webhooks.
+
+pub mod email;
+pub mod ntfy;
+pub mod text;
+pub mod webhook;
+
+use serde::Deserialize;
+use std::collections::HashSet;
+
+use crate::alert::matrix::MatrixSettings;
+use crate::alert::queue::Notification;
+use crate::alert::thresholds::FloodSeverity;
+use crate::logging;
+
+use self::email::EmailSettings;
+use self::ntfy::NtfySettings;
+use self::text::{Charset, Readability, TextRules};
+use self::webhook::WebhookSettings;
+
+/// A delivery channel
+pub trait Notifier: Send {
+    /// Channel type, e.g. "email"
+    fn kind(&self) -> &'static str;
+
+    /// Deliver one notification. `address` is the part of the recipient
+    /// after `name:`; `None` means the channel's configured default.
+    fn send(&self, address: Option<&str>, notification: &Notification) -> Result<(), String>;
+}
+
+fn default_min_severity() -> FloodSeverity {
+    FloodSeverity::Action
+}
+
+/// `[[notifiers]]` entry in flomon.toml
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotifierConfig {
+    /// Recipient name that selects this channel
+    pub name: String,
+    /// Notifications below this severity are not sent on this channel
+    #[serde(default = "default_min_severity")]
+    pub min_severity: FloodSeverity,
+    /// Longest alert text sent on this channel; unlimited when absent
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    /// Characters this channel can carry
+    #[serde(default)]
+    pub charset: Charset,
+    #[serde(flatten)]
+    pub channel: ChannelConfig,
+}
+
+/// Channel type and its settings, selected by `kind`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChannelConfig {
+    Email(EmailSettings),
+    Webhook(WebhookSettings),
+    Ntfy(NtfySettings),
+}
+
+impl NotifierConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.name.contains([':', '@']) {
+            return Err(format!("notifier name '{}' must be non-empty without ':' or '@'", self.name));
+        }
+        self.text_rules().validate()
+            .and_then(|_| match &self.channel {
+                ChannelConfig::Email(settings) => settings.validate(),
+                ChannelConfig::Webhook(settings) => settings.validate(),
+                ChannelConfig::Ntfy(settings) => settings.validate(),
+            })
+            .map_err(|e| format!("notifier {}: {}", self.name, e))
+    }
+
+    pub fn text_rules(&self) -> TextRules {
+        TextRules { max_length: self.max_length, charset: self.charset }
+    }
+
+    fn notifier(&self) -> Box<dyn Notifier> {
+        match &self.channel {
+            ChannelConfig::Email(settings) => Box::new(settings.clone()),
+            ChannelConfig::Webhook(settings) => Box::new(settings.clone()),
+            ChannelConfig::Ntfy(settings) => Box::new(settings.clone()),
+        }
+    }
+}
+
+/// Name the `[matrix]` section's channel is addressed by
+pub const MATRIX_CHANNEL: &str = "matrix";
+
+/// Notifier names are unique, and "matrix" is taken when `[matrix]` is set
+pub fn validate_names(notifiers: &[NotifierConfig], matrix: bool) -> Result<(), String> {
+    let mut seen: HashSet<&str> = HashSet::new();
+    if matrix {
+        seen.insert(MATRIX_CHANNEL);
+    }
+    match notifiers.iter().find(|n| !seen.insert(n.name.as_str())) {
+        Some(duplicate) => Err(format!("notifier name '{}' is used twice", duplicate.name)),
+        None => Ok(()),
+    }
+}
+
+/// One configured channel
+pub struct Channel {
+    pub name: String,
+    pub min_severity: FloodSeverity,
+    pub text: TextRules,
+    notifier: Box<dyn Notifier>,
+}
+
+/// Routes notifications to channels
+#[derive(Default)]
+pub struct Dispatcher {
+    channels: Vec<Channel>,
+}
+
+impl Dispatcher {
+    pub fn from_config(notifiers: &[NotifierConfig], matrix: Option<&MatrixSettings>) -> Self {
+        let mut dispatcher = Self::default();
+        if let Some(matrix) = matrix {
+            dispatcher.add(MATRIX_CHANNEL, FloodSeverity::Action, Box::new(matrix.clone()));
+        }
+        for config in notifiers {
+            dispatcher.add_with_text(&config.name, config.min_severity.clone(), config.text_rules(), config.notifier());
+        }
+        dispatcher
+    }
+
+    pub fn add(&mut self, name: &str, min_severity: FloodSeverity, notifier: Box<dyn Notifier>) {
+        self.add_with_text(name, min_severity, TextRules::default(), notifier);
+    }
+
+    /// Add a channel whose alert text is constrained by `text`
+    pub fn add_with_text(&mut self, name: &str, min_severity: FloodSeverity, text: TextRules, notifier: Box<dyn Notifier>) {
+        self.channels.push(Channel { name: name.to_string(), min_severity, text, notifier });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    /// Channel and address for a recipient, if any channel claims it
+    pub fn route<'a>(&self, recipient: &'a str) -> Option<(&Channel, Option<&'a str>)> {
+        let (name, address) = match recipient.split_once(':') {
+            Some((name, address)) => (name, Some(address)),
+            None => (recipient, None),
+        };
+        if let Some(channel) = self.channels.iter().find(|c| c.name == name) {
+            return Some((channel, address));
+        }
+        (address.is_none() && recipient.contains('@'))
+            .then(|| self.channels.iter().find(|c| c.notifier.kind() == email::KIND))
+            .flatten()
+            .map(|channel| (channel, Some(recipient)))
+    }
+
+    /// Deliver through the recipient's channel; None when no channel
+    /// claims the recipient
+    pub fn deliver(&self, notification: &Notification) -> Option<Result<(), String>> {
+        let (channel, address) = self.route(&notification.recipient)?;
+        if notification.alert.severity < channel.min_severity {
+            logging::debug(
+                logging::DataSource::System,
+                None,
+                &format!("Not sending {} alert to {}: below {} floor ({})",
+                    notification.alert.severity.as_str(), notification.recipient,
+                    channel.name, channel.min_severity.as_str()),
+            );
+            return Some(Ok(()));
+        }
+        if channel.text.is_unconstrained() {
+            return Some(channel.notifier.send(address, notification));
+        }
+        let (message, readability) = channel.text.apply(&notification.alert.message);
+        if readability != Readability::Full {
+            logging::debug(
+                logging::DataSource::System,
+                None,
+                &format!("Alert for {} shortened to fit {} ({}): {}",
+                    notification.recipient, channel.name, readability.as_str(), message),
+            );
+        }
+        let mut shaped = notification.clone();
+        shaped.alert.message = message;
+        Some(channel.notifier.send(address, &shaped))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alert::thresholds::FloodAlert;
+    use chrono::Utc;
+    use std::sync::{Arc, Mutex};
+
+    /// (address, message) pairs sent
+    type Sent = Arc<Mutex<Vec<(Option<String>, String)>>>;
+
+    /// Records what it was asked to send
+    struct Recorder {
+        kind: &'static str,
+        sent: Sent,
+    }
+
+    impl Notifier for Recorder {
+        fn kind(&self) -> &'static str {
+            self.kind
+        }
+
+        fn send(&self, address: Option<&str>, notification: &Notification) -> Result<(), String> {
+            self.sent.lock().unwrap().push((address.map(str::to_string), notification.alert.message.clone()));
+            Ok(())
+        }
+    }
+
+    fn notification(recipient: &str, severity: FloodSeverity) -> Notification {
+        Notification {
+            recipient: recipient.to_string(),
+            urgency: Default::default(),
+            alert: FloodAlert { severity, message: recipient.to_string(), registry_version: None },
+            queued_at: Utc::now(),
+            attempts: 0,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn test_routing_and_severity_floor() {
+        let sent: Sent = Arc::default();
+        let mut dispatcher = Dispatcher::default();
+        dispatcher.add("phones", FloodSeverity::Action, Box::new(Recorder { kind: ntfy::KIND, sent: sent.clone() }));
+        dispatcher.add("email", FloodSeverity::Flood, Box::new(Recorder { kind: email::KIND, sent: sent.clone() }));
+
+        assert!(dispatcher.deliver(&notification("phones", FloodSeverity::Action)).unwrap().is_ok());
+        assert!(dispatcher.deliver(&notification("phones:dock", FloodSeverity::Major)).unwrap().is_ok());
+        assert!(dispatcher.deliver(&notification("ema@example.org", FloodSeverity::Moderate)).unwrap().is_ok());
+        // Below the email floor: dropped, not an error
+        assert!(dispatcher.deliver(&notification("neighbor@example.com", FloodSeverity::Action)).unwrap().is_ok());
+        assert!(dispatcher.deliver(&notification("+13095550100", FloodSeverity::Major)).is_none());
+
+        assert_eq!(*sent.lock().unwrap(), [
+            (None, "phones".to_string()),
+            (Some("dock".to_string()), "phones:dock".to_string()),
+            (Some("ema@example.org".to_string()), "ema@example.org".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_channel_text_rules_applied_before_send() {
+        let sent: Sent = Arc::default();
+        let mut dispatcher = Dispatcher::default();
+        let rules = TextRules { max_length: Some(40), charset: Charset::Gsm7 };
+        dispatcher.add_with_text("sms", FloodSeverity::Action, rules, Box::new(Recorder { kind: email::KIND, sent: sent.clone() }));
+
+        let mut long = notification("sms", FloodSeverity::Major);
+        long.alert.message = "MAJOR FLOOD at Illinois River at Peoria, IL: 29.10 ft (major flood stage: 28.00 ft)".to_string();
+        assert!(dispatcher.deliver(&long).unwrap().is_ok());
+        assert_eq!(sent.lock().unwrap()[0].1, "MAJOR FLOOD at Illinois Riv...: 29.10 ft");
+    }
+
+    #[test]
+    fn test_notifier_config_from_toml() {
+        let notifiers: Vec<NotifierConfig> = toml::from_str::<toml::Table>(r#"
+[[notifiers]]
+name = "phones"
+kind = "ntfy"
+topic = "river-alerts"
+min_severity = "moderate"
+max_length = 160
+charset = "gsm7"
+
+[[notifiers]]
+name = "hook"
+kind = "webhook"
+url = "https://example.org/flomon"
+"#).unwrap()["notifiers"].clone().try_into().unwrap();
+
+        assert_eq!(notifiers[0].min_severity, FloodSeverity::Moderate);
+        assert!(matches!(&notifiers[0].channel, ChannelConfig::Ntfy(s) if s.topic == "river-alerts"));
+        assert_eq!(notifiers[0].text_rules(), TextRules { max_length: Some(160), charset: Charset::Gsm7 });
+        assert_eq!(notifiers[1].min_severity, FloodSeverity::Action);
+        assert!(notifiers[1].text_rules().is_unconstrained());
+        assert!(notifiers.iter().all(|n| n.validate().is_ok()));
+        assert!(validate_names(&notifiers, true).is_ok());
+
+        let clash = NotifierConfig { name: MATRIX_CHANNEL.to_string(), ..notifiers[1].clone() };
+        let clash = [clash];
+        assert!(validate_names(&clash, false).is_ok());
+        assert!(validate_names(&clash, true).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! ntfy channel: push notifications through ntfy.sh or a self-hosted ntfy.
+//!
+//! Publishes the alert text to `{server}/{topic}` with a title and a
+//! priority that rises with severity, so phones can be set to break
+//! through do-not-disturb only for major flooding. `name:othertopic`
+//! publishes to another topic on the same server.
+
+use serde::Deserialize;
+
+use crate::alert::dispatch::Notifier;
+use crate::alert::queue::{Notification, Urgency};
+use crate::alert::thresholds::FloodSeverity;
+
+pub const KIND: &str = "ntfy";
+
+fn default_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+/// `kind = "ntfy"` settings
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct NtfySettings {
+    #[serde(default = "default_server")]
+    pub server: String,
+    pub topic: String,
+    /// Access token for protected topics
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl NtfySettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.server.starts_with("https://") || self.server.starts_with("http://")) {
+            return Err(format!("server must be an http(s) URL, got '{}'", self.server));
+        }
+        if !valid_topic(&self.topic) {
+            return Err(format!("topic '{}' must be letters, digits, '-' or '_'", self.topic));
+        }
+        if self.timeout_secs == 0 {
+            return Err("timeout_secs must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn topic_url(&self, topic: &str) -> String {
+        format!("{}/{}", self.server.trim_end_matches('/'), topic)
+    }
+}
+
+fn valid_topic(topic: &str) -> bool {
+    !topic.is_empty() && topic.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// ntfy priority 1-5 (5 = urgent); quiet notifications are capped at 2
+/// (no sound or vibration)
+pub fn priority(severity: &FloodSeverity, urgency: Urgency) -> u8 {
+    let priority = match severity {
+        FloodSeverity::Action => 3,
+        FloodSeverity::Flood => 4,
+        FloodSeverity::Moderate => 4,
+        FloodSeverity::Major => 5,
+    };
+    match urgency {
+        Urgency::Normal => priority,
+        Urgency::Quiet => priority.min(2),
+    }
+}
+
+impl Notifier for NtfySettings {
+    fn kind(&self) -> &'static str {
+        KIND
+    }
+
+    fn send(&self, address: Option<&str>, notification: &Notification) -> Result<(), String> {
+        let topic = address.unwrap_or(&self.topic);
+        if !valid_topic(topic) {
+            return Err(format!("Invalid ntfy topic '{}'", topic));
+        }
+        let severity = &notification.alert.severity;
+        let client = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(self.timeout_secs))
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+        let mut request = client.post(self.topic_url(topic))
+            .header("Title", format!("Flood alert: {}", severity.as_str()))
+            .header("Priority", priority(severity, notification.urgency).to_string())
+            .header("Tags", "ocean")
+            .body(notification.alert.message.clone());
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send()
+            .map_err(|e| format!("ntfy publish to {} failed: {}", topic, e))?;
+        if !response.status().is_success() {
+            return Err(format!("ntfy publish to {} returned HTTP {}", topic, response.status()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+//! Per-channel alert text rules: length limit and character set.
+//!
+//! An SMS gateway behind an email or webhook channel splits anything over
+//! 160 GSM-7 characters (70 once a single character outside GSM-7 appears)
+//! into parts, which some carriers deliver out of order. A `[[notifiers]]`
+//! entry can constrain the alert text it sends:
+//!
+//! ```toml
+//! [[notifiers]]
+//! name = "sms"
+//! kind = "email"
+//! max_length = 160     # characters of alert text (GSM-7 escapes count 2)
+//! charset = "gsm7"     # any (default) | gsm7 | ascii
+//! ```
+//!
+//! Characters outside the charset are replaced by a look-alike (`≥` by
+//! `>=`, `–` by `-`) or `?`. Text over `max_length` is shortened one step
+//! at a time until it fits, so the station, severity and value always
+//! survive:
+//!
+//! 1. `Detail` — the parenthesized threshold detail is dropped
+//! 2. `Station` — the station name is cut short with an ellipsis
+//! 3. `Cut` — the text is cut at `max_length` (only for text that isn't
+//!    a `<SEVERITY> at <station>: <value> ...` alert)
+//!
+//! The step reached is the text's readability: `Full` is the message as
+//! written, and each later step loses more of it.
+
+use serde::Deserialize;
+
+/// Shortest `max_length` accepted; below it even a shortened alert loses
+/// its value
+pub const MIN_MAX_LENGTH: usize = 40;
+
+/// Characters a channel can carry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Charset {
+    /// Anything (Unicode)
+    #[default]
+    Any,
+    /// GSM 03.38 default alphabet plus its escape extension (SMS)
+    Gsm7,
+    /// Printable ASCII and newlines
+    Ascii,
+}
+
+/// GSM 03.38 default alphabet
+const GSM7_BASIC: &str = "@£$¥èéùìòÇ\nØø\rÅåΔ_ΦΓΛΩΠΨΣΘΞÆæßÉ !\"#¤%&'()*+,-./0123456789:;<=>?\
+    ¡ABCDEFGHIJKLMNOPQRSTUVWXYZÄÖÑÜ§¿abcdefghijklmnopqrstuvwxyzäöñüà";
+/// Characters sent as an escape plus a code, so counted twice
+const GSM7_EXTENSION: &str = "^{}\\[~]|€";
+
+impl Charset {
+    fn allows(self, c: char) -> bool {
+        match self {
+            Charset::Any => true,
+            Charset::Gsm7 => GSM7_BASIC.contains(c) || GSM7_EXTENSION.contains(c),
+            Charset::Ascii => c == '\n' || c.is_ascii_graphic() || c == ' ',
+        }
+    }
+
+    /// Length of `text` as the channel counts it
+    pub fn length(self, text: &str) -> usize {
+        match self {
+            Charset::Gsm7 => text.chars().map(|c| if GSM7_EXTENSION.contains(c) { 2 } else { 1 }).sum(),
+            _ => text.chars().count(),
+        }
+    }
+
+    fn ellipsis(self) -> &'static str {
+        match self {
+            Charset::Any => "…",
+            _ => "...",
+        }
+    }
+
+    /// `text` with characters the charset lacks replaced
+    pub fn convert(self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        for c in text.chars() {
+            if self.allows(c) {
+                out.push(c);
+                continue;
+            }
+            let replacement = match c {
+                '≥' => ">=",
+                '≤' => "<=",
+                '–' | '—' | '−' => "-",
+                '‘' | '’' => "'",
+                '“' | '”' => "\"",
+                '…' => "...",
+                '→' => "->",
+                '↑' => "up",
+                '↓' => "down",
+                '°' => " deg",
+                '\u{a0}' | '\t' => " ",
+                _ => "?",
+            };
+            // A look-alike may itself be outside the charset (e.g. `{`)
+            out.extend(replacement.chars().map(|r| if self.allows(r) { r } else { '?' }));
+        }
+        out
+    }
+}
+
+/// How far text had to be shortened to fit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Readability {
+    Full,
+    Detail,
+    Station,
+    Cut,
+}
+
+impl Readability {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Readability::Full => "full",
+            Readability::Detail => "detail dropped",
+            Readability::Station => "station shortened",
+            Readability::Cut => "cut",
+        }
+    }
+}
+
+/// A channel's text constraints
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextRules {
+    pub max_length: Option<usize>,
+    pub charset: Charset,
+}
+
+/// An alert message split into the parts truncation must keep
+struct Parts<'a> {
+    /// Severity label, e.g. "MAJOR FLOOD"
+    head: &'a str,
+    station: &'a str,
+    value: &'a str,
+}
+
+/// `<head> at <station>: <value>[ (detail)| , detail]`, as
+/// `alert::thresholds` and `alert::stalenesses` write them
+fn parse(message: &str) -> Option<Parts<'_>> {
+    let (head, rest) = message.split_once(" at ")?;
+    let (station, rest) = rest.split_once(": ")?;
+    let value_end = [rest.find(" ("), rest.find(", ")].into_iter().flatten().min().unwrap_or(rest.len());
+    Some(Parts { head, station, value: &rest[..value_end] })
+}
+
+impl TextRules {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.max_length {
+            Some(max) if max < MIN_MAX_LENGTH => {
+                Err(format!("max_length must be at least {}, got {}", MIN_MAX_LENGTH, max))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the rules change nothing
+    pub fn is_unconstrained(&self) -> bool {
+        self.max_length.is_none() && self.charset == Charset::Any
+    }
+
+    /// `message` in the channel's charset, shortened to fit its limit
+    pub fn apply(&self, message: &str) -> (String, Readability) {
+        let charset = self.charset;
+        let text = charset.convert(message);
+        let Some(max) = self.max_length else {
+            return (text, Readability::Full);
+        };
+        if charset.length(&text) <= max {
+            return (text, Readability::Full);
+        }
+        let ellipsis = charset.ellipsis();
+
+        let Some(parts) = parse(&text) else {
+            return (cut(&text, max, charset), Readability::Cut);
+        };
+        let short = format!("{} at {}: {}", parts.head, parts.station, parts.value);
+        if charset.length(&short) <= max {
+            return (short, Readability::Detail);
+        }
+        let fixed = charset.length(&format!("{} at : {}", parts.head, parts.value)) + charset.length(ellipsis);
+        if let Some(room) = max.checked_sub(fixed).filter(|room| *room > 0) {
+            let station = take(parts.station, room, charset);
+            let text = format!("{} at {}{}: {}", parts.head, station.trim_end(), ellipsis, parts.value);
+            return (text, Readability::Station);
+        }
+        (cut(&short, max, charset), Readability::Cut)
+    }
+}
+
+/// Leading characters of `text` up to `room` counted length
+fn take(text: &str, room: usize, charset: Charset) -> &str {
+    let mut used = 0;
+    for (i, c) in text.char_indices() {
+        used += charset.length(c.encode_utf8(&mut [0; 4]));
+        if used > room {
+            return &text[..i];
+        }
+    }
+    text
+}
+
+/// `text` cut to `max` including an ellipsis
+fn cut(text: &str, max: usize, charset: Charset) -> String {
+    let ellipsis = charset.ellipsis();
+    let room = max.saturating_sub(charset.length(ellipsis));
+    format!("{}{}", take(text, room, charset).trim_end(), ellipsis)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALERT: &str = "MODERATE FLOOD at Illinois River at Kingston Mines, IL: 21.34 ft (moderate flood stage: 20.00 ft)";
+
+    #[test]
+    fn test_shortening_keeps_station_severity_and_value() {
+        let unlimited = TextRules::default();
+        assert_eq!(unlimited.apply(ALERT), (ALERT.to_string(), Readability::Full));
+
+        let rules = TextRules { max_length: Some(70), charset: Charset::Gsm7 };
+        let (text, readability) = rules.apply(ALERT);
+        assert_eq!(text, "MODERATE FLOOD at Illinois River at Kingston Mines, IL: 21.34 ft");
+        assert_eq!(readability, Readability::Detail);
+
+        let rules = TextRules { max_length: Some(45), charset: Charset::Gsm7 };
+        let (text, readability) = rules.apply(ALERT);
+        assert_eq!(text, "MODERATE FLOOD at Illinois River...: 21.34 ft");
+        assert_eq!(readability, Readability::Station);
+        assert!(Charset::Gsm7.length(&text) <= 45);
+    }
+
+    #[test]
+    fn test_unparsed_text_is_cut() {
+        let rules = TextRules { max_length: Some(40), charset: Charset::Any };
+        let (text, readability) = rules.apply(&"River status report. ".repeat(4));
+        assert_eq!(readability, Readability::Cut);
+        assert_eq!(text.chars().count(), 40);
+        assert!(text.ends_with('…'));
+    }
+
+    #[test]
+    fn test_charset_conversion() {
+        assert_eq!(Charset::Gsm7.convert("Stage ≥ 21 ft – rising ↑ {ok}"), "Stage >= 21 ft - rising up {ok}");
+        assert_eq!(Charset::Gsm7.length("{ok}"), 6);
+        assert_eq!(Charset::Ascii.convert("Peoria Pool ±0.5 ft, café"), "Peoria Pool ?0.5 ft, caf?");
+        assert!(TextRules { max_length: Some(20), charset: Charset::Any }.validate().is_err());
+    }
+}
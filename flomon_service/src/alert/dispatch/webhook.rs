@@ -0,0 +1,77 @@
+//! Webhook channel: POSTs `AlertWebhookPayload` JSON to a URL.
+//!
+//! The payload is the versioned type in `flomon_types::webhook`, so a
+//! receiver can deserialize it without depending on this crate. An
+//! `address` on the recipient (`hook:https://...`) is not supported; each
+//! URL is its own `[[notifiers]]` entry.
+
+use flomon_types::webhook::AlertWebhookPayload;
+use serde::Deserialize;
+
+use crate::alert::dispatch::Notifier;
+use crate::alert::queue::Notification;
+
+pub const KIND: &str = "webhook";
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+/// `kind = "webhook"` settings
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct WebhookSettings {
+    pub url: String,
+    /// Sent as `Authorization: Bearer <token>` when set
+    #[serde(default)]
+    pub bearer_token: Option<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl WebhookSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.url.starts_with("https://") || self.url.starts_with("http://")) {
+            return Err(format!("url must be an http(s) URL, got '{}'", self.url));
+        }
+        if self.timeout_secs == 0 {
+            return Err("timeout_secs must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Webhook body for a notification
+pub fn payload(notification: &Notification) -> AlertWebhookPayload {
+    let alert = &notification.alert;
+    let mut payload = AlertWebhookPayload::new(alert.severity.as_str(), &alert.message, notification.queued_at);
+    payload.registry_version = alert.registry_version;
+    payload
+}
+
+impl Notifier for WebhookSettings {
+    fn kind(&self) -> &'static str {
+        KIND
+    }
+
+    fn send(&self, address: Option<&str>, notification: &Notification) -> Result<(), String> {
+        if let Some(address) = address {
+            return Err(format!("webhook recipients take no address (got '{}')", address));
+        }
+        let client = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(self.timeout_secs))
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+        let mut request = client.post(&self.url)
+            .header("X-Flomon-Urgency", notification.urgency.as_str())
+            .json(&payload(notification));
+        if let Some(token) = &self.bearer_token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send()
+            .map_err(|e| format!("Webhook POST to {} failed: {}", self.url, e))?;
+        if !response.status().is_success() {
+            return Err(format!("Webhook POST to {} returned HTTP {}", self.url, response.status()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+//! One notification sender across instances.
+//!
+//! A warm standby on a second box runs the same daemon against the same
+//! database: it ingests and evaluates alerts too, so it can take over at
+//! once. Only one of them may send, or every alert goes out twice. The
+//! instance holding a Postgres session-level advisory lock
+//! (`DISPATCH_LOCK_KEY`) is the leader and delivers notifications; the
+//! others are standbys and discard what they queue, leaving delivery to
+//! the leader.
+//!
+//! The lock lives as long as the leader's database session, so when the
+//! leader stops (or its connection dies) the lock is released by Postgres
+//! and the next standby to try on its poll cycle takes over, first picking
+//! up whatever the old leader persisted to `pending_notifications`. A
+//! single instance simply finds the lock free and leads.
+
+use postgres::Client;
+
+/// Advisory lock key for notification dispatch ("flomon" + 1)
+pub const DISPATCH_LOCK_KEY: i64 = 0x666c_6f6d_6f6e_0001;
+
+/// Whether this instance delivers notifications
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Leader,
+    Standby,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Leader => "leader",
+            Role::Standby => "standby",
+        }
+    }
+}
+
+/// This instance's hold on the dispatch lock
+#[derive(Debug)]
+pub struct DispatchLock {
+    role: Role,
+}
+
+impl Default for DispatchLock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DispatchLock {
+    /// Starts as a standby until `refresh` wins the lock
+    pub fn new() -> Self {
+        Self { role: Role::Standby }
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.role == Role::Leader
+    }
+
+    /// Confirm the lock is still held, or try to take it. Returns the new
+    /// role when it changed.
+    pub fn refresh(&mut self, client: &mut Client) -> Result<Option<Role>, String> {
+        let held: bool = match self.role {
+            // Lost with the session if the connection was reset
+            Role::Leader => client.query_one(
+                "SELECT EXISTS (
+                     SELECT 1 FROM pg_locks
+                     WHERE locktype = 'advisory' AND pid = pg_backend_pid() AND granted
+                       AND objsubid = 1 AND ((classid::BIGINT << 32) | objid::BIGINT) = $1
+                 )",
+                &[&DISPATCH_LOCK_KEY],
+            ),
+            Role::Standby => client.query_one("SELECT pg_try_advisory_lock($1)", &[&DISPATCH_LOCK_KEY]),
+        }
+        .map_err(|e| format!("Failed to check notification dispatch lock: {}", e))?
+        .get(0);
+        Ok(self.observe(held))
+    }
+
+    /// Record whether the lock is held; the new role if it changed
+    fn observe(&mut self, held: bool) -> Option<Role> {
+        let role = if held { Role::Leader } else { Role::Standby };
+        (role != self.role).then(|| {
+            self.role = role;
+            role
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_changes_reported_once() {
+        let mut lock = DispatchLock::new();
+        assert!(!lock.is_leader());
+        assert_eq!(lock.observe(false), None);
+        assert_eq!(lock.observe(true), Some(Role::Leader));
+        assert_eq!(lock.observe(true), None);
+        assert!(lock.is_leader());
+        // Session reset: the lock went with it
+        assert_eq!(lock.observe(false), Some(Role::Standby));
+    }
+}
@@ -0,0 +1,214 @@
+//! Alert delivery to a Matrix room.
+//!
+//! Messages go to a room on a (typically self-hosted) homeserver through the
+//! client-server API, as the account whose access token is configured in
+//! `[matrix]`. It is the dispatch channel named `matrix` (see
+//! `alert::dispatch`), so subscriptions opt in by recipient:
+//!
+//! - `matrix` — the configured `room_id`
+//! - `matrix:!roomid:server` — that room (the account must have joined it)
+//!
+//! Each message is sent
+//! with a transaction ID derived from the notification, so a retry after a
+//! timeout whose request actually landed is deduplicated by the homeserver
+//! instead of posting twice.
+
+use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::alert::dispatch::Notifier;
+use crate::alert::queue::{Notification, Urgency};
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+/// `[matrix]` section of flomon.toml
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MatrixSettings {
+    /// Homeserver base URL, e.g. "https://matrix.example.org"
+    pub homeserver: String,
+    /// Access token of the sending account
+    pub access_token: String,
+    /// Room for the plain `matrix` recipient, e.g. "!abc123:example.org"
+    pub room_id: String,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl MatrixSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.homeserver.starts_with("https://") || self.homeserver.starts_with("http://")) {
+            return Err(format!("matrix.homeserver must be an http(s) URL, got '{}'", self.homeserver));
+        }
+        if self.access_token.trim().is_empty() {
+            return Err("matrix.access_token must not be empty".to_string());
+        }
+        if !is_room_id(&self.room_id) {
+            return Err(format!("matrix.room_id must look like !id:server, got '{}'", self.room_id));
+        }
+        if self.timeout_secs == 0 {
+            return Err("matrix.timeout_secs must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+
+    /// Room a recipient address names (the configured room without one);
+    /// None if the address isn't a room ID
+    pub fn room<'a>(&'a self, address: Option<&'a str>) -> Option<&'a str> {
+        match address {
+            None => Some(&self.room_id),
+            Some(room) => is_room_id(room).then_some(room),
+        }
+    }
+
+    /// PUT URL for sending one message event to `room_id`
+    pub fn send_url(&self, room_id: &str, txn_id: &str) -> Result<reqwest::Url, String> {
+        let mut url = reqwest::Url::parse(&self.homeserver)
+            .map_err(|e| format!("Invalid matrix.homeserver: {}", e))?;
+        url.path_segments_mut()
+            .map_err(|_| "matrix.homeserver cannot be a base URL".to_string())?
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v3", "rooms", room_id, "send", "m.room.message", txn_id]);
+        Ok(url)
+    }
+
+    /// Send one notification to `room_id`
+    pub fn send(&self, room_id: &str, notification: &Notification) -> Result<(), String> {
+        let url = self.send_url(room_id, &transaction_id(notification))?;
+        let client = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(self.timeout_secs))
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+        let response = client.put(url)
+            .bearer_auth(&self.access_token)
+            .json(&message_content(notification))
+            .send()
+            .map_err(|e| format!("Matrix send to {} failed: {}", room_id, e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().unwrap_or_default();
+            return Err(format!("Matrix send to {} returned HTTP {}: {}", room_id, status, body.trim()));
+        }
+        Ok(())
+    }
+}
+
+impl Notifier for MatrixSettings {
+    fn kind(&self) -> &'static str {
+        "matrix"
+    }
+
+    fn send(&self, address: Option<&str>, notification: &Notification) -> Result<(), String> {
+        let room = self.room(address)
+            .ok_or_else(|| format!("'{}' is not a Matrix room ID", address.unwrap_or_default()))?;
+        MatrixSettings::send(self, room, notification)
+    }
+}
+
+/// "!localpart:server"
+fn is_room_id(room: &str) -> bool {
+    room.strip_prefix('!')
+        .and_then(|rest| rest.split_once(':'))
+        .is_some_and(|(local, server)| !local.is_empty() && !server.is_empty())
+}
+
+/// Stable per notification, so retries reuse it
+pub fn transaction_id(notification: &Notification) -> String {
+    let mut hasher = DefaultHasher::new();
+    notification.recipient.hash(&mut hasher);
+    notification.alert.message.hash(&mut hasher);
+    notification.alert.severity.as_str().hash(&mut hasher);
+    format!("flomon-{}-{:016x}", notification.queued_at.timestamp_millis(), hasher.finish())
+}
+
+/// `m.room.message` content: plain text plus a bold severity in HTML
+pub fn message_content(notification: &Notification) -> serde_json::Value {
+    let severity = notification.alert.severity.as_str().to_uppercase();
+    // Notices don't trigger push rules' sound by default
+    let msgtype = match notification.urgency {
+        Urgency::Normal => "m.text",
+        Urgency::Quiet => "m.notice",
+    };
+    serde_json::json!({
+        "msgtype": msgtype,
+        "body": format!("[{}] {}", severity, notification.alert.message),
+        "format": "org.matrix.custom.html",
+        "formatted_body": format!("<b>[{}]</b> {}", severity, escape_html(&notification.alert.message)),
+    })
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alert::thresholds::{FloodAlert, FloodSeverity};
+    use chrono::{TimeZone, Utc};
+
+    fn settings() -> MatrixSettings {
+        MatrixSettings {
+            homeserver: "https://matrix.example.org/".to_string(),
+            access_token: "syt_token".to_string(),
+            room_id: "!family:example.org".to_string(),
+            timeout_secs: 10,
+        }
+    }
+
+    fn notification(recipient: &str) -> Notification {
+        Notification {
+            recipient: recipient.to_string(),
+            urgency: Default::default(),
+            alert: FloodAlert {
+                severity: FloodSeverity::Moderate,
+                message: "Kingston Mines at 21.3 ft <moderate>".to_string(),
+                registry_version: None,
+            },
+            queued_at: Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            attempts: 0,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn test_room_addressing() {
+        let settings = settings();
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.room(None), Some("!family:example.org"));
+        assert_eq!(settings.room(Some("!dock:example.org")), Some("!dock:example.org"));
+        assert_eq!(settings.room(Some("general")), None);
+
+        let bad = MatrixSettings { room_id: "#family:example.org".to_string(), ..settings };
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_send_url_and_stable_transaction_id() {
+        let n = notification("matrix");
+        let txn = transaction_id(&n);
+        assert_eq!(txn, transaction_id(&n.clone()));
+        assert_ne!(txn, transaction_id(&notification("matrix:!dock:example.org")));
+
+        let url = settings().send_url("!family:example.org", &txn).unwrap();
+        assert_eq!(
+            url.as_str(),
+            format!("https://matrix.example.org/_matrix/client/v3/rooms/!family:example.org/send/m.room.message/{}", txn),
+        );
+
+        let content = message_content(&n);
+        assert_eq!(content["body"], "[MODERATE] Kingston Mines at 21.3 ft <moderate>");
+        assert_eq!(content["formatted_body"], "<b>[MODERATE]</b> Kingston Mines at 21.3 ft &lt;moderate&gt;");
+        assert_eq!(content["msgtype"], "m.text");
+
+        let quiet = Notification { urgency: Urgency::Quiet, ..n };
+        assert_eq!(message_content(&quiet)["msgtype"], "m.notice");
+    }
+}
@@ -1,2 +1,13 @@
+pub mod dispatch;
+pub mod leader;
+pub mod matrix;
+pub mod occupancy;
+pub mod outlook;
+pub mod precip_alarm;
+pub mod queue;
+pub mod rules;
+pub mod scheme;
 pub mod stalenesses;
+pub mod subscriptions;
 pub mod thresholds;
+pub mod transitions;
@@ -0,0 +1,384 @@
+//! Per-zone occupancy and quiet notifications.
+//!
+//! The cabin upriver is empty most weeks, and a 3 a.m. push for its zone
+//! helps nobody when no one is there to move the boat. Zones listed under
+//! `[occupancy]` are either `primary` (the residence: always full urgency)
+//! or occupied only when:
+//! - their flag in `zone_occupancy` says so, set by `POST /api/occupancy`
+//!   or a presence sensor publishing to the zone's MQTT `topic`, or
+//! - without a flag, one of their `schedule` windows covers the current
+//!   time (host local time; a window whose `end` is not after `start` runs
+//!   past midnight).
+//!
+//! Alerts for a station whose zones are all unoccupied are queued with
+//! `Urgency::Quiet`; each channel still delivers them, at reduced priority
+//! (see `alert::dispatch`). Zones not listed are always full urgency.
+//!
+//! ```toml
+//! [occupancy]
+//! token = "${OCCUPANCY_TOKEN}"   # bearer token for POST /api/occupancy
+//!
+//! [[occupancy.zones]]
+//! zone_id = 1
+//! primary = true
+//!
+//! [[occupancy.zones]]
+//! zone_id = 4
+//! topic = "home/cabin/presence"  # payload occupied/away, on the [local_sensors] broker
+//!
+//! [[occupancy.zones.schedule]]        # Fri 16:00-Sat 10:00 and Sat 16:00-Sun 10:00
+//! days = ["fri", "sat"]
+//! start = "16:00"
+//! end = "10:00"
+//! ```
+
+use chrono::{DateTime, Datelike, NaiveDateTime, NaiveTime, Utc, Weekday};
+use postgres::Client;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::alert::queue::Urgency;
+use crate::alert::subscriptions::MAX_ZONE_ID;
+use crate::ingest::mqtt::{ConnectOptions, Subscriber};
+use crate::logging;
+use crate::auth;
+use crate::shutdown;
+
+/// `source` of flags set through the HTTP API
+pub const SOURCE_API: &str = "api";
+/// `source` of flags set by a presence sensor
+pub const SOURCE_MQTT: &str = "mqtt";
+
+/// How long `run` waits for a message before checking for shutdown
+const RECEIVE_SLICE: std::time::Duration = std::time::Duration::from_secs(5);
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// `[occupancy]` section of flomon.toml
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OccupancySettings {
+    /// Shared secret for `POST /api/occupancy`; the POST is disabled without it
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default)]
+    pub zones: Vec<ZoneOccupancy>,
+}
+
+/// `[[occupancy.zones]]` entry
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ZoneOccupancy {
+    pub zone_id: usize,
+    /// Always occupied (the primary residence)
+    #[serde(default)]
+    pub primary: bool,
+    /// Exact MQTT topic of the zone's presence sensor
+    #[serde(default)]
+    pub topic: Option<String>,
+    #[serde(default)]
+    pub schedule: Vec<ScheduleWindow>,
+}
+
+/// `[[occupancy.zones.schedule]]` entry: `start`..`end` (HH:MM) on each of `days`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleWindow {
+    /// "mon" through "sun"
+    pub days: Vec<String>,
+    pub start: String,
+    pub end: String,
+}
+
+fn parse_day(day: &str) -> Result<Weekday, String> {
+    day.parse::<Weekday>().map_err(|_| format!("unknown day '{}' (use mon..sun)", day))
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| format!("invalid time '{}' (use HH:MM)", time))
+}
+
+impl ScheduleWindow {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.days.is_empty() {
+            return Err("schedule window lists no days".to_string());
+        }
+        for day in &self.days {
+            parse_day(day)?;
+        }
+        parse_time(&self.start)?;
+        parse_time(&self.end)?;
+        Ok(())
+    }
+
+    /// Whether local time `at` falls inside the window. Unparseable
+    /// windows (rejected by `validate`) never match.
+    pub fn contains(&self, at: NaiveDateTime) -> bool {
+        let (Ok(start), Ok(end)) = (parse_time(&self.start), parse_time(&self.end)) else {
+            return false;
+        };
+        let time = at.time();
+        let weekday = at.weekday();
+        self.days.iter().filter_map(|d| parse_day(d).ok()).any(|day| {
+            if end > start {
+                day == weekday && time >= start && time < end
+            } else {
+                (day == weekday && time >= start) || (day.succ() == weekday && time < end)
+            }
+        })
+    }
+}
+
+impl OccupancySettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(token) = &self.token {
+            auth::validate_token("occupancy.token", token)?;
+        }
+        let mut zones = HashSet::new();
+        let mut topics = HashSet::new();
+        for zone in &self.zones {
+            if zone.zone_id > MAX_ZONE_ID {
+                return Err(format!("occupancy zone {} does not exist (zones are 0-{})", zone.zone_id, MAX_ZONE_ID));
+            }
+            if !zones.insert(zone.zone_id) {
+                return Err(format!("occupancy zone {} is listed twice", zone.zone_id));
+            }
+            if let Some(topic) = &zone.topic {
+                if topic.is_empty() || topic.contains(['+', '#']) {
+                    return Err(format!("occupancy zone {}: topic '{}' must be exact (no wildcards)", zone.zone_id, topic));
+                }
+                if !topics.insert(topic.as_str()) {
+                    return Err(format!("occupancy topic '{}' is used by two zones", topic));
+                }
+            }
+            for window in &zone.schedule {
+                window.validate().map_err(|e| format!("occupancy zone {}: {}", zone.zone_id, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `authorization` (the header value) carries the token
+    pub fn authorized(&self, authorization: Option<&str>) -> bool {
+        self.token.as_deref().is_some_and(|expected| auth::bearer_authorized(authorization, expected))
+    }
+
+    pub fn zone(&self, zone_id: usize) -> Option<&ZoneOccupancy> {
+        self.zones.iter().find(|z| z.zone_id == zone_id)
+    }
+
+    /// Whether anyone is at `zone_id`, given the stored flags and local time
+    pub fn is_occupied(&self, zone_id: usize, flags: &HashMap<usize, bool>, local: NaiveDateTime) -> bool {
+        let Some(zone) = self.zone(zone_id) else {
+            return true;
+        };
+        if zone.primary {
+            return true;
+        }
+        match flags.get(&zone_id) {
+            Some(&occupied) => occupied,
+            None => zone.schedule.iter().any(|w| w.contains(local)),
+        }
+    }
+
+    /// Urgency for an alert reaching a recipient through `zone_ids`: full
+    /// when any of them is occupied
+    pub fn urgency(&self, zone_ids: &[usize], flags: &HashMap<usize, bool>, local: NaiveDateTime) -> Urgency {
+        if zone_ids.iter().any(|&z| self.is_occupied(z, flags, local)) {
+            Urgency::Normal
+        } else {
+            Urgency::Quiet
+        }
+    }
+
+    /// Zone whose presence sensor publishes to `topic`
+    pub fn zone_for_topic(&self, topic: &str) -> Option<usize> {
+        self.zones.iter()
+            .find(|z| z.topic.as_deref() == Some(topic))
+            .map(|z| z.zone_id)
+    }
+
+    pub fn topics(&self) -> Vec<String> {
+        let mut topics: Vec<String> = self.zones.iter().filter_map(|z| z.topic.clone()).collect();
+        topics.sort();
+        topics
+    }
+}
+
+/// Occupied/vacant from a presence sensor payload
+pub fn parse_presence(payload: &[u8]) -> Result<bool, String> {
+    let text = std::str::from_utf8(payload).map_err(|_| "payload is not UTF-8".to_string())?.trim();
+    match text.to_ascii_lowercase().as_str() {
+        "1" | "true" | "on" | "occupied" | "home" | "present" => Ok(true),
+        "0" | "false" | "off" | "vacant" | "away" | "unoccupied" => Ok(false),
+        _ => Err(format!("unrecognized presence payload {:?}", text)),
+    }
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+/// Stored flag for one zone
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct OccupancyFlag {
+    pub zone_id: usize,
+    pub occupied: bool,
+    pub source: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Set a zone's flag, or clear it (back to the schedule) with `None`
+pub fn set_flag(client: &mut Client, zone_id: usize, occupied: Option<bool>, source: &str, now: DateTime<Utc>) -> Result<(), String> {
+    if zone_id > MAX_ZONE_ID {
+        return Err(format!("zone {} does not exist (zones are 0-{})", zone_id, MAX_ZONE_ID));
+    }
+    let zone = zone_id as i16;
+    match occupied {
+        Some(occupied) => client.execute(
+            "INSERT INTO zone_occupancy (zone_id, occupied, source, updated_at)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (zone_id) DO UPDATE
+             SET occupied = EXCLUDED.occupied, source = EXCLUDED.source, updated_at = EXCLUDED.updated_at",
+            &[&zone, &occupied, &source, &now],
+        ),
+        None => client.execute("DELETE FROM zone_occupancy WHERE zone_id = $1", &[&zone]),
+    }.map_err(|e| format!("Failed to save occupancy for zone {}: {}", zone_id, e))?;
+    Ok(())
+}
+
+/// Every stored flag, ordered by zone
+pub fn load_flags(client: &mut Client) -> Result<Vec<OccupancyFlag>, String> {
+    let rows = client.query(
+        "SELECT zone_id, occupied, source, updated_at FROM zone_occupancy ORDER BY zone_id",
+        &[],
+    ).map_err(|e| format!("Failed to load zone occupancy: {}", e))?;
+    Ok(rows.iter()
+        .map(|row| {
+            let zone_id: i16 = row.get(0);
+            OccupancyFlag {
+                zone_id: zone_id as usize,
+                occupied: row.get(1),
+                source: row.get(2),
+                updated_at: row.get(3),
+            }
+        })
+        .collect())
+}
+
+/// Stored flags keyed by zone, as `is_occupied` takes them
+pub fn flag_map(flags: &[OccupancyFlag]) -> HashMap<usize, bool> {
+    flags.iter().map(|f| (f.zone_id, f.occupied)).collect()
+}
+
+// ============================================================================
+// Presence subscriber
+// ============================================================================
+
+/// Subscribe to the zones' presence topics and store each change until
+/// shutdown. Returns an error when the broker connection fails so the
+/// supervisor can reconnect with backoff.
+pub fn run(broker: &str, options: &ConnectOptions, settings: &OccupancySettings, client: &mut Client) -> Result<(), String> {
+    let topics = settings.topics();
+    let mut subscriber = Subscriber::connect(broker, options, &topics, 1)?;
+    logging::info(logging::DataSource::System, None, &format!(
+        "Subscribed to {} presence topic(s) on {}", topics.len(), broker));
+
+    while !shutdown::requested() {
+        let Some(message) = subscriber.next_message(RECEIVE_SLICE)? else {
+            continue;
+        };
+        let Some(zone_id) = settings.zone_for_topic(&message.topic) else {
+            continue;
+        };
+        let result = parse_presence(&message.payload)
+            .and_then(|occupied| set_flag(client, zone_id, Some(occupied), SOURCE_MQTT, Utc::now()).map(|_| occupied));
+        match result {
+            Ok(occupied) => logging::debug(logging::DataSource::System, None, &format!(
+                "Zone {} {} (presence sensor)", zone_id, if occupied { "occupied" } else { "vacant" })),
+            Err(e) => logging::warn(logging::DataSource::System, None, &format!(
+                "Rejected presence message on {}: {}", message.topic, e)),
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn settings() -> OccupancySettings {
+        toml::from_str(r#"
+            token = "0123456789abcdef"
+            [[zones]]
+            zone_id = 1
+            primary = true
+            [[zones]]
+            zone_id = 4
+            topic = "home/cabin/presence"
+            [[zones.schedule]]
+            days = ["fri"]
+            start = "16:00"
+            end = "02:00"
+        "#).unwrap()
+    }
+
+    fn local(day: u32, hour: u32) -> NaiveDateTime {
+        // 2024-05-03 is a Friday
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_schedule_window_runs_past_midnight() {
+        let settings = settings();
+        settings.validate().unwrap();
+        let none = HashMap::new();
+
+        assert!(!settings.is_occupied(4, &none, local(3, 15)));
+        assert!(settings.is_occupied(4, &none, local(3, 22)));
+        assert!(settings.is_occupied(4, &none, local(4, 1)));
+        assert!(!settings.is_occupied(4, &none, local(4, 2)));
+        // Primary and unlisted zones are always occupied
+        assert!(settings.is_occupied(1, &none, local(1, 3)));
+        assert!(settings.is_occupied(6, &none, local(1, 3)));
+    }
+
+    #[test]
+    fn test_flag_overrides_schedule_and_sets_urgency() {
+        let settings = settings();
+        let away = HashMap::from([(4, false)]);
+        let home = HashMap::from([(4, true)]);
+
+        assert_eq!(settings.urgency(&[4], &away, local(3, 22)), Urgency::Quiet);
+        assert_eq!(settings.urgency(&[4], &home, local(1, 3)), Urgency::Normal);
+        // A station also in the primary zone keeps full urgency
+        assert_eq!(settings.urgency(&[1, 4], &away, local(3, 22)), Urgency::Normal);
+
+        assert_eq!(parse_presence(b" Away\n"), Ok(false));
+        assert!(parse_presence(b"maybe").is_err());
+        assert!(settings.authorized(Some("Bearer 0123456789abcdef")));
+        assert!(!settings.authorized(Some("Bearer wrong")));
+    }
+
+    #[test]
+    fn test_validation_rejects_bad_entries() {
+        let mut bad = settings();
+        bad.zones[1].schedule[0].days = vec!["someday".to_string()];
+        assert!(bad.validate().is_err());
+
+        let mut bad = settings();
+        bad.zones[1].zone_id = 1;
+        assert!(bad.validate().is_err());
+
+        let mut bad = settings();
+        bad.zones[1].topic = Some("home/+/presence".to_string());
+        assert!(bad.validate().is_err());
+    }
+}
@@ -0,0 +1,348 @@
+//! Pre-flood outlook: "flooding likely within N hours at Kingston Mines".
+//!
+//! Threshold alerts fire when the river is already there. The outlook
+//! puts together what the daemon knows before that happens:
+//! - heavy rain on the basin: the wettest 24-hour ASOS basin mean
+//!   (`analysis::precip`) or the MRMS radar basin total (`ingest::mrms`),
+//! - upstream gauges rising by `upstream_rise_ft` or more over the last
+//!   `trend_hours`, and
+//! - how long each rise takes to reach the target, from the fitted lag
+//!   for the target's current flow regime (`analysis::lag::lead_time`),
+//!   or the registry travel time to Peoria for a gauge with no fit yet.
+//!
+//! An advisory is issued when the target is below flood stage but within
+//! `margin_ft` of it, at least one upstream gauge is rising with a lead
+//! time inside `horizon_hours`, and either the basin had heavy rain or a
+//! rising gauge is itself at action stage or above. N is the shortest of
+//! those lead times, when the first rise arrives.
+//!
+//! ```toml
+//! [outlook]
+//! target = "05568500"        # Kingston Mines
+//! basin_inches = 1.5         # 24 h basin rain that counts as heavy
+//! upstream_rise_ft = 1.0     # over trend_hours
+//! trend_hours = 6
+//! margin_ft = 4.0            # below flood stage
+//! horizon_hours = 72
+//! severity = "action"
+//! ```
+//!
+//! Like the rainfall alarm, the advisory is issued once and again only
+//! after the conditions have cleared. It is guidance, not an NWS forecast.
+
+use serde::Deserialize;
+
+use crate::alert::thresholds::{FloodAlert, FloodSeverity};
+use crate::analysis::lag::{self, LagEstimate, Regime};
+use crate::model::FloodThresholds;
+use chrono::{DateTime, Utc};
+
+fn default_target() -> String {
+    "05568500".to_string()
+}
+
+fn default_basin_inches() -> f64 {
+    1.5
+}
+
+fn default_upstream_rise_ft() -> f64 {
+    1.0
+}
+
+fn default_trend_hours() -> i64 {
+    6
+}
+
+fn default_margin_ft() -> f64 {
+    4.0
+}
+
+fn default_horizon_hours() -> i64 {
+    72
+}
+
+fn default_severity() -> FloodSeverity {
+    FloodSeverity::Action
+}
+
+/// `[outlook]` section of flomon.toml
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutlookSettings {
+    /// Station the outlook is for (needs flood thresholds)
+    #[serde(default = "default_target")]
+    pub target: String,
+    #[serde(default = "default_basin_inches")]
+    pub basin_inches: f64,
+    #[serde(default = "default_upstream_rise_ft")]
+    pub upstream_rise_ft: f64,
+    #[serde(default = "default_trend_hours")]
+    pub trend_hours: i64,
+    #[serde(default = "default_margin_ft")]
+    pub margin_ft: f64,
+    #[serde(default = "default_horizon_hours")]
+    pub horizon_hours: i64,
+    #[serde(default = "default_severity")]
+    pub severity: FloodSeverity,
+}
+
+impl Default for OutlookSettings {
+    fn default() -> Self {
+        Self {
+            target: default_target(),
+            basin_inches: default_basin_inches(),
+            upstream_rise_ft: default_upstream_rise_ft(),
+            trend_hours: default_trend_hours(),
+            margin_ft: default_margin_ft(),
+            horizon_hours: default_horizon_hours(),
+            severity: default_severity(),
+        }
+    }
+}
+
+impl OutlookSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("basin_inches", self.basin_inches),
+            ("upstream_rise_ft", self.upstream_rise_ft),
+            ("margin_ft", self.margin_ft),
+        ] {
+            if !value.is_finite() || value <= 0.0 {
+                return Err(format!("outlook: {} must be greater than zero", name));
+            }
+        }
+        if self.trend_hours <= 0 || self.horizon_hours <= 0 {
+            return Err("outlook: trend_hours and horizon_hours must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Recent stage at one upstream gauge
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpstreamTrend {
+    pub site_code: String,
+    pub stage_ft: f64,
+    /// Latest stage minus the first stage of the trend window
+    pub rise_ft: f64,
+    /// At or above the gauge's own action stage
+    pub at_action: bool,
+    /// Registry travel time to Peoria (fallback when no lag is fitted)
+    pub travel_time_hours: f64,
+}
+
+/// Latest stage and its rise over `readings` (time, stage ft), if at least
+/// two readings span the window
+pub fn trend(readings: &[(DateTime<Utc>, f64)]) -> Option<(f64, f64)> {
+    let first = readings.iter().min_by_key(|(t, _)| *t)?;
+    let last = readings.iter().max_by_key(|(t, _)| *t)?;
+    (last.0 > first.0).then_some((last.1, last.1 - first.1))
+}
+
+/// What the outlook looks at in one cycle
+#[derive(Debug, Clone)]
+pub struct OutlookInputs<'a> {
+    pub target_stage_ft: f64,
+    pub thresholds: &'a FloodThresholds,
+    pub upstream: &'a [UpstreamTrend],
+    pub lags: &'a [LagEstimate],
+    /// Wettest 24-hour ASOS basin mean: (basin, in)
+    pub asos_basin_24h: Option<(String, f64)>,
+    /// MRMS basin-wide mean over the last 24 hours, in
+    pub mrms_basin_24h_in: Option<f64>,
+}
+
+/// One advisory issued
+#[derive(Debug, Clone, PartialEq)]
+pub struct Advisory {
+    pub lead_hours: i64,
+    pub alert: FloodAlert,
+}
+
+/// Follows whether an advisory is out for the current episode
+#[derive(Debug)]
+pub struct Outlook {
+    settings: OutlookSettings,
+    issued: bool,
+}
+
+impl Outlook {
+    pub fn new(settings: OutlookSettings) -> Self {
+        Self { settings, issued: false }
+    }
+
+    pub fn settings(&self) -> &OutlookSettings {
+        &self.settings
+    }
+
+    /// The advisory, the first cycle the conditions in the module docs hold
+    pub fn evaluate(&mut self, inputs: &OutlookInputs) -> Option<Advisory> {
+        let settings = &self.settings;
+        let target = &settings.target;
+        let flood = inputs.thresholds.flood_stage_ft;
+        let regime = Regime::of(inputs.target_stage_ft, inputs.thresholds);
+
+        // Rising upstream gauges with when their rise reaches the target
+        let mut rising: Vec<(&UpstreamTrend, i64, &'static str)> = inputs.upstream.iter()
+            .filter(|t| t.rise_ft >= settings.upstream_rise_ft)
+            .filter_map(|t| match lag::lead_time(inputs.lags, &t.site_code, target, regime) {
+                Some(estimate) => Some((t, estimate.lag_hours, "fitted")),
+                None if t.travel_time_hours > 0.0 => Some((t, t.travel_time_hours.round() as i64, "registry")),
+                None => None,
+            })
+            .filter(|(_, lead, _)| *lead <= settings.horizon_hours)
+            .collect();
+        rising.sort_by_key(|(t, lead, _)| (*lead, t.site_code.clone()));
+
+        let rain = [
+            inputs.asos_basin_24h.as_ref().map(|(basin, inches)| (*inches, format!("{} basin (ASOS)", basin))),
+            inputs.mrms_basin_24h_in.map(|inches| (inches, "basin-wide radar (MRMS)".to_string())),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|(inches, _)| *inches >= settings.basin_inches)
+        .max_by(|a, b| a.0.total_cmp(&b.0));
+
+        let likely = inputs.target_stage_ft < flood
+            && inputs.target_stage_ft >= flood - settings.margin_ft
+            && !rising.is_empty()
+            && (rain.is_some() || rising.iter().any(|(t, _, _)| t.at_action));
+        if !likely {
+            self.issued = false;
+            return None;
+        }
+        if self.issued {
+            return None;
+        }
+        self.issued = true;
+
+        let lead_hours = rising[0].1;
+        let mut reasons: Vec<String> = rising.iter()
+            .map(|(t, lead, source)| format!("{} up {:.1} ft in {} h to {:.2} ft{} ({} h, {} lag)",
+                t.site_code, t.rise_ft, settings.trend_hours, t.stage_ft,
+                if t.at_action { ", above action" } else { "" }, lead, source))
+            .collect();
+        if let Some((inches, area)) = rain {
+            reasons.push(format!("{:.2} in of rain in 24 h over the {}", inches, area));
+        }
+        Some(Advisory {
+            lead_hours,
+            alert: FloodAlert {
+                severity: settings.severity.clone(),
+                message: format!(
+                    "[outlook] Flooding likely within {} h at {} (now {:.2} ft, flood stage {:.1} ft): {}",
+                    lead_hours, target, inputs.target_stage_ft, flood, reasons.join("; ")),
+                registry_version: None,
+            },
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn thresholds() -> FloodThresholds {
+        FloodThresholds {
+            action_stage_ft: 14.0,
+            flood_stage_ft: 16.0,
+            moderate_flood_stage_ft: 20.0,
+            major_flood_stage_ft: 24.0,
+        }
+    }
+
+    fn rising(site_code: &str, rise_ft: f64, at_action: bool) -> UpstreamTrend {
+        UpstreamTrend {
+            site_code: site_code.to_string(),
+            stage_ft: 12.0,
+            rise_ft,
+            at_action,
+            travel_time_hours: 30.0,
+        }
+    }
+
+    fn fitted(upstream_site: &str, regime: Regime, lag_hours: i64) -> LagEstimate {
+        LagEstimate {
+            upstream_site: upstream_site.to_string(),
+            downstream_site: "05568500".to_string(),
+            regime,
+            lag_hours,
+            correlation: 0.8,
+            pairs: 200,
+        }
+    }
+
+    fn inputs<'a>(
+        stage: f64,
+        thresholds: &'a FloodThresholds,
+        upstream: &'a [UpstreamTrend],
+        lags: &'a [LagEstimate],
+        basin_in: f64,
+    ) -> OutlookInputs<'a> {
+        OutlookInputs {
+            target_stage_ft: stage,
+            thresholds,
+            upstream,
+            lags,
+            asos_basin_24h: Some(("Mackinaw River".to_string(), basin_in)),
+            mrms_basin_24h_in: None,
+        }
+    }
+
+    #[test]
+    fn test_trend_is_latest_minus_first() {
+        let t0 = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        let readings = [(t0 + Duration::hours(6), 13.5), (t0, 12.0), (t0 + Duration::hours(3), 12.6)];
+        assert_eq!(trend(&readings), Some((13.5, 1.5)));
+        assert_eq!(trend(&readings[..1]), None);
+    }
+
+    #[test]
+    fn test_advisory_uses_fitted_lag_for_regime_and_fires_once() {
+        let thresholds = thresholds();
+        let upstream = [rising("05568000", 2.1, false), rising("05567500", 0.4, true)];
+        let lags = [fitted("05568000", Regime::Elevated, 18), fitted("05568000", Regime::Low, 30)];
+        let mut outlook = Outlook::new(OutlookSettings::default());
+
+        let advisory = outlook.evaluate(&inputs(14.5, &thresholds, &upstream, &lags, 1.8)).expect("advisory");
+        assert_eq!(advisory.lead_hours, 18, "elevated regime lag");
+        assert!(advisory.alert.message.starts_with("[outlook] Flooding likely within 18 h at 05568500"));
+        assert!(advisory.alert.message.contains("1.80 in of rain in 24 h over the Mackinaw River basin (ASOS)"));
+        assert!(!advisory.alert.message.contains("05567500"), "not rising enough");
+
+        assert!(outlook.evaluate(&inputs(14.8, &thresholds, &upstream, &lags, 1.8)).is_none(), "once per episode");
+        assert!(outlook.evaluate(&inputs(16.2, &thresholds, &upstream, &lags, 1.8)).is_none(), "already flooding");
+        assert!(outlook.evaluate(&inputs(15.0, &thresholds, &upstream, &lags, 1.8)).is_some(), "rearmed");
+    }
+
+    #[test]
+    fn test_advisory_needs_rain_or_an_elevated_upstream_gauge() {
+        let thresholds = thresholds();
+        let mut outlook = Outlook::new(OutlookSettings::default());
+
+        let upstream = [rising("05568000", 1.5, false)];
+        assert!(outlook.evaluate(&inputs(14.0, &thresholds, &upstream, &[], 0.7)).is_none(), "dry");
+        assert!(outlook.evaluate(&inputs(11.0, &thresholds, &upstream, &[], 1.8)).is_none(), "not within margin");
+
+        // Registry travel time stands in for a missing fit
+        let upstream = [rising("05568000", 1.5, true)];
+        let advisory = outlook.evaluate(&inputs(14.0, &thresholds, &upstream, &[], 0.7)).expect("advisory");
+        assert_eq!(advisory.lead_hours, 30);
+        assert!(advisory.alert.message.contains("above action (30 h, registry lag)"));
+        assert!(!advisory.alert.message.contains("rain"));
+    }
+
+    #[test]
+    fn test_settings_defaults_and_validate() {
+        let parsed: OutlookSettings = toml::from_str("basin_inches = 2.0").unwrap();
+        assert_eq!(parsed, OutlookSettings { basin_inches: 2.0, ..Default::default() });
+        assert!(parsed.validate().is_ok());
+        assert!(OutlookSettings { trend_hours: 0, ..parsed.clone() }.validate().is_err());
+        assert!(OutlookSettings { margin_ft: -1.0, ..parsed }.validate().unwrap_err().contains("margin_ft"));
+    }
+}
@@ -0,0 +1,247 @@
+//! Rainfall alarm that doesn't wait for the river.
+//!
+//! Threshold alerts and most rules key on stage, but the Mackinaw turns
+//! heavy rain into a rise at its mouth in under six hours, often before
+//! any main-stem gauge has moved. This alarm looks only at the ASOS
+//! precipitation rollups (see `analysis::precip`) and fires when either
+//! - a basin's mean total over `basin_hours` reaches `basin_inches`, or
+//! - any single station's last hour reaches `station_inches_per_hour`.
+//!
+//! ```toml
+//! [precip_alarm]
+//! basin_inches = 2.0
+//! basin_hours = 6                  # one of the rollup windows
+//! station_inches_per_hour = 1.0
+//! basins = ["Mackinaw River"]      # default: every basin
+//! site = "05568500"                # subscribers of this station's zones
+//! severity = "action"
+//! ```
+//!
+//! Each basin and station alarms once when it crosses and again only
+//! after it has dropped back below (or stopped reporting), like a rule.
+//! Stations are limited to the listed basins as well.
+
+use serde::Deserialize;
+use std::collections::HashSet;
+
+use crate::alert::thresholds::{FloodAlert, FloodSeverity};
+use crate::analysis::precip::{PrecipRollup, RollupScope, PRECIP_WINDOWS_HOURS};
+use crate::asos_locations::AsosLocation;
+
+fn default_basin_hours() -> i64 {
+    6
+}
+
+fn default_severity() -> FloodSeverity {
+    FloodSeverity::Action
+}
+
+/// `[precip_alarm]` section of flomon.toml
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrecipAlarmSettings {
+    /// Basin mean total (in) over `basin_hours` that alarms
+    #[serde(default)]
+    pub basin_inches: Option<f64>,
+    #[serde(default = "default_basin_hours")]
+    pub basin_hours: i64,
+    /// One station's last-hour total (in) that alarms
+    #[serde(default)]
+    pub station_inches_per_hour: Option<f64>,
+    /// Basins watched (names as in iem_asos.toml); every basin when empty
+    #[serde(default)]
+    pub basins: Vec<String>,
+    /// Station whose zones' subscribers receive the alarm
+    pub site: String,
+    #[serde(default = "default_severity")]
+    pub severity: FloodSeverity,
+}
+
+impl PrecipAlarmSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.basin_inches.is_none() && self.station_inches_per_hour.is_none() {
+            return Err("precip_alarm: set basin_inches, station_inches_per_hour or both".to_string());
+        }
+        if !PRECIP_WINDOWS_HOURS.contains(&self.basin_hours) {
+            return Err(format!("precip_alarm: basin_hours must be one of {:?}", PRECIP_WINDOWS_HOURS));
+        }
+        for inches in [self.basin_inches, self.station_inches_per_hour].into_iter().flatten() {
+            if !inches.is_finite() || inches <= 0.0 {
+                return Err("precip_alarm: thresholds must be greater than zero".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn watches(&self, basin: &str) -> bool {
+        self.basins.is_empty() || self.basins.iter().any(|b| b == basin)
+    }
+}
+
+/// One alarm raised this cycle
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alarm {
+    /// Basin name or ASOS station ID that crossed
+    pub scope_id: String,
+    pub alert: FloodAlert,
+}
+
+/// Follows which basins and stations are over their threshold
+#[derive(Debug)]
+pub struct PrecipAlarm {
+    settings: PrecipAlarmSettings,
+    ringing: HashSet<(RollupScope, String)>,
+}
+
+impl PrecipAlarm {
+    pub fn new(settings: PrecipAlarmSettings) -> Self {
+        Self { settings, ringing: HashSet::new() }
+    }
+
+    /// Station to route alarms through
+    pub fn site(&self) -> &str {
+        &self.settings.site
+    }
+
+    /// Alarms newly raised by this cycle's station and basin rollups
+    pub fn evaluate(&mut self, rollups: &[PrecipRollup], locations: &[AsosLocation]) -> Vec<Alarm> {
+        let settings = &self.settings;
+        let basin_of = |station: &str| locations.iter()
+            .find(|l| l.station_id == station)
+            .map(|l| l.basin.as_str());
+
+        let mut over = HashSet::new();
+        let mut alarms = Vec::new();
+        for rollup in rollups {
+            let message = match rollup.scope {
+                RollupScope::Basin => {
+                    let Some(limit) = settings.basin_inches else { continue };
+                    if rollup.window_hours != settings.basin_hours
+                        || !settings.watches(&rollup.scope_id)
+                        || rollup.total_in < limit {
+                        continue;
+                    }
+                    format!("{:.2} in basin average over {} h in the {} basin (alarm at {:.2} in)",
+                        rollup.total_in, rollup.window_hours, rollup.scope_id, limit)
+                }
+                RollupScope::Station => {
+                    let Some(limit) = settings.station_inches_per_hour else { continue };
+                    let Some(basin) = basin_of(&rollup.scope_id) else { continue };
+                    if rollup.window_hours != 1 || !settings.watches(basin) || rollup.total_in < limit {
+                        continue;
+                    }
+                    format!("{:.2} in in the last hour at {} ({}; alarm at {:.2} in/hr)",
+                        rollup.total_in, rollup.scope_id, basin, limit)
+                }
+            };
+            let key = (rollup.scope, rollup.scope_id.clone());
+            if !self.ringing.contains(&key) {
+                alarms.push(Alarm {
+                    scope_id: rollup.scope_id.clone(),
+                    alert: FloodAlert {
+                        severity: settings.severity.clone(),
+                        message: format!("[rainfall] {}; rivers may not have responded yet", message),
+                        registry_version: None,
+                    },
+                });
+            }
+            over.insert(key);
+        }
+        self.ringing = over;
+        alarms
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn settings() -> PrecipAlarmSettings {
+        toml::from_str(r#"
+            basin_inches = 2.0
+            station_inches_per_hour = 1.0
+            basins = ["Mackinaw River"]
+            site = "05568500"
+        "#).unwrap()
+    }
+
+    fn rollup(scope: RollupScope, scope_id: &str, window_hours: i64, total_in: f64) -> PrecipRollup {
+        PrecipRollup {
+            scope,
+            scope_id: scope_id.to_string(),
+            window_hours,
+            window_end: Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            total_in,
+            max_in: total_in,
+            sample_count: 1,
+        }
+    }
+
+    fn location(station_id: &str, basin: &str) -> AsosLocation {
+        AsosLocation {
+            station_id: station_id.to_string(),
+            name: station_id.to_string(),
+            latitude: 40.5,
+            longitude: -89.0,
+            elevation_ft: 800.0,
+            data_types: Vec::new(),
+            relevance: String::new(),
+            basin: basin.to_string(),
+            upstream_gauge: String::new(),
+            priority: crate::asos_locations::MonitoringPriority::High,
+        }
+    }
+
+    #[test]
+    fn test_settings_default_and_validate() {
+        let parsed = settings();
+        assert_eq!((parsed.basin_hours, parsed.severity.clone()), (6, FloodSeverity::Action));
+        assert!(parsed.validate().is_ok());
+
+        let neither = PrecipAlarmSettings { basin_inches: None, station_inches_per_hour: None, ..parsed.clone() };
+        assert!(neither.validate().is_err());
+        assert!(PrecipAlarmSettings { basin_hours: 12, ..parsed }.validate().unwrap_err().contains("basin_hours"));
+    }
+
+    #[test]
+    fn test_basin_alarm_fires_once_until_it_clears() {
+        let mut alarm = PrecipAlarm::new(settings());
+        let wet = [rollup(RollupScope::Basin, "Mackinaw River", 6, 2.3)];
+
+        let first = alarm.evaluate(&wet, &[]);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].scope_id, "Mackinaw River");
+        assert!(first[0].alert.message.contains("2.30 in basin average over 6 h"));
+
+        assert!(alarm.evaluate(&wet, &[]).is_empty(), "still raining: no repeat");
+        alarm.evaluate(&[rollup(RollupScope::Basin, "Mackinaw River", 6, 1.0)], &[]);
+        assert_eq!(alarm.evaluate(&wet, &[]).len(), 1, "rearmed after dropping below");
+
+        // Other windows and unwatched basins are ignored
+        let mut alarm = PrecipAlarm::new(settings());
+        assert!(alarm.evaluate(&[
+            rollup(RollupScope::Basin, "Mackinaw River", 24, 3.0),
+            rollup(RollupScope::Basin, "Spoon River", 6, 3.0),
+        ], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_single_station_hourly_rate() {
+        let mut alarm = PrecipAlarm::new(settings());
+        let locations = [location("KBMI", "Mackinaw River"), location("KGBG", "Spoon River")];
+        let alarms = alarm.evaluate(&[
+            rollup(RollupScope::Station, "KBMI", 1, 1.2),
+            rollup(RollupScope::Station, "KBMI", 3, 1.5),
+            rollup(RollupScope::Station, "KGBG", 1, 2.0),
+            rollup(RollupScope::Basin, "Mackinaw River", 6, 1.5),
+        ], &locations);
+        assert_eq!(alarms.len(), 1);
+        assert_eq!(alarms[0].scope_id, "KBMI");
+        assert!(alarms[0].alert.message.contains("last hour at KBMI (Mackinaw River"));
+    }
+}
@@ -0,0 +1,383 @@
+//! Pending alert deliveries, drained on shutdown and redelivered at start.
+//!
+//! Alerts bound for a recipient are queued here and delivered once per poll
+//! cycle. On SIGTERM/SIGINT the daemon gives the queue a bounded drain
+//! (`DaemonConfig::shutdown_drain_seconds`), most severe alerts first, and
+//! whatever is still undelivered (deadline reached or the channel failed)
+//! is written to `pending_notifications`. The next start loads those rows
+//! back into the queue, so a Major alert that coincides with a restart is
+//! delayed rather than dropped.
+//!
+//! A notification is given up on (logged and dropped) after
+//! `MAX_ATTEMPTS` failed deliveries or once it is older than
+//! `MAX_AGE_HOURS`, and the queue holds at most `MAX_QUEUED` entries,
+//! shedding the least severe, oldest one when full, so a channel that is
+//! down for days cannot grow it without limit.
+//!
+//! Delivery itself is a callback (`Deliver`); the queue only decides order,
+//! retries, and persistence.
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::alert::thresholds::{FloodAlert, FloodSeverity};
+use crate::logging;
+
+/// Default time allowed for the shutdown drain, in seconds (well inside
+/// systemd's default 90 s stop timeout)
+pub const DEFAULT_DRAIN_SECONDS: u64 = 20;
+
+/// Failed deliveries after which a notification is dropped
+pub const MAX_ATTEMPTS: i32 = 10;
+
+/// Age after which an undelivered notification is dropped as stale
+pub const MAX_AGE_HOURS: i64 = 24;
+
+/// Most notifications held at once
+pub const MAX_QUEUED: usize = 1000;
+
+/// Delivers one notification; an `Err` leaves it queued
+pub type Deliver = dyn FnMut(&Notification) -> Result<(), String> + Send;
+
+/// How insistently a channel presents a notification
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Urgency {
+    #[default]
+    Normal,
+    /// Nobody is at the zone (see `alert::occupancy`): deliver without
+    /// pinging, at low priority
+    Quiet,
+}
+
+impl Urgency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Urgency::Normal => "normal",
+            Urgency::Quiet => "quiet",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "normal" => Some(Urgency::Normal),
+            "quiet" => Some(Urgency::Quiet),
+            _ => None,
+        }
+    }
+}
+
+/// One alert addressed to one recipient
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub recipient: String,
+    pub alert: FloodAlert,
+    pub urgency: Urgency,
+    pub queued_at: DateTime<Utc>,
+    /// Failed delivery attempts so far (including previous runs)
+    pub attempts: i32,
+    pub last_error: Option<String>,
+}
+
+/// Outcome of one delivery pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrainReport {
+    pub delivered: usize,
+    pub failed: usize,
+    /// Still queued afterwards (failed plus any not reached before the deadline)
+    pub remaining: usize,
+    /// Given up on: too many attempts or too old
+    pub dropped: usize,
+}
+
+/// In-memory queue of undelivered notifications
+#[derive(Debug, Default)]
+pub struct NotificationQueue {
+    pending: VecDeque<Notification>,
+}
+
+impl NotificationQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue `alert` for `recipient`
+    pub fn enqueue(&mut self, recipient: &str, alert: FloodAlert, now: DateTime<Utc>) {
+        self.enqueue_with_urgency(recipient, alert, Urgency::Normal, now);
+    }
+
+    /// Queue `alert` for `recipient` at `urgency`
+    pub fn enqueue_with_urgency(&mut self, recipient: &str, alert: FloodAlert, urgency: Urgency, now: DateTime<Utc>) {
+        self.pending.push_back(Notification {
+            recipient: recipient.to_string(),
+            alert,
+            urgency,
+            queued_at: now,
+            attempts: 0,
+            last_error: None,
+        });
+        self.shed_excess();
+    }
+
+    /// Drop the least severe, oldest notifications beyond `MAX_QUEUED`
+    fn shed_excess(&mut self) {
+        while self.pending.len() > MAX_QUEUED {
+            let Some(index) = self.pending.iter().enumerate()
+                .min_by(|(_, a), (_, b)| a.alert.severity.cmp(&b.alert.severity).then(a.queued_at.cmp(&b.queued_at)))
+                .map(|(i, _)| i) else { break };
+            if let Some(shed) = self.pending.remove(index) {
+                give_up(&shed, &format!("queue full ({} notifications)", MAX_QUEUED));
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Drop every queued notification (a standby leaves delivery to the
+    /// leader, see `alert::leader`). Returns the number dropped.
+    pub fn discard(&mut self) -> usize {
+        let dropped = self.pending.len();
+        self.pending.clear();
+        dropped
+    }
+
+    /// Queued notifications in delivery order
+    pub fn pending(&self) -> impl Iterator<Item = &Notification> {
+        self.pending.iter()
+    }
+
+    /// Attempt each queued notification once, most severe first (oldest
+    /// first within a severity), stopping early at `deadline`. Notifications
+    /// older than `MAX_AGE_HOURS` at `now`, or failing for the
+    /// `MAX_ATTEMPTS`th time, are dropped.
+    pub fn deliver_pending(
+        &mut self,
+        deliver: &mut dyn FnMut(&Notification) -> Result<(), String>,
+        deadline: Option<Instant>,
+        now: DateTime<Utc>,
+    ) -> DrainReport {
+        self.pending.make_contiguous()
+            .sort_by(|a, b| b.alert.severity.cmp(&a.alert.severity).then(a.queued_at.cmp(&b.queued_at)));
+
+        let mut report = DrainReport::default();
+        let mut retry = VecDeque::new();
+        while let Some(mut notification) = self.pending.pop_front() {
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                self.pending.push_front(notification);
+                break;
+            }
+            if now - notification.queued_at > Duration::hours(MAX_AGE_HOURS) {
+                give_up(&notification, &format!("undelivered after {} h", MAX_AGE_HOURS));
+                report.dropped += 1;
+                continue;
+            }
+            match deliver(&notification) {
+                Ok(()) => report.delivered += 1,
+                Err(e) => {
+                    report.failed += 1;
+                    notification.attempts += 1;
+                    notification.last_error = Some(e);
+                    if notification.attempts >= MAX_ATTEMPTS {
+                        give_up(&notification, &format!("{} failed attempts", notification.attempts));
+                        report.dropped += 1;
+                    } else {
+                        retry.push_back(notification);
+                    }
+                }
+            }
+        }
+        // Failures go behind anything not yet attempted
+        self.pending.extend(retry);
+        report.remaining = self.pending.len();
+        report
+    }
+
+    /// Write every queued notification to `pending_notifications` and
+    /// empty the queue. Returns the number written.
+    pub fn persist(&mut self, client: &mut Client) -> Result<usize, String> {
+        if self.pending.is_empty() {
+            return Ok(0);
+        }
+        let mut tx = client.transaction()
+            .map_err(|e| format!("Failed to start notification transaction: {}", e))?;
+        for n in &self.pending {
+            tx.execute(
+                "INSERT INTO pending_notifications
+                 (recipient, severity, message, registry_version, queued_at, attempts, last_error, urgency)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+                &[
+                    &n.recipient,
+                    &n.alert.severity.as_str(),
+                    &n.alert.message,
+                    &n.alert.registry_version,
+                    &n.queued_at,
+                    &n.attempts,
+                    &n.last_error,
+                    &n.urgency.as_str(),
+                ],
+            ).map_err(|e| format!("Failed to persist notification for {}: {}", n.recipient, e))?;
+        }
+        tx.commit().map_err(|e| format!("Failed to commit pending notifications: {}", e))?;
+
+        let written = self.pending.len();
+        self.pending.clear();
+        Ok(written)
+    }
+
+    /// Take notifications persisted by a previous run into the queue,
+    /// removing them from the table. Returns the number restored.
+    pub fn restore(&mut self, client: &mut Client) -> Result<usize, String> {
+        let rows = client.query(
+            "DELETE FROM pending_notifications
+             RETURNING recipient, severity, message, registry_version, queued_at, attempts, last_error, urgency",
+            &[],
+        ).map_err(|e| format!("Failed to load pending notifications: {}", e))?;
+
+        let mut restored: Vec<Notification> = rows.iter()
+            .map(|row| {
+                let severity: String = row.get(1);
+                let urgency: String = row.get(7);
+                Notification {
+                    recipient: row.get(0),
+                    alert: FloodAlert {
+                        // Constrained by the table; Major is the safe reading of anything else
+                        severity: FloodSeverity::parse(&severity).unwrap_or(FloodSeverity::Major),
+                        message: row.get(2),
+                        registry_version: row.get(3),
+                    },
+                    urgency: Urgency::parse(&urgency).unwrap_or_default(),
+                    queued_at: row.get(4),
+                    attempts: row.get(5),
+                    last_error: row.get(6),
+                }
+            })
+            .collect();
+        restored.sort_by_key(|n| n.queued_at);
+
+        let count = restored.len();
+        // Older alerts go ahead of anything queued since startup
+        for n in restored.into_iter().rev() {
+            self.pending.push_front(n);
+        }
+        self.shed_excess();
+        Ok(count)
+    }
+}
+
+/// Log a notification that will not be delivered
+fn give_up(notification: &Notification, reason: &str) {
+    logging::error(logging::DataSource::System, None, &format!(
+        "Dropping {} notification for {} ({}): {} (last error: {})",
+        notification.alert.severity.as_str(), notification.recipient, reason, notification.alert.message,
+        notification.last_error.as_deref().unwrap_or("none")));
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn alert(severity: FloodSeverity, message: &str) -> FloodAlert {
+        FloodAlert { severity, message: message.to_string(), registry_version: Some(3) }
+    }
+
+    fn t(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_most_severe_delivered_first() {
+        let mut queue = NotificationQueue::new();
+        queue.enqueue("ema@example.org", alert(FloodSeverity::Action, "action"), t(0));
+        queue.enqueue("ema@example.org", alert(FloodSeverity::Major, "major"), t(1));
+        queue.enqueue("ema@example.org", alert(FloodSeverity::Flood, "flood"), t(2));
+
+        let mut order = Vec::new();
+        let report = queue.deliver_pending(&mut |n: &Notification| {
+            order.push(n.alert.message.clone());
+            Ok(())
+        }, None, t(5));
+
+        assert_eq!(order, vec!["major", "flood", "action"]);
+        assert_eq!(report, DrainReport { delivered: 3, failed: 0, remaining: 0, dropped: 0 });
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_failed_delivery_stays_queued() {
+        let mut queue = NotificationQueue::new();
+        queue.enqueue("down@example.org", alert(FloodSeverity::Major, "major"), t(0));
+        queue.enqueue("ok@example.org", alert(FloodSeverity::Flood, "flood"), t(1));
+
+        let report = queue.deliver_pending(&mut |n: &Notification| {
+            if n.recipient.starts_with("down") { Err("connection refused".to_string()) } else { Ok(()) }
+        }, None, t(5));
+
+        assert_eq!(report, DrainReport { delivered: 1, failed: 1, remaining: 1, dropped: 0 });
+        let left = queue.pending().next().unwrap();
+        assert_eq!(left.alert.message, "major");
+        assert_eq!(left.attempts, 1);
+        assert_eq!(left.last_error.as_deref(), Some("connection refused"));
+    }
+
+    #[test]
+    fn test_expired_deadline_leaves_everything_queued() {
+        let mut queue = NotificationQueue::new();
+        queue.enqueue("ema@example.org", alert(FloodSeverity::Major, "major"), t(0));
+        queue.enqueue("ema@example.org", alert(FloodSeverity::Flood, "flood"), t(1));
+
+        let mut calls = 0;
+        let report = queue.deliver_pending(&mut |_: &Notification| {
+            calls += 1;
+            Ok(())
+        }, Some(Instant::now()), t(5));
+
+        assert_eq!(calls, 0);
+        assert_eq!(report.remaining, 2);
+        assert_eq!(queue.pending().next().unwrap().alert.message, "major");
+    }
+
+    #[test]
+    fn test_gives_up_after_max_attempts_or_age() {
+        let now = t(0) + Duration::hours(MAX_AGE_HOURS) + Duration::minutes(1);
+        let mut queue = NotificationQueue::new();
+        queue.enqueue("down@example.org", alert(FloodSeverity::Major, "retried"), now);
+        queue.enqueue("ema@example.org", alert(FloodSeverity::Flood, "stale"), t(0));
+        queue.pending.make_contiguous()[0].attempts = MAX_ATTEMPTS - 1;
+
+        let mut delivered = Vec::new();
+        let report = queue.deliver_pending(&mut |n: &Notification| {
+            delivered.push(n.alert.message.clone());
+            Err("connection refused".to_string())
+        }, None, now);
+
+        assert_eq!(delivered, vec!["retried"], "a stale notification is not attempted");
+        assert_eq!(report, DrainReport { delivered: 0, failed: 1, remaining: 0, dropped: 2 });
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_full_queue_sheds_least_severe_oldest() {
+        let mut queue = NotificationQueue::new();
+        queue.enqueue("ema@example.org", alert(FloodSeverity::Action, "oldest action"), t(0));
+        for _ in 1..MAX_QUEUED {
+            queue.enqueue("ema@example.org", alert(FloodSeverity::Action, "action"), t(1));
+        }
+        queue.enqueue("ema@example.org", alert(FloodSeverity::Major, "major"), t(2));
+
+        assert_eq!(queue.len(), MAX_QUEUED);
+        assert!(queue.pending().all(|n| n.alert.message != "oldest action"));
+        assert!(queue.pending().any(|n| n.alert.message == "major"));
+    }
+}
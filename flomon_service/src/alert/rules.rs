@@ -0,0 +1,371 @@
+//! Composite alert rules, with a shadow mode for trialling them.
+//!
+//! A rule fires when every one of its conditions holds, on the first cycle
+//! they all do; it fires again only after it has stopped matching. Each
+//! `[[alert_rules]]` entry names the station whose zones' subscribers get
+//! the alert, and a mode:
+//! - `shadow` (the default): evaluated every cycle, counted and logged, but
+//!   nothing is dispatched. A new rule can run like this for a season and
+//!   its firings be reviewed (`flomon alerts rules`) before it is trusted.
+//! - `active`: firings are sent to subscribers like threshold alerts.
+//!
+//! ```toml
+//! [[alert_rules]]
+//! name = "kingston-pre-warning"
+//! mode = "shadow"                 # promote with mode = "active"
+//! site = "05568500"
+//! severity = "action"
+//! message = "Kingston Mines rising fast with heavy rain upstream"
+//!
+//! [[alert_rules.all]]
+//! kind = "stage_above"
+//! site = "05568500"
+//! ft = 12.0
+//!
+//! [[alert_rules.all]]
+//! kind = "rapid_rise"
+//! site = "05568500"
+//!
+//! [[alert_rules.all]]
+//! kind = "basin_precip_above"
+//! basin = "Mackinaw River"
+//! hours = 24
+//! inches = 1.5
+//! ```
+//!
+//! A condition whose input is missing this cycle (station not reporting,
+//! no rollup for the basin) does not hold. Counters and firings are kept
+//! in `nws.alert_rule_stats` and `nws.alert_rule_firings`.
+
+use chrono::{DateTime, Utc};
+use postgres::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::alert::thresholds::{FloodAlert, FloodSeverity};
+use crate::analysis::precip::PRECIP_WINDOWS_HOURS;
+
+/// Whether a rule's firings are dispatched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleMode {
+    Active,
+    #[default]
+    Shadow,
+}
+
+impl RuleMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleMode::Active => "active",
+            RuleMode::Shadow => "shadow",
+        }
+    }
+}
+
+/// One `[[alert_rules.all]]` condition
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Condition {
+    /// Latest stage at `site` above `ft`
+    StageAbove { site: String, ft: f64 },
+    /// `site` is polled at the elevated interval for a rapid rise
+    /// (see `monitor::adaptive`)
+    RapidRise { site: String },
+    /// `site` at or above a flood severity
+    SeverityAtLeast { site: String, severity: FloodSeverity },
+    /// Basin mean precipitation over the last `hours` above `inches`
+    /// (see `analysis::precip`)
+    BasinPrecipAbove { basin: String, hours: i64, inches: f64 },
+}
+
+impl Condition {
+    fn holds(&self, inputs: &RuleInputs) -> bool {
+        match self {
+            Condition::StageAbove { site, ft } => inputs.stages.get(site).is_some_and(|stage| stage > ft),
+            Condition::RapidRise { site } => inputs.rapid_rise.contains(site),
+            Condition::SeverityAtLeast { site, severity } => {
+                inputs.severities.get(site).is_some_and(|level| level >= severity)
+            }
+            Condition::BasinPrecipAbove { basin, hours, inches } => inputs.basin_precip
+                .get(&(basin.clone(), *hours))
+                .is_some_and(|total| total > inches),
+        }
+    }
+}
+
+/// `[[alert_rules]]` entry of flomon.toml
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    pub name: String,
+    #[serde(default)]
+    pub mode: RuleMode,
+    /// Station whose zones' subscribers receive the alert
+    pub site: String,
+    pub severity: FloodSeverity,
+    /// Alert text; defaults to the rule name
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Conditions that must all hold
+    pub all: Vec<Condition>,
+}
+
+impl AlertRule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("alert rule name must not be empty".to_string());
+        }
+        if self.all.is_empty() {
+            return Err(format!("alert rule {} must list at least one condition", self.name));
+        }
+        for condition in &self.all {
+            match condition {
+                Condition::BasinPrecipAbove { hours, .. } if !PRECIP_WINDOWS_HOURS.contains(hours) => {
+                    return Err(format!(
+                        "alert rule {}: basin_precip_above hours must be one of {:?}",
+                        self.name, PRECIP_WINDOWS_HOURS));
+                }
+                Condition::StageAbove { ft, .. } | Condition::BasinPrecipAbove { inches: ft, .. }
+                    if !ft.is_finite() => {
+                    return Err(format!("alert rule {}: condition thresholds must be finite", self.name));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn alert(&self) -> FloodAlert {
+        FloodAlert {
+            severity: self.severity.clone(),
+            message: format!("[{}] {}", self.name, self.message.as_deref().unwrap_or(&self.name)),
+            registry_version: None,
+        }
+    }
+}
+
+/// Rule names must be unique; they key the stored counters
+pub fn validate_names(rules: &[AlertRule]) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for rule in rules {
+        if !seen.insert(rule.name.as_str()) {
+            return Err(format!("duplicate [[alert_rules]] name {}", rule.name));
+        }
+    }
+    Ok(())
+}
+
+/// What the rules can see of one cycle
+#[derive(Debug, Clone, Default)]
+pub struct RuleInputs {
+    /// Latest stage (ft) per station
+    pub stages: HashMap<String, f64>,
+    pub rapid_rise: Vec<String>,
+    pub severities: HashMap<String, FloodSeverity>,
+    /// Basin mean total (in) per (basin, window hours)
+    pub basin_precip: HashMap<(String, i64), f64>,
+}
+
+/// One rule's result for a cycle
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    pub rule: String,
+    pub mode: RuleMode,
+    pub site: String,
+    pub matched: bool,
+    /// Started matching this cycle; carries the alert
+    pub fired: Option<FloodAlert>,
+}
+
+/// Evaluates the configured rules cycle to cycle, remembering which match
+#[derive(Debug, Default)]
+pub struct RuleEngine {
+    rules: Vec<AlertRule>,
+    matching: HashSet<String>,
+}
+
+impl RuleEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self { rules, matching: HashSet::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Evaluate every rule against this cycle's inputs
+    pub fn evaluate(&mut self, inputs: &RuleInputs) -> Vec<Evaluation> {
+        self.rules.iter()
+            .map(|rule| {
+                let matched = rule.all.iter().all(|c| c.holds(inputs));
+                let fired = if matched {
+                    self.matching.insert(rule.name.clone()).then(|| rule.alert())
+                } else {
+                    self.matching.remove(&rule.name);
+                    None
+                };
+                Evaluation {
+                    rule: rule.name.clone(),
+                    mode: rule.mode,
+                    site: rule.site.clone(),
+                    matched,
+                    fired,
+                }
+            })
+            .collect()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Persistence
+// ---------------------------------------------------------------------------
+
+/// Count one cycle's evaluation, and store its firing if it fired
+pub fn record(client: &mut Client, evaluation: &Evaluation, now: DateTime<Utc>) -> Result<(), String> {
+    let matched_at = evaluation.matched.then_some(now);
+    let fired_at = evaluation.fired.as_ref().map(|_| now);
+    client.execute(
+        "INSERT INTO nws.alert_rule_stats
+         (rule_name, mode, evaluations, matches, firings, last_matched_at, last_fired_at, updated_at)
+         VALUES ($1, $2, 1, $3, $4, $5, $6, $7)
+         ON CONFLICT (rule_name) DO UPDATE
+         SET mode = EXCLUDED.mode,
+             evaluations = alert_rule_stats.evaluations + 1,
+             matches = alert_rule_stats.matches + EXCLUDED.matches,
+             firings = alert_rule_stats.firings + EXCLUDED.firings,
+             last_matched_at = COALESCE(EXCLUDED.last_matched_at, alert_rule_stats.last_matched_at),
+             last_fired_at = COALESCE(EXCLUDED.last_fired_at, alert_rule_stats.last_fired_at),
+             updated_at = EXCLUDED.updated_at",
+        &[
+            &evaluation.rule,
+            &evaluation.mode.as_str(),
+            &i64::from(evaluation.matched),
+            &i64::from(fired_at.is_some()),
+            &matched_at,
+            &fired_at,
+            &now,
+        ],
+    ).map_err(|e| format!("Failed to record alert rule {}: {}", evaluation.rule, e))?;
+
+    if let Some(alert) = &evaluation.fired {
+        client.execute(
+            "INSERT INTO nws.alert_rule_firings (rule_name, mode, fired_at, site_code, severity, message)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            &[&evaluation.rule, &evaluation.mode.as_str(), &now, &evaluation.site,
+              &alert.severity.as_str(), &alert.message],
+        ).map_err(|e| format!("Failed to record alert rule {} firing: {}", evaluation.rule, e))?;
+    }
+    Ok(())
+}
+
+/// Stored counters for one rule (`flomon alerts rules`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleStats {
+    pub rule_name: String,
+    pub mode: String,
+    pub evaluations: i64,
+    pub matches: i64,
+    pub firings: i64,
+    pub last_matched_at: Option<DateTime<Utc>>,
+    pub last_fired_at: Option<DateTime<Utc>>,
+}
+
+/// Counters for every rule evaluated so far, by name
+pub fn load_stats(client: &mut Client) -> Result<Vec<RuleStats>, String> {
+    let rows = client.query(
+        "SELECT rule_name, mode, evaluations, matches, firings, last_matched_at, last_fired_at
+         FROM nws.alert_rule_stats ORDER BY rule_name",
+        &[],
+    ).map_err(|e| format!("Failed to load alert rule stats: {}", e))?;
+    Ok(rows.iter()
+        .map(|row| RuleStats {
+            rule_name: row.get(0),
+            mode: row.get(1),
+            evaluations: row.get(2),
+            matches: row.get(3),
+            firings: row.get(4),
+            last_matched_at: row.get(5),
+            last_fired_at: row.get(6),
+        })
+        .collect())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(toml_text: &str) -> AlertRule {
+        toml::from_str(toml_text).unwrap()
+    }
+
+    const PRE_WARNING: &str = r#"
+        name = "pre-warning"
+        site = "05568500"
+        severity = "action"
+
+        [[all]]
+        kind = "stage_above"
+        site = "05568500"
+        ft = 12.0
+
+        [[all]]
+        kind = "basin_precip_above"
+        basin = "Mackinaw River"
+        hours = 24
+        inches = 1.5
+    "#;
+
+    fn inputs(stage: f64, precip: f64) -> RuleInputs {
+        RuleInputs {
+            stages: HashMap::from([("05568500".to_string(), stage)]),
+            basin_precip: HashMap::from([(("Mackinaw River".to_string(), 24), precip)]),
+            ..RuleInputs::default()
+        }
+    }
+
+    #[test]
+    fn test_rule_defaults_to_shadow_and_validates() {
+        let parsed = rule(PRE_WARNING);
+        assert_eq!(parsed.mode, RuleMode::Shadow);
+        assert!(parsed.validate().is_ok());
+
+        let bad_window = rule(&PRE_WARNING.replace("hours = 24", "hours = 12"));
+        assert!(bad_window.validate().unwrap_err().contains("hours"));
+        assert!(validate_names(&[parsed.clone(), parsed]).unwrap_err().contains("duplicate"));
+    }
+
+    #[test]
+    fn test_rule_fires_once_per_match_and_needs_every_condition() {
+        let mut engine = RuleEngine::new(vec![rule(PRE_WARNING)]);
+
+        let wet_but_low = engine.evaluate(&inputs(11.0, 2.0));
+        assert!(!wet_but_low[0].matched && wet_but_low[0].fired.is_none());
+
+        let first = engine.evaluate(&inputs(12.5, 2.0));
+        assert!(first[0].matched);
+        let alert = first[0].fired.as_ref().expect("fires on first match");
+        assert_eq!(alert.severity, FloodSeverity::Action);
+        assert!(alert.message.starts_with("[pre-warning]"));
+
+        let still = engine.evaluate(&inputs(13.0, 2.0));
+        assert!(still[0].matched && still[0].fired.is_none());
+
+        engine.evaluate(&inputs(13.0, 0.5));
+        assert!(engine.evaluate(&inputs(13.0, 2.0))[0].fired.is_some(), "fires again after clearing");
+    }
+
+    #[test]
+    fn test_missing_inputs_do_not_hold() {
+        let mut engine = RuleEngine::new(vec![rule(PRE_WARNING)]);
+        let no_precip = RuleInputs {
+            stages: HashMap::from([("05568500".to_string(), 15.0)]),
+            ..RuleInputs::default()
+        };
+        assert!(!engine.evaluate(&no_precip)[0].matched);
+    }
+}
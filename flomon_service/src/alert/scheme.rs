@@ -0,0 +1,162 @@
+//! Severity taxonomy shared by every output.
+//!
+//! Names, colors and ordering of the severity levels live in one place
+//! (`[severity]` in flomon.toml, NWS hydrograph colors by default) and are
+//! served at `/api/severity_scheme`, so the embed widget, GeoJSON feature
+//! properties and any downstream display agree. Outputs carry the level's
+//! `key` as `severity` plus its `label` and `color` from here; none of them
+//! hardcodes a color.
+//!
+//! `levels` run least to most severe and must include every level
+//! `thresholds::FloodSeverity` can produce. Extra keys (a future "record"
+//! above "major") may be listed ahead of the code that emits them.
+//!
+//! ```toml
+//! [severity]
+//! levels = [
+//!     { key = "action",   label = "Action",            color = "#f9a825" },
+//!     { key = "flood",    label = "Minor Flooding",    color = "#ef6c00" },
+//!     { key = "moderate", label = "Moderate Flooding", color = "#c62828" },
+//!     { key = "major",    label = "Major Flooding",    color = "#6a1b9a" },
+//! ]
+//! normal = { key = "normal", label = "Normal", color = "#2e7d32" }
+//! ```
+
+use serde::Deserialize;
+use std::collections::HashSet;
+
+use crate::alert::thresholds::FloodSeverity;
+
+pub use flomon_types::api::{SeveritySchemeResponse, SeverityStyle};
+
+/// Every level the threshold evaluator can produce
+const REQUIRED_LEVELS: [FloodSeverity; 4] =
+    [FloodSeverity::Action, FloodSeverity::Flood, FloodSeverity::Moderate, FloodSeverity::Major];
+
+/// `[severity]` section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SeverityScheme {
+    /// Least to most severe
+    pub levels: Vec<SeverityStyle>,
+    pub normal: SeverityStyle,
+    pub stale: SeverityStyle,
+    pub no_data: SeverityStyle,
+}
+
+fn style(key: &str, label: &str, color: &str) -> SeverityStyle {
+    SeverityStyle { key: key.to_string(), label: label.to_string(), color: color.to_string() }
+}
+
+impl Default for SeverityScheme {
+    fn default() -> Self {
+        Self {
+            levels: vec![
+                style("action", "Action", "#f9a825"),
+                style("flood", "Minor Flooding", "#ef6c00"),
+                style("moderate", "Moderate Flooding", "#c62828"),
+                style("major", "Major Flooding", "#6a1b9a"),
+            ],
+            normal: style("normal", "Normal", "#2e7d32"),
+            stale: style("stale", "Stale", "#757575"),
+            no_data: style("no_data", "No data", "#757575"),
+        }
+    }
+}
+
+impl SeverityScheme {
+    pub fn validate(&self) -> Result<(), String> {
+        let mut keys = HashSet::new();
+        for entry in self.levels.iter().chain([&self.normal, &self.stale, &self.no_data]) {
+            if entry.key.is_empty() || entry.label.is_empty() {
+                return Err("severity entries need a key and a label".to_string());
+            }
+            if !keys.insert(entry.key.as_str()) {
+                return Err(format!("severity key '{}' is listed twice", entry.key));
+            }
+            let hex = entry.color.strip_prefix('#').unwrap_or("");
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("severity '{}' color '{}' must be #rrggbb", entry.key, entry.color));
+            }
+        }
+        for level in REQUIRED_LEVELS {
+            if self.level(level.as_str()).is_none() {
+                return Err(format!("severity.levels must include '{}'", level.as_str()));
+            }
+        }
+        // Configured order must agree with the evaluator's
+        let ranks: Vec<usize> = REQUIRED_LEVELS.iter().filter_map(|l| self.rank(l.as_str())).collect();
+        if ranks.windows(2).any(|w| w[1] < w[0]) {
+            return Err("severity.levels must list action, flood, moderate, major in ascending order".to_string());
+        }
+        Ok(())
+    }
+
+    /// Style for a level key, if configured
+    pub fn level(&self, key: &str) -> Option<&SeverityStyle> {
+        self.levels.iter().find(|l| l.key == key)
+    }
+
+    /// Position of a level key, least severe first
+    pub fn rank(&self, key: &str) -> Option<usize> {
+        self.levels.iter().position(|l| l.key == key)
+    }
+
+    /// Style for a current condition: stale wins, then the severity, else normal
+    pub fn style_for(&self, severity: Option<&FloodSeverity>, stale: bool) -> &SeverityStyle {
+        if stale {
+            return &self.stale;
+        }
+        severity.and_then(|s| self.level(s.as_str())).unwrap_or(&self.normal)
+    }
+
+    pub fn to_response(&self) -> SeveritySchemeResponse {
+        SeveritySchemeResponse {
+            levels: self.levels.clone(),
+            normal: self.normal.clone(),
+            stale: self.stale.clone(),
+            no_data: self.no_data.clone(),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_scheme_is_valid_and_styles_conditions() {
+        let scheme = SeverityScheme::default();
+        scheme.validate().unwrap();
+        assert_eq!(scheme.style_for(Some(&FloodSeverity::Moderate), false).color, "#c62828");
+        assert_eq!(scheme.style_for(Some(&FloodSeverity::Major), true).key, "stale");
+        assert_eq!(scheme.style_for(None, false).label, "Normal");
+    }
+
+    #[test]
+    fn test_extra_levels_allowed_but_required_ones_checked() {
+        let mut scheme: SeverityScheme = toml::from_str(r##"
+            levels = [
+                { key = "action", label = "Action", color = "#f9a825" },
+                { key = "flood", label = "Flood", color = "#ef6c00" },
+                { key = "moderate", label = "Moderate", color = "#c62828" },
+                { key = "major", label = "Major", color = "#6a1b9a" },
+                { key = "record", label = "Record", color = "#000000" },
+            ]
+        "##).unwrap();
+        scheme.validate().unwrap();
+        assert_eq!(scheme.rank("record"), Some(4));
+        assert_eq!(scheme.normal.key, "normal");
+
+        scheme.levels.swap(0, 3);
+        assert!(scheme.validate().unwrap_err().contains("ascending"));
+        scheme.levels.remove(0);
+        assert!(scheme.validate().unwrap_err().contains("'major'"));
+        scheme.stale.color = "grey".to_string();
+        assert!(scheme.validate().unwrap_err().contains("#rrggbb"));
+    }
+}
@@ -1,18 +1,30 @@
-///is_stale + staleness tests
-/// will depend on chrono and may eventually require clock injection for testing. 
-/// Separating these functions out into their own module keeps the core data model and ingestion logic free of external dependencies and makes it easier to test the staleness logic in isolation.
-/// /// Gauge reading staleness detection.
-///
-/// USGS gauges update every 15 minutes under normal conditions. During active
-/// flood events, stale data is dangerous — a sensor outage or communication
-/// failure may not be immediately obvious from the dashboard. This module
-/// provides staleness checking so the alerting system can flag gaps.
-///
-/// # Clock injection
-/// All functions accept a `now: DateTime<Utc>` parameter rather than calling
-/// `Utc::now()` internally. This makes staleness purely deterministic in
-/// tests without mocking or time manipulation.
+//!is_stale + staleness tests
+//! will depend on chrono and may eventually require clock injection for testing. 
+//! Separating these functions out into their own module keeps the core data model and ingestion logic free of external dependencies and makes it easier to test the staleness logic in isolation.
+//! /// Gauge reading staleness detection.
+//!
+//! USGS gauges update every 15 minutes under normal conditions. During active
+//! flood events, stale data is dangerous — a sensor outage or communication
+//! failure may not be immediately obvious from the dashboard. This module
+//! provides staleness checking so the alerting system can flag gaps.
+//!
+//! # Clock injection
+//! All functions accept a `now: DateTime<Utc>` parameter rather than calling
+//! `Utc::now()` internally. This makes staleness purely deterministic in
+//! tests without mocking or time manipulation.
+//!
+//! # Stale data alerts
+//! Each poll cycle the daemon runs every monitored station's latest reading
+//! through a `StalenessTracker`. A station going stale yields one
+//! `StaleDataAlert`, queued for the station's zone subscribers like a flood
+//! alert; it alerts again only after fresh data has arrived in between.
+//! A station with no reading since the daemon started counts its age from
+//! the start.
+
+use chrono::{DateTime, FixedOffset, Utc};
+use std::collections::HashSet;
 
+use crate::alert::thresholds::{FloodAlert, FloodSeverity};
 use crate::model::GaugeReading;
 
 // ---------------------------------------------------------------------------
@@ -26,9 +38,6 @@ use crate::model::GaugeReading;
 ///   age > max_age_minutes  →  stale
 ///   age == max_age_minutes →  not stale
 ///
-/// Returns an error if the reading's datetime string cannot be parsed.
-/// Callers should treat parse failures as stale (fail-safe default).
-///
 /// # Typical thresholds
 /// - Normal monitoring: 60 minutes (four missed 15-min updates)
 /// - Active flood event: 20 minutes (one missed update)
@@ -36,33 +45,108 @@ pub fn is_stale_at(
     reading: &GaugeReading,
     max_age_minutes: u64,
     now: chrono::DateTime<chrono::Utc>,
-) -> Result<bool, String> {
-    // Parse the reading's datetime as a fixed-offset datetime
-    let reading_time = chrono::DateTime::parse_from_rfc3339(&reading.datetime)
-        .map_err(|e| format!("Failed to parse datetime '{}': {}", reading.datetime, e))?;
-    
-    // Convert to UTC for comparison
-    let reading_time_utc = reading_time.with_timezone(&chrono::Utc);
-    
+) -> bool {
     // Calculate the age in minutes
-    let age = now.signed_duration_since(reading_time_utc);
+    let age = now.signed_duration_since(reading.datetime);
     let age_minutes = age.num_minutes();
     
     // Negative age means reading is from the future - treat as stale (suspicious data)
     if age_minutes < 0 {
-        return Ok(true);
+        return true;
     }
     
     // Staleness is strictly greater than threshold
-    Ok(age_minutes as u64 > max_age_minutes)
+    age_minutes as u64 > max_age_minutes
 }
 
 /// Convenience wrapper that uses the real current time.
 /// Use `is_stale_at` in tests to keep them deterministic.
-pub fn is_stale(reading: &GaugeReading, max_age_minutes: u64) -> Result<bool, String> {
+pub fn is_stale(reading: &GaugeReading, max_age_minutes: u64) -> bool {
     is_stale_at(reading, max_age_minutes, chrono::Utc::now())
 }
 
+// ---------------------------------------------------------------------------
+// Stale data alerts
+// ---------------------------------------------------------------------------
+
+/// A station whose latest reading is older than the staleness threshold
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaleDataAlert {
+    pub site_code: String,
+    /// Datetime of the latest reading; `None` if none arrived since startup
+    pub last_reading: Option<DateTime<FixedOffset>>,
+    pub max_age_minutes: u64,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl StaleDataAlert {
+    pub fn message(&self) -> String {
+        match &self.last_reading {
+            Some(datetime) => format!(
+                "Stale data at {}: latest reading {} is more than {} min old",
+                self.site_code, datetime.to_rfc3339(), self.max_age_minutes,
+            ),
+            None => format!(
+                "Stale data at {}: no reading in the {} min since monitoring started",
+                self.site_code, self.max_age_minutes,
+            ),
+        }
+    }
+
+    /// The alert as queued for delivery, at `severity` so it reaches the
+    /// subscribers who would hear about the station's current level
+    pub fn to_flood_alert(&self, severity: FloodSeverity) -> FloodAlert {
+        FloodAlert {
+            severity,
+            message: self.message(),
+            registry_version: None,
+        }
+    }
+}
+
+/// Stations currently stale, so each outage alerts once
+#[derive(Debug)]
+pub struct StalenessTracker {
+    started_at: DateTime<Utc>,
+    stale: HashSet<String>,
+}
+
+impl StalenessTracker {
+    pub fn new(started_at: DateTime<Utc>) -> Self {
+        Self { started_at, stale: HashSet::new() }
+    }
+
+    /// Evaluate a station's latest reading at `now`. Returns an alert when
+    /// the station has just gone stale; a fresh reading clears it.
+    pub fn check(
+        &mut self,
+        site_code: &str,
+        latest: Option<&GaugeReading>,
+        max_age_minutes: u64,
+        now: DateTime<Utc>,
+    ) -> Option<StaleDataAlert> {
+        let stale = match latest {
+            Some(reading) => is_stale_at(reading, max_age_minutes, now),
+            None => (now - self.started_at).num_minutes() > max_age_minutes as i64,
+        };
+        if !stale {
+            self.stale.remove(site_code);
+            return None;
+        }
+        self.stale.insert(site_code.to_string()).then(|| StaleDataAlert {
+            site_code: site_code.to_string(),
+            last_reading: latest.map(|r| r.datetime),
+            max_age_minutes,
+            detected_at: now,
+        })
+    }
+
+    /// Whether the station was stale at its last check
+    pub fn is_stale(&self, site_code: &str) -> bool {
+        self.stale.contains(site_code)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -80,8 +164,9 @@ mod tests {
             parameter_code: "00060".to_string(),
             unit: "ft3/s".to_string(),
             value: 42_300.0,
-            datetime: datetime.to_string(),
+            datetime: chrono::DateTime::parse_from_rfc3339(datetime).unwrap(),
             qualifier: "P".to_string(),
+            original_unit: None,
         }
     }
 
@@ -96,8 +181,7 @@ mod tests {
     fn test_reading_5_minutes_old_is_not_stale() {
         // Reading at 12:55 UTC, now is 13:00 UTC — age is 5 minutes.
         let reading = reading_at("2024-05-01T12:55:00.000+00:00");
-        let stale = is_stale_at(&reading, 15, fixed_now())
-            .expect("valid datetime should not error");
+        let stale = is_stale_at(&reading, 15, fixed_now());
         assert!(!stale, "5-minute-old reading should not be stale with 15-min threshold");
     }
 
@@ -105,8 +189,7 @@ mod tests {
     fn test_reading_exactly_at_threshold_is_not_stale() {
         // Age == threshold should NOT be considered stale (strictly greater than).
         let reading = reading_at("2024-05-01T12:45:00.000+00:00"); // 15 min ago
-        let stale = is_stale_at(&reading, 15, fixed_now())
-            .expect("valid datetime should not error");
+        let stale = is_stale_at(&reading, 15, fixed_now());
         assert!(
             !stale,
             "reading exactly at threshold (15 min) should not be stale — \
@@ -119,8 +202,7 @@ mod tests {
         // USGS returns Central time with -05:00 or -06:00 offset.
         // 2024-05-01T08:00:00-05:00 == 2024-05-01T13:00:00Z — exactly 0 min old.
         let reading = reading_at("2024-05-01T08:00:00.000-05:00");
-        let stale = is_stale_at(&reading, 15, fixed_now())
-            .expect("timezone-offset datetime should parse correctly");
+        let stale = is_stale_at(&reading, 15, fixed_now());
         assert!(!stale, "reading from 0 minutes ago should not be stale");
     }
 
@@ -130,45 +212,28 @@ mod tests {
     fn test_reading_one_minute_past_threshold_is_stale() {
         // Age is 16 minutes, threshold is 15 — should be stale.
         let reading = reading_at("2024-05-01T12:44:00.000+00:00");
-        let stale = is_stale_at(&reading, 15, fixed_now())
-            .expect("valid datetime should not error");
+        let stale = is_stale_at(&reading, 15, fixed_now());
         assert!(stale, "16-minute-old reading should be stale with 15-min threshold");
     }
 
     #[test]
     fn test_reading_from_hours_ago_is_stale() {
         let reading = reading_at("2024-05-01T09:00:00.000+00:00"); // 4 hours ago
-        let stale = is_stale_at(&reading, 60, fixed_now())
-            .expect("valid datetime should not error");
+        let stale = is_stale_at(&reading, 60, fixed_now());
         assert!(stale, "4-hour-old reading should be stale with 60-min threshold");
     }
 
     #[test]
     fn test_reading_from_2020_is_stale_under_any_threshold() {
         let reading = reading_at("2020-01-01T00:00:00.000+00:00");
-        let stale = is_stale_at(&reading, 60, fixed_now())
-            .expect("old but valid datetime should parse");
+        let stale = is_stale_at(&reading, 60, fixed_now());
         assert!(stale, "reading from 2020 should be stale under any reasonable threshold");
     }
 
-    // --- Error handling -----------------------------------------------------
-
-    #[test]
-    fn test_invalid_datetime_returns_error() {
-        let reading = reading_at("not-a-datetime");
-        let result = is_stale_at(&reading, 15, fixed_now());
-        assert!(
-            result.is_err(),
-            "unparseable datetime should return Err, got {:?}",
-            result
-        );
-    }
-
     #[test]
-    fn test_empty_datetime_returns_error() {
-        let reading = reading_at("");
-        let result = is_stale_at(&reading, 15, fixed_now());
-        assert!(result.is_err(), "empty datetime should return Err");
+    fn test_reading_from_the_future_is_stale() {
+        let reading = reading_at("2024-05-01T13:30:00.000+00:00");
+        assert!(is_stale_at(&reading, 60, fixed_now()), "a future timestamp is suspect");
     }
 
     // --- Threshold variation ------------------------------------------------
@@ -177,9 +242,41 @@ mod tests {
     fn test_same_reading_stale_under_tight_threshold_not_under_loose() {
         // Reading is 30 minutes old.
         let reading = reading_at("2024-05-01T12:30:00.000+00:00");
-        let stale_20 = is_stale_at(&reading, 20, fixed_now()).expect("should not error");
-        let stale_60 = is_stale_at(&reading, 60, fixed_now()).expect("should not error");
+        let stale_20 = is_stale_at(&reading, 20, fixed_now());
+        let stale_60 = is_stale_at(&reading, 60, fixed_now());
         assert!(stale_20, "30-min-old reading is stale under a 20-min threshold");
         assert!(!stale_60, "30-min-old reading is not stale under a 60-min threshold");
     }
+
+    // --- Tracker ------------------------------------------------------------
+
+    #[test]
+    fn test_tracker_alerts_once_per_outage() {
+        let mut tracker = StalenessTracker::new(fixed_now() - chrono::Duration::hours(6));
+        let old = reading_at("2024-05-01T11:00:00.000+00:00"); // 2 hours ago
+        let fresh = reading_at("2024-05-01T12:55:00.000+00:00");
+
+        let alert = tracker.check("05568500", Some(&old), 60, fixed_now())
+            .expect("first stale check should alert");
+        assert_eq!(alert.last_reading, Some(old.datetime));
+        assert!(alert.message().contains("latest reading 2024-05-01T11:00:00+00:00"));
+        assert!(tracker.check("05568500", Some(&old), 60, fixed_now()).is_none(),
+            "still stale next cycle should not alert again");
+
+        assert!(tracker.check("05568500", Some(&fresh), 60, fixed_now()).is_none());
+        assert!(!tracker.is_stale("05568500"));
+        assert!(tracker.check("05568500", Some(&old), 60, fixed_now()).is_some(),
+            "a new outage after recovery should alert");
+    }
+
+    #[test]
+    fn test_tracker_without_reading_counts_from_start() {
+        let mut tracker = StalenessTracker::new(fixed_now() - chrono::Duration::minutes(30));
+        assert!(tracker.check("05567500", None, 60, fixed_now()).is_none());
+
+        let later = fixed_now() + chrono::Duration::minutes(31);
+        let alert = tracker.check("05567500", None, 60, later).expect("no reading for 61 min");
+        assert!(alert.last_reading.is_none());
+        assert!(alert.message().contains("since monitoring started"));
+    }
 }
@@ -0,0 +1,240 @@
+//! Zone-level notification subscriptions.
+//!
+//! Recipients subscribe to individual zones (0-6, see zones.toml) instead of
+//! the whole basin, each with a severity floor: the neighbor downstream can
+//! follow only the boat-ramp zone at `moderate` and above while the EMA
+//! follows every zone from `action`.
+//!
+//! Subscriptions come from two places and are merged:
+//! - `[[subscriptions]]` in flomon.toml (reviewed with the rest of the config)
+//! - the `zone_subscriptions` table, managed with `flomon subscribe`
+//!
+//! When a station escalates, the daemon asks `recipients_for(zone, severity)`
+//! for each zone the station is in and queues the alert for them; the
+//! recipient string selects the delivery channel (see `alert::dispatch`).
+//! Zones nobody is at get the alert quietly (see `alert::occupancy`).
+
+use postgres::Client;
+use serde::Deserialize;
+
+use crate::alert::thresholds::FloodSeverity;
+
+/// Highest zone id defined in zones.toml
+pub const MAX_ZONE_ID: usize = 6;
+
+/// One recipient following one zone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    /// Channel name, `channel:address`, or an email address (see `alert::dispatch`)
+    pub recipient: String,
+    pub zone_id: usize,
+    /// Only alerts at or above this severity are delivered
+    pub min_severity: FloodSeverity,
+}
+
+/// `[[subscriptions]]` entry in flomon.toml; one entry may cover several zones
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubscriptionConfig {
+    pub recipient: String,
+    pub zones: Vec<usize>,
+    #[serde(default = "default_min_severity")]
+    pub min_severity: FloodSeverity,
+}
+
+fn default_min_severity() -> FloodSeverity {
+    FloodSeverity::Flood
+}
+
+impl SubscriptionConfig {
+    /// One `Subscription` per listed zone
+    pub fn expand(&self) -> Vec<Subscription> {
+        self.zones.iter()
+            .map(|&zone_id| Subscription {
+                recipient: self.recipient.clone(),
+                zone_id,
+                min_severity: self.min_severity.clone(),
+            })
+            .collect()
+    }
+
+    /// Recipient present and zone ids in range
+    pub fn validate(&self) -> Result<(), String> {
+        if self.recipient.trim().is_empty() {
+            return Err("subscription recipient must not be empty".to_string());
+        }
+        if self.zones.is_empty() {
+            return Err(format!("subscription for {} must list at least one zone", self.recipient));
+        }
+        if let Some(zone) = self.zones.iter().find(|&&z| z > MAX_ZONE_ID) {
+            return Err(format!(
+                "subscription for {} has zone {} (zones are 0-{})",
+                self.recipient, zone, MAX_ZONE_ID
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Recipients of an alert in `zone_id` at `severity`, sorted and deduplicated.
+///
+/// Expects one entry per recipient/zone pair, as `merge` leaves them (a
+/// stored floor replaces the config one, raised or lowered). Should a pair
+/// appear twice anyway, the lower floor is the one that matters.
+pub fn recipients_for(subscriptions: &[Subscription], zone_id: usize, severity: &FloodSeverity) -> Vec<String> {
+    let mut recipients: Vec<String> = subscriptions.iter()
+        .filter(|s| s.zone_id == zone_id && *severity >= s.min_severity)
+        .map(|s| s.recipient.clone())
+        .collect();
+    recipients.sort();
+    recipients.dedup();
+    recipients
+}
+
+/// Merge config subscriptions with stored ones; stored rows override the
+/// config floor for the same recipient and zone.
+pub fn merge(config: &[SubscriptionConfig], stored: Vec<Subscription>) -> Vec<Subscription> {
+    let mut merged: Vec<Subscription> = config.iter().flat_map(SubscriptionConfig::expand).collect();
+    for sub in stored {
+        match merged.iter_mut().find(|m| m.recipient == sub.recipient && m.zone_id == sub.zone_id) {
+            Some(existing) => existing.min_severity = sub.min_severity,
+            None => merged.push(sub),
+        }
+    }
+    merged
+}
+
+// ============================================================================
+// Storage (flomon subscribe)
+// ============================================================================
+
+/// Subscribe (or update the floor for) a recipient on a zone
+pub fn upsert(client: &mut Client, sub: &Subscription) -> Result<(), String> {
+    if sub.zone_id > MAX_ZONE_ID {
+        return Err(format!("zone {} does not exist (zones are 0-{})", sub.zone_id, MAX_ZONE_ID));
+    }
+    let zone_id = sub.zone_id as i16;
+    client.execute(
+        "INSERT INTO zone_subscriptions (recipient, zone_id, min_severity)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (recipient, zone_id) DO UPDATE
+         SET min_severity = EXCLUDED.min_severity, updated_at = NOW()",
+        &[&sub.recipient, &zone_id, &sub.min_severity.as_str()],
+    ).map_err(|e| format!("Failed to save subscription: {}", e))?;
+    Ok(())
+}
+
+/// Remove a recipient from one zone, or from all zones when `zone_id` is None.
+/// Returns the number of subscriptions removed.
+pub fn remove(client: &mut Client, recipient: &str, zone_id: Option<usize>) -> Result<u64, String> {
+    let zone_id = zone_id.map(|z| z as i16);
+    client.execute(
+        "DELETE FROM zone_subscriptions
+         WHERE recipient = $1 AND ($2::SMALLINT IS NULL OR zone_id = $2)",
+        &[&recipient, &zone_id],
+    ).map_err(|e| format!("Failed to remove subscription: {}", e))
+}
+
+/// All stored subscriptions, ordered by zone then recipient
+pub fn load_stored(client: &mut Client) -> Result<Vec<Subscription>, String> {
+    let rows = client.query(
+        "SELECT recipient, zone_id, min_severity FROM zone_subscriptions ORDER BY zone_id, recipient",
+        &[],
+    ).map_err(|e| format!("Failed to load subscriptions: {}", e))?;
+
+    rows.iter()
+        .map(|row| {
+            let zone_id: i16 = row.get(1);
+            let severity: String = row.get(2);
+            Ok(Subscription {
+                recipient: row.get(0),
+                zone_id: zone_id as usize,
+                min_severity: FloodSeverity::parse(&severity)
+                    .ok_or_else(|| format!("Unknown severity '{}' in zone_subscriptions", severity))?,
+            })
+        })
+        .collect()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sub(recipient: &str, zone_id: usize, min_severity: FloodSeverity) -> Subscription {
+        Subscription { recipient: recipient.to_string(), zone_id, min_severity }
+    }
+
+    #[test]
+    fn test_recipients_respect_zone_and_severity_floor() {
+        let subs = vec![
+            sub("neighbor@example.com", 2, FloodSeverity::Moderate),
+            sub("ema@example.com", 2, FloodSeverity::Action),
+            sub("ema@example.com", 3, FloodSeverity::Action),
+        ];
+
+        assert_eq!(recipients_for(&subs, 2, &FloodSeverity::Flood), vec!["ema@example.com"]);
+        assert_eq!(
+            recipients_for(&subs, 2, &FloodSeverity::Major),
+            vec!["ema@example.com", "neighbor@example.com"]
+        );
+        assert!(recipients_for(&subs, 5, &FloodSeverity::Major).is_empty());
+    }
+
+    #[test]
+    fn test_config_entry_expands_per_zone_and_validates() {
+        let config: SubscriptionConfig = toml::from_str(
+            r#"
+            recipient = "+13095550100"
+            zones = [1, 2]
+            min_severity = "moderate"
+            "#,
+        ).unwrap();
+        let subs = config.expand();
+        assert_eq!(subs.len(), 2);
+        assert_eq!(subs[1].zone_id, 2);
+        assert_eq!(subs[1].min_severity, FloodSeverity::Moderate);
+        assert!(config.validate().is_ok());
+
+        let bad = SubscriptionConfig { zones: vec![7], ..config.clone() };
+        assert!(bad.validate().is_err());
+        let empty = SubscriptionConfig { zones: vec![], ..config };
+        assert!(empty.validate().is_err());
+    }
+
+    #[test]
+    fn test_default_floor_is_flood() {
+        let config: SubscriptionConfig = toml::from_str("recipient = \"a\"\nzones = [0]").unwrap();
+        assert_eq!(config.min_severity, FloodSeverity::Flood);
+    }
+
+    #[test]
+    fn test_stored_subscription_overrides_config_floor() {
+        let config = vec![
+            SubscriptionConfig {
+                recipient: "neighbor@example.com".to_string(),
+                zones: vec![2],
+                min_severity: FloodSeverity::Major,
+            },
+            SubscriptionConfig {
+                recipient: "marina@example.com".to_string(),
+                zones: vec![2],
+                min_severity: FloodSeverity::Action,
+            },
+        ];
+        let stored = vec![
+            sub("neighbor@example.com", 2, FloodSeverity::Flood),
+            sub("marina@example.com", 2, FloodSeverity::Major),
+            sub("ema@example.com", 4, FloodSeverity::Action),
+        ];
+        let merged = merge(&config, stored);
+
+        assert_eq!(merged.len(), 3);
+        // Stored floors win whether they are lower or higher than the config's
+        assert_eq!(recipients_for(&merged, 2, &FloodSeverity::Flood), vec!["neighbor@example.com"]);
+        assert_eq!(recipients_for(&merged, 2, &FloodSeverity::Major), vec!["marina@example.com", "neighbor@example.com"]);
+    }
+}
@@ -6,17 +6,25 @@
 //! **Philosophy:**
 //! - Rust daemon: Simple, fast threshold checks for real-time alerts
 //! - Python/FloML: Complex analysis to discover better thresholds via segmented regression
-//! - Thresholds can be updated based on ML findings for improved accuracy
+//! - Thresholds can be updated based on ML findings for improved accuracy, by
+//!   writing them to `flood_analysis.thresholds` (see `threshold_overrides`)
+//!
+//! Regulated pool gauges (Peoria pool) can instead be evaluated against
+//! deviation from their target pool elevation (`check_pool_deviation`);
+//! `check_station` picks whichever definition a station has.
 //!
 //! Notification dispatch, alert deduplication, and cooldown logic will also likely
 //! live here, since they're closely related to the concept of a "threshold breach"
 //! and may require access to the same metadata about each site (e.g. which parameters
 //! have thresholds, what are the threshold values, etc.).
 
-use crate::model::{FloodThresholds, GaugeReading};
+use crate::model::{FloodThresholds, GaugeReading, PoolDeviationThresholds};
+use crate::stations::Station;
+use serde::Deserialize;
 
 /// Flood severity levels, in ascending order of severity.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum FloodSeverity {
     Action,
     Flood,
@@ -24,17 +32,51 @@ pub enum FloodSeverity {
     Major,
 }
 
+impl FloodSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FloodSeverity::Action => "action",
+            FloodSeverity::Flood => "flood",
+            FloodSeverity::Moderate => "moderate",
+            FloodSeverity::Major => "major",
+        }
+    }
+
+    /// Parse the lowercase form used in config files and the database
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "action" => Some(FloodSeverity::Action),
+            "flood" => Some(FloodSeverity::Flood),
+            "moderate" => Some(FloodSeverity::Moderate),
+            "major" => Some(FloodSeverity::Major),
+            _ => None,
+        }
+    }
+}
+
 /// A flood alert triggered when a reading exceeds a threshold.
 #[derive(Debug, Clone, PartialEq)]
 pub struct FloodAlert {
     pub severity: FloodSeverity,
     pub message: String,
+    /// Station registry version whose thresholds produced this alert
+    /// (see `registry`); `None` until stamped by the caller.
+    pub registry_version: Option<i32>,
+}
+
+impl FloodAlert {
+    /// Stamp the registry version in effect
+    pub fn with_registry_version(mut self, version: Option<i32>) -> Self {
+        self.registry_version = version;
+        self
+    }
 }
 
 /// Checks if a stage reading exceeds any flood thresholds and returns an
 /// alert if so.
 ///
-/// Returns `None` if the reading is below the action stage threshold.
+/// Returns `None` if the reading is below the action stage threshold, or
+/// is not a stage at all (a discharge is never compared with stage levels).
 ///
 /// **Note:** These are simple static thresholds. The ML/analysis layer
 /// can discover better thresholds through segmented regression and update
@@ -43,43 +85,152 @@ pub fn check_flood_stage(
     reading: &GaugeReading,
     thresholds: &FloodThresholds,
 ) -> Option<FloodAlert> {
-    let stage = reading.value;
+    let stage = reading.stage()?;
     
     // Check thresholds in descending order of severity
-    if stage >= thresholds.major_flood_stage_ft {
-        Some(FloodAlert {
-            severity: FloodSeverity::Major,
-            message: format!(
-                "MAJOR FLOOD at {}: {:.2} ft (major flood stage: {:.2} ft)",
-                reading.site_name, stage, thresholds.major_flood_stage_ft
-            ),
-        })
-    } else if stage >= thresholds.moderate_flood_stage_ft {
-        Some(FloodAlert {
-            severity: FloodSeverity::Moderate,
-            message: format!(
-                "MODERATE FLOOD at {}: {:.2} ft (moderate flood stage: {:.2} ft)",
-                reading.site_name, stage, thresholds.moderate_flood_stage_ft
-            ),
-        })
-    } else if stage >= thresholds.flood_stage_ft {
-        Some(FloodAlert {
-            severity: FloodSeverity::Flood,
-            message: format!(
-                "FLOOD at {}: {:.2} ft (flood stage: {:.2} ft)",
-                reading.site_name, stage, thresholds.flood_stage_ft
-            ),
-        })
-    } else if stage >= thresholds.action_stage_ft {
-        Some(FloodAlert {
-            severity: FloodSeverity::Action,
-            message: format!(
-                "Action stage reached at {}: {:.2} ft (action stage: {:.2} ft)",
-                reading.site_name, stage, thresholds.action_stage_ft
-            ),
-        })
+    let (severity, message) = if stage >= thresholds.major() {
+        (FloodSeverity::Major, format!(
+            "MAJOR FLOOD at {}: {:.2} (major flood stage: {:.2})",
+            reading.site_name, stage, thresholds.major()
+        ))
+    } else if stage >= thresholds.moderate() {
+        (FloodSeverity::Moderate, format!(
+            "MODERATE FLOOD at {}: {:.2} (moderate flood stage: {:.2})",
+            reading.site_name, stage, thresholds.moderate()
+        ))
+    } else if stage >= thresholds.flood() {
+        (FloodSeverity::Flood, format!(
+            "FLOOD at {}: {:.2} (flood stage: {:.2})",
+            reading.site_name, stage, thresholds.flood()
+        ))
+    } else if stage >= thresholds.action() {
+        (FloodSeverity::Action, format!(
+            "Action stage reached at {}: {:.2} (action stage: {:.2})",
+            reading.site_name, stage, thresholds.action()
+        ))
     } else {
         // Below action stage - no alert
-        None
+        return None;
+    };
+    Some(FloodAlert { severity, message, registry_version: None })
+}
+
+/// Checks a pool gauge stage against levels relative to the target pool.
+///
+/// Returns `None` below the action deviation.
+pub fn check_pool_deviation(
+    reading: &GaugeReading,
+    pool: &PoolDeviationThresholds,
+) -> Option<FloodAlert> {
+    let elevation = reading.stage()?.ft() + pool.gauge_datum_ft;
+    let deviation = elevation - pool.target_elevation_ft;
+    let (severity, level, label) = if deviation >= pool.major_ft {
+        (FloodSeverity::Major, pool.major_ft, "MAJOR FLOOD")
+    } else if deviation >= pool.moderate_ft {
+        (FloodSeverity::Moderate, pool.moderate_ft, "MODERATE FLOOD")
+    } else if deviation >= pool.flood_ft {
+        (FloodSeverity::Flood, pool.flood_ft, "FLOOD")
+    } else if deviation >= pool.action_ft {
+        (FloodSeverity::Action, pool.action_ft, "Action level reached")
+    } else {
+        return None;
+    };
+    Some(FloodAlert {
+        severity,
+        message: format!(
+            "{} at {}: pool {:.2} ft, {:+.2} ft from target {:.2} ft NGVD29 (level: {:+.2} ft)",
+            label, reading.site_name, elevation, deviation, pool.target_elevation_ft, level
+        ),
+        registry_version: None,
+    })
+}
+
+/// Evaluates a station's stage reading with its alert definition: pool
+/// deviation for regulated pool gauges, otherwise stage thresholds.
+///
+/// Returns `None` below action level or when the station has neither.
+pub fn check_station(station: &Station, reading: &GaugeReading) -> Option<FloodAlert> {
+    match (&station.pool_deviation, &station.thresholds) {
+        (Some(pool), _) => check_pool_deviation(reading, pool),
+        (None, Some(thresholds)) => check_flood_stage(reading, thresholds),
+        (None, None) => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peoria_pool() -> PoolDeviationThresholds {
+        PoolDeviationThresholds {
+            pool_location: "Peoria-Pool".to_string(),
+            target_elevation_ft: 447.0,
+            gauge_datum_ft: 429.0,
+            action_ft: 1.0,
+            flood_ft: 2.0,
+            moderate_ft: 4.0,
+            major_ft: 6.0,
+        }
+    }
+
+    fn stage(value: f64) -> GaugeReading {
+        GaugeReading {
+            site_code: "05567500".to_string(),
+            site_name: "Illinois River at Peoria, IL".to_string(),
+            parameter_code: "00065".to_string(),
+            unit: "ft".to_string(),
+            value,
+            datetime: chrono::DateTime::parse_from_rfc3339("2024-05-01T12:00:00-05:00").unwrap(),
+            qualifier: "P".to_string(),
+            original_unit: None,
+        }
+    }
+
+    #[test]
+    fn test_pool_deviation_levels() {
+        let pool = peoria_pool();
+        // 18.0 ft stage = 447.0 ft pool: at target
+        assert_eq!(check_pool_deviation(&stage(18.0), &pool), None);
+        assert_eq!(check_pool_deviation(&stage(19.0), &pool).unwrap().severity, FloodSeverity::Action);
+        let flood = check_pool_deviation(&stage(20.5), &pool).unwrap();
+        assert_eq!(flood.severity, FloodSeverity::Flood);
+        assert!(flood.message.contains("+2.50 ft from target 447.00"));
+        assert_eq!(check_pool_deviation(&stage(24.0), &pool).unwrap().severity, FloodSeverity::Major);
+    }
+
+    #[test]
+    fn test_pool_definition_takes_precedence() {
+        let mut station = crate::stations::find_station("05567500").expect("Peoria is in the registry");
+        station.pool_deviation = Some(peoria_pool());
+        // 18.5 ft is above the stage thresholds' flood stage but only
+        // 0.5 ft over target pool
+        assert_eq!(check_station(&station, &stage(18.5)), None);
+
+        station.pool_deviation = None;
+        assert!(check_station(&station, &stage(18.5)).is_some());
+        station.thresholds = None;
+        assert_eq!(check_station(&station, &stage(30.0)), None);
+    }
+
+    #[test]
+    fn test_only_stages_are_checked_in_feet() {
+        let thresholds = crate::stations::find_station("05567500").unwrap().thresholds.unwrap();
+
+        // 12,400 cfs is not a 12,400 ft stage
+        let mut discharge = stage(12_400.0);
+        discharge.parameter_code = crate::model::PARAM_DISCHARGE.to_string();
+        discharge.unit = "ft3/s".to_string();
+        assert_eq!(check_flood_stage(&discharge, &thresholds), None);
+
+        // A metric stage is compared in feet
+        let mut metric = stage(thresholds.flood_stage_ft / 3.280_839_895 + 0.01);
+        metric.unit = "m".to_string();
+        let alert = check_flood_stage(&metric, &thresholds).unwrap();
+        assert_eq!(alert.severity, FloodSeverity::Flood);
+        assert!(alert.message.contains(&format!("(flood stage: {:.2} ft)", thresholds.flood_stage_ft)));
     }
 }
@@ -0,0 +1,249 @@
+//! Per-station flood severity transitions.
+//!
+//! The daemon feeds each station's latest stage severity (`None` below
+//! action stage) to `SeverityTracker::update` every cycle. A `Transition`
+//! is returned only when the level changes, so consumers that should act
+//! once per escalation or recession (webcam snapshots, notifications) do not
+//! fire on every poll while the river sits at one level.
+//!
+//! While a station is at action stage or above the tracker also follows
+//! the event: when it began and its crest so far. A de-escalation carries
+//! that record (`Transition::crest`), so "dropped below flood stage" and
+//! the all-clear ("returned below action stage") can say how high the
+//! river got and for how long; a property owner needs the way down as much
+//! as the way up.
+//!
+//! State is in memory: after a restart the first reading of a station that
+//! is already above action stage counts as a transition from `None`, and
+//! the event is taken to start there.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+use crate::alert::thresholds::FloodSeverity;
+
+/// A station moving from one severity level to another
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    pub site_code: String,
+    /// `None`: below action stage
+    pub from: Option<FloodSeverity>,
+    pub to: Option<FloodSeverity>,
+    pub stage_ft: f64,
+    pub reading_time: DateTime<Utc>,
+    /// The event so far, on de-escalations
+    pub crest: Option<Crest>,
+}
+
+/// Highest stage of the current event above action stage
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crest {
+    pub stage_ft: f64,
+    pub reading_time: DateTime<Utc>,
+    /// First reading at action stage or above
+    pub event_started: DateTime<Utc>,
+}
+
+impl Transition {
+    pub fn is_escalation(&self) -> bool {
+        self.to > self.from
+    }
+
+    pub fn is_deescalation(&self) -> bool {
+        self.to < self.from
+    }
+
+    /// Back below action stage: the event is over
+    pub fn is_all_clear(&self) -> bool {
+        self.from.is_some() && self.to.is_none()
+    }
+
+    /// Time since the event began, on de-escalations
+    pub fn event_duration(&self) -> Option<Duration> {
+        self.crest.as_ref().map(|crest| self.reading_time - crest.event_started)
+    }
+
+    /// "dropped below flood stage at 17.80 ft (crest 21.35 ft at
+    /// 2024-05-03 06:00 UTC, 2 d 4 h above action stage)"; None unless a
+    /// de-escalation. Names the lowest level crossed on the way down.
+    pub fn recession_message(&self) -> Option<String> {
+        if !self.is_deescalation() {
+            return None;
+        }
+        let crossed = match &self.to {
+            None => "returned below action stage",
+            Some(FloodSeverity::Action) => "dropped below flood stage",
+            Some(FloodSeverity::Flood) => "dropped below moderate flood stage",
+            Some(FloodSeverity::Moderate) | Some(FloodSeverity::Major) => "dropped below major flood stage",
+        };
+        let mut message = format!("{} at {:.2} ft", crossed, self.stage_ft);
+        if let (Some(crest), Some(duration)) = (&self.crest, self.event_duration()) {
+            message.push_str(&format!(
+                " (crest {:.2} ft at {}, {} above action stage)",
+                crest.stage_ft,
+                crest.reading_time.format("%Y-%m-%d %H:%M UTC"),
+                format_duration(duration),
+            ));
+        }
+        Some(message)
+    }
+    /// "flood -> moderate", with "normal" for below action stage
+    pub fn describe(&self) -> String {
+        format!("{} -> {}", severity_label(self.from.as_ref()), severity_label(self.to.as_ref()))
+    }
+}
+
+/// "2 d 4 h", "5 h 20 min", "40 min"
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{} d {} h", days, hours)
+    } else if hours > 0 {
+        format!("{} h {} min", hours, minutes)
+    } else {
+        format!("{} min", minutes)
+    }
+}
+
+/// Lowercase severity name, "normal" below action stage
+pub fn severity_label(severity: Option<&FloodSeverity>) -> &'static str {
+    severity.map(FloodSeverity::as_str).unwrap_or("normal")
+}
+
+/// Last severity seen per station
+#[derive(Debug, Default)]
+pub struct SeverityTracker {
+    levels: HashMap<String, Option<FloodSeverity>>,
+    /// Stations at action stage or above: their event so far
+    crests: HashMap<String, Crest>,
+}
+
+impl SeverityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Most severe station right now, if any is at action stage or above
+    pub fn highest(&self) -> Option<(&str, FloodSeverity)> {
+        self.levels.iter()
+            .filter_map(|(site, level)| level.clone().map(|l| (site.as_str(), l)))
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+    }
+
+    /// Last severity recorded for a station (`None` below action stage or unseen)
+    pub fn level(&self, site_code: &str) -> Option<FloodSeverity> {
+        self.levels.get(site_code).cloned().flatten()
+    }
+
+    /// Crest of the station's current event, if it is above action stage
+    pub fn crest(&self, site_code: &str) -> Option<&Crest> {
+        self.crests.get(site_code)
+    }
+
+    /// Record the current severity for a station; returns the transition
+    /// when it differs from the previous one
+    pub fn update(
+        &mut self,
+        site_code: &str,
+        severity: Option<FloodSeverity>,
+        stage_ft: f64,
+        reading_time: DateTime<Utc>,
+    ) -> Option<Transition> {
+        let previous = self.levels.insert(site_code.to_string(), severity.clone()).flatten();
+        if severity.is_some() {
+            let crest = self.crests.entry(site_code.to_string()).or_insert(Crest {
+                stage_ft,
+                reading_time,
+                event_started: reading_time,
+            });
+            if stage_ft > crest.stage_ft {
+                crest.stage_ft = stage_ft;
+                crest.reading_time = reading_time;
+            }
+        }
+        let crest = match (&severity, severity < previous) {
+            (None, _) => self.crests.remove(site_code),
+            (Some(_), true) => self.crests.get(site_code).cloned(),
+            (Some(_), false) => None,
+        };
+        (previous != severity).then(|| Transition {
+            site_code: site_code.to_string(),
+            from: previous,
+            to: severity,
+            stage_ft,
+            reading_time,
+            crest,
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_only_level_changes_are_transitions() {
+        let t = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut tracker = SeverityTracker::new();
+
+        assert_eq!(tracker.update("05568500", None, 12.0, t), None);
+        let up = tracker.update("05568500", Some(FloodSeverity::Flood), 16.2, t).unwrap();
+        assert!(up.is_escalation());
+        assert_eq!(up.describe(), "normal -> flood");
+        assert_eq!(tracker.update("05568500", Some(FloodSeverity::Flood), 16.5, t), None);
+
+        let down = tracker.update("05568500", Some(FloodSeverity::Action), 15.1, t).unwrap();
+        assert!(!down.is_escalation());
+        assert_eq!(down.from, Some(FloodSeverity::Flood));
+    }
+
+    #[test]
+    fn test_deescalations_carry_the_crest_and_duration() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let at = |hours| start + Duration::hours(hours);
+        let mut tracker = SeverityTracker::new();
+
+        let up = tracker.update("05567500", Some(FloodSeverity::Action), 14.2, at(0)).unwrap();
+        assert_eq!(up.crest, None);
+        assert_eq!(up.recession_message(), None);
+        tracker.update("05567500", Some(FloodSeverity::Flood), 18.4, at(20));
+        tracker.update("05567500", Some(FloodSeverity::Flood), 21.35, at(42));
+        tracker.update("05567500", Some(FloodSeverity::Flood), 20.9, at(48));
+        assert_eq!(tracker.crest("05567500").unwrap().stage_ft, 21.35);
+
+        let down = tracker.update("05567500", Some(FloodSeverity::Action), 17.8, at(52)).unwrap();
+        assert!(down.is_deescalation() && !down.is_all_clear());
+        let crest = down.crest.as_ref().unwrap();
+        assert_eq!((crest.stage_ft, crest.reading_time, crest.event_started), (21.35, at(42), at(0)));
+        assert_eq!(
+            down.recession_message().unwrap(),
+            "dropped below flood stage at 17.80 ft (crest 21.35 ft at 2024-05-03 06:00 UTC, 2 d 4 h above action stage)",
+        );
+
+        let clear = tracker.update("05567500", None, 13.9, at(70)).unwrap();
+        assert!(clear.is_all_clear());
+        assert_eq!(clear.event_duration(), Some(Duration::hours(70)));
+        assert!(clear.recession_message().unwrap().starts_with("returned below action stage at 13.90 ft (crest 21.35 ft"));
+        assert_eq!(tracker.crest("05567500"), None, "event over");
+
+        // The next event starts afresh
+        tracker.update("05567500", Some(FloodSeverity::Action), 14.1, at(90));
+        assert_eq!(tracker.crest("05567500").unwrap().event_started, at(90));
+    }
+
+    #[test]
+    fn test_first_reading_above_action_is_a_transition() {
+        let t = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut tracker = SeverityTracker::new();
+        let first = tracker.update("05567500", Some(FloodSeverity::Moderate), 21.0, t).unwrap();
+        assert_eq!(first.from, None);
+        tracker.update("05568500", Some(FloodSeverity::Action), 14.5, t);
+        assert_eq!(tracker.highest(), Some(("05567500", FloodSeverity::Moderate)));
+    }
+}
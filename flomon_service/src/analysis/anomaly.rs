@@ -0,0 +1,180 @@
+//! Per-station anomaly scoring with robust statistics.
+//!
+//! Each new USGS reading is compared against the same station/parameter's
+//! recent history using a median/MAD z-score:
+//!
+//! ```text
+//! robust_z = (value - median) / (1.4826 * MAD)
+//! ```
+//!
+//! Median and MAD are insensitive to the outliers being hunted, so one bad
+//! value doesn't widen the band enough to hide the next. This is a cheap,
+//! real-time "suspect value" flag computed at ingest; the offline Python
+//! analysis remains the place for seasonal models and regression.
+//!
+//! # Scale floor
+//! A flat pool can have a MAD of zero, which would make every hundredth of
+//! a foot infinitely anomalous. The scale is floored per parameter (see
+//! `scale_floor`) so quiet periods don't turn into false alarms.
+
+use chrono::{DateTime, Utc};
+
+/// History considered for each score, in hours
+pub const ANOMALY_WINDOW_HOURS: i64 = 72;
+
+/// Minimum history samples before a score is produced
+pub const MIN_HISTORY_SAMPLES: usize = 12;
+
+/// |robust_z| at or above this marks the reading suspect
+pub const SUSPECT_Z: f64 = 6.0;
+
+/// Scales MAD to the standard deviation of a normal distribution
+pub(crate) const MAD_TO_SIGMA: f64 = 1.4826;
+
+/// Score for one reading against its station's recent history
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnomalyScore {
+    pub site_code: String,
+    pub parameter_code: String,
+    pub reading_time: DateTime<Utc>,
+    pub value: f64,
+    pub window_median: f64,
+    pub window_mad: f64,
+    pub robust_z: f64,
+    pub sample_count: i32,
+    pub suspect: bool,
+}
+
+/// Median of `values` (sorted in place). None when empty.
+pub fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) / 2.0)
+    } else {
+        Some(values[mid])
+    }
+}
+
+/// Median absolute deviation from `center`
+pub fn mad(values: &[f64], center: f64) -> Option<f64> {
+    let mut deviations: Vec<f64> = values.iter().map(|v| (v - center).abs()).collect();
+    median(&mut deviations)
+}
+
+/// Smallest scale (sigma units) used for a parameter.
+///
+/// Stage: 0.05 ft. Discharge: 1% of the median flow (at least 1 cfs).
+/// Anything else: 1% of the median magnitude.
+fn scale_floor(parameter_code: &str, median: f64) -> f64 {
+    match parameter_code {
+        "00065" => 0.05,
+        "00060" => (median.abs() * 0.01).max(1.0),
+        _ => (median.abs() * 0.01).max(1e-6),
+    }
+}
+
+/// Score `value` against `history` (prior readings, any order).
+///
+/// Returns None until `MIN_HISTORY_SAMPLES` readings are available.
+pub fn score_reading(
+    site_code: &str,
+    parameter_code: &str,
+    reading_time: DateTime<Utc>,
+    value: f64,
+    history: &[f64],
+) -> Option<AnomalyScore> {
+    if history.len() < MIN_HISTORY_SAMPLES {
+        return None;
+    }
+    let mut sorted = history.to_vec();
+    let center = median(&mut sorted)?;
+    let deviation = mad(history, center)?;
+    let scale = (MAD_TO_SIGMA * deviation).max(scale_floor(parameter_code, center));
+    let robust_z = (value - center) / scale;
+
+    Some(AnomalyScore {
+        site_code: site_code.to_string(),
+        parameter_code: parameter_code.to_string(),
+        reading_time,
+        value,
+        window_median: center,
+        window_mad: deviation,
+        robust_z,
+        sample_count: history.len() as i32,
+        suspect: robust_z.abs() >= SUSPECT_Z,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn t() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()
+    }
+
+    /// Gently rising stage, 15-minute readings
+    fn rising_stage() -> Vec<f64> {
+        (0..48).map(|i| 14.0 + i as f64 * 0.01).collect()
+    }
+
+    #[test]
+    fn test_median_and_mad() {
+        let mut odd = vec![3.0, 1.0, 2.0];
+        assert_eq!(median(&mut odd), Some(2.0));
+        let mut even = vec![4.0, 1.0, 3.0, 2.0];
+        assert_eq!(median(&mut even), Some(2.5));
+        assert_eq!(median(&mut []), None);
+        assert_eq!(mad(&[1.0, 2.0, 3.0, 4.0, 100.0], 3.0), Some(1.0));
+    }
+
+    #[test]
+    fn test_plausible_reading_is_not_suspect() {
+        let score = score_reading("05567500", "00065", t(), 14.5, &rising_stage()).unwrap();
+        assert!(!score.suspect);
+        assert!(score.robust_z.abs() < 2.0);
+        assert_eq!(score.sample_count, 48);
+    }
+
+    #[test]
+    fn test_spike_is_suspect() {
+        // Transducer glitch: 14 ft river reported at 41 ft
+        let score = score_reading("05567500", "00065", t(), 41.0, &rising_stage()).unwrap();
+        assert!(score.suspect);
+        assert!(score.robust_z > SUSPECT_Z);
+    }
+
+    #[test]
+    fn test_outliers_in_history_do_not_mask_next_outlier() {
+        let mut history = rising_stage();
+        history[10] = 41.0;
+        history[20] = -5.0;
+        let score = score_reading("05567500", "00065", t(), 41.0, &history).unwrap();
+        assert!(score.suspect);
+    }
+
+    #[test]
+    fn test_flat_history_uses_scale_floor() {
+        let history = vec![440.0; 24];
+        // Pool held flat; a 0.1 ft change is two floor-sigmas, not infinity
+        let score = score_reading("LaGrange", "00065", t(), 440.1, &history).unwrap();
+        assert_eq!(score.window_mad, 0.0);
+        assert!(score.robust_z.is_finite());
+        assert!(!score.suspect);
+    }
+
+    #[test]
+    fn test_requires_minimum_history() {
+        let history = vec![14.0; MIN_HISTORY_SAMPLES - 1];
+        assert!(score_reading("05567500", "00065", t(), 14.0, &history).is_none());
+    }
+}
@@ -0,0 +1,197 @@
+//! Mississippi backwater on the LaGrange and Peoria pools.
+//!
+//! A dam holds its pool above its tailwater; the head across it (pool
+//! minus tailwater elevation) is what lets the pool drain downstream. When
+//! the Mississippi rises the Illinois cannot fall away below LaGrange, the
+//! tailwater climbs toward the pool and the head collapses: the dam is
+//! drowned and the river level is set from downstream. The same happens at
+//! Peoria once Grafton is high enough for the backwater to reach that far.
+//!
+//! Each cycle the daemon assesses the latest Grafton stage, the LaGrange
+//! and Peoria pool and tailwater elevations, and the Peoria-Kingston Mines
+//! slope (see `slope`), stores the result in
+//! `flood_analysis.backwater_status`, and alerts the Peoria zones when the
+//! backwater starts affecting the Peoria reach.
+
+use crate::alert::thresholds::{FloodAlert, FloodSeverity};
+use crate::analysis::slope::{self, SlopeTrend};
+
+/// CWMS location of the Mississippi stage driving the backwater
+pub const GRAFTON_LOCATION: &str = "Grafton";
+
+/// CWMS pool locations; their tailwaters are matched by prefix
+pub const LAGRANGE_POOL: &str = "LaGrange-Pool";
+pub const PEORIA_POOL: &str = "Peoria-Pool";
+
+/// Hours after which a CWMS value is too old to assess
+pub const MAX_AGE_HOURS: i64 = 6;
+
+/// Grafton stage above which backwater is felt at Peoria, ft
+pub const PEORIA_REACH_GRAFTON_FT: f64 = 25.0;
+
+/// Head across a dam below which its tailwater controls the pool, ft
+pub const DROWNED_HEAD_FT: f64 = 1.0;
+
+/// Latest values the assessment is made from; any may be missing
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackwaterInputs {
+    pub grafton_stage_ft: Option<f64>,
+    pub lagrange_pool_ft: Option<f64>,
+    pub lagrange_tailwater_ft: Option<f64>,
+    pub peoria_pool_ft: Option<f64>,
+    pub peoria_tailwater_ft: Option<f64>,
+    /// Peoria-Kingston Mines slope, ft/mile, and its trend
+    pub slope: Option<(f64, Option<SlopeTrend>)>,
+}
+
+impl BackwaterInputs {
+    pub fn is_empty(&self) -> bool {
+        *self == BackwaterInputs::default()
+    }
+
+    /// LaGrange pool minus tailwater
+    pub fn lagrange_head_ft(&self) -> Option<f64> {
+        Some(self.lagrange_pool_ft? - self.lagrange_tailwater_ft?)
+    }
+
+    /// Peoria pool minus tailwater
+    pub fn peoria_head_ft(&self) -> Option<f64> {
+        Some(self.peoria_pool_ft? - self.peoria_tailwater_ft?)
+    }
+}
+
+/// Outcome of one assessment
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackwaterStatus {
+    /// "LOW" .. "CRITICAL", or "UNKNOWN" without Grafton and LaGrange
+    pub risk_level: String,
+    /// "LOW", "MEDIUM" or "HIGH" (see `slope::combine_with_backwater`)
+    pub confidence: &'static str,
+    pub affecting_peoria: bool,
+}
+
+/// Backwater risk from the Grafton stage and the LaGrange head alone
+pub fn pool_risk(grafton_stage_ft: Option<f64>, lagrange_head_ft: Option<f64>) -> &'static str {
+    match (grafton_stage_ft, lagrange_head_ft) {
+        (Some(grafton), Some(head)) => {
+            if grafton > 25.0 && head < 0.5 {
+                "CRITICAL"
+            } else if grafton > 20.0 && head < 1.0 {
+                "HIGH"
+            } else if grafton > 18.0 || head < 2.0 {
+                "MODERATE"
+            } else {
+                "LOW"
+            }
+        }
+        _ => "UNKNOWN",
+    }
+}
+
+/// Assess the backwater. It affects the Peoria reach when the risk is HIGH
+/// or CRITICAL, the slope does not contradict it, and either Grafton is
+/// high enough for the backwater to reach Peoria or the Peoria dam is
+/// itself drowned.
+pub fn assess(inputs: &BackwaterInputs) -> BackwaterStatus {
+    let risk = pool_risk(inputs.grafton_stage_ft, inputs.lagrange_head_ft());
+    let (risk_level, confidence) = slope::combine_with_backwater(
+        risk,
+        inputs.slope.and_then(|(value, trend)| Some((value, trend?))),
+    );
+    let reaches_peoria = inputs.grafton_stage_ft.is_some_and(|g| g >= PEORIA_REACH_GRAFTON_FT)
+        || inputs.peoria_head_ft().is_some_and(|h| h < DROWNED_HEAD_FT);
+    let affecting_peoria = matches!(risk_level.as_str(), "HIGH" | "CRITICAL")
+        && confidence != "LOW"
+        && reaches_peoria;
+    BackwaterStatus { risk_level, confidence, affecting_peoria }
+}
+
+/// Alert for the Peoria zones when the backwater reaches them
+pub fn alert(inputs: &BackwaterInputs, status: &BackwaterStatus) -> FloodAlert {
+    let feet = |value: Option<f64>| value.map(|v| format!("{:.1} ft", v)).unwrap_or_else(|| "n/a".to_string());
+    let mut message = format!(
+        "[backwater] Mississippi backwater is affecting the Peoria reach: risk {} (confidence {}); \
+         Grafton {}, LaGrange head {}, Peoria head {}",
+        status.risk_level,
+        status.confidence,
+        feet(inputs.grafton_stage_ft),
+        feet(inputs.lagrange_head_ft()),
+        feet(inputs.peoria_head_ft()),
+    );
+    if let Some((value, trend)) = inputs.slope {
+        message.push_str(&format!(", Peoria-Kingston Mines slope {:.3} ft/mi", value));
+        if let Some(trend) = trend {
+            message.push_str(&format!(" ({})", trend.as_str()));
+        }
+    }
+    FloodAlert {
+        severity: if status.risk_level == "CRITICAL" { FloodSeverity::Flood } else { FloodSeverity::Action },
+        message,
+        registry_version: None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(grafton: f64, lagrange_head: f64, peoria_head: f64) -> BackwaterInputs {
+        BackwaterInputs {
+            grafton_stage_ft: Some(grafton),
+            lagrange_pool_ft: Some(430.0),
+            lagrange_tailwater_ft: Some(430.0 - lagrange_head),
+            peoria_pool_ft: Some(447.0),
+            peoria_tailwater_ft: Some(447.0 - peoria_head),
+            slope: None,
+        }
+    }
+
+    #[test]
+    fn test_drowned_lagrange_with_high_grafton_affects_peoria() {
+        let status = assess(&inputs(26.0, 0.3, 3.0));
+        assert_eq!(status.risk_level, "CRITICAL");
+        assert!(status.affecting_peoria);
+        assert_eq!(alert(&inputs(26.0, 0.3, 3.0), &status).severity, FloodSeverity::Flood);
+
+        // High risk at LaGrange, but Grafton too low to reach Peoria
+        let status = assess(&inputs(22.0, 0.8, 3.0));
+        assert_eq!(status.risk_level, "HIGH");
+        assert!(!status.affecting_peoria);
+
+        // ... unless the Peoria dam is drowned as well
+        let status = assess(&inputs(22.0, 0.8, 0.6));
+        assert!(status.affecting_peoria);
+        assert_eq!(alert(&inputs(22.0, 0.8, 0.6), &status).severity, FloodSeverity::Action);
+    }
+
+    #[test]
+    fn test_slope_confirms_or_contradicts() {
+        // A flattening slope lifts MODERATE to HIGH
+        let mut flattening = inputs(25.5, 1.5, 0.8);
+        assert_eq!(assess(&flattening).risk_level, "MODERATE");
+        flattening.slope = Some((0.05, Some(SlopeTrend::Flattening)));
+        let status = assess(&flattening);
+        assert_eq!((status.risk_level.as_str(), status.confidence), ("HIGH", "HIGH"));
+        assert!(status.affecting_peoria);
+        assert!(alert(&flattening, &status).message.contains("slope 0.050 ft/mi (flattening)"));
+
+        // A steepening slope says the river is still draining freely
+        let mut steepening = inputs(26.0, 0.8, 3.0);
+        steepening.slope = Some((0.4, Some(SlopeTrend::Steepening)));
+        let status = assess(&steepening);
+        assert_eq!(status.confidence, "LOW");
+        assert!(!status.affecting_peoria);
+    }
+
+    #[test]
+    fn test_missing_inputs_are_unknown() {
+        assert!(BackwaterInputs::default().is_empty());
+        let status = assess(&BackwaterInputs { grafton_stage_ft: Some(30.0), ..Default::default() });
+        assert_eq!(status.risk_level, "UNKNOWN");
+        assert!(!status.affecting_peoria);
+    }
+}
@@ -0,0 +1,203 @@
+//! Stage datum shift detection.
+//!
+//! A gauge that is re-datumed, or whose transducer gets bumped, reports a
+//! step in stage that the river never made. It wrecks trends and
+//! projections until someone applies a correction, and nothing about any
+//! single reading looks wrong. The signature is:
+//!
+//! - **abrupt**: one interval carries a jump of at least `MIN_STEP_FT` and
+//!   `STEP_SIGMA` times the series' usual interval-to-interval change
+//!   (scaled MAD of first differences);
+//! - **persistent**: the median of the `AFTER_SAMPLES` readings from the
+//!   jump on sits at least `PERSIST_FRACTION` of the jump away from the
+//!   median of the `BEFORE_SAMPLES` readings before it;
+//! - **unmatched by discharge**: discharge at the same timestamps changes by
+//!   less than `MIN_MATCHING_Q_PER_FT` of its value per foot of step. A real
+//!   rise of a few tenths of a foot moves discharge by several percent; a
+//!   shifted stage sensor leaves index-velocity discharge where it was.
+//!
+//! Sites without discharge are not checked, since the last condition can't
+//! be evaluated. A detection is a candidate for manual review
+//! (`data_quality`), never an automatic correction.
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use crate::analysis::anomaly::{MAD_TO_SIGMA, mad, median};
+
+/// Smallest step considered, ft
+pub const MIN_STEP_FT: f64 = 0.2;
+
+/// Jump size relative to typical interval change
+pub const STEP_SIGMA: f64 = 8.0;
+
+/// Readings compared on each side of the jump
+pub const BEFORE_SAMPLES: usize = 8;
+pub const AFTER_SAMPLES: usize = 8;
+
+/// Share of the jump that must remain in the after-window median
+pub const PERSIST_FRACTION: f64 = 0.8;
+
+/// Relative discharge change per foot of stage that counts as a real rise
+pub const MIN_MATCHING_Q_PER_FT: f64 = 0.05;
+
+/// Hours of history the daemon checks each cycle
+pub const WINDOW_HOURS: i64 = 12;
+
+/// Floor on the typical interval change, ft
+const NOISE_FLOOR_FT: f64 = 0.01;
+
+/// A suspected datum shift
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatumShift {
+    pub site_code: String,
+    /// First reading on the new datum
+    pub shift_time: DateTime<Utc>,
+    /// Single-interval jump, ft
+    pub step_ft: f64,
+    pub stage_before_ft: f64,
+    pub stage_after_ft: f64,
+    pub discharge_before_cfs: f64,
+    pub discharge_after_cfs: f64,
+    /// Typical interval change the jump was compared with, ft
+    pub noise_ft: f64,
+}
+
+impl DatumShift {
+    /// Relative discharge change across the shift
+    pub fn discharge_change(&self) -> f64 {
+        (self.discharge_after_cfs - self.discharge_before_cfs) / self.discharge_before_cfs
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "Possible datum shift at {}: stage stepped {:+.2} ft ({:.2} -> {:.2} ft median, {:.0}x typical change) \
+             while discharge changed {:+.1}% ({:.0} -> {:.0} cfs)",
+            self.shift_time.to_rfc3339(), self.step_ft, self.stage_before_ft, self.stage_after_ft,
+            self.step_ft.abs() / self.noise_ft, self.discharge_change() * 100.0,
+            self.discharge_before_cfs, self.discharge_after_cfs,
+        )
+    }
+}
+
+/// Look for a datum shift in `stage` (time-ordered) using `discharge` at
+/// the same timestamps. Returns the largest qualifying jump.
+pub fn detect(
+    site_code: &str,
+    stage: &[(DateTime<Utc>, f64)],
+    discharge: &[(DateTime<Utc>, f64)],
+) -> Option<DatumShift> {
+    if stage.len() < BEFORE_SAMPLES + AFTER_SAMPLES {
+        return None;
+    }
+    let flow: HashMap<DateTime<Utc>, f64> = discharge.iter().copied().collect();
+
+    let diffs: Vec<f64> = stage.windows(2).map(|w| w[1].1 - w[0].1).collect();
+    let mut sorted = diffs.clone();
+    let center = median(&mut sorted)?;
+    let noise = (mad(&diffs, center)? * MAD_TO_SIGMA).max(NOISE_FLOOR_FT);
+
+    (BEFORE_SAMPLES..=stage.len() - AFTER_SAMPLES)
+        .filter_map(|k| {
+            let jump = stage[k].1 - stage[k - 1].1;
+            if jump.abs() < MIN_STEP_FT || jump.abs() < STEP_SIGMA * noise {
+                return None;
+            }
+            let before = &stage[k - BEFORE_SAMPLES..k];
+            let after = &stage[k..k + AFTER_SAMPLES];
+            let stage_before = median(&mut before.iter().map(|s| s.1).collect::<Vec<_>>())?;
+            let stage_after = median(&mut after.iter().map(|s| s.1).collect::<Vec<_>>())?;
+            let persisted = (stage_after - stage_before) * jump.signum() >= PERSIST_FRACTION * jump.abs();
+            if !persisted {
+                return None;
+            }
+
+            let mut q_before: Vec<f64> = before.iter().filter_map(|s| flow.get(&s.0).copied()).collect();
+            let mut q_after: Vec<f64> = after.iter().filter_map(|s| flow.get(&s.0).copied()).collect();
+            if q_before.len() < BEFORE_SAMPLES / 2 || q_after.len() < AFTER_SAMPLES / 2 {
+                return None;
+            }
+            let (q_before, q_after) = (median(&mut q_before)?, median(&mut q_after)?);
+            if q_before <= 0.0 {
+                return None;
+            }
+            let matched = ((q_after - q_before) / q_before).abs() >= MIN_MATCHING_Q_PER_FT * jump.abs();
+            (!matched).then(|| DatumShift {
+                site_code: site_code.to_string(),
+                shift_time: stage[k].0,
+                step_ft: jump,
+                stage_before_ft: stage_before,
+                stage_after_ft: stage_after,
+                discharge_before_cfs: q_before,
+                discharge_after_cfs: q_after,
+                noise_ft: noise,
+            })
+        })
+        .max_by(|a, b| a.step_ft.abs().total_cmp(&b.step_ft.abs()))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn t(i: usize) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap() + Duration::minutes(15 * i as i64)
+    }
+
+    type Series = Vec<(DateTime<Utc>, f64)>;
+
+    /// 24 readings of a slowly rising river with a little noise
+    fn series(stage_at: impl Fn(usize) -> f64, flow_at: impl Fn(usize) -> f64) -> (Series, Series) {
+        let wobble = |i: usize| [0.0, 0.01, -0.01, 0.005][i % 4];
+        (
+            (0..24).map(|i| (t(i), stage_at(i) + wobble(i))).collect(),
+            (0..24).map(|i| (t(i), flow_at(i))).collect(),
+        )
+    }
+
+    #[test]
+    fn test_bumped_transducer_is_flagged() {
+        // +0.45 ft at 03:00 while discharge keeps its gentle trend
+        let (stage, flow) = series(
+            |i| 12.0 + 0.005 * i as f64 + if i >= 12 { 0.45 } else { 0.0 },
+            |i| 18_000.0 + 10.0 * i as f64,
+        );
+        let shift = detect("05567500", &stage, &flow).unwrap();
+        assert_eq!(shift.shift_time, t(12));
+        assert!((shift.step_ft - 0.45).abs() < 0.03);
+        assert!(shift.discharge_change().abs() < 0.01);
+        assert!(shift.describe().starts_with("Possible datum shift at 2024-05-01T03:00:00+00:00"));
+    }
+
+    #[test]
+    fn test_real_rise_with_discharge_is_not_flagged() {
+        // Same stage step, but discharge rises 6% with it
+        let (stage, flow) = series(
+            |i| 12.0 + if i >= 12 { 0.45 } else { 0.0 },
+            |i| if i >= 12 { 19_080.0 } else { 18_000.0 },
+        );
+        assert!(detect("05567500", &stage, &flow).is_none());
+    }
+
+    #[test]
+    fn test_spike_that_returns_is_not_a_shift() {
+        let (stage, flow) = series(|i| 12.0 + if (12..14).contains(&i) { 0.5 } else { 0.0 }, |_| 18_000.0);
+        assert!(detect("05567500", &stage, &flow).is_none());
+    }
+
+    #[test]
+    fn test_steady_rise_and_missing_discharge() {
+        // 0.1 ft per interval is fast but smooth: no single jump stands out
+        let (stage, flow) = series(|i| 12.0 + 0.1 * i as f64, |_| 18_000.0);
+        assert!(detect("05567500", &stage, &flow).is_none());
+
+        let (stage, _) = series(|i| 12.0 + if i >= 12 { 0.45 } else { 0.0 }, |_| 18_000.0);
+        assert!(detect("05567500", &stage, &[]).is_none());
+        assert!(detect("05567500", &stage[..10], &[]).is_none());
+    }
+}
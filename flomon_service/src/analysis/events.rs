@@ -0,0 +1,267 @@
+//! Flood event segmentation from stored stage series.
+//!
+//! A gauge's stage history is cut into events: the first reading at or
+//! above action stage starts one, the highest reading is its crest, and the
+//! first reading back below action stage ends it. A dip below action stage
+//! that rises again within `MERGE_HOURS` is one event with a wobble, not
+//! two events. Severity follows the crest:
+//!
+//! ```text
+//! stage  ^              crest (moderate)
+//!        |             /\
+//! action +- - - - - - /  \/\ - - - - - -   dip shorter than MERGE_HOURS
+//!        |           /      \
+//!        +----------start----end--------> time
+//! ```
+//!
+//! `flomon event detect` runs the scan over the stored (hot + archived)
+//! readings and writes each event to `flood_analysis.events` with
+//! `detected_by = 'stage_scan'`, keyed by site and start so a rescan
+//! refreshes the crest and end of an event still in progress. Catalogued
+//! events for the same site and time are left alone and the detected one is
+//! skipped (sql/036_flood_event_detection.sql).
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+use serde::Serialize;
+
+use crate::archive;
+use crate::model::{FloodThresholds, PARAM_STAGE};
+use crate::stations::Station;
+
+/// A dip below action stage shorter than this doesn't end the event
+pub const MERGE_HOURS: i64 = 24;
+
+/// `flood_analysis.events.detected_by` of rows written here
+pub const DETECTED_BY: &str = "stage_scan";
+
+/// One event found in a stage series
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DetectedEvent {
+    pub site_code: String,
+    pub start: DateTime<Utc>,
+    pub crest_time: DateTime<Utc>,
+    pub crest_stage_ft: f64,
+    /// First reading back below action stage; None while still above
+    pub end: Option<DateTime<Utc>>,
+    /// "action", "minor", "moderate" or "major", from the crest
+    pub severity: &'static str,
+}
+
+impl DetectedEvent {
+    pub fn duration_hours(&self) -> Option<f64> {
+        self.end.map(|end| (end - self.start).num_minutes() as f64 / 60.0)
+    }
+}
+
+/// `flood_analysis.events` severity of a crest that reached action stage
+pub fn severity_of(crest_ft: f64, thresholds: &FloodThresholds) -> &'static str {
+    if crest_ft >= thresholds.major_flood_stage_ft {
+        "major"
+    } else if crest_ft >= thresholds.moderate_flood_stage_ft {
+        "moderate"
+    } else if crest_ft >= thresholds.flood_stage_ft {
+        "minor"
+    } else {
+        "action"
+    }
+}
+
+/// Event being followed through the series
+struct Open {
+    start: DateTime<Utc>,
+    crest_time: DateTime<Utc>,
+    crest: f64,
+    below_since: Option<DateTime<Utc>>,
+}
+
+/// Events in time-ordered `readings`
+pub fn detect(site_code: &str, readings: &[(DateTime<Utc>, f64)], thresholds: &FloodThresholds) -> Vec<DetectedEvent> {
+    let merge = Duration::hours(MERGE_HOURS);
+    let close = |open: Open, end: Option<DateTime<Utc>>| DetectedEvent {
+        site_code: site_code.to_string(),
+        start: open.start,
+        crest_time: open.crest_time,
+        crest_stage_ft: open.crest,
+        end,
+        severity: severity_of(open.crest, thresholds),
+    };
+
+    let mut events = Vec::new();
+    let mut current: Option<Open> = None;
+    for &(at, stage) in readings {
+        if stage >= thresholds.action_stage_ft {
+            match current.as_mut() {
+                Some(open) => {
+                    open.below_since = None;
+                    if stage > open.crest {
+                        open.crest = stage;
+                        open.crest_time = at;
+                    }
+                }
+                None => current = Some(Open { start: at, crest_time: at, crest: stage, below_since: None }),
+            }
+            continue;
+        }
+        let Some(open) = current.as_mut() else { continue };
+        match open.below_since {
+            None => open.below_since = Some(at),
+            Some(since) if at - since >= merge => {
+                events.push(close(current.take().unwrap(), Some(since)));
+            }
+            Some(_) => {}
+        }
+    }
+    if let Some(open) = current {
+        let end = open.below_since;
+        events.push(close(open, end));
+    }
+    events
+}
+
+/// Scan `station`'s stage readings in `[start, end]`
+pub fn scan(client: &mut Client, station: &Station, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<DetectedEvent>, String> {
+    let Some(thresholds) = &station.thresholds else {
+        return Ok(Vec::new());
+    };
+    let readings: Vec<(DateTime<Utc>, f64)> = archive::readings_between(client, &station.site_code, PARAM_STAGE, start, end)?
+        .iter()
+        .map(|r| (r.datetime.with_timezone(&Utc), r.value))
+        .collect();
+    Ok(detect(&station.site_code, &readings, thresholds))
+}
+
+/// Insert or refresh detected events; returns how many were written
+/// (events overlapping a catalogued event are skipped)
+pub fn store(client: &mut Client, events: &[DetectedEvent], thresholds: &FloodThresholds) -> Result<usize, String> {
+    let mut written = 0;
+    for event in events {
+        written += client.execute(
+            "INSERT INTO flood_analysis.events
+             (site_code, event_start, event_peak, event_end, severity, peak_stage_ft, flood_stage_ft,
+              detected_by, analysis_version, analyzed_at)
+             SELECT $1, $2, $3, $4, $5, $6::FLOAT8, $7::FLOAT8, $8, $9, NOW()
+             WHERE NOT EXISTS (
+                 SELECT 1 FROM flood_analysis.events
+                 WHERE site_code = $1 AND detected_by IS DISTINCT FROM $8
+                   AND event_start <= $3 AND COALESCE(event_end, 'infinity') >= $2)
+             ON CONFLICT (site_code, event_start) WHERE detected_by = 'stage_scan' DO UPDATE
+             SET event_peak = EXCLUDED.event_peak,
+                 event_end = EXCLUDED.event_end,
+                 severity = EXCLUDED.severity,
+                 peak_stage_ft = EXCLUDED.peak_stage_ft,
+                 flood_stage_ft = EXCLUDED.flood_stage_ft,
+                 analysis_version = EXCLUDED.analysis_version,
+                 analyzed_at = EXCLUDED.analyzed_at",
+            &[
+                &event.site_code,
+                &event.start,
+                &event.crest_time,
+                &event.end,
+                &event.severity,
+                &event.crest_stage_ft,
+                &thresholds.flood_stage_ft,
+                &DETECTED_BY,
+                &env!("CARGO_PKG_VERSION"),
+            ],
+        ).map_err(|e| format!("Failed to store event at {} from {}: {}", event.site_code, event.start, e))? as usize;
+    }
+    Ok(written)
+}
+
+/// A stored event, detected or catalogued
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StoredEvent {
+    pub id: i32,
+    pub site_code: String,
+    pub start: DateTime<Utc>,
+    pub crest_time: DateTime<Utc>,
+    pub crest_stage_ft: Option<f64>,
+    pub end: Option<DateTime<Utc>>,
+    pub duration_hours: Option<f64>,
+    pub severity: String,
+    pub detected_by: Option<String>,
+}
+
+/// Stored events starting at or after `since`, optionally for one site,
+/// newest first
+pub fn list(client: &mut Client, site_code: Option<&str>, since: DateTime<Utc>) -> Result<Vec<StoredEvent>, String> {
+    let rows = client.query(
+        "SELECT id, site_code, event_start, event_peak, peak_stage_ft::FLOAT8, event_end,
+                duration_hours::FLOAT8, severity, detected_by
+         FROM flood_analysis.events
+         WHERE event_start >= $1 AND ($2::TEXT IS NULL OR site_code = $2)
+         ORDER BY event_start DESC",
+        &[&since, &site_code],
+    ).map_err(|e| format!("Failed to list flood events: {}", e))?;
+    Ok(rows.iter().map(|row| StoredEvent {
+        id: row.get(0),
+        site_code: row.get(1),
+        start: row.get(2),
+        crest_time: row.get(3),
+        crest_stage_ft: row.get(4),
+        end: row.get(5),
+        duration_hours: row.get(6),
+        severity: row.get(7),
+        detected_by: row.get(8),
+    }).collect())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn thresholds() -> FloodThresholds {
+        FloodThresholds {
+            action_stage_ft: 14.0,
+            flood_stage_ft: 16.0,
+            moderate_flood_stage_ft: 20.0,
+            major_flood_stage_ft: 24.0,
+        }
+    }
+
+    fn series(stages: &[f64]) -> Vec<(DateTime<Utc>, f64)> {
+        let t0 = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        stages.iter().enumerate().map(|(i, &s)| (t0 + Duration::hours(6 * i as i64), s)).collect()
+    }
+
+    #[test]
+    fn test_rise_crest_recession_is_one_event() {
+        let readings = series(&[12.0, 14.5, 18.0, 21.0, 19.0, 15.0, 13.0, 12.0]);
+        let events = detect("05568500", &readings, &thresholds());
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.start, readings[1].0);
+        assert_eq!((event.crest_time, event.crest_stage_ft), readings[3]);
+        assert_eq!(event.end, Some(readings[6].0));
+        assert_eq!(event.severity, "moderate");
+        assert_eq!(event.duration_hours(), Some(30.0));
+    }
+
+    #[test]
+    fn test_short_dip_merges_and_long_recession_splits() {
+        // Dip of 12 h (two readings below) stays one event
+        let merged = detect("s", &series(&[15.0, 13.0, 13.5, 17.0, 12.0, 12.0, 12.0, 12.0, 12.0]), &thresholds());
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].severity, "minor");
+        assert_eq!(merged[0].end, Some(series(&[0.0; 5])[4].0));
+
+        // Below for a day and more: two events
+        let split = detect("s", &series(&[15.0, 13.0, 13.0, 13.0, 13.0, 13.0, 14.2, 14.0]), &thresholds());
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[0].severity, "action");
+        assert_eq!(split[1].end, None, "still above action stage at the end of the series");
+        assert_eq!(split[1].duration_hours(), None);
+    }
+
+    #[test]
+    fn test_no_event_below_action_stage() {
+        assert!(detect("s", &series(&[10.0, 13.9, 12.0]), &thresholds()).is_empty());
+        assert_eq!(severity_of(25.0, &thresholds()), "major");
+    }
+}
@@ -0,0 +1,177 @@
+//! Property freeboard: water surface elevation vs critical property elevations.
+//!
+//! Freeboard is the vertical distance between the water surface and a point
+//! that matters (a road low point, a first floor, a well head). It is the
+//! number a property owner actually acts on, so it is derived once at ingest
+//! and persisted to `flood_analysis.freeboard` as its own timeseries rather
+//! than recomputed by every consumer.
+//!
+//! # Datum
+//! The water surface comes from the Peoria pool elevation (CWMS, NGVD29;
+//! a source location on another datum is converted with its
+//! `to_ngvd29_ft` offset, see `datum`). Critical elevations must be
+//! surveyed in NGVD29; stage readings (height above a gauge datum) cannot
+//! be used directly.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::ingest::cwms::CwmsTimeseries;
+
+/// Default freeboard below which an alert is raised
+pub const DEFAULT_ALERT_BELOW_FT: f64 = 2.0;
+
+/// `[freeboard]` section of flomon.toml
+#[derive(Debug, Clone, Deserialize)]
+pub struct FreeboardSettings {
+    /// CWMS location whose pool elevation is the water surface (e.g. "Peoria-Pool")
+    pub source_location: String,
+
+    /// Alert when freeboard at any point drops below this many feet
+    #[serde(default = "default_alert_below_ft")]
+    pub alert_below_ft: f64,
+
+    #[serde(default)]
+    pub critical_elevations: Vec<CriticalElevation>,
+}
+
+fn default_alert_below_ft() -> f64 {
+    DEFAULT_ALERT_BELOW_FT
+}
+
+/// A surveyed point whose flooding matters
+#[derive(Debug, Clone, Deserialize)]
+pub struct CriticalElevation {
+    /// Short label, e.g. "Sunset Drive low point"
+    pub name: String,
+    /// Elevation in feet, same datum as the source water surface (NGVD29)
+    pub elevation_ft: f64,
+}
+
+/// One freeboard observation for one critical point
+#[derive(Debug, Clone, PartialEq)]
+pub struct FreeboardReading {
+    pub point_name: String,
+    pub timestamp: DateTime<Utc>,
+    pub water_surface_ft: f64,
+    pub critical_elevation_ft: f64,
+    /// Positive: water is below the point. Negative: point is underwater.
+    pub freeboard_ft: f64,
+    pub source_timeseries_id: String,
+}
+
+impl FreeboardReading {
+    /// True if freeboard is strictly below `alert_below_ft`
+    pub fn is_below(&self, alert_below_ft: f64) -> bool {
+        self.freeboard_ft < alert_below_ft
+    }
+}
+
+/// Compute freeboard for each critical point at each water surface sample.
+///
+/// Output is ordered by sample, then by point in configuration order.
+pub fn compute_freeboard(
+    water_surface: &[CwmsTimeseries],
+    points: &[CriticalElevation],
+) -> Vec<FreeboardReading> {
+    water_surface
+        .iter()
+        .flat_map(|sample| {
+            points.iter().map(move |point| FreeboardReading {
+                point_name: point.name.clone(),
+                timestamp: sample.timestamp,
+                water_surface_ft: sample.value,
+                critical_elevation_ft: point.elevation_ft,
+                freeboard_ft: point.elevation_ft - sample.value,
+                source_timeseries_id: sample.timeseries_id.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Latest reading per point that is below the alert threshold
+pub fn readings_below(
+    readings: &[FreeboardReading],
+    alert_below_ft: f64,
+) -> Vec<&FreeboardReading> {
+    let mut latest: Vec<&FreeboardReading> = Vec::new();
+    for reading in readings {
+        match latest.iter_mut().find(|r| r.point_name == reading.point_name) {
+            Some(existing) if existing.timestamp < reading.timestamp => *existing = reading,
+            Some(_) => {}
+            None => latest.push(reading),
+        }
+    }
+    latest.retain(|r| r.is_below(alert_below_ft));
+    latest
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn pool_sample(hour: u32, value: f64) -> CwmsTimeseries {
+        CwmsTimeseries {
+            timeseries_id: "Peoria-Pool.Elev.Inst.~1Hour.0.CBT-RAW".to_string(),
+            location_id: "Peoria-Pool".to_string(),
+            parameter_id: "Elev".to_string(),
+            timestamp: Utc.with_ymd_and_hms(2019, 6, 5, hour, 0, 0).unwrap(),
+            value,
+            unit: "ft".to_string(),
+            original_unit: None,
+            quality_code: 0,
+        }
+    }
+
+    fn points() -> Vec<CriticalElevation> {
+        vec![
+            CriticalElevation { name: "road".to_string(), elevation_ft: 452.0 },
+            CriticalElevation { name: "first floor".to_string(), elevation_ft: 458.5 },
+        ]
+    }
+
+    #[test]
+    fn test_freeboard_is_elevation_minus_water_surface() {
+        let readings = compute_freeboard(&[pool_sample(0, 449.25)], &points());
+
+        assert_eq!(readings.len(), 2);
+        assert_eq!(readings[0].point_name, "road");
+        assert!((readings[0].freeboard_ft - 2.75).abs() < 1e-9);
+        assert!((readings[1].freeboard_ft - 9.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_submerged_point_has_negative_freeboard() {
+        let readings = compute_freeboard(&[pool_sample(0, 453.0)], &points());
+        assert!(readings[0].freeboard_ft < 0.0);
+        assert!(readings[0].is_below(DEFAULT_ALERT_BELOW_FT));
+    }
+
+    #[test]
+    fn test_alert_threshold_is_strict() {
+        let readings = compute_freeboard(&[pool_sample(0, 450.0)], &points());
+        // road freeboard is exactly 2.0 ft: not below 2.0
+        assert!(!readings[0].is_below(2.0));
+    }
+
+    #[test]
+    fn test_readings_below_uses_latest_sample_per_point() {
+        // Water was high at 00:00 but receded by 01:00
+        let samples = [pool_sample(0, 451.0), pool_sample(1, 448.0)];
+        let readings = compute_freeboard(&samples, &points());
+        assert!(readings_below(&readings, 2.0).is_empty());
+
+        // Rising instead: only the road is within 2 ft at the latest sample
+        let samples = [pool_sample(0, 448.0), pool_sample(1, 450.5)];
+        let readings = compute_freeboard(&samples, &points());
+        let below = readings_below(&readings, 2.0);
+        assert_eq!(below.len(), 1);
+        assert_eq!(below[0].point_name, "road");
+        assert_eq!(below[0].timestamp.format("%H").to_string(), "01");
+    }
+}
@@ -0,0 +1,298 @@
+//! Flood frequency: return period of a discharge from the annual peaks.
+//!
+//! A Log-Pearson Type III distribution is fitted to the logarithms of a
+//! station's annual peak discharges (`ingest::usgs_peaks`) by the method
+//! of moments, in the manner of Bulletin 17C:
+//!
+//! - the systematic record only: historic peaks (peak code 7) and peaks
+//!   caused by a dam failure (code 3) are left out, as are zero flows;
+//! - low outliers below the Grubbs-Beck threshold (10% significance) are
+//!   dropped once and the moments recomputed;
+//! - station skew, without weighting toward a regional skew.
+//!
+//! Bulletin 17C proper uses the Expected Moments Algorithm with historic
+//! information, the multiple Grubbs-Beck test and a weighted skew, so
+//! these are approximations for context ("~10-year flow") and not design
+//! values. Frequency factors use the Wilson-Hilferty approximation, which
+//! is close to the tabulated values for skews between -2 and 2.
+
+use serde::Serialize;
+
+use crate::ingest::usgs_peaks::PeakFlowRecord;
+
+/// Fewest usable annual peaks a curve is fitted to
+pub const MIN_YEARS: usize = 10;
+
+/// Return periods beyond this are reported as beyond it; the record
+/// rarely supports extrapolating further
+pub const MAX_DESCRIBED_YEARS: f64 = 500.0;
+
+/// Peak codes whose discharge is not part of the systematic record:
+/// 3 (dam failure) and 7 (historic peak)
+const EXCLUDED_PEAK_CODES: [&str; 2] = ["3", "7"];
+
+/// Log-Pearson III fit to one station's annual peak discharges
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FrequencyCurve {
+    /// Mean of log10(discharge)
+    pub mean: f64,
+    /// Standard deviation of log10(discharge)
+    pub std_dev: f64,
+    /// Station skew of log10(discharge)
+    pub skew: f64,
+    /// Annual peaks fitted
+    pub years: usize,
+    /// Peaks dropped as low outliers
+    pub low_outliers: usize,
+    pub first_year: i32,
+    pub last_year: i32,
+}
+
+impl FrequencyCurve {
+    /// Fit the systematic annual peak discharges in `peaks`; None with
+    /// fewer than `MIN_YEARS` of them or no spread
+    pub fn fit(peaks: &[PeakFlowRecord]) -> Option<Self> {
+        let systematic: Vec<(i32, f64)> = peaks.iter()
+            .filter(|p| !p.peak_qualification_codes.iter().any(|c| EXCLUDED_PEAK_CODES.contains(&c.as_str())))
+            .filter_map(|p| p.peak_discharge_cfs.filter(|q| *q > 0.0).map(|q| (p.water_year, q.log10())))
+            .collect();
+        if systematic.len() < MIN_YEARS {
+            return None;
+        }
+        let logs: Vec<f64> = systematic.iter().map(|(_, x)| *x).collect();
+        let (mean, std_dev, _) = moments(&logs)?;
+
+        let threshold = mean - grubbs_beck_k(logs.len()) * std_dev;
+        let kept: Vec<(i32, f64)> = systematic.iter().copied().filter(|(_, x)| *x >= threshold).collect();
+        if kept.len() < MIN_YEARS {
+            return None;
+        }
+        let logs: Vec<f64> = kept.iter().map(|(_, x)| *x).collect();
+        let (mean, std_dev, skew) = moments(&logs)?;
+        Some(Self {
+            mean,
+            std_dev,
+            skew,
+            years: kept.len(),
+            low_outliers: systematic.len() - kept.len(),
+            first_year: kept.iter().map(|(year, _)| *year).min()?,
+            last_year: kept.iter().map(|(year, _)| *year).max()?,
+        })
+    }
+
+    /// Chance that the annual peak reaches `discharge_cfs` in any one year
+    pub fn annual_exceedance_probability(&self, discharge_cfs: f64) -> f64 {
+        if discharge_cfs <= 0.0 {
+            return 1.0;
+        }
+        let k = (discharge_cfs.log10() - self.mean) / self.std_dev;
+        1.0 - normal_cdf(standard_normal_deviate(k, self.skew))
+    }
+
+    /// Average years between annual peaks reaching `discharge_cfs`
+    pub fn return_period_years(&self, discharge_cfs: f64) -> f64 {
+        1.0 / self.annual_exceedance_probability(discharge_cfs).max(f64::MIN_POSITIVE)
+    }
+
+    /// Discharge with a return period of `years` (> 1), e.g. the 100-year flow
+    pub fn discharge_for(&self, years: f64) -> f64 {
+        let z = normal_quantile(1.0 - 1.0 / years);
+        10f64.powf(self.mean + frequency_factor(z, self.skew) * self.std_dev)
+    }
+
+    /// "~12-year flow (8.1% annual chance)"
+    pub fn describe(&self, discharge_cfs: f64) -> String {
+        let years = self.return_period_years(discharge_cfs);
+        if years < 2.0 {
+            return "below the 2-year flow".to_string();
+        }
+        if years > MAX_DESCRIBED_YEARS {
+            return format!("beyond the {:.0}-year flow", MAX_DESCRIBED_YEARS);
+        }
+        let chance = 100.0 * self.annual_exceedance_probability(discharge_cfs);
+        format!(
+            "~{}-year flow ({} annual chance)",
+            round_years(years),
+            if chance >= 10.0 { format!("{:.0}%", chance) } else { format!("{:.1}%", chance) },
+        )
+    }
+}
+
+/// Mean, sample standard deviation and sample skew; None without spread
+fn moments(values: &[f64]) -> Option<(f64, f64, f64)> {
+    let n = values.len() as f64;
+    if n < 3.0 {
+        return None;
+    }
+    let mean = values.iter().sum::<f64>() / n;
+    let sum_sq: f64 = values.iter().map(|x| (x - mean).powi(2)).sum();
+    let std_dev = (sum_sq / (n - 1.0)).sqrt();
+    if std_dev <= 0.0 {
+        return None;
+    }
+    let sum_cubed: f64 = values.iter().map(|x| (x - mean).powi(3)).sum();
+    let skew = n * sum_cubed / ((n - 1.0) * (n - 2.0) * std_dev.powi(3));
+    Some((mean, std_dev, skew))
+}
+
+/// Grubbs-Beck one-sided outlier test deviate at 10% significance for
+/// `n` values (the Bulletin 17B approximation)
+fn grubbs_beck_k(n: usize) -> f64 {
+    let log_n = (n as f64).log10();
+    -0.9043 + 3.345 * log_n.sqrt() - 0.4046 * log_n
+}
+
+/// Pearson III frequency factor for standard normal deviate `z` and skew
+/// `g` (Wilson-Hilferty)
+fn frequency_factor(z: f64, g: f64) -> f64 {
+    if g.abs() < 1e-6 {
+        return z;
+    }
+    (2.0 / g) * ((1.0 + g * z / 6.0 - g * g / 36.0).powi(3) - 1.0)
+}
+
+/// Inverse of `frequency_factor`: the standard normal deviate of
+/// frequency factor `k` at skew `g`
+fn standard_normal_deviate(k: f64, g: f64) -> f64 {
+    if g.abs() < 1e-6 {
+        return k;
+    }
+    (6.0 / g) * ((g * k / 2.0 + 1.0).cbrt() - 1.0) + g / 6.0
+}
+
+/// Standard normal CDF (complementary error function, Numerical Recipes
+/// `erfcc`, fractional error below 1.2e-7)
+fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.5 * x);
+    let erfc = t * (-x * x - 1.265_512_23
+        + t * (1.000_023_68 + t * (0.374_091_96 + t * (0.096_784_18 + t * (-0.186_288_06
+        + t * (0.278_868_07 + t * (-1.135_203_98 + t * (1.488_515_87
+        + t * (-0.822_152_23 + t * 0.170_872_77))))))))).exp();
+    if z >= 0.0 { 1.0 - 0.5 * erfc } else { 0.5 * erfc }
+}
+
+/// Standard normal quantile of `p` in (0, 1) (Acklam's rational
+/// approximation, relative error below 1.2e-9)
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [-3.969683028665376e1, 2.209460984245205e2, -2.759285104469687e2,
+        1.38357751867269e2, -3.066479806614716e1, 2.506628277459239];
+    const B: [f64; 5] = [-5.447609879822406e1, 1.615858368580409e2, -1.556989798598866e2,
+        6.680131188771972e1, -1.328068155288572e1];
+    const C: [f64; 6] = [-7.784894002430293e-3, -3.223964580411365e-1, -2.400758277161838,
+        -2.549732539343734, 4.374664141464968, 2.938163982698783];
+    const D: [f64; 4] = [7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996, 3.754408661907416];
+    const P_LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - P_LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+/// Whole years under 10, two significant figures above (12, 140)
+fn round_years(years: f64) -> String {
+    if years < 10.0 {
+        return format!("{:.0}", years);
+    }
+    let scale = 10f64.powi(years.log10().floor() as i32 - 1);
+    format!("{:.0}", (years / scale).round() * scale)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn peak(year: i32, discharge_cfs: f64) -> PeakFlowRecord {
+        PeakFlowRecord {
+            site_code: "05567500".to_string(),
+            peak_date: NaiveDate::from_ymd_opt(year, 4, 15).unwrap(),
+            peak_time: None,
+            peak_discharge_cfs: Some(discharge_cfs),
+            peak_qualification_codes: vec![],
+            gage_height_ft: None,
+            gage_height_qualification_codes: vec![],
+            water_year: year,
+            alternate_gage_height_ft: None,
+        }
+    }
+
+    /// Log-symmetric peaks: zero skew, mean log 4.5, so the curve is
+    /// log-normal and the quantiles are the normal ones
+    fn symmetric_peaks() -> Vec<PeakFlowRecord> {
+        [-2.0, -1.5, -1.0, -0.5, 0.0, 0.0, 0.5, 1.0, 1.5, 2.0, -0.25, 0.25]
+            .iter()
+            .enumerate()
+            .map(|(i, d)| peak(1990 + i as i32, 10f64.powf(4.5 + 0.1 * d)))
+            .collect()
+    }
+
+    #[test]
+    fn test_fit_recovers_normal_quantiles_at_zero_skew() {
+        let curve = FrequencyCurve::fit(&symmetric_peaks()).unwrap();
+        assert!((curve.mean - 4.5).abs() < 1e-9);
+        assert!(curve.skew.abs() < 1e-9);
+        assert_eq!((curve.years, curve.low_outliers), (12, 0));
+        assert_eq!((curve.first_year, curve.last_year), (1990, 2001));
+
+        // 100-year flow: 1% annual chance, z = 2.3263
+        let q100 = curve.discharge_for(100.0);
+        assert!((q100.log10() - (curve.mean + 2.3263 * curve.std_dev)).abs() < 1e-3);
+        assert!((curve.return_period_years(q100) - 100.0).abs() < 0.1);
+        // The median annual peak is the 2-year flow
+        assert!((curve.return_period_years(10f64.powf(4.5)) - 2.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_skew_moves_the_upper_tail() {
+        let mut skewed = symmetric_peaks();
+        skewed.push(peak(2002, 10f64.powf(4.5 + 0.5)));
+        let curve = FrequencyCurve::fit(&skewed).unwrap();
+        assert!(curve.skew > 0.5);
+        assert!((curve.return_period_years(curve.discharge_for(50.0)) - 50.0).abs() < 0.5);
+        assert!(curve.discharge_for(100.0) > curve.discharge_for(10.0));
+    }
+
+    #[test]
+    fn test_fit_leaves_out_historic_and_too_short_records() {
+        let mut peaks = symmetric_peaks();
+        let mut historic = peak(1844, 200_000.0);
+        historic.peak_qualification_codes = vec!["7".to_string()];
+        peaks.push(historic);
+        assert_eq!(FrequencyCurve::fit(&peaks).unwrap().years, 12);
+
+        assert!(FrequencyCurve::fit(&peaks[..9]).is_none());
+    }
+
+    #[test]
+    fn test_low_outlier_is_dropped() {
+        let mut peaks = symmetric_peaks();
+        peaks.push(peak(1988, 150.0));
+        let curve = FrequencyCurve::fit(&peaks).unwrap();
+        assert_eq!((curve.years, curve.low_outliers), (12, 1));
+    }
+
+    #[test]
+    fn test_describe() {
+        let curve = FrequencyCurve::fit(&symmetric_peaks()).unwrap();
+        assert_eq!(curve.describe(curve.discharge_for(10.0)), "~10-year flow (10% annual chance)");
+        assert_eq!(curve.describe(curve.discharge_for(137.0)), "~140-year flow (0.7% annual chance)");
+        assert_eq!(curve.describe(1000.0), "below the 2-year flow");
+        assert_eq!(curve.describe(1e9), "beyond the 500-year flow");
+    }
+}
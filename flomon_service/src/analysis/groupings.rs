@@ -1,20 +1,20 @@
-/// Site grouping and data organization utilities.
-///
-/// `group_by_site` takes the flat list of `GaugeReading`s produced by the
-/// ingest layer and organizes them into per-site `SiteReadings` structs,
-/// making it convenient to ask "what is the current stage at Kingston Mines?"
-/// without filtering a flat list every time.
-///
-/// `group_by_zone` organizes readings by hydrological zone from zones.toml,
-/// providing geographic context and lead time analysis for flood forecasting.
-///
-/// This module provides basic data organization helpers. Complex analysis
-/// such as trend detection, rate-of-rise calculations, and upstream correlation
-/// are handled by external Python scripts that operate on the curated database.
-///
-/// The integration test at the bottom of this module exercises the full
-/// parse → group → threshold-check pipeline, and lives here because
-/// grouping is the final step in that chain.
+//! Site grouping and data organization utilities.
+//!
+//! `group_by_site` takes the flat list of `GaugeReading`s produced by the
+//! ingest layer and organizes them into per-site `SiteReadings` structs,
+//! making it convenient to ask "what is the current stage at Kingston Mines?"
+//! without filtering a flat list every time.
+//!
+//! `group_by_zone` organizes readings by hydrological zone from zones.toml,
+//! providing geographic context and lead time analysis for flood forecasting.
+//!
+//! This module provides basic data organization helpers. Complex analysis
+//! such as trend detection, rate-of-rise calculations, and upstream correlation
+//! are handled by external Python scripts that operate on the curated database.
+//!
+//! The integration test at the bottom of this module exercises the full
+//! parse → group → threshold-check pipeline, and lives here because
+//! grouping is the final step in that chain.
 
 use std::collections::HashMap;
 
@@ -265,8 +265,9 @@ mod tests {
             parameter_code: "00065".to_string(),
             unit: "ft".to_string(),
             value: 12.0,
-            datetime: "2024-05-01T12:00:00.000-05:00".to_string(),
+            datetime: chrono::DateTime::parse_from_rfc3339("2024-05-01T12:00:00.000-05:00").unwrap(),
             qualifier: "P".to_string(),
+            original_unit: None,
         };
 
         let alert = check_flood_stage(&low_reading, &thresholds);
@@ -0,0 +1,264 @@
+//! Approximate inundation extent from a DEM sample grid.
+//!
+//! Each zone may have a ground-elevation grid, preprocessed offline from a
+//! DEM and exported as an ESRI ASCII grid (`.asc`, geographic coordinates,
+//! elevations in feet). Given a water surface elevation, every cell whose
+//! ground is below it is marked flooded, and the flooded cells are merged
+//! into rectangles and returned as one GeoJSON MultiPolygon. This is a
+//! "bathtub" fill: it ignores levees, culverts and connectivity, so it is
+//! meant to show roughly which part of a property goes under at a given
+//! stage, not to replace a hydraulic model.
+//!
+//! The water surface is the zone's reference gauge stage plus its
+//! `gauge_datum_ft`, converted to the grid's vertical datum with the
+//! gauge's `to_ngvd29_ft` offsets (see `datum`); `[[inundation]]` entries
+//! on a datum the gauge has no offset to are rejected at startup.
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Highest zone id (zones are numbered 0-6)
+pub const MAX_ZONE_ID: usize = 6;
+
+/// `[[inundation]]` entry in flomon.toml
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct InundationSettings {
+    pub zone_id: usize,
+    /// ESRI ASCII grid, relative to flomon.toml
+    pub grid: PathBuf,
+    /// USGS gauge whose stage sets the water surface
+    pub reference_site: String,
+    /// Vertical datum of the grid elevations ("NGVD29", "NAVD88")
+    pub datum: String,
+}
+
+/// An `[[inundation]]` entry resolved against the station registry
+#[derive(Debug, Clone, PartialEq)]
+pub struct InundationLayer {
+    pub zone_id: usize,
+    pub grid: PathBuf,
+    pub reference_site: String,
+    pub gauge_datum_ft: f64,
+    pub datum: String,
+}
+
+impl InundationLayer {
+    pub fn water_surface_ft(&self, stage_ft: f64) -> f64 {
+        stage_ft + self.gauge_datum_ft
+    }
+}
+
+/// Regular elevation grid; row 0 is the northern edge
+#[derive(Debug, Clone, PartialEq)]
+pub struct DemGrid {
+    pub ncols: usize,
+    pub nrows: usize,
+    /// Western edge (longitude)
+    pub xll: f64,
+    /// Southern edge (latitude)
+    pub yll: f64,
+    pub cellsize: f64,
+    /// Row-major ground elevations, `None` for NODATA
+    pub elevations: Vec<Option<f64>>,
+}
+
+/// Parse an ESRI ASCII grid
+pub fn parse_ascii_grid(text: &str) -> Result<DemGrid, String> {
+    let mut tokens = text.split_whitespace().peekable();
+    let mut header = std::collections::HashMap::new();
+    while let Some(key) = tokens.peek().filter(|t| t.starts_with(|c: char| c.is_ascii_alphabetic())) {
+        let key = key.to_ascii_lowercase();
+        tokens.next();
+        let value: f64 = tokens.next()
+            .ok_or_else(|| format!("Grid header {} has no value", key))?
+            .parse()
+            .map_err(|_| format!("Grid header {} is not a number", key))?;
+        header.insert(key, value);
+    }
+    let get = |key: &str| header.get(key).copied().ok_or_else(|| format!("Grid header is missing {}", key));
+
+    let ncols = get("ncols")? as usize;
+    let nrows = get("nrows")? as usize;
+    let cellsize = get("cellsize")?;
+    if ncols == 0 || nrows == 0 || cellsize <= 0.0 {
+        return Err("Grid must have positive ncols, nrows and cellsize".to_string());
+    }
+    // Cell-center origins are shifted to the corner
+    let xll = get("xllcorner").or_else(|_| get("xllcenter").map(|x| x - cellsize / 2.0))?;
+    let yll = get("yllcorner").or_else(|_| get("yllcenter").map(|y| y - cellsize / 2.0))?;
+    let nodata = header.get("nodata_value").copied();
+
+    let elevations = tokens
+        .map(|t| t.parse::<f64>().map_err(|_| format!("Invalid grid value {:?}", t)))
+        .map(|v| v.map(|v| Some(v).filter(|v| Some(*v) != nodata)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if elevations.len() != ncols * nrows {
+        return Err(format!("Grid has {} values, expected {} x {}", elevations.len(), ncols, nrows));
+    }
+    Ok(DemGrid { ncols, nrows, xll, yll, cellsize, elevations })
+}
+
+/// Read and parse a grid file
+pub fn load_grid(path: &Path) -> Result<DemGrid, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read inundation grid {}: {}", path.display(), e))?;
+    parse_ascii_grid(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Flooded area for one water surface elevation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inundation {
+    pub water_surface_ft: f64,
+    pub flooded_cells: usize,
+    /// Cells with an elevation (excludes NODATA)
+    pub valid_cells: usize,
+    /// Merged flooded cells as [west, south, east, north]
+    pub rectangles: Vec<[f64; 4]>,
+}
+
+/// Mark cells below `water_surface_ft` and merge them into rectangles.
+///
+/// Runs of flooded cells in a row become one rectangle, which is extended
+/// south while the rows below have a run with the same columns.
+pub fn inundation(grid: &DemGrid, water_surface_ft: f64) -> Inundation {
+    let flooded = |row: usize, col: usize| {
+        grid.elevations[row * grid.ncols + col].is_some_and(|e| e < water_surface_ft)
+    };
+    let edge_x = |col: usize| grid.xll + col as f64 * grid.cellsize;
+    let edge_y = |row: usize| grid.yll + (grid.nrows - row) as f64 * grid.cellsize;
+
+    let mut rectangles = Vec::new();
+    // (first col, end col, first row) of rectangles still growing south
+    let mut open: Vec<(usize, usize, usize)> = Vec::new();
+    for row in 0..=grid.nrows {
+        let mut runs = Vec::new();
+        if row < grid.nrows {
+            let mut col = 0;
+            while col < grid.ncols {
+                if flooded(row, col) {
+                    let start = col;
+                    while col < grid.ncols && flooded(row, col) {
+                        col += 1;
+                    }
+                    runs.push((start, col));
+                } else {
+                    col += 1;
+                }
+            }
+        }
+
+        let mut next = Vec::new();
+        for (start, end) in runs {
+            match open.iter().position(|&(s, e, _)| (s, e) == (start, end)) {
+                Some(i) => next.push(open.swap_remove(i)),
+                None => next.push((start, end, row)),
+            }
+        }
+        for (start, end, top) in open {
+            rectangles.push([edge_x(start), edge_y(row), edge_x(end), edge_y(top)]);
+        }
+        open = next;
+    }
+
+    let valid_cells = grid.elevations.iter().filter(|e| e.is_some()).count();
+    let flooded_cells = grid.elevations.iter().filter(|e| e.is_some_and(|e| e < water_surface_ft)).count();
+    Inundation { water_surface_ft, flooded_cells, valid_cells, rectangles }
+}
+
+impl Inundation {
+    /// Share of valid cells that are flooded
+    pub fn flooded_fraction(&self) -> f64 {
+        if self.valid_cells == 0 {
+            0.0
+        } else {
+            self.flooded_cells as f64 / self.valid_cells as f64
+        }
+    }
+
+    /// GeoJSON Feature (MultiPolygon, lon/lat) carrying `properties` plus
+    /// the water surface and cell counts
+    pub fn to_geojson(&self, mut properties: Map<String, Value>) -> Value {
+        let polygons: Vec<Value> = self.rectangles.iter()
+            .map(|&[w, s, e, n]| json!([[[w, s], [e, s], [e, n], [w, n], [w, s]]]))
+            .collect();
+        properties.insert("water_surface_ft".to_string(), json!(self.water_surface_ft));
+        properties.insert("flooded_cells".to_string(), json!(self.flooded_cells));
+        properties.insert("valid_cells".to_string(), json!(self.valid_cells));
+        properties.insert("flooded_fraction".to_string(), json!(self.flooded_fraction()));
+        json!({
+            "type": "Feature",
+            "geometry": { "type": "MultiPolygon", "coordinates": polygons },
+            "properties": properties,
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A yard sloping up from the river on the west; one NODATA cell
+    const YARD: &str = "\
+ncols 4
+nrows 3
+xllcorner -89.600
+yllcorner 40.690
+cellsize 0.001
+NODATA_value -9999
+446.0 447.5 449.0 452.0
+446.5 447.0 450.0 452.5
+446.0 448.0 -9999 453.0
+";
+
+    #[test]
+    fn test_parse_ascii_grid() {
+        let grid = parse_ascii_grid(YARD).unwrap();
+        assert_eq!((grid.ncols, grid.nrows), (4, 3));
+        assert_eq!(grid.elevations[10], None);
+        assert_eq!(grid.elevations[11], Some(453.0));
+
+        assert!(parse_ascii_grid("ncols 2\nnrows 1\nxllcorner 0\nyllcorner 0\ncellsize 1\n1.0").is_err());
+        let centered = parse_ascii_grid("ncols 1\nnrows 1\nxllcenter 0.5\nyllcenter 0.5\ncellsize 1\n1.0").unwrap();
+        assert_eq!((centered.xll, centered.yll), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_flooded_cells_merge_into_rectangles() {
+        let grid = parse_ascii_grid(YARD).unwrap();
+        let flood = inundation(&grid, 447.2);
+        // Column 0 in every row, plus (1,1)
+        assert_eq!(flood.flooded_cells, 4);
+        assert_eq!(flood.valid_cells, 11);
+        assert_eq!(flood.rectangles.len(), 3);
+
+        let western_strip = flood.rectangles.iter()
+            .find(|r| (r[2] - r[0] - 0.001).abs() < 1e-9 && (r[3] - r[1] - 0.001).abs() < 1e-9 && (r[3] - 40.693).abs() < 1e-9)
+            .unwrap();
+        assert!((western_strip[0] + 89.6).abs() < 1e-9);
+
+        assert_eq!(inundation(&grid, 440.0).rectangles.len(), 0);
+        let all = inundation(&grid, 460.0);
+        assert_eq!(all.flooded_cells, 11);
+        assert!((all.flooded_fraction() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_geojson_feature() {
+        let grid = parse_ascii_grid("ncols 2\nnrows 1\nxllcorner -89.6\nyllcorner 40.69\ncellsize 0.01\n446 450").unwrap();
+        let mut props = Map::new();
+        props.insert("zone_id".to_string(), json!(2));
+        let feature = inundation(&grid, 447.0).to_geojson(props);
+        assert_eq!(feature["geometry"]["type"], "MultiPolygon");
+        let ring = &feature["geometry"]["coordinates"][0][0];
+        assert_eq!(ring.as_array().unwrap().len(), 5);
+        assert_eq!(ring[0], ring[4]);
+        assert_eq!(feature["properties"]["zone_id"], 2);
+        assert_eq!(feature["properties"]["flooded_cells"], 1);
+    }
+}
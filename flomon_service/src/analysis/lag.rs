@@ -0,0 +1,324 @@
+//! Travel time from upstream gauges to Kingston Mines by cross-correlation.
+//!
+//! The registry's `travel_time_to_peoria_hours` values are midpoints of
+//! ranges in the station descriptions ("Henry leads Peoria by 12–24
+//! hours"). This module measures them instead: each stage series is cut
+//! to hourly values, differenced (so a shared seasonal level doesn't
+//! correlate at every lag), and the upstream rises are correlated against
+//! the downstream rises `lag` hours later for every lag up to
+//! `MAX_LAG_HOURS`. The lag with the highest correlation is the travel
+//! time.
+//!
+//! A flood wave moves faster at high flow, so the fit is done separately
+//! per flow regime, classed by the downstream stage at the time of arrival
+//! (`Regime`). A regime with fewer than `MIN_PAIRS` hourly pairs, or no
+//! positive correlation at any lag, gets no estimate.
+//!
+//! `flomon lag fit` stores the estimates in `flood_analysis.lag_estimates`
+//! (sql/038_lag_estimates.sql); `lead_time` picks the one for the current
+//! regime for forecast-style alerts, and /api/lag serves them.
+
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use postgres::Client;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::archive;
+use crate::model::{FloodThresholds, PARAM_STAGE};
+use crate::stations::Station;
+
+/// Longest travel time tried; Marseilles to Kingston Mines is about two days
+pub const MAX_LAG_HOURS: i64 = 96;
+
+/// Fewest hourly pairs a regime needs for an estimate
+pub const MIN_PAIRS: usize = 48;
+
+/// Flow regime, from the downstream stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Regime {
+    /// Below action stage
+    Low,
+    /// Action stage up to flood stage
+    Elevated,
+    /// At or above flood stage
+    Flood,
+}
+
+impl Regime {
+    pub const ALL: [Regime; 3] = [Regime::Low, Regime::Elevated, Regime::Flood];
+
+    pub fn of(stage_ft: f64, thresholds: &FloodThresholds) -> Self {
+        if stage_ft >= thresholds.flood_stage_ft {
+            Regime::Flood
+        } else if stage_ft >= thresholds.action_stage_ft {
+            Regime::Elevated
+        } else {
+            Regime::Low
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Regime::Low => "low",
+            Regime::Elevated => "elevated",
+            Regime::Flood => "flood",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Regime::ALL.into_iter().find(|r| r.as_str() == s)
+    }
+}
+
+/// Fitted travel time from one gauge to another in one regime
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LagEstimate {
+    pub upstream_site: String,
+    pub downstream_site: String,
+    pub regime: Regime,
+    pub lag_hours: i64,
+    /// Pearson correlation of the hourly rises at `lag_hours`
+    pub correlation: f64,
+    pub pairs: usize,
+}
+
+/// Last value in each clock hour, keyed by hour start
+pub fn hourly(readings: &[(DateTime<Utc>, f64)]) -> BTreeMap<DateTime<Utc>, f64> {
+    readings.iter()
+        .filter_map(|&(at, value)| at.duration_trunc(Duration::hours(1)).ok().map(|hour| (hour, value)))
+        .collect()
+}
+
+/// Hour-to-hour change, for hours whose previous hour has a value
+fn rises(hourly: &BTreeMap<DateTime<Utc>, f64>) -> BTreeMap<DateTime<Utc>, f64> {
+    hourly.iter()
+        .filter_map(|(&hour, &value)| {
+            hourly.get(&(hour - Duration::hours(1))).map(|previous| (hour, value - previous))
+        })
+        .collect()
+}
+
+fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+    let n = pairs.len() as f64;
+    let (mean_x, mean_y) = pairs.iter().fold((0.0, 0.0), |(x, y), (a, b)| (x + a / n, y + b / n));
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in pairs {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    (var_x > 0.0 && var_y > 0.0).then(|| cov / (var_x * var_y).sqrt())
+}
+
+/// Best lag per regime between two hourly stage series
+pub fn fit(
+    upstream_site: &str,
+    upstream: &BTreeMap<DateTime<Utc>, f64>,
+    downstream_site: &str,
+    downstream: &BTreeMap<DateTime<Utc>, f64>,
+    thresholds: &FloodThresholds,
+) -> Vec<LagEstimate> {
+    let (up, down) = (rises(upstream), rises(downstream));
+    let mut estimates = Vec::new();
+    for regime in Regime::ALL {
+        let arrivals: Vec<(DateTime<Utc>, f64)> = down.iter()
+            .filter(|(hour, _)| downstream.get(hour).is_some_and(|&stage| Regime::of(stage, thresholds) == regime))
+            .map(|(&hour, &rise)| (hour, rise))
+            .collect();
+        let mut best: Option<LagEstimate> = None;
+        for lag_hours in 0..=MAX_LAG_HOURS {
+            let pairs: Vec<(f64, f64)> = arrivals.iter()
+                .filter_map(|(hour, rise)| up.get(&(*hour - Duration::hours(lag_hours))).map(|u| (*u, *rise)))
+                .collect();
+            if pairs.len() < MIN_PAIRS {
+                continue;
+            }
+            let Some(correlation) = pearson(&pairs).filter(|r| *r > 0.0) else { continue };
+            if best.as_ref().is_none_or(|b| correlation > b.correlation) {
+                best = Some(LagEstimate {
+                    upstream_site: upstream_site.to_string(),
+                    downstream_site: downstream_site.to_string(),
+                    regime,
+                    lag_hours,
+                    correlation,
+                    pairs: pairs.len(),
+                });
+            }
+        }
+        estimates.extend(best);
+    }
+    estimates
+}
+
+/// Every gauge that feeds `downstream`: upstream on the main stem by
+/// river mile, or a tributary with a travel time; by site code
+pub fn upstream_of<'a>(stations: impl IntoIterator<Item = &'a Station>, downstream: &Station) -> Vec<&'a Station> {
+    let mut upstream: Vec<&Station> = stations.into_iter()
+        .filter(|s| s.site_code != downstream.site_code)
+        .filter(|s| match (s.river_mile, downstream.river_mile) {
+            (Some(mile), Some(downstream_mile)) => mile > downstream_mile,
+            _ => s.travel_time_to_peoria_hours > 0.0,
+        })
+        .collect();
+    upstream.sort_by(|a, b| a.site_code.cmp(&b.site_code));
+    upstream
+}
+
+/// Fit every station in `upstream` against `downstream` over `[start, end]`
+pub fn fit_stored(
+    client: &mut Client,
+    upstream: &[Station],
+    downstream: &Station,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<LagEstimate>, String> {
+    let thresholds = downstream.thresholds.as_ref()
+        .ok_or_else(|| format!("{} has no flood thresholds to class flow regimes by", downstream.site_code))?;
+    let mut load = |site: &str| -> Result<BTreeMap<DateTime<Utc>, f64>, String> {
+        let readings: Vec<(DateTime<Utc>, f64)> = archive::readings_between(client, site, PARAM_STAGE, start, end)?
+            .iter()
+            .map(|r| (r.datetime.with_timezone(&Utc), r.value))
+            .collect();
+        Ok(hourly(&readings))
+    };
+    let downstream_series = load(&downstream.site_code)?;
+    let mut estimates = Vec::new();
+    for station in upstream {
+        let series = load(&station.site_code)?;
+        estimates.extend(fit(&station.site_code, &series, &downstream.site_code, &downstream_series, thresholds));
+    }
+    Ok(estimates)
+}
+
+/// The estimate for a pair in `regime`, falling back to the nearest
+/// regime that has one (a flood fit is often missing in a dry record)
+pub fn lead_time<'a>(estimates: &'a [LagEstimate], upstream_site: &str, downstream_site: &str, regime: Regime) -> Option<&'a LagEstimate> {
+    estimates.iter()
+        .filter(|e| e.upstream_site == upstream_site && e.downstream_site == downstream_site)
+        .min_by_key(|e| (e.regime as i32 - regime as i32).abs())
+}
+
+// ---------------------------------------------------------------------------
+// Persistence
+// ---------------------------------------------------------------------------
+
+/// Replace the stored estimates for the fitted pairs
+pub fn store(client: &mut Client, estimates: &[LagEstimate], start: DateTime<Utc>, end: DateTime<Utc>) -> Result<usize, String> {
+    let mut written = 0;
+    for e in estimates {
+        written += client.execute(
+            "INSERT INTO flood_analysis.lag_estimates
+             (upstream_site, downstream_site, regime, lag_hours, correlation, pairs, window_start, window_end, fitted_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NOW())
+             ON CONFLICT (upstream_site, downstream_site, regime) DO UPDATE
+             SET lag_hours = EXCLUDED.lag_hours,
+                 correlation = EXCLUDED.correlation,
+                 pairs = EXCLUDED.pairs,
+                 window_start = EXCLUDED.window_start,
+                 window_end = EXCLUDED.window_end,
+                 fitted_at = EXCLUDED.fitted_at",
+            &[
+                &e.upstream_site,
+                &e.downstream_site,
+                &e.regime.as_str(),
+                &(e.lag_hours as i32),
+                &e.correlation,
+                &(e.pairs as i32),
+                &start,
+                &end,
+            ],
+        ).map_err(|err| format!("Failed to store lag {} -> {}: {}", e.upstream_site, e.downstream_site, err))? as usize;
+    }
+    Ok(written)
+}
+
+/// Every stored estimate
+pub fn load(client: &mut Client) -> Result<Vec<LagEstimate>, String> {
+    let rows = client.query(
+        "SELECT upstream_site, downstream_site, regime, lag_hours, correlation, pairs
+         FROM flood_analysis.lag_estimates
+         ORDER BY downstream_site, lag_hours, upstream_site, regime",
+        &[],
+    ).map_err(|e| format!("Failed to load lag estimates: {}", e))?;
+    Ok(rows.iter()
+        .filter_map(|row| Some(LagEstimate {
+            upstream_site: row.get(0),
+            downstream_site: row.get(1),
+            regime: Regime::parse(row.get(2))?,
+            lag_hours: i64::from(row.get::<_, i32>(3)),
+            correlation: row.get(4),
+            pairs: row.get::<_, i32>(5) as usize,
+        }))
+        .collect())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn thresholds() -> FloodThresholds {
+        FloodThresholds {
+            action_stage_ft: 14.0,
+            flood_stage_ft: 16.0,
+            moderate_flood_stage_ft: 20.0,
+            major_flood_stage_ft: 24.0,
+        }
+    }
+
+    /// Irregular hydrograph: sum of two incommensurate waves
+    fn wave(hour: i64, base: f64) -> f64 {
+        let h = hour as f64;
+        base + 2.0 * (h / 17.0).sin() + 1.3 * (h / 7.3).cos()
+    }
+
+    fn series(hours: i64, shift: i64, base: f64) -> BTreeMap<DateTime<Utc>, f64> {
+        let t0 = Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap();
+        let readings: Vec<(DateTime<Utc>, f64)> = (0..hours)
+            .map(|h| (t0 + Duration::hours(h) + Duration::minutes(10), wave(h - shift, base)))
+            .collect();
+        hourly(&readings)
+    }
+
+    #[test]
+    fn test_recovers_shifted_series_lag() {
+        let upstream = series(600, 0, 10.0);
+        let downstream = series(600, 18, 10.0);   // below action: low regime
+        let estimates = fit("05557000", &upstream, "05568500", &downstream, &thresholds());
+        assert_eq!(estimates.len(), 1);
+        let estimate = &estimates[0];
+        assert_eq!((estimate.regime, estimate.lag_hours), (Regime::Low, 18));
+        assert!(estimate.correlation > 0.99);
+        assert!(estimate.pairs >= MIN_PAIRS);
+    }
+
+    #[test]
+    fn test_regimes_fit_separately() {
+        let upstream = series(600, 0, 10.0);
+        // Downstream swings from below action stage to above flood stage
+        let downstream = series(600, 9, 14.0);
+        let mut estimates = fit("05568000", &upstream, "05568500", &downstream, &thresholds());
+        let regimes: Vec<Regime> = estimates.iter().map(|e| e.regime).collect();
+        assert_eq!(regimes, Regime::ALL);
+        assert!(estimates.iter().all(|e| e.lag_hours == 9));
+
+        estimates.retain(|e| e.regime != Regime::Flood);
+        let flood = lead_time(&estimates, "05568000", "05568500", Regime::Flood).unwrap();
+        assert_eq!(flood.regime, Regime::Elevated, "nearest regime with a fit");
+        assert!(lead_time(&estimates, "05552500", "05568500", Regime::Low).is_none());
+    }
+
+    #[test]
+    fn test_too_few_pairs_gives_no_estimate() {
+        let upstream = series(40, 0, 10.0);
+        let downstream = series(40, 5, 10.0);
+        assert!(fit("a", &upstream, "b", &downstream, &thresholds()).is_empty());
+        assert_eq!(Regime::of(16.0, &thresholds()), Regime::Flood);
+        assert_eq!(Regime::parse("elevated"), Some(Regime::Elevated));
+    }
+}
@@ -1,11 +1,43 @@
-/// Data organization utilities for the flood monitoring service.
-///
-/// This module provides basic data grouping and organization helpers.
-/// Complex statistical analysis, regression, and pattern detection
-/// are handled by external Python scripts that read from the curated
-/// database.
-///
-/// Submodules:
-/// - `groupings` — organizes flat ingest output into per-site structures.
+//! Data organization utilities for the flood monitoring service.
+//!
+//! This module provides basic data grouping and organization helpers.
+//! Complex statistical analysis, regression, and pattern detection
+//! are handled by external Python scripts that read from the curated
+//! database.
+//!
+//! Submodules:
+//! - `anomaly` — median/MAD anomaly score for each new reading.
+//! - `backwater` — Mississippi backwater on the LaGrange and Peoria pools.
+//! - `datum_shift` — abrupt persistent stage steps not matched by discharge.
+//! - `events` — flood events (start, crest, end) segmented from stage history.
+//! - `frequency` — Log-Pearson III return period of a discharge from the annual peaks.
+//! - `groupings` — organizes flat ingest output into per-site structures.
+//! - `freeboard` — property freeboard derived from pool elevation.
+//! - `inundation` — bathtub-fill flooded area from a per-zone DEM grid.
+//! - `lag` — upstream-to-Kingston Mines travel time per flow regime, by cross-correlation.
+//! - `nowcast` — radar-extrapolation rainfall nowcast per sub-basin.
+//! - `profile` — stage at ungauged river miles interpolated between gauges.
+//! - `precip` — rolling precipitation windows per ASOS station and basin.
+//! - `precip_response` — expected gauge rise after basin rain, from fitted curves.
+//! - `rating_drift` — field measurements consistently off the rated discharge.
+//! - `slope` — Peoria–Kingston Mines water-surface slope and its trend.
+//! - `volume` — acre-feet passed at Kingston Mines over an event, against upstream gauges.
+//! - `window_stats` — min/max/percentiles and time above thresholds over a window.
 
+pub mod anomaly;
+pub mod backwater;
+pub mod datum_shift;
+pub mod events;
+pub mod freeboard;
+pub mod frequency;
 pub mod groupings;
+pub mod inundation;
+pub mod lag;
+pub mod nowcast;
+pub mod precip;
+pub mod precip_response;
+pub mod profile;
+pub mod rating_drift;
+pub mod slope;
+pub mod volume;
+pub mod window_stats;
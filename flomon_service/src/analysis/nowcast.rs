@@ -0,0 +1,331 @@
+//! Short-range precipitation nowcast by radar extrapolation.
+//!
+//! Given two consecutive precipitation-rate grids (MRMS `PrecipRate`,
+//! converted to in/hr), the storm motion is estimated as the grid shift
+//! that best maps the older grid onto the newer one, and the intensity
+//! trend as the change in mean rate over raining cells. The newer grid is
+//! then advected along that motion and scaled by the trend in 5-minute
+//! steps, and the rate falling inside each sub-basin is accumulated for
+//! each lead time (60 and 120 minutes by default).
+//!
+//! This is a heuristic, not a forecast model: it assumes the storm keeps
+//! its direction, speed, and growth rate. That is usually good for an hour
+//! or two, which is the window that matters on the Mackinaw, where the
+//! river responds within a few hours of heavy rain.
+//!
+//! `assess` adds the nowcast to the observed 6-hour total and compares the
+//! result with the basin's `PrecipThresholds`, which is what the
+//! flash-rise rules consume.
+
+use chrono::{DateTime, Utc};
+
+use crate::asos_locations::PrecipThresholds;
+
+/// Default nowcast lead times, minutes
+pub const DEFAULT_LEAD_MINUTES: [i64; 2] = [60, 120];
+
+/// Integration step, minutes
+const STEP_MINUTES: i64 = 5;
+
+/// Rates below this (in/hr) are treated as dry when tracking the storm
+const WET_THRESHOLD_IN_HR: f64 = 0.01;
+
+/// Largest storm displacement searched between two grids, in cells
+const MAX_SHIFT_CELLS: i64 = 10;
+
+/// Bounds on the hourly intensity growth factor
+const GROWTH_LIMITS: (f64, f64) = (0.5, 2.0);
+
+/// Precipitation rate on a regular lat/lon grid, row 0 at the north edge
+#[derive(Debug, Clone, PartialEq)]
+pub struct RainGrid {
+    pub valid_time: DateTime<Utc>,
+    /// Latitude of the north edge of row 0
+    pub north: f64,
+    /// Longitude of the west edge of column 0
+    pub west: f64,
+    /// Cell size in degrees (MRMS is 0.01)
+    pub cell_deg: f64,
+    pub rows: usize,
+    pub cols: usize,
+    /// Row-major rates, in/hr
+    pub rate_in_hr: Vec<f64>,
+}
+
+impl RainGrid {
+    fn at(&self, row: i64, col: i64) -> Option<f64> {
+        if row < 0 || col < 0 || row >= self.rows as i64 || col >= self.cols as i64 {
+            return None;
+        }
+        self.rate_in_hr.get(row as usize * self.cols + col as usize).copied()
+    }
+
+    /// Cells whose centres fall inside `basin`
+    fn cells_in(&self, basin: &SubBasin) -> Vec<(i64, i64)> {
+        let mut cells = Vec::new();
+        for row in 0..self.rows {
+            let lat = self.north - (row as f64 + 0.5) * self.cell_deg;
+            for col in 0..self.cols {
+                let lon = self.west + (col as f64 + 0.5) * self.cell_deg;
+                if basin.contains(lat, lon) {
+                    cells.push((row as i64, col as i64));
+                }
+            }
+        }
+        cells
+    }
+
+    fn wet_mean(&self) -> Option<f64> {
+        let wet: Vec<f64> = self.rate_in_hr.iter().copied().filter(|r| *r >= WET_THRESHOLD_IN_HR).collect();
+        (!wet.is_empty()).then(|| wet.iter().sum::<f64>() / wet.len() as f64)
+    }
+}
+
+/// Sub-basin outline (bounding box)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubBasin {
+    /// Basin name as used by `AsosLocation::basin` ("Mackinaw River")
+    pub name: String,
+    pub south: f64,
+    pub north: f64,
+    pub west: f64,
+    pub east: f64,
+}
+
+impl SubBasin {
+    fn contains(&self, lat: f64, lon: f64) -> bool {
+        (self.south..=self.north).contains(&lat) && (self.west..=self.east).contains(&lon)
+    }
+}
+
+/// Approximate outlines of the fast-responding tributary basins
+pub fn default_sub_basins() -> Vec<SubBasin> {
+    let basin = |name: &str, south, north, west, east| SubBasin { name: name.to_string(), south, north, west, east };
+    vec![
+        basin("Mackinaw River", 40.35, 40.75, -89.65, -88.45),
+        basin("Spoon River", 40.30, 41.25, -90.45, -89.55),
+    ]
+}
+
+/// Estimated storm motion and intensity trend between two grids
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StormMotion {
+    /// Cells per minute, positive southward
+    pub rows_per_min: f64,
+    /// Cells per minute, positive eastward
+    pub cols_per_min: f64,
+    /// Multiplier on rates per hour of lead time
+    pub growth_per_hour: f64,
+}
+
+/// Nowcast accumulation over one sub-basin for one lead time
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasinNowcast {
+    pub basin: String,
+    /// Valid time of the newest grid
+    pub issued_at: DateTime<Utc>,
+    pub lead_minutes: i64,
+    /// Basin-mean accumulation over the lead time, inches
+    pub accumulation_in: f64,
+    /// Highest basin-mean rate at any step, in/hr
+    pub peak_rate_in_hr: f64,
+}
+
+/// Flash-rise level implied by observed plus nowcast rainfall
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NowcastLevel {
+    Watch,
+    Warning,
+}
+
+impl NowcastLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NowcastLevel::Watch => "watch",
+            NowcastLevel::Warning => "warning",
+        }
+    }
+}
+
+/// Storm motion from `previous` to `current` (same grid geometry).
+///
+/// Returns None if the grids differ in shape or are not in time order.
+pub fn storm_motion(previous: &RainGrid, current: &RainGrid) -> Option<StormMotion> {
+    if (previous.rows, previous.cols) != (current.rows, current.cols) {
+        return None;
+    }
+    let minutes = (current.valid_time - previous.valid_time).num_seconds() as f64 / 60.0;
+    if minutes <= 0.0 {
+        return None;
+    }
+
+    // Shift (dr, dc) minimising the mean squared difference between
+    // current(r, c) and previous(r - dr, c - dc) over cells raining in either
+    let mut best = (f64::INFINITY, 0i64, 0i64);
+    for dr in -MAX_SHIFT_CELLS..=MAX_SHIFT_CELLS {
+        for dc in -MAX_SHIFT_CELLS..=MAX_SHIFT_CELLS {
+            let (mut sum, mut n) = (0.0, 0usize);
+            for row in 0..current.rows as i64 {
+                for col in 0..current.cols as i64 {
+                    let (Some(now), Some(before)) = (current.at(row, col), previous.at(row - dr, col - dc)) else {
+                        continue;
+                    };
+                    if now < WET_THRESHOLD_IN_HR && before < WET_THRESHOLD_IN_HR {
+                        continue;
+                    }
+                    sum += (now - before).powi(2);
+                    n += 1;
+                }
+            }
+            if n == 0 {
+                continue;
+            }
+            let score = sum / n as f64;
+            let shorter = dr.abs() + dc.abs() < best.1.abs() + best.2.abs();
+            if score < best.0 || (score == best.0 && shorter) {
+                best = (score, dr, dc);
+            }
+        }
+    }
+
+    let growth_per_hour = match (previous.wet_mean(), current.wet_mean()) {
+        (Some(before), Some(now)) => (now / before).powf(60.0 / minutes).clamp(GROWTH_LIMITS.0, GROWTH_LIMITS.1),
+        _ => 1.0,
+    };
+    Some(StormMotion {
+        rows_per_min: best.1 as f64 / minutes,
+        cols_per_min: best.2 as f64 / minutes,
+        growth_per_hour,
+    })
+}
+
+/// Extrapolate `current` along `motion` and accumulate over each basin
+pub fn nowcast(
+    current: &RainGrid,
+    motion: &StormMotion,
+    basins: &[SubBasin],
+    lead_minutes: &[i64],
+) -> Vec<BasinNowcast> {
+    let horizon = lead_minutes.iter().copied().max().unwrap_or(0);
+    let mut out = Vec::new();
+    for basin in basins {
+        let cells = current.cells_in(basin);
+        if cells.is_empty() {
+            continue;
+        }
+        let (mut accumulation, mut peak) = (0.0, 0.0f64);
+        let mut t = STEP_MINUTES;
+        while t <= horizon {
+            let growth = motion.growth_per_hour.powf(t as f64 / 60.0);
+            let shift_rows = (motion.rows_per_min * t as f64).round() as i64;
+            let shift_cols = (motion.cols_per_min * t as f64).round() as i64;
+            let mean_rate = cells.iter()
+                .map(|(row, col)| current.at(row - shift_rows, col - shift_cols).unwrap_or(0.0) * growth)
+                .sum::<f64>() / cells.len() as f64;
+            accumulation += mean_rate * STEP_MINUTES as f64 / 60.0;
+            peak = peak.max(mean_rate);
+            if lead_minutes.contains(&t) {
+                out.push(BasinNowcast {
+                    basin: basin.name.clone(),
+                    issued_at: current.valid_time,
+                    lead_minutes: t,
+                    accumulation_in: accumulation,
+                    peak_rate_in_hr: peak,
+                });
+            }
+            t += STEP_MINUTES;
+        }
+    }
+    out
+}
+
+/// Nowcast for consecutive grids at the default lead times
+pub fn nowcast_from_grids(previous: &RainGrid, current: &RainGrid, basins: &[SubBasin]) -> Option<Vec<BasinNowcast>> {
+    let motion = storm_motion(previous, current)?;
+    Some(nowcast(current, &motion, basins, &DEFAULT_LEAD_MINUTES))
+}
+
+/// Compare observed 6-hour rainfall plus the nowcast with the basin's
+/// 6-hour watch/warning thresholds
+pub fn assess(observed_6hr_in: f64, nowcast: &BasinNowcast, thresholds: &PrecipThresholds) -> Option<NowcastLevel> {
+    let projected = observed_6hr_in + nowcast.accumulation_in;
+    if projected >= thresholds.warning_6hr_in {
+        Some(NowcastLevel::Warning)
+    } else if projected >= thresholds.watch_6hr_in {
+        Some(NowcastLevel::Watch)
+    } else {
+        None
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// 40 x 40 grid of 0.05° cells over central Illinois with a 4x4 cell
+    /// storm of `rate` whose top-left corner is at (`row`, `col`)
+    fn grid(minute: u32, row: usize, col: usize, rate: f64) -> RainGrid {
+        let (rows, cols) = (40, 40);
+        let mut rate_in_hr = vec![0.0; rows * cols];
+        for r in row..row + 4 {
+            for c in col..col + 4 {
+                rate_in_hr[r * cols + c] = rate;
+            }
+        }
+        RainGrid {
+            valid_time: Utc.with_ymd_and_hms(2024, 6, 1, 18, minute, 0).unwrap(),
+            north: 41.5,
+            west: -91.0,
+            cell_deg: 0.05,
+            rows,
+            cols,
+            rate_in_hr,
+        }
+    }
+
+    #[test]
+    fn test_motion_tracks_shifted_storm() {
+        // Storm moves two cells east in 10 minutes
+        let motion = storm_motion(&grid(0, 20, 5, 1.0), &grid(10, 20, 7, 1.0)).unwrap();
+        assert!((motion.cols_per_min - 0.2).abs() < 1e-9);
+        assert_eq!(motion.rows_per_min, 0.0);
+        assert!((motion.growth_per_hour - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_storm_moving_into_basin_accumulates() {
+        let basins = default_sub_basins();
+        // West of the Mackinaw box, moving east 0.2 cells/min (0.6°/hr)
+        let results = nowcast_from_grids(&grid(0, 18, 14, 2.0), &grid(10, 18, 16, 2.0), &basins).unwrap();
+        let mackinaw: Vec<&BasinNowcast> = results.iter().filter(|n| n.basin == "Mackinaw River").collect();
+        assert_eq!(mackinaw.iter().map(|n| n.lead_minutes).collect::<Vec<_>>(), vec![60, 120]);
+        assert!(mackinaw[0].accumulation_in > 0.0);
+        assert!(mackinaw[1].accumulation_in >= mackinaw[0].accumulation_in);
+        assert!(mackinaw[1].peak_rate_in_hr > 0.0);
+    }
+
+    #[test]
+    fn test_assess_against_thresholds() {
+        let thresholds = PrecipThresholds {
+            watch_6hr_in: 1.0,
+            warning_6hr_in: 2.0,
+            watch_24hr_in: 2.5,
+            warning_24hr_in: 4.0,
+        };
+        let nowcast = BasinNowcast {
+            basin: "Mackinaw River".to_string(),
+            issued_at: Utc.with_ymd_and_hms(2024, 6, 1, 18, 0, 0).unwrap(),
+            lead_minutes: 60,
+            accumulation_in: 0.6,
+            peak_rate_in_hr: 1.0,
+        };
+        assert_eq!(assess(0.2, &nowcast, &thresholds), None);
+        assert_eq!(assess(0.5, &nowcast, &thresholds), Some(NowcastLevel::Watch));
+        assert_eq!(assess(1.5, &nowcast, &thresholds), Some(NowcastLevel::Warning));
+    }
+}
@@ -0,0 +1,253 @@
+//! Rolling precipitation accumulations per ASOS station and per basin.
+//!
+//! The rules engine, risk score, digest and API all ask the same question
+//! ("how much rain fell in the last N hours?"). The daemon answers it once
+//! per cycle for a fixed set of windows and stores the result in
+//! `asos_precip_rollups`, so consumers read a row instead of re-running
+//! SUM queries over `asos_observations`.
+//!
+//! # Hourly buckets
+//! `precip_1hr_in` is an hourly accumulation. Stations can report more
+//! than once an hour (specials), and each report repeats the running hourly
+//! total, so observations are bucketed by clock hour and the largest value
+//! in each bucket is used. Summing raw observations would double count.
+
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use std::collections::BTreeMap;
+
+use crate::asos_locations::AsosLocation;
+use crate::ingest::iem::AsosObservation;
+
+/// Accumulation windows computed every cycle, in hours
+pub const PRECIP_WINDOWS_HOURS: [i64; 5] = [1, 3, 6, 24, 72];
+
+/// Whether a rollup describes one station or a whole basin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RollupScope {
+    Station,
+    Basin,
+}
+
+impl RollupScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RollupScope::Station => "station",
+            RollupScope::Basin => "basin",
+        }
+    }
+}
+
+/// Precipitation total over one window ending at `window_end`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrecipRollup {
+    pub scope: RollupScope,
+    /// Station ID ("KPIA") or basin name ("Mackinaw River")
+    pub scope_id: String,
+    pub window_hours: i64,
+    pub window_end: DateTime<Utc>,
+    /// Station: accumulated inches. Basin: mean of member station totals.
+    pub total_in: f64,
+    /// Basin: wettest member station. Station: same as `total_in`.
+    pub max_in: f64,
+    /// Station: hourly buckets with data. Basin: member stations with data.
+    pub sample_count: i32,
+}
+
+/// Largest `precip_1hr_in` per clock hour, keyed by hour start
+fn hourly_buckets(observations: &[AsosObservation]) -> BTreeMap<DateTime<Utc>, f64> {
+    let mut buckets: BTreeMap<DateTime<Utc>, f64> = BTreeMap::new();
+    for obs in observations {
+        let Some(precip) = obs.precip_1hr_in else { continue };
+        let Ok(hour) = obs.timestamp.duration_trunc(Duration::hours(1)) else { continue };
+        let entry = buckets.entry(hour).or_insert(precip);
+        if precip > *entry {
+            *entry = precip;
+        }
+    }
+    buckets
+}
+
+/// Rollups for one station over every window in `PRECIP_WINDOWS_HOURS`.
+///
+/// A window covers observations in `(now - window, now]`. Windows with no
+/// precipitation data at all are omitted rather than reported as zero, so
+/// a silent station can't masquerade as a dry one.
+pub fn station_rollups(
+    station_id: &str,
+    observations: &[AsosObservation],
+    now: DateTime<Utc>,
+) -> Vec<PrecipRollup> {
+    let in_range: Vec<AsosObservation> = observations.iter()
+        .filter(|o| o.station_id == station_id && o.timestamp <= now)
+        .cloned()
+        .collect();
+
+    PRECIP_WINDOWS_HOURS.iter()
+        .filter_map(|&hours| {
+            let start = now - Duration::hours(hours);
+            let window: Vec<AsosObservation> = in_range.iter()
+                .filter(|o| o.timestamp > start)
+                .cloned()
+                .collect();
+            let buckets = hourly_buckets(&window);
+            if buckets.is_empty() {
+                return None;
+            }
+            let total: f64 = buckets.values().sum();
+            Some(PrecipRollup {
+                scope: RollupScope::Station,
+                scope_id: station_id.to_string(),
+                window_hours: hours,
+                window_end: now,
+                total_in: total,
+                max_in: total,
+                sample_count: buckets.len() as i32,
+            })
+        })
+        .collect()
+}
+
+/// Basin rollups from station rollups, grouping stations by `AsosLocation::basin`.
+///
+/// The basin total is the unweighted mean of member stations that have
+/// data for the window; `max_in` is the wettest of them.
+pub fn basin_rollups(station_rollups: &[PrecipRollup], locations: &[AsosLocation]) -> Vec<PrecipRollup> {
+    let mut grouped: BTreeMap<(&str, i64), Vec<&PrecipRollup>> = BTreeMap::new();
+    for rollup in station_rollups.iter().filter(|r| r.scope == RollupScope::Station) {
+        let Some(location) = locations.iter().find(|l| l.station_id == rollup.scope_id) else {
+            continue;
+        };
+        grouped.entry((location.basin.as_str(), rollup.window_hours)).or_default().push(rollup);
+    }
+
+    grouped.into_iter()
+        .map(|((basin, window_hours), members)| {
+            let total: f64 = members.iter().map(|r| r.total_in).sum();
+            PrecipRollup {
+                scope: RollupScope::Basin,
+                scope_id: basin.to_string(),
+                window_hours,
+                window_end: members.iter().map(|r| r.window_end).max().unwrap(),
+                total_in: total / members.len() as f64,
+                max_in: members.iter().map(|r| r.total_in).fold(f64::MIN, f64::max),
+                sample_count: members.len() as i32,
+            }
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asos_locations::MonitoringPriority;
+    use chrono::TimeZone;
+
+    fn obs(station: &str, hour: u32, minute: u32, precip: Option<f64>) -> AsosObservation {
+        AsosObservation {
+            station_id: station.to_string(),
+            timestamp: Utc.with_ymd_and_hms(2024, 5, 1, hour, minute, 0).unwrap(),
+            temp_f: None,
+            dewpoint_f: None,
+            relative_humidity: None,
+            wind_direction_deg: None,
+            wind_speed_knots: None,
+            wind_gust_knots: None,
+            precip_1hr_in: precip,
+            pressure_mb: None,
+            visibility_mi: None,
+            sky_condition: None,
+            weather_codes: None,
+            relative_humidity_reported: None,
+            rh_qc_flag: None,
+        }
+    }
+
+    fn location(station: &str, basin: &str) -> AsosLocation {
+        AsosLocation {
+            station_id: station.to_string(),
+            name: station.to_string(),
+            latitude: 0.0,
+            longitude: 0.0,
+            elevation_ft: 0.0,
+            data_types: vec!["precipitation".to_string()],
+            relevance: "High".to_string(),
+            basin: basin.to_string(),
+            upstream_gauge: String::new(),
+            priority: MonitoringPriority::High,
+        }
+    }
+
+    fn window(rollups: &[PrecipRollup], hours: i64) -> &PrecipRollup {
+        rollups.iter().find(|r| r.window_hours == hours).expect("window present")
+    }
+
+    #[test]
+    fn test_station_windows_sum_hourly_totals() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let observations = vec![
+            obs("KBMI", 6, 53, Some(0.40)),
+            obs("KBMI", 9, 53, Some(0.25)),
+            obs("KBMI", 10, 53, Some(0.10)),
+            obs("KBMI", 11, 53, Some(0.05)),
+        ];
+        let rollups = station_rollups("KBMI", &observations, now);
+
+        assert!((window(&rollups, 1).total_in - 0.05).abs() < 1e-9);
+        assert!((window(&rollups, 3).total_in - 0.40).abs() < 1e-9);
+        assert!((window(&rollups, 6).total_in - 0.80).abs() < 1e-9);
+        assert_eq!(window(&rollups, 72).sample_count, 4);
+    }
+
+    #[test]
+    fn test_special_observations_do_not_double_count() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        // Special at :20 and routine at :53 within the same hour
+        let observations = vec![
+            obs("KPIA", 11, 20, Some(0.30)),
+            obs("KPIA", 11, 53, Some(0.45)),
+        ];
+        let rollups = station_rollups("KPIA", &observations, now);
+        assert!((window(&rollups, 1).total_in - 0.45).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_windows_without_precip_data_are_omitted() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let observations = vec![obs("KPIA", 2, 53, Some(0.0)), obs("KPIA", 11, 53, None)];
+        let rollups = station_rollups("KPIA", &observations, now);
+
+        assert!(rollups.iter().all(|r| r.window_hours >= 24));
+        assert_eq!(window(&rollups, 24).total_in, 0.0);
+    }
+
+    #[test]
+    fn test_basin_rollup_is_mean_of_members() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let observations = vec![
+            obs("KBMI", 11, 53, Some(1.0)),
+            obs("KPIA", 11, 53, Some(0.5)),
+            obs("KSPI", 11, 53, Some(0.25)),
+        ];
+        let locations = vec![
+            location("KBMI", "Mackinaw River"),
+            location("KPIA", "Mackinaw River"),
+            location("KSPI", "Sangamon River"),
+        ];
+        let stations: Vec<PrecipRollup> = ["KBMI", "KPIA", "KSPI"].iter()
+            .flat_map(|s| station_rollups(s, &observations, now))
+            .collect();
+        let basins = basin_rollups(&stations, &locations);
+
+        let mackinaw = basins.iter()
+            .find(|r| r.scope_id == "Mackinaw River" && r.window_hours == 1)
+            .unwrap();
+        assert!((mackinaw.total_in - 0.75).abs() < 1e-9);
+        assert!((mackinaw.max_in - 1.0).abs() < 1e-9);
+        assert_eq!(mackinaw.sample_count, 2);
+        assert_eq!(mackinaw.scope, RollupScope::Basin);
+    }
+}
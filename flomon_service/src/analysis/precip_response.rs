@@ -0,0 +1,269 @@
+//! Expected tributary response to a rain event.
+//!
+//! The floml layer fits one lookup table per basin/gauge pair from the
+//! event catalog: 24-hour basin precipitation against the rise the gauge
+//! went on to make, plus the typical lag from the end of the rain to the
+//! crest. They live in `flood_analysis.precip_response_curves` and are
+//! replaced on every refit, so the daemon re-reads them each cycle.
+//!
+//! Given the current 24-hour basin rollup (`analysis::precip`), a curve
+//! gives a rough "expect about +3 ft on the Mackinaw in ~30 h" as soon as
+//! the rain is in, hours before the gauge itself moves. Lookups interpolate
+//! linearly between points, from (0 in, 0 ft) up to the first point, and
+//! hold the last value beyond the wettest catalog event (flagged as
+//! `beyond_catalog`). The curves are empirical medians: a guide for
+//! attention, not a forecast.
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::analysis::precip::{PrecipRollup, RollupScope};
+
+/// 24-hour basin mean below which no response is reported, in
+pub const MIN_PRECIP_IN: f64 = 0.5;
+
+/// Rollup window the curves are fitted against
+pub const WINDOW_HOURS: i64 = 24;
+
+/// Growth in expected rise that is reported again during an event, ft
+pub const REPORT_STEP_FT: f64 = 0.5;
+
+/// One fitted lookup table
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseCurve {
+    pub basin: String,
+    pub site_code: String,
+    /// (24h precip in, rise ft), precip ascending
+    pub points: Vec<(f64, f64)>,
+    pub lag_hours: f64,
+    pub event_count: i32,
+    pub fitted_at: DateTime<Utc>,
+}
+
+/// What a curve expects from the current rain
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExpectedResponse {
+    pub basin: String,
+    pub site_code: String,
+    pub precip_24h_in: f64,
+    pub expected_rise_ft: f64,
+    /// Rain window end plus the curve's lag
+    pub expected_crest_time: DateTime<Utc>,
+    /// Rain exceeds the wettest event the curve was fitted on
+    pub beyond_catalog: bool,
+    pub event_count: i32,
+    pub fitted_at: DateTime<Utc>,
+}
+
+impl ResponseCurve {
+    /// Build a curve, checking the table is usable
+    pub fn new(
+        basin: String,
+        site_code: String,
+        precip_in: &[f64],
+        rise_ft: &[f64],
+        lag_hours: f64,
+        event_count: i32,
+        fitted_at: DateTime<Utc>,
+    ) -> Result<Self, String> {
+        if precip_in.len() != rise_ft.len() || precip_in.len() < 2 {
+            return Err(format!("{}/{}: curve needs at least two matching points", basin, site_code));
+        }
+        if precip_in.windows(2).any(|w| w[1] <= w[0]) || precip_in[0] < 0.0 {
+            return Err(format!("{}/{}: precipitation points must ascend from zero or above", basin, site_code));
+        }
+        if !lag_hours.is_finite() || lag_hours < 0.0 {
+            return Err(format!("{}/{}: invalid lag {} h", basin, site_code, lag_hours));
+        }
+        Ok(Self {
+            basin,
+            site_code,
+            points: precip_in.iter().copied().zip(rise_ft.iter().copied()).collect(),
+            lag_hours,
+            event_count,
+            fitted_at,
+        })
+    }
+
+    /// Interpolated rise for `precip_in`, and whether it is beyond the last point
+    pub fn rise_for(&self, precip_in: f64) -> (f64, bool) {
+        let (last_p, last_r) = self.points[self.points.len() - 1];
+        if precip_in >= last_p {
+            return (last_r, precip_in > last_p);
+        }
+        let mut previous = (0.0, 0.0);
+        for &(p, r) in &self.points {
+            if precip_in <= p {
+                let (p0, r0) = previous;
+                return (r0 + (r - r0) * (precip_in - p0) / (p - p0), false);
+            }
+            previous = (p, r);
+        }
+        (last_r, false)
+    }
+
+    /// Expected response to `rollup`, a basin rollup for this curve's basin
+    pub fn expect(&self, rollup: &PrecipRollup) -> ExpectedResponse {
+        let (rise, beyond) = self.rise_for(rollup.total_in);
+        ExpectedResponse {
+            basin: self.basin.clone(),
+            site_code: self.site_code.clone(),
+            precip_24h_in: rollup.total_in,
+            expected_rise_ft: rise,
+            expected_crest_time: rollup.window_end + Duration::minutes((self.lag_hours * 60.0).round() as i64),
+            beyond_catalog: beyond,
+            event_count: self.event_count,
+            fitted_at: self.fitted_at,
+        }
+    }
+}
+
+impl ExpectedResponse {
+    pub fn describe(&self) -> String {
+        format!(
+            "{:.2} in over the {} basin in 24h: expect {} to rise about {:.1} ft{} by around {} ({} catalog events)",
+            self.precip_24h_in, self.basin, self.site_code, self.expected_rise_ft,
+            if self.beyond_catalog { " or more (wetter than any catalog event)" } else { "" },
+            self.expected_crest_time.format("%Y-%m-%d %H:%M UTC"), self.event_count,
+        )
+    }
+}
+
+/// Responses for every curve whose basin has at least `MIN_PRECIP_IN` in
+/// its 24-hour rollup
+pub fn expected_responses(curves: &[ResponseCurve], rollups: &[PrecipRollup]) -> Vec<ExpectedResponse> {
+    curves.iter()
+        .filter_map(|curve| {
+            let rollup = rollups.iter().find(|r| {
+                r.scope == RollupScope::Basin && r.window_hours == WINDOW_HOURS && r.scope_id == curve.basin
+            })?;
+            (rollup.total_in >= MIN_PRECIP_IN).then(|| curve.expect(rollup))
+        })
+        .collect()
+}
+
+/// Load all fitted curves; malformed rows are skipped with a warning
+pub fn load_curves(client: &mut Client) -> Result<Vec<ResponseCurve>, String> {
+    let rows = client.query(
+        "SELECT basin, site_code, precip_24h_in, rise_ft, lag_hours, event_count, fitted_at
+         FROM flood_analysis.precip_response_curves
+         ORDER BY basin, site_code",
+        &[],
+    ).map_err(|e| format!("Failed to load precipitation response curves: {}", e))?;
+
+    Ok(rows.iter()
+        .filter_map(|row| {
+            let precip: Vec<f64> = row.get(2);
+            let rise: Vec<f64> = row.get(3);
+            ResponseCurve::new(row.get(0), row.get(1), &precip, &rise, row.get(4), row.get(5), row.get(6))
+                .map_err(|e| crate::logging::warn(crate::logging::DataSource::Database, None, &e))
+                .ok()
+        })
+        .collect())
+}
+
+/// Decides which expected responses are worth logging: the first one of a
+/// rain event, then again only if the expected rise grows by
+/// `REPORT_STEP_FT`. An event ends when its basin drops below `MIN_PRECIP_IN`.
+#[derive(Debug, Default)]
+pub struct ResponseTracker {
+    /// Last reported rise per (basin, site)
+    reported: HashMap<(String, String), f64>,
+}
+
+impl ResponseTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, responses: &[ExpectedResponse]) -> Vec<ExpectedResponse> {
+        let key = |r: &ExpectedResponse| (r.basin.clone(), r.site_code.clone());
+        self.reported.retain(|k, _| responses.iter().any(|r| key(r) == *k));
+
+        responses.iter()
+            .filter(|response| {
+                let previous = self.reported.get(&key(response)).copied();
+                let report = previous.is_none_or(|rise| response.expected_rise_ft >= rise + REPORT_STEP_FT);
+                if report {
+                    self.reported.insert(key(response), response.expected_rise_ft);
+                }
+                report
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()
+    }
+
+    fn mackinaw() -> ResponseCurve {
+        ResponseCurve::new(
+            "Mackinaw River".to_string(), "05568580".to_string(),
+            &[0.5, 1.0, 2.0, 3.0], &[0.5, 1.5, 4.0, 6.0], 30.0, 14, now(),
+        ).unwrap()
+    }
+
+    fn basin(total_in: f64) -> PrecipRollup {
+        PrecipRollup {
+            scope: RollupScope::Basin,
+            scope_id: "Mackinaw River".to_string(),
+            window_hours: 24,
+            window_end: now(),
+            total_in,
+            max_in: total_in,
+            sample_count: 2,
+        }
+    }
+
+    #[test]
+    fn test_lookup_interpolates_and_holds_beyond_catalog() {
+        let curve = mackinaw();
+        assert_eq!(curve.rise_for(0.25), (0.25, false));
+        assert_eq!(curve.rise_for(1.5), (2.75, false));
+        assert_eq!(curve.rise_for(3.0), (6.0, false));
+        assert_eq!(curve.rise_for(4.2), (6.0, true));
+
+        assert!(ResponseCurve::new("b".into(), "s".into(), &[1.0, 1.0], &[1.0, 2.0], 1.0, 1, now()).is_err());
+        assert!(ResponseCurve::new("b".into(), "s".into(), &[1.0], &[1.0], 1.0, 1, now()).is_err());
+    }
+
+    #[test]
+    fn test_responses_only_for_wet_matching_basins() {
+        let curves = [mackinaw()];
+        let response = &expected_responses(&curves, &[basin(2.0)])[0];
+        assert_eq!(response.expected_rise_ft, 4.0);
+        assert_eq!(response.expected_crest_time, now() + Duration::hours(30));
+        assert!(response.describe().contains("expect 05568580 to rise about 4.0 ft by around 2024-05-02 18:00 UTC"));
+
+        assert!(expected_responses(&curves, &[basin(0.3)]).is_empty());
+        let mut hourly = basin(2.0);
+        hourly.window_hours = 1;
+        assert!(expected_responses(&curves, &[hourly]).is_empty());
+    }
+
+    #[test]
+    fn test_tracker_reports_event_start_and_growth_only() {
+        let curves = [mackinaw()];
+        let mut tracker = ResponseTracker::new();
+        assert_eq!(tracker.update(&expected_responses(&curves, &[basin(1.0)])).len(), 1);
+        // +0.25 ft: not enough to repeat
+        assert!(tracker.update(&expected_responses(&curves, &[basin(1.1)])).is_empty());
+        assert_eq!(tracker.update(&expected_responses(&curves, &[basin(1.3)])).len(), 1);
+        // Rain ages out of the window, then a new event starts
+        assert!(tracker.update(&expected_responses(&curves, &[basin(0.2)])).is_empty());
+        assert_eq!(tracker.update(&expected_responses(&curves, &[basin(0.8)])).len(), 1);
+    }
+}
@@ -0,0 +1,226 @@
+//! Estimated stage at ungauged points along the Illinois River.
+//!
+//! Each main-stem gauge with a `river_mile` and a `gauge_datum_ft` in the
+//! registry is a point on the water-surface profile (stage plus datum, as
+//! in `slope`). A point between two gauges gets the elevation interpolated
+//! linearly by river mile between its neighbours, so a property at RM 158
+//! reads the surface between Peoria and Kingston Mines instead of taking
+//! Kingston Mines as its own:
+//!
+//! ```text
+//! elev ^  Peoria (164.6)
+//!      |   *-----___        estimate at RM 158
+//!      |            --x--___
+//!      |                    ---* Kingston Mines (145.6)
+//!      +-----------------------------> river mile (downstream)
+//! ```
+//!
+//! Points are configured in flomon.toml (`[[profile_points]]`) with an
+//! optional local `datum_ft` that turns the elevation back into a stage
+//! (e.g. a dock staff gauge). Outside the gauged reach there is nothing to
+//! interpolate between and no estimate is made.
+//!
+//! # Limits
+//! The line is a straight fall between gauges. It ignores the head drop
+//! at a lock and dam in the reach (Peoria L&D, RM 157.6), which is only
+//! negligible once the wickets are down in open-river conditions, i.e.
+//! during the floods this is for. Gauges with the offsets to reach NGVD29
+//! are placed on it (see `datum`); any left on another datum must all
+//! share it, or the profile is rejected as in `slope`.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::datum::VerticalDatum;
+use crate::stations::Station;
+
+/// A place whose stage is estimated (`[[profile_points]]` in flomon.toml)
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfilePoint {
+    /// Short label, e.g. "Property dock"
+    pub name: String,
+    /// Illinois Waterway river mile (above Grafton)
+    pub river_mile: f64,
+    /// Elevation of zero stage at the point, same datum as the gauges;
+    /// the estimate then includes a local stage
+    pub datum_ft: Option<f64>,
+}
+
+/// One gauge on the profile
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProfileGauge {
+    pub site_code: String,
+    pub river_mile: f64,
+    pub stage_ft: f64,
+    pub water_surface_ft: f64,
+    pub reading_time: DateTime<Utc>,
+}
+
+/// Gauged water surface along the river, upstream first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Profile {
+    /// Vertical datum of every elevation ("NGVD29"); None when empty
+    pub datum: Option<String>,
+    pub gauges: Vec<ProfileGauge>,
+}
+
+/// Interpolated water surface at one point
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StageEstimate {
+    pub name: String,
+    pub river_mile: f64,
+    pub water_surface_ft: f64,
+    /// Stage against the point's own `datum_ft`, when it has one
+    pub stage_ft: Option<f64>,
+    pub upstream_site: String,
+    pub downstream_site: String,
+    /// Older of the two readings the estimate rests on
+    pub as_of: DateTime<Utc>,
+}
+
+/// Profile from the latest stage per site (`site -> (stage, time)`);
+/// stations without a river mile, datum or current stage are left out
+pub fn build(stations: &[Station], latest_stages: &HashMap<String, (f64, DateTime<Utc>)>) -> Result<Profile, String> {
+    let mut datum: Option<VerticalDatum> = None;
+    let mut gauges = Vec::new();
+    for station in stations {
+        let (Some(river_mile), Some((zero_ft, station_datum)), Some(&(stage_ft, reading_time))) = (
+            station.river_mile,
+            station.site_datum().and_then(|d| d.common_zero()),
+            latest_stages.get(&station.site_code),
+        ) else {
+            continue;
+        };
+        match datum {
+            Some(d) if d != station_datum => {
+                return Err(format!(
+                    "{} is referenced to {} but the profile to {}; give it a to_ngvd29_ft offset to convert to one datum",
+                    station.site_code, station_datum, d,
+                ));
+            }
+            _ => datum = Some(station_datum),
+        }
+        gauges.push(ProfileGauge {
+            site_code: station.site_code.clone(),
+            river_mile,
+            stage_ft,
+            water_surface_ft: stage_ft + zero_ft,
+            reading_time,
+        });
+    }
+    gauges.sort_by(|a, b| b.river_mile.total_cmp(&a.river_mile));
+    Ok(Profile { datum: datum.map(|d| d.as_str().to_string()), gauges })
+}
+
+impl Profile {
+    /// Estimate at `point`; None outside the gauged reach
+    pub fn estimate(&self, point: &ProfilePoint) -> Option<StageEstimate> {
+        let (up, down) = self.gauges.windows(2)
+            .map(|pair| (&pair[0], &pair[1]))
+            .find(|(up, down)| up.river_mile >= point.river_mile && point.river_mile >= down.river_mile)?;
+        let reach = up.river_mile - down.river_mile;
+        let water_surface_ft = if reach > 0.0 {
+            let share = (up.river_mile - point.river_mile) / reach;
+            up.water_surface_ft + share * (down.water_surface_ft - up.water_surface_ft)
+        } else {
+            up.water_surface_ft
+        };
+        Some(StageEstimate {
+            name: point.name.clone(),
+            river_mile: point.river_mile,
+            water_surface_ft,
+            stage_ft: point.datum_ft.map(|datum| water_surface_ft - datum),
+            upstream_site: up.site_code.clone(),
+            downstream_site: down.site_code.clone(),
+            as_of: up.reading_time.min(down.reading_time),
+        })
+    }
+}
+
+/// Checks on `[[profile_points]]` entries
+pub fn validate_points(points: &[ProfilePoint]) -> Result<(), String> {
+    let mut seen = std::collections::HashSet::new();
+    for point in points {
+        if !seen.insert(point.name.as_str()) {
+            return Err(format!("profile_points: duplicate name {:?}", point.name));
+        }
+        if !point.river_mile.is_finite() || point.river_mile < 0.0 {
+            return Err(format!("profile_points {:?}: river_mile must be zero or more", point.name));
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn t0() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()
+    }
+
+    fn latest() -> HashMap<String, (f64, DateTime<Utc>)> {
+        HashMap::from([
+            ("05567500".to_string(), (19.0, t0())),                          // Peoria: 448.0
+            ("05568500".to_string(), (22.0, t0() - Duration::minutes(15))),  // Kingston Mines: 442.0
+        ])
+    }
+
+    fn dock(river_mile: f64) -> ProfilePoint {
+        ProfilePoint { name: "dock".to_string(), river_mile, datum_ft: Some(430.0) }
+    }
+
+    #[test]
+    fn test_estimate_interpolates_between_neighbouring_gauges() {
+        let profile = build(&crate::stations::load_stations(), &latest()).unwrap();
+        assert_eq!(profile.datum.as_deref(), Some("NGVD29"));
+        assert_eq!(profile.gauges.iter().map(|g| g.site_code.as_str()).collect::<Vec<_>>(), ["05567500", "05568500"]);
+
+        // RM 158.266..: one third of the way from Peoria (164.6) to Kingston Mines (145.6)
+        let estimate = profile.estimate(&dock(164.6 - 19.0 / 3.0)).unwrap();
+        assert!((estimate.water_surface_ft - 446.0).abs() < 1e-9);
+        assert!((estimate.stage_ft.unwrap() - 16.0).abs() < 1e-9);
+        assert_eq!((estimate.upstream_site.as_str(), estimate.downstream_site.as_str()), ("05567500", "05568500"));
+        assert_eq!(estimate.as_of, t0() - Duration::minutes(15));
+
+        // At a gauge, the gauge itself
+        assert_eq!(profile.estimate(&dock(145.6)).unwrap().water_surface_ft, 442.0);
+    }
+
+    #[test]
+    fn test_no_estimate_outside_the_gauged_reach() {
+        let profile = build(&crate::stations::load_stations(), &latest()).unwrap();
+        assert!(profile.estimate(&dock(120.0)).is_none());
+        assert!(profile.estimate(&dock(170.0)).is_none());
+    }
+
+    #[test]
+    fn test_mixed_datums_rejected() {
+        let mut stations = crate::stations::load_stations();
+        let kingston = stations.iter_mut().find(|s| s.site_code == "05568500").unwrap();
+        kingston.gauge_datum = Some("NAVD88".to_string());
+        assert!(build(&stations, &latest()).unwrap_err().contains("one datum"));
+        assert!(validate_points(&[dock(1.0), dock(2.0)]).unwrap_err().contains("duplicate"));
+    }
+
+    #[test]
+    fn test_gauges_with_offsets_are_converted_to_ngvd29() {
+        let mut stations = crate::stations::load_stations();
+        let kingston = stations.iter_mut().find(|s| s.site_code == "05568500").unwrap();
+        kingston.gauge_datum = Some("NAVD88".to_string());
+        kingston.gauge_datum_ft = Some(419.7);
+        kingston.to_ngvd29_ft = vec![(VerticalDatum::Navd88, 0.3)];
+        let profile = build(&stations, &latest()).unwrap();
+        assert_eq!(profile.datum.as_deref(), Some("NGVD29"));
+        let gauge = profile.gauges.iter().find(|g| g.site_code == "05568500").unwrap();
+        assert!((gauge.water_surface_ft - 442.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,196 @@
+//! Rating drift from field measurements.
+//!
+//! IV discharge is stage pushed through the station's rating curve. When
+//! the channel scours, fills or weeds up, the real stage–discharge relation
+//! moves away from the rating, and every discharge-based threshold and
+//! volume inherits the error until USGS shifts the rating. Field
+//! measurements (`ingest::field_measurements`) are the independent check:
+//! each is compared with the rated discharge nearest its time (within
+//! `MAX_MATCH_MINUTES`).
+//!
+//! One measurement off by 10% is measurement error as often as not. Drift
+//! is flagged when the latest `MIN_MEASUREMENTS` or more usable
+//! measurements within `LOOKBACK_DAYS` all miss the rating by at least
+//! `DRIFT_PERCENT` in the same direction. Poor-quality measurements are
+//! not used. A detection opens a data-quality alert for review
+//! (`data_quality`); nothing is corrected automatically.
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+use serde::Serialize;
+
+use crate::archive;
+use crate::ingest::field_measurements::FieldMeasurement;
+use crate::stations::PARAM_DISCHARGE;
+
+/// Furthest a rated reading may be from the measurement, minutes
+pub const MAX_MATCH_MINUTES: i64 = 30;
+
+/// Measured vs rated difference that counts as a miss, percent
+pub const DRIFT_PERCENT: f64 = 8.0;
+
+/// Consecutive same-direction misses needed
+pub const MIN_MEASUREMENTS: usize = 2;
+
+/// Measurements older than this are not considered
+pub const LOOKBACK_DAYS: i64 = 365;
+
+/// One measurement against the rating
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Comparison {
+    pub measurement_number: String,
+    pub measured_at: DateTime<Utc>,
+    pub measured_cfs: f64,
+    pub rated_cfs: f64,
+    /// (measured - rated) / rated, percent
+    pub percent: f64,
+}
+
+/// Suspected rating drift at one site
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RatingDrift {
+    pub site_code: String,
+    /// First measurement of the drifting run
+    pub since: DateTime<Utc>,
+    /// The drifting run, oldest first
+    pub comparisons: Vec<Comparison>,
+    pub mean_percent: f64,
+}
+
+impl RatingDrift {
+    pub fn describe(&self) -> String {
+        format!(
+            "Possible rating drift since {}: last {} field measurements ran {:+.1}% {} the rated discharge on average",
+            self.since.to_rfc3339(), self.comparisons.len(), self.mean_percent,
+            if self.mean_percent > 0.0 { "above" } else { "below" },
+        )
+    }
+}
+
+/// Compare one measurement with the rated series (time-ordered). None when
+/// the measurement has no usable discharge or no rated reading is close.
+pub fn compare(measurement: &FieldMeasurement, rated: &[(DateTime<Utc>, f64)]) -> Option<Comparison> {
+    let measured_cfs = measurement.discharge_cfs.filter(|q| *q > 0.0)?;
+    if measurement.is_poor() {
+        return None;
+    }
+    let (time, rated_cfs) = rated.iter()
+        .copied()
+        .min_by_key(|(t, _)| (*t - measurement.measured_at).num_seconds().abs())?;
+    if (time - measurement.measured_at).num_minutes().abs() > MAX_MATCH_MINUTES || rated_cfs <= 0.0 {
+        return None;
+    }
+    Some(Comparison {
+        measurement_number: measurement.measurement_number.clone(),
+        measured_at: measurement.measured_at,
+        measured_cfs,
+        rated_cfs,
+        percent: (measured_cfs - rated_cfs) / rated_cfs * 100.0,
+    })
+}
+
+/// Trailing run of same-direction misses in `comparisons` (any order),
+/// if at least `MIN_MEASUREMENTS` long
+pub fn detect(site_code: &str, comparisons: &[Comparison], now: DateTime<Utc>) -> Option<RatingDrift> {
+    let mut recent: Vec<&Comparison> = comparisons.iter()
+        .filter(|c| c.measured_at >= now - Duration::days(LOOKBACK_DAYS))
+        .collect();
+    recent.sort_by_key(|c| c.measured_at);
+
+    let direction = recent.last()?.percent.signum();
+    let mut run: Vec<Comparison> = recent.iter()
+        .rev()
+        .take_while(|c| c.percent.abs() >= DRIFT_PERCENT && c.percent.signum() == direction)
+        .map(|c| (*c).clone())
+        .collect();
+    if run.len() < MIN_MEASUREMENTS {
+        return None;
+    }
+    run.reverse();
+    Some(RatingDrift {
+        site_code: site_code.to_string(),
+        since: run[0].measured_at,
+        mean_percent: run.iter().map(|c| c.percent).sum::<f64>() / run.len() as f64,
+        comparisons: run,
+    })
+}
+
+/// Compare a site's measurements within the lookback with stored rated
+/// discharge and look for drift
+pub fn assess(client: &mut Client, site_code: &str, measurements: &[FieldMeasurement], now: DateTime<Utc>) -> Result<Option<RatingDrift>, String> {
+    let window = Duration::minutes(MAX_MATCH_MINUTES);
+    let mut comparisons = Vec::new();
+    for measurement in measurements.iter().filter(|m| m.measured_at >= now - Duration::days(LOOKBACK_DAYS)) {
+        let rated: Vec<(DateTime<Utc>, f64)> = archive::readings_between(
+            client, site_code, PARAM_DISCHARGE, measurement.measured_at - window, measurement.measured_at + window,
+        )?
+            .iter()
+            .map(|r| (r.datetime.with_timezone(&Utc), r.value))
+            .collect();
+        comparisons.extend(compare(measurement, &rated));
+    }
+    Ok(detect(site_code, &comparisons, now))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 9, 1, 0, 0, 0).unwrap()
+    }
+
+    fn comparison(days_ago: i64, percent: f64) -> Comparison {
+        Comparison {
+            measurement_number: days_ago.to_string(),
+            measured_at: now() - Duration::days(days_ago),
+            measured_cfs: 10_000.0 * (1.0 + percent / 100.0),
+            rated_cfs: 10_000.0,
+            percent,
+        }
+    }
+
+    #[test]
+    fn test_compare_uses_nearest_rated_reading() {
+        let measurement = FieldMeasurement {
+            site_code: "05568500".to_string(),
+            measurement_number: "512".to_string(),
+            measured_at: now(),
+            gage_height_ft: Some(14.6),
+            discharge_cfs: Some(11_000.0),
+            rating: Some("Good".to_string()),
+            used: true,
+        };
+        let rated = [
+            (now() - Duration::minutes(20), 9_000.0),
+            (now() + Duration::minutes(5), 10_000.0),
+        ];
+        let c = compare(&measurement, &rated).unwrap();
+        assert_eq!(c.rated_cfs, 10_000.0);
+        assert!((c.percent - 10.0).abs() < 1e-9);
+
+        assert!(compare(&measurement, &[(now() + Duration::hours(2), 10_000.0)]).is_none());
+        let poor = FieldMeasurement { rating: Some("Poor".to_string()), ..measurement };
+        assert!(compare(&poor, &rated).is_none());
+    }
+
+    #[test]
+    fn test_detect_needs_consecutive_same_direction_misses() {
+        let drifting = [comparison(200, 2.0), comparison(90, -9.0), comparison(40, -12.0)];
+        let drift = detect("05568500", &drifting, now()).unwrap();
+        assert_eq!(drift.since, now() - Duration::days(90));
+        assert_eq!(drift.comparisons.len(), 2);
+        assert!((drift.mean_percent + 10.5).abs() < 1e-9);
+
+        // A single miss, or misses that flip direction, are not drift
+        assert!(detect("05568500", &[comparison(90, 3.0), comparison(40, -12.0)], now()).is_none());
+        assert!(detect("05568500", &[comparison(90, 9.0), comparison(40, -12.0)], now()).is_none());
+        // Old measurements fall out of the lookback
+        assert!(detect("05568500", &[comparison(500, -9.0), comparison(40, -12.0)], now()).is_none());
+    }
+}
@@ -0,0 +1,222 @@
+//! Water-surface slope between Peoria and Kingston Mines.
+//!
+//! Each cycle the latest stage at the Peoria pool gauge and at Kingston
+//! Mines is converted to elevation (stage plus gauge datum, from
+//! `gauge_datum_ft` in usgs_stations.toml) and the fall between them is
+//! divided by the river miles separating them. The result is persisted to
+//! `flood_analysis.stage_slope` together with its trend over the preceding
+//! hours.
+//!
+//! When the Mississippi backs water up the Illinois, the lower gauge rises
+//! faster than the upper one and the slope flattens. That is a direct
+//! hydraulic signature of backwater, so a flattening slope that agrees
+//! with the LaGrange pool/tailwater differential raises the backwater
+//! detector's confidence (see `combine_with_backwater`).
+//!
+//! # Datum
+//! Both gauges must report stage against datums in the same vertical
+//! system; a pair in different systems is rejected rather than silently
+//! producing a slope that is off by the datum difference. The daemon puts
+//! each gauge zero on NGVD29 first where the station has the offsets (see
+//! `datum`), so only a gauge without them can cause this.
+
+use chrono::{DateTime, Utc};
+
+/// Upstream gauge (Illinois River at Peoria, pool)
+pub const UPSTREAM_SITE: &str = "05567500";
+
+/// Downstream gauge (Illinois River at Kingston Mines)
+pub const DOWNSTREAM_SITE: &str = "05568500";
+
+/// Hours of slope history used for the trend
+pub const TREND_WINDOW_HOURS: i64 = 6;
+
+/// Slope below which the reach is considered backwatered, ft/mile
+pub const FLAT_SLOPE_FT_PER_MILE: f64 = 0.1;
+
+/// Change in slope per hour that counts as a trend, ft/mile/hour
+const TREND_TOLERANCE_FT_PER_MILE_HR: f64 = 0.005;
+
+/// Stage converted to elevation at one gauge
+#[derive(Debug, Clone, PartialEq)]
+pub struct GaugeElevation {
+    pub site_code: String,
+    pub stage_ft: f64,
+    pub datum_ft: f64,
+    /// Vertical system of `datum_ft` ("NGVD29", "NAVD88")
+    pub datum: String,
+    pub reading_time: DateTime<Utc>,
+}
+
+impl GaugeElevation {
+    pub fn elevation_ft(&self) -> f64 {
+        self.stage_ft + self.datum_ft
+    }
+}
+
+/// Direction the slope is moving
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlopeTrend {
+    Steepening,
+    Steady,
+    Flattening,
+}
+
+impl SlopeTrend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SlopeTrend::Steepening => "steepening",
+            SlopeTrend::Steady => "steady",
+            SlopeTrend::Flattening => "flattening",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "steepening" => Some(SlopeTrend::Steepening),
+            "steady" => Some(SlopeTrend::Steady),
+            "flattening" => Some(SlopeTrend::Flattening),
+            _ => None,
+        }
+    }
+}
+
+/// One slope sample
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageSlope {
+    /// Later of the two reading times
+    pub timestamp: DateTime<Utc>,
+    pub upstream_elevation_ft: f64,
+    pub downstream_elevation_ft: f64,
+    pub reach_miles: f64,
+    /// Fall per river mile; negative if the downstream gauge is higher
+    pub slope_ft_per_mile: f64,
+    pub datum: String,
+}
+
+/// Slope between two gauges `reach_miles` apart
+pub fn stage_slope(upstream: &GaugeElevation, downstream: &GaugeElevation, reach_miles: f64) -> Result<StageSlope, String> {
+    if upstream.datum != downstream.datum {
+        return Err(format!(
+            "{} is referenced to {} but {} to {}; convert to one datum first",
+            upstream.site_code, upstream.datum, downstream.site_code, downstream.datum,
+        ));
+    }
+    if reach_miles <= 0.0 {
+        return Err(format!("Reach length must be positive (got {} mi)", reach_miles));
+    }
+    let (up, down) = (upstream.elevation_ft(), downstream.elevation_ft());
+    Ok(StageSlope {
+        timestamp: upstream.reading_time.max(downstream.reading_time),
+        upstream_elevation_ft: up,
+        downstream_elevation_ft: down,
+        reach_miles,
+        slope_ft_per_mile: (up - down) / reach_miles,
+        datum: upstream.datum.clone(),
+    })
+}
+
+/// Least-squares rate of change of the slope (ft/mile/hour) and its
+/// direction over `history` (any order, at least two distinct times)
+pub fn slope_trend(history: &[(DateTime<Utc>, f64)]) -> Option<(SlopeTrend, f64)> {
+    let first = history.iter().map(|(t, _)| *t).min()?;
+    let points: Vec<(f64, f64)> = history.iter()
+        .map(|(t, slope)| ((*t - first).num_seconds() as f64 / 3600.0, *slope))
+        .collect();
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if sxx == 0.0 {
+        return None;
+    }
+    let rate = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum::<f64>() / sxx;
+
+    let trend = if rate <= -TREND_TOLERANCE_FT_PER_MILE_HR {
+        SlopeTrend::Flattening
+    } else if rate >= TREND_TOLERANCE_FT_PER_MILE_HR {
+        SlopeTrend::Steepening
+    } else {
+        SlopeTrend::Steady
+    };
+    Some((trend, rate))
+}
+
+/// Backwater risk and confidence once the slope is taken into account.
+///
+/// `risk_level` is the pool/tailwater assessment ("LOW" .. "CRITICAL",
+/// or "UNKNOWN"). A flattening or already-flat slope confirms it (HIGH
+/// confidence) and lifts MODERATE to HIGH; a steepening slope contradicts
+/// it (LOW confidence). Without a slope the confidence is MEDIUM.
+pub fn combine_with_backwater(risk_level: &str, slope: Option<(f64, SlopeTrend)>) -> (String, &'static str) {
+    let Some((slope_ft_per_mile, trend)) = slope else {
+        return (risk_level.to_string(), "MEDIUM");
+    };
+    let backwater_signature = trend == SlopeTrend::Flattening || slope_ft_per_mile < FLAT_SLOPE_FT_PER_MILE;
+    let elevated = matches!(risk_level, "MODERATE" | "HIGH" | "CRITICAL");
+
+    match (elevated, backwater_signature, trend) {
+        (true, true, _) if risk_level == "MODERATE" => ("HIGH".to_string(), "HIGH"),
+        (true, true, _) => (risk_level.to_string(), "HIGH"),
+        (true, false, SlopeTrend::Steepening) => (risk_level.to_string(), "LOW"),
+        _ => (risk_level.to_string(), "MEDIUM"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn gauge(site: &str, stage: f64, datum_ft: f64, datum: &str) -> GaugeElevation {
+        GaugeElevation {
+            site_code: site.to_string(),
+            stage_ft: stage,
+            datum_ft,
+            datum: datum.to_string(),
+            reading_time: Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_slope_uses_elevations() {
+        let slope = stage_slope(
+            &gauge(UPSTREAM_SITE, 18.0, 429.0, "NGVD29"),
+            &gauge(DOWNSTREAM_SITE, 21.0, 420.0, "NGVD29"),
+            10.0,
+        ).unwrap();
+        assert_eq!(slope.upstream_elevation_ft, 447.0);
+        assert_eq!(slope.downstream_elevation_ft, 441.0);
+        assert!((slope.slope_ft_per_mile - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_mixed_datums_rejected() {
+        let err = stage_slope(
+            &gauge(UPSTREAM_SITE, 18.0, 429.0, "NGVD29"),
+            &gauge(DOWNSTREAM_SITE, 21.0, 420.0, "NAVD88"),
+            10.0,
+        ).unwrap_err();
+        assert!(err.contains("NAVD88"));
+    }
+
+    #[test]
+    fn test_trend_and_combination() {
+        let t0 = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        let history: Vec<_> = (0..6)
+            .map(|h| (t0 + chrono::Duration::hours(h), 0.40 - 0.02 * h as f64))
+            .collect();
+        let (trend, rate) = slope_trend(&history).unwrap();
+        assert_eq!(trend, SlopeTrend::Flattening);
+        assert!((rate + 0.02).abs() < 1e-9);
+
+        assert_eq!(combine_with_backwater("MODERATE", Some((0.3, trend))), ("HIGH".to_string(), "HIGH"));
+        assert_eq!(combine_with_backwater("HIGH", Some((0.3, SlopeTrend::Steepening))), ("HIGH".to_string(), "LOW"));
+        assert_eq!(combine_with_backwater("HIGH", None), ("HIGH".to_string(), "MEDIUM"));
+        assert!(slope_trend(&history[..1]).is_none());
+    }
+}
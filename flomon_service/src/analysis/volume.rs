@@ -0,0 +1,249 @@
+//! Water volume passed over an event window.
+//!
+//! Integrating discharge (trapezoidal, cfs × hours → acre-feet) over an
+//! event gives the total water that went past Kingston Mines, the figure a
+//! post-event summary leads with. The same integral for each upstream
+//! gauge, over the window shifted earlier by its travel time, shows where
+//! that water came from.
+//!
+//! The gauged inflows to the Kingston Mines reach are the mainstem at
+//! Peoria and the Mackinaw (`INFLOW_SITES`); their lagged sum against the
+//! outlet volume is a continuity check. A residual of a few percent is
+//! ungauged local inflow and rating error; a large one points at a rating
+//! shift, a bad lag, or missing data. The residual is only reported when
+//! every inflow and the outlet cover at least `MIN_COVERAGE` of the window,
+//! since a gap integrates as zero water. Intervals longer than
+//! `window_stats::MAX_GAP_MINUTES` count as gaps.
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+use serde::Serialize;
+
+use crate::analysis::window_stats::MAX_GAP_MINUTES;
+use crate::archive;
+use crate::stations::{Station, PARAM_DISCHARGE};
+
+/// One cfs flowing for one hour, in acre-feet (3600 ft³ / 43560 ft²)
+pub const ACRE_FT_PER_CFS_HOUR: f64 = 3600.0 / 43_560.0;
+
+/// Gauge whose volume is accounted for
+pub const OUTLET_SITE: &str = "05568500";
+
+/// Gauged inflows to the outlet reach: Illinois River at Peoria, Mackinaw
+/// River near Green Valley
+pub const INFLOW_SITES: [&str; 2] = ["05567500", "05568580"];
+
+/// Fraction of the window a site must cover to enter the continuity check
+pub const MIN_COVERAGE: f64 = 0.9;
+
+/// Integral of one discharge series
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Integral {
+    pub acre_ft: f64,
+    pub hours_covered: f64,
+}
+
+/// Volume one gauge passed over its (lagged) window
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SiteVolume {
+    pub site_code: String,
+    pub name: String,
+    /// Travel time to the outlet; the window is shifted earlier by this
+    pub lag_hours: f64,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub acre_ft: f64,
+    /// Share of the window with data, 0–1
+    pub coverage: f64,
+    /// This volume over the outlet's
+    pub ratio_to_outlet: Option<f64>,
+    /// Counted in the continuity check
+    pub inflow: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VolumeAccount {
+    pub outlet: SiteVolume,
+    pub upstream: Vec<SiteVolume>,
+    /// Lagged sum of `INFLOW_SITES`
+    pub inflow_acre_ft: Option<f64>,
+    /// Outlet minus inflow (ungauged inflow plus error)
+    pub residual_acre_ft: Option<f64>,
+    pub residual_percent: Option<f64>,
+}
+
+/// Trapezoidal integral of time-ordered discharge readings (cfs), skipping
+/// intervals longer than `MAX_GAP_MINUTES`
+pub fn integrate(series: &[(DateTime<Utc>, f64)]) -> Integral {
+    let mut integral = Integral { acre_ft: 0.0, hours_covered: 0.0 };
+    for pair in series.windows(2) {
+        let ((t0, q0), (t1, q1)) = (pair[0], pair[1]);
+        let interval = t1 - t0;
+        if interval <= Duration::zero() || interval > Duration::minutes(MAX_GAP_MINUTES) {
+            continue;
+        }
+        let hours = interval.num_seconds() as f64 / 3600.0;
+        integral.acre_ft += (q0 + q1) / 2.0 * hours * ACRE_FT_PER_CFS_HOUR;
+        integral.hours_covered += hours;
+    }
+    integral
+}
+
+/// Stations compared against the outlet: the inflows and every mainstem
+/// gauge upstream, with their travel times
+pub fn upstream_stations(stations: &[Station]) -> Vec<&Station> {
+    stations.iter()
+        .filter(|s| s.site_code != OUTLET_SITE && s.expected_parameters.iter().any(|p| p == PARAM_DISCHARGE))
+        .filter(|s| INFLOW_SITES.contains(&s.site_code.as_str()) || s.distance_direction.starts_with("upstream"))
+        .collect()
+}
+
+/// Combine per-site volumes into the account; ratios and the continuity
+/// residual need a covered outlet
+pub fn account(outlet: SiteVolume, mut upstream: Vec<SiteVolume>) -> VolumeAccount {
+    let outlet_ok = outlet.coverage >= MIN_COVERAGE && outlet.acre_ft > 0.0;
+    for site in &mut upstream {
+        site.ratio_to_outlet = outlet_ok.then(|| site.acre_ft / outlet.acre_ft);
+    }
+
+    let inflows: Vec<&SiteVolume> = upstream.iter().filter(|s| s.inflow).collect();
+    let inflow_acre_ft = (inflows.len() == INFLOW_SITES.len() && inflows.iter().all(|s| s.coverage >= MIN_COVERAGE))
+        .then(|| inflows.iter().map(|s| s.acre_ft).sum::<f64>());
+    let residual_acre_ft = inflow_acre_ft.filter(|_| outlet_ok).map(|inflow| outlet.acre_ft - inflow);
+
+    VolumeAccount {
+        residual_percent: residual_acre_ft.map(|r| r / outlet.acre_ft * 100.0),
+        residual_acre_ft,
+        inflow_acre_ft,
+        upstream,
+        outlet,
+    }
+}
+
+/// Volume one station passed over [start, end] shifted earlier by `lag_hours`
+fn site_volume(client: &mut Client, station: &Station, lag_hours: f64, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<SiteVolume, String> {
+    let lag = Duration::minutes((lag_hours * 60.0).round() as i64);
+    let (window_start, window_end) = (start - lag, end - lag);
+    let series: Vec<(DateTime<Utc>, f64)> = archive::readings_between(client, &station.site_code, PARAM_DISCHARGE, window_start, window_end)?
+        .iter()
+        .map(|r| (r.datetime.with_timezone(&Utc), r.value))
+        .collect();
+    let integral = integrate(&series);
+    let window_hours = (window_end - window_start).num_seconds() as f64 / 3600.0;
+
+    Ok(SiteVolume {
+        site_code: station.site_code.clone(),
+        name: station.name.clone(),
+        lag_hours,
+        window_start,
+        window_end,
+        acre_ft: integral.acre_ft,
+        coverage: if window_hours > 0.0 { (integral.hours_covered / window_hours).min(1.0) } else { 0.0 },
+        ratio_to_outlet: None,
+        inflow: INFLOW_SITES.contains(&station.site_code.as_str()),
+    })
+}
+
+/// Volume account for the outlet over [start, end]
+pub fn compute(client: &mut Client, stations: &[Station], start: DateTime<Utc>, end: DateTime<Utc>) -> Result<VolumeAccount, String> {
+    let outlet_station = stations.iter()
+        .find(|s| s.site_code == OUTLET_SITE)
+        .ok_or_else(|| format!("Outlet station {} is not configured", OUTLET_SITE))?;
+    let outlet = site_volume(client, outlet_station, 0.0, start, end)?;
+    let upstream = upstream_stations(stations).into_iter()
+        .map(|station| site_volume(client, station, station.travel_time_to_peoria_hours, start, end))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(account(outlet, upstream))
+}
+
+/// Start and end of a time window
+pub type Window = (DateTime<Utc>, DateTime<Utc>);
+
+/// Window of a catalogued event: its start to its end (or now, if ongoing)
+pub fn event_window(client: &mut Client, event_id: i32, now: DateTime<Utc>) -> Result<Option<Window>, String> {
+    let row = client.query_opt(
+        "SELECT event_start, event_end FROM flood_analysis.events WHERE id = $1",
+        &[&event_id],
+    ).map_err(|e| format!("Failed to look up event {}: {}", event_id, e))?;
+    Ok(row.map(|row| {
+        let end: Option<DateTime<Utc>> = row.get(1);
+        (row.get(0), end.unwrap_or(now))
+    }))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn t0() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap()
+    }
+
+    fn site(code: &str, acre_ft: f64, coverage: f64) -> SiteVolume {
+        SiteVolume {
+            site_code: code.to_string(),
+            name: code.to_string(),
+            lag_hours: 0.0,
+            window_start: t0(),
+            window_end: t0() + Duration::days(1),
+            acre_ft,
+            coverage,
+            ratio_to_outlet: None,
+            inflow: INFLOW_SITES.contains(&code),
+        }
+    }
+
+    #[test]
+    fn test_integrate_constant_and_gapped_flow() {
+        // 1000 cfs for a day is about 1983 acre-feet
+        let steady: Vec<_> = (0..=96).map(|i| (t0() + Duration::minutes(15 * i), 1000.0)).collect();
+        let integral = integrate(&steady);
+        assert!((integral.acre_ft - 1983.47).abs() < 0.01);
+        assert_eq!(integral.hours_covered, 24.0);
+
+        // A rise from 0 to 2000 cfs over an hour averages 1000
+        let ramp = [(t0(), 0.0), (t0() + Duration::hours(1), 2000.0)];
+        assert!((integrate(&ramp).acre_ft - 1000.0 * ACRE_FT_PER_CFS_HOUR).abs() < 1e-9);
+
+        let gapped = [(t0(), 1000.0), (t0() + Duration::hours(6), 1000.0), (t0() + Duration::hours(7), 1000.0)];
+        assert_eq!(integrate(&gapped).hours_covered, 1.0);
+    }
+
+    #[test]
+    fn test_account_residual_needs_full_coverage() {
+        let result = account(site(OUTLET_SITE, 100_000.0, 1.0), vec![
+            site("05567500", 85_000.0, 0.98),
+            site("05568580", 9_000.0, 0.95),
+            site("05557000", 80_000.0, 0.5),
+        ]);
+        assert_eq!(result.inflow_acre_ft, Some(94_000.0));
+        assert_eq!(result.residual_acre_ft, Some(6_000.0));
+        assert!((result.residual_percent.unwrap() - 6.0).abs() < 1e-9);
+        assert_eq!(result.upstream[0].ratio_to_outlet, Some(0.85));
+
+        let result = account(site(OUTLET_SITE, 100_000.0, 1.0), vec![
+            site("05567500", 85_000.0, 0.98),
+            site("05568580", 4_000.0, 0.4),
+        ]);
+        assert_eq!(result.inflow_acre_ft, None);
+        assert_eq!(result.residual_percent, None);
+
+        let result = account(site(OUTLET_SITE, 60_000.0, 0.6), vec![site("05567500", 85_000.0, 1.0)]);
+        assert_eq!(result.upstream[0].ratio_to_outlet, None);
+    }
+
+    #[test]
+    fn test_upstream_stations_are_inflows_and_mainstem() {
+        let stations = crate::stations::load_stations();
+        let codes: Vec<&str> = upstream_stations(&stations).iter().map(|s| s.site_code.as_str()).collect();
+        assert!(INFLOW_SITES.iter().all(|code| codes.contains(code)));
+        assert!(codes.contains(&"05568000"));
+        // Spoon River joins below the outlet
+        assert!(!codes.contains(&"05570000") && !codes.contains(&OUTLET_SITE));
+    }
+}
@@ -0,0 +1,192 @@
+//! Summary statistics for one parameter over an arbitrary time window.
+//!
+//! Backs `GET /api/stations/{site}/stats` so a post-event report can quote
+//! peak, spread and time above flood levels without exporting the raw
+//! series. Percentiles interpolate linearly between sorted readings.
+//!
+//! Threshold durations are time-weighted: each interval between
+//! consecutive readings counts for the part of it spent at or above the
+//! level, assuming a straight line between the two readings. Intervals
+//! longer than `MAX_GAP_MINUTES` are data gaps and count toward nothing.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::model::FloodThresholds;
+
+/// Longest interval between readings that is treated as continuous
+pub const MAX_GAP_MINUTES: i64 = 120;
+
+/// Percentiles reported, in percent
+pub const PERCENTILES: [u8; 5] = [10, 25, 50, 75, 90];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Percentile {
+    pub percent: u8,
+    pub value: f64,
+}
+
+/// Time spent at or above one threshold
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Exceedance {
+    /// "action", "flood", "moderate" or "major"
+    pub level: String,
+    pub threshold: f64,
+    pub hours_above: f64,
+    /// First and last reading at or above the level
+    pub first_reached: Option<DateTime<Utc>>,
+    pub last_above: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WindowStats {
+    pub count: usize,
+    pub first_time: DateTime<Utc>,
+    pub last_time: DateTime<Utc>,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub percentiles: Vec<Percentile>,
+    /// Time of the maximum (the earliest, if repeated)
+    pub peak_time: DateTime<Utc>,
+    /// Peak minus the lowest reading before it
+    pub total_rise: f64,
+    /// Last reading minus first
+    pub net_change: f64,
+    /// Hours covered by intervals no longer than `MAX_GAP_MINUTES`
+    pub hours_covered: f64,
+    pub exceedances: Vec<Exceedance>,
+}
+
+/// Named levels from a station's NWS thresholds, ascending
+pub fn threshold_levels(thresholds: &FloodThresholds) -> Vec<(&'static str, f64)> {
+    vec![
+        ("action", thresholds.action_stage_ft),
+        ("flood", thresholds.flood_stage_ft),
+        ("moderate", thresholds.moderate_flood_stage_ft),
+        ("major", thresholds.major_flood_stage_ft),
+    ]
+}
+
+/// Statistics over time-ordered `readings`; None when there are none
+pub fn summarize(readings: &[(DateTime<Utc>, f64)], levels: &[(&str, f64)]) -> Option<WindowStats> {
+    let (first, last) = (readings.first()?, readings.last()?);
+
+    let mut sorted: Vec<f64> = readings.iter().map(|r| r.1).collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mean = sorted.iter().sum::<f64>() / sorted.len() as f64;
+
+    let mut peak = first;
+    let mut low_before_peak = first.1;
+    let mut running_low = first.1;
+    for reading in readings {
+        running_low = running_low.min(reading.1);
+        if reading.1 > peak.1 {
+            peak = reading;
+            low_before_peak = running_low;
+        }
+    }
+
+    let continuous = || readings.windows(2)
+        .filter(|w| (w[1].0 - w[0].0).num_minutes() <= MAX_GAP_MINUTES);
+    let hours = |w: &[(DateTime<Utc>, f64)]| (w[1].0 - w[0].0).num_seconds() as f64 / 3600.0;
+
+    Some(WindowStats {
+        count: readings.len(),
+        first_time: first.0,
+        last_time: last.0,
+        min: sorted[0],
+        max: sorted[sorted.len() - 1],
+        mean,
+        percentiles: PERCENTILES.iter()
+            .map(|&percent| Percentile { percent, value: percentile(&sorted, percent as f64) })
+            .collect(),
+        peak_time: peak.0,
+        total_rise: peak.1 - low_before_peak,
+        net_change: last.1 - first.1,
+        hours_covered: continuous().map(hours).sum(),
+        exceedances: levels.iter()
+            .map(|&(level, threshold)| Exceedance {
+                level: level.to_string(),
+                threshold,
+                hours_above: continuous().map(|w| hours(w) * fraction_above(w[0].1, w[1].1, threshold)).sum(),
+                first_reached: readings.iter().find(|r| r.1 >= threshold).map(|r| r.0),
+                last_above: readings.iter().rev().find(|r| r.1 >= threshold).map(|r| r.0),
+            })
+            .collect(),
+    })
+}
+
+/// Linear-interpolated percentile of ascending `sorted`
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    let rank = percent / 100.0 * (sorted.len() - 1) as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+}
+
+/// Share of a straight segment from `a` to `b` at or above `level`
+fn fraction_above(a: f64, b: f64, level: f64) -> f64 {
+    match (a >= level, b >= level) {
+        (true, true) => 1.0,
+        (false, false) => 0.0,
+        (true, false) => (a - level) / (a - b),
+        (false, true) => (b - level) / (b - a),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn t(hour: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap() + Duration::hours(hour)
+    }
+
+    #[test]
+    fn test_hydrograph_summary() {
+        // 15 -> 21 ft crest at hour 4, receding to 17
+        let stage = [15.0, 16.0, 18.0, 20.0, 21.0, 20.0, 19.0, 18.0, 17.0];
+        let readings: Vec<_> = stage.iter().enumerate().map(|(i, &v)| (t(i as i64), v)).collect();
+        let stats = summarize(&readings, &[("flood", 18.0), ("major", 25.0)]).unwrap();
+
+        assert_eq!(stats.count, 9);
+        assert_eq!((stats.min, stats.max), (15.0, 21.0));
+        assert!((stats.mean - 164.0 / 9.0).abs() < 1e-9);
+        assert_eq!(stats.peak_time, t(4));
+        assert_eq!(stats.total_rise, 6.0);
+        assert_eq!(stats.net_change, 2.0);
+        assert_eq!(stats.hours_covered, 8.0);
+        assert_eq!(stats.percentiles[2], Percentile { percent: 50, value: 18.0 });
+
+        // At or above 18 ft from hour 2 through hour 7
+        let flood = &stats.exceedances[0];
+        assert_eq!(flood.hours_above, 5.0);
+        assert_eq!((flood.first_reached, flood.last_above), (Some(t(2)), Some(t(7))));
+        let major = &stats.exceedances[1];
+        assert_eq!((major.hours_above, major.first_reached), (0.0, None));
+    }
+
+    #[test]
+    fn test_crossings_interpolate_and_gaps_are_skipped() {
+        // Crosses 18 ft halfway through the first hour, then a 6-hour gap
+        let readings = vec![(t(0), 17.0), (t(1), 19.0), (t(7), 19.0), (t(8), 19.0)];
+        let stats = summarize(&readings, &[("flood", 18.0)]).unwrap();
+        assert_eq!(stats.exceedances[0].hours_above, 1.5);
+        assert_eq!(stats.hours_covered, 2.0);
+    }
+
+    #[test]
+    fn test_rise_is_measured_from_low_before_peak() {
+        // Falls to 10 before rising to 14; the later dip to 8 doesn't count
+        let readings = vec![(t(0), 12.0), (t(1), 10.0), (t(2), 14.0), (t(3), 8.0)];
+        let stats = summarize(&readings, &[]).unwrap();
+        assert_eq!(stats.total_rise, 4.0);
+        assert_eq!(stats.min, 8.0);
+        assert!(summarize(&[], &[]).is_none());
+    }
+}
@@ -0,0 +1,292 @@
+//! Dashboard API: resource-style routes the dashboard reads through
+//! instead of querying the database directly.
+//!
+//! - GET /api/stations?site=&as_of= - Every station with its latest readings (`/api/latest`)
+//! - GET /api/stations/{site}/latest?as_of= - One station (`/api/latest?site=`)
+//! - GET /api/zones?site= - Zones with metadata (`/zones`), or the zones with a sensor on `site`
+//! - GET /api/alerts/active?site=&as_of= - Current alerts (`/api/alerts`)
+//!
+//! Each answers as the route it names, narrowed to one station by `site`;
+//! a site not in the registry is 404 rather than an empty result.
+//! `endpoint` recognizes the routes with `DashboardRoute::parse` and hands
+//! them to `handle`.
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+use std::collections::HashMap;
+
+use crate::analysis::frequency::FrequencyCurve;
+use crate::archive;
+use crate::cams;
+use crate::cli_output;
+use crate::endpoint::{create_response, fetch_zones_list};
+use crate::ingest::{usgs_peaks, usgs_stats};
+use crate::model::GaugeReading;
+use crate::registry;
+use crate::stations::{self, Station};
+use crate::status_cache::SnapshotReader;
+use crate::threshold_overrides;
+use crate::zones;
+
+/// How far back /api/latest and /api/alerts look for a station's latest
+/// reading; older ones are left out rather than reported stale
+pub(crate) const LATEST_LOOKBACK_DAYS: i64 = 7;
+
+/// Dashboard API routes and the station each is about: `{site}` in the
+/// path or the optional `?site=` (`/api/latest` and `/api/alerts` are
+/// older spellings)
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum DashboardRoute {
+    /// GET /api/stations
+    Stations(Option<String>),
+    /// GET /api/stations/{site}/latest
+    StationLatest(String),
+    /// GET /api/zones
+    Zones(Option<String>),
+    /// GET /api/alerts/active
+    ActiveAlerts(Option<String>),
+}
+
+impl DashboardRoute {
+    pub(crate) fn parse(url: &str, query: &HashMap<String, String>) -> Option<Self> {
+        let site = query.get("site").cloned();
+        match url {
+            "/api/stations" | "/api/latest" => Some(DashboardRoute::Stations(site)),
+            "/api/zones" => Some(DashboardRoute::Zones(site)),
+            "/api/alerts/active" | "/api/alerts" => Some(DashboardRoute::ActiveAlerts(site)),
+            _ => url.strip_prefix("/api/stations/")
+                .and_then(|rest| rest.strip_suffix("/latest"))
+                .map(|site| DashboardRoute::StationLatest(site.to_string())),
+        }
+    }
+
+    fn site(&self) -> Option<&str> {
+        match self {
+            DashboardRoute::StationLatest(site) => Some(site),
+            DashboardRoute::Stations(site) | DashboardRoute::Zones(site) | DashboardRoute::ActiveAlerts(site) => site.as_deref(),
+        }
+    }
+}
+
+/// 404 for a dashboard request about a station not in the registry, so an
+/// unknown site is not mistaken for one with nothing to report
+fn unknown_station(route: &DashboardRoute, stations: &[Station]) -> Option<tiny_http::Response<std::io::Cursor<Vec<u8>>>> {
+    let site = route.site()?;
+    (!stations.iter().any(|s| s.site_code == site))
+        .then(|| create_response(404, serde_json::json!({"error": format!("Unknown station {}", site)})))
+}
+
+/// Answer a dashboard route: 404 for an unknown station, otherwise the
+/// route it names (live alerts from the status snapshot while it is fresh)
+pub(crate) fn handle(
+    client: &mut Client,
+    route: DashboardRoute,
+    query: &HashMap<String, String>,
+    snapshot: Option<&mut SnapshotReader>,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    if let Some(response) = unknown_station(&route, &stations::load_stations()) {
+        return response;
+    }
+    match route {
+        DashboardRoute::Stations(_) => handle_latest(client, query),
+        DashboardRoute::StationLatest(site) => {
+            let mut query = query.clone();
+            query.insert("site".to_string(), site);
+            handle_latest(client, &query)
+        }
+        DashboardRoute::Zones(site) => handle_zones(client, site.as_deref()),
+        DashboardRoute::ActiveAlerts(site) => {
+            let cached = snapshot
+                .filter(|_| site.is_none() && !query.contains_key("as_of"))
+                .and_then(|s| s.alerts(Utc::now()));
+            match cached {
+                Some(alerts) => create_response(200, alerts),
+                None => handle_alerts(client, query),
+            }
+        }
+    }
+}
+
+/// Handle GET /api/zones: the /zones list, or only the zones with a sensor
+/// on `site`
+fn handle_zones(client: &mut Client, site: Option<&str>) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let result = fetch_zones_list(client).and_then(|mut data| {
+        if let Some(site) = site {
+            let config = zones::load_zones_default()
+                .map_err(|e| format!("Failed to load zones.toml: {}", e))?;
+            let ids = zones::zones_for_site(&config, site);
+            data.zones.retain(|z| ids.contains(&z.zone_id));
+        }
+        Ok(data)
+    });
+    match result {
+        Ok(data) => create_response(200, serde_json::to_value(&data).unwrap()),
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
+}
+
+/// Handle GET /api/latest: every station (or `site`) with its latest
+/// readings, as `station show --json` reports them
+fn handle_latest(client: &mut Client, query: &HashMap<String, String>) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let now = Utc::now();
+    let as_of = match as_of_param(query, now) {
+        Ok(as_of) => as_of,
+        Err(e) => return create_response(400, serde_json::json!({"error": e})),
+    };
+    let at = as_of.unwrap_or(now);
+    let site = query.get("site").map(String::as_str);
+    
+    let result = registry_at(client, as_of).and_then(|(stations, _)| {
+        let readings = archive::latest_known(client, site, false, at, Duration::days(LATEST_LOOKBACK_DAYS))?;
+        Ok(stations.iter()
+            .filter(|s| site.is_none_or(|code| s.site_code == code))
+            .map(|station| {
+                let latest: Vec<GaugeReading> = readings.iter()
+                    .filter(|r| r.site_code == station.site_code)
+                    .cloned()
+                    .collect();
+                cli_output::station_detail(station, &latest, at)
+            })
+            .collect::<Vec<_>>())
+    }).and_then(|mut details| {
+        // Discharge percentile for the date, from the USGS daily statistics,
+        // and return period, from the annual peaks
+        for detail in &mut details {
+            if !detail.latest.iter().any(|r| r.parameter_code == stations::PARAM_DISCHARGE) {
+                continue;
+            }
+            let curve = FrequencyCurve::fit(&usgs_peaks::load(client, &detail.site_code)?);
+            for reading in detail.latest.iter_mut().filter(|r| r.parameter_code == stations::PARAM_DISCHARGE) {
+                reading.percentile = usgs_stats::percentile_at(
+                    client, &detail.site_code, &reading.parameter_code, reading.value, reading.reading_time,
+                )?.map(|p| (p * 10.0).round() / 10.0);
+                reading.return_period_years = curve.as_ref()
+                    .map(|curve| (curve.return_period_years(reading.value) * 10.0).round() / 10.0);
+            }
+        }
+        Ok(details)
+    });
+    match result {
+        Ok(details) if details.is_empty() && site.is_some() => {
+            create_response(404, serde_json::json!({"error": format!("Unknown station {}", site.unwrap_or_default())}))
+        }
+        Ok(details) => create_response(200, serde_json::to_value(cli_output::envelope("latest", details, at)).unwrap()),
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
+}
+
+/// Handle GET /api/alerts: stations (or `site`) at or above action stage,
+/// as `alerts list --json` reports them
+fn handle_alerts(client: &mut Client, query: &HashMap<String, String>) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let now = Utc::now();
+    let as_of = match as_of_param(query, now) {
+        Ok(as_of) => as_of,
+        Err(e) => return create_response(400, serde_json::json!({"error": e})),
+    };
+    let at = as_of.unwrap_or(now);
+    let site = query.get("site").map(String::as_str);
+    
+    let result = registry_at(client, as_of).and_then(|(stations, version)| {
+        let stages = archive::latest_known(client, site, true, at, Duration::days(LATEST_LOOKBACK_DAYS))?;
+        let mut alerts = cli_output::active_alerts(&stations, &stages, version, at);
+        alerts.retain(|alert| site.is_none_or(|code| alert.site_code == code));
+        // Snapshot links are only meaningful for the live view
+        if as_of.is_none() {
+            for alert in &mut alerts {
+                alert.snapshots = cams::recent_snapshot_links(client, &alert.site_code, cams::recent_since(at))
+                    .unwrap_or_default();
+            }
+        }
+        Ok(alerts)
+    });
+    match result {
+        Ok(alerts) => create_response(200, serde_json::to_value(cli_output::envelope("alerts", alerts, at)).unwrap()),
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
+}
+
+/// Stations and registry version in effect at `as_of` (default: the
+/// current registry with its threshold overrides). Before any version was
+/// recorded, the current registry with no version.
+pub(crate) fn registry_at(client: &mut Client, as_of: Option<DateTime<Utc>>) -> Result<(Vec<Station>, Option<i32>), String> {
+    let stations = stations::load_stations();
+    match as_of {
+        None => {
+            let (stations, _) = threshold_overrides::apply(&stations, &threshold_overrides::load(client)?);
+            Ok((stations, registry::current_version(client)?))
+        }
+        Some(at) => Ok(match registry::version_at(client, at)? {
+            Some((version, snapshot)) => (registry::stations_at(&stations, &snapshot), Some(version)),
+            None => (stations, None),
+        }),
+    }
+}
+
+/// Optional `as_of` query parameter: RFC 3339, not in the future
+pub(crate) fn as_of_param(query: &HashMap<String, String>, now: DateTime<Utc>) -> Result<Option<DateTime<Utc>>, String> {
+    let Some(value) = query.get("as_of") else {
+        return Ok(None);
+    };
+    let as_of = DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| format!("as_of must be RFC 3339: {}", e))?;
+    if as_of > now {
+        return Err("as_of must not be in the future".to_string());
+    }
+    Ok(Some(as_of))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::split_query;
+
+    #[test]
+    fn test_dashboard_routes_404_for_unknown_station() {
+        let stations = stations::load_stations();
+        let known = stations[0].site_code.clone();
+        let route = |url: &str| {
+            let (path, query) = split_query(url);
+            DashboardRoute::parse(&path, &query)
+        };
+        let status = |url: &str| unknown_station(&route(url).unwrap(), &stations).map(|r| r.status_code().0);
+        
+        assert_eq!(route("/api/stations"), Some(DashboardRoute::Stations(None)));
+        assert_eq!(route(&format!("/api/stations/{}/latest", known)), Some(DashboardRoute::StationLatest(known.clone())));
+        assert_eq!(route("/api/zones?site=05568500"), Some(DashboardRoute::Zones(Some("05568500".to_string()))));
+        assert_eq!(route("/api/alerts/active"), Some(DashboardRoute::ActiveAlerts(None)));
+        assert_eq!(route("/api/stations/05568500/stats"), None);
+        
+        for path in ["/api/stations", "/api/zones", "/api/alerts/active"] {
+            assert_eq!(status(path), None, "{} without a site", path);
+            assert_eq!(status(&format!("{}?site={}", path, known)), None, "{} for a known site", path);
+            assert_eq!(status(&format!("{}?site=00000000", path)), Some(404), "{} for an unknown site", path);
+        }
+        assert_eq!(status(&format!("/api/stations/{}/latest", known)), None);
+        assert_eq!(status("/api/stations/00000000/latest"), Some(404));
+        assert_eq!(status("/api/stations//latest"), Some(404));
+    }
+    
+    #[test]
+    fn test_as_of_param_parses_and_rejects_future() {
+        let now = Utc::now();
+        let (_, query) = split_query("/api/alerts");
+        assert_eq!(as_of_param(&query, now), Ok(None));
+        
+        let (_, query) = split_query("/zone/3?as_of=2024-05-01T12%3A00%3A00-05%3A00");
+        assert_eq!(
+            as_of_param(&query, now).unwrap().unwrap().to_rfc3339(),
+            "2024-05-01T17:00:00+00:00",
+        );
+        
+        let (_, query) = split_query("/api/latest?as_of=yesterday");
+        assert!(as_of_param(&query, now).unwrap_err().contains("RFC 3339"));
+        let future = (now + Duration::hours(1)).to_rfc3339().replace('+', "%2B");
+        let (_, query) = split_query(&format!("/api/latest?as_of={}", future));
+        assert!(as_of_param(&query, now).unwrap_err().contains("future"));
+    }
+}
@@ -0,0 +1,243 @@
+//! Long-term archive for USGS readings past the hot retention window.
+//!
+//! `usgs_raw.gauge_readings` stores one row per 15-minute reading, which is
+//! ~70k rows per site and parameter per year plus index overhead. Readings
+//! older than the hot window are moved into
+//! `usgs_raw.gauge_readings_archive`: one row per site, parameter and UTC
+//! day, with the day's readings packed into arrays. Arrays that large are
+//! TOASTed and compressed by Postgres (lz4 where the server supports it,
+//! see migration 014), so the archive costs a fraction of the hot table.
+//!
+//! Historical queries go through `readings_between`, which unions hot and
+//! archived rows so callers never need to know where the cutoff is. The
+//! `usgs_raw.gauge_readings_all` view does the same for ad hoc SQL and the
+//! Python analysis scripts. `latest_known` answers "what was the latest
+//! reading at that instant" the same way, for the `as_of` API parameter.
+//!
+//! Entry points:
+//! - `flomon archive [--days N] [--dry-run]` (CLI, intended for cron)
+//! - `GET /history?site=&param=&start=&end=` (HTTP API)
+
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use postgres::Client;
+
+use crate::model::GaugeReading;
+
+/// Readings newer than this many days stay in the hot table
+pub const HOT_RETENTION_DAYS: i64 = 365;
+
+/// Result of one archive run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveStats {
+    /// Everything before this instant was archived
+    pub cutoff: DateTime<Utc>,
+    /// Hot rows moved (or that would be moved, on a dry run)
+    pub readings: u64,
+    /// Archive day rows written
+    pub days: u64,
+}
+
+/// First instant kept hot: `retention_days` before `now`, rounded down to
+/// UTC midnight so whole days are archived at once.
+pub fn archive_cutoff(now: DateTime<Utc>, retention_days: i64) -> DateTime<Utc> {
+    let cutoff = now - Duration::days(retention_days);
+    cutoff.duration_trunc(Duration::days(1)).unwrap_or(cutoff)
+}
+
+/// Move hot readings older than the retention window into the archive.
+///
+/// Runs in a single transaction: the day rows are written (merged into any
+/// existing row for the same day, e.g. after a deep backfill) and the hot
+/// rows deleted together. With `dry_run`, only counts what would move.
+pub fn archive_readings(
+    client: &mut Client,
+    now: DateTime<Utc>,
+    retention_days: i64,
+    dry_run: bool,
+) -> Result<ArchiveStats, String> {
+    let cutoff = archive_cutoff(now, retention_days);
+
+    if dry_run {
+        let row = client.query_one(
+            "SELECT COUNT(*),
+                    COUNT(DISTINCT (site_code, parameter_code, date_trunc('day', reading_time, 'UTC')))
+             FROM usgs_raw.gauge_readings
+             WHERE reading_time < $1",
+            &[&cutoff],
+        ).map_err(|e| format!("Failed to count archivable readings: {}", e))?;
+        let readings: i64 = row.get(0);
+        let days: i64 = row.get(1);
+        return Ok(ArchiveStats { cutoff, readings: readings as u64, days: days as u64 });
+    }
+
+    let mut tx = client.transaction()
+        .map_err(|e| format!("Failed to start archive transaction: {}", e))?;
+
+    let days = tx.execute(
+        "INSERT INTO usgs_raw.gauge_readings_archive
+         (site_code, parameter_code, day, unit, reading_times, reading_values, qualifiers, reading_count)
+         SELECT site_code,
+                parameter_code,
+                (date_trunc('day', reading_time, 'UTC') AT TIME ZONE 'UTC')::DATE,
+                MAX(unit),
+                array_agg(reading_time ORDER BY reading_time),
+                array_agg(value ORDER BY reading_time),
+                array_agg(qualifier::TEXT ORDER BY reading_time),
+                COUNT(*)
+         FROM usgs_raw.gauge_readings
+         WHERE reading_time < $1
+         GROUP BY 1, 2, 3
+         ON CONFLICT (site_code, parameter_code, day) DO UPDATE SET
+            reading_times = gauge_readings_archive.reading_times || EXCLUDED.reading_times,
+            reading_values = gauge_readings_archive.reading_values || EXCLUDED.reading_values,
+            qualifiers = gauge_readings_archive.qualifiers || EXCLUDED.qualifiers,
+            reading_count = gauge_readings_archive.reading_count + EXCLUDED.reading_count,
+            archived_at = NOW()",
+        &[&cutoff],
+    ).map_err(|e| format!("Failed to write archive rows: {}", e))?;
+
+    let readings = tx.execute(
+        "DELETE FROM usgs_raw.gauge_readings WHERE reading_time < $1",
+        &[&cutoff],
+    ).map_err(|e| format!("Failed to remove archived readings: {}", e))?;
+
+    tx.commit().map_err(|e| format!("Failed to commit archive: {}", e))?;
+
+    Ok(ArchiveStats { cutoff, readings, days })
+}
+
+/// Readings for one site and parameter in `[start, end]`, oldest first,
+/// drawn from both the hot table and the archive.
+///
+/// A reading present in both (re-ingested after archiving) is returned once,
+/// preferring the hot copy.
+pub fn readings_between(
+    client: &mut Client,
+    site_code: &str,
+    parameter_code: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<GaugeReading>, String> {
+    let rows = client.query(
+        "SELECT DISTINCT ON (reading_time) reading_time, value, unit, qualifier
+         FROM (
+             SELECT reading_time, value, unit, qualifier::TEXT AS qualifier, 0 AS tier
+             FROM usgs_raw.gauge_readings
+             WHERE site_code = $1 AND parameter_code = $2
+               AND reading_time BETWEEN $3 AND $4
+             UNION ALL
+             SELECT r.reading_time, r.value, a.unit, r.qualifier, 1 AS tier
+             FROM usgs_raw.gauge_readings_archive a
+             CROSS JOIN LATERAL unnest(a.reading_times, a.reading_values, a.qualifiers)
+                 AS r(reading_time, value, qualifier)
+             WHERE a.site_code = $1 AND a.parameter_code = $2
+               AND a.day BETWEEN ($3 AT TIME ZONE 'UTC')::DATE AND ($4 AT TIME ZONE 'UTC')::DATE
+               AND r.reading_time BETWEEN $3 AND $4
+         ) combined
+         ORDER BY reading_time, tier",
+        &[&site_code, &parameter_code, &start, &end],
+    ).map_err(|e| format!("Failed to query reading history: {}", e))?;
+
+    Ok(rows.iter()
+        .map(|row| {
+            let reading_time: DateTime<Utc> = row.get(0);
+            let value: rust_decimal::Decimal = row.get(1);
+            GaugeReading {
+                site_code: site_code.to_string(),
+                site_name: site_code.to_string(),
+                parameter_code: parameter_code.to_string(),
+                unit: row.get(2),
+                value: value.to_string().parse().unwrap_or(0.0),
+                datetime: reading_time.fixed_offset(),
+                qualifier: row.get(3),
+                original_unit: None,
+            }
+        })
+        .collect())
+}
+
+/// Latest reading of each parameter as the system knew it at `as_of`,
+/// optionally for one site (stage only when `stage_only`), looking back at
+/// most `lookback`.
+///
+/// Hot rows count only once ingested (`ingested_at <= as_of`), so a reading
+/// backfilled after the fact does not appear in the past. Archived rows
+/// keep no ingest time and are taken as known when read.
+pub fn latest_known(
+    client: &mut Client,
+    site_code: Option<&str>,
+    stage_only: bool,
+    as_of: DateTime<Utc>,
+    lookback: Duration,
+) -> Result<Vec<GaugeReading>, String> {
+    let since = as_of - lookback;
+    let rows = client.query(
+        "SELECT DISTINCT ON (site_code, parameter_code)
+                site_code, parameter_code, value, unit, reading_time, qualifier
+         FROM (
+             SELECT site_code, parameter_code, value, unit, reading_time, qualifier::TEXT AS qualifier, 0 AS tier
+             FROM usgs_raw.gauge_readings
+             WHERE ($1::TEXT IS NULL OR site_code = $1)
+               AND (NOT $2 OR parameter_code = '00065')
+               AND reading_time BETWEEN $3 AND $4
+               AND ingested_at <= $4
+             UNION ALL
+             SELECT a.site_code, a.parameter_code, r.value, a.unit, r.reading_time, r.qualifier, 1 AS tier
+             FROM usgs_raw.gauge_readings_archive a
+             CROSS JOIN LATERAL unnest(a.reading_times, a.reading_values, a.qualifiers)
+                 AS r(reading_time, value, qualifier)
+             WHERE ($1::TEXT IS NULL OR a.site_code = $1)
+               AND (NOT $2 OR a.parameter_code = '00065')
+               AND a.day BETWEEN ($3 AT TIME ZONE 'UTC')::DATE AND ($4 AT TIME ZONE 'UTC')::DATE
+               AND r.reading_time BETWEEN $3 AND $4
+         ) combined
+         ORDER BY site_code, parameter_code, reading_time DESC, tier",
+        &[&site_code, &stage_only, &since, &as_of],
+    ).map_err(|e| format!("Failed to query readings as of {}: {}", as_of.to_rfc3339(), e))?;
+
+    Ok(rows.iter()
+        .map(|row| {
+            let site_code: String = row.get(0);
+            let value: rust_decimal::Decimal = row.get(2);
+            let reading_time: DateTime<Utc> = row.get(4);
+            GaugeReading {
+                site_name: site_code.clone(),
+                site_code,
+                parameter_code: row.get(1),
+                unit: row.get(3),
+                value: value.to_string().parse().unwrap_or(0.0),
+                datetime: reading_time.fixed_offset(),
+                qualifier: row.get(5),
+                original_unit: None,
+            }
+        })
+        .collect())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_cutoff_rounds_down_to_utc_midnight() {
+        let now = Utc.with_ymd_and_hms(2025, 5, 1, 17, 42, 9).unwrap();
+        assert_eq!(
+            archive_cutoff(now, 365),
+            Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_cutoff_keeps_partial_day_hot() {
+        // Just after midnight: the day that began 365 days ago stays hot
+        let now = Utc.with_ymd_and_hms(2025, 5, 1, 0, 5, 0).unwrap();
+        let cutoff = archive_cutoff(now, 365);
+        assert!(cutoff <= now - Duration::days(365));
+        assert!(now - Duration::days(365) - cutoff < Duration::days(1));
+    }
+}
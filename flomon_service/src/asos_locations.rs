@@ -1,7 +1,7 @@
-/// ASOS Station TOML Configuration Loader
-///
-/// Loads ASOS (Automated Surface Observing System) station metadata from
-/// iem_asos.toml for weather monitoring relevant to tributary flood forecasting.
+//! ASOS Station TOML Configuration Loader
+//!
+//! Loads ASOS (Automated Surface Observing System) station metadata from
+//! iem_asos.toml for weather monitoring relevant to tributary flood forecasting.
 
 use serde::Deserialize;
 use std::fs;
@@ -96,27 +96,31 @@ pub fn load_locations<P: AsRef<Path>>(path: P) -> Result<Vec<AsosLocation>, Box<
     let config: AsosConfig = toml::from_str(&content)?;
     
     let locations: Vec<AsosLocation> = config.stations.into_iter()
-        .map(|station| {
-            let priority = determine_priority(&station.relevance);
-            
-            AsosLocation {
-                station_id: station.station_id,
-                name: station.name,
-                latitude: station.latitude,
-                longitude: station.longitude,
-                elevation_ft: station.elevation_ft,
-                data_types: station.data_types,
-                relevance: station.relevance,
-                basin: station.basin,
-                upstream_gauge: station.upstream_gauge,
-                priority,
-            }
-        })
+        .map(AsosLocation::from)
         .collect();
     
     Ok(locations)
 }
 
+impl From<AsosStation> for AsosLocation {
+    fn from(station: AsosStation) -> Self {
+        let priority = determine_priority(&station.relevance);
+        
+        AsosLocation {
+            station_id: station.station_id,
+            name: station.name,
+            latitude: station.latitude,
+            longitude: station.longitude,
+            elevation_ft: station.elevation_ft,
+            data_types: station.data_types,
+            relevance: station.relevance,
+            basin: station.basin,
+            upstream_gauge: station.upstream_gauge,
+            priority,
+        }
+    }
+}
+
 /// Determine monitoring priority from relevance text
 fn determine_priority(relevance: &str) -> MonitoringPriority {
     let lower = relevance.to_lowercase();
@@ -173,7 +177,7 @@ impl AsosLocation {
                 watch_24hr_in: 2.5,
                 warning_24hr_in: 4.5,
             },
-            "Illinois River" | _ => PrecipThresholds {
+            _ => PrecipThresholds { // Illinois River mainstem and default
                 watch_6hr_in: 1.5,
                 warning_6hr_in: 2.5,
                 watch_24hr_in: 3.0,
@@ -189,7 +193,7 @@ impl AsosLocation {
             "Spoon River" => 18,       // Galesburg to Seville
             "Sangamon River" => 24,    // Springfield to Oakford
             "Des Plaines River" => 6,  // Chicago to Joliet (fast response)
-            "Illinois River" | _ => 48, // Mainstem (slow response)
+            _ => 48, // Mainstem (slow response)
         }
     }
 }
@@ -0,0 +1,189 @@
+//! Data provider attribution carried with everything the service publishes.
+//!
+//! USGS and USACE data are U.S. Government works, but USGS asks that
+//! provisional data be labelled as such, and the Iowa Environmental Mesonet
+//! asks to be credited wherever its ASOS archive is reused. Rather than
+//! relying on whoever shares a snapshot to add a footer, attribution is
+//! attached where the data leaves the service:
+//!
+//! - HTTP: every successful JSON object response gains an `attribution`
+//!   array for the providers behind its route (`for_path`)
+//! - `--json` documents: the `Envelope` carries the providers behind its
+//!   schema (`for_schema`)
+//! - the embed widget prints a `Data:` line under the gauge
+//! - event reports end with a data sources section
+//!
+//! ```text
+//! "attribution": [
+//!   {"provider": "USGS", "text": "U.S. Geological Survey, ...", "url": "...", "license": "..."}
+//! ]
+//! ```
+
+use serde::Serialize;
+
+/// An upstream whose data the service republishes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Provider {
+    Usgs,
+    Usace,
+    Iem,
+    Nws,
+}
+
+impl Provider {
+    pub const ALL: [Provider; 4] = [Provider::Usgs, Provider::Usace, Provider::Iem, Provider::Nws];
+
+    /// Short name for footers
+    pub fn label(&self) -> &'static str {
+        match self {
+            Provider::Usgs => "USGS",
+            Provider::Usace => "USACE",
+            Provider::Iem => "Iowa Environmental Mesonet",
+            Provider::Nws => "NWS",
+        }
+    }
+
+    /// Credit line as the provider asks for it
+    pub fn text(&self) -> &'static str {
+        match self {
+            Provider::Usgs => "U.S. Geological Survey, National Water Information System. \
+                Provisional data subject to revision.",
+            Provider::Usace => "U.S. Army Corps of Engineers, Corps Water Management System (CWMS).",
+            Provider::Iem => "Iowa Environmental Mesonet, Iowa State University (ASOS observations).",
+            Provider::Nws => "NOAA National Weather Service flood categories.",
+        }
+    }
+
+    pub fn url(&self) -> &'static str {
+        match self {
+            Provider::Usgs => "https://waterdata.usgs.gov",
+            Provider::Usace => "https://cwms-data.usace.army.mil",
+            Provider::Iem => "https://mesonet.agron.iastate.edu",
+            Provider::Nws => "https://water.noaa.gov",
+        }
+    }
+
+    pub fn license(&self) -> &'static str {
+        match self {
+            Provider::Usgs | Provider::Usace | Provider::Nws => "Public domain (U.S. Government work)",
+            Provider::Iem => "Free to use with attribution",
+        }
+    }
+
+    pub fn attribution(&self) -> Attribution {
+        Attribution {
+            provider: self.label(),
+            text: self.text(),
+            url: self.url(),
+            license: self.license(),
+        }
+    }
+}
+
+/// One provider's attribution as serialized into responses
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Attribution {
+    pub provider: &'static str,
+    pub text: &'static str,
+    pub url: &'static str,
+    pub license: &'static str,
+}
+
+pub fn attributions(providers: &[Provider]) -> Vec<Attribution> {
+    providers.iter().map(Provider::attribution).collect()
+}
+
+/// `Data: USGS, NWS` (empty for no providers)
+pub fn footer(providers: &[Provider]) -> String {
+    if providers.is_empty() {
+        return String::new();
+    }
+    let labels: Vec<&str> = providers.iter().map(Provider::label).collect();
+    format!("Data: {}", labels.join(", "))
+}
+
+/// Providers behind an HTTP route; empty for routes serving only local
+/// data (field observations, occupancy, map layers, health)
+pub fn for_path(path: &str) -> &'static [Provider] {
+    use Provider::*;
+    const GAUGES: &[Provider] = &[Usgs, Nws];
+    const ALL: &[Provider] = &Provider::ALL;
+
+    if path.starts_with("/zone/") && path.ends_with("/inundation") {
+        &[Usgs]
+    } else if path == "/status" || path == "/api/snapshot" || path == "/zones" || path == "/api/zones"
+        || path.starts_with("/zone/") || path.starts_with("/api/zones/") {
+        ALL
+    } else if path.starts_with("/api/stations/") && (path.ends_with("/stats") || path.ends_with("/quality")) {
+        &[Usgs]
+    } else if path == "/api/latest" || path.starts_with("/api/stations") || path.starts_with("/api/alerts")
+        || path == "/api/embed/summary" || path.starts_with("/site/") {
+        GAUGES
+    } else if path == "/history" || path == "/api/volume" || path == "/backwater" || path == "/api/profile" || path == "/api/lag" {
+        &[Usgs]
+    } else if path == "/api/expected_response" {
+        &[Usgs, Iem]
+    } else {
+        &[]
+    }
+}
+
+/// Providers behind a `--json` schema kind (see `cli_output::envelope`)
+pub fn for_schema(kind: &str) -> &'static [Provider] {
+    use Provider::*;
+    match kind {
+        "status" | "alerts" | "station" | "latest" => &[Usgs, Nws],
+        "verify" | "data_quality" => &[Usgs],
+        "registry_history" => &[Nws],
+        _ => &[],
+    }
+}
+
+/// Add `attribution` to a JSON object that doesn't already carry one;
+/// other values are returned unchanged
+pub fn attach(value: serde_json::Value, providers: &[Provider]) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(mut fields) if !providers.is_empty() && !fields.contains_key("attribution") => {
+            fields.insert(
+                "attribution".to_string(),
+                serde_json::to_value(attributions(providers)).unwrap_or_default(),
+            );
+            serde_json::Value::Object(fields)
+        }
+        other => other,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_routes_map_to_providers() {
+        assert_eq!(for_path("/zone/3"), &Provider::ALL);
+        assert_eq!(for_path("/api/zones/2/status"), &Provider::ALL);
+        assert_eq!(for_path("/zone/3/inundation"), &[Provider::Usgs]);
+        assert_eq!(for_path("/api/stations/05567500/latest"), &[Provider::Usgs, Provider::Nws]);
+        assert_eq!(for_path("/api/stations/05567500/stats"), &[Provider::Usgs]);
+        assert!(for_path("/field_obs").is_empty());
+        assert!(for_path("/map/levees.geojson").is_empty());
+    }
+
+    #[test]
+    fn test_attach_keeps_existing_and_skips_non_objects() {
+        let attached = attach(json!({"stage_ft": 17.2}), &[Provider::Iem]);
+        assert_eq!(attached["attribution"][0]["provider"], "Iowa Environmental Mesonet");
+        assert_eq!(attached["attribution"][0]["license"], "Free to use with attribution");
+
+        let existing = json!({"attribution": []});
+        assert_eq!(attach(existing.clone(), &[Provider::Usgs]), existing);
+        assert_eq!(attach(json!([1, 2]), &[Provider::Usgs]), json!([1, 2]));
+        assert_eq!(attach(json!({}), &[]), json!({}));
+        assert_eq!(footer(&[Provider::Usgs, Provider::Usace]), "Data: USGS, USACE");
+    }
+}
@@ -0,0 +1,52 @@
+//! Bearer tokens for the protected HTTP routes.
+//!
+//! Every route that takes a shared secret (`POST /field_obs`,
+//! `POST /manual_readings`, `POST /api/occupancy`, `POST /api/suppressions`
+//! and, when configured, `GET /api/stream`) checks it the same way: its
+//! settings section holds a `token`, `validate_token` rejects a short one
+//! at config load, and `bearer_authorized` compares the request's
+//! `Authorization: Bearer <token>` header against it in constant time.
+
+/// Shortest token a settings section accepts
+pub const MIN_TOKEN_LEN: usize = 16;
+
+/// Reject a configured token shorter than `MIN_TOKEN_LEN`; `setting` names
+/// it in the error ("field_obs.token")
+pub fn validate_token(setting: &str, token: &str) -> Result<(), String> {
+    if token.len() < MIN_TOKEN_LEN {
+        return Err(format!("{} must be at least {} characters", setting, MIN_TOKEN_LEN));
+    }
+    Ok(())
+}
+
+/// Whether `authorization` (an `Authorization` header value) is
+/// `Bearer <expected>`
+pub fn bearer_authorized(authorization: Option<&str>, expected: &str) -> bool {
+    authorization
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_eq(token.trim().as_bytes(), expected.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_length_and_bearer_check() {
+        assert!(validate_token("field_obs.token", "0123456789abcdef").is_ok());
+        assert_eq!(
+            validate_token("field_obs.token", "short"),
+            Err("field_obs.token must be at least 16 characters".to_string()),
+        );
+
+        assert!(bearer_authorized(Some("Bearer 0123456789abcdef"), "0123456789abcdef"));
+        assert!(bearer_authorized(Some("Bearer 0123456789abcdef "), "0123456789abcdef"));
+        assert!(!bearer_authorized(Some("Bearer 0123456789abcde"), "0123456789abcdef"));
+        assert!(!bearer_authorized(Some("0123456789abcdef"), "0123456789abcdef"));
+        assert!(!bearer_authorized(None, "0123456789abcdef"));
+    }
+}
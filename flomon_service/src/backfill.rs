@@ -0,0 +1,340 @@
+//! Historical backfill over arbitrary multi-year ranges.
+//!
+//! `flomon backfill --site CODE --start DATE` used to issue one request
+//! for the whole range, which USGS answers slowly (or not at all) for a
+//! decade of data and which starts over from scratch after any failure.
+//! The range is now split into chunks:
+//! - daily values, one calendar year per request, for the part of the
+//!   range older than the instantaneous values window, and
+//! - instantaneous values, one calendar month per request, for the rest
+//!   (the window begins on the first of the month after
+//!   `IV_HISTORY_DAYS` ago, so it stays on month boundaries).
+//!
+//! Chunks run oldest first, spaced by `catchup::Source::min_request_interval`.
+//! Every chunk fetched and warehoused is recorded in
+//! `usgs_raw.backfill_checkpoints`; a rerun of the same command skips them
+//! and resumes at the first missing chunk. A chunk counts as done only if
+//! it was fetched after its last day ended, so the current month (and the
+//! current year of daily values) is always fetched again.
+//!
+//! A run stops between chunks on a shutdown request or after
+//! `MAX_CONSECUTIVE_FAILURES` failed chunks in a row (USGS is down; there is
+//! no point hammering it), and ends with `logging::log_backfill_summary`.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use postgres::Client;
+use std::time::Instant;
+
+use crate::logging::{self, DataSource};
+use crate::monitor::catchup::{RateLimiter, Source};
+use crate::shutdown;
+
+/// How far back the instantaneous values service is used
+pub const IV_HISTORY_DAYS: i64 = 120;
+
+/// Failed chunks in a row that end a run
+pub const MAX_CONSECUTIVE_FAILURES: usize = 3;
+
+/// USGS service a chunk is requested from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChunkKind {
+    /// Daily values (one calendar year per chunk)
+    Daily,
+    /// Instantaneous values (one calendar month per chunk)
+    Instantaneous,
+}
+
+impl ChunkKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChunkKind::Daily => "dv",
+            ChunkKind::Instantaneous => "iv",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "dv" => Some(ChunkKind::Daily),
+            "iv" => Some(ChunkKind::Instantaneous),
+            _ => None,
+        }
+    }
+}
+
+/// One request of a backfill run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub site_code: String,
+    pub kind: ChunkKind,
+    pub start: NaiveDate,
+    /// Inclusive
+    pub end: NaiveDate,
+}
+
+impl Chunk {
+    /// e.g. "DV 2019-01-01..2019-12-31"
+    pub fn label(&self) -> String {
+        format!("{} {}..{}", self.kind.as_str().to_uppercase(), self.start, self.end)
+    }
+}
+
+/// A chunk already fetched and warehoused
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub kind: ChunkKind,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub readings: usize,
+    pub completed_at: DateTime<Utc>,
+}
+
+impl Checkpoint {
+    /// Whether this checkpoint covers `chunk` with data that can no longer
+    /// change (fetched after the chunk's last day was over)
+    pub fn covers(&self, chunk: &Chunk) -> bool {
+        self.kind == chunk.kind
+            && self.start == chunk.start
+            && self.end >= chunk.end
+            && self.end < self.completed_at.date_naive()
+    }
+}
+
+/// First day served from instantaneous values when the run starts on `today`
+pub fn iv_start(today: NaiveDate) -> NaiveDate {
+    first_of_next_month(today - Duration::days(IV_HISTORY_DAYS))
+}
+
+fn first_of_next_month(date: NaiveDate) -> NaiveDate {
+    let (year, month) = if date.month() == 12 { (date.year() + 1, 1) } else { (date.year(), date.month() + 1) };
+    NaiveDate::from_ymd_opt(year, month, 1).expect("first of month is a valid date")
+}
+
+/// Chunks covering `start..=end` for `site_code`, oldest first
+pub fn plan(site_code: &str, start: NaiveDate, end: NaiveDate, today: NaiveDate) -> Vec<Chunk> {
+    let end = end.min(today);
+    let split = iv_start(today);
+    let mut chunks = Vec::new();
+
+    let mut from = start;
+    while from <= end && from < split {
+        let year_end = NaiveDate::from_ymd_opt(from.year(), 12, 31).expect("Dec 31 is a valid date");
+        let to = year_end.min(split.pred_opt().expect("date after the epoch")).min(end);
+        chunks.push(Chunk { site_code: site_code.to_string(), kind: ChunkKind::Daily, start: from, end: to });
+        from = to.succ_opt().expect("date before the end of time");
+    }
+
+    let mut from = from.max(split);
+    while from <= end {
+        let to = first_of_next_month(from).pred_opt().expect("date after the epoch").min(end);
+        chunks.push(Chunk { site_code: site_code.to_string(), kind: ChunkKind::Instantaneous, start: from, end: to });
+        from = to.succ_opt().expect("date before the end of time");
+    }
+    chunks
+}
+
+/// Outcome of a backfill run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackfillReport {
+    pub planned: usize,
+    /// Skipped because a checkpoint covers them
+    pub resumed: usize,
+    pub completed: usize,
+    pub failed: usize,
+    /// Not attempted: shutdown requested or too many failures in a row
+    pub remaining: usize,
+    pub readings: usize,
+}
+
+impl BackfillReport {
+    pub fn summary(&self) -> String {
+        let mut line = format!("{} of {} chunks fetched, {} readings", self.completed, self.planned, self.readings);
+        if self.resumed > 0 {
+            line.push_str(&format!(", {} already done", self.resumed));
+        }
+        if self.failed > 0 {
+            line.push_str(&format!(", {} failed", self.failed));
+        }
+        if self.remaining > 0 {
+            line.push_str(&format!(", {} left for the next run", self.remaining));
+        }
+        line
+    }
+
+    /// Whether rerunning the same command would have anything left to do
+    pub fn is_complete(&self) -> bool {
+        self.failed == 0 && self.remaining == 0
+    }
+}
+
+/// Run `step` (fetch, warehouse, checkpoint; returns the readings written)
+/// for each chunk not covered by `checkpoints`, rate limited and stopping
+/// early as described in the module docs
+pub fn run(
+    chunks: &[Chunk],
+    checkpoints: &[Checkpoint],
+    mut step: impl FnMut(&Chunk) -> Result<usize, String>,
+) -> BackfillReport {
+    let pending: Vec<&Chunk> = chunks.iter()
+        .filter(|chunk| !checkpoints.iter().any(|c| c.covers(chunk)))
+        .collect();
+    let mut report = BackfillReport {
+        planned: chunks.len(),
+        resumed: chunks.len() - pending.len(),
+        ..Default::default()
+    };
+
+    let mut limiter = RateLimiter::new();
+    let mut consecutive_failures = 0;
+    for (i, chunk) in pending.iter().enumerate() {
+        let wait = limiter.wait_before(Source::Usgs, Instant::now());
+        if consecutive_failures >= MAX_CONSECUTIVE_FAILURES || shutdown::requested() || !shutdown::sleep(wait) {
+            report.remaining = pending.len() - i;
+            break;
+        }
+        limiter.record(Source::Usgs, Instant::now());
+
+        let progress = format!("chunk {}/{} {}", i + 1, pending.len(), chunk.label());
+        match step(chunk) {
+            Ok(readings) => {
+                consecutive_failures = 0;
+                report.completed += 1;
+                report.readings += readings;
+                logging::info(DataSource::Usgs, Some(&chunk.site_code), &format!("Backfill {}: {} readings", progress, readings));
+            }
+            Err(e) => {
+                consecutive_failures += 1;
+                report.failed += 1;
+                logging::warn(DataSource::Usgs, Some(&chunk.site_code), &format!("Backfill {} failed: {}", progress, e));
+            }
+        }
+    }
+
+    logging::log_backfill_summary(DataSource::Usgs, pending.len(), report.completed, report.failed);
+    report
+}
+
+/// Checkpoints recorded for `site_code`
+pub fn load_checkpoints(client: &mut Client, site_code: &str) -> Result<Vec<Checkpoint>, String> {
+    let rows = client.query(
+        "SELECT kind, chunk_start, chunk_end, readings, completed_at
+         FROM usgs_raw.backfill_checkpoints
+         WHERE site_code = $1",
+        &[&site_code],
+    ).map_err(|e| format!("Failed to load backfill checkpoints for {}: {}", site_code, e))?;
+    Ok(rows.iter()
+        .filter_map(|row| Some(Checkpoint {
+            kind: ChunkKind::parse(row.get(0))?,
+            start: row.get(1),
+            end: row.get(2),
+            readings: row.get::<_, i32>(3) as usize,
+            completed_at: row.get(4),
+        }))
+        .collect())
+}
+
+/// Record `chunk` as done
+pub fn store_checkpoint(client: &mut Client, chunk: &Chunk, readings: usize) -> Result<(), String> {
+    client.execute(
+        "INSERT INTO usgs_raw.backfill_checkpoints (site_code, kind, chunk_start, chunk_end, readings, completed_at)
+         VALUES ($1, $2, $3, $4, $5, NOW())
+         ON CONFLICT (site_code, kind, chunk_start) DO UPDATE
+         SET chunk_end = EXCLUDED.chunk_end,
+             readings = EXCLUDED.readings,
+             completed_at = EXCLUDED.completed_at",
+        &[&chunk.site_code, &chunk.kind.as_str(), &chunk.start, &chunk.end, &(readings as i32)],
+    ).map_err(|e| format!("Failed to record backfill checkpoint {} for {}: {}", chunk.label(), chunk.site_code, e))?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_plan_splits_years_of_daily_and_months_of_instantaneous() {
+        // 120 days before 2024-06-15 is 2024-02-16: IV from March 1
+        let today = date(2024, 6, 15);
+        assert_eq!(iv_start(today), date(2024, 3, 1));
+
+        let chunks = plan("05568500", date(2022, 7, 4), date(2030, 1, 1), today);
+        let spans: Vec<(ChunkKind, NaiveDate, NaiveDate)> = chunks.iter().map(|c| (c.kind, c.start, c.end)).collect();
+        assert_eq!(spans, vec![
+            (ChunkKind::Daily, date(2022, 7, 4), date(2022, 12, 31)),
+            (ChunkKind::Daily, date(2023, 1, 1), date(2023, 12, 31)),
+            (ChunkKind::Daily, date(2024, 1, 1), date(2024, 2, 29)),
+            (ChunkKind::Instantaneous, date(2024, 3, 1), date(2024, 3, 31)),
+            (ChunkKind::Instantaneous, date(2024, 4, 1), date(2024, 4, 30)),
+            (ChunkKind::Instantaneous, date(2024, 5, 1), date(2024, 5, 31)),
+            (ChunkKind::Instantaneous, date(2024, 6, 1), date(2024, 6, 15)),
+        ]);
+
+        // Entirely recent or entirely old ranges use one service
+        assert!(plan("05568500", date(2024, 5, 10), date(2024, 5, 20), today).iter()
+            .all(|c| c.kind == ChunkKind::Instantaneous));
+        assert_eq!(plan("05568500", date(1990, 3, 1), date(1990, 3, 2), today).len(), 1);
+        assert!(plan("05568500", date(2024, 7, 1), date(2024, 8, 1), today).is_empty());
+    }
+
+    #[test]
+    fn test_checkpoints_cover_only_finished_chunks() {
+        let chunks = plan("05568500", date(2023, 1, 1), date(2024, 6, 15), date(2024, 6, 15));
+        let done = |chunk: &Chunk, completed_at: DateTime<Utc>| Checkpoint {
+            kind: chunk.kind,
+            start: chunk.start,
+            end: chunk.end,
+            readings: 10,
+            completed_at,
+        };
+        let year = &chunks[0];
+        let current_month = chunks.last().unwrap();
+
+        assert!(done(year, Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap()).covers(year));
+        assert!(!done(year, Utc.with_ymd_and_hms(2023, 12, 31, 23, 0, 0).unwrap()).covers(year),
+            "fetched before the year was over");
+        assert!(!done(current_month, Utc.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap()).covers(current_month));
+
+        // A shorter chunk from an earlier run doesn't cover a longer one
+        let partial = Checkpoint { end: date(2024, 6, 10), ..done(current_month, Utc.with_ymd_and_hms(2024, 6, 11, 0, 0, 0).unwrap()) };
+        assert!(!partial.covers(current_month));
+        assert!(!done(year, Utc::now()).covers(&Chunk { kind: ChunkKind::Instantaneous, ..year.clone() }));
+    }
+
+    #[test]
+    fn test_run_resumes_and_stops_after_repeated_failures() {
+        let chunks = plan("05568500", date(2020, 1, 1), date(2022, 12, 31), date(2024, 6, 15));
+        assert_eq!(chunks.len(), 3);
+        // One chunk left, so the rate limiter never has to wait
+        let checkpoints: Vec<Checkpoint> = chunks[..2].iter()
+            .map(|chunk| Checkpoint {
+                kind: chunk.kind,
+                start: chunk.start,
+                end: chunk.end,
+                readings: 732,
+                completed_at: Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap(),
+            })
+            .collect();
+
+        let mut fetched = Vec::new();
+        let report = run(&chunks, &checkpoints, |chunk| {
+            fetched.push(chunk.start);
+            Ok(730)
+        });
+        assert_eq!(fetched, vec![date(2022, 1, 1)]);
+        assert_eq!(report, BackfillReport { planned: 3, resumed: 2, completed: 1, readings: 730, ..Default::default() });
+        assert!(report.is_complete());
+        assert_eq!(report.summary(), "1 of 3 chunks fetched, 730 readings, 2 already done");
+
+        let report = BackfillReport { planned: 10, failed: MAX_CONSECUTIVE_FAILURES, remaining: 7, ..Default::default() };
+        assert!(!report.is_complete());
+        assert!(report.summary().ends_with("3 failed, 7 left for the next run"));
+    }
+}
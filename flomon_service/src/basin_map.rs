@@ -0,0 +1,325 @@
+//! Static GeoJSON layers for the dashboard's basin map.
+//!
+//! The map view needs the river centerline, sub-basin boundaries and levee
+//! segments under the live station markers. These don't change between
+//! deployments, so rather than run a tile service on the LAN the endpoint
+//! precomputes each layer once at startup and serves it from memory at
+//! `GET /map/{name}.geojson`, with an `ETag` so browsers revalidate with
+//! `If-None-Match` and get `304 Not Modified` instead of the body.
+//!
+//! Layers are GeoJSON exported from GIS, listed in flomon.toml:
+//!
+//! ```toml
+//! [[map_layers]]
+//! name = "centerline"
+//! path = "geo/illinois_river_centerline.geojson"   # relative to flomon.toml
+//! properties = ["name", "river_mile"]               # kept; all when absent
+//! precision = 5                                     # decimal places (~1 m)
+//! ```
+//!
+//! Precomputing rounds coordinates to `precision` places, drops positions
+//! that become consecutive duplicates, keeps only the listed properties and
+//! writes compact JSON, which typically shrinks a GIS export several-fold.
+//! A `stations` layer of gauge points is built from the station registry.
+//! A missing or malformed file stops startup, like an inundation grid.
+
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::stations::Station;
+
+/// Name of the layer built from the station registry
+pub const STATIONS_LAYER: &str = "stations";
+
+pub const DEFAULT_PRECISION: u32 = 5;
+/// More places than this is sub-millimetre and only adds bytes
+pub const MAX_PRECISION: u32 = 8;
+
+fn default_precision() -> u32 {
+    DEFAULT_PRECISION
+}
+
+/// `[[map_layers]]` entry in flomon.toml
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MapLayerSettings {
+    /// URL name: `/map/{name}.geojson`
+    pub name: String,
+    /// GeoJSON file, relative to flomon.toml
+    pub path: PathBuf,
+    /// Feature properties to keep; all when absent
+    #[serde(default)]
+    pub properties: Option<Vec<String>>,
+    #[serde(default = "default_precision")]
+    pub precision: u32,
+}
+
+impl MapLayerSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty()
+            || !self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!("map layer name '{}' must be letters, digits, '-' or '_'", self.name));
+        }
+        if self.name == STATIONS_LAYER {
+            return Err(format!("map layer name '{}' is reserved for the station registry", STATIONS_LAYER));
+        }
+        if self.precision > MAX_PRECISION {
+            return Err(format!("map layer {}: precision must be 0-{}", self.name, MAX_PRECISION));
+        }
+        Ok(())
+    }
+}
+
+/// Layer names are unique
+pub fn validate_names(layers: &[MapLayerSettings]) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    match layers.iter().find(|l| !seen.insert(l.name.as_str())) {
+        Some(duplicate) => Err(format!("map layer name '{}' is used twice", duplicate.name)),
+        None => Ok(()),
+    }
+}
+
+/// A precomputed layer, ready to serve
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapTile {
+    pub name: String,
+    /// Compact FeatureCollection JSON
+    pub body: Vec<u8>,
+    /// Quoted strong validator for `ETag` / `If-None-Match`
+    pub etag: String,
+    pub features: usize,
+    /// [west, south, east, north], when the layer has any positions
+    pub bbox: Option<[f64; 4]>,
+}
+
+impl MapTile {
+    /// Whether an `If-None-Match` header value matches this layer
+    pub fn matches(&self, if_none_match: &str) -> bool {
+        if_none_match.split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == self.etag)
+    }
+}
+
+/// Every layer: the station registry first, then the configured files
+pub fn build(layers: &[MapLayerSettings], stations: &[Station]) -> Result<Vec<MapTile>, String> {
+    let mut tiles = vec![stations_layer(stations)];
+    for layer in layers {
+        tiles.push(load(layer)?);
+    }
+    Ok(tiles)
+}
+
+/// Read and precompute one configured layer
+pub fn load(layer: &MapLayerSettings) -> Result<MapTile, String> {
+    let text = fs::read_to_string(&layer.path)
+        .map_err(|e| format!("Failed to read map layer {} ({}): {}", layer.name, layer.path.display(), e))?;
+    let geojson: serde_json::Value = serde_json::from_str(&text)
+        .map_err(|e| format!("Map layer {} is not valid JSON: {}", layer.name, e))?;
+    precompute(&layer.name, &geojson, layer.properties.as_deref(), layer.precision)
+        .map_err(|e| format!("Map layer {}: {}", layer.name, e))
+}
+
+/// Gauge locations from the registry
+pub fn stations_layer(stations: &[Station]) -> MapTile {
+    let features: Vec<serde_json::Value> = stations.iter().map(|s| serde_json::json!({
+        "type": "Feature",
+        "geometry": {"type": "Point", "coordinates": [s.longitude, s.latitude]},
+        "properties": {
+            "site_code": s.site_code,
+            "name": s.name,
+            "has_thresholds": s.thresholds.is_some() || s.pool_deviation.is_some(),
+        },
+    })).collect();
+    let collection = serde_json::json!({"type": "FeatureCollection", "features": features});
+    precompute(STATIONS_LAYER, &collection, None, DEFAULT_PRECISION)
+        .expect("registry layer is a valid FeatureCollection")
+}
+
+/// Normalize a FeatureCollection, Feature or bare geometry into a compact
+/// FeatureCollection
+pub fn precompute(
+    name: &str,
+    geojson: &serde_json::Value,
+    properties: Option<&[String]>,
+    precision: u32,
+) -> Result<MapTile, String> {
+    let features: Vec<serde_json::Value> = match geojson["type"].as_str() {
+        Some("FeatureCollection") => geojson["features"].as_array()
+            .ok_or("FeatureCollection without a features array")?
+            .clone(),
+        Some("Feature") => vec![geojson.clone()],
+        Some(_) if geojson.get("coordinates").is_some() || geojson.get("geometries").is_some() => {
+            vec![serde_json::json!({"type": "Feature", "geometry": geojson, "properties": {}})]
+        }
+        _ => return Err("not a GeoJSON FeatureCollection, Feature or geometry".to_string()),
+    };
+
+    let scale = 10f64.powi(precision as i32);
+    let mut bbox: Option<[f64; 4]> = None;
+    let mut out = Vec::with_capacity(features.len());
+    for (i, feature) in features.iter().enumerate() {
+        if feature["type"] != "Feature" {
+            return Err(format!("features[{}] is not a Feature", i));
+        }
+        let geometry = match &feature["geometry"] {
+            serde_json::Value::Null => serde_json::Value::Null,
+            geometry => simplify_geometry(geometry, scale, &mut bbox)
+                .map_err(|e| format!("features[{}]: {}", i, e))?,
+        };
+        let kept: serde_json::Map<String, serde_json::Value> = feature["properties"].as_object()
+            .map(|props| props.iter()
+                .filter(|(key, _)| properties.is_none_or(|keep| keep.iter().any(|k| k == *key)))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect())
+            .unwrap_or_default();
+        out.push(serde_json::json!({"type": "Feature", "geometry": geometry, "properties": kept}));
+    }
+
+    let features = out.len();
+    let mut collection = serde_json::json!({"type": "FeatureCollection", "features": out});
+    if let Some(bbox) = bbox {
+        collection["bbox"] = serde_json::json!(bbox);
+    }
+    let body = serde_json::to_vec(&collection).map_err(|e| e.to_string())?;
+    Ok(MapTile { name: name.to_string(), etag: etag(&body), body, features, bbox })
+}
+
+fn simplify_geometry(
+    geometry: &serde_json::Value,
+    scale: f64,
+    bbox: &mut Option<[f64; 4]>,
+) -> Result<serde_json::Value, String> {
+    let mut geometry = geometry.clone();
+    if let Some(parts) = geometry.get("geometries").and_then(|g| g.as_array()).cloned() {
+        let parts = parts.iter()
+            .map(|part| simplify_geometry(part, scale, bbox))
+            .collect::<Result<Vec<_>, _>>()?;
+        geometry["geometries"] = serde_json::Value::Array(parts);
+        return Ok(geometry);
+    }
+    let coordinates = geometry.get("coordinates").ok_or("geometry without coordinates")?;
+    geometry["coordinates"] = simplify_coordinates(coordinates, scale, bbox)?;
+    Ok(geometry)
+}
+
+/// Round every position and drop consecutive duplicates from position lists
+fn simplify_coordinates(
+    value: &serde_json::Value,
+    scale: f64,
+    bbox: &mut Option<[f64; 4]>,
+) -> Result<serde_json::Value, String> {
+    let items = value.as_array().ok_or("coordinates must be arrays")?;
+    if items.first().is_some_and(|v| v.is_number()) {
+        let position: Vec<f64> = items.iter()
+            .map(|v| v.as_f64().map(|x| (x * scale).round() / scale).ok_or("non-numeric coordinate"))
+            .collect::<Result<_, _>>()?;
+        if position.len() < 2 {
+            return Err("position with fewer than two coordinates".to_string());
+        }
+        let (x, y) = (position[0], position[1]);
+        let b = bbox.get_or_insert([x, y, x, y]);
+        *b = [b[0].min(x), b[1].min(y), b[2].max(x), b[3].max(y)];
+        return Ok(serde_json::json!(position));
+    }
+    let mut children = items.iter()
+        .map(|item| simplify_coordinates(item, scale, bbox))
+        .collect::<Result<Vec<_>, _>>()?;
+    let is_position_list = children.first()
+        .and_then(|c| c.as_array())
+        .is_some_and(|c| c.first().is_some_and(|v| v.is_number()));
+    if is_position_list {
+        let mut deduped = children.clone();
+        deduped.dedup();
+        // A line shorter than two positions (or a ring shorter than four)
+        // would no longer be valid GeoJSON; keep such lists as they were
+        if deduped.len() >= 4 || (deduped.len() >= 2 && deduped.first() != deduped.last()) {
+            children = deduped;
+        }
+    }
+    Ok(serde_json::Value::Array(children))
+}
+
+/// Quoted 64-bit FNV-1a of `body`; stable across builds and restarts
+pub fn etag(body: &[u8]) -> String {
+    let hash = body.iter().fold(0xcbf2_9ce4_8422_2325u64, |h, b| (h ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3));
+    format!("\"{:016x}\"", hash)
+}
+
+/// `GET /map/layers`: what the front end can load
+pub fn index(tiles: &[MapTile]) -> serde_json::Value {
+    let layers: Vec<serde_json::Value> = tiles.iter().map(|t| serde_json::json!({
+        "name": t.name,
+        "url": format!("/map/{}.geojson", t.name),
+        "features": t.features,
+        "bytes": t.body.len(),
+        "bbox": t.bbox,
+        "etag": t.etag,
+    })).collect();
+    serde_json::json!({"layers": layers})
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precompute_rounds_dedups_and_filters_properties() {
+        let geojson = serde_json::json!({
+            "type": "FeatureCollection",
+            "features": [{
+                "type": "Feature",
+                "geometry": {"type": "LineString", "coordinates": [
+                    [-89.6012341, 40.6912349], [-89.6012344, 40.6912346], [-89.58, 40.70]
+                ]},
+                "properties": {"name": "Illinois River", "river_mile": 166.2, "OBJECTID": 4711}
+            }, {
+                "type": "Feature",
+                "geometry": {"type": "Polygon", "coordinates": [[
+                    [-89.7, 40.6], [-89.5, 40.6], [-89.5, 40.8], [-89.7, 40.6]
+                ]]},
+                "properties": {"name": "Peoria Lake"}
+            }]
+        });
+        let keep = ["name".to_string(), "river_mile".to_string()];
+        let tile = precompute("centerline", &geojson, Some(&keep), 5).unwrap();
+        let out: serde_json::Value = serde_json::from_slice(&tile.body).unwrap();
+
+        let line = &out["features"][0];
+        assert_eq!(line["geometry"]["coordinates"], serde_json::json!([[-89.60123, 40.69123], [-89.58, 40.7]]));
+        assert_eq!(line["properties"], serde_json::json!({"name": "Illinois River", "river_mile": 166.2}));
+        // A closed ring keeps its closing position
+        assert_eq!(out["features"][1]["geometry"]["coordinates"][0].as_array().unwrap().len(), 4);
+        assert_eq!(tile.features, 2);
+        assert_eq!(tile.bbox, Some([-89.7, 40.6, -89.5, 40.8]));
+        assert_eq!(out["bbox"], serde_json::json!([-89.7, 40.6, -89.5, 40.8]));
+    }
+
+    #[test]
+    fn test_bare_geometry_and_invalid_input() {
+        let point = serde_json::json!({"type": "Point", "coordinates": [-89.6, 40.7]});
+        let tile = precompute("levees", &point, None, 5).unwrap();
+        assert_eq!(tile.features, 1);
+
+        assert!(precompute("levees", &serde_json::json!({"type": "Topology"}), None, 5).is_err());
+        let bad = serde_json::json!({"type": "Point", "coordinates": ["x", 40.7]});
+        assert!(precompute("levees", &bad, None, 5).is_err());
+    }
+
+    #[test]
+    fn test_etag_matching() {
+        let tile = precompute("levees", &serde_json::json!({"type": "Point", "coordinates": [-89.6, 40.7]}), None, 5).unwrap();
+        assert_eq!(tile.etag, etag(&tile.body));
+        assert!(tile.matches(&tile.etag));
+        assert!(tile.matches(&format!("\"other\", W/{}", tile.etag)));
+        assert!(tile.matches("*"));
+        assert!(!tile.matches("\"0000000000000000\""));
+    }
+}
@@ -0,0 +1,266 @@
+//! Webcam snapshots captured on severity transitions.
+//!
+//! Optional: enabled by a `[cams]` section in flomon.toml listing still-image
+//! URLs (the IDOT river cams, a private camera). When a station changes
+//! flood severity (see `alert::transitions`) the daemon fetches every camera
+//! watching that station and stores the image in `flood_analysis.cam_snapshots`
+//! with the capture time, the transition, and the open flood event at the
+//! site, if any. Images are served at `/cams/snapshot/{id}`; `snapshot_path`
+//! builds the link used in alert messages and `flomon alerts list`.
+//!
+//! A camera that is down does not hold up the poll cycle: each fetch is
+//! bounded by `timeout_secs`, and failures are logged and skipped.
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+use serde::Deserialize;
+use std::collections::HashSet;
+
+use crate::alert::transitions::{severity_label, Transition};
+
+/// Default per-camera fetch timeout
+pub const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+/// Default largest image accepted
+pub const DEFAULT_MAX_BYTES: usize = 5 * 1024 * 1024;
+
+/// How far back `recent_snapshot_links` looks by default
+pub const RECENT_HOURS: i64 = 24;
+
+/// `[cams]` section of flomon.toml
+#[derive(Debug, Clone, Deserialize)]
+pub struct CamSettings {
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: usize,
+
+    #[serde(default)]
+    pub cameras: Vec<Camera>,
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+fn default_max_bytes() -> usize {
+    DEFAULT_MAX_BYTES
+}
+
+/// A webcam serving a still image at `url`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Camera {
+    /// Short unique label, e.g. "idot-peoria-i74"
+    pub name: String,
+    pub url: String,
+    /// Stations whose transitions trigger a snapshot
+    pub site_codes: Vec<String>,
+}
+
+impl CamSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.cameras.is_empty() {
+            return Err("cams.cameras must list at least one camera".to_string());
+        }
+        if self.timeout_secs == 0 {
+            return Err("cams.timeout_secs must be positive".to_string());
+        }
+        let mut seen = HashSet::new();
+        for camera in &self.cameras {
+            if camera.name.trim().is_empty() {
+                return Err("cams.cameras entries need a name".to_string());
+            }
+            if !seen.insert(camera.name.as_str()) {
+                return Err(format!("duplicate cams.cameras name {}", camera.name));
+            }
+            if !(camera.url.starts_with("http://") || camera.url.starts_with("https://")) {
+                return Err(format!("camera {} url must be http(s)", camera.name));
+            }
+            if camera.site_codes.is_empty() {
+                return Err(format!("camera {} must list at least one site_code", camera.name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Cameras watching `site_code`
+    pub fn cameras_for(&self, site_code: &str) -> Vec<&Camera> {
+        self.cameras.iter().filter(|c| c.site_codes.iter().any(|s| s == site_code)).collect()
+    }
+}
+
+/// One fetched image
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub camera: String,
+    pub url: String,
+    pub captured_at: DateTime<Utc>,
+    pub content_type: String,
+    pub image: Vec<u8>,
+}
+
+/// Fetch the current image from `camera`
+pub fn fetch(
+    http: &reqwest::blocking::Client,
+    camera: &Camera,
+    max_bytes: usize,
+    now: DateTime<Utc>,
+) -> Result<Snapshot, String> {
+    let response = http.get(&camera.url).send()
+        .map_err(|e| format!("Camera {} fetch failed: {}", camera.name, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Camera {} returned HTTP {}", camera.name, response.status()));
+    }
+    let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    let image = response.bytes()
+        .map_err(|e| format!("Camera {} body read failed: {}", camera.name, e))?
+        .to_vec();
+    check_image(&camera.name, &content_type, image.len(), max_bytes)?;
+    Ok(Snapshot {
+        camera: camera.name.clone(),
+        url: camera.url.clone(),
+        captured_at: now,
+        content_type,
+        image,
+    })
+}
+
+/// Reject non-image and oversized responses (error pages, HTML viewers)
+fn check_image(camera: &str, content_type: &str, len: usize, max_bytes: usize) -> Result<(), String> {
+    if !content_type.starts_with("image/") {
+        return Err(format!("Camera {} returned {:?}, not an image", camera, content_type));
+    }
+    if len == 0 || len > max_bytes {
+        return Err(format!("Camera {} image is {} bytes (limit {})", camera, len, max_bytes));
+    }
+    Ok(())
+}
+
+/// Store a snapshot taken for `transition`; returns its id.
+///
+/// The snapshot is tied to the site's open flood event, if one exists.
+pub fn store(client: &mut Client, snapshot: &Snapshot, transition: &Transition) -> Result<i64, String> {
+    let row = client.query_one(
+        "INSERT INTO flood_analysis.cam_snapshots
+         (camera, url, site_code, captured_at, severity_from, severity_to, stage_ft,
+          event_id, content_type, image)
+         VALUES ($1, $2, $3, $4, $5, $6, $7,
+                 (SELECT id FROM flood_analysis.events
+                  WHERE site_code = $3 AND event_end IS NULL
+                  ORDER BY event_start DESC LIMIT 1),
+                 $8, $9)
+         RETURNING id",
+        &[
+            &snapshot.camera,
+            &snapshot.url,
+            &transition.site_code,
+            &snapshot.captured_at,
+            &severity_label(transition.from.as_ref()),
+            &severity_label(transition.to.as_ref()),
+            &transition.stage_ft,
+            &snapshot.content_type,
+            &snapshot.image,
+        ],
+    ).map_err(|e| format!("Failed to store {} snapshot: {}", snapshot.camera, e))?;
+    Ok(row.get(0))
+}
+
+/// Endpoint path serving snapshot `id`
+pub fn snapshot_path(id: i64) -> String {
+    format!("/cams/snapshot/{}", id)
+}
+
+/// Links to snapshots taken at `site_code` since `since`, newest first
+pub fn recent_snapshot_links(client: &mut Client, site_code: &str, since: DateTime<Utc>) -> Result<Vec<String>, String> {
+    let rows = client.query(
+        "SELECT id FROM flood_analysis.cam_snapshots
+         WHERE site_code = $1 AND captured_at >= $2
+         ORDER BY captured_at DESC, id DESC",
+        &[&site_code, &since],
+    ).map_err(|e| format!("Failed to query snapshots for {}: {}", site_code, e))?;
+    Ok(rows.iter().map(|row| snapshot_path(row.get(0))).collect())
+}
+
+/// Default `since` for `recent_snapshot_links`
+pub fn recent_since(now: DateTime<Utc>) -> DateTime<Utc> {
+    now - Duration::hours(RECENT_HOURS)
+}
+
+/// Content type and bytes of snapshot `id`
+pub fn load_image(client: &mut Client, id: i64) -> Result<Option<(String, Vec<u8>)>, String> {
+    let row = client.query_opt(
+        "SELECT content_type, image FROM flood_analysis.cam_snapshots WHERE id = $1",
+        &[&id],
+    ).map_err(|e| format!("Failed to load snapshot {}: {}", id, e))?;
+    Ok(row.map(|r| (r.get(0), r.get(1))))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(toml_text: &str) -> CamSettings {
+        toml::from_str(toml_text).unwrap()
+    }
+
+    #[test]
+    fn test_settings_defaults_and_camera_lookup() {
+        let cams = settings(r#"
+            [[cameras]]
+            name = "idot-i74"
+            url = "https://cams.example.gov/i74.jpg"
+            site_codes = ["05567500"]
+
+            [[cameras]]
+            name = "dock"
+            url = "http://192.168.1.20/snapshot.jpg"
+            site_codes = ["05567500", "05568500"]
+        "#);
+        assert!(cams.validate().is_ok());
+        assert_eq!(cams.timeout_secs, DEFAULT_TIMEOUT_SECS);
+        assert_eq!(cams.cameras_for("05567500").len(), 2);
+        assert_eq!(cams.cameras_for("05568500")[0].name, "dock");
+        assert!(cams.cameras_for("05568000").is_empty());
+    }
+
+    #[test]
+    fn test_validation_rejects_bad_cameras() {
+        let duplicate = settings(r#"
+            [[cameras]]
+            name = "dock"
+            url = "http://a/1.jpg"
+            site_codes = ["05567500"]
+            [[cameras]]
+            name = "dock"
+            url = "http://a/2.jpg"
+            site_codes = ["05567500"]
+        "#);
+        assert!(duplicate.validate().unwrap_err().contains("duplicate"));
+
+        let ftp = settings(r#"
+            [[cameras]]
+            name = "old"
+            url = "ftp://a/1.jpg"
+            site_codes = ["05567500"]
+        "#);
+        assert!(ftp.validate().is_err());
+        assert!(settings("").validate().is_err());
+    }
+
+    #[test]
+    fn test_non_image_responses_rejected() {
+        assert!(check_image("dock", "image/jpeg", 40_000, DEFAULT_MAX_BYTES).is_ok());
+        assert!(check_image("dock", "text/html; charset=utf-8", 900, DEFAULT_MAX_BYTES).is_err());
+        assert!(check_image("dock", "image/jpeg", 0, DEFAULT_MAX_BYTES).is_err());
+        assert!(check_image("dock", "image/jpeg", DEFAULT_MAX_BYTES + 1, DEFAULT_MAX_BYTES).is_err());
+        assert_eq!(snapshot_path(12), "/cams/snapshot/12");
+    }
+}
@@ -0,0 +1,389 @@
+//! Machine-readable output for the informational CLI commands (`--json`).
+//!
+//! `status`, `alerts list`, `station show`, `verify`, `registry history`,
+//! `field-obs list` and `subscribe list` all accept `--json` and print one
+//! JSON document to stdout (progress and errors go to stderr):
+//!
+//! ```text
+//! {
+//!   "schema": "flomon.alerts",
+//!   "schema_version": 1,
+//!   "generated_at": "2024-05-01T12:00:00Z",
+//!   "data": [ ... ],
+//!   "attribution": [ ... ]
+//! }
+//! ```
+//!
+//! Within a schema version fields are only ever added, never renamed or
+//! removed, so scripts can rely on what they read today. Absent values are
+//! `null` rather than omitted. `attribution` credits the providers behind
+//! the schema (see `attribution::for_schema`); it is empty for local data.
+//! The structs below are the schemas; their tests pin the field names.
+
+use chrono::{DateTime, Utc};
+use postgres::Client;
+use serde::Serialize;
+
+use crate::alert::stalenesses::is_stale_at;
+use crate::alert::subscriptions::Subscription;
+use crate::alert::thresholds::check_station;
+use crate::attribution::{self, Attribution};
+use crate::model::{FloodThresholds, GaugeReading};
+use crate::registry::ChangeRecord;
+use crate::stations::Station;
+
+/// Version stamped into every document
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Readings older than this are reported `stale` (the daemon's default
+/// staleness threshold)
+pub const STALE_AFTER_MINUTES: u64 = 60;
+
+/// Common wrapper around every `--json` document
+#[derive(Debug, Serialize)]
+pub struct Envelope<T: Serialize> {
+    pub schema: String,
+    pub schema_version: u32,
+    pub generated_at: DateTime<Utc>,
+    pub data: T,
+    pub attribution: Vec<Attribution>,
+}
+
+/// Wrap `data` as schema `flomon.<kind>`
+pub fn envelope<T: Serialize>(kind: &str, data: T, now: DateTime<Utc>) -> Envelope<T> {
+    Envelope {
+        schema: format!("flomon.{}", kind),
+        schema_version: SCHEMA_VERSION,
+        generated_at: now,
+        data,
+        attribution: attribution::attributions(attribution::for_schema(kind)),
+    }
+}
+
+/// Pretty-printed document for `data`
+pub fn to_json<T: Serialize>(kind: &str, data: T) -> String {
+    serde_json::to_string_pretty(&envelope(kind, data, Utc::now()))
+        .unwrap_or_else(|e| format!("{{\"error\": \"failed to serialize {}: {}\"}}", kind, e))
+}
+
+// ============================================================================
+// Schemas
+// ============================================================================
+
+/// NWS stage thresholds, feet
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ThresholdsJson {
+    pub action_ft: f64,
+    pub flood_ft: f64,
+    pub moderate_ft: f64,
+    pub major_ft: f64,
+}
+
+impl From<&FloodThresholds> for ThresholdsJson {
+    fn from(t: &FloodThresholds) -> Self {
+        Self {
+            action_ft: t.action_stage_ft,
+            flood_ft: t.flood_stage_ft,
+            moderate_ft: t.moderate_flood_stage_ft,
+            major_ft: t.major_flood_stage_ft,
+        }
+    }
+}
+
+/// `flomon alerts list`: one station at or above action stage
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActiveAlert {
+    pub site_code: String,
+    pub site_name: String,
+    /// "action", "flood", "moderate" or "major"
+    pub severity: String,
+    pub stage_ft: f64,
+    pub reading_time: DateTime<Utc>,
+    /// Reading older than `STALE_AFTER_MINUTES`
+    pub stale: bool,
+    pub message: String,
+    pub registry_version: Option<i32>,
+    /// Links to webcam snapshots of the station from the last day (see `cams`)
+    pub snapshots: Vec<String>,
+}
+
+/// Latest value of one parameter at a station
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatestReading {
+    pub parameter_code: String,
+    pub value: f64,
+    pub unit: String,
+    pub qualifier: String,
+    pub reading_time: DateTime<Utc>,
+    pub age_minutes: i64,
+    pub stale: bool,
+    /// Percentile of the value among those on record for its date, when
+    /// USGS daily statistics are stored (see `ingest::usgs_stats`)
+    pub percentile: Option<f64>,
+    /// Return period of a discharge, years, from a Log-Pearson III fit to
+    /// the station's annual peaks (see `analysis::frequency`)
+    pub return_period_years: Option<f64>,
+}
+
+/// `flomon station show SITE`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StationDetail {
+    pub site_code: String,
+    pub name: String,
+    pub description: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub thresholds: Option<ThresholdsJson>,
+    pub expected_parameters: Vec<String>,
+    /// Current flood severity from the latest stage, if at or above action
+    pub severity: Option<String>,
+    pub latest: Vec<LatestReading>,
+}
+
+/// `flomon registry history`: one field change
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RegistryChangeJson {
+    pub version: i32,
+    pub changed_at: DateTime<Utc>,
+    pub changed_by: String,
+    pub site_code: String,
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+impl From<&ChangeRecord> for RegistryChangeJson {
+    fn from(r: &ChangeRecord) -> Self {
+        Self {
+            version: r.version,
+            changed_at: r.changed_at,
+            changed_by: r.changed_by.clone(),
+            site_code: r.change.site_code.clone(),
+            field: r.change.field.clone(),
+            old_value: r.change.old_value.clone(),
+            new_value: r.change.new_value.clone(),
+        }
+    }
+}
+
+/// `flomon subscribe list`: one recipient/zone pair
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubscriptionJson {
+    pub recipient: String,
+    pub zone_id: usize,
+    pub min_severity: String,
+    /// "config" (flomon.toml) or "db" (`flomon subscribe add`)
+    pub source: String,
+}
+
+impl SubscriptionJson {
+    pub fn new(sub: &Subscription, source: &str) -> Self {
+        Self {
+            recipient: sub.recipient.clone(),
+            zone_id: sub.zone_id,
+            min_severity: sub.min_severity.as_str().to_string(),
+            source: source.to_string(),
+        }
+    }
+}
+
+// ============================================================================
+// Builders
+// ============================================================================
+
+/// Alerts for stations whose latest stage is at or above action stage,
+/// most severe first
+pub fn active_alerts(
+    stations: &[Station],
+    latest_stages: &[GaugeReading],
+    registry_version: Option<i32>,
+    now: DateTime<Utc>,
+) -> Vec<ActiveAlert> {
+    let mut alerts: Vec<(crate::alert::thresholds::FloodSeverity, ActiveAlert)> = latest_stages.iter()
+        .filter_map(|reading| {
+            let station = stations.iter().find(|s| s.site_code == reading.site_code)?;
+            let alert = check_station(station, reading)?
+                .with_registry_version(registry_version);
+            Some((alert.severity.clone(), ActiveAlert {
+                site_code: station.site_code.clone(),
+                site_name: station.name.clone(),
+                severity: alert.severity.as_str().to_string(),
+                stage_ft: reading.value,
+                reading_time: reading.datetime.with_timezone(&Utc),
+                stale: is_stale_at(reading, STALE_AFTER_MINUTES, now),
+                message: alert.message,
+                registry_version: alert.registry_version,
+                snapshots: Vec::new(),
+            }))
+        })
+        .collect();
+    alerts.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.site_code.cmp(&b.1.site_code)));
+    alerts.into_iter().map(|(_, alert)| alert).collect()
+}
+
+/// Station registry entry with its latest readings
+pub fn station_detail(station: &Station, latest: &[GaugeReading], now: DateTime<Utc>) -> StationDetail {
+    let severity = latest.iter()
+        .find(|r| r.parameter_code == "00065")
+        .and_then(|stage| check_station(station, stage))
+        .map(|alert| alert.severity.as_str().to_string());
+
+    StationDetail {
+        site_code: station.site_code.clone(),
+        name: station.name.clone(),
+        description: station.description.clone(),
+        latitude: station.latitude,
+        longitude: station.longitude,
+        thresholds: station.thresholds.as_ref().map(ThresholdsJson::from),
+        expected_parameters: station.expected_parameters.clone(),
+        severity,
+        latest: latest.iter()
+            .map(|r| {
+                let time = r.datetime.with_timezone(&Utc);
+                LatestReading {
+                    parameter_code: r.parameter_code.clone(),
+                    value: r.value,
+                    unit: r.unit.clone(),
+                    qualifier: r.qualifier.clone(),
+                    reading_time: time,
+                    age_minutes: (now - time).num_minutes(),
+                    stale: is_stale_at(r, STALE_AFTER_MINUTES, now),
+                    percentile: None,
+                    return_period_years: None,
+                }
+            })
+            .collect(),
+    }
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// Latest reading of each parameter, optionally for one site (stage only
+/// when `stage_only`), from the hot table
+pub fn latest_readings(client: &mut Client, site_code: Option<&str>, stage_only: bool) -> Result<Vec<GaugeReading>, String> {
+    let rows = client.query(
+        "SELECT DISTINCT ON (site_code, parameter_code)
+                site_code, parameter_code, value, unit, reading_time, qualifier::TEXT
+         FROM usgs_raw.gauge_readings
+         WHERE ($1::TEXT IS NULL OR site_code = $1)
+           AND (NOT $2 OR parameter_code = '00065')
+         ORDER BY site_code, parameter_code, reading_time DESC",
+        &[&site_code, &stage_only],
+    ).map_err(|e| format!("Failed to query latest readings: {}", e))?;
+
+    Ok(rows.iter()
+        .map(|row| {
+            let site_code: String = row.get(0);
+            let value: rust_decimal::Decimal = row.get(2);
+            let time: DateTime<Utc> = row.get(4);
+            GaugeReading {
+                site_name: site_code.clone(),
+                site_code,
+                parameter_code: row.get(1),
+                value: value.to_string().parse().unwrap_or(0.0),
+                unit: row.get(3),
+                datetime: time.fixed_offset(),
+                qualifier: row.get(5),
+                original_unit: None,
+            }
+        })
+        .collect())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::stations::load_stations;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()
+    }
+
+    fn stage(site_code: &str, value: f64, minutes_ago: i64) -> GaugeReading {
+        GaugeReading {
+            site_code: site_code.to_string(),
+            site_name: site_code.to_string(),
+            parameter_code: "00065".to_string(),
+            unit: "ft".to_string(),
+            value,
+            datetime: (now() - chrono::Duration::minutes(minutes_ago)).fixed_offset(),
+            qualifier: "P".to_string(),
+            original_unit: None,
+        }
+    }
+
+    fn field_names(value: &serde_json::Value) -> Vec<String> {
+        value.as_object().unwrap().keys().cloned().collect()
+    }
+
+    #[test]
+    fn test_envelope_schema() {
+        let doc = serde_json::to_value(envelope("alerts", Vec::<ActiveAlert>::new(), now())).unwrap();
+        assert_eq!(doc["schema"], "flomon.alerts");
+        assert_eq!(doc["schema_version"], 1);
+        assert_eq!(doc["generated_at"], "2024-05-01T12:00:00Z");
+        assert!(doc["data"].as_array().unwrap().is_empty());
+        assert_eq!(doc["attribution"][0]["provider"], "USGS");
+    }
+
+    #[test]
+    fn test_active_alerts_most_severe_first() {
+        let stations = load_stations();
+        // Pool-regulated stations alert on deviation, not stage thresholds
+        let with_thresholds: Vec<&Station> = stations.iter()
+            .filter(|s| s.thresholds.is_some() && s.pool_deviation.is_none())
+            .take(2)
+            .collect();
+        let (a, b) = (with_thresholds[0], with_thresholds[1]);
+        let ta = a.thresholds.as_ref().unwrap();
+        let tb = b.thresholds.as_ref().unwrap();
+
+        let latest = vec![
+            stage(&a.site_code, ta.action_stage_ft + 0.1, 15),
+            stage(&b.site_code, tb.major_flood_stage_ft + 1.0, 120),
+        ];
+        let alerts = active_alerts(&stations, &latest, Some(7), now());
+
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].site_code, b.site_code);
+        assert_eq!(alerts[0].severity, "major");
+        assert!(alerts[0].stale);
+        assert_eq!(alerts[1].severity, "action");
+        assert!(!alerts[1].stale);
+        assert_eq!(alerts[1].registry_version, Some(7));
+
+        // Below action stage: nothing to report
+        let quiet = vec![stage(&a.site_code, ta.action_stage_ft - 1.0, 15)];
+        assert!(active_alerts(&stations, &quiet, None, now()).is_empty());
+    }
+
+    #[test]
+    fn test_alert_and_station_field_names_are_stable() {
+        let stations = load_stations();
+        let station = stations.iter().find(|s| s.thresholds.is_some()).unwrap();
+        let t = station.thresholds.as_ref().unwrap();
+        let latest = vec![stage(&station.site_code, t.flood_stage_ft, 30)];
+
+        let alert = serde_json::to_value(&active_alerts(&stations, &latest, None, now())[0]).unwrap();
+        assert_eq!(field_names(&alert), [
+            "message", "reading_time", "registry_version", "severity",
+            "site_code", "site_name", "snapshots", "stage_ft", "stale",
+        ]);
+
+        let detail = serde_json::to_value(station_detail(station, &latest, now())).unwrap();
+        assert_eq!(field_names(&detail), [
+            "description", "expected_parameters", "latest", "latitude", "longitude",
+            "name", "severity", "site_code", "thresholds",
+        ]);
+        assert_eq!(detail["severity"], "flood");
+        assert_eq!(detail["latest"][0]["age_minutes"], 30);
+        assert_eq!(field_names(&detail["thresholds"]), ["action_ft", "flood_ft", "major_ft", "moderate_ft"]);
+    }
+}
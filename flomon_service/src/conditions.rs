@@ -0,0 +1,570 @@
+//! Current-conditions queries behind the status routes.
+//!
+//! Zone detail, basin status and the embed widget summary, built from the
+//! latest readings in the database. `endpoint` serves them over HTTP and
+//! `status_cache` writes them into the daemon's snapshot, so neither
+//! depends on the other.
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+
+use crate::alert::scheme::SeverityScheme;
+use crate::alert::thresholds::check_station;
+use crate::analysis::backwater;
+use crate::analysis::groupings::group_by_zone;
+use crate::analysis::slope;
+use crate::archive;
+use crate::ingest::cwms_quality;
+use crate::model::GaugeReading;
+use crate::registry;
+use crate::stations;
+use crate::zones::{self, ZoneMetadata, get_zone};
+use flomon_types::api::{
+    ActiveZoneStatus, BackwaterRiskResponse, BasinStatusResponse, CoordinatesResponse, EmbedSummaryResponse,
+    SensorDetailResponse, UpstreamFloodPulseResponse, ZoneDetailResponse, ZoneMetadataResponse,
+    ZoneStatusResponse,
+};
+
+// ============================================================================
+// Zones and Basin Status
+// ============================================================================
+
+/// Fetch zone detail with all sensor readings, as of `as_of` (default now)
+pub fn fetch_zone_detail(
+    client: &mut Client,
+    zone_id: usize,
+    as_of: Option<DateTime<Utc>>,
+) -> Result<ZoneDetailResponse, String> {
+    let now = as_of.unwrap_or_else(Utc::now);
+    let zones_config = zones::load_zones_default()
+        .map_err(|e| format!("Failed to load zones.toml: {}", e))?;
+    
+    let zone = get_zone(&zones_config, zone_id)
+        .ok_or_else(|| format!("Zone {} not found", zone_id))?;
+    
+    let metadata = ZoneMetadata::for_zone(zone_id);
+    
+    // Fetch all recent USGS readings
+    let usgs_readings = archive::latest_known(client, None, false, now, Duration::hours(ZONE_LOOKBACK_HOURS))?;
+    
+    // Group by zone
+    let zone_readings = group_by_zone(usgs_readings, &zones_config);
+    let this_zone_readings = zone_readings.iter()
+        .find(|zr| zr.zone_id == zone_id)
+        .ok_or_else(|| format!("Zone {} readings not found", zone_id))?;
+    
+    // Build sensor details
+    let mut sensors = Vec::new();
+    let mut sensors_above_action = Vec::new();
+    let mut sensors_above_flood = Vec::new();
+    let mut active_count = 0;
+    let mut stale_count = 0;
+    
+    for sensor_data in &this_zone_readings.sensors {
+        let sensor = &sensor_data.sensor;
+        
+        // Extract current reading
+        let (current_value, current_unit, current_timestamp, staleness) = 
+            if let Some(ref readings) = sensor_data.readings {
+                // Prefer stage over discharge for thresholds
+                let stage_reading = readings.stage_ft.as_ref();
+                let discharge_reading = readings.discharge_cfs.as_ref();
+                
+                let reading_opt = stage_reading.or(discharge_reading);
+                
+                if let Some(reading) = reading_opt {
+                    let staleness_min = (now - reading.datetime.with_timezone(&Utc)).num_minutes();
+                    
+                    active_count += 1;
+                    if staleness_min > 120 {
+                        stale_count += 1;
+                    }
+                    
+                    (Some(reading.value), Some(reading.unit.clone()), 
+                     Some(reading.datetime.to_rfc3339()), Some(staleness_min))
+                } else {
+                    stale_count += 1;
+                    (None, None, None, None)
+                }
+            } else {
+                // For CWMS/ASOS sensors, fetch from appropriate tables
+                let (val, unit, ts, stale) = fetch_sensor_reading(client, sensor, now)?;
+                if val.is_some() {
+                    active_count += 1;
+                    if stale.unwrap_or(9999) > 120 {
+                        stale_count += 1;
+                    }
+                } else {
+                    stale_count += 1;
+                }
+                (val, unit, ts, stale)
+            };
+        
+        // Check thresholds
+        if let (Some(value), Some(action)) = (current_value, sensor.action_stage_ft)
+            && value >= action {
+            sensors_above_action.push(sensor.primary_id());
+        }
+        
+        if let (Some(value), Some(flood)) = (current_value, sensor.flood_stage_ft)
+            && value >= flood {
+            sensors_above_flood.push(sensor.primary_id());
+        }
+        
+        sensors.push(SensorDetailResponse {
+            sensor_id: sensor.primary_id(),
+            sensor_type: sensor.sensor_type.clone(),
+            role: sensor.role.clone(),
+            location: sensor.location.clone(),
+            coordinates: CoordinatesResponse {
+                lat: sensor.lat,
+                lon: sensor.lon,
+            },
+            source: sensor.source.clone(),
+            current_value,
+            current_unit,
+            current_timestamp,
+            staleness_minutes: staleness,
+            flood_stage_ft: sensor.flood_stage_ft,
+            action_stage_ft: sensor.action_stage_ft,
+            relevance: sensor.relevance.clone(),
+        });
+    }
+    
+    // Determine zone alert level
+    let alert_level = if !sensors_above_flood.is_empty() {
+        "CRITICAL"
+    } else if !sensors_above_action.is_empty() {
+        "WARNING"
+    } else if stale_count > sensors.len() / 2 {
+        "DEGRADED"
+    } else {
+        "NORMAL"
+    };
+    
+    Ok(ZoneDetailResponse {
+        zone_id,
+        zone_name: zone.name.clone(),
+        description: zone.description.clone(),
+        metadata: ZoneMetadataResponse {
+            lead_time_hours_min: metadata.lead_time_hours_min,
+            lead_time_hours_max: metadata.lead_time_hours_max,
+            primary_alert_condition: metadata.primary_alert_condition,
+        },
+        sensors,
+        zone_status: ZoneStatusResponse {
+            alert_level: alert_level.to_string(),
+            active_sensors: active_count,
+            stale_sensors: stale_count,
+            sensors_above_action,
+            sensors_above_flood,
+        },
+        last_updated: now,
+    })
+}
+
+/// Fetch overall basin status
+pub fn fetch_basin_status(client: &mut Client) -> Result<BasinStatusResponse, String> {
+    let _zones_config = zones::load_zones_default()
+        .map_err(|e| format!("Failed to load zones.toml: {}", e))?;
+    
+    let mut active_zones = Vec::new();
+    let mut overall_elevated = false;
+    let mut overall_watch = false;
+    let mut overall_warning = false;
+    
+    // Check each zone for activity
+    for zone_id in 0..=6 {
+        let zone_detail = fetch_zone_detail(client, zone_id, None)?;
+        
+        let zone_active = match zone_detail.zone_status.alert_level.as_str() {
+            "CRITICAL" => {
+                overall_warning = true;
+                true
+            }
+            "WARNING" => {
+                overall_watch = true;
+                true
+            }
+            "DEGRADED" | "NORMAL" => false,
+            _ => false,
+        };
+        
+        if zone_active || !zone_detail.zone_status.sensors_above_action.is_empty() {
+            overall_elevated = true;
+            let metadata = ZoneMetadata::for_zone(zone_id);
+            
+            active_zones.push(ActiveZoneStatus {
+                zone_id,
+                zone_name: zone_detail.zone_name.clone(),
+                status: zone_detail.zone_status.alert_level.clone(),
+                lead_time_hours: metadata.lead_time_hours_max,
+                key_sensors_elevated: zone_detail.zone_status.sensors_above_action,
+            });
+        }
+    }
+    
+    // Determine overall status
+    let overall_status = if overall_warning {
+        "FLOOD_WARNING"
+    } else if overall_watch {
+        "FLOOD_WATCH"
+    } else if overall_elevated {
+        "ELEVATED"
+    } else {
+        "NORMAL"
+    };
+    
+    // Backwater risk analysis
+    let backwater_risk = analyze_backwater_risk(client)?;
+    
+    // Upstream flood pulse detection
+    let upstream_pulse = detect_upstream_flood_pulse(&active_zones);
+    
+    // Compound event risk
+    let zone_0_active = active_zones.iter().any(|z| z.zone_id == 0);
+    let zone_4_plus_active = active_zones.iter().any(|z| z.zone_id >= 4);
+    
+    let compound_risk = if zone_0_active && zone_4_plus_active {
+        "HIGH"
+    } else if zone_0_active || zone_4_plus_active {
+        "MODERATE"
+    } else {
+        "LOW"
+    };
+    
+    Ok(BasinStatusResponse {
+        overall_status: overall_status.to_string(),
+        active_zones,
+        backwater_risk,
+        upstream_flood_pulse: upstream_pulse,
+        compound_event_risk: compound_risk.to_string(),
+        last_updated: Utc::now(),
+        registry_version: registry::current_version(client)?,
+    })
+}
+
+/// Analyze backwater flood risk
+pub fn analyze_backwater_risk(client: &mut Client) -> Result<BackwaterRiskResponse, String> {
+    // Fetch key sensors from Zone 0 (Mississippi) and Zone 1 (LaGrange)
+    let grafton_stage = fetch_cwms_stage(client, "Grafton", "GRFI2")?;
+    let lagrange_pool = fetch_cwms_stage(client, "LaGrange", "IL08P")?;
+    let lagrange_tailwater = fetch_cwms_stage(client, "LaGrange", "IL08TW")?;
+    
+    let differential = match (lagrange_pool, lagrange_tailwater) {
+        (Some(pool), Some(tw)) => Some(pool - tw),
+        _ => None,
+    };
+    
+    // Analyze risk level from pool/tailwater
+    let pool_risk = backwater::pool_risk(grafton_stage, differential);
+    
+    // A flattening Peoria-Kingston Mines slope confirms backwater
+    let stage_slope = fetch_stage_slope(client)?;
+    let (risk_level, confidence) = slope::combine_with_backwater(
+        pool_risk,
+        stage_slope.and_then(|(value, trend)| Some((value, trend?))),
+    );
+    
+    let mut explanation = format!(
+        "Backwater risk is {} based on Grafton stage ({:.1} ft) and LaGrange pool-tailwater differential ({:.1} ft). \
+         When Grafton exceeds 20ft and LaGrange differential drops below 1ft, Mississippi backwater is dominating Illinois River drainage.",
+        risk_level,
+        grafton_stage.unwrap_or(0.0),
+        differential.unwrap_or(99.0)
+    );
+    if let Some((value, trend)) = stage_slope {
+        explanation.push_str(&format!(
+            " Peoria-Kingston Mines slope is {:.3} ft/mile ({}); confidence {}.",
+            value,
+            trend.map(|t| t.as_str()).unwrap_or("trend unknown"),
+            confidence,
+        ));
+    }
+    
+    Ok(BackwaterRiskResponse {
+        risk_level,
+        grafton_stage_ft: grafton_stage,
+        lagrange_pool_ft: lagrange_pool,
+        lagrange_tailwater_ft: lagrange_tailwater,
+        pool_tailwater_differential_ft: differential,
+        stage_slope_ft_per_mile: stage_slope.map(|(value, _)| value),
+        stage_slope_trend: stage_slope.and_then(|(_, trend)| trend).map(|t| t.as_str().to_string()),
+        confidence: confidence.to_string(),
+        explanation,
+    })
+}
+
+/// Latest Peoria-Kingston Mines slope and trend, if computed in the last two hours
+fn fetch_stage_slope(client: &mut Client) -> Result<Option<(f64, Option<slope::SlopeTrend>)>, String> {
+    let row = client.query_opt(
+        "SELECT slope_ft_per_mile, trend
+         FROM flood_analysis.stage_slope
+         WHERE timestamp >= NOW() - INTERVAL '2 hours'
+         ORDER BY timestamp DESC
+         LIMIT 1",
+        &[],
+    ).map_err(|e| format!("Stage slope query failed: {}", e))?;
+    
+    Ok(row.map(|row| {
+        let trend: Option<String> = row.get(1);
+        (row.get(0), trend.as_deref().and_then(slope::SlopeTrend::parse))
+    }))
+}
+
+/// Detect upstream flood pulse
+fn detect_upstream_flood_pulse(active_zones: &[ActiveZoneStatus]) -> UpstreamFloodPulseResponse {
+    let upstream_active: Vec<usize> = active_zones.iter()
+        .filter(|z| z.zone_id >= 4)  // Zones 4, 5, 6
+        .map(|z| z.zone_id)
+        .collect();
+    
+    let pulse_detected = !upstream_active.is_empty();
+    
+    let estimated_arrival = if upstream_active.contains(&6) {
+        Some(72)  // 3 days from Chicago
+    } else if upstream_active.contains(&5) {
+        Some(48)  // 2 days from Dresden Island
+    } else if upstream_active.contains(&4) {
+        Some(24)  // 1 day from Starved Rock
+    } else {
+        None
+    };
+    
+    let explanation = if pulse_detected {
+        format!(
+            "Upstream flood pulse detected in zones: {}. Estimated arrival at property in {} hours.",
+            upstream_active.iter().map(|z| z.to_string()).collect::<Vec<_>>().join(", "),
+            estimated_arrival.unwrap_or(0)
+        )
+    } else {
+        "No upstream flood pulse detected in upper basin zones.".to_string()
+    };
+    
+    UpstreamFloodPulseResponse {
+        pulse_detected,
+        estimated_arrival_hours: estimated_arrival,
+        source_zones: upstream_active,
+        explanation,
+    }
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+/// How far back zone detail looks for a sensor's latest reading
+const ZONE_LOOKBACK_HOURS: i64 = 4;
+
+/// Latest sensor value: (value, unit, timestamp, age in minutes)
+type SensorReading = (Option<f64>, Option<String>, Option<String>, Option<i64>);
+
+/// Fetch sensor reading (for CWMS/ASOS sensors) as known at `now`;
+/// rejected or missing CWMS values are skipped
+fn fetch_sensor_reading(
+    client: &mut Client,
+    sensor: &zones::Sensor,
+    now: DateTime<Utc>,
+) -> Result<SensorReading, String> {
+    
+    if sensor.is_cwms() {
+        // Query CWMS timeseries table
+        if let Some(cwms_loc) = &sensor.cwms_location {
+            let rows = client.query(
+                &format!(
+                    "SELECT value, unit, timestamp
+                     FROM usace.cwms_timeseries
+                     WHERE location_id = $1 AND {}
+                       AND timestamp <= $2 AND (ingested_at IS NULL OR ingested_at <= $2)
+                     ORDER BY timestamp DESC
+                     LIMIT 1",
+                    cwms_quality::USABLE_SQL,
+                ),
+                &[cwms_loc, &now]
+            ).map_err(|e| format!("CWMS query failed: {}", e))?;
+            
+            if let Some(row) = rows.first() {
+                let value: rust_decimal::Decimal = row.get(0);
+                let unit: String = row.get(1);
+                let timestamp: DateTime<Utc> = row.get(2);
+                let staleness = (now - timestamp).num_minutes();
+                
+                return Ok((
+                    Some(value.to_string().parse().unwrap_or(0.0)),
+                    Some(unit),
+                    Some(timestamp.to_rfc3339()),
+                    Some(staleness)
+                ));
+            }
+        }
+    } else if sensor.is_asos() {
+        // Query ASOS observations table
+        if let Some(station_id) = &sensor.station_id {
+            let rows = client.query(
+                "SELECT precip_1hr_in, observation_time
+                 FROM asos_observations
+                 WHERE station_id = $1
+                   AND observation_time <= $2 AND (ingested_at IS NULL OR ingested_at <= $2)
+                 ORDER BY observation_time DESC
+                 LIMIT 1",
+                &[station_id, &now]
+            ).map_err(|e| format!("ASOS query failed: {}", e))?;
+            
+            if let Some(row) = rows.first() {
+                let value_opt: Option<f64> = row.get(0);
+                let timestamp: DateTime<Utc> = row.get(1);
+                let staleness = (now - timestamp).num_minutes();
+                
+                if let Some(value) = value_opt {
+                    return Ok((
+                        Some(value),
+                        Some("in".to_string()),
+                        Some(timestamp.to_rfc3339()),
+                        Some(staleness)
+                    ));
+                }
+            }
+        }
+    }
+    
+    Ok((None, None, None, None))
+}
+
+/// Latest usable CWMS stage for a specific location
+fn fetch_cwms_stage(client: &mut Client, location_name: &str, _shef_id: &str) -> Result<Option<f64>, String> {
+    let rows = client.query(
+        &format!(
+            "SELECT value
+             FROM usace.cwms_timeseries
+             WHERE location_id LIKE $1 AND {}
+             ORDER BY timestamp DESC
+             LIMIT 1",
+            cwms_quality::USABLE_SQL,
+        ),
+        &[&format!("%{}%", location_name)]
+    ).map_err(|e| format!("CWMS stage query failed: {}", e))?;
+    
+    if let Some(row) = rows.first() {
+        let value: rust_decimal::Decimal = row.get(0);
+        Ok(Some(value.to_string().parse().unwrap_or(0.0)))
+    } else {
+        Ok(None)
+    }
+}
+
+// ============================================================================
+// Embed Widget Summary
+// ============================================================================
+
+/// Stage change over this window sets the trend arrow
+const EMBED_TREND_WINDOW_HOURS: i64 = 3;
+/// Changes smaller than this (ft) are reported as steady
+const EMBED_TREND_DEADBAND_FT: f64 = 0.1;
+/// Readings older than this are shown as stale (grey)
+const EMBED_STALE_MINUTES: i64 = 120;
+
+/// Classify the change between an earlier and the latest stage
+fn embed_trend(change_ft: Option<f64>) -> (&'static str, &'static str) {
+    match change_ft {
+        Some(c) if c > EMBED_TREND_DEADBAND_FT => ("rising", "↑"),
+        Some(c) if c < -EMBED_TREND_DEADBAND_FT => ("falling", "↓"),
+        Some(_) => ("steady", "→"),
+        None => ("unknown", ""),
+    }
+}
+
+/// Build the widget summary for one gauge
+pub fn fetch_embed_summary(
+    client: &mut Client,
+    site_code: &str,
+    scheme: &SeverityScheme,
+) -> Result<EmbedSummaryResponse, String> {
+    let station = stations::find_station(site_code)
+        .ok_or_else(|| format!("Unknown site {}", site_code))?;
+    
+    let rows = client.query(
+        "SELECT value, reading_time
+         FROM usgs_raw.gauge_readings
+         WHERE site_code = $1 AND parameter_code = '00065'
+           AND reading_time >= NOW() - make_interval(hours => $2)
+         ORDER BY reading_time DESC",
+        &[&site_code, &(EMBED_TREND_WINDOW_HOURS as i32 + 1)]
+    ).map_err(|e| format!("Failed to fetch stage: {}", e))?;
+    
+    let samples: Vec<(f64, DateTime<Utc>)> = rows.iter()
+        .map(|row| {
+            let value: rust_decimal::Decimal = row.get(0);
+            (value.to_string().parse().unwrap_or(0.0), row.get(1))
+        })
+        .collect();
+    
+    let Some(&(stage, observed_at)) = samples.first() else {
+        return Ok(EmbedSummaryResponse {
+            site_code: station.site_code.clone(),
+            site_name: station.name.clone(),
+            stage_ft: None,
+            severity: scheme.no_data.key.clone(),
+            color: scheme.no_data.color.clone(),
+            severity_label: scheme.no_data.label.clone(),
+            trend: "unknown".to_string(),
+            trend_arrow: String::new(),
+            change_ft: None,
+            observed_at: None,
+            registry_version: None,
+        });
+    };
+    
+    // Earliest sample at or before the start of the trend window
+    let window_start = observed_at - Duration::hours(EMBED_TREND_WINDOW_HOURS);
+    let change_ft = samples.iter()
+        .find(|(_, t)| *t <= window_start)
+        .map(|(earlier, _)| stage - earlier);
+    
+    let reading = GaugeReading {
+        site_code: station.site_code.clone(),
+        site_name: station.name.clone(),
+        parameter_code: "00065".to_string(),
+        unit: "ft".to_string(),
+        value: stage,
+        datetime: observed_at.fixed_offset(),
+        qualifier: "P".to_string(),
+        original_unit: None,
+    };
+    let registry_version = registry::current_version(client)?;
+    let severity = check_station(&station, &reading).map(|alert| alert.severity);
+    let stale = (Utc::now() - observed_at).num_minutes() > EMBED_STALE_MINUTES;
+    
+    let style = scheme.style_for(severity.as_ref(), stale);
+    let (trend, arrow) = embed_trend(change_ft);
+    
+    Ok(EmbedSummaryResponse {
+        site_code: station.site_code.clone(),
+        site_name: station.name.clone(),
+        stage_ft: Some(stage),
+        severity: style.key.clone(),
+        color: style.color.clone(),
+        severity_label: style.label.clone(),
+        trend: trend.to_string(),
+        trend_arrow: arrow.to_string(),
+        change_ft: change_ft.map(|c| (c * 100.0).round() / 100.0),
+        observed_at: Some(observed_at),
+        registry_version,
+    })
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embed_trend_deadband() {
+        assert_eq!(embed_trend(Some(0.4)), ("rising", "↑"));
+        assert_eq!(embed_trend(Some(-0.25)), ("falling", "↓"));
+        assert_eq!(embed_trend(Some(0.05)), ("steady", "→"));
+        assert_eq!(embed_trend(None).0, "unknown");
+    }
+}
@@ -1,11 +1,18 @@
-/// Station configuration loader - parses usgs_stations.toml
-///
-/// Separates station metadata from code, making it easy to update
-/// thresholds, add stations, or adjust travel time estimates without
-/// recompiling the service.
+//! Station configuration loader - parses usgs_stations.toml
+//!
+//! Separates station metadata from code, making it easy to update
+//! thresholds, add stations, or adjust travel time estimates without
+//! recompiling the service.
+//!
+//! The consolidated service configuration (`flomon.toml`, with includes and
+//! environment interpolation) lives in the [`service`] submodule.
+
+pub mod service;
+
+pub use service::{ConfigError, ServiceConfig};
 
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 
 use crate::model::FloodThresholds;
@@ -26,14 +33,35 @@ pub struct StationConfig {
     pub distance_direction: String,  // "upstream", "downstream", "tributary_south", etc.
     pub travel_time_to_peoria_hours: f64,
     
+    // Illinois Waterway river mile (above Grafton); main-stem gauges only
+    pub river_mile: Option<f64>,
+    
     // NWS flood stage thresholds (optional - not all stations have official thresholds)
     pub thresholds: Option<ThresholdConfig>,
     
+    // Gauge datum: elevation of zero stage, and the vertical system it is in
+    pub gauge_datum_ft: Option<f64>,
+    pub gauge_datum: Option<String>,  // e.g., "NGVD29"
+    // Feet added to an elevation in each datum to give NGVD29, e.g. { NAVD88 = 0.3 }
+    #[serde(default)]
+    pub to_ngvd29_ft: BTreeMap<String, f64>,
+    
+    // NWS geography for alert relevance (looked up from coordinates if absent)
+    pub nws_zone: Option<String>,     // forecast zone UGC, e.g., "ILZ031"
+    pub county_fips: Option<String>,  // e.g., "17143" (Peoria County)
+    
     // Expected USGS parameters at this site
     pub expected_parameters: Vec<String>,  // e.g., ["00060", "00065"]
     
+    // Regulated pool gauges: alert on deviation from target pool instead
+    pub pool_deviation: Option<PoolDeviationConfig>,
+    
     // Peak flow data metadata (optional)
     pub peak_flow: Option<PeakFlowMetadata>,
+    
+    // CRITICAL priority: fetched ahead of the shared concurrency bound in event mode
+    #[serde(default)]
+    pub critical: bool,
 }
 
 /// Flood stage thresholds from NWS AHPS
@@ -46,6 +74,19 @@ pub struct ThresholdConfig {
     pub description: String,
 }
 
+/// Alert levels relative to a regulated pool's target elevation (feet,
+/// negative = below target). Takes precedence over `thresholds` for alerting.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PoolDeviationConfig {
+    pub pool_location: String,                 // zones.toml cwms_location, e.g., "Peoria-Pool"
+    pub target_elevation_ft: Option<f64>,      // NGVD29; default: the zones.toml pool_target_ft_ngvd29
+    pub action_ft: f64,
+    pub flood_ft: f64,
+    pub moderate_ft: f64,
+    pub major_ft: f64,
+    pub description: String,
+}
+
 /// Peak flow data availability and metadata
 #[derive(Debug, Clone, Deserialize)]
 pub struct PeakFlowMetadata {
@@ -64,6 +105,17 @@ struct StationRegistry {
     station: Vec<StationConfig>,
 }
 
+/// The usgs_stations.toml shipped with the service, compiled in as the
+/// built-in registry (see `stations::builtin_stations`)
+pub const BUILTIN_REGISTRY: &str = include_str!("../../usgs_stations.toml");
+
+/// Parses the `[[station]]` entries of a usgs_stations.toml document.
+pub fn parse_registry(contents: &str) -> Result<Vec<StationConfig>, String> {
+    toml::from_str::<StationRegistry>(contents)
+        .map(|registry| registry.station)
+        .map_err(|e| e.to_string())
+}
+
 /// Loads station registry from usgs_stations.toml configuration file.
 ///
 /// # Panics
@@ -80,10 +132,8 @@ pub fn load_config() -> Vec<StationConfig> {
     let contents = fs::read_to_string(config_path)
         .unwrap_or_else(|e| panic!("Failed to read {}: {}", config_path, e));
     
-    let registry: StationRegistry = toml::from_str(&contents)
-        .unwrap_or_else(|e| panic!("Failed to parse {}: {}", config_path, e));
-    
-    registry.station
+    parse_registry(&contents)
+        .unwrap_or_else(|e| panic!("Failed to parse {}: {}", config_path, e))
 }
 
 /// Loads station registry and builds a lookup map keyed by site code.
@@ -129,7 +179,7 @@ mod tests {
             assert!(!station.name.is_empty(), "Name must not be empty");
             assert!(station.latitude >= -90.0 && station.latitude <= 90.0);
             assert!(station.longitude >= -180.0 && station.longitude <= 180.0);
-            assert!(station.expected_parameters.len() > 0, "Must have at least one parameter");
+            assert!(!station.expected_parameters.is_empty(), "Must have at least one parameter");
         }
     }
 
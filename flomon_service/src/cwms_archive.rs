@@ -0,0 +1,379 @@
+//! Delta-encoded storage for CWMS timeseries past the hot window.
+//!
+//! CWMS pool elevations arrive every 15 minutes but, on a regulated pool,
+//! hold the same value for hours or days at a time. Rows in
+//! `usace.cwms_timeseries` older than the hot window are compacted into
+//! `usace.cwms_timeseries_runs`: each run is one value (with its quality
+//! code) and the regular sampling grid over which it held, stored as
+//! `valid_from`, `valid_to`, `step_seconds` and `sample_count`. A week of
+//! unchanged pool becomes one row instead of 672.
+//!
+//! The encoding is lossless: a run only grows while the value and quality
+//! are identical *and* the next sample falls exactly one step later, so a
+//! gap in the data starts a new run and every original timestamp can be
+//! regenerated. `series_between` reconstructs the full series from hot rows
+//! and runs; the `usace.cwms_timeseries_all` view does the same for ad hoc
+//! SQL (migration 021).
+//!
+//! Entry points:
+//! - `flomon archive --cwms [--days N] [--dry-run]` (CLI, intended for cron)
+//! - `series_between` (query layer)
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::archive;
+use crate::ingest::cwms::CwmsTimeseries;
+
+/// CWMS rows newer than this many days stay in `usace.cwms_timeseries`
+pub const HOT_RETENTION_DAYS: i64 = 30;
+
+/// One stored sample
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub timestamp: DateTime<Utc>,
+    pub value: Decimal,
+    pub quality_code: Option<i32>,
+}
+
+/// Consecutive identical samples on a regular grid
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub value: Decimal,
+    pub quality_code: Option<i32>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_to: DateTime<Utc>,
+    /// Spacing of the samples; 0 for a single-sample run
+    pub step_seconds: i64,
+    pub sample_count: i64,
+}
+
+impl Run {
+    fn start(sample: &Sample) -> Self {
+        Run {
+            value: sample.value,
+            quality_code: sample.quality_code,
+            valid_from: sample.timestamp,
+            valid_to: sample.timestamp,
+            step_seconds: 0,
+            sample_count: 1,
+        }
+    }
+
+    /// Append `sample` if it repeats the value one step after the run
+    fn extend(&mut self, sample: &Sample) -> bool {
+        if sample.value != self.value || sample.quality_code != self.quality_code {
+            return false;
+        }
+        let gap = (sample.timestamp - self.valid_to).num_seconds();
+        if gap <= 0 || (self.sample_count > 1 && gap != self.step_seconds) {
+            return false;
+        }
+        self.step_seconds = gap;
+        self.valid_to = sample.timestamp;
+        self.sample_count += 1;
+        true
+    }
+
+    /// Every sample the run stands for
+    pub fn samples(&self) -> impl Iterator<Item = Sample> + '_ {
+        (0..self.sample_count).map(move |i| Sample {
+            timestamp: self.valid_from + Duration::seconds(i * self.step_seconds),
+            value: self.value,
+            quality_code: self.quality_code,
+        })
+    }
+}
+
+/// Delta-encode `samples` (ascending, all after `seed.valid_to`).
+///
+/// `seed` is the series' last stored run; if the first samples continue it
+/// the returned first run is the extended seed.
+pub fn encode(seed: Option<Run>, samples: &[Sample]) -> Vec<Run> {
+    let mut runs: Vec<Run> = seed.into_iter().collect();
+    for sample in samples {
+        if !runs.last_mut().is_some_and(|run| run.extend(sample)) {
+            runs.push(Run::start(sample));
+        }
+    }
+    runs
+}
+
+/// Expand runs back into samples, ordered by time
+pub fn decode(runs: &[Run]) -> Vec<Sample> {
+    let mut samples: Vec<Sample> = runs.iter().flat_map(Run::samples).collect();
+    samples.sort_by_key(|s| s.timestamp);
+    samples
+}
+
+// ============================================================================
+// Compaction
+// ============================================================================
+
+/// Result of one compaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactStats {
+    /// Everything before this instant was compacted
+    pub cutoff: DateTime<Utc>,
+    /// Hot rows removed (or that would be, on a dry run)
+    pub rows: u64,
+    /// Runs inserted or extended
+    pub runs: u64,
+    /// Timeseries touched
+    pub series: u64,
+}
+
+/// (location_id, timeseries_id, parameter_id, unit, original_unit)
+type SeriesKey = (String, String, String, String, Option<String>);
+
+/// Compact hot rows older than `retention_days` into runs.
+///
+/// Runs in one transaction: runs are written and the hot rows deleted
+/// together. Rows already covered by a stored run (re-ingested after an
+/// earlier compaction) are dropped. With `dry_run`, nothing is written and
+/// the stats describe what would happen.
+pub fn compact(
+    client: &mut Client,
+    now: DateTime<Utc>,
+    retention_days: i64,
+    dry_run: bool,
+) -> Result<CompactStats, String> {
+    let cutoff = archive::archive_cutoff(now, retention_days);
+    let mut tx = client.transaction()
+        .map_err(|e| format!("Failed to start CWMS compaction transaction: {}", e))?;
+
+    let rows = tx.query(
+        "SELECT location_id, timeseries_id, parameter_id, unit, original_unit,
+                timestamp, value, quality_code
+         FROM usace.cwms_timeseries
+         WHERE timestamp < $1
+         ORDER BY timestamp",
+        &[&cutoff],
+    ).map_err(|e| format!("Failed to read CWMS rows to compact: {}", e))?;
+
+    let mut series: BTreeMap<SeriesKey, Vec<Sample>> = BTreeMap::new();
+    for row in &rows {
+        series.entry((row.get(0), row.get(1), row.get(2), row.get(3), row.get(4)))
+            .or_default()
+            .push(Sample { timestamp: row.get(5), value: row.get(6), quality_code: row.get(7) });
+    }
+
+    let mut runs_written = 0;
+    for (key, samples) in &series {
+        let (location_id, timeseries_id, parameter_id, unit, original_unit) = key;
+        let seed = tx.query_opt(
+            "SELECT id, value, quality_code, valid_from, valid_to, step_seconds, sample_count
+             FROM usace.cwms_timeseries_runs
+             WHERE timeseries_id = $1 AND parameter_id = $2 AND unit = $3
+               AND original_unit IS NOT DISTINCT FROM $4
+             ORDER BY valid_to DESC
+             LIMIT 1",
+            &[timeseries_id, parameter_id, unit, original_unit],
+        ).map_err(|e| format!("Failed to read last run of {}: {}", timeseries_id, e))?
+            .map(|row| (row.get::<_, i64>(0), Run {
+                value: row.get(1),
+                quality_code: row.get(2),
+                valid_from: row.get(3),
+                valid_to: row.get(4),
+                step_seconds: row.get::<_, i32>(5) as i64,
+                sample_count: row.get::<_, i32>(6) as i64,
+            }));
+
+        // Samples before the last run may be backfill or re-ingested duplicates
+        let last_end = seed.as_ref().map(|(_, run)| run.valid_to);
+        let (earlier, later): (Vec<Sample>, Vec<Sample>) = samples.iter().cloned()
+            .partition(|s| last_end.is_some_and(|end| s.timestamp <= end));
+        let earlier = uncovered(&mut tx, key, earlier)?;
+
+        let mut new_runs = encode(None, &earlier);
+        let continued = encode(seed.as_ref().map(|(_, run)| run.clone()), &later);
+        let mut continued = continued.into_iter();
+        if let Some((seed_id, seed_run)) = &seed {
+            let first = continued.next().expect("encode keeps the seed run");
+            if first != *seed_run {
+                runs_written += 1;
+                if !dry_run {
+                    tx.execute(
+                        "UPDATE usace.cwms_timeseries_runs
+                         SET valid_to = $2, step_seconds = $3, sample_count = $4, compacted_at = NOW()
+                         WHERE id = $1",
+                        &[seed_id, &first.valid_to, &(first.step_seconds as i32), &(first.sample_count as i32)],
+                    ).map_err(|e| format!("Failed to extend run of {}: {}", timeseries_id, e))?;
+                }
+            }
+        }
+        new_runs.extend(continued);
+
+        runs_written += new_runs.len() as u64;
+        if dry_run {
+            continue;
+        }
+        for run in &new_runs {
+            tx.execute(
+                "INSERT INTO usace.cwms_timeseries_runs
+                 (location_id, timeseries_id, parameter_id, unit, original_unit,
+                  value, quality_code, valid_from, valid_to, step_seconds, sample_count)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+                &[
+                    location_id, timeseries_id, parameter_id, unit, original_unit,
+                    &run.value, &run.quality_code, &run.valid_from, &run.valid_to,
+                    &(run.step_seconds as i32), &(run.sample_count as i32),
+                ],
+            ).map_err(|e| format!("Failed to write run of {}: {}", timeseries_id, e))?;
+        }
+    }
+
+    let stats = CompactStats {
+        cutoff,
+        rows: rows.len() as u64,
+        runs: runs_written,
+        series: series.len() as u64,
+    };
+    if dry_run {
+        return Ok(stats);
+    }
+    tx.execute("DELETE FROM usace.cwms_timeseries WHERE timestamp < $1", &[&cutoff])
+        .map_err(|e| format!("Failed to remove compacted CWMS rows: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to commit CWMS compaction: {}", e))?;
+    Ok(stats)
+}
+
+/// Drop samples whose timestamp a stored run of the series already covers
+fn uncovered(tx: &mut postgres::Transaction, key: &SeriesKey, samples: Vec<Sample>) -> Result<Vec<Sample>, String> {
+    let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+        return Ok(samples);
+    };
+    let (_, timeseries_id, parameter_id, unit, original_unit) = key;
+    let stored: Vec<Run> = tx.query(
+        "SELECT value, quality_code, valid_from, valid_to, step_seconds, sample_count
+         FROM usace.cwms_timeseries_runs
+         WHERE timeseries_id = $1 AND parameter_id = $2 AND unit = $3
+           AND original_unit IS NOT DISTINCT FROM $4
+           AND valid_to >= $5 AND valid_from <= $6",
+        &[timeseries_id, parameter_id, unit, original_unit, &first.timestamp, &last.timestamp],
+    ).map_err(|e| format!("Failed to read runs of {}: {}", timeseries_id, e))?
+        .iter()
+        .map(|row| Run {
+            value: row.get(0),
+            quality_code: row.get(1),
+            valid_from: row.get(2),
+            valid_to: row.get(3),
+            step_seconds: row.get::<_, i32>(4) as i64,
+            sample_count: row.get::<_, i32>(5) as i64,
+        })
+        .collect();
+    let covered: HashSet<DateTime<Utc>> = decode(&stored).into_iter().map(|s| s.timestamp).collect();
+    Ok(samples.into_iter().filter(|s| !covered.contains(&s.timestamp)).collect())
+}
+
+// ============================================================================
+// Query layer
+// ============================================================================
+
+/// Full series for one location and parameter in `[start, end]`, oldest
+/// first, from hot rows and decoded runs.
+///
+/// A timestamp present in both is returned once, preferring the hot row.
+pub fn series_between(
+    client: &mut Client,
+    location_id: &str,
+    parameter_id: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<CwmsTimeseries>, String> {
+    let rows = client.query(
+        "SELECT DISTINCT ON (timestamp) timeseries_id, timestamp, value, unit, original_unit, quality_code
+         FROM (
+             SELECT timeseries_id, timestamp, value, unit, original_unit, quality_code, 0 AS tier
+             FROM usace.cwms_timeseries
+             WHERE location_id = $1 AND parameter_id = $2
+               AND timestamp BETWEEN $3 AND $4
+             UNION ALL
+             SELECT r.timeseries_id, t, r.value, r.unit, r.original_unit, r.quality_code, 1 AS tier
+             FROM usace.cwms_timeseries_runs r
+             CROSS JOIN LATERAL generate_series(
+                 r.valid_from, r.valid_to, make_interval(secs => GREATEST(r.step_seconds, 1))) AS t
+             WHERE r.location_id = $1 AND r.parameter_id = $2
+               AND r.valid_to >= $3 AND r.valid_from <= $4
+               AND t BETWEEN $3 AND $4
+         ) combined
+         ORDER BY timestamp, tier",
+        &[&location_id, &parameter_id, &start, &end],
+    ).map_err(|e| format!("Failed to query CWMS history: {}", e))?;
+
+    Ok(rows.iter()
+        .map(|row| {
+            let value: Decimal = row.get(2);
+            CwmsTimeseries {
+                timeseries_id: row.get(0),
+                location_id: location_id.to_string(),
+                parameter_id: parameter_id.to_string(),
+                timestamp: row.get(1),
+                value: value.to_string().parse().unwrap_or(0.0),
+                unit: row.get(3),
+                original_unit: row.get(4),
+                quality_code: row.get::<_, Option<i32>>(5).unwrap_or(0),
+            }
+        })
+        .collect())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn pool(minutes_and_values: &[(i64, &str)]) -> Vec<Sample> {
+        let t0 = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        minutes_and_values.iter()
+            .map(|(m, v)| Sample {
+                timestamp: t0 + Duration::minutes(*m),
+                value: v.parse().unwrap(),
+                quality_code: Some(0),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_flat_pool_collapses_to_one_run() {
+        let samples = pool(&(0..96).map(|i| (i * 15, "440.0200")).collect::<Vec<_>>());
+        let runs = encode(None, &samples);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].sample_count, 96);
+        assert_eq!(runs[0].step_seconds, 900);
+        assert_eq!(decode(&runs), samples);
+    }
+
+    #[test]
+    fn test_changes_and_gaps_start_new_runs() {
+        // Value change at 30 min, then a missing sample at 75 min
+        let samples = pool(&[(0, "440.02"), (15, "440.02"), (30, "440.05"), (45, "440.05"), (60, "440.05"), (90, "440.05")]);
+        let runs = encode(None, &samples);
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs.iter().map(|r| r.sample_count).collect::<Vec<_>>(), [2, 3, 1]);
+        assert_eq!(decode(&runs), samples);
+    }
+
+    #[test]
+    fn test_quality_change_breaks_run() {
+        let mut samples = pool(&[(0, "440.02"), (15, "440.02")]);
+        samples[1].quality_code = Some(3);
+        assert_eq!(encode(None, &samples).len(), 2);
+    }
+
+    #[test]
+    fn test_seed_run_is_extended() {
+        let all = pool(&[(0, "440.02"), (15, "440.02"), (30, "440.02"), (45, "440.10")]);
+        let seed = encode(None, &all[..2]).pop();
+        let runs = encode(seed, &all[2..]);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].sample_count, 3);
+        assert_eq!(decode(&runs), all);
+    }
+}
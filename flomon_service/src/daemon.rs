@@ -1,24 +1,175 @@
-/// Core daemon implementation for flood monitoring service
-///
-/// This module implements the main daemon loop that:
-/// 1. Validates database connectivity and schemas on startup
-/// 2. Detects staleness of existing data
-/// 3. Backfills missing historical data
-/// 4. Continuously polls USGS/USACE APIs for new data
-/// 5. Warehouses readings and maintains monitoring state
-/// 6. Generates alerts for threshold exceedances and staleness
-
-use crate::db;
+//! Core daemon implementation for flood monitoring service
+//!
+//! This module implements the main daemon loop that:
+//! 1. Validates database connectivity and schemas on startup
+//! 2. Detects staleness of existing data
+//! 3. Backfills missing historical data
+//! 4. Continuously polls USGS/USACE APIs for new data
+//! 5. Warehouses readings and maintains monitoring state
+//! 6. Generates alerts for threshold exceedances, overdue readings
+//!    (per-feed learned cadence, see `monitor::cadence`) and stale data
+//!    (see `alert::stalenesses`)
+//!
+//! With `[isws]` set, the ISWS/IDNR stage product for the Peoria pool is
+//! read every few hours into `flood_analysis.stage_forecasts` for
+//! verification against observed stage (see `ingest::isws`).
+//!
+//! # Poll scheduling
+//! Each USGS station, CWMS location and ASOS station has its own next poll
+//! time: a cycle fetches only the feeds that are due and the loop sleeps
+//! until the next one is. USGS stations follow the adaptive interval, CWMS
+//! and ASOS feeds their monitoring priority; all tighten while any station
+//! is at or above action stage (see `monitor::schedule`).
+//!
+//! # Dry run
+//! With `set_dry_run(true)` every fetch and parse runs as normal, but each
+//! write is replaced by a printed `[dry-run]` line naming the table, the
+//! row, and whether the insert would add a row, update one, or be skipped
+//! by its `ON CONFLICT` clause. Reads (staleness, history, conflict checks)
+//! still hit the database, so this is safe against production.
+//!
+//! # Shutdown
+//! `run()` returns after SIGINT/SIGTERM (see `shutdown`). Queued alert
+//! notifications get `shutdown_drain_seconds` to go out; the rest are
+//! persisted and redelivered by the next `initialize()` (see
+//! `alert::queue`).
+//!
+//! # Registry reload
+//! Each cycle starts by checking the station registry's files (flomon.toml
+//! and its includes, or usgs_stations.toml) for edits. A changed registry
+//! that validates replaces the running one and is recorded as a new
+//! registry version; one that doesn't is logged and ignored.
+//!
+//! # Threshold overrides
+//! Each cycle also re-reads `flood_analysis.thresholds` and applies it on
+//! top of the registry; a change in the effective thresholds is recorded
+//! as a registry version credited to whoever wrote the override (see
+//! `threshold_overrides`).
+//!
+//! # Suppressions
+//! Each cycle also re-reads the stations and sensors marked down in
+//! `station_suppressions`. A suppressed station's poll failures are logged
+//! as expected and its stale and overdue readings raise nothing; a
+//! suppressed sensor's readings are left out of severity (see
+//! `monitor::suppression`).
+//!
+//! # Alert rules
+//! Composite `[[alert_rules]]` are evaluated at the end of every cycle
+//! (see `alert::rules`). Rules in shadow mode are counted and logged like
+//! active ones but never reach the notification queue.
+//!
+//! # Rainfall alarm
+//! A `[precip_alarm]` is checked against every cycle's precipitation
+//! rollups and alerts on heavy basin or station rain whatever the gauges
+//! say (see `alert::precip_alarm`).
+//!
+//! # Live stream
+//! Each station's newest readings (when a poll stored new rows) and every
+//! severity transition are announced with PostgreSQL NOTIFY, which the
+//! API server relays to /api/stream clients (see `stream`).
+//!
+//! # Flood outlook
+//! With `[outlook]` set, every cycle also weighs basin rain, upstream
+//! stage trends and the fitted lags to the target station, and tells its
+//! zone subscribers when flooding there looks likely within the next
+//! hours, before any threshold is crossed (see `alert::outlook`).
+//!
+//! # Backwater
+//! Every cycle the LaGrange and Peoria pool/tailwater heads, the Grafton
+//! stage and the reach slope are assessed for Mississippi backwater and
+//! recorded in `flood_analysis.backwater_status`; the Peoria zones are
+//! alerted when it starts affecting their reach (see `analysis::backwater`).
+//!
+//! # Zone status
+//! At the end of every cycle each zone's sensors are rolled up into its
+//! worst severity, fastest stage rise, stale sensors and rainfall, and
+//! recorded in `flood_analysis.zone_status` (see `zones::status`).
+//!
+//! # Climatology
+//! Once a week the USGS daily statistics of every station are refreshed
+//! into `usgs_raw.daily_statistics`; an escalation alert quotes where the
+//! station's latest discharge falls among the values on record for the
+//! date (see `ingest::usgs_stats`). Once a month the USGS annual peaks
+//! are refreshed into `usgs_raw.annual_peaks`, and the alert also says
+//! where the stage would rank among them (see `ingest::usgs_peaks`) and
+//! the return period of the discharge from a Log-Pearson III fit to them
+//! (see `analysis::frequency`).
+//!
+//! # NWS alerts
+//! With `[nws_alerts]` set, the flood watches and warnings in effect for
+//! the local counties are fetched every cycle, stored, and dispatched to
+//! the zones they cover the first time they are seen (see
+//! `ingest::nws_alerts`).
+//!
+//! # Standby instances
+//! Several daemons may run against one database (a warm standby on a
+//! second box). Only the holder of the dispatch lock delivers
+//! notifications; the others evaluate alerts but drop what they queue,
+//! and take over when the leader's lock is released (see `alert::leader`).
+//! A dry run never takes the lock.
+
+use crate::alert::dispatch::Dispatcher;
+use crate::alert::leader::{DispatchLock, Role};
+use crate::alert::occupancy::{self, OccupancySettings};
+use crate::alert::outlook::{self, Outlook, OutlookInputs, UpstreamTrend};
+use crate::alert::queue::{self, Deliver, DrainReport, NotificationQueue, Urgency};
+use crate::alert::precip_alarm::PrecipAlarm;
+use crate::alert::rules::{self, RuleEngine, RuleInputs, RuleMode};
+use crate::alert::scheme::SeverityScheme;
+use crate::alert::stalenesses::StalenessTracker;
+use crate::alert::thresholds::{self as thresholds, FloodAlert, FloodSeverity};
+use crate::alert::subscriptions::{self, Subscription, SubscriptionConfig};
+use crate::alert::transitions::{self, SeverityTracker, Transition};
+use crate::analysis::anomaly;
+use crate::analysis::datum_shift;
+use crate::analysis::freeboard::{self, FreeboardSettings};
+use crate::analysis::frequency::FrequencyCurve;
+use crate::analysis::lag;
+use crate::analysis::precip;
+use crate::analysis::rating_drift;
+use crate::analysis::precip_response::{self, ResponseTracker};
+use crate::analysis::backwater::{self, BackwaterInputs};
+use crate::analysis::slope;
+use crate::archive;
+use crate::cams::{self, CamSettings};
+use crate::config::ServiceConfig;
+use crate::data_quality;
+use crate::forecasts;
+use crate::db::{self, bulk};
 use crate::logging;
-use crate::stations::{self, Station};
+use crate::monitor::adaptive::{self, AdaptiveScheduler, PollingDecision};
+use crate::monitor::cadence::{CadenceTracker, StalenessChange};
+use crate::monitor::catchup::{self, CatchUpReport, RateLimiter};
+use crate::monitor::event_mode::{self, EventMode, Signals};
+use crate::monitor::failover::{self, FailoverSettings, ReadinessTracker};
+use crate::monitor::maintenance::{self, MaintenanceWindow, Source};
+use crate::monitor::suppression::{self, Suppression};
+use crate::monitor::schedule::{self, PollSchedule};
+use crate::quality;
+use crate::registry;
+use crate::shutdown;
+use crate::status_cache::{self, StatusCacheSettings};
+use crate::stream::{self, StreamEvent};
+use crate::stations::{self, RegistryWatcher, Station};
+use crate::threshold_overrides::{self, ThresholdOverride};
 use crate::usace_locations::{self, UsaceLocation};
+use crate::zones::{self, ZonesConfig};
+use crate::zones::status::{self as zone_status, CycleState};
+use crate::nws_geo::{self, AlertFilter};
 use crate::asos_locations::{self, AsosLocation};
-use crate::model::GaugeReading;
-use crate::ingest::{usgs, cwms, iem};
+use crate::backfill::{self, BackfillReport};
+use crate::model::{GaugeReading, IngestError, PARAM_STAGE};
+use crate::ingest::{usgs, usgs_rdb, usgs_peaks, usgs_stats, cwms, cwms_quality, iem, field_measurements};
+use crate::ingest::fetch::{self, CyclePlan, CycleResults, CwmsRequest, FetchLimits};
+use crate::ingest::mrms::{self, MrmsSettings};
+use crate::ingest::nws_alerts::{self, NwsAlertSettings};
+use crate::ingest::isws::{self, IswsSettings};
+use crate::ingest::rating::{self, Rating};
+use crate::ingest::retry::{self, RetryPolicy};
+use crate::ingest::plugin::{self, DataSourcePlugin};
 use chrono::{DateTime, Duration, Utc};
 use postgres::Client;
-use rust_decimal::Decimal;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
 
 // ---------------------------------------------------------------------------
@@ -30,19 +181,83 @@ pub struct DaemonConfig {
     /// How often to poll USGS API (default: 15 minutes to match USGS update frequency)
     pub poll_interval_minutes: u64,
     
-    /// Maximum age of data before considered stale (default: 60 minutes)
+    /// Poll interval while any station is at action stage or rising fast
+    /// (default: 5 minutes; see `monitor::adaptive`)
+    pub elevated_poll_interval_minutes: u64,
+    
+    /// Maximum age of data before considered stale (default: 60 minutes).
+    /// Overdue alerts use it only until a feed's cadence has been learned
+    /// (see `monitor::cadence`).
     pub staleness_threshold_minutes: u64,
     
     /// How many days of historical data to backfill (default: 120 days)
     pub backfill_days: u64,
+    
+    /// Time allowed to deliver queued notifications on shutdown (default: 20 seconds)
+    pub shutdown_drain_seconds: u64,
+    
+    /// Requests in flight at once during a poll cycle (default: 8; see `ingest::fetch`)
+    pub fetch_concurrency: usize,
+    
+    /// Extra requests in flight for CRITICAL stations in event mode, which
+    /// skip the `fetch_concurrency` bound (default: 2; 0 disables)
+    pub critical_fetch_concurrency: usize,
+    
+    /// Per-source fetch timeouts, counted per station (defaults: 15, 45, 15 seconds)
+    pub usgs_timeout_seconds: u64,
+    pub cwms_timeout_seconds: u64,
+    pub asos_timeout_seconds: u64,
+    
+    /// Backoff for transient HTTP failures: first and longest wait
+    /// (defaults: 500 ms, 4000 ms), time after which no further attempt is
+    /// started (default: 10 seconds; 0 disables retries) and the largest
+    /// share of a wait removed at random (default: 0.5; see `ingest::retry`)
+    pub retry_initial_delay_ms: u64,
+    pub retry_max_delay_ms: u64,
+    pub retry_max_elapsed_seconds: u64,
+    pub retry_jitter: f64,
+}
+
+impl DaemonConfig {
+    /// Concurrency bound and timeouts for the poll cycle's fetch phase
+    pub fn fetch_limits(&self) -> FetchLimits {
+        FetchLimits {
+            max_concurrent: self.fetch_concurrency,
+            critical_concurrent: self.critical_fetch_concurrency,
+            usgs_timeout: std::time::Duration::from_secs(self.usgs_timeout_seconds),
+            cwms_timeout: std::time::Duration::from_secs(self.cwms_timeout_seconds),
+            asos_timeout: std::time::Duration::from_secs(self.asos_timeout_seconds),
+        }
+    }
+    
+    /// Backoff applied to every ingest HTTP request
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            initial_delay: std::time::Duration::from_millis(self.retry_initial_delay_ms),
+            max_delay: std::time::Duration::from_millis(self.retry_max_delay_ms),
+            max_elapsed: std::time::Duration::from_secs(self.retry_max_elapsed_seconds),
+            jitter: self.retry_jitter,
+        }
+    }
 }
 
 impl Default for DaemonConfig {
     fn default() -> Self {
         Self {
             poll_interval_minutes: 15,
+            elevated_poll_interval_minutes: adaptive::DEFAULT_ELEVATED_MINUTES,
             staleness_threshold_minutes: 60,
             backfill_days: 120,
+            shutdown_drain_seconds: queue::DEFAULT_DRAIN_SECONDS,
+            fetch_concurrency: fetch::DEFAULT_CONCURRENCY,
+            critical_fetch_concurrency: fetch::DEFAULT_CRITICAL_CONCURRENCY,
+            usgs_timeout_seconds: fetch::DEFAULT_USGS_TIMEOUT_SECS,
+            cwms_timeout_seconds: fetch::DEFAULT_CWMS_TIMEOUT_SECS,
+            asos_timeout_seconds: fetch::DEFAULT_ASOS_TIMEOUT_SECS,
+            retry_initial_delay_ms: retry::DEFAULT_INITIAL_DELAY_MS,
+            retry_max_delay_ms: retry::DEFAULT_MAX_DELAY_MS,
+            retry_max_elapsed_seconds: retry::DEFAULT_MAX_ELAPSED_SECS,
+            retry_jitter: retry::DEFAULT_JITTER,
         }
     }
 }
@@ -51,70 +266,311 @@ impl Default for DaemonConfig {
 // Daemon State
 // ---------------------------------------------------------------------------
 
+/// Station registries supplied up front (from flomon.toml) rather than
+/// loaded from the individual TOML files during `initialize()`
+struct Registries {
+    stations: Vec<Station>,
+    cwms_locations: Vec<UsaceLocation>,
+    asos_locations: Vec<AsosLocation>,
+}
+
 /// Main daemon state
 pub struct Daemon {
     config: DaemonConfig,
     stations: Vec<Station>,
+    /// Stations as the registry defines them, before threshold overrides
+    registry_stations: Vec<Station>,
+    /// Overrides applied to `stations` (see `threshold_overrides`)
+    threshold_overrides: Vec<ThresholdOverride>,
     cwms_locations: Vec<UsaceLocation>,
     asos_locations: Vec<AsosLocation>,
+    preloaded: Option<Registries>,
+    /// Reloads the station registry when its files change (see `stations::RegistryWatcher`)
+    registry_watcher: Option<RegistryWatcher>,
+    freeboard: Option<FreeboardSettings>,
+    cams: Option<CamSettings>,
+    failover: Option<FailoverSettings>,
+    readiness: ReadinessTracker,
+    measurements_refreshed: Option<DateTime<Utc>>,
+    isws: Option<IswsSettings>,
+    isws_refreshed: Option<DateTime<Utc>>,
+    /// Stage-discharge rating per discharge station (see `ingest::rating`)
+    ratings: HashMap<String, Rating>,
+    ratings_refreshed: Option<DateTime<Utc>>,
+    stats_refreshed: Option<DateTime<Utc>>,
+    peaks_refreshed: Option<DateTime<Utc>>,
+    dry_run: bool,
+    /// Only source polled, when restricted (see `set_source`)
+    only_source: Option<Source>,
+    registry_version: Option<i32>,
+    notifications: NotificationQueue,
+    deliver: Box<Deliver>,
+    subscription_config: Vec<SubscriptionConfig>,
+    subscriptions: Vec<Subscription>,
+    zones: Option<ZonesConfig>,
+    occupancy: Option<OccupancySettings>,
+    /// Composite alert rules, active or shadow (see `alert::rules`)
+    rules: RuleEngine,
+    /// Latest basin mean precipitation per (basin, window hours), for the rules
+    basin_precip: HashMap<(String, i64), f64>,
+    /// Latest station precipitation rollups, for the zone status
+    station_precip: Vec<precip::PrecipRollup>,
+    /// Rainfall alarm, independent of stage (see `alert::precip_alarm`)
+    precip_alarm: Option<PrecipAlarm>,
+    /// Pre-flood advisory for one target station (see `alert::outlook`)
+    outlook: Option<Outlook>,
+    nws_alerts: Option<NwsAlertSettings>,
+    /// Periods when a source's failures are expected (see `monitor::maintenance`)
+    maintenance: Vec<MaintenanceWindow>,
+    /// Stations and sensors marked down, re-read every cycle (see `monitor::suppression`)
+    suppressions: Vec<Suppression>,
+    mrms: Option<MrmsSettings>,
+    /// Snapshot rewritten each cycle for the endpoint (see `status_cache`)
+    status_cache: Option<StatusCacheSettings>,
+    severity: SeverityScheme,
+    dispatch: DispatchLock,
+    adaptive: AdaptiveScheduler,
+    /// Next poll time of each USGS station, CWMS location and ASOS station
+    schedule: PollSchedule,
+    severities: SeverityTracker,
+    /// Latest USGS reading per station this run, for stale data alerts
+    latest_readings: HashMap<String, GaugeReading>,
+    staleness: StalenessTracker,
+    event_mode: EventMode,
+    /// Runs the poll cycle's concurrent fetches; started on first use
+    fetch_runtime: Option<tokio::runtime::Runtime>,
+    responses: ResponseTracker,
+    usgs_cadence: CadenceTracker,
+    asos_cadence: CadenceTracker,
+    plugins: Vec<Box<dyn DataSourcePlugin>>,
     client: Option<Client>,
 }
 
+impl Default for Daemon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Daemon {
     /// Create a new daemon instance with default configuration
     pub fn new() -> Self {
-        Self {
-            config: DaemonConfig::default(),
-            stations: Vec::new(),
-            cwms_locations: Vec::new(),
-            asos_locations: Vec::new(),
-            client: None,
-        }
+        Self::with_config(DaemonConfig::default())
     }
     
     /// Create daemon with custom configuration
     pub fn with_config(config: DaemonConfig) -> Self {
+        retry::set_policy(config.retry_policy());
         Self {
+            adaptive: AdaptiveScheduler::new(config.poll_interval_minutes, config.elevated_poll_interval_minutes),
+            schedule: PollSchedule::new(),
+            usgs_cadence: CadenceTracker::new(config.staleness_threshold_minutes),
+            // METARs are hourly, so the fixed limit would fire on schedule
+            asos_cadence: CadenceTracker::new(config.staleness_threshold_minutes * 2),
             config,
             stations: Vec::new(),
+            registry_stations: Vec::new(),
+            threshold_overrides: Vec::new(),
             cwms_locations: Vec::new(),
             asos_locations: Vec::new(),
+            preloaded: None,
+            registry_watcher: None,
+            freeboard: None,
+            cams: None,
+            failover: None,
+            readiness: ReadinessTracker::new(),
+            measurements_refreshed: None,
+            isws: None,
+            isws_refreshed: None,
+            ratings: HashMap::new(),
+            ratings_refreshed: None,
+            stats_refreshed: None,
+            peaks_refreshed: None,
+            dry_run: false,
+            only_source: None,
+            registry_version: None,
+            notifications: NotificationQueue::new(),
+            deliver: Box::new(log_delivery),
+            subscription_config: Vec::new(),
+            subscriptions: Vec::new(),
+            zones: None,
+            occupancy: None,
+            rules: RuleEngine::default(),
+            basin_precip: HashMap::new(),
+            station_precip: Vec::new(),
+            precip_alarm: None,
+            outlook: None,
+            nws_alerts: None,
+            maintenance: Vec::new(),
+            suppressions: Vec::new(),
+            mrms: None,
+            status_cache: None,
+            severity: SeverityScheme::default(),
+            dispatch: DispatchLock::new(),
+            severities: SeverityTracker::new(),
+            latest_readings: HashMap::new(),
+            staleness: StalenessTracker::new(Utc::now()),
+            event_mode: EventMode::new(Utc::now()),
+            fetch_runtime: None,
+            responses: ResponseTracker::new(),
+            plugins: Vec::new(),
             client: None,
         }
     }
     
+    /// Create daemon from the consolidated flomon.toml configuration.
+    ///
+    /// Registries come from the config instead of the individual TOML
+    /// files that `initialize()` would otherwise read.
+    pub fn from_service_config(config: &ServiceConfig) -> Self {
+        let mut daemon = Self::with_config(config.daemon_config());
+        daemon.preloaded = Some(Registries {
+            stations: config.stations(),
+            cwms_locations: config.usace_locations(),
+            asos_locations: config.asos_locations(),
+        });
+        daemon.registry_watcher = Some(RegistryWatcher::service_config(config));
+        daemon.freeboard = config.freeboard.clone();
+        daemon.cams = config.cams.clone();
+        daemon.failover = config.failover.clone();
+        let dispatcher = Dispatcher::from_config(&config.notifiers, config.matrix.as_ref());
+        if !dispatcher.is_empty() {
+            daemon.deliver = Box::new(move |n: &queue::Notification| {
+                dispatcher.deliver(n).unwrap_or_else(|| log_delivery(n))
+            });
+        }
+        daemon.subscription_config = config.subscriptions.clone();
+        daemon.zones = config.zones_config();
+        daemon.occupancy = config.occupancy.clone();
+        daemon.rules = RuleEngine::new(config.alert_rules.clone());
+        daemon.precip_alarm = config.precip_alarm.clone().map(PrecipAlarm::new);
+        daemon.outlook = config.outlook.clone().map(Outlook::new);
+        daemon.nws_alerts = config.nws_alerts.clone();
+        daemon.maintenance = config.maintenance_windows.clone();
+        daemon.isws = config.isws.clone();
+        daemon.mrms = config.mrms.clone();
+        daemon.status_cache = config.status_cache.clone();
+        daemon.severity = config.severity.clone();
+        // Already instantiated once by config validation
+        daemon.plugins = config.plugins().expect("[[plugins]] validated when flomon.toml was loaded");
+        daemon
+    }
+    
+    /// Replace the data source plugins polled each cycle (see `ingest::plugin`)
+    pub fn set_plugins(&mut self, plugins: Vec<Box<dyn DataSourcePlugin>>) {
+        self.plugins = plugins;
+    }
+    
+    /// Print would-be writes instead of executing them (see module docs)
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+    
+    /// Poll only `source` (`flomon ingest --source`); every source when None.
+    /// Plugins are polled only when every source is.
+    pub fn set_source(&mut self, source: Option<Source>) {
+        self.only_source = source;
+        self.restrict_to_source();
+    }
+    
+    /// Drop the registries of sources other than `only_source`
+    fn restrict_to_source(&mut self) {
+        let Some(source) = self.only_source else {
+            return;
+        };
+        if source != Source::Usgs {
+            self.stations.clear();
+        }
+        if source != Source::Cwms {
+            self.cwms_locations.clear();
+        }
+        if source != Source::Asos {
+            self.asos_locations.clear();
+        }
+        self.plugins.clear();
+    }
+    
+    /// Whether writes are being replaced by dry-run reports
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+    
+    /// Replace the notification channel (default: write alerts to the log)
+    pub fn set_deliver(&mut self, deliver: Box<Deliver>) {
+        self.deliver = deliver;
+    }
+    
+    /// Queue `alert` for `recipient`, stamped with the current registry version
+    pub fn notify(&mut self, recipient: &str, alert: FloodAlert) {
+        self.notify_with_urgency(recipient, alert, Urgency::Normal);
+    }
+    
+    fn notify_with_urgency(&mut self, recipient: &str, alert: FloodAlert, urgency: Urgency) {
+        let alert = alert.with_registry_version(self.registry_version);
+        self.notifications.enqueue_with_urgency(recipient, alert, urgency, Utc::now());
+    }
+    
+    /// Notifications waiting for delivery
+    pub fn pending_notifications(&self) -> usize {
+        self.notifications.len()
+    }
+    
     /// Initialize daemon: validate database and load stations
     pub fn initialize(&mut self) -> Result<(), Box<dyn Error>> {
         // Validate database schemas
         let mut client = db::connect_and_verify(&["usgs_raw", "nws", "usace"])?;
         
+        let preloaded = self.preloaded.take();
+        
         // Load USGS station registry from TOML
-        self.stations = stations::load_stations();
+        self.stations = match &preloaded {
+            Some(registries) => registries.stations.clone(),
+            None => {
+                self.registry_watcher = Some(RegistryWatcher::station_file(stations::DEFAULT_REGISTRY_PATH));
+                stations::load_stations()
+            }
+        };
         
         if self.stations.is_empty() {
             return Err("No stations configured in usgs_stations.toml".into());
         }
         
+        self.registry_stations = std::mem::take(&mut self.stations);
+        self.threshold_overrides = threshold_overrides::load(&mut client).unwrap_or_else(|e| {
+            logging::warn(logging::DataSource::Database, None, &e);
+            Vec::new()
+        });
+        self.stations = apply_threshold_overrides(&self.registry_stations, &self.threshold_overrides);
+        self.registry_version = record_registry(&mut client, &self.stations, &registry::changed_by(), self.dry_run)?;
+        
+        // Carry an event (or its recovery) across the restart
+        if let Some((mode, since, reason)) = event_mode::load_current(&mut client)? {
+            self.event_mode = EventMode::resume(mode, since);
+            self.usgs_cadence.set_overdue_sigma(mode.overdue_sigma());
+            self.asos_cadence.set_overdue_sigma(mode.overdue_sigma());
+            logging::info(logging::DataSource::System, None,
+                &format!("🌊 Event mode: {} since {} ({})", mode.as_str(), since.to_rfc3339(), reason));
+        }
+        
         // Load CWMS locations from TOML
-        let mut locations = usace_locations::load_locations()?;
+        let mut locations = match &preloaded {
+            Some(registries) => registries.cwms_locations.clone(),
+            None => usace_locations::load_locations()?,
+        };
         
         if locations.is_empty() {
-            eprintln!("Warning: No USACE/CWMS locations configured in usace_stations.toml");
+            logging::warn(logging::DataSource::Cwms, None, "No USACE/CWMS locations configured in usace_stations.toml");
         } else {
             // Discover actual CWMS timeseries IDs from catalog endpoint
-            println!("🔍 Discovering CWMS timeseries IDs from catalog...");
+            logging::info(logging::DataSource::Cwms, None, "🔍 Discovering CWMS timeseries IDs from catalog...");
             let http_client = reqwest::blocking::Client::builder()
                 .timeout(std::time::Duration::from_secs(15))
                 .build()?;
             
             for location in &mut locations {
-                print!("   {} ... ", location.name);
-                match usace_locations::update_with_discovered_timeseries(location, &http_client) {
-                    Ok(_) => println!("✓"),
-                    Err(e) => {
-                        println!("✗ {}", e);
-                        eprintln!("      Warning: Will skip polling for {}", location.name);
-                    }
+                if let Err(e) = usace_locations::update_with_discovered_timeseries(location, &http_client) {
+                    logging::warn(logging::DataSource::Cwms, Some(&location.name),
+                        &format!("Timeseries discovery failed, will skip polling: {}", e));
                 }
             }
             
@@ -123,17 +579,22 @@ impl Daemon {
                 .filter(|loc| loc.discovered_timeseries.is_some())
                 .count();
             
-            println!("   Discovered timeseries for {}/{} locations\n", 
-                    discovered_count, locations.len());
+            logging::info(logging::DataSource::Cwms, None,
+                &format!("Discovered timeseries for {}/{} locations", discovered_count, locations.len()));
         }
         
         self.cwms_locations = locations;
         
         // Load ASOS locations from TOML
         let asos_path = std::path::Path::new("iem_asos.toml");
-        if asos_path.exists() {
-            let asos_locs = asos_locations::load_locations(asos_path)?;
-            println!("📡 Loaded {} ASOS stations for precipitation monitoring", asos_locs.len());
+        let asos_locs = match preloaded {
+            Some(registries) => Some(registries.asos_locations),
+            None if asos_path.exists() => Some(asos_locations::load_locations(asos_path)?),
+            None => None,
+        };
+        if let Some(asos_locs) = asos_locs {
+            logging::info(logging::DataSource::Asos, None,
+                &format!("📡 Loaded {} ASOS stations for precipitation monitoring", asos_locs.len()));
             
             // Register ASOS stations in database
             for loc in &asos_locs {
@@ -145,8 +606,17 @@ impl Daemon {
                     &loc.station_id[..]  // Use as-is
                 };
                 
-                println!("   {} ({}) - {} basin - Priority: {:?}",
-                    loc.station_id, loc.name, loc.basin, loc.priority);
+                logging::debug(logging::DataSource::Asos, Some(&loc.station_id),
+                    &format!("{} - {} basin - Priority: {:?}", loc.name, loc.basin, loc.priority));
+                
+                if self.dry_run {
+                    let exists: bool = client.query_one(
+                        "SELECT EXISTS(SELECT 1 FROM asos_stations WHERE station_id = $1)",
+                        &[&db_station_id]
+                    )?.get(0);
+                    report_dry_run("asos_stations", OnConflict::DoUpdate, exists, db_station_id);
+                    continue;
+                }
                 
                 // Insert or update station metadata
                 match client.execute(
@@ -178,20 +648,113 @@ impl Daemon {
                     ]
                 ) {
                     Ok(_) => {},
-                    Err(e) => eprintln!("      Warning: Failed to register ASOS station {}: {}", db_station_id, e),
+                    Err(e) => logging::warn(logging::DataSource::Database, Some(db_station_id),
+                        &format!("Failed to register ASOS station: {}", e)),
                 }
             }
             
             self.asos_locations = asos_locs;
         } else {
-            eprintln!("Warning: iem_asos.toml not found, skipping ASOS monitoring");
+            logging::warn(logging::DataSource::Asos, None, "iem_asos.toml not found, skipping ASOS monitoring");
+        }
+        
+        // Who hears about escalations, by zone
+        if self.zones.is_none() {
+            self.zones = zones::load_zones_default().ok();
+        }
+        let stored = subscriptions::load_stored(&mut client).unwrap_or_else(|e| {
+            logging::warn(logging::DataSource::System, None, &e);
+            Vec::new()
+        });
+        self.subscriptions = subscriptions::merge(&self.subscription_config, stored);
+        if !self.subscriptions.is_empty() {
+            logging::info(logging::DataSource::System, None, &format!("📨 {} zone subscriptions", self.subscriptions.len()));
         }
         
         self.client = Some(client);
+        self.restrict_to_source();
+        
+        // Leader picks up notifications a previous run could not deliver
+        if !self.dry_run {
+            self.refresh_dispatch_role();
+            if !self.dispatch.is_leader() {
+                logging::info(logging::DataSource::System, None, "⏸  Standby: another instance holds the notification dispatch lock");
+            }
+        }
         
         Ok(())
     }
     
+    /// Station registry version in effect, once initialized.
+    /// Swap in the station registry if its files changed and the new one
+    /// validates; otherwise keep the running one and log why
+    fn reload_registry(&mut self) {
+        let Some(reloaded) = self.registry_watcher.as_mut().and_then(RegistryWatcher::poll) else {
+            return;
+        };
+        let stations = match reloaded {
+            Ok(stations) => stations,
+            Err(e) => {
+                logging::warn(logging::DataSource::Usgs, None,
+                    &format!("Station registry changed but was not reloaded: {}", e));
+                return;
+            }
+        };
+        let Some(client) = self.client.as_mut() else {
+            return;
+        };
+        let effective = apply_threshold_overrides(&stations, &self.threshold_overrides);
+        match record_registry(client, &effective, &registry::changed_by(), self.dry_run) {
+            Ok(version) => {
+                logging::info(logging::DataSource::Usgs, None,
+                    &format!("🔄 Station registry reloaded: {} stations (was {})", stations.len(), self.registry_stations.len()));
+                self.registry_version = version;
+                self.registry_stations = stations;
+                self.stations = effective;
+                self.restrict_to_source();
+            }
+            Err(e) => logging::warn(logging::DataSource::Usgs, None,
+                &format!("Station registry changed but its version could not be recorded: {}", e)),
+        }
+    }
+    
+    /// Re-apply `flood_analysis.thresholds` when its rows changed,
+    /// recording the new effective thresholds as a registry version
+    fn refresh_threshold_overrides(&mut self) {
+        let Some(client) = self.client.as_mut() else {
+            return;
+        };
+        let overrides = match threshold_overrides::load(client) {
+            Ok(overrides) => overrides,
+            Err(e) => {
+                logging::warn(logging::DataSource::Database, None, &e);
+                return;
+            }
+        };
+        if overrides == self.threshold_overrides {
+            return;
+        }
+        let effective = apply_threshold_overrides(&self.registry_stations, &overrides);
+        let changed_by = threshold_overrides::changed_by(&self.threshold_overrides, &overrides);
+        match record_registry(client, &effective, &changed_by, self.dry_run) {
+            Ok(version) => {
+                logging::info(logging::DataSource::Database, None,
+                    &format!("🔄 Threshold overrides changed: {} in effect ({})", overrides.len(), changed_by));
+                self.registry_version = version;
+                self.threshold_overrides = overrides;
+                self.stations = effective;
+                self.restrict_to_source();
+            }
+            Err(e) => logging::warn(logging::DataSource::Usgs, None,
+                &format!("Threshold overrides changed but the registry version could not be recorded: {}", e)),
+        }
+    }
+    
+    /// Stamp alerts with this (`FloodAlert::with_registry_version`).
+    pub fn registry_version(&self) -> Option<i32> {
+        self.registry_version
+    }
+    
     /// Get reference to loaded stations
     pub fn get_stations(&self) -> &[Station] {
         &self.stations
@@ -275,17 +838,18 @@ impl Daemon {
         match latest_data {
             None => {
                 // No data at all - get high-resolution recent data + optional deep history
-                println!("   Empty database for {} - fetching high-resolution data", site_code);
+                logging::info(logging::DataSource::Usgs, Some(site_code), "Empty database - fetching high-resolution data");
                 
                 // Always get the last 120 days as instantaneous values (high resolution)
-                match self.backfill_instantaneous_values(site_code, 120) {
+                match self.backfill_instantaneous_values(site_code, "P120D") {
                     Ok(count) => {
                         total_inserted += count;
-                        println!("   Fetched {} instantaneous readings (last 120 days)", count);
+                        logging::info(logging::DataSource::Usgs, Some(site_code),
+                            &format!("Fetched {} instantaneous readings (last 120 days)", count));
                     }
                     Err(e) => {
                         logging::log_usgs_failure(site_code, "IV backfill", &*e);
-                        eprintln!("   Falling back to daily values for {}", site_code);
+                        logging::warn(logging::DataSource::Usgs, Some(site_code), "Falling back to daily values");
                         total_inserted += self.backfill_daily_values(
                             site_code, 
                             now - Duration::days(120), 
@@ -297,7 +861,8 @@ impl Daemon {
                 // Optionally get older data as daily values if backfill_days > 120
                 if self.config.backfill_days > 120 {
                     let deep_history_days = self.config.backfill_days - 120;
-                    println!("   Fetching {} additional days of daily values for historical context", deep_history_days);
+                    logging::info(logging::DataSource::Usgs, Some(site_code),
+                        &format!("Fetching {} additional days of daily values for historical context", deep_history_days));
                     
                     total_inserted += self.backfill_daily_values(
                         site_code,
@@ -310,18 +875,22 @@ impl Daemon {
                 // We have some data - intelligently fill the gap
                 let gap_days = staleness.num_days();
                 
-                if gap_days <= 120 {
-                    // Gap is within IV API range - get high-resolution data
-                    println!("   Filling {}-day gap with instantaneous values (high-res)", gap_days);
+                if staleness <= Duration::days(120) {
+                    // Gap is within IV API range - get high-resolution data,
+                    // exactly the hole rather than whole days of it
+                    let period = catchup::iv_period(staleness);
+                    logging::info(logging::DataSource::Usgs, Some(site_code),
+                        &format!("Filling {} h gap with instantaneous values (high-res, period {})", staleness.num_hours(), period));
                     
-                    match self.backfill_instantaneous_values(site_code, gap_days as u64) {
+                    match self.backfill_instantaneous_values(site_code, &period) {
                         Ok(count) => {
                             total_inserted += count;
-                            println!("   Fetched {} instantaneous readings", count);
+                            logging::info(logging::DataSource::Usgs, Some(site_code),
+                                &format!("Fetched {} instantaneous readings", count));
                         }
                         Err(e) => {
                             logging::log_usgs_failure(site_code, "IV backfill (gap fill)", &*e);
-                            eprintln!("   Falling back to daily values for {}", site_code);
+                            logging::warn(logging::DataSource::Usgs, Some(site_code), "Falling back to daily values");
                             total_inserted += self.backfill_daily_values(
                                 site_code, 
                                 now - staleness, 
@@ -331,7 +900,8 @@ impl Daemon {
                     }
                 } else {
                     // Gap is too large for IV API - use hybrid strategy
-                    println!("   Large gap ({} days) - using hybrid backfill", gap_days);
+                    logging::info(logging::DataSource::Usgs, Some(site_code),
+                        &format!("Large gap ({} days) - using hybrid backfill", gap_days));
                     
                     // Get old data (beyond 120 days) as daily values
                     let old_data_start = now - staleness;
@@ -340,18 +910,20 @@ impl Daemon {
                     if old_data_end > old_data_start {
                         let dv_count = self.backfill_daily_values(site_code, old_data_start, old_data_end)?;
                         total_inserted += dv_count;
-                        println!("   Fetched {} daily values for days {}-120", dv_count, gap_days);
+                        logging::info(logging::DataSource::Usgs, Some(site_code),
+                            &format!("Fetched {} daily values for days {}-120", dv_count, gap_days));
                     }
                     
                     // Get recent 120 days as instantaneous values (high resolution)
-                    match self.backfill_instantaneous_values(site_code, 120) {
+                    match self.backfill_instantaneous_values(site_code, "P120D") {
                         Ok(count) => {
                             total_inserted += count;
-                            println!("   Fetched {} instantaneous readings (last 120 days)", count);
+                            logging::info(logging::DataSource::Usgs, Some(site_code),
+                                &format!("Fetched {} instantaneous readings (last 120 days)", count));
                         }
                         Err(e) => {
                             logging::log_usgs_failure(site_code, "Recent IV backfill", &*e);
-                            eprintln!("   Falling back to daily values for {}", site_code);
+                            logging::warn(logging::DataSource::Usgs, Some(site_code), "Falling back to daily values");
                             total_inserted += self.backfill_daily_values(
                                 site_code, 
                                 now - Duration::days(120), 
@@ -366,36 +938,51 @@ impl Daemon {
         Ok(total_inserted)
     }
     
+    /// Backfill `site_code` over `[start, end]` (`flomon backfill`) in
+    /// yearly daily value and monthly instantaneous value chunks, skipping
+    /// chunks checkpointed by an earlier run (see `backfill`). A dry run
+    /// fetches every chunk but records no checkpoints.
+    pub fn backfill_range(&mut self, site_code: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<BackfillReport, Box<dyn Error>> {
+        if start >= end {
+            return Err(format!("Backfill start {} is not before end {}", start, end).into());
+        }
+        let chunks = backfill::plan(site_code, start.date_naive(), end.date_naive(), Utc::now().date_naive());
+        let client = self.client.as_mut()
+            .ok_or("Daemon not initialized")?;
+        let checkpoints = backfill::load_checkpoints(client, site_code)?;
+        
+        Ok(backfill::run(&chunks, &checkpoints, |chunk| {
+            let readings = fetch_backfill_chunk(chunk).map_err(|e| e.to_string())?;
+            let written = self.warehouse_readings(&readings).map_err(|e| e.to_string())?;
+            if !self.dry_run {
+                let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+                backfill::store_checkpoint(client, chunk, written)?;
+            }
+            Ok(written)
+        }))
+    }
+    
     /// Backfill using Daily Values API (coarse resolution, longer history)
     fn backfill_daily_values(&mut self, site_code: &str, start_date: DateTime<Utc>, end_date: DateTime<Utc>) -> Result<usize, Box<dyn Error>> {
         let start_date_str = start_date.format("%Y-%m-%d").to_string();
         let end_date_str = end_date.format("%Y-%m-%d").to_string();
         
-        let url = usgs::build_dv_url(
-            &[site_code],
-            &["00060", "00065"], // Discharge and stage
-            &start_date_str,
-            &end_date_str,
-        );
-        
-        println!("   Fetching daily values from {} to {}", start_date_str, end_date_str);
-        
-        let client = reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()?;
-        
-        let response = client.get(&url).send()?;
-        
-        if !response.status().is_success() {
-            return Err(format!("USGS API returned status {}", response.status()).into());
-        }
+        let params = ["00060", "00065"]; // Discharge and stage
+        let json_url = usgs::build_dv_url(&[site_code], &params, &start_date_str, &end_date_str);
+        let rdb_url = usgs::build_dv_rdb_url(&[site_code], &params, &start_date_str, &end_date_str);
         
-        let body = response.text()?;
+        logging::info(logging::DataSource::Usgs, Some(site_code),
+            &format!("Fetching daily values from {} to {}", start_date_str, end_date_str));
         
-        let readings = match usgs::parse_dv_response(&body) {
+        let readings = match fetch_usgs_with_fallback(
+            site_code,
+            (&json_url, usgs::parse_dv_response),
+            (&rdb_url, usgs_rdb::parse_dv_rdb),
+            30,
+        ) {
             Ok(r) => r,
             Err(e) => {
-                logging::log_usgs_failure(site_code, "DV API parsing", &e);
+                logging::log_usgs_failure(site_code, "DV API fetch", &*e);
                 return Ok(0);
             }
         };
@@ -404,32 +991,25 @@ impl Daemon {
     }
     
     /// Backfill using Instantaneous Values API (high resolution, limited history)
-    fn backfill_instantaneous_values(&mut self, site_code: &str, days: u64) -> Result<usize, Box<dyn Error>> {
-        // Convert days to ISO 8601 period format (e.g. P30D for 30 days)
-        let period = format!("P{}D", days);
-        
-        let url = usgs::build_iv_url(
-            &[site_code],
-            &["00060", "00065"], // Discharge and stage
-            &period,
-        );
-        
-        let client = reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()?;
-        
-        let response = client.get(&url).send()?;
-        
-        if !response.status().is_success() {
-            return Err(format!("USGS API returned status {}", response.status()).into());
-        }
-        
-        let body = response.text()?;
+    fn backfill_instantaneous_values(&mut self, site_code: &str, period: &str) -> Result<usize, Box<dyn Error>> {
+        let readings = self.fetch_instantaneous_values(site_code, period)?;
+        self.warehouse_readings(&readings)
+    }
+    
+    /// Every instantaneous value within ISO 8601 `period` back from now
+    /// (e.g. "PT5H", "P30D")
+    fn fetch_instantaneous_values(&self, site_code: &str, period: &str) -> Result<Vec<GaugeReading>, Box<dyn Error>> {
+        let params = ["00060", "00065"]; // Discharge and stage
         
         // Use parse_iv_response_all to get ALL readings in the time period
-        let readings = usgs::parse_iv_response_all(&body)?;
+        let readings = fetch_usgs_with_fallback(
+            site_code,
+            (&usgs::build_iv_url(&[site_code], &params, period), usgs::parse_iv_response_all),
+            (&usgs::build_iv_rdb_url(&[site_code], &params, period), usgs_rdb::parse_iv_rdb_all),
+            30,
+        )?;
         
-        self.warehouse_readings(&readings)
+        Ok(readings)
     }
     
     // ---------------------------------------------------------------------------
@@ -448,45 +1028,14 @@ impl Daemon {
             .timeout(std::time::Duration::from_secs(15))
             .build()?;
         
-        let mut total_inserted = 0;
-        
-        // Fetch pool elevation if available
-        if let Some(ref ts_id) = discovered.pool_elevation {
-            match cwms::fetch_recent(&http_client, ts_id, &location.office, 4) {
-                Ok(timeseries) => {
-                    total_inserted += self.warehouse_cwms_timeseries(&timeseries)?;
-                }
-                Err(e) => {
-                    eprintln!("   Failed to fetch pool elevation for {}: {}", location.name, e);
-                }
-            }
-        }
-        
-        // Fetch tailwater elevation if available
-        if let Some(ref ts_id) = discovered.tailwater_elevation {
-            match cwms::fetch_recent(&http_client, ts_id, &location.office, 4) {
-                Ok(timeseries) => {
-                    total_inserted += self.warehouse_cwms_timeseries(&timeseries)?;
-                }
-                Err(e) => {
-                    eprintln!("   Failed to fetch tailwater elevation for {}: {}", location.name, e);
-                }
-            }
-        }
-        
-        // Fetch stage if available (for river gauges)
-        if let Some(ref ts_id) = discovered.stage {
-            match cwms::fetch_recent(&http_client, ts_id, &location.office, 4) {
-                Ok(timeseries) => {
-                    total_inserted += self.warehouse_cwms_timeseries(&timeseries)?;
-                }
-                Err(e) => {
-                    eprintln!("   Failed to fetch stage for {}: {}", location.name, e);
-                }
-            }
+        let end = Utc::now();
+        let fetched = cwms::fetch_location(&http_client, &discovered.series(), &location.office, end - Duration::hours(4), end);
+        for (parameter, e) in &fetched.failures {
+            logging::warn(logging::DataSource::Cwms, Some(&location.name),
+                &format!("Failed to fetch {}: {}", parameter.label(), e));
         }
         
-        Ok(total_inserted)
+        self.warehouse_cwms_timeseries(&fetched.records)
     }
     
     /// Backfill CWMS location with historical data
@@ -500,16 +1049,7 @@ impl Daemon {
         let now = Utc::now();
         
         // Collect all timeseries IDs we need to backfill
-        let mut timeseries_to_backfill = Vec::new();
-        if let Some(ref ts_id) = discovered.pool_elevation {
-            timeseries_to_backfill.push((ts_id.clone(), "pool"));
-        }
-        if let Some(ref ts_id) = discovered.tailwater_elevation {
-            timeseries_to_backfill.push((ts_id.clone(), "tailwater"));
-        }
-        if let Some(ref ts_id) = discovered.stage {
-            timeseries_to_backfill.push((ts_id.clone(), "stage"));
-        }
+        let timeseries_to_backfill = discovered.series();
         
         if timeseries_to_backfill.is_empty() {
             return Ok(0);
@@ -517,14 +1057,16 @@ impl Daemon {
         
         let mut total_inserted = 0;
         
-        for (ts_id, param_type) in timeseries_to_backfill {
+        for (parameter, ts_id) in timeseries_to_backfill {
+            let param_type = parameter.label();
             // Check staleness for this specific timeseries
             let latest_data = self.check_cwms_staleness(&location.cwms_location)?;
             
             match latest_data {
                 None => {
                     // No data at all - get last 120 days
-                    println!("   Empty database for {} ({}) - fetching CWMS data", location.name, param_type);
+                    logging::info(logging::DataSource::Cwms, Some(&location.name),
+                        &format!("Empty database ({}) - fetching CWMS data", param_type));
                     
                     let http_client = reqwest::blocking::Client::builder()
                         .timeout(std::time::Duration::from_secs(30))
@@ -534,13 +1076,15 @@ impl Daemon {
                     let end = now.naive_utc();
                     
                     match cwms::fetch_historical(&http_client, &ts_id, &location.office, start, end) {
-                        Ok(timeseries) => {
+                        Ok(mut timeseries) => {
+                            parameter.normalize(&mut timeseries);
                             let inserted = self.warehouse_cwms_timeseries(&timeseries)?;
                             total_inserted += inserted;
-                            println!("      Fetched {} {} readings", inserted, param_type);
+                            logging::info(logging::DataSource::Cwms, Some(&location.name),
+                                &format!("Fetched {} {} readings", inserted, param_type));
                         }
                         Err(e) => {
-                            eprintln!("      Failed to fetch {}: {}", param_type, e);
+                            logging::log_cwms_failure(&location.cwms_location, &format!("{} backfill", param_type), &e);
                         }
                     }
                 }
@@ -549,7 +1093,8 @@ impl Daemon {
                     let gap_days = staleness.num_days();
                     
                     if gap_days > 1 {
-                        println!("   Filling {}-day CWMS gap for {} ({})", gap_days, location.name, param_type);
+                        logging::info(logging::DataSource::Cwms, Some(&location.name),
+                            &format!("Filling {}-day CWMS gap ({})", gap_days, param_type));
                         
                         let http_client = reqwest::blocking::Client::builder()
                             .timeout(std::time::Duration::from_secs(30))
@@ -559,13 +1104,15 @@ impl Daemon {
                         let end = now.naive_utc();
                         
                         match cwms::fetch_historical(&http_client, &ts_id, &location.office, start, end) {
-                            Ok(timeseries) => {
+                            Ok(mut timeseries) => {
+                                parameter.normalize(&mut timeseries);
                                 let inserted = self.warehouse_cwms_timeseries(&timeseries)?;
                                 total_inserted += inserted;
-                                println!("      Fetched {} {} readings", inserted, param_type);
+                                logging::info(logging::DataSource::Cwms, Some(&location.name),
+                                    &format!("Fetched {} {} readings", inserted, param_type));
                             }
                             Err(e) => {
-                                eprintln!("      Failed to fetch {}: {}", param_type, e);
+                                logging::log_cwms_failure(&location.cwms_location, &format!("{} gap fill", param_type), &e);
                             }
                         }
                     }
@@ -583,36 +1130,151 @@ impl Daemon {
         
         let mut inserted = 0;
         
-        for record in timeseries {
-            // Convert value to Decimal for PostgreSQL NUMERIC type
-            let value_decimal = rust_decimal::Decimal::from_f64_retain(record.value)
-                .ok_or_else(|| format!("Failed to convert value {} to decimal", record.value))?;
+        if !self.dry_run && timeseries.len() >= bulk::MIN_BULK_ROWS {
+            inserted = bulk::insert_cwms_timeseries(client, timeseries)?;
+        } else {
+            for record in timeseries {
+                // Convert value to Decimal for PostgreSQL NUMERIC type
+                let value_decimal = rust_decimal::Decimal::from_f64_retain(record.value)
+                    .ok_or_else(|| format!("Failed to convert value {} to decimal", record.value))?;
             
-            // Use INSERT ... ON CONFLICT DO NOTHING for idempotency
+                if self.dry_run {
+                    let exists: bool = client.query_one(
+                        "SELECT EXISTS(SELECT 1 FROM usace.cwms_timeseries
+                         WHERE location_id = $1 AND timestamp = $2 AND parameter_id = $3)",
+                        &[&record.location_id, &record.timestamp, &record.parameter_id]
+                    )?.get(0);
+                    inserted += report_dry_run(
+                        "usace.cwms_timeseries",
+                        OnConflict::DoNothing,
+                        exists,
+                        &format!("{} {} {} = {} {}", record.location_id, record.parameter_id,
+                            record.timestamp.to_rfc3339(), record.value, record.unit),
+                    );
+                    continue;
+                }
+            
+                // Use INSERT ... ON CONFLICT DO NOTHING for idempotency
+                let rows_affected = client.execute(
+                    "INSERT INTO usace.cwms_timeseries 
+                     (location_id, timeseries_id, parameter_id, parameter_type, interval, duration, version,
+                      timestamp, value, unit, quality_code, original_unit)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                     ON CONFLICT (location_id, timestamp, parameter_id) DO NOTHING",
+                    &[
+                        &record.location_id,
+                        &record.timeseries_id,
+                        &record.parameter_id,
+                        &"Inst",  // parameter_type - instantaneous
+                        &"15Minutes",  // interval
+                        &"0",  // duration
+                        &"Ccp-Rev",  // version
+                        &record.timestamp,
+                        &value_decimal,
+                        &record.unit,
+                        &record.quality_code,
+                        &record.original_unit,
+                    ]
+                )?;
+            
+                inserted += rows_affected as usize;
+            }
+        }
+        
+        // Stored as reported, but kept out of freeboard and alerting
+        let unusable = timeseries.iter().filter(|r| !r.quality().is_usable()).count();
+        if unusable > 0 {
+            logging::info(logging::DataSource::Cwms, timeseries.first().map(|r| r.location_id.as_str()),
+                &format!("{} of {} CWMS values flagged rejected/missing; excluded from analysis", unusable, timeseries.len()));
+        }
+        
+        self.warehouse_freeboard(timeseries)?;
+        
+        Ok(inserted)
+    }
+    
+    /// Derive and warehouse property freeboard from pool elevation samples.
+    ///
+    /// No-op unless `[freeboard]` is configured and `timeseries` is the
+    /// configured source location's elevation. Warns when current freeboard
+    /// at any point is below the alert threshold.
+    fn warehouse_freeboard(&mut self, timeseries: &[cwms::CwmsTimeseries]) -> Result<usize, Box<dyn Error>> {
+        let settings = match &self.freeboard {
+            Some(s) => s.clone(),
+            None => return Ok(0),
+        };
+        
+        // Critical elevations are NGVD29; a pool on another datum is converted
+        let source = self.cwms_locations.iter().find(|l| l.cwms_location == settings.source_location);
+        let water_surface: Vec<cwms::CwmsTimeseries> = timeseries.iter()
+            .filter(|r| r.location_id == settings.source_location && r.parameter_id == "Elev")
+            .filter(|r| r.quality().is_usable())
+            .map(|r| cwms::CwmsTimeseries {
+                value: source.and_then(|l| l.elevation_ngvd29(r.value)).unwrap_or(r.value),
+                ..r.clone()
+            })
+            .collect();
+        if water_surface.is_empty() {
+            return Ok(0);
+        }
+        
+        let readings = freeboard::compute_freeboard(&water_surface, &settings.critical_elevations);
+        
+        let client = self.client.as_mut()
+            .ok_or("Daemon not initialized")?;
+        
+        let to_decimal = |v: f64| rust_decimal::Decimal::from_f64_retain(v)
+            .map(|d| d.round_dp(2))
+            .ok_or_else(|| format!("Failed to convert value {} to decimal", v));
+        
+        let mut inserted = 0;
+        for reading in &readings {
+            if self.dry_run {
+                let exists: bool = client.query_one(
+                    "SELECT EXISTS(SELECT 1 FROM flood_analysis.freeboard
+                     WHERE point_name = $1 AND timestamp = $2)",
+                    &[&reading.point_name, &reading.timestamp]
+                )?.get(0);
+                inserted += report_dry_run(
+                    "flood_analysis.freeboard",
+                    OnConflict::DoNothing,
+                    exists,
+                    &format!("{} {} freeboard {:.2} ft", reading.point_name,
+                        reading.timestamp.to_rfc3339(), reading.freeboard_ft),
+                );
+                continue;
+            }
             let rows_affected = client.execute(
-                "INSERT INTO usace.cwms_timeseries 
-                 (location_id, timeseries_id, parameter_id, parameter_type, interval, duration, version,
-                  timestamp, value, unit, quality_code)
-                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-                 ON CONFLICT (location_id, timestamp, parameter_id) DO NOTHING",
+                "INSERT INTO flood_analysis.freeboard
+                 (point_name, timestamp, water_surface_ft, critical_elevation_ft, freeboard_ft, source_timeseries_id)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (point_name, timestamp) DO NOTHING",
                 &[
-                    &record.location_id,
-                    &record.timeseries_id,
-                    &record.parameter_id,
-                    &"Inst",  // parameter_type - instantaneous
-                    &"15Minutes",  // interval
-                    &"0",  // duration
-                    &"Ccp-Rev",  // version
-                    &record.timestamp,
-                    &value_decimal,
-                    &record.unit,
-                    &record.quality_code,
+                    &reading.point_name,
+                    &reading.timestamp,
+                    &to_decimal(reading.water_surface_ft)?,
+                    &to_decimal(reading.critical_elevation_ft)?,
+                    &to_decimal(reading.freeboard_ft)?,
+                    &reading.source_timeseries_id,
                 ]
             )?;
-            
             inserted += rows_affected as usize;
         }
         
+        // Only alert on current conditions, not on backfilled history
+        let cutoff = Utc::now() - Duration::minutes(self.config.staleness_threshold_minutes as i64);
+        let current: Vec<_> = readings.into_iter().filter(|r| r.timestamp >= cutoff).collect();
+        for reading in freeboard::readings_below(&current, settings.alert_below_ft) {
+            logging::warn(
+                logging::DataSource::Cwms,
+                Some(&settings.source_location),
+                &format!(
+                    "Freeboard at {} is {:.2} ft (alert below {:.1} ft; water surface {:.2} ft)",
+                    reading.point_name, reading.freeboard_ft, settings.alert_below_ft, reading.water_surface_ft
+                ),
+            );
+        }
+        
         Ok(inserted)
     }
     
@@ -625,18 +1287,39 @@ impl Daemon {
         let client = self.client.as_mut()
             .ok_or("Daemon not initialized")?;
         
+        if !self.dry_run && observations.len() >= bulk::MIN_BULK_ROWS {
+            return Ok(bulk::insert_asos_observations(client, observations)?);
+        }
+        
         let mut inserted = 0;
         
         for obs in observations {
             // Determine data source
             let data_source = "IEM_ASOS";
             
+            if self.dry_run {
+                let exists: bool = client.query_one(
+                    "SELECT EXISTS(SELECT 1 FROM asos_observations
+                     WHERE station_id = $1 AND observation_time = $2)",
+                    &[&obs.station_id, &obs.timestamp]
+                )?.get(0);
+                inserted += report_dry_run(
+                    "asos_observations",
+                    OnConflict::DoNothing,
+                    exists,
+                    &format!("{} {} temp={:?} precip_1hr={:?}", obs.station_id,
+                        obs.timestamp.to_rfc3339(), obs.temp_f, obs.precip_1hr_in),
+                );
+                continue;
+            }
+            
             let rows_affected = client.execute(
                 "INSERT INTO asos_observations 
                  (station_id, observation_time, temp_f, dewpoint_f, relative_humidity,
                   wind_direction_deg, wind_speed_knots, wind_gust_knots, precip_1hr_in,
-                  pressure_mb, visibility_mi, sky_condition, weather_codes, data_source)
-                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+                  pressure_mb, visibility_mi, sky_condition, weather_codes, data_source,
+                  relative_humidity_reported, rh_qc_flag)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
                  ON CONFLICT (station_id, observation_time) DO NOTHING",
                 &[
                     &obs.station_id,
@@ -653,6 +1336,8 @@ impl Daemon {
                     &obs.sky_condition,
                     &obs.weather_codes,
                     &data_source,
+                    &obs.relative_humidity_reported,
+                    &obs.rh_qc_flag.map(|f| f.as_str()),
                 ]
             )?;
             
@@ -662,119 +1347,746 @@ impl Daemon {
         Ok(inserted)
     }
     
-    /// Poll ASOS station for recent observations
-    fn poll_asos_station(&mut self, station_id: &str) -> Result<Vec<iem::AsosObservation>, Box<dyn Error>> {
-        let http_client = reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(15))
-            .build()?;
-        
-        // Fetch last 4 hours for recent poll
-        let observations = iem::fetch_recent_precip(&http_client, station_id, 4)?;
-        
-        Ok(observations)
-    }
-    
-    /// Backfill ASOS historical data for a station
-    fn backfill_asos_station(&mut self, station_id: &str, days: i64) -> Result<usize, Box<dyn Error>> {
-        let http_client = reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()?;
-        
-        let hours = days * 24;
-        let observations = iem::fetch_recent_precip(&http_client, station_id, hours)?;
-        
-        self.warehouse_asos_observations(&observations)
-    }
-    
-    // ---------------------------------------------------------------------------
-    // USGS Data Warehousing
-    // ---------------------------------------------------------------------------
-    
-    /// Poll a single station for latest data
-    pub fn poll_station(&mut self, site_code: &str) -> Result<Vec<GaugeReading>, Box<dyn Error>> {
-        // Build URL for instantaneous values (last 4 hours to ensure we get recent data)
-        // USGS updates IV data every 15-60 minutes depending on the station
-        let url = usgs::build_iv_url(
-            &[site_code],
-            &["00060", "00065"], // Discharge and stage
-            "PT4H", // Last 4 hours
-        );
-        
-        // Fetch data from USGS API
-        let client = reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(15))
-            .build()?;
-        
-        let response = client.get(&url).send()?;
-        
-        if !response.status().is_success() {
-            return Err(format!("USGS API returned status {}", response.status()).into());
+    /// Recompute and store precipitation rollups ending at `now`.
+    ///
+    /// Reads the last 72 hours of ASOS observations once, then writes one
+    /// row per station and basin for each window in `PRECIP_WINDOWS_HOURS`.
+    fn warehouse_precip_rollups(&mut self, now: DateTime<Utc>) -> Result<usize, Box<dyn Error>> {
+        if self.asos_locations.is_empty() {
+            return Ok(0);
         }
         
-        let body = response.text()?;
-        
-        // Parse response - note this returns the most recent value per parameter
-        let readings = usgs::parse_iv_response(&body)?;
-        
-        Ok(readings)
-    }
-    
-    /// Warehouse readings into database (idempotent)
-    pub fn warehouse_readings(&mut self, readings: &[GaugeReading]) -> Result<usize, Box<dyn Error>> {
         let client = self.client.as_mut()
             .ok_or("Daemon not initialized")?;
         
-        let mut inserted = 0;
+        let longest = *precip::PRECIP_WINDOWS_HOURS.iter().max().unwrap();
+        let rows = client.query(
+            "SELECT station_id, observation_time, precip_1hr_in
+             FROM asos_observations
+             WHERE observation_time > $1 AND observation_time <= $2
+               AND precip_1hr_in IS NOT NULL",
+            &[&(now - Duration::hours(longest)), &now]
+        )?;
         
-        for reading in readings {
-            // Parse datetime string to DateTime<Utc>
-            // Try RFC3339 first (for instantaneous values with timezone)
-            let reading_time = if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(&reading.datetime) {
-                dt.with_timezone(&Utc)
-            } else {
-                // Fall back to NaiveDateTime for daily values (no timezone)
-                // Assume local time is UTC for daily values
-                let naive = chrono::NaiveDateTime::parse_from_str(&reading.datetime, "%Y-%m-%dT%H:%M:%S%.3f")
-                    .or_else(|_| chrono::NaiveDateTime::parse_from_str(&reading.datetime, "%Y-%m-%d"))
-                    .map_err(|e| format!("Failed to parse datetime '{}': {}", reading.datetime, e))?;
-                chrono::DateTime::<Utc>::from_naive_utc_and_offset(naive, Utc)
-            };
-            
-            // Convert value to Decimal for PostgreSQL NUMERIC type
-            let value_decimal = rust_decimal::Decimal::from_f64_retain(reading.value)
-                .ok_or_else(|| format!("Failed to convert value {} to decimal", reading.value))?;
-            
-            // Use INSERT ... ON CONFLICT DO NOTHING for idempotency
-            let rows_affected = client.execute(
-                "INSERT INTO usgs_raw.gauge_readings 
-                 (site_code, parameter_code, unit, value, reading_time, qualifier)
-                 VALUES ($1, $2, $3, $4, $5, $6)
-                 ON CONFLICT (site_code, parameter_code, reading_time) DO NOTHING",
+        let observations: Vec<iem::AsosObservation> = rows.iter()
+            .map(|row| iem::AsosObservation {
+                station_id: row.get(0),
+                timestamp: row.get(1),
+                temp_f: None,
+                dewpoint_f: None,
+                relative_humidity: None,
+                wind_direction_deg: None,
+                wind_speed_knots: None,
+                wind_gust_knots: None,
+                precip_1hr_in: row.get(2),
+                pressure_mb: None,
+                visibility_mi: None,
+                sky_condition: None,
+                weather_codes: None,
+                relative_humidity_reported: None,
+                rh_qc_flag: None,
+            })
+            .collect();
+        
+        let station_rollups: Vec<precip::PrecipRollup> = self.asos_locations.iter()
+            .flat_map(|loc| precip::station_rollups(&loc.station_id, &observations, now))
+            .collect();
+        let basin_rollups = precip::basin_rollups(&station_rollups, &self.asos_locations);
+        self.basin_precip = basin_rollups.iter()
+            .map(|r| ((r.scope_id.clone(), r.window_hours), r.total_in))
+            .collect();
+        
+        let mut written = 0;
+        for rollup in station_rollups.iter().chain(&basin_rollups) {
+            let window_hours = rollup.window_hours as i32;
+            if self.dry_run {
+                let exists: bool = client.query_one(
+                    "SELECT EXISTS(SELECT 1 FROM asos_precip_rollups
+                     WHERE scope = $1 AND scope_id = $2 AND window_hours = $3 AND window_end = $4)",
+                    &[&rollup.scope.as_str(), &rollup.scope_id, &window_hours, &rollup.window_end]
+                )?.get(0);
+                written += report_dry_run(
+                    "asos_precip_rollups",
+                    OnConflict::DoUpdate,
+                    exists,
+                    &format!("{} {} {}h = {:.2} in", rollup.scope.as_str(), rollup.scope_id,
+                        rollup.window_hours, rollup.total_in),
+                );
+                continue;
+            }
+            written += client.execute(
+                "INSERT INTO asos_precip_rollups
+                 (scope, scope_id, window_hours, window_end, total_in, max_in, sample_count)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 ON CONFLICT (scope, scope_id, window_hours, window_end) DO UPDATE
+                 SET total_in = EXCLUDED.total_in,
+                     max_in = EXCLUDED.max_in,
+                     sample_count = EXCLUDED.sample_count,
+                     computed_at = NOW()",
                 &[
-                    &reading.site_code,
-                    &reading.parameter_code,
-                    &reading.unit,
-                    &value_decimal,
-                    &reading_time,
-                    &reading.qualifier,
+                    &rollup.scope.as_str(),
+                    &rollup.scope_id,
+                    &window_hours,
+                    &rollup.window_end,
+                    &rollup.total_in,
+                    &rollup.max_in,
+                    &rollup.sample_count,
                 ]
-            )?;
-            
-            inserted += rows_affected as usize;
+            )? as usize;
         }
         
-        Ok(inserted)
+        // The basin totals are also what the response curves are keyed on
+        if let Err(e) = self.report_expected_responses(&basin_rollups) {
+            logging::debug(logging::DataSource::Asos, None, &e);
+        }
+        
+        self.station_precip = station_rollups.clone();
+        let rollups: Vec<precip::PrecipRollup> = station_rollups.into_iter().chain(basin_rollups).collect();
+        self.sound_precip_alarm(&rollups);
+        
+        Ok(written)
     }
     
-    /// Update monitoring state after successful poll
-    pub fn update_monitoring_state(
-        &mut self, 
-        site_code: &str, 
+    /// Rewrite the status snapshot the endpoint serves from (see
+    /// `status_cache`). Not written in a dry run.
+    fn refresh_status_snapshot(&mut self, now: DateTime<Utc>) -> Result<(), String> {
+        let Some(settings) = &self.status_cache else {
+            return Ok(());
+        };
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        let snapshot = status_cache::build(client, &self.stations, &self.severity, now)?;
+        if self.dry_run {
+            println!("[dry-run] status snapshot {}: {} stations (not written)",
+                settings.path.display(), snapshot.stations.len());
+            return Ok(());
+        }
+        let bytes = status_cache::write(&settings.path, &snapshot)?;
+        logging::debug(logging::DataSource::System, None,
+            &format!("Status snapshot {} refreshed ({} bytes)", settings.path.display(), bytes));
+        Ok(())
+    }
+    
+    /// Fetch and summarize the MRMS QPE hours not yet stored (see `ingest::mrms`).
+    /// A missing hour is logged and retried next cycle while in the lookback.
+    fn warehouse_radar_qpe(&mut self, now: DateTime<Utc>) -> Result<usize, String> {
+        let Some(settings) = self.mrms.clone() else {
+            return Ok(0);
+        };
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        let due = settings.hours_due(now);
+        let stored = mrms::stored_hours(client, &due)?;
+        let footprints = settings.zone_footprints(self.zones.as_ref());
+        
+        let mut written = 0;
+        for hour_end in due.into_iter().filter(|h| !stored.contains(h)) {
+            let summaries = match mrms::fetch_grid(&settings, hour_end) {
+                Ok(grid) => mrms::summarize(&grid, hour_end, &footprints),
+                Err(e) => {
+                    logging::warn(logging::DataSource::System, Some("MRMS"), &e);
+                    continue;
+                }
+            };
+            if self.dry_run {
+                for s in &summaries {
+                    written += report_dry_run("precip_grid_summary", OnConflict::DoUpdate, false,
+                        &format!("{} {} {} = {:.2} in", s.scope.as_str(), s.scope.id(), hour_end, s.mean_in));
+                }
+                continue;
+            }
+            let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+            written += mrms::store(client, &summaries)?;
+        }
+        Ok(written)
+    }
+    
+    /// Log the expected tributary rise for basins with a rain event under
+    /// way, once at the start and again as the expectation grows
+    fn report_expected_responses(&mut self, basin_rollups: &[precip::PrecipRollup]) -> Result<(), String> {
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        // Reloaded each cycle: floml replaces the curves when it refits
+        let curves = precip_response::load_curves(client)?;
+        let responses = precip_response::expected_responses(&curves, basin_rollups);
+        for response in self.responses.update(&responses) {
+            logging::info(logging::DataSource::Asos, Some(&response.site_code), &response.describe());
+        }
+        Ok(())
+    }
+    
+    /// Alert the alarm site's subscribers for each basin or station that
+    /// crossed a `[precip_alarm]` threshold this cycle
+    fn sound_precip_alarm(&mut self, rollups: &[precip::PrecipRollup]) {
+        let Some(alarm) = self.precip_alarm.as_mut() else {
+            return;
+        };
+        let site = alarm.site().to_string();
+        for raised in alarm.evaluate(rollups, &self.asos_locations) {
+            logging::warn(logging::DataSource::Asos, Some(&raised.scope_id), &raised.alert.message);
+            self.notify_subscribers(&site, raised.alert);
+        }
+    }
+    
+    /// Notify the target's zone subscribers when rain, upstream rises and
+    /// lag estimates point to flooding there soon (see `alert::outlook`)
+    fn issue_outlook(&mut self, now: DateTime<Utc>) -> Result<(), String> {
+        let Some(settings) = self.outlook.as_ref().map(|o| o.settings().clone()) else {
+            return Ok(());
+        };
+        // Not monitored in this run (`ingest --source`)
+        let Some(target) = self.stations.iter().find(|s| s.site_code == settings.target).cloned() else {
+            return Ok(());
+        };
+        let thresholds = target.thresholds.clone()
+            .ok_or_else(|| format!("outlook target {} has no flood thresholds", target.site_code))?;
+        let upstream_stations: Vec<Station> = lag::upstream_of(&self.stations, &target).into_iter().cloned().collect();
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        
+        let since = now - Duration::hours(settings.trend_hours);
+        let Some((target_stage, _)) = stage_trend(client, &target.site_code, since, now)? else {
+            return Ok(());
+        };
+        let mut upstream = Vec::new();
+        for station in &upstream_stations {
+            if let Some((stage_ft, rise_ft)) = stage_trend(client, &station.site_code, since, now)? {
+                upstream.push(UpstreamTrend {
+                    site_code: station.site_code.clone(),
+                    stage_ft,
+                    rise_ft,
+                    at_action: station.thresholds.as_ref().is_some_and(|t| stage_ft >= t.action_stage_ft),
+                    travel_time_hours: station.travel_time_to_peoria_hours,
+                });
+            }
+        }
+        let lags = lag::load(client)?;
+        let mrms_basin_24h_in = match self.mrms {
+            Some(_) => mrms::basin_total(client, 24, now)?,
+            None => None,
+        };
+        let inputs = OutlookInputs {
+            target_stage_ft: target_stage,
+            thresholds: &thresholds,
+            upstream: &upstream,
+            lags: &lags,
+            asos_basin_24h: self.basin_precip.iter()
+                .filter(|((_, hours), _)| *hours == 24)
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .map(|((basin, _), inches)| (basin.clone(), *inches)),
+            mrms_basin_24h_in,
+        };
+        
+        let Some(advisory) = self.outlook.as_mut().and_then(|o| o.evaluate(&inputs)) else {
+            return Ok(());
+        };
+        logging::warn(logging::DataSource::System, Some(&target.site_code), &advisory.alert.message);
+        self.notify_subscribers(&target.site_code, advisory.alert);
+        Ok(())
+    }
+    
+    /// Assess Mississippi backwater on the LaGrange and Peoria pools, record
+    /// it, and alert the Peoria zones when it starts affecting that reach
+    fn assess_backwater(&mut self, now: DateTime<Utc>) -> Result<(), String> {
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        let since = now - Duration::hours(backwater::MAX_AGE_HOURS);
+        let slope_row = client.query_opt(
+            "SELECT slope_ft_per_mile, trend FROM flood_analysis.stage_slope
+             WHERE timestamp >= $1 ORDER BY timestamp DESC LIMIT 1",
+            &[&since],
+        ).map_err(|e| format!("Stage slope query failed: {}", e))?;
+        let inputs = BackwaterInputs {
+            grafton_stage_ft: latest_cwms(client, backwater::GRAFTON_LOCATION, "Stage", since)?,
+            lagrange_pool_ft: latest_cwms(client, backwater::LAGRANGE_POOL, "Elev", since)?,
+            lagrange_tailwater_ft: latest_cwms(client, "LaGrange%", "Elev-Tailwater", since)?,
+            peoria_pool_ft: latest_cwms(client, backwater::PEORIA_POOL, "Elev", since)?,
+            peoria_tailwater_ft: latest_cwms(client, "Peoria%", "Elev-Tailwater", since)?,
+            slope: slope_row.map(|row| {
+                let trend: Option<String> = row.get(1);
+                (row.get(0), trend.as_deref().and_then(slope::SlopeTrend::parse))
+            }),
+        };
+        if inputs.is_empty() {
+            return Ok(());
+        }
+        let status = backwater::assess(&inputs);
+        
+        let was_affecting = client.query_opt(
+            "SELECT affecting_peoria FROM flood_analysis.backwater_status ORDER BY timestamp DESC LIMIT 1",
+            &[],
+        ).map_err(|e| format!("Backwater status query failed: {}", e))?
+            .is_some_and(|row| row.get::<_, bool>(0));
+        
+        let row = format!("{} {} ({}){}", now.to_rfc3339(), status.risk_level, status.confidence,
+            if status.affecting_peoria { ", affecting Peoria" } else { "" });
+        if self.dry_run {
+            report_dry_run("flood_analysis.backwater_status", OnConflict::DoNothing, false, &row);
+        } else {
+            client.execute(
+                "INSERT INTO flood_analysis.backwater_status
+                 (timestamp, grafton_stage_ft, lagrange_pool_ft, lagrange_tailwater_ft, peoria_pool_ft,
+                  peoria_tailwater_ft, slope_ft_per_mile, slope_trend, risk_level, confidence, affecting_peoria)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                 ON CONFLICT (timestamp) DO NOTHING",
+                &[
+                    &now,
+                    &inputs.grafton_stage_ft,
+                    &inputs.lagrange_pool_ft,
+                    &inputs.lagrange_tailwater_ft,
+                    &inputs.peoria_pool_ft,
+                    &inputs.peoria_tailwater_ft,
+                    &inputs.slope.map(|(value, _)| value),
+                    &inputs.slope.and_then(|(_, trend)| trend).map(|t| t.as_str()),
+                    &status.risk_level,
+                    &status.confidence,
+                    &status.affecting_peoria,
+                ],
+            ).map_err(|e| format!("Failed to store backwater status: {}", e))?;
+        }
+        
+        if status.affecting_peoria && !was_affecting {
+            let alert = backwater::alert(&inputs, &status);
+            logging::warn(logging::DataSource::Cwms, Some(backwater::PEORIA_POOL), &alert.message);
+            self.notify_subscribers(slope::UPSTREAM_SITE, alert);
+        }
+        Ok(())
+    }
+    
+    /// Roll every zone's sensors up into its status and record it (see
+    /// `zones::status`)
+    fn warehouse_zone_status(&mut self, now: DateTime<Utc>) -> Result<usize, String> {
+        let Some(zones) = self.zones.clone() else {
+            return Ok(0);
+        };
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        let since = now - Duration::days(1);
+        let latest_by = |client: &mut Client, sql: &str| -> Result<HashMap<String, DateTime<Utc>>, String> {
+            Ok(client.query(sql, &[&since])
+                .map_err(|e| format!("Latest observation query failed: {}", e))?
+                .iter()
+                .map(|row| (row.get(0), row.get(1)))
+                .collect())
+        };
+        let mut state = CycleState {
+            usgs_observed: self.latest_readings.iter()
+                .map(|(site, r)| (site.clone(), r.datetime.with_timezone(&Utc)))
+                .collect(),
+            cwms_observed: latest_by(client,
+                "SELECT location_id, MAX(timestamp) FROM usace.cwms_timeseries
+                 WHERE timestamp >= $1 GROUP BY location_id")?,
+            asos_observed: latest_by(client,
+                "SELECT station_id, MAX(observation_time) FROM asos_observations
+                 WHERE observation_time >= $1 GROUP BY station_id")?,
+            severities: HashMap::new(),
+            rise_rates: HashMap::new(),
+            precip: self.station_precip.clone(),
+        };
+        
+        let mut sites: Vec<&str> = zones::get_all_zones(&zones).into_iter()
+            .flat_map(|(_, zone)| zone.sensors.iter().filter_map(|s| s.usgs_id.as_deref()))
+            .collect();
+        sites.sort_unstable();
+        sites.dedup();
+        for site in sites {
+            if let Some(severity) = self.severities.level(site) {
+                state.severities.insert(site.to_string(), severity);
+            }
+            let readings = archive::readings_between(
+                client, site, PARAM_STAGE, now - Duration::hours(zone_status::RISE_WINDOW_HOURS), now,
+            )?;
+            let stages: Vec<(DateTime<Utc>, f64)> = quality::alertable(&readings).iter()
+                .map(|r| (r.datetime.with_timezone(&Utc), r.value))
+                .collect();
+            if let Some(rate) = zone_status::rise_rate(&stages) {
+                state.rise_rates.insert(site.to_string(), rate);
+            }
+        }
+        
+        let mut written = 0;
+        for (zone_id, zone) in zones::get_all_zones(&zones) {
+            let status = zone_status::rollup(zone_id, zone, &state, now);
+            if self.dry_run {
+                written += report_dry_run("flood_analysis.zone_status", OnConflict::DoNothing, false, &format!(
+                    "zone {} {} rise {} stale {}/{}",
+                    zone_id,
+                    transitions::severity_label(status.severity.as_ref()),
+                    status.max_rise_ft_per_hour.map(|r| format!("{:+.2} ft/h", r)).unwrap_or_else(|| "-".to_string()),
+                    status.stale_sensors.len(),
+                    status.sensor_count,
+                ));
+                continue;
+            }
+            written += zone_status::store(client, &status)?;
+        }
+        Ok(written)
+    }
+    
+    /// Fetch the NWS flood watches and warnings in effect, store them, and
+    /// dispatch the ones not seen before to the zones they cover
+    fn ingest_nws_alerts(&mut self, now: DateTime<Utc>) -> Result<(), String> {
+        let Some(settings) = &self.nws_alerts else {
+            return Ok(());
+        };
+        let alerts = nws_alerts::fetch(settings)?;
+        if self.dry_run {
+            for alert in &alerts {
+                report_dry_run("nws.active_alerts", OnConflict::DoUpdate, false, &alert.describe());
+            }
+            return Ok(());
+        }
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        let new_ids = nws_alerts::store(client, &alerts, now)?;
+        if new_ids.is_empty() {
+            return Ok(());
+        }
+        let filter = nws_geo::load_filter(client).unwrap_or_else(|e| {
+            logging::warn(logging::DataSource::Database, Some("NWS"), &e);
+            AlertFilter::default()
+        });
+        for alert in alerts.iter().filter(|a| new_ids.contains(&a.id)) {
+            logging::warn(logging::DataSource::System, Some("NWS"), &alert.describe());
+            let Some(zones) = &self.zones else { continue };
+            let zone_ids = nws_alerts::zones_affected(&filter, zones, &alert.area);
+            self.notify_zones(&zone_ids, alert.to_flood_alert());
+        }
+        Ok(())
+    }
+    
+    /// Poll ASOS station for recent observations
+    /// Fetch every USGS station, CWMS location and ASOS station due in the
+    /// cycle starting at `now` concurrently (see `ingest::fetch`)
+    fn fetch_cycle(&mut self, now: DateTime<Utc>) -> Result<CycleResults, Box<dyn Error>> {
+        let plan = CyclePlan {
+            usgs_sites: self.stations.iter()
+                .filter(|s| self.schedule.is_due(Source::Usgs, &s.site_code, now))
+                .map(|s| s.site_code.clone())
+                .collect(),
+            cwms: self.cwms_locations.iter()
+                .filter(|location| self.schedule.is_due(Source::Cwms, &location.name, now))
+                .filter_map(|location| {
+                    let discovered = location.discovered_timeseries.as_ref()?;
+                    let timeseries = discovered.series();
+                    (!timeseries.is_empty()).then(|| CwmsRequest {
+                        location: location.name.clone(),
+                        office: location.office.clone(),
+                        timeseries,
+                    })
+                })
+                .collect(),
+            asos_stations: self.asos_locations.iter()
+                .filter(|l| self.schedule.is_due(Source::Asos, &l.station_id, now))
+                .map(|l| l.station_id.clone())
+                .collect(),
+            gentle: [Source::Usgs, Source::Cwms, Source::Asos].into_iter()
+                .filter(|&source| maintenance::active(&self.maintenance, source, Utc::now()).is_some())
+                .collect(),
+            critical: if self.event_mode.mode() == event_mode::Mode::Event {
+                self.critical_stations()
+            } else {
+                Vec::new()
+            },
+        };
+        if self.fetch_runtime.is_none() {
+            self.fetch_runtime = Some(fetch::runtime()?);
+        }
+        let runtime = self.fetch_runtime.as_ref().expect("fetch runtime was just started");
+        Ok(runtime.block_on(fetch::fetch_cycle(&plan, self.config.fetch_limits())))
+    }
+    
+    /// Site codes, CWMS location names and ASOS ids of CRITICAL-priority
+    /// stations (the keys `CyclePlan::critical` matches on)
+    fn critical_stations(&self) -> Vec<String> {
+        let usgs = self.stations.iter().filter(|s| s.critical).map(|s| s.site_code.clone());
+        let cwms = self.cwms_locations.iter()
+            .filter(|l| l.priority == usace_locations::MonitoringPriority::Critical)
+            .map(|l| l.name.clone());
+        let asos = self.asos_locations.iter()
+            .filter(|l| l.priority == asos_locations::MonitoringPriority::Critical)
+            .map(|l| l.station_id.clone());
+        usgs.chain(cwms).chain(asos).collect()
+    }
+    
+    /// Latest stored observation for an ASOS station
+    pub fn check_asos_latest(&mut self, station_id: &str) -> Result<Option<DateTime<Utc>>, Box<dyn Error>> {
+        let client = self.client.as_mut()
+            .ok_or("Daemon not initialized")?;
+        
+        let row = client.query_one(
+            "SELECT MAX(observation_time) FROM asos_observations WHERE station_id = $1",
+            &[&station_id]
+        )?;
+        Ok(row.get(0))
+    }
+    
+    /// Backfill every feed left with a hole by downtime before normal
+    /// polling resumes: oldest hole first, requests to each source spaced
+    /// by `catchup::Source::min_request_interval`. Stops early (leaving the
+    /// rest for the next start) on a shutdown request.
+    pub fn catch_up(&mut self) -> Result<CatchUpReport, Box<dyn Error>> {
+        let now = Utc::now();
+        let mut feeds = Vec::new();
+        for site_code in self.stations.iter().map(|s| s.site_code.clone()).collect::<Vec<_>>() {
+            let latest = self.check_staleness(&site_code)?.map(|age| now - age);
+            feeds.push((catchup::Source::Usgs, site_code, latest));
+        }
+        for location in self.cwms_locations.iter().filter(|l| l.discovered_timeseries.is_some()).cloned().collect::<Vec<_>>() {
+            let latest = self.check_cwms_staleness(&location.cwms_location)?.map(|age| now - age);
+            feeds.push((catchup::Source::Cwms, location.cwms_location, latest));
+        }
+        for station_id in self.asos_locations.iter().map(|l| l.station_id.clone()).collect::<Vec<_>>() {
+            let latest = self.check_asos_latest(&station_id)?;
+            feeds.push((catchup::Source::Asos, station_id, latest));
+        }
+        
+        let tasks = catchup::plan(feeds, now);
+        let mut report = CatchUpReport::default();
+        if tasks.is_empty() {
+            return Ok(report);
+        }
+        logging::info(logging::DataSource::System, None, &format!("📥 Catching up {} feeds (oldest gap first)...", tasks.len()));
+        
+        let mut limiter = RateLimiter::new();
+        for (i, task) in tasks.iter().enumerate() {
+            let wait = limiter.wait_before(task.source, std::time::Instant::now());
+            if shutdown::requested() || !shutdown::sleep(wait) {
+                report.skipped = tasks.len() - i;
+                break;
+            }
+            limiter.record(task.source, std::time::Instant::now());
+            
+            let result = match task.source {
+                catchup::Source::Usgs => self.backfill_station(&task.feed),
+                catchup::Source::Cwms => match self.cwms_locations.iter().find(|l| l.cwms_location == task.feed).cloned() {
+                    Some(location) => self.backfill_cwms_location(&location),
+                    None => Ok(0),
+                },
+                catchup::Source::Asos => {
+                    let days = match task.latest {
+                        Some(_) => task.days(now).min(catchup::MAX_ASOS_CATCHUP_DAYS),
+                        None => catchup::NEW_ASOS_CATCHUP_DAYS,
+                    };
+                    self.backfill_asos_station(&task.feed, days)
+                }
+            }
+            .map_err(|e| e.to_string());
+            
+            match &result {
+                Ok(count) => logging::info(logging::DataSource::System, Some(&task.feed),
+                    &format!("✓ {} catch-up: {} readings", task.source.as_str(), count)),
+                Err(e) => logging::warn(
+                    logging::DataSource::System,
+                    Some(&task.feed),
+                    &format!("{} catch-up failed: {}", task.source.as_str(), e),
+                ),
+            }
+            report.record(task, result);
+        }
+        
+        for line in report.summary(Utc::now()) {
+            logging::info(logging::DataSource::System, None, &format!("Catch-up: {}", line));
+        }
+        Ok(report)
+    }
+    
+    /// Backfill ASOS historical data for a station
+    pub fn backfill_asos_station(&mut self, station_id: &str, days: i64) -> Result<usize, Box<dyn Error>> {
+        let http_client = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()?;
+        
+        let hours = days * 24;
+        let observations = iem::dedup_observations(
+            iem::fetch_recent_precip(&http_client, station_id, hours)?
+        );
+        
+        self.warehouse_asos_observations(&observations)
+    }
+    
+    // ---------------------------------------------------------------------------
+    // USGS Data Warehousing
+    // ---------------------------------------------------------------------------
+    
+    /// Poll a single station for latest data
+    pub fn poll_station(&mut self, site_code: &str) -> Result<Vec<GaugeReading>, Box<dyn Error>> {
+        // Build URL for instantaneous values (last 4 hours to ensure we get recent data)
+        // USGS updates IV data every 15-60 minutes depending on the station
+        let params = ["00060", "00065"]; // Discharge and stage
+        let period = "PT4H"; // Last 4 hours
+        
+        // Fetch and parse - note this returns the most recent value per parameter
+        let readings = fetch_usgs_with_fallback(
+            site_code,
+            (&usgs::build_iv_url(&[site_code], &params, period), usgs::parse_iv_response),
+            (&usgs::build_iv_rdb_url(&[site_code], &params, period), usgs_rdb::parse_iv_rdb),
+            15,
+        )?;
+        
+        Ok(readings)
+    }
+    
+    /// Warehouse readings into database (idempotent)
+    pub fn warehouse_readings(&mut self, readings: &[GaugeReading]) -> Result<usize, Box<dyn Error>> {
+        let client = self.client.as_mut()
+            .ok_or("Daemon not initialized")?;
+        
+        if !self.dry_run && readings.len() >= bulk::MIN_BULK_ROWS {
+            return Ok(bulk::insert_gauge_readings(client, readings)?);
+        }
+        
+        let mut inserted = 0;
+        
+        for reading in readings {
+            let reading_time = reading.datetime.with_timezone(&Utc);
+            
+            if self.dry_run {
+                let exists: bool = client.query_one(
+                    "SELECT EXISTS(SELECT 1 FROM usgs_raw.gauge_readings
+                     WHERE site_code = $1 AND parameter_code = $2 AND reading_time = $3)",
+                    &[&reading.site_code, &reading.parameter_code, &reading_time]
+                )?.get(0);
+                inserted += report_dry_run(
+                    "usgs_raw.gauge_readings",
+                    OnConflict::DoNothing,
+                    exists,
+                    &format!("{} {} {} = {} {}", reading.site_code, reading.parameter_code,
+                        reading_time.to_rfc3339(), reading.value, reading.unit),
+                );
+                continue;
+            }
+            
+            // Convert value to Decimal for PostgreSQL NUMERIC type
+            let value_decimal = rust_decimal::Decimal::from_f64_retain(reading.value)
+                .ok_or_else(|| format!("Failed to convert value {} to decimal", reading.value))?;
+            
+            // Use INSERT ... ON CONFLICT DO NOTHING for idempotency
+            let rows_affected = client.execute(
+                "INSERT INTO usgs_raw.gauge_readings 
+                 (site_code, parameter_code, unit, value, reading_time, qualifier, original_unit)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 ON CONFLICT (site_code, parameter_code, reading_time) DO NOTHING",
+                &[
+                    &reading.site_code,
+                    &reading.parameter_code,
+                    &reading.unit,
+                    &value_decimal,
+                    &reading_time,
+                    &reading.qualifier,
+                    &reading.original_unit,
+                ]
+            )?;
+            
+            inserted += rows_affected as usize;
+        }
+        
+        Ok(inserted)
+    }
+    
+    /// Score readings against each series' recent history and store the scores.
+    ///
+    /// Suspect values are logged as warnings but still warehoused; the score
+    /// is advisory. Returns the number of suspect readings.
+    pub fn score_readings(&mut self, readings: &[GaugeReading]) -> Result<usize, Box<dyn Error>> {
+        let client = self.client.as_mut()
+            .ok_or("Daemon not initialized")?;
+        
+        let mut suspect = 0;
+        
+        for reading in readings {
+            let reading_time = reading.datetime.with_timezone(&Utc);
+            let rows = client.query(
+                "SELECT value FROM usgs_raw.gauge_readings
+                 WHERE site_code = $1 AND parameter_code = $2
+                   AND reading_time >= $3 AND reading_time < $4",
+                &[
+                    &reading.site_code,
+                    &reading.parameter_code,
+                    &(reading_time - Duration::hours(anomaly::ANOMALY_WINDOW_HOURS)),
+                    &reading_time,
+                ]
+            )?;
+            let history: Vec<f64> = rows.iter()
+                .filter_map(|row| row.get::<_, rust_decimal::Decimal>(0).to_string().parse().ok())
+                .collect();
+            
+            let Some(score) = anomaly::score_reading(
+                &reading.site_code,
+                &reading.parameter_code,
+                reading_time,
+                reading.value,
+                &history,
+            ) else {
+                continue;
+            };
+            
+            if self.dry_run {
+                let exists: bool = client.query_one(
+                    "SELECT EXISTS(SELECT 1 FROM usgs_raw.reading_anomaly_scores
+                     WHERE site_code = $1 AND parameter_code = $2 AND reading_time = $3)",
+                    &[&score.site_code, &score.parameter_code, &score.reading_time]
+                )?.get(0);
+                report_dry_run(
+                    "usgs_raw.reading_anomaly_scores",
+                    OnConflict::DoUpdate,
+                    exists,
+                    &format!("{} {} {} z={:.1} suspect={}", score.site_code, score.parameter_code,
+                        score.reading_time.to_rfc3339(), score.robust_z, score.suspect),
+                );
+            } else {
+                client.execute(
+                    "INSERT INTO usgs_raw.reading_anomaly_scores
+                     (site_code, parameter_code, reading_time, value, window_median,
+                      window_mad, robust_z, sample_count, suspect)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                     ON CONFLICT (site_code, parameter_code, reading_time) DO UPDATE
+                     SET value = EXCLUDED.value,
+                         window_median = EXCLUDED.window_median,
+                         window_mad = EXCLUDED.window_mad,
+                         robust_z = EXCLUDED.robust_z,
+                         sample_count = EXCLUDED.sample_count,
+                         suspect = EXCLUDED.suspect,
+                         scored_at = NOW()",
+                    &[
+                        &score.site_code,
+                        &score.parameter_code,
+                        &score.reading_time,
+                        &score.value,
+                        &score.window_median,
+                        &score.window_mad,
+                        &score.robust_z,
+                        &score.sample_count,
+                        &score.suspect,
+                    ]
+                )?;
+            }
+            
+            if score.suspect {
+                suspect += 1;
+                logging::warn(
+                    logging::DataSource::Usgs,
+                    Some(&score.site_code),
+                    &format!(
+                        "Suspect {} value {} at {} (robust z {:.1}, {}h median {:.2})",
+                        score.parameter_code, score.value, score.reading_time,
+                        score.robust_z, anomaly::ANOMALY_WINDOW_HOURS, score.window_median
+                    ),
+                );
+            }
+        }
+        
+        Ok(suspect)
+    }
+    
+    /// Update monitoring state after successful poll
+    pub fn update_monitoring_state(
+        &mut self, 
+        site_code: &str, 
         last_reading_time: Option<DateTime<Utc>>
     ) -> Result<(), Box<dyn Error>> {
         let client = self.client.as_mut()
             .ok_or("Daemon not initialized")?;
         
+        if self.dry_run {
+            let latest = last_reading_time.map(|t| t.to_rfc3339()).unwrap_or_else(|| "none".to_string());
+            report_monitoring_state_dry_run(client, site_code, &format!("{} latest={}", site_code, latest))?;
+            return Ok(());
+        }
+        
         // Update or insert monitoring state
         client.execute(
             "INSERT INTO usgs_raw.monitoring_state 
@@ -795,6 +2107,11 @@ impl Daemon {
         let client = self.client.as_mut()
             .ok_or("Daemon not initialized")?;
         
+        if self.dry_run {
+            report_monitoring_state_dry_run(client, site_code, &format!("{} failure", site_code))?;
+            return Ok(());
+        }
+        
         client.execute(
             "INSERT INTO usgs_raw.monitoring_state 
              (site_code, parameter_code, last_poll_attempted, consecutive_failures)
@@ -811,38 +2128,96 @@ impl Daemon {
     /// Run one iteration of the monitoring loop for all stations
     pub fn poll_all_stations(&mut self) -> Result<HashMap<String, usize>, Box<dyn Error>> {
         let mut results = HashMap::new();
+        let mut latest_stages: HashMap<String, (f64, DateTime<Utc>)> = HashMap::new();
+        
+        let cycle_start = Utc::now();
+        self.refresh_suppressions(cycle_start);
+        let mut fetched = self.fetch_cycle(cycle_start)?;
+        self.refresh_ratings(Utc::now());
+        let mut polled_feeds: Vec<(Source, String)> = Vec::new();
         
         // Poll USGS stations
         for station in &self.stations.clone() {
-            match self.poll_station(&station.site_code) {
+            if !self.schedule.is_due(Source::Usgs, &station.site_code, cycle_start) {
+                continue;
+            }
+            polled_feeds.push((Source::Usgs, station.site_code.clone()));
+            let polled = fetched.usgs.remove(&station.site_code)
+                .unwrap_or_else(|| Err(IngestError::Transport("fetch task did not complete".to_string())));
+            match polled {
                 Ok(readings) => {
                     let inserted = self.warehouse_readings(&readings)?;
+                    if inserted > 0 {
+                        self.publish(&latest_per_parameter(&readings));
+                    }
+                    if let Some(stage) = latest_stage(&readings) {
+                        latest_stages.insert(station.site_code.clone(), stage);
+                    }
+                    
+                    if let Err(e) = self.score_readings(&readings) {
+                        logging::warn(
+                            logging::DataSource::Usgs,
+                            Some(&station.site_code),
+                            &format!("Failed to score readings: {}", e),
+                        );
+                    }
                     
                     // Get latest timestamp from readings
                     let latest = readings.iter()
-                        .filter_map(|r| chrono::DateTime::parse_from_rfc3339(&r.datetime).ok())
-                        .map(|dt| dt.with_timezone(&Utc))
+                        .map(|r| r.datetime.with_timezone(&Utc))
                         .max();
                     
+                    if let Some(latest) = latest {
+                        self.usgs_cadence.observe(&station.site_code, latest, Utc::now());
+                    }
+                    if let Some(reading) = readings.iter().max_by_key(|r| r.datetime).cloned() {
+                        self.latest_readings.insert(station.site_code.clone(), reading);
+                    }
                     self.update_monitoring_state(&station.site_code, latest)?;
+                    self.adapt_poll_interval(station, &readings)?;
+                    if let Err(e) = self.check_datum_shift(&station.site_code) {
+                        logging::warn(
+                            logging::DataSource::Usgs,
+                            Some(&station.site_code),
+                            &format!("Failed to check for datum shift: {}", e),
+                        );
+                    }
+                    self.track_severity(station, &readings);
                     results.insert(format!("USGS:{}", station.site_code), inserted);
                 }
                 Err(e) => {
-                    eprintln!("Failed to poll USGS {}: {}", station.site_code, e);
+                    self.report_poll_failure(Source::Usgs, &station.site_code, &e);
                     self.record_failure(&station.site_code)?;
                     results.insert(format!("USGS:{}", station.site_code), 0);
                 }
             }
         }
         
-        // Poll CWMS locations
+        if let Err(e) = self.warehouse_stage_slope(&latest_stages) {
+            logging::warn(
+                logging::DataSource::Usgs,
+                Some(slope::UPSTREAM_SITE),
+                &format!("Failed to update Peoria-Kingston Mines slope: {}", e),
+            );
+        }
+        
+        // Poll CWMS locations (those without discovered timeseries weren't fetched)
         for location in &self.cwms_locations.clone() {
-            match self.poll_cwms_location(&location) {
+            if !self.schedule.is_due(Source::Cwms, &location.name, cycle_start) {
+                continue;
+            }
+            polled_feeds.push((Source::Cwms, location.name.clone()));
+            let polled = match fetched.cwms.remove(&location.name) {
+                Some(Ok(timeseries)) => self.warehouse_cwms_timeseries(&timeseries),
+                Some(Err(e)) => Err(e.into()),
+                None => Ok(0),
+            };
+            match polled {
                 Ok(inserted) => {
                     results.insert(format!("CWMS:{}", location.name), inserted);
                 }
                 Err(e) => {
-                    eprintln!("Failed to poll CWMS {}: {}", location.name, e);
+                    self.report_poll_failure(Source::Cwms, &location.name, e.as_ref());
                     results.insert(format!("CWMS:{}", location.name), 0);
                 }
             }
@@ -850,69 +2225,1351 @@ impl Daemon {
         
         // Poll ASOS stations (based on priority)
         for location in &self.asos_locations.clone() {
-            match self.poll_asos_station(&location.station_id) {
+            if !self.schedule.is_due(Source::Asos, &location.station_id, cycle_start) {
+                continue;
+            }
+            polled_feeds.push((Source::Asos, location.station_id.clone()));
+            let polled = fetched.asos.remove(&location.station_id)
+                .unwrap_or_else(|| Err(IngestError::Transport("fetch task did not complete".to_string())));
+            match polled {
                 Ok(observations) => {
+                    if let Some(latest) = observations.iter().map(|o| o.timestamp).max() {
+                        self.asos_cadence.observe(&location.station_id, latest, Utc::now());
+                    }
                     let inserted = self.warehouse_asos_observations(&observations)?;
                     results.insert(format!("ASOS:{}", location.station_id), inserted);
                 }
                 Err(e) => {
-                    eprintln!("Failed to poll ASOS {}: {}", location.station_id, e);
+                    self.report_poll_failure(Source::Asos, &location.station_id, &e);
                     results.insert(format!("ASOS:{}", location.station_id), 0);
                 }
             }
         }
         
-        Ok(results)
-    }
-    
-    /// Main daemon loop (runs indefinitely)
-    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
-        println!("🚀 Starting daemon loop...");
-        println!("   Poll interval: {} minutes", self.config.poll_interval_minutes);
-        println!("   Monitoring {} USGS stations + {} CWMS locations + {} ASOS stations", 
-                self.stations.len(), self.cwms_locations.len(), self.asos_locations.len());
-        
-        loop {
-            let start = Utc::now();
-            
-            match self.poll_all_stations() {
-                Ok(results) => {
-                    let total: usize = results.values().sum();
-                    let usgs_count = results.iter().filter(|(k, _)| k.starts_with("USGS:")).count();
-                    let cwms_count = results.iter().filter(|(k, _)| k.starts_with("CWMS:")).count();
-                    let asos_count = results.iter().filter(|(k, _)| k.starts_with("ASOS:")).count();
-                    println!("✓ Poll complete: {} new readings ({} USGS, {} CWMS, {} ASOS)", 
-                            total, usgs_count, cwms_count, asos_count);
+        // Community/private sources
+        let mut plugins = std::mem::take(&mut self.plugins);
+        for source in plugins.iter_mut() {
+            let key = format!("PLUGIN:{}", source.name());
+            match self.poll_plugin(source.as_mut()) {
+                Ok(inserted) => {
+                    results.insert(key, inserted);
                 }
                 Err(e) => {
-                    eprintln!("✗ Poll error: {}", e);
+                    logging::warn(logging::DataSource::System, Some(source.name()), &e);
+                    results.insert(key, 0);
                 }
             }
-            
-            // Sleep until next poll interval
-            let elapsed = (Utc::now() - start).num_seconds();
-            let sleep_seconds = (self.config.poll_interval_minutes * 60) as i64 - elapsed;
-            
-            if sleep_seconds > 0 {
-                std::thread::sleep(std::time::Duration::from_secs(sleep_seconds as u64));
-            }
         }
+        self.plugins = plugins;
+        
+        self.update_event_mode(Utc::now());
+        self.schedule_next_polls(cycle_start, &polled_feeds);
+        self.report_overdue(Utc::now());
+        self.report_stale_data(Utc::now());
+        
+        // Refresh precipitation windows once all stations are in
+        if let Err(e) = self.warehouse_precip_rollups(Utc::now()) {
+            logging::warn(
+                logging::DataSource::Asos,
+                None,
+                &format!("Failed to update precipitation rollups: {}", e),
+            );
+        }
+        
+        self.evaluate_rules(&latest_stages, Utc::now());
+        
+        if let Err(e) = self.ingest_nws_alerts(Utc::now()) {
+            logging::warn(logging::DataSource::System, Some("NWS"), &format!("Failed to update NWS alerts: {}", e));
+        }
+        
+        if let Err(e) = self.warehouse_radar_qpe(Utc::now()) {
+            logging::warn(logging::DataSource::System, Some("MRMS"), &format!("Failed to update radar QPE: {}", e));
+        }
+        
+        if let Err(e) = self.issue_outlook(Utc::now()) {
+            logging::warn(logging::DataSource::System, None, &format!("Failed to evaluate the flood outlook: {}", e));
+        }
+        
+        if let Err(e) = self.assess_backwater(Utc::now()) {
+            logging::warn(logging::DataSource::Cwms, Some(backwater::LAGRANGE_POOL), &format!("Failed to assess backwater: {}", e));
+        }
+        
+        if let Err(e) = self.warehouse_zone_status(Utc::now()) {
+            logging::warn(logging::DataSource::System, None, &format!("Failed to update zone status: {}", e));
+        }
+        
+        self.refresh_field_measurements(Utc::now());
+        self.refresh_daily_stats(Utc::now());
+        self.refresh_annual_peaks(Utc::now());
+        
+        if let Err(e) = self.refresh_isws_forecasts(Utc::now()) {
+            logging::warn(logging::DataSource::System, Some("ISWS"), &format!("Failed to update ISWS forecasts: {}", e));
+        }
+        
+        Ok(results)
     }
-}
-
-// ---------------------------------------------------------------------------
-// Tests
-// ---------------------------------------------------------------------------
-
-#[cfg(test)]
-mod tests {
-    use super::*;
     
-    #[test]
-    fn test_daemon_creation() {
-        let daemon = Daemon::new();
-        assert_eq!(daemon.config.poll_interval_minutes, 15);
-        assert_eq!(daemon.config.staleness_threshold_minutes, 60);
+    /// Re-read the suppressions in effect, logging those that began or
+    /// ended since the last cycle; on a failed read the previous ones stay
+    fn refresh_suppressions(&mut self, now: DateTime<Utc>) {
+        let Some(client) = self.client.as_mut() else {
+            return;
+        };
+        let current = match suppression::active(client, now) {
+            Ok(current) => current,
+            Err(e) => {
+                logging::warn(logging::DataSource::Database, None, &e);
+                return;
+            }
+        };
+        for s in current.iter().filter(|s| !self.suppressions.iter().any(|p| p.id == s.id)) {
+            logging::info(s.source.data_source(), Some(&s.station_id), &format!(
+                "{}{} in effect", s.label(), s.parameter_code.as_ref().map(|p| format!(" for {}", p)).unwrap_or_default()));
+        }
+        for s in self.suppressions.iter().filter(|p| !current.iter().any(|s| s.id == p.id)) {
+            logging::info(s.source.data_source(), Some(&s.station_id), &format!("Suppression #{} over", s.id));
+        }
+        self.suppressions = current;
+    }
+    
+    /// Whether `id` of `source` is suppressed as a whole at `now`
+    fn is_suppressed(&self, source: Source, id: &str, now: DateTime<Utc>) -> bool {
+        suppression::find(&self.suppressions, source, id, None, now).is_some()
+    }
+    
+    /// Log a failed poll: at DEBUG as expected while the station is
+    /// suppressed or inside one of the source's maintenance windows,
+    /// otherwise as the source's `logging::classify_*_failure` rates it
+    fn report_poll_failure(&self, source: Source, id: &str, error: &(dyn Error + 'static)) {
+        if let Some(suppressed) = suppression::find(&self.suppressions, source, id, None, Utc::now()) {
+            logging::log_classified_failure(
+                source.data_source(),
+                id,
+                &format!("Poll during {}", suppressed.label()),
+                logging::FailureType::Expected,
+                &error.to_string(),
+            );
+            return;
+        }
+        match maintenance::active(&self.maintenance, source, Utc::now()) {
+            Some(window) => logging::log_classified_failure(
+                source.data_source(),
+                id,
+                &format!("Poll during {}", window.label()),
+                logging::FailureType::Expected,
+                &error.to_string(),
+            ),
+            None => match source {
+                Source::Usgs => logging::log_usgs_failure(id, "Poll", error),
+                Source::Cwms => logging::log_cwms_failure(id, "Poll", error),
+                Source::Asos => logging::log_asos_failure(id, "Poll", error),
+            },
+        }
+    }
+    
+    /// Fetch, parse and store one plugin's readings
+    fn poll_plugin(&mut self, source: &mut dyn DataSourcePlugin) -> Result<usize, String> {
+        let http = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+        let payload = source.fetch(&http)?;
+        let readings = source.parse(&payload)?;
+        
+        if self.dry_run {
+            let table = source.destination().qualified()?;
+            for r in &readings {
+                report_dry_run(&table, OnConflict::DoNothing, false, &format!(
+                    "{} {} {} {} {} {}", source.name(), r.site_id, r.parameter, r.reading_time, r.value, r.unit));
+            }
+            return Ok(readings.len());
+        }
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        plugin::store(client, source, &readings)
+    }
+    
+    /// Look for a stage datum shift in the last `datum_shift::WINDOW_HOURS`
+    /// and open a data-quality alert for a new one
+    fn check_datum_shift(&mut self, site_code: &str) -> Result<(), Box<dyn Error>> {
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        let rows = client.query(
+            "SELECT parameter_code, reading_time, value FROM usgs_raw.gauge_readings
+             WHERE site_code = $1 AND parameter_code IN ('00065', '00060')
+               AND reading_time >= $2
+             ORDER BY reading_time",
+            &[&site_code, &(Utc::now() - Duration::hours(datum_shift::WINDOW_HOURS))],
+        )?;
+        let (mut stage, mut discharge) = (Vec::new(), Vec::new());
+        for row in &rows {
+            let parameter: String = row.get(0);
+            let Ok(value) = row.get::<_, rust_decimal::Decimal>(2).to_string().parse::<f64>() else {
+                continue;
+            };
+            let series = if parameter == "00065" { &mut stage } else { &mut discharge };
+            series.push((row.get::<_, DateTime<Utc>>(1), value));
+        }
+        let Some(shift) = datum_shift::detect(site_code, &stage, &discharge) else {
+            return Ok(());
+        };
+
+        if self.dry_run {
+            report_dry_run("usgs_raw.data_quality_alerts", OnConflict::DoNothing, false,
+                &format!("{} {} {}", site_code, data_quality::KIND_DATUM_SHIFT, shift.describe()));
+            return Ok(());
+        }
+        if let Some(id) = data_quality::open_datum_shift(client, &shift, Utc::now())? {
+            logging::warn(
+                logging::DataSource::Usgs,
+                Some(site_code),
+                &format!("{}; opened data-quality alert #{} for review", shift.describe(), id),
+            );
+        }
+        Ok(())
+    }
+    
+    /// Once every `field_measurements::REFRESH_HOURS`, fetch each discharge
+    /// station's field measurements and check them against the rating
+    fn refresh_field_measurements(&mut self, now: DateTime<Utc>) {
+        if self.measurements_refreshed.is_some_and(|t| now - t < Duration::hours(field_measurements::REFRESH_HOURS)) {
+            return;
+        }
+        self.measurements_refreshed = Some(now);
+        
+        let sites: Vec<String> = self.stations.iter()
+            .filter(|s| s.expected_parameters.iter().any(|p| p == stations::PARAM_DISCHARGE))
+            .map(|s| s.site_code.clone())
+            .collect();
+        for site_code in &sites {
+            if let Err(e) = self.check_rating_drift(site_code, now) {
+                logging::warn(
+                    logging::DataSource::Usgs,
+                    Some(site_code),
+                    &format!("Failed to check field measurements: {}", e),
+                );
+            }
+        }
+    }
+    
+    /// Once every `isws::REFRESH_HOURS`, read the ISWS/IDNR stage product
+    /// into the shared forecast table
+    fn refresh_isws_forecasts(&mut self, now: DateTime<Utc>) -> Result<(), String> {
+        let Some(settings) = &self.isws else {
+            return Ok(());
+        };
+        if self.isws_refreshed.is_some_and(|t| now - t < Duration::hours(isws::REFRESH_HOURS)) {
+            return Ok(());
+        }
+        self.isws_refreshed = Some(now);
+        
+        let forecasts = isws::fetch(settings)?;
+        if self.dry_run {
+            for f in &forecasts {
+                report_dry_run("flood_analysis.stage_forecasts", OnConflict::DoUpdate, false, &format!(
+                    "isws {} issued {} valid {} {:.2} ft", f.site_code, f.issued_at.to_rfc3339(), f.valid_at.to_rfc3339(), f.stage_ft));
+            }
+            return Ok(());
+        }
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        let written = forecasts::store(client, &forecasts, now)?;
+        logging::debug(logging::DataSource::System, Some("ISWS"), &format!("Stored {} forecast values", written));
+        Ok(())
+    }
+    
+    /// Once every `usgs_stats::REFRESH_HOURS`, fetch and store each
+    /// station's daily statistics
+    fn refresh_daily_stats(&mut self, now: DateTime<Utc>) {
+        if self.stats_refreshed.is_some_and(|t| now - t < Duration::hours(usgs_stats::REFRESH_HOURS)) {
+            return;
+        }
+        self.stats_refreshed = Some(now);
+        
+        let sites: Vec<String> = self.stations.iter()
+            .filter(|s| s.expected_parameters.iter().any(|p| usgs_stats::PARAMETERS.contains(&p.as_str())))
+            .map(|s| s.site_code.clone())
+            .collect();
+        for site_code in &sites {
+            if let Err(e) = self.refresh_site_stats(site_code, now) {
+                logging::warn(
+                    logging::DataSource::Usgs,
+                    Some(site_code),
+                    &format!("Failed to refresh daily statistics: {}", e),
+                );
+            }
+        }
+    }
+    
+    fn refresh_site_stats(&mut self, site_code: &str, now: DateTime<Utc>) -> Result<(), String> {
+        let stats = usgs_stats::fetch(site_code)?;
+        if self.dry_run {
+            report_dry_run("usgs_raw.daily_statistics", OnConflict::DoUpdate, false,
+                &format!("{} {} days", site_code, stats.len()));
+            return Ok(());
+        }
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        let written = usgs_stats::store(client, &stats, now)?;
+        logging::debug(logging::DataSource::Usgs, Some(site_code), &format!("Stored daily statistics for {} days", written));
+        Ok(())
+    }
+    
+    /// Once every `usgs_peaks::REFRESH_HOURS`, fetch and store each
+    /// station's annual peaks
+    fn refresh_annual_peaks(&mut self, now: DateTime<Utc>) {
+        if self.peaks_refreshed.is_some_and(|t| now - t < Duration::hours(usgs_peaks::REFRESH_HOURS)) {
+            return;
+        }
+        self.peaks_refreshed = Some(now);
+        
+        let sites: Vec<String> = self.stations.iter()
+            .filter(|s| s.expected_parameters.iter().any(|p| usgs_stats::PARAMETERS.contains(&p.as_str())))
+            .map(|s| s.site_code.clone())
+            .collect();
+        for site_code in &sites {
+            if let Err(e) = self.refresh_site_peaks(site_code, now) {
+                logging::warn(
+                    logging::DataSource::Usgs,
+                    Some(site_code),
+                    &format!("Failed to refresh annual peaks: {}", e),
+                );
+            }
+        }
+    }
+    
+    fn refresh_site_peaks(&mut self, site_code: &str, now: DateTime<Utc>) -> Result<(), String> {
+        let peaks = usgs_peaks::fetch(site_code)?;
+        if self.dry_run {
+            report_dry_run("usgs_raw.annual_peaks", OnConflict::DoUpdate, false,
+                &format!("{} {} water years", site_code, peaks.len()));
+            return Ok(());
+        }
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        let written = usgs_peaks::store(client, &peaks, now)?;
+        logging::debug(logging::DataSource::Usgs, Some(site_code), &format!("Stored annual peaks for {} water years", written));
+        Ok(())
+    }
+    
+    /// Where `stage_ft` would rank among the station's annual peaks, e.g.
+    /// "stage 24.10 ft would rank 6th of 118 annual peaks (1903-2021),
+    /// highest since 2013"
+    fn peak_rank(&mut self, site_code: &str, stage_ft: f64) -> Option<String> {
+        let client = self.client.as_mut()?;
+        match usgs_peaks::load(client, site_code) {
+            Ok(peaks) => usgs_peaks::rank_stage(&peaks, stage_ft)
+                .map(|rank| format!("stage {:.2} ft {}", stage_ft, rank.describe())),
+            Err(e) => {
+                logging::debug(logging::DataSource::Database, Some(site_code), &e);
+                None
+            }
+        }
+    }
+    
+    /// Return period of the latest discharge in `readings` from the
+    /// station's annual peaks, e.g. "~12-year flow (8.1% annual chance)";
+    /// the discharge itself is in the climatology beside it
+    fn discharge_frequency(&mut self, site_code: &str, readings: &[GaugeReading]) -> Option<String> {
+        let reading = readings.iter()
+            .filter(|r| r.parameter_code == stations::PARAM_DISCHARGE)
+            .max_by_key(|r| r.datetime)?;
+        let client = self.client.as_mut()?;
+        match usgs_peaks::load(client, site_code) {
+            Ok(peaks) => FrequencyCurve::fit(&peaks)
+                .map(|curve| curve.describe(reading.value)),
+            Err(e) => {
+                logging::debug(logging::DataSource::Database, Some(site_code), &e);
+                None
+            }
+        }
+    }
+    
+    /// Where the latest discharge in `readings` falls among the values on
+    /// record for its date, e.g. "discharge 31200 cfs at the 97th
+    /// percentile for May 1 (1940-2023)"
+    fn discharge_climatology(&mut self, site_code: &str, readings: &[GaugeReading]) -> Option<String> {
+        let (reading, time) = readings.iter()
+            .filter(|r| r.parameter_code == stations::PARAM_DISCHARGE)
+            .map(|r| (r, r.datetime.with_timezone(&Utc)))
+            .max_by_key(|(_, t)| *t)?;
+        let (month, day) = usgs_stats::stats_day(time);
+        let client = self.client.as_mut()?;
+        match usgs_stats::load(client, site_code, stations::PARAM_DISCHARGE, month, day) {
+            Ok(stat) => stat?.describe(reading.value)
+                .map(|describe| format!("discharge {:.0} cfs {}", reading.value, describe)),
+            Err(e) => {
+                logging::debug(logging::DataSource::Database, Some(site_code), &e);
+                None
+            }
+        }
+    }
+    
+    /// Once every `rating::REFRESH_HOURS`, fetch each discharge station's
+    /// rating and cache it; a failed fetch keeps the previous table, or the
+    /// cached one after a restart
+    fn refresh_ratings(&mut self, now: DateTime<Utc>) {
+        if self.ratings_refreshed.is_some_and(|t| now - t < Duration::hours(rating::REFRESH_HOURS)) {
+            return;
+        }
+        self.ratings_refreshed = Some(now);
+        
+        let sites: Vec<String> = self.stations.iter()
+            .filter(|s| s.expected_parameters.iter().any(|p| p == stations::PARAM_DISCHARGE))
+            .map(|s| s.site_code.clone())
+            .collect();
+        for site_code in &sites {
+            if let Err(e) = self.refresh_rating(site_code, now) {
+                logging::warn(
+                    logging::DataSource::Usgs,
+                    Some(site_code),
+                    &format!("Failed to refresh rating: {}", e),
+                );
+            }
+        }
+    }
+    
+    fn refresh_rating(&mut self, site_code: &str, now: DateTime<Utc>) -> Result<(), String> {
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        let fetched = match rating::fetch(site_code) {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                if !self.ratings.contains_key(site_code)
+                    && let Some(cached) = rating::load(client, site_code)?
+                {
+                    self.ratings.insert(site_code.to_string(), cached);
+                }
+                return Err(e);
+            }
+        };
+        if self.dry_run {
+            report_dry_run("usgs_raw.rating_tables", OnConflict::DoUpdate, false,
+                &format!("{} rating {} ({} points)", site_code,
+                    fetched.rating_id.as_deref().unwrap_or("?"), fetched.points().len()));
+        } else {
+            rating::store(client, &fetched, now)?;
+        }
+        self.ratings.insert(site_code.to_string(), fetched);
+        Ok(())
+    }
+    
+    fn check_rating_drift(&mut self, site_code: &str, now: DateTime<Utc>) -> Result<(), String> {
+        let measurements = field_measurements::fetch(site_code)?;
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        if self.dry_run {
+            report_dry_run("usgs_raw.field_measurements", OnConflict::DoNothing, false,
+                &format!("{} {} measurements", site_code, measurements.len()));
+        } else {
+            let inserted = field_measurements::store(client, &measurements)?;
+            if inserted > 0 {
+                logging::info(logging::DataSource::Usgs, Some(site_code), &format!("Stored {} new field measurements", inserted));
+            }
+        }
+        
+        let Some(drift) = rating_drift::assess(client, site_code, &measurements, now)? else {
+            return Ok(());
+        };
+        if self.dry_run {
+            report_dry_run("usgs_raw.data_quality_alerts", OnConflict::DoNothing, false,
+                &format!("{} {} {}", site_code, data_quality::KIND_RATING_DRIFT, drift.describe()));
+            return Ok(());
+        }
+        if let Some(id) = data_quality::open_rating_drift(client, &drift, now)? {
+            logging::warn(
+                logging::DataSource::Usgs,
+                Some(site_code),
+                &format!("{}; opened data-quality alert #{} for review", drift.describe(), id),
+            );
+        }
+        Ok(())
+    }
+    
+    /// Log feeds that became overdue or resumed since the last cycle;
+    /// suppressed stations going overdue is expected, logged at DEBUG
+    fn report_overdue(&mut self, now: DateTime<Utc>) {
+        let changes = [
+            (Source::Usgs, self.usgs_cadence.check_all(now)),
+            (Source::Asos, self.asos_cadence.check_all(now)),
+        ];
+        for (source, changes) in changes {
+            let log_source = source.data_source();
+            for change in changes {
+                match change {
+                    StalenessChange::Overdue { .. } if self.is_suppressed(source, change.feed(), now) => {
+                        logging::debug(log_source, Some(change.feed()), &change.describe())
+                    }
+                    StalenessChange::Overdue { .. } => logging::warn(log_source, Some(change.feed()), &change.describe()),
+                    StalenessChange::Resumed { .. } => logging::info(log_source, Some(change.feed()), &change.describe()),
+                }
+            }
+        }
+    }
+    
+    /// Check every monitored station's latest reading against the staleness
+    /// threshold; a station that just went stale is logged and its zone
+    /// subscribers notified (see `alert::stalenesses`)
+    fn report_stale_data(&mut self, now: DateTime<Utc>) {
+        let max_age = self.config.staleness_threshold_minutes;
+        for station in self.stations.clone() {
+            if self.is_suppressed(Source::Usgs, &station.site_code, now) {
+                continue;
+            }
+            let latest = self.latest_readings.get(&station.site_code);
+            let Some(alert) = self.staleness.check(&station.site_code, latest, max_age, now) else {
+                continue;
+            };
+            logging::warn(logging::DataSource::Usgs, Some(&station.site_code), &alert.message());
+            // Stale data matters most at the level the river was last seen at
+            let severity = self.severities.level(&station.site_code).unwrap_or(FloodSeverity::Action);
+            self.notify_subscribers(&station.site_code, alert.to_flood_alert(severity));
+        }
+    }
+    
+    /// Feed a station's latest stage to the adaptive scheduler, logging and
+    /// recording any change to its poll interval
+    fn adapt_poll_interval(&mut self, station: &Station, readings: &[GaugeReading]) -> Result<(), Box<dyn Error>> {
+        let Some((stage, time)) = latest_stage(readings) else {
+            return Ok(());
+        };
+        
+        let Some(decision) = self.adaptive.observe(&station.site_code, stage, time, station.thresholds.as_ref(), Utc::now())
+        else {
+            return Ok(());
+        };
+        self.record_polling_decision(&decision)
+    }
+    
+    fn record_polling_decision(&mut self, decision: &PollingDecision) -> Result<(), Box<dyn Error>> {
+        let effective = self.adaptive.effective_interval();
+        logging::info(
+            logging::DataSource::System,
+            Some(&decision.site_code),
+            &format!("Poll interval {}; daemon now polling every {} min", decision.reason(), effective),
+        );
+        
+        if self.dry_run {
+            report_dry_run("polling_decisions", OnConflict::DoNothing, false,
+                &format!("{} {}", decision.site_code, decision.reason()));
+            return Ok(());
+        }
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        adaptive::record(client, decision, effective)?;
+        Ok(())
+    }
+    
+    /// Compare a station's latest stage severity with the previous cycle's;
+    /// on a change, log it and capture webcam snapshots (see `cams`).
+    /// Without a stage reading, stage is estimated from discharge through
+    /// the station's rating (see `ingest::rating`). Readings of suppressed
+    /// sensors are left out (see `monitor::suppression`).
+    fn track_severity(&mut self, station: &Station, readings: &[GaugeReading]) {
+        if station.thresholds.is_none() && station.pool_deviation.is_none() {
+            return;
+        }
+        let now = Utc::now();
+        let unsuppressed: Vec<GaugeReading> = readings.iter()
+            .filter(|r| suppression::find(&self.suppressions, Source::Usgs, &station.site_code, Some(&r.parameter_code), now).is_none())
+            .cloned()
+            .collect();
+        let readings = unsuppressed.as_slice();
+        // Estimated and ice-affected stage neither raises nor clears an
+        // alert; a stage estimated here from a measured discharge does
+        let alertable = quality::alertable(readings);
+        if alertable.len() < readings.len() {
+            logging::debug(
+                logging::DataSource::Usgs,
+                Some(&station.site_code),
+                &format!("{} reading(s) not alertable by quality, ignored for severity", readings.len() - alertable.len()),
+            );
+        }
+        let estimated = self.ratings.get(&station.site_code)
+            .and_then(|r| rating::estimate_stage(r, &alertable));
+        let Some((reading, time)) = alertable.iter()
+            .chain(estimated.as_ref())
+            .filter(|r| r.parameter_code == "00065")
+            .map(|r| (r, r.datetime.with_timezone(&Utc)))
+            .max_by_key(|(_, t)| *t)
+        else {
+            return;
+        };
+        let alert = thresholds::check_station(station, reading).map(|mut alert| {
+            if estimated.is_some() {
+                alert.message.push_str(" (stage estimated from discharge via rating)");
+            }
+            alert
+        });
+        let severity = alert.as_ref().map(|alert| alert.severity.clone());
+        let Some(transition) = self.severities.update(&station.site_code, severity, reading.value, time) else {
+            return;
+        };
+        self.publish(&[StreamEvent::transition(&transition)]);
+        
+        let links = self.capture_snapshots(&transition);
+        let message = format!(
+            "Severity {} at {:.2} ft{}{}",
+            transition.describe(),
+            transition.stage_ft,
+            transition.crest.as_ref().map(|crest| format!(", crest {:.2} ft", crest.stage_ft)).unwrap_or_default(),
+            if links.is_empty() { String::new() } else { format!("; snapshots: {}", links.join(" ")) },
+        );
+        if transition.is_escalation() {
+            logging::warn(logging::DataSource::Usgs, Some(&station.site_code), &message);
+        } else {
+            logging::info(logging::DataSource::Usgs, Some(&station.site_code), &message);
+        }
+        
+        if let Some(mut alert) = alert.filter(|_| transition.is_escalation()) {
+            if let Some(climatology) = self.discharge_climatology(&station.site_code, readings) {
+                alert.message.push_str(&format!("; {}", climatology));
+            }
+            if let Some(frequency) = self.discharge_frequency(&station.site_code, readings) {
+                alert.message.push_str(&format!("; {}", frequency));
+            }
+            if let Some(rank) = self.peak_rank(&station.site_code, transition.stage_ft) {
+                alert.message.push_str(&format!("; {}", rank));
+            }
+            self.notify_subscribers(&station.site_code, alert);
+        } else if let (Some(recession), Some(from)) = (transition.recession_message(), transition.from.clone()) {
+            // At the level being left, so whoever heard about the rise to
+            // it hears about the way down
+            let prefix = if transition.is_all_clear() { "All clear" } else { "Receding" };
+            self.notify_subscribers(&station.site_code, FloodAlert {
+                severity: from,
+                message: format!("{}: {} {}", prefix, station.name, recession),
+                registry_version: None,
+            });
+        }
+    }
+    
+    /// Announce new readings or a transition to the API servers' live
+    /// streams (see `stream`); a dry run announces nothing
+    fn publish(&mut self, events: &[StreamEvent]) {
+        if self.dry_run {
+            return;
+        }
+        if let Some(client) = self.client.as_mut()
+            && let Err(e) = stream::publish(client, events) {
+            logging::debug(logging::DataSource::Database, None, &e);
+        }
+    }
+    
+    /// Queue an escalation for everyone subscribed to a zone the station
+    /// is in, at or above their severity floor
+    fn notify_subscribers(&mut self, site_code: &str, alert: FloodAlert) {
+        let Some(zones) = &self.zones else {
+            return;
+        };
+        let zone_ids = zones::zones_for_site(zones, site_code);
+        self.notify_zones(&zone_ids, alert);
+    }
+    
+    /// Queue `alert` once for each subscriber of any of `zone_ids`
+    fn notify_zones(&mut self, zone_ids: &[usize], alert: FloodAlert) {
+        // Zones through which each recipient receives this alert
+        let mut recipients: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for &zone_id in zone_ids {
+            for recipient in subscriptions::recipients_for(&self.subscriptions, zone_id, &alert.severity) {
+                recipients.entry(recipient).or_default().push(zone_id);
+            }
+        }
+        if recipients.is_empty() {
+            return;
+        }
+        let flags = self.occupancy_flags();
+        let local = Utc::now().with_timezone(&chrono::Local).naive_local();
+        for (recipient, zone_ids) in &recipients {
+            let urgency = match (&self.occupancy, &flags) {
+                (Some(settings), Some(flags)) => settings.urgency(zone_ids, flags, local),
+                _ => Urgency::Normal,
+            };
+            self.notify_with_urgency(recipient, alert.clone(), urgency);
+        }
+    }
+    
+    /// Evaluate the composite alert rules on this cycle's stages, rises,
+    /// severities and basin precipitation. Active firings go to
+    /// subscribers; shadow firings are only logged. Both are counted.
+    fn evaluate_rules(&mut self, latest_stages: &HashMap<String, (f64, DateTime<Utc>)>, now: DateTime<Utc>) {
+        if self.rules.is_empty() {
+            return;
+        }
+        let inputs = RuleInputs {
+            stages: latest_stages.iter().map(|(site, (stage, _))| (site.clone(), *stage)).collect(),
+            rapid_rise: self.adaptive.sites_with(adaptive::Trigger::RapidRise),
+            severities: self.stations.iter()
+                .filter_map(|s| self.severities.level(&s.site_code).map(|level| (s.site_code.clone(), level)))
+                .collect(),
+            basin_precip: self.basin_precip.clone(),
+        };
+        
+        for evaluation in self.rules.evaluate(&inputs) {
+            if let Some(alert) = &evaluation.fired {
+                let line = format!("Rule {} ({}) fired: {} {}",
+                    evaluation.rule, evaluation.mode.as_str(), alert.severity.as_str(), alert.message);
+                match evaluation.mode {
+                    RuleMode::Active => {
+                        logging::warn(logging::DataSource::System, Some(&evaluation.site), &line);
+                        self.notify_subscribers(&evaluation.site, alert.clone());
+                    }
+                    RuleMode::Shadow => logging::info(logging::DataSource::System, Some(&evaluation.site), &line),
+                }
+            }
+            if self.dry_run {
+                if evaluation.fired.is_some() {
+                    report_dry_run("nws.alert_rule_firings", OnConflict::DoNothing, false, &evaluation.rule);
+                }
+                continue;
+            }
+            if let Some(client) = self.client.as_mut()
+                && let Err(e) = rules::record(client, &evaluation, now) {
+                logging::warn(logging::DataSource::System, None, &e);
+            }
+        }
+    }
+    
+    /// Stored occupancy flags, or `None` (full urgency everywhere) when
+    /// occupancy isn't configured or the flags can't be read
+    fn occupancy_flags(&mut self) -> Option<HashMap<usize, bool>> {
+        self.occupancy.as_ref()?;
+        let client = self.client.as_mut()?;
+        match occupancy::load_flags(client) {
+            Ok(flags) => Some(occupancy::flag_map(&flags)),
+            Err(e) => {
+                logging::warn(logging::DataSource::Database, None, &format!("{}; alerting at full urgency", e));
+                None
+            }
+        }
+    }
+    
+    /// Fetch and store every camera watching the transitioning station.
+    ///
+    /// Returns links to the stored images. Camera failures are logged and
+    /// skipped.
+    fn capture_snapshots(&mut self, transition: &Transition) -> Vec<String> {
+        let Some(settings) = &self.cams else {
+            return Vec::new();
+        };
+        let cameras = settings.cameras_for(&transition.site_code);
+        if cameras.is_empty() {
+            return Vec::new();
+        }
+        let warn = |e: &str| logging::warn(logging::DataSource::System, Some(&transition.site_code), e);
+        let http = match reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(settings.timeout_secs))
+            .build()
+        {
+            Ok(http) => http,
+            Err(e) => {
+                warn(&format!("Failed to build camera HTTP client: {}", e));
+                return Vec::new();
+            }
+        };
+        
+        let mut links = Vec::new();
+        for camera in cameras {
+            let snapshot = match cams::fetch(&http, camera, settings.max_bytes, Utc::now()) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    warn(&e);
+                    continue;
+                }
+            };
+            if self.dry_run {
+                report_dry_run("flood_analysis.cam_snapshots", OnConflict::DoNothing, false, &format!(
+                    "{} {} {} {} bytes ({})", camera.name, transition.site_code,
+                    snapshot.content_type, snapshot.image.len(), transition.describe()));
+                continue;
+            }
+            let Some(client) = self.client.as_mut() else {
+                warn("Daemon not initialized; snapshot discarded");
+                break;
+            };
+            match cams::store(client, &snapshot, transition) {
+                Ok(id) => links.push(cams::snapshot_path(id)),
+                Err(e) => warn(&e),
+            }
+        }
+        links
+    }
+    
+    /// Persist the Peoria-Kingston Mines water-surface slope and its trend.
+    ///
+    /// Skipped (Ok(0)) when either gauge has no stage this cycle or no
+    /// `gauge_datum_ft` in the registry.
+    fn warehouse_stage_slope(&mut self, latest_stages: &HashMap<String, (f64, DateTime<Utc>)>) -> Result<usize, Box<dyn Error>> {
+        let elevation = |site: &str| -> Option<(slope::GaugeElevation, f64)> {
+            let station = self.stations.iter().find(|s| s.site_code == site)?;
+            let (stage_ft, reading_time) = *latest_stages.get(site)?;
+            let (datum_ft, datum) = station.site_datum()?.common_zero()?;
+            Some((slope::GaugeElevation {
+                site_code: site.to_string(),
+                stage_ft,
+                datum_ft,
+                datum: datum.as_str().to_string(),
+                reading_time,
+            }, station.distance_from_peoria_miles))
+        };
+        let (Some((upstream, up_miles)), Some((downstream, down_miles))) =
+            (elevation(slope::UPSTREAM_SITE), elevation(slope::DOWNSTREAM_SITE))
+        else {
+            return Ok(0);
+        };
+        let sample = slope::stage_slope(&upstream, &downstream, (down_miles - up_miles).abs())?;
+        
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        let mut history: Vec<(DateTime<Utc>, f64)> = client.query(
+            "SELECT timestamp, slope_ft_per_mile FROM flood_analysis.stage_slope
+             WHERE timestamp >= $1 AND timestamp < $2",
+            &[&(sample.timestamp - Duration::hours(slope::TREND_WINDOW_HOURS)), &sample.timestamp],
+        )?.iter().map(|row| (row.get(0), row.get(1))).collect();
+        history.push((sample.timestamp, sample.slope_ft_per_mile));
+        let trend = slope::slope_trend(&history);
+        
+        let row = format!("{} {:.4} ft/mi {}", sample.timestamp.to_rfc3339(), sample.slope_ft_per_mile,
+            trend.map(|(t, _)| t.as_str()).unwrap_or("-"));
+        if self.dry_run {
+            let exists: bool = client.query_one(
+                "SELECT EXISTS(SELECT 1 FROM flood_analysis.stage_slope WHERE timestamp = $1)",
+                &[&sample.timestamp],
+            )?.get(0);
+            return Ok(report_dry_run("flood_analysis.stage_slope", OnConflict::DoNothing, exists, &row));
+        }
+        
+        let inserted = client.execute(
+            "INSERT INTO flood_analysis.stage_slope
+             (timestamp, upstream_site, downstream_site, upstream_elevation_ft, downstream_elevation_ft,
+              datum, reach_miles, slope_ft_per_mile, trend, trend_ft_per_mile_per_hour)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             ON CONFLICT (timestamp) DO NOTHING",
+            &[
+                &sample.timestamp,
+                &slope::UPSTREAM_SITE,
+                &slope::DOWNSTREAM_SITE,
+                &sample.upstream_elevation_ft,
+                &sample.downstream_elevation_ft,
+                &sample.datum,
+                &sample.reach_miles,
+                &sample.slope_ft_per_mile,
+                &trend.map(|(t, _)| t.as_str()),
+                &trend.map(|(_, rate)| rate),
+            ],
+        )?;
+        if inserted > 0 && trend.is_some_and(|(t, _)| t == slope::SlopeTrend::Flattening) {
+            logging::info(logging::DataSource::Usgs, Some(slope::UPSTREAM_SITE), &format!("Reach slope flattening: {}", row));
+        }
+        Ok(inserted as usize)
+    }
+    
+    /// Whether polling is tightened: some station is at or above action
+    /// stage, or the event mode calls for elevated polling
+    fn polling_tightened(&self) -> bool {
+        !self.adaptive.sites_with(adaptive::Trigger::ActionStage).is_empty()
+            || self.event_mode.mode().elevated_polling()
+    }
+    
+    /// Schedule the feeds polled in the cycle that started at `cycle_start`
+    /// at their own intervals, and bring every other feed forward to its
+    /// (possibly just tightened) interval (see `monitor::schedule`)
+    fn schedule_next_polls(&mut self, cycle_start: DateTime<Utc>, polled_feeds: &[(Source, String)]) {
+        let tightened = self.polling_tightened();
+        let base = self.config.poll_interval_minutes;
+        let mut intervals: Vec<(Source, String, u64)> = Vec::new();
+        for station in &self.stations {
+            let adaptive = self.adaptive.interval_for(&station.site_code);
+            let interval = if tightened { adaptive.min(self.config.elevated_poll_interval_minutes) } else { adaptive };
+            intervals.push((Source::Usgs, station.site_code.clone(), interval));
+        }
+        for location in &self.cwms_locations {
+            let priority = usace_locations::poll_interval_minutes(location.priority);
+            intervals.push((Source::Cwms, location.name.clone(), schedule::priority_interval(priority, base, tightened)));
+        }
+        for location in &self.asos_locations {
+            let priority = location.priority.poll_interval_minutes() as u64;
+            intervals.push((Source::Asos, location.station_id.clone(), schedule::priority_interval(priority, base, tightened)));
+        }
+        
+        for (source, id, interval) in intervals {
+            if polled_feeds.iter().any(|(s, polled)| *s == source && *polled == id) {
+                self.schedule.polled(source, &id, cycle_start, interval);
+            } else {
+                self.schedule.advance(source, &id, cycle_start + Duration::minutes(interval as i64));
+            }
+        }
+    }
+    
+    /// Poll interval currently in effect (the shortest any station needs)
+    pub fn effective_poll_interval_minutes(&self) -> u64 {
+        let adaptive = self.adaptive.effective_interval();
+        if self.event_mode.mode().elevated_polling() {
+            adaptive.min(self.config.elevated_poll_interval_minutes)
+        } else {
+            adaptive
+        }
+    }
+    
+    /// Move the event mode on from this cycle's signals (see
+    /// `monitor::event_mode`), applying and recording any change
+    fn update_event_mode(&mut self, now: DateTime<Utc>) {
+        let (highest_site, highest_severity) = self.severities.highest()
+            .map(|(site, severity)| (site.to_string(), severity))
+            .unzip();
+        let signals = Signals {
+            highest_site,
+            highest_severity,
+            rapid_rise_sites: self.adaptive.sites_with(adaptive::Trigger::RapidRise),
+            ..Signals::default()
+        };
+        let Some(change) = self.event_mode.update(&signals, now) else {
+            return;
+        };
+        let sigma = change.to.overdue_sigma();
+        self.usgs_cadence.set_overdue_sigma(sigma);
+        self.asos_cadence.set_overdue_sigma(sigma);
+        
+        if matches!(change.to, event_mode::Mode::Watch | event_mode::Mode::Event) {
+            logging::warn(logging::DataSource::System, None, &change.describe());
+        } else {
+            logging::info(logging::DataSource::System, None, &change.describe());
+        }
+        if self.dry_run {
+            report_dry_run("usgs_raw.event_mode_history", OnConflict::DoNothing, false, &change.describe());
+            return;
+        }
+        if let Some(client) = self.client.as_mut()
+            && let Err(e) = event_mode::record(client, &change) {
+            logging::warn(logging::DataSource::System, None, &e);
+        }
+    }
+    
+    /// Current system-wide event mode
+    pub fn event_mode(&self) -> event_mode::Mode {
+        self.event_mode.mode()
+    }
+    
+    /// Whether this instance delivers notifications (see `alert::leader`)
+    pub fn dispatch_role(&self) -> Role {
+        if self.dry_run { Role::Leader } else { self.dispatch.role() }
+    }
+    
+    /// Confirm or take the dispatch lock; on becoming leader, load the
+    /// notifications a previous leader persisted
+    fn refresh_dispatch_role(&mut self) {
+        let Some(client) = self.client.as_mut() else {
+            return;
+        };
+        match self.dispatch.refresh(client) {
+            Ok(Some(Role::Leader)) => {
+                logging::info(logging::DataSource::System, None, "Holding the notification dispatch lock: delivering alerts");
+                match self.notifications.restore(client) {
+                    Ok(0) => {}
+                    Ok(count) => logging::info(logging::DataSource::System, None,
+                        &format!("📨 Restored {} undelivered notifications from last shutdown", count)),
+                    Err(e) => logging::warn(logging::DataSource::System, None, &e),
+                }
+            }
+            Ok(Some(Role::Standby)) => logging::warn(
+                logging::DataSource::System,
+                None,
+                "Lost the notification dispatch lock (database session reset): now standby",
+            ),
+            Ok(None) => {}
+            Err(e) => logging::warn(logging::DataSource::System, None, &e),
+        }
+    }
+    
+    /// Attempt every queued notification once; a standby drops its queue
+    pub fn deliver_notifications(&mut self) -> DrainReport {
+        if !self.dry_run {
+            self.refresh_dispatch_role();
+        }
+        if self.dispatch_role() == Role::Standby {
+            let dropped = self.notifications.discard();
+            if dropped > 0 {
+                logging::debug(logging::DataSource::System, None,
+                    &format!("Standby: left {} notifications to the dispatch leader", dropped));
+            }
+            return DrainReport::default();
+        }
+        self.notifications.deliver_pending(self.deliver.as_mut(), None, Utc::now())
+    }
+    
+    /// Drain queued notifications within `shutdown_drain_seconds`, then
+    /// persist whatever is left for the next start (a standby has nothing
+    /// to drain)
+    pub fn shutdown(&mut self) -> Result<DrainReport, Box<dyn Error>> {
+        if self.dispatch_role() == Role::Standby {
+            self.notifications.discard();
+            return Ok(DrainReport::default());
+        }
+        let deadline = std::time::Instant::now()
+            + std::time::Duration::from_secs(self.config.shutdown_drain_seconds);
+        let report = self.notifications.deliver_pending(self.deliver.as_mut(), Some(deadline), Utc::now());
+        
+        if report.remaining > 0 {
+            if self.dry_run {
+                println!("[dry-run] pending_notifications: would persist {} undelivered", report.remaining);
+            } else {
+                let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+                let persisted = self.notifications.persist(client)?;
+                logging::warn(
+                    logging::DataSource::System,
+                    None,
+                    &format!("Persisted {} undelivered notifications for redelivery at next start", persisted),
+                );
+            }
+        }
+        Ok(report)
+    }
+    
+    /// Write this daemon's heartbeat and, on the dispatch leader, report
+    /// changes in standby readiness (see `monitor::failover`)
+    fn check_failover_readiness(&mut self) -> Result<(), String> {
+        let Some(settings) = self.failover.clone() else {
+            return Ok(());
+        };
+        if self.dry_run {
+            return Ok(());
+        }
+        let role = self.dispatch_role();
+        let client = self.client.as_mut().ok_or("Daemon not initialized")?;
+        let instance = settings.instance_name();
+        failover::write_heartbeat(client, &instance, role, self.registry_version)?;
+        if role != Role::Leader {
+            return Ok(());
+        }
+        
+        let heartbeats = failover::load_heartbeats(client)?;
+        let replicas = failover::load_replicas(client)?;
+        let issues = failover::assess(&settings, &instance, &heartbeats, replicas.as_deref(), Utc::now());
+        let (opened, cleared) = self.readiness.update(&issues);
+        for issue in &opened {
+            logging::error(logging::DataSource::System, None, &format!("Failover readiness degraded: {}", issue.describe()));
+        }
+        for issue in &cleared {
+            logging::info(logging::DataSource::System, None, &format!("Failover readiness restored: {}", issue.describe()));
+        }
+        Ok(())
+    }
+    
+    /// Main daemon loop (runs until a shutdown is requested)
+    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        logging::info(logging::DataSource::System, None, &format!(
+            "🚀 Starting daemon loop: poll every {} minutes ({} while a station is elevated), CWMS and ASOS by priority",
+            self.config.poll_interval_minutes, self.config.elevated_poll_interval_minutes));
+        logging::info(logging::DataSource::System, None, &format!(
+            "Monitoring {} USGS stations + {} CWMS locations + {} ASOS stations",
+            self.stations.len(), self.cwms_locations.len(), self.asos_locations.len()));
+        
+        loop {
+            let start = Utc::now();
+            
+            self.reload_registry();
+            self.refresh_threshold_overrides();
+            
+            match self.poll_all_stations() {
+                Ok(results) => {
+                    let total: usize = results.values().sum();
+                    let usgs_count = results.iter().filter(|(k, _)| k.starts_with("USGS:")).count();
+                    let cwms_count = results.iter().filter(|(k, _)| k.starts_with("CWMS:")).count();
+                    let asos_count = results.iter().filter(|(k, _)| k.starts_with("ASOS:")).count();
+                    logging::info(logging::DataSource::System, None, &format!(
+                        "✓ Poll complete: {} new readings ({} USGS, {} CWMS, {} ASOS)",
+                        total, usgs_count, cwms_count, asos_count));
+                }
+                Err(e) => {
+                    logging::error(logging::DataSource::System, None, &format!("Poll error: {}", e));
+                }
+            }
+            
+            if let Err(e) = self.refresh_status_snapshot(Utc::now()) {
+                logging::warn(logging::DataSource::System, None, &e);
+            }
+            
+            let delivery = self.deliver_notifications();
+            if delivery.failed > 0 {
+                logging::warn(logging::DataSource::System, None,
+                &format!("{} notifications failed delivery ({} queued)", delivery.failed, delivery.remaining));
+            }
+            
+            if let Err(e) = self.check_failover_readiness() {
+                logging::warn(logging::DataSource::System, None, &e);
+            }
+            
+            // Sleep until the next feed is due (see `monitor::schedule`)
+            let sleep_seconds = match self.schedule.next_due() {
+                Some(next) => (next - Utc::now()).num_seconds(),
+                None => (self.effective_poll_interval_minutes() * 60) as i64 - (Utc::now() - start).num_seconds(),
+            };
+            
+            if shutdown::requested()
+                || (sleep_seconds > 0 && !shutdown::sleep(std::time::Duration::from_secs(sleep_seconds as u64)))
+            {
+                break;
+            }
+        }
+        
+        logging::info(logging::DataSource::System, None, &format!(
+            "🛑 Shutdown requested, draining {} queued notifications (up to {}s)...",
+            self.notifications.len(), self.config.shutdown_drain_seconds));
+        let report = self.shutdown()?;
+        logging::info(logging::DataSource::System, None,
+            &format!("✓ Delivered {}, {} left for next start", report.delivered, report.remaining));
+        Ok(())
+    }
+
+    /// A single poll cycle followed by the same shutdown as `run`
+    /// (`flomon ingest --once`): alerts the cycle queued are delivered, or
+    /// persisted for the next start, even when the poll itself failed
+    pub fn run_once(&mut self) -> Result<HashMap<String, usize>, Box<dyn Error>> {
+        let polled = self.poll_all_stations();
+
+        let delivery = self.deliver_notifications();
+        if delivery.failed > 0 {
+            logging::warn(logging::DataSource::System, None,
+                &format!("{} notifications failed delivery ({} queued)", delivery.failed, delivery.remaining));
+        }
+        let report = self.shutdown()?;
+        if report.remaining > 0 {
+            logging::info(logging::DataSource::System, None, &format!(
+                "✓ Delivered {}, {} left for next start", delivery.delivered + report.delivered, report.remaining));
+        }
+        polled
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Dry run
+// ---------------------------------------------------------------------------
+
+/// How an `INSERT ... ON CONFLICT` treats a row whose key already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OnConflict {
+    DoNothing,
+    DoUpdate,
+}
+
+/// Predicted effect of a skipped write
+fn dry_run_action(on_conflict: OnConflict, exists: bool) -> &'static str {
+    match (exists, on_conflict) {
+        (false, _) => "insert",
+        (true, OnConflict::DoNothing) => "skip",
+        (true, OnConflict::DoUpdate) => "update",
+    }
+}
+
+/// Print a write that dry-run mode skipped.
+///
+/// Returns 1 if the write would have changed a row, 0 if `ON CONFLICT`
+/// would have discarded it, so callers can total would-be inserts.
+fn report_dry_run(table: &str, on_conflict: OnConflict, exists: bool, row: &str) -> usize {
+    let action = dry_run_action(on_conflict, exists);
+    println!("   [dry-run] {:<6} {} {}", action, table, row);
+    usize::from(action != "skip")
+}
+
+/// Dry-run report for a `usgs_raw.monitoring_state` upsert
+fn report_monitoring_state_dry_run(client: &mut Client, site_code: &str, row: &str) -> Result<(), Box<dyn Error>> {
+    let exists: bool = client.query_one(
+        "SELECT EXISTS(SELECT 1 FROM usgs_raw.monitoring_state WHERE site_code = $1)",
+        &[&site_code]
+    )?.get(0);
+    report_dry_run("usgs_raw.monitoring_state", OnConflict::DoUpdate, exists, row);
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Notifications
+// ---------------------------------------------------------------------------
+
+/// Default channel until one is configured: record the alert in the log
+/// `registry` with threshold overrides applied, logging each one ignored
+fn apply_threshold_overrides(registry: &[Station], overrides: &[ThresholdOverride]) -> Vec<Station> {
+    let (stations, rejected) = threshold_overrides::apply(registry, overrides);
+    for reason in rejected {
+        logging::warn(logging::DataSource::Usgs, None, &reason);
+    }
+    stations
+}
+
+/// Record `stations` as the current registry version (just read it in a
+/// dry run), logging the field changes of a new version
+fn record_registry(client: &mut Client, stations: &[Station], changed_by: &str, dry_run: bool) -> Result<Option<i32>, Box<dyn Error>> {
+    if dry_run {
+        return Ok(registry::current_version(client)?);
+    }
+    let recorded = registry::record_version(client, stations, changed_by)?;
+    if recorded.created {
+        logging::info(logging::DataSource::Usgs, None,
+            &format!("📝 Station registry v{} recorded ({} field changes)", recorded.version, recorded.changes.len()));
+        for change in &recorded.changes {
+            logging::info(
+                logging::DataSource::Usgs,
+                Some(&change.site_code),
+                &format!(
+                    "Registry v{}: {} {} -> {}",
+                    recorded.version,
+                    change.field,
+                    change.old_value.as_deref().unwrap_or("(none)"),
+                    change.new_value.as_deref().unwrap_or("(none)"),
+                ),
+            );
+        }
+    }
+    Ok(Some(recorded.version))
+}
+
+fn log_delivery(notification: &queue::Notification) -> Result<(), String> {
+    logging::warn(
+        logging::DataSource::System,
+        None,
+        &format!(
+            "ALERT [{}] to {}: {}",
+            notification.alert.severity.as_str(),
+            notification.recipient,
+            notification.alert.message,
+        ),
+    );
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Reading timestamps
+// ---------------------------------------------------------------------------
+
+/// Latest stage (00065) value and time among `readings`
+fn latest_stage(readings: &[GaugeReading]) -> Option<(f64, DateTime<Utc>)> {
+    readings.iter()
+        .filter(|r| r.parameter_code == "00065")
+        .map(|r| (r.value, r.datetime.with_timezone(&Utc)))
+        .max_by_key(|(_, t)| *t)
+}
+
+/// Stream events for the newest reading of each parameter in `readings`
+fn latest_per_parameter(readings: &[GaugeReading]) -> Vec<StreamEvent> {
+    let mut latest: BTreeMap<&str, (DateTime<Utc>, &GaugeReading)> = BTreeMap::new();
+    for reading in readings {
+        let time = reading.datetime.with_timezone(&Utc);
+        if latest.get(reading.parameter_code.as_str()).is_none_or(|(t, _)| time > *t) {
+            latest.insert(&reading.parameter_code, (time, reading));
+        }
+    }
+    latest.values().map(|(_, reading)| StreamEvent::reading(reading)).collect()
+}
+
+/// Latest stage at `site_code` and its rise since `since` (see `outlook::trend`)
+fn stage_trend(client: &mut Client, site_code: &str, since: DateTime<Utc>, now: DateTime<Utc>) -> Result<Option<(f64, f64)>, String> {
+    let readings: Vec<(DateTime<Utc>, f64)> = archive::readings_between(client, site_code, PARAM_STAGE, since, now)?
+        .iter()
+        .map(|r| (r.datetime.with_timezone(&Utc), r.value))
+        .collect();
+    Ok(outlook::trend(&readings))
+}
+
+/// Newest usable value of `parameter_id` at a CWMS location matching
+/// `location` (a LIKE pattern) since `since`
+fn latest_cwms(client: &mut Client, location: &str, parameter_id: &str, since: DateTime<Utc>) -> Result<Option<f64>, String> {
+    let row = client.query_opt(
+        &format!(
+            "SELECT value::DOUBLE PRECISION FROM usace.cwms_timeseries
+             WHERE location_id LIKE $1 AND parameter_id = $2 AND timestamp >= $3 AND {}
+             ORDER BY timestamp DESC LIMIT 1",
+            cwms_quality::USABLE_SQL,
+        ),
+        &[&location, &parameter_id, &since],
+    ).map_err(|e| format!("CWMS {} query for {} failed: {}", parameter_id, location, e))?;
+    Ok(row.map(|row| row.get(0)))
+}
+
+// ---------------------------------------------------------------------------
+// USGS format fallback
+// ---------------------------------------------------------------------------
+
+/// Parser for one rendering (JSON or RDB) of a USGS IV/DV response
+type UsgsParser = fn(&str) -> Result<Vec<GaugeReading>, IngestError>;
+
+/// Fetch a USGS request as JSON, falling back to the RDB rendering of the
+/// same request when the JSON service is degraded.
+///
+/// Each format is retried on transient failures first (see `ingest::retry`).
+/// Falls back on transport errors, non-2xx responses and JSON parse errors.
+/// `NoDataAvailable` is a real answer (RDB would say the same), so it is
+/// returned as-is. If both formats fail, the JSON error is returned.
+fn fetch_usgs_with_fallback(
+    site_code: &str,
+    (json_url, parse_json): (&str, UsgsParser),
+    (rdb_url, parse_rdb): (&str, UsgsParser),
+    timeout_secs: u64,
+) -> Result<Vec<GaugeReading>, Box<dyn Error>> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(timeout_secs))
+        .build()?;
+    
+    let fetch = |url: &str, parse: UsgsParser| -> Result<Vec<GaugeReading>, Box<dyn Error>> {
+        let body = retry::get_text_blocking(&client, logging::DataSource::Usgs, url, false)?;
+        Ok(parse(&body)?)
+    };
+    
+    let json_err = match fetch(json_url, parse_json) {
+        Ok(readings) => return Ok(readings),
+        Err(e) if matches!(e.downcast_ref::<IngestError>(), Some(IngestError::NoData(_))) => {
+            return Err(e);
+        }
+        Err(e) => e,
+    };
+    
+    logging::warn(
+        logging::DataSource::Usgs,
+        Some(site_code),
+        &format!("JSON request failed ({}), retrying as RDB", json_err),
+    );
+    
+    match fetch(rdb_url, parse_rdb) {
+        Ok(readings) => Ok(readings),
+        Err(rdb_err) => {
+            logging::warn(
+                logging::DataSource::Usgs,
+                Some(site_code),
+                &format!("RDB fallback also failed: {}", rdb_err),
+            );
+            Err(json_err)
+        }
+    }
+}
+
+/// Every discharge and stage reading of one backfill chunk; a chunk USGS
+/// has nothing for (gauge not yet installed, outage) is empty, not an error
+fn fetch_backfill_chunk(chunk: &backfill::Chunk) -> Result<Vec<GaugeReading>, Box<dyn Error>> {
+    let params = ["00060", "00065"]; // Discharge and stage
+    let sites = [chunk.site_code.as_str()];
+    let (start, end) = (chunk.start.to_string(), chunk.end.to_string());
+    let fetched = match chunk.kind {
+        backfill::ChunkKind::Daily => fetch_usgs_with_fallback(
+            &chunk.site_code,
+            (&usgs::build_dv_url(&sites, &params, &start, &end), usgs::parse_dv_response),
+            (&usgs::build_dv_rdb_url(&sites, &params, &start, &end), usgs_rdb::parse_dv_rdb),
+            60,
+        ),
+        backfill::ChunkKind::Instantaneous => fetch_usgs_with_fallback(
+            &chunk.site_code,
+            (&usgs::build_iv_range_url(&sites, &params, &start, &end), usgs::parse_iv_response_all),
+            (&usgs::build_iv_range_rdb_url(&sites, &params, &start, &end), usgs_rdb::parse_iv_rdb_all),
+            60,
+        ),
+    };
+    match fetched {
+        Err(e) if matches!(e.downcast_ref::<IngestError>(), Some(IngestError::NoData(_))) => Ok(Vec::new()),
+        other => other,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_daemon_creation() {
+        let daemon = Daemon::new();
+        assert_eq!(daemon.config.poll_interval_minutes, 15);
+        assert_eq!(daemon.config.staleness_threshold_minutes, 60);
         assert_eq!(daemon.config.backfill_days, 120);
     }
     
@@ -920,8 +3577,11 @@ mod tests {
     fn test_custom_daemon_config() {
         let config = DaemonConfig {
             poll_interval_minutes: 5,
+            elevated_poll_interval_minutes: 2,
             staleness_threshold_minutes: 30,
             backfill_days: 30,
+            shutdown_drain_seconds: 5,
+            ..DaemonConfig::default()
         };
         
         let daemon = Daemon::with_config(config);
@@ -939,6 +3599,63 @@ mod tests {
         assert!(result.is_err(), "Should fail before initialization");
     }
     
+    #[test]
+    fn test_dry_run_conflict_prediction() {
+        assert_eq!(dry_run_action(OnConflict::DoNothing, false), "insert");
+        assert_eq!(dry_run_action(OnConflict::DoNothing, true), "skip");
+        assert_eq!(dry_run_action(OnConflict::DoUpdate, true), "update");
+        assert_eq!(report_dry_run("usgs_raw.gauge_readings", OnConflict::DoNothing, true, "05567500"), 0);
+        assert_eq!(report_dry_run("asos_precip_rollups", OnConflict::DoUpdate, true, "KPIA 24h"), 1);
+    }
+    
+    #[test]
+    fn test_dry_run_defaults_off() {
+        let mut daemon = Daemon::new();
+        assert!(!daemon.is_dry_run());
+        daemon.set_dry_run(true);
+        assert!(daemon.is_dry_run());
+    }
+    
+    #[test]
+    fn test_source_restriction_drops_other_registries() {
+        let mut daemon = Daemon::new();
+        daemon.stations = stations::load_stations();
+        daemon.asos_locations = asos_locations::load_locations("iem_asos.toml").unwrap();
+        daemon.set_source(Some(Source::Asos));
+        assert!(daemon.stations.is_empty());
+        assert!(!daemon.asos_locations.is_empty());
+        
+        // Restriction holds through a registry reload
+        daemon.stations = stations::load_stations();
+        daemon.restrict_to_source();
+        assert!(daemon.stations.is_empty());
+    }
+    
+    #[test]
+    fn test_run_once_delivers_queued_notifications() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        
+        // Dry run stands in for holding the dispatch lock without a database
+        let mut daemon = Daemon::new();
+        daemon.set_dry_run(true);
+        let delivered = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&delivered);
+        daemon.set_deliver(Box::new(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }));
+        daemon.notify("ops@example.org", FloodAlert {
+            severity: FloodSeverity::Flood,
+            message: "Illinois River at Peoria above flood stage".to_string(),
+            registry_version: None,
+        });
+        
+        let _ = daemon.run_once();
+        assert_eq!(delivered.load(Ordering::SeqCst), 1);
+        assert_eq!(daemon.notifications.len(), 0);
+    }
+    
     // Additional tests would require database connection
     // See tests/daemon_lifecycle.rs for integration tests
 }
@@ -0,0 +1,187 @@
+//! Data-quality alerts awaiting manual review
+//!
+//! Some problems can be detected but not fixed automatically: a stage
+//! datum shift (`analysis::datum_shift`) needs someone to check the gauge
+//! log and apply or request a correction, and rating drift
+//! (`analysis::rating_drift`) needs the thresholds checked against the
+//! next USGS rating shift. Each detection opens a row in
+//! `usgs_raw.data_quality_alerts`; reviewing it marks it `confirmed`
+//! (a real data problem) or `dismissed` (the river really did that).
+//! Reopening the same event is a no-op, so the daemon can re-detect it
+//! every cycle without duplicating the alert.
+//!
+//! Entry points:
+//! - `flomon data-quality list|review` (CLI)
+
+use chrono::{DateTime, Utc};
+use postgres::Client;
+use serde::Serialize;
+
+use crate::analysis::datum_shift::DatumShift;
+use crate::analysis::rating_drift::RatingDrift;
+
+/// Default and maximum rows returned by `list`
+pub const DEFAULT_LIST_LIMIT: i64 = 100;
+const MAX_LIST_LIMIT: i64 = 1000;
+
+/// What the alert is about
+pub const KIND_DATUM_SHIFT: &str = "datum_shift";
+pub const KIND_RATING_DRIFT: &str = "rating_drift";
+
+/// Review state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Open,
+    Confirmed,
+    Dismissed,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Open => "open",
+            Status::Confirmed => "confirmed",
+            Status::Dismissed => "dismissed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "open" => Some(Status::Open),
+            "confirmed" => Some(Status::Confirmed),
+            "dismissed" => Some(Status::Dismissed),
+            _ => None,
+        }
+    }
+}
+
+/// One stored alert
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DataQualityAlert {
+    pub id: i64,
+    pub site_code: String,
+    pub kind: String,
+    /// When the problem starts in the data
+    pub event_time: DateTime<Utc>,
+    pub detected_at: DateTime<Utc>,
+    pub summary: String,
+    pub status: Status,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_note: Option<String>,
+}
+
+/// Open an alert for a datum shift; returns the new id, or None when this
+/// shift already has one
+pub fn open_datum_shift(client: &mut Client, shift: &DatumShift, now: DateTime<Utc>) -> Result<Option<i64>, String> {
+    let details = serde_json::json!({
+        "step_ft": shift.step_ft,
+        "stage_before_ft": shift.stage_before_ft,
+        "stage_after_ft": shift.stage_after_ft,
+        "discharge_before_cfs": shift.discharge_before_cfs,
+        "discharge_after_cfs": shift.discharge_after_cfs,
+        "noise_ft": shift.noise_ft,
+    });
+    let row = client.query_opt(
+        "INSERT INTO usgs_raw.data_quality_alerts
+         (site_code, kind, event_time, detected_at, summary, details)
+         VALUES ($1, $2, $3, $4, $5, $6::TEXT::JSONB)
+         ON CONFLICT (site_code, kind, event_time) DO NOTHING
+         RETURNING id",
+        &[&shift.site_code, &KIND_DATUM_SHIFT, &shift.shift_time, &now, &shift.describe(), &details.to_string()],
+    ).map_err(|e| format!("Failed to open data-quality alert for {}: {}", shift.site_code, e))?;
+    Ok(row.map(|r| r.get(0)))
+}
+
+/// Open an alert for rating drift, keyed on the first drifting
+/// measurement; returns the new id, or None when this drift already has one
+pub fn open_rating_drift(client: &mut Client, drift: &RatingDrift, now: DateTime<Utc>) -> Result<Option<i64>, String> {
+    let details = serde_json::json!({
+        "mean_percent": drift.mean_percent,
+        "comparisons": drift.comparisons,
+    });
+    let row = client.query_opt(
+        "INSERT INTO usgs_raw.data_quality_alerts
+         (site_code, kind, event_time, detected_at, summary, details)
+         VALUES ($1, $2, $3, $4, $5, $6::TEXT::JSONB)
+         ON CONFLICT (site_code, kind, event_time) DO NOTHING
+         RETURNING id",
+        &[&drift.site_code, &KIND_RATING_DRIFT, &drift.since, &now, &drift.describe(), &details.to_string()],
+    ).map_err(|e| format!("Failed to open data-quality alert for {}: {}", drift.site_code, e))?;
+    Ok(row.map(|r| r.get(0)))
+}
+
+/// Alerts, newest event first, optionally only those with `status`
+pub fn list(client: &mut Client, status: Option<Status>, limit: Option<i64>) -> Result<Vec<DataQualityAlert>, String> {
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+    let status = status.map(|s| s.as_str());
+    let rows = client.query(
+        "SELECT id, site_code, kind, event_time, detected_at, summary,
+                status, reviewed_by, reviewed_at, review_note
+         FROM usgs_raw.data_quality_alerts
+         WHERE ($1::TEXT IS NULL OR status = $1)
+         ORDER BY event_time DESC
+         LIMIT $2",
+        &[&status, &limit],
+    ).map_err(|e| format!("Failed to query data-quality alerts: {}", e))?;
+
+    rows.iter()
+        .map(|row| {
+            let status: String = row.get(6);
+            Ok(DataQualityAlert {
+                id: row.get(0),
+                site_code: row.get(1),
+                kind: row.get(2),
+                event_time: row.get(3),
+                detected_at: row.get(4),
+                summary: row.get(5),
+                status: Status::parse(&status).ok_or_else(|| format!("Unknown alert status '{}'", status))?,
+                reviewed_by: row.get(7),
+                reviewed_at: row.get(8),
+                review_note: row.get(9),
+            })
+        })
+        .collect()
+}
+
+/// Record a review decision on alert `id`
+pub fn review(
+    client: &mut Client,
+    id: i64,
+    status: Status,
+    reviewer: &str,
+    note: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    if status == Status::Open {
+        return Err("a review must confirm or dismiss the alert".to_string());
+    }
+    let updated = client.execute(
+        "UPDATE usgs_raw.data_quality_alerts
+         SET status = $2, reviewed_by = $3, reviewed_at = $4, review_note = $5
+         WHERE id = $1",
+        &[&id, &status.as_str(), &reviewer, &now, &note],
+    ).map_err(|e| format!("Failed to update data-quality alert #{}: {}", id, e))?;
+    if updated == 0 {
+        return Err(format!("No data-quality alert #{}", id));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_round_trip() {
+        for status in [Status::Open, Status::Confirmed, Status::Dismissed] {
+            assert_eq!(Status::parse(status.as_str()), Some(status));
+        }
+        assert_eq!(Status::parse("closed"), None);
+    }
+}
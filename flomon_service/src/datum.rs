@@ -0,0 +1,182 @@
+//! Vertical datums and per-site conversion between them.
+//!
+//! Elevations reach the service on three references:
+//!
+//! - USGS stage: feet above the gauge's own zero, whose elevation
+//!   (`gauge_datum_ft` in usgs_stations.toml) is in NGVD29 or NAVD88
+//! - CWMS pool and tailwater elevations, and the zones.toml pool targets
+//!   (`pool_target_ft_ngvd29`): NGVD29
+//! - the Lockport pool on the Sanitary and Ship Canal: IGLD85
+//!
+//! NGVD29 is the common datum ([`COMMON`]) since the pool targets the
+//! alerting compares against are in it. The difference between NAVD88 and
+//! NGVD29 is not a constant; across the basin it varies by a few tenths of
+//! a foot (VERTCON), which is as much as some pool deviation levels. So
+//! there is no basin-wide conversion: each site lists its own offsets
+//! (`to_ngvd29_ft`, the feet added to an elevation in that datum to give
+//! NGVD29), and a conversion the site has no offset for fails instead of
+//! guessing.
+//!
+//! ```text
+//! NGVD29 = stage + gauge zero (in the gauge's datum) + to_ngvd29_ft[gauge datum]
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Datum pool elevations and stages are compared on
+pub const COMMON: VerticalDatum = VerticalDatum::Ngvd29;
+
+/// A vertical reference system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum VerticalDatum {
+    /// National Geodetic Vertical Datum of 1929
+    Ngvd29,
+    /// North American Vertical Datum of 1988
+    Navd88,
+    /// International Great Lakes Datum of 1985
+    Igld85,
+}
+
+impl VerticalDatum {
+    /// Parses the spellings found in USGS and USACE metadata ("NGVD29",
+    /// "NGVD 1929", "NAVD88", "IGLD", "IGLD 1985", ...)
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let compact: String = name.chars()
+            .filter(|c| !c.is_whitespace() && *c != '-' && *c != '_')
+            .collect::<String>()
+            .to_ascii_uppercase();
+        match compact.as_str() {
+            "NGVD29" | "NGVD1929" => Ok(VerticalDatum::Ngvd29),
+            "NAVD88" | "NAVD1988" => Ok(VerticalDatum::Navd88),
+            "IGLD" | "IGLD85" | "IGLD1985" => Ok(VerticalDatum::Igld85),
+            _ => Err(format!("unknown vertical datum {:?} (expected NGVD29, NAVD88 or IGLD85)", name)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerticalDatum::Ngvd29 => "NGVD29",
+            VerticalDatum::Navd88 => "NAVD88",
+            VerticalDatum::Igld85 => "IGLD85",
+        }
+    }
+}
+
+impl fmt::Display for VerticalDatum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `to_ngvd29_ft` table from a registry entry, datum names parsed
+pub fn parse_offsets(offsets: &BTreeMap<String, f64>) -> Result<Vec<(VerticalDatum, f64)>, String> {
+    offsets.iter()
+        .map(|(name, &offset)| {
+            let datum = VerticalDatum::parse(name)?;
+            if datum == VerticalDatum::Ngvd29 {
+                return Err("to_ngvd29_ft cannot list NGVD29 itself".to_string());
+            }
+            if !offset.is_finite() {
+                return Err(format!("to_ngvd29_ft.{} must be a number of feet", name));
+            }
+            Ok((datum, offset))
+        })
+        .collect()
+}
+
+/// How elevations at one site convert between datums
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiteDatum {
+    /// Datum the site reports in
+    pub datum: VerticalDatum,
+    /// Elevation of zero stage in `datum`; None for sites reporting
+    /// elevations directly (CWMS pools)
+    pub gauge_zero_ft: Option<f64>,
+    /// Feet added to an elevation in each datum to give NGVD29
+    pub to_ngvd29_ft: Vec<(VerticalDatum, f64)>,
+}
+
+impl SiteDatum {
+    fn ngvd29_offset(&self, datum: VerticalDatum) -> Option<f64> {
+        if datum == VerticalDatum::Ngvd29 {
+            return Some(0.0);
+        }
+        self.to_ngvd29_ft.iter().find(|(d, _)| *d == datum).map(|(_, offset)| *offset)
+    }
+
+    /// `elevation_ft` in `from` expressed in `to`; None without the offsets
+    pub fn convert(&self, elevation_ft: f64, from: VerticalDatum, to: VerticalDatum) -> Option<f64> {
+        if from == to {
+            return Some(elevation_ft);
+        }
+        Some(elevation_ft + self.ngvd29_offset(from)? - self.ngvd29_offset(to)?)
+    }
+
+    /// Water surface in `to` for a stage at this site
+    pub fn elevation(&self, stage_ft: f64, to: VerticalDatum) -> Option<f64> {
+        self.convert(stage_ft + self.gauge_zero_ft?, self.datum, to)
+    }
+
+    /// Stage at this site for a water surface given in `from`
+    pub fn stage(&self, elevation_ft: f64, from: VerticalDatum) -> Option<f64> {
+        Some(self.convert(elevation_ft, from, self.datum)? - self.gauge_zero_ft?)
+    }
+
+    /// Gauge zero on [`COMMON`] when the site can be converted to it, else
+    /// on the site's own datum
+    pub fn common_zero(&self) -> Option<(f64, VerticalDatum)> {
+        let zero = self.gauge_zero_ft?;
+        Some(match self.convert(zero, self.datum, COMMON) {
+            Some(common) => (common, COMMON),
+            None => (zero, self.datum),
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn navd88_gauge() -> SiteDatum {
+        SiteDatum {
+            datum: VerticalDatum::Navd88,
+            gauge_zero_ft: Some(428.7),
+            to_ngvd29_ft: vec![(VerticalDatum::Navd88, 0.3)],
+        }
+    }
+
+    #[test]
+    fn test_parse_accepts_metadata_spellings() {
+        assert_eq!(VerticalDatum::parse("NGVD 1929").unwrap(), VerticalDatum::Ngvd29);
+        assert_eq!(VerticalDatum::parse("navd88").unwrap(), VerticalDatum::Navd88);
+        assert_eq!(VerticalDatum::parse("IGLD").unwrap(), VerticalDatum::Igld85);
+        assert!(VerticalDatum::parse("MSL").is_err());
+
+        let offsets = BTreeMap::from([("IGLD".to_string(), 1.3)]);
+        assert_eq!(parse_offsets(&offsets).unwrap(), vec![(VerticalDatum::Igld85, 1.3)]);
+        assert!(parse_offsets(&BTreeMap::from([("NGVD29".to_string(), 0.0)])).is_err());
+    }
+
+    #[test]
+    fn test_stage_and_elevation_round_trip_through_ngvd29() {
+        let site = navd88_gauge();
+        // 18.0 ft stage: 446.7 NAVD88, 447.0 NGVD29
+        assert!((site.elevation(18.0, VerticalDatum::Navd88).unwrap() - 446.7).abs() < 1e-9);
+        assert!((site.elevation(18.0, COMMON).unwrap() - 447.0).abs() < 1e-9);
+        assert!((site.stage(447.0, COMMON).unwrap() - 18.0).abs() < 1e-9);
+        let (zero, datum) = site.common_zero().unwrap();
+        assert_eq!(datum, COMMON);
+        assert!((zero - 429.0).abs() < 1e-9);
+
+        // No IGLD85 offset: no conversion, and the zero stays on NAVD88
+        assert_eq!(site.convert(580.0, VerticalDatum::Igld85, COMMON), None);
+        let unconverted = SiteDatum { to_ngvd29_ft: Vec::new(), ..navd88_gauge() };
+        assert_eq!(unconverted.elevation(18.0, COMMON), None);
+        assert_eq!(unconverted.common_zero(), Some((428.7, VerticalDatum::Navd88)));
+    }
+}
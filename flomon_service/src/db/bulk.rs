@@ -0,0 +1,254 @@
+//! Batched COPY-based inserts for the high-volume reading tables.
+//!
+//! The warehouse paths insert one row per statement, which costs a round
+//! trip per reading; a multi-year backfill is tens of thousands of them
+//! per station. Here a batch is streamed with `COPY ... FROM STDIN
+//! (FORMAT binary)` into a temporary staging table and merged in one
+//! statement:
+//!
+//! ```text
+//! BEGIN
+//! CREATE TEMP TABLE bulk_<target> ON COMMIT DROP AS SELECT <columns> FROM <target> WITH NO DATA
+//! COPY bulk_<target> (<columns>) FROM STDIN (FORMAT binary)
+//! INSERT INTO <target> (<columns>) SELECT <columns> FROM bulk_<target> ON CONFLICT (<key>) DO NOTHING
+//! COMMIT
+//! ```
+//!
+//! The merge keeps the conflict key and `DO NOTHING` of the per-row
+//! inserts, so re-running a backfill stays idempotent and the count
+//! returned is rows actually inserted. The staging table takes its column
+//! types from the target, and the binary writer is built from those, so a
+//! type mismatch fails the batch instead of coercing values. The whole
+//! batch is one transaction: it lands completely or not at all.
+//!
+//! Batches under `MIN_BULK_ROWS` go through the per-row path, where the
+//! staging setup would cost more than it saves.
+
+use postgres::binary_copy::BinaryCopyInWriter;
+use postgres::types::{ToSql, Type};
+use postgres::Client;
+use rust_decimal::Decimal;
+
+use crate::ingest::{cwms, iem};
+use crate::model::GaugeReading;
+
+/// Smallest batch worth staging; smaller batches insert row by row
+pub const MIN_BULK_ROWS: usize = 500;
+
+/// A target table and how rows merge into it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulkTable {
+    pub target: &'static str,
+    /// Columns written, in COPY order
+    pub columns: &'static [&'static str],
+    /// Conflict key of the target's unique constraint
+    pub conflict: &'static [&'static str],
+}
+
+pub const GAUGE_READINGS: BulkTable = BulkTable {
+    target: "usgs_raw.gauge_readings",
+    columns: &["site_code", "parameter_code", "unit", "value", "reading_time", "qualifier", "original_unit"],
+    conflict: &["site_code", "parameter_code", "reading_time"],
+};
+
+pub const CWMS_TIMESERIES: BulkTable = BulkTable {
+    target: "usace.cwms_timeseries",
+    columns: &[
+        "location_id", "timeseries_id", "parameter_id", "parameter_type", "interval", "duration", "version",
+        "timestamp", "value", "unit", "quality_code", "original_unit",
+    ],
+    conflict: &["location_id", "timestamp", "parameter_id"],
+};
+
+pub const ASOS_OBSERVATIONS: BulkTable = BulkTable {
+    target: "asos_observations",
+    columns: &[
+        "station_id", "observation_time", "temp_f", "dewpoint_f", "relative_humidity",
+        "wind_direction_deg", "wind_speed_knots", "wind_gust_knots", "precip_1hr_in",
+        "pressure_mb", "visibility_mi", "sky_condition", "weather_codes", "data_source",
+        "relative_humidity_reported", "rh_qc_flag",
+    ],
+    conflict: &["station_id", "observation_time"],
+};
+
+impl BulkTable {
+    /// Temporary staging table name, e.g. `bulk_usgs_raw_gauge_readings`
+    pub fn staging(&self) -> String {
+        format!("bulk_{}", self.target.replace('.', "_"))
+    }
+
+    fn column_list(&self) -> String {
+        self.columns.join(", ")
+    }
+
+    pub fn create_staging_sql(&self) -> String {
+        format!(
+            "CREATE TEMP TABLE {} ON COMMIT DROP AS SELECT {} FROM {} WITH NO DATA",
+            self.staging(), self.column_list(), self.target
+        )
+    }
+
+    pub fn copy_sql(&self) -> String {
+        format!("COPY {} ({}) FROM STDIN (FORMAT binary)", self.staging(), self.column_list())
+    }
+
+    pub fn merge_sql(&self) -> String {
+        format!(
+            "INSERT INTO {target} ({columns}) SELECT {columns} FROM {staging} ON CONFLICT ({conflict}) DO NOTHING",
+            target = self.target,
+            columns = self.column_list(),
+            staging = self.staging(),
+            conflict = self.conflict.join(", "),
+        )
+    }
+}
+
+/// Stage `rows` with binary COPY and merge them into `table`.
+///
+/// `write` is called once per row and must pass values in `table.columns`
+/// order. Returns the number of rows inserted (conflicts excluded).
+fn copy_merge<R>(
+    client: &mut Client,
+    table: &BulkTable,
+    rows: &[R],
+    mut write: impl FnMut(&mut BinaryCopyInWriter, &R) -> Result<(), String>,
+) -> Result<usize, String> {
+    let mut tx = client.transaction()
+        .map_err(|e| format!("Failed to start bulk insert into {}: {}", table.target, e))?;
+    tx.batch_execute(&table.create_staging_sql())
+        .map_err(|e| format!("Failed to create staging table for {}: {}", table.target, e))?;
+
+    // Exact column types from the staging table, for the binary encoder
+    let probe = tx.prepare(&format!("SELECT {} FROM {}", table.column_list(), table.staging()))
+        .map_err(|e| format!("Failed to read staging columns for {}: {}", table.target, e))?;
+    let types: Vec<Type> = probe.columns().iter().map(|c| c.type_().clone()).collect();
+
+    let sink = tx.copy_in(&table.copy_sql())
+        .map_err(|e| format!("Failed to start COPY into {}: {}", table.staging(), e))?;
+    let mut writer = BinaryCopyInWriter::new(sink, &types);
+    for row in rows {
+        write(&mut writer, row)?;
+    }
+    writer.finish()
+        .map_err(|e| format!("COPY into {} failed: {}", table.staging(), e))?;
+
+    let inserted = tx.execute(&table.merge_sql(), &[])
+        .map_err(|e| format!("Failed to merge staged rows into {}: {}", table.target, e))?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit bulk insert into {}: {}", table.target, e))?;
+    Ok(inserted as usize)
+}
+
+fn write_row(writer: &mut BinaryCopyInWriter, table: &BulkTable, values: &[&(dyn ToSql + Sync)]) -> Result<(), String> {
+    writer.write(values)
+        .map_err(|e| format!("Failed to encode row for {}: {}", table.target, e))
+}
+
+fn decimal(value: f64) -> Result<Decimal, String> {
+    Decimal::from_f64_retain(value)
+        .ok_or_else(|| format!("Failed to convert value {} to decimal", value))
+}
+
+/// Bulk insert gauge readings
+pub fn insert_gauge_readings(client: &mut Client, readings: &[GaugeReading]) -> Result<usize, String> {
+    insert_gauge_readings_into(client, &GAUGE_READINGS, readings)
+}
+
+/// `insert_gauge_readings` into a table shaped like `usgs_raw.gauge_readings`
+/// (the bench harness's scratch copy)
+pub fn insert_gauge_readings_into(
+    client: &mut Client,
+    table: &BulkTable,
+    readings: &[GaugeReading],
+) -> Result<usize, String> {
+    copy_merge(client, table, readings, |writer, reading| {
+        let value = decimal(reading.value)?;
+        write_row(writer, table, &[
+            &reading.site_code,
+            &reading.parameter_code,
+            &reading.unit,
+            &value,
+            &reading.datetime,
+            &reading.qualifier,
+            &reading.original_unit,
+        ])
+    })
+}
+
+/// Bulk insert CWMS timeseries values (instantaneous 15-minute series)
+pub fn insert_cwms_timeseries(client: &mut Client, timeseries: &[cwms::CwmsTimeseries]) -> Result<usize, String> {
+    copy_merge(client, &CWMS_TIMESERIES, timeseries, |writer, record| {
+        let value = decimal(record.value)?;
+        write_row(writer, &CWMS_TIMESERIES, &[
+            &record.location_id,
+            &record.timeseries_id,
+            &record.parameter_id,
+            &"Inst",
+            &"15Minutes",
+            &"0",
+            &"Ccp-Rev",
+            &record.timestamp,
+            &value,
+            &record.unit,
+            &record.quality_code,
+            &record.original_unit,
+        ])
+    })
+}
+
+/// Bulk insert ASOS observations
+pub fn insert_asos_observations(client: &mut Client, observations: &[iem::AsosObservation]) -> Result<usize, String> {
+    copy_merge(client, &ASOS_OBSERVATIONS, observations, |writer, obs| {
+        write_row(writer, &ASOS_OBSERVATIONS, &[
+            &obs.station_id,
+            &obs.timestamp,
+            &obs.temp_f,
+            &obs.dewpoint_f,
+            &obs.relative_humidity,
+            &obs.wind_direction_deg,
+            &obs.wind_speed_knots,
+            &obs.wind_gust_knots,
+            &obs.precip_1hr_in,
+            &obs.pressure_mb,
+            &obs.visibility_mi,
+            &obs.sky_condition,
+            &obs.weather_codes,
+            &"IEM_ASOS",
+            &obs.relative_humidity_reported,
+            &obs.rh_qc_flag.map(|f| f.as_str()),
+        ])
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statements_share_columns_and_conflict_key() {
+        let table = GAUGE_READINGS;
+        assert_eq!(table.staging(), "bulk_usgs_raw_gauge_readings");
+        assert_eq!(
+            table.create_staging_sql(),
+            "CREATE TEMP TABLE bulk_usgs_raw_gauge_readings ON COMMIT DROP AS SELECT site_code, parameter_code, \
+             unit, value, reading_time, qualifier, original_unit FROM usgs_raw.gauge_readings WITH NO DATA"
+        );
+        assert!(table.copy_sql().ends_with("FROM STDIN (FORMAT binary)"));
+        assert!(table.merge_sql().ends_with("ON CONFLICT (site_code, parameter_code, reading_time) DO NOTHING"));
+    }
+
+    #[test]
+    fn test_conflict_keys_are_copied_columns() {
+        for table in [GAUGE_READINGS, CWMS_TIMESERIES, ASOS_OBSERVATIONS] {
+            for key in table.conflict {
+                assert!(table.columns.contains(key), "{} conflict key {} not copied", table.target, key);
+            }
+        }
+        assert_eq!(ASOS_OBSERVATIONS.columns.len(), 16);
+        assert_eq!(CWMS_TIMESERIES.columns.len(), 12);
+    }
+}
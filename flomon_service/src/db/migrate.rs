@@ -0,0 +1,198 @@
+//! Embedded SQL migrations.
+//!
+//! The files under `sql/` are compiled into the binary and applied in
+//! version order by `migrate`, each in its own transaction together with
+//! its row in `public.schema_migrations`:
+//!
+//! ```text
+//! schema_migrations (version, name, applied_at)
+//! 001 initial_schema      2024-05-01 12:00:00+00
+//! 002 monitoring_metadata 2024-05-01 12:00:01+00
+//! ```
+//!
+//! The files are also written to be run by hand with `psql -f`, so before
+//! executing one the runner drops psql meta-commands (`\set`, `\echo`) and
+//! the file's own top-level `BEGIN;` / `COMMIT;` lines; a migration that
+//! fails leaves neither its changes nor its row behind.
+//!
+//! Databases set up by hand before this table existed have no record of
+//! what was applied; `baseline` marks migrations through a version as
+//! applied without running them (`flomon migrate --baseline N`).
+
+use postgres::Client;
+
+use super::DbConfigError;
+
+/// One embedded migration file
+#[derive(Debug)]
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// Every migration in `sql/`, in version order
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "initial_schema", sql: include_str!("../../sql/001_initial_schema.sql") },
+    Migration { version: 2, name: "monitoring_metadata", sql: include_str!("../../sql/002_monitoring_metadata.sql") },
+    Migration { version: 3, name: "flood_metadata", sql: include_str!("../../sql/003_flood_metadata.sql") },
+    Migration { version: 4, name: "usace_cwms", sql: include_str!("../../sql/004_usace_cwms.sql") },
+    Migration { version: 5, name: "flood_analysis", sql: include_str!("../../sql/005_flood_analysis.sql") },
+    Migration { version: 6, name: "iem_asos", sql: include_str!("../../sql/006_iem_asos.sql") },
+    Migration { version: 7, name: "freeboard", sql: include_str!("../../sql/007_freeboard.sql") },
+    Migration { version: 8, name: "precip_rollups", sql: include_str!("../../sql/008_precip_rollups.sql") },
+    Migration { version: 9, name: "asos_humidity_qc", sql: include_str!("../../sql/009_asos_humidity_qc.sql") },
+    Migration { version: 10, name: "field_obs", sql: include_str!("../../sql/010_field_obs.sql") },
+    Migration { version: 11, name: "zone_subscriptions", sql: include_str!("../../sql/011_zone_subscriptions.sql") },
+    Migration { version: 12, name: "reading_anomaly_scores", sql: include_str!("../../sql/012_reading_anomaly_scores.sql") },
+    Migration { version: 13, name: "unit_normalization", sql: include_str!("../../sql/013_unit_normalization.sql") },
+    Migration { version: 14, name: "reading_archive", sql: include_str!("../../sql/014_reading_archive.sql") },
+    Migration { version: 15, name: "registry_versions", sql: include_str!("../../sql/015_registry_versions.sql") },
+    Migration { version: 16, name: "pending_notifications", sql: include_str!("../../sql/016_pending_notifications.sql") },
+    Migration { version: 17, name: "polling_decisions", sql: include_str!("../../sql/017_polling_decisions.sql") },
+    Migration { version: 18, name: "stage_slope", sql: include_str!("../../sql/018_stage_slope.sql") },
+    Migration { version: 19, name: "cam_snapshots", sql: include_str!("../../sql/019_cam_snapshots.sql") },
+    Migration { version: 20, name: "manual_readings", sql: include_str!("../../sql/020_manual_readings.sql") },
+    Migration { version: 21, name: "cwms_timeseries_runs", sql: include_str!("../../sql/021_cwms_timeseries_runs.sql") },
+    Migration { version: 22, name: "nws_geography", sql: include_str!("../../sql/022_nws_geography.sql") },
+    Migration { version: 23, name: "api_errors", sql: include_str!("../../sql/023_api_errors.sql") },
+    Migration { version: 24, name: "cwms_quality", sql: include_str!("../../sql/024_cwms_quality.sql") },
+    Migration { version: 25, name: "community_readings", sql: include_str!("../../sql/025_community_readings.sql") },
+    Migration { version: 26, name: "data_quality_alerts", sql: include_str!("../../sql/026_data_quality_alerts.sql") },
+    Migration { version: 27, name: "precip_response_curves", sql: include_str!("../../sql/027_precip_response_curves.sql") },
+    Migration { version: 28, name: "daemon_heartbeats", sql: include_str!("../../sql/028_daemon_heartbeats.sql") },
+    Migration { version: 29, name: "field_measurements", sql: include_str!("../../sql/029_field_measurements.sql") },
+    Migration { version: 30, name: "event_mode", sql: include_str!("../../sql/030_event_mode.sql") },
+    Migration { version: 31, name: "zone_occupancy", sql: include_str!("../../sql/031_zone_occupancy.sql") },
+    Migration { version: 32, name: "stage_forecasts", sql: include_str!("../../sql/032_stage_forecasts.sql") },
+    Migration { version: 33, name: "precip_grid_summary", sql: include_str!("../../sql/033_precip_grid_summary.sql") },
+    Migration { version: 34, name: "rating_tables", sql: include_str!("../../sql/034_rating_tables.sql") },
+    Migration { version: 35, name: "alert_rules", sql: include_str!("../../sql/035_alert_rules.sql") },
+    Migration { version: 36, name: "flood_event_detection", sql: include_str!("../../sql/036_flood_event_detection.sql") },
+    Migration { version: 37, name: "nws_active_alerts", sql: include_str!("../../sql/037_nws_active_alerts.sql") },
+    Migration { version: 38, name: "lag_estimates", sql: include_str!("../../sql/038_lag_estimates.sql") },
+    Migration { version: 39, name: "backfill_checkpoints", sql: include_str!("../../sql/039_backfill_checkpoints.sql") },
+    Migration { version: 40, name: "backwater_status", sql: include_str!("../../sql/040_backwater_status.sql") },
+    Migration { version: 41, name: "usgs_daily_stats", sql: include_str!("../../sql/041_usgs_daily_stats.sql") },
+    Migration { version: 42, name: "threshold_overrides", sql: include_str!("../../sql/042_threshold_overrides.sql") },
+    Migration { version: 43, name: "usgs_value_qualifiers", sql: include_str!("../../sql/043_usgs_value_qualifiers.sql") },
+    Migration { version: 44, name: "zone_status", sql: include_str!("../../sql/044_zone_status.sql") },
+    Migration { version: 45, name: "annual_peaks", sql: include_str!("../../sql/045_annual_peaks.sql") },
+    Migration { version: 46, name: "station_suppressions", sql: include_str!("../../sql/046_station_suppressions.sql") },
+];
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS public.schema_migrations (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+)";
+
+/// The statements of a migration file without psql meta-commands or the
+/// file's own transaction control
+pub fn executable_sql(sql: &str) -> String {
+    sql.lines()
+        .filter(|line| {
+            let trimmed = line.trim();
+            !trimmed.starts_with('\\')
+                && !trimmed.eq_ignore_ascii_case("BEGIN;")
+                && !trimmed.eq_ignore_ascii_case("COMMIT;")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Migrations whose version is not in `applied`, in version order
+pub fn pending(applied: &[i32]) -> Vec<&'static Migration> {
+    MIGRATIONS.iter().filter(|m| !applied.contains(&m.version)).collect()
+}
+
+/// Versions recorded in `schema_migrations`, creating the table if needed
+pub fn applied_versions(client: &mut Client) -> Result<Vec<i32>, DbConfigError> {
+    client.batch_execute(CREATE_TABLE).map_err(DbConfigError::ConnectionFailed)?;
+    let rows = client
+        .query("SELECT version FROM public.schema_migrations ORDER BY version", &[])
+        .map_err(DbConfigError::ConnectionFailed)?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+/// Apply every pending migration in order; returns those applied.
+/// Stops at the first failure, leaving later migrations pending.
+pub fn migrate(client: &mut Client) -> Result<Vec<&'static Migration>, DbConfigError> {
+    let applied = applied_versions(client)?;
+    let mut newly_applied = Vec::new();
+
+    for migration in pending(&applied) {
+        let failed = |error| DbConfigError::MigrationFailed {
+            version: migration.version,
+            name: migration.name,
+            error,
+        };
+        let mut tx = client.transaction().map_err(failed)?;
+        tx.batch_execute(&executable_sql(migration.sql)).map_err(failed)?;
+        tx.execute(
+            "INSERT INTO public.schema_migrations (version, name) VALUES ($1, $2)",
+            &[&migration.version, &migration.name],
+        ).map_err(failed)?;
+        tx.commit().map_err(failed)?;
+        newly_applied.push(migration);
+    }
+
+    Ok(newly_applied)
+}
+
+/// Record migrations up to and including `through` as applied without
+/// running them, for databases migrated by hand; returns how many were
+/// newly recorded
+pub fn baseline(client: &mut Client, through: i32) -> Result<usize, DbConfigError> {
+    let applied = applied_versions(client)?;
+    let mut recorded = 0;
+    for migration in pending(&applied).into_iter().filter(|m| m.version <= through) {
+        client.execute(
+            "INSERT INTO public.schema_migrations (version, name) VALUES ($1, $2)",
+            &[&migration.version, &migration.name],
+        ).map_err(DbConfigError::ConnectionFailed)?;
+        recorded += 1;
+    }
+    Ok(recorded)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_ordered_and_embed_their_files() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as i32 + 1, "gap or reorder before {}", migration.name);
+            assert!(!migration.sql.trim().is_empty());
+        }
+        let on_disk = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/sql"))
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().path().extension().is_some_and(|x| x == "sql"))
+            .count();
+        assert_eq!(on_disk, MIGRATIONS.len(), "sql/ has a migration that is not embedded");
+    }
+
+    #[test]
+    fn test_executable_sql_strips_psql_and_transaction_lines() {
+        let sql = "\\set ON_ERROR_STOP on\n\nBEGIN;\nCREATE TABLE t (x INT);\n\
+            CREATE FUNCTION f() RETURNS void AS $$\nBEGIN\n  NULL;\nEND;\n$$ LANGUAGE plpgsql;\nCOMMIT;\n\\echo 'done'";
+        let stripped = executable_sql(sql);
+        assert!(!stripped.contains("\\set") && !stripped.contains("\\echo"));
+        assert!(!stripped.contains("BEGIN;") && !stripped.contains("COMMIT;"));
+        assert!(stripped.contains("CREATE TABLE t (x INT);"));
+        assert!(stripped.contains("\nBEGIN\n"), "plpgsql block must survive");
+    }
+
+    #[test]
+    fn test_pending_skips_applied_versions() {
+        let pending = pending(&[1, 2, 4]);
+        assert_eq!(pending[0].version, 3);
+        assert_eq!(pending[1].version, 5);
+        assert_eq!(pending.len(), MIGRATIONS.len() - 3);
+    }
+}
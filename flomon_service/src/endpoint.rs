@@ -1,141 +1,140 @@
-/// Zone-based HTTP endpoint for flood monitoring
-///
-/// Provides REST API organized by hydrological zones with lead times
-/// and geographic context for flood forecasting.
-///
-/// ## NEW Zone-Based Endpoints:
-/// - GET /zones - List all zones with metadata
-/// - GET /zone/{zone_id} - Get all sensors in a zone with current readings
-/// - GET /status - Overall basin flood status across all zones
-/// - GET /backwater - Backwater flood analysis (Zone 0 + Zone 1)
-/// - GET /forecast - Lead time forecast based on active zones
-/// - GET /health - Service health check
-///
-/// ## DEPRECATED Endpoints (still functional but use zone-based views instead):
-/// - GET /site/{site_code} - Returns single-site data (use /zone/{zone_id} instead)
+//! Zone-based HTTP endpoint for flood monitoring
+//!
+//! Provides REST API organized by hydrological zones with lead times
+//! and geographic context for flood forecasting.
+//!
+//! ## NEW Zone-Based Endpoints:
+//! - GET /zones - List all zones with metadata
+//! - GET /zone/{zone_id}?as_of= - Get all sensors in a zone with current readings
+//! - GET /status - Overall basin flood status across all zones
+//! - GET /backwater - Backwater flood analysis (Zone 0 + Zone 1)
+//! - GET /forecast - Lead time forecast based on active zones
+//! - GET /health - Service health check
+//! - GET /field_obs?site=&event=&kind=&limit= - Manual field observations
+//! - POST /field_obs - Record a field observation (JSON body, bearer token, see `field_obs`)
+//! - GET /history?site=&param=&start=&end=&include_manual= - Readings across hot and archived storage
+//! - GET /manual_readings?site=&start=&end=&limit= - Staff gauge readings reported by SMS/email
+//! - POST /manual_readings - Forwarded SMS/email report (bearer token, see `manual_readings`)
+//! - GET /embed/widget.js - Self-contained embeddable status widget
+//! - GET /api/embed/summary?site= - Compact status for the widget
+//! - GET /api/snapshot - Current conditions and alerts in one public document (see `status_cache`)
+//! - GET /api/stations/{site}/stats?param=&start=&end= - Summary statistics for a window (see `analysis::window_stats`)
+//! - GET /api/stations/{site}/quality?start=&end= - Stage and discharge readings per quality flag (see `quality`)
+//! - GET /api/volume?start=&end= or ?event= - Water volume past Kingston Mines vs upstream gauges (see `analysis::volume`)
+//! - GET /api/severity_scheme - Severity names, colors and order every display should use (see `alert::scheme`)
+//! - GET /api/latest?site=&as_of= - Stations with their latest readings
+//! - GET /api/alerts?site=&as_of= - Stations at or above action stage, most severe first
+//! - GET /api/mode - System-wide event mode and dashboard banner (see `monitor::event_mode`)
+//! - GET /api/occupancy - Zone occupancy as alert urgency sees it (see `alert::occupancy`)
+//! - POST /api/occupancy - Set or clear a zone's occupancy flag (bearer token)
+//! - GET /api/suppressions?all= - Stations/sensors marked down for maintenance; `all=true` adds ended ones (see `monitor::suppression`)
+//! - POST /api/suppressions - Mark a station or sensor down until a time (bearer token)
+//! - POST /api/suppressions/{id}/end - End a suppression early (bearer token)
+//! - GET /api/expected_response - Expected tributary rise from current basin rain (see `analysis::precip_response`)
+//! - GET /api/lag - Fitted upstream travel times per flow regime (see `analysis::lag`)
+//! - GET /api/profile?river_mile= - Gauged water surface by river mile and interpolated stage at `[[profile_points]]` (see `analysis::profile`)
+//! - GET /api/stream?site= - Server-Sent Events: new readings and severity transitions as the daemon writes them (client limit and optional token, see `stream`)
+//! - GET /cams/snapshot/{id} - Webcam image captured on a severity change (see `cams`)
+//! - GET /zone/{zone_id}/inundation?stage= - Approximate flooded area as GeoJSON
+//! - GET /map/layers - Static basin map layers available (see `basin_map`)
+//! - GET /map/{name}.geojson - One precomputed layer; `ETag` / `If-None-Match` revalidation
+//! - GET /api/geojson/stations - Stations as GeoJSON points with current stage, severity color and thresholds (see `geojson`)
+//! - GET /api/geojson/zones - Zones as GeoJSON with their worst current severity
+//! - GET /api/zones/{zone_id}/status - The zone's rolled-up severity, rise, staleness and rain from the last cycle (see `zones::status`)
+//!
+//! ## Dashboard API
+//! Resource-style names for the dashboard, answered by `api` (a site not
+//! in the registry is 404):
+//! - GET /api/stations?site=&as_of= - Every station with its latest readings (`/api/latest`)
+//! - GET /api/stations/{site}/latest?as_of= - One station (`/api/latest?site=`)
+//! - GET /api/zones?site= - Zones with metadata (`/zones`), or the zones with a sensor on `site`
+//! - GET /api/alerts/active?site=&as_of= - Current alerts (`/api/alerts`)
+//!
+//! ## Past instants (`as_of`)
+//! `/zone/{zone_id}`, `/api/latest` and `/api/alerts` take an optional
+//! RFC 3339 `as_of` and answer with what the system knew then: readings
+//! taken and ingested by that instant (see `archive::latest_known`),
+//! staleness measured from it, and for stations the registry version in
+//! effect (see `registry::version_at`). The event replay slider and
+//! postmortems use it; omitted, it means now.
+//!
+//! ## Status snapshot
+//! With `[status_cache]` configured, `/status`, `/api/embed/summary` and
+//! the live `/api/alerts` are answered from the daemon's snapshot file
+//! while it is fresh, without a database query (see
+//! `status_cache`); otherwise they query as usual.
+//!
+//! ## Attribution
+//! Successful JSON object responses carry an `attribution` array crediting
+//! the providers behind the route (USGS, USACE, IEM, NWS; see
+//! `attribution::for_path`), and the embed widget prints them under the
+//! gauge, so a shared snapshot keeps its credits without a manual footer.
+//!
+//! ## DEPRECATED Endpoints (still functional but use zone-based views instead):
+//! - GET /site/{site_code} - Returns single-site data (use /zone/{zone_id} instead)
+//!
+//! ## Serving
+//! Plain HTTP by default; with `tls_cert`/`tls_key` configured the port
+//! speaks HTTPS (see `tls`). Behind a reverse proxy, requests from a
+//! `trusted_proxies` address have their client IP and scheme taken from
+//! `X-Forwarded-For` / `X-Forwarded-Proto`; from anyone else those headers
+//! are ignored. The resolved client is what the request log records.
+//!
+//! ## Errors and request IDs
+//! Every request gets an ID, returned as `X-Request-Id` and written on its
+//! request log line (a trusted proxy's own `X-Request-Id` is reused). JSON
+//! error responses carry it too, as `ApiErrorResponse` (`error`, `status`,
+//! `request_id`), and 5xx errors are also logged at ERROR with the
+//! underlying message and recorded in the `api_errors` table, so an ID
+//! shown by the dashboard leads straight to the failing query.
 
-use crate::analysis::groupings::group_by_zone;
-use crate::zones::{self, ZoneMetadata, get_zone, get_all_zones};
+use crate::alert::scheme::SeverityScheme;
+use crate::alert::thresholds::check_station;
+use crate::api::{self, DashboardRoute, LATEST_LOOKBACK_DAYS, as_of_param, registry_at};
+use crate::analysis::inundation::{self, DemGrid, InundationLayer};
+use crate::analysis::lag;
+use crate::analysis::precip::{PrecipRollup, RollupScope};
+use crate::analysis::precip_response;
+use crate::analysis::profile::{self, ProfilePoint};
+use crate::analysis::volume;
+use crate::analysis::window_stats;
+use crate::archive;
+use crate::attribution;
+use crate::basin_map::{self, MapLayerSettings, MapTile};
+use crate::cams;
+use crate::conditions::{analyze_backwater_risk, fetch_basin_status, fetch_embed_summary, fetch_zone_detail};
+use crate::manual_readings::{self, ForwardedMessage, ManualSettings};
+use crate::alert::occupancy::{self, OccupancySettings};
+use crate::geojson;
+use crate::field_obs::{self, FieldObsFilter, FieldObsKind, FieldObsSettings, FieldObservation};
+use crate::zones::{self, ZoneMetadata, get_all_zones};
 use crate::model::GaugeReading;
-use chrono::{DateTime, Utc};
+use crate::quality;
+use crate::monitor::event_mode::{self, Mode};
+use crate::monitor::suppression::{self, NewSuppression, SuppressionSettings};
+use crate::stations;
+use crate::status_cache::{SnapshotReader, StatusCacheSettings};
+use crate::stream::{self, Hub, StreamSettings, Subscription};
+use crate::logging;
+use crate::tls::{self, TlsFiles, TlsPeers};
+use chrono::{DateTime, Duration, Utc};
 use postgres::Client;
-use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 // ============================================================================
 // Response Types
 // ============================================================================
 
-/// List of all zones with metadata
-#[derive(Debug, Serialize)]
-pub struct ZonesListResponse {
-    pub zones: Vec<ZoneListItem>,
-    pub system_time: DateTime<Utc>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct ZoneListItem {
-    pub zone_id: usize,
-    pub name: String,
-    pub lead_time_hours_min: Option<i64>,
-    pub lead_time_hours_max: Option<i64>,
-    pub primary_alert_condition: String,
-    pub sensor_count: usize,
-}
-
-/// Zone detail with all sensor readings
-#[derive(Debug, Serialize)]
-pub struct ZoneDetailResponse {
-    pub zone_id: usize,
-    pub zone_name: String,
-    pub description: String,
-    pub metadata: ZoneMetadataResponse,
-    pub sensors: Vec<SensorDetailResponse>,
-    pub zone_status: ZoneStatusResponse,
-    pub last_updated: DateTime<Utc>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct ZoneMetadataResponse {
-    pub lead_time_hours_min: Option<i64>,
-    pub lead_time_hours_max: Option<i64>,
-    pub primary_alert_condition: String,
-}
-
-#[derive(Debug, Serialize)]
-pub struct SensorDetailResponse {
-    pub sensor_id: String,
-    pub sensor_type: String,
-    pub role: String,
-    pub location: String,
-    pub coordinates: CoordinatesResponse,
-    pub source: String,
-    
-    // Current readings
-    pub current_value: Option<f64>,
-    pub current_unit: Option<String>,
-    pub current_timestamp: Option<String>,
-    pub staleness_minutes: Option<i64>,
-    
-    // Thresholds (if applicable)
-    pub flood_stage_ft: Option<f64>,
-    pub action_stage_ft: Option<f64>,
-    
-    // Relevance explanation
-    pub relevance: String,
-}
-
-#[derive(Debug, Serialize)]
-pub struct CoordinatesResponse {
-    pub lat: f64,
-    pub lon: f64,
-}
-
-#[derive(Debug, Serialize)]
-pub struct ZoneStatusResponse {
-    pub alert_level: String,  // "NORMAL", "WATCH", "WARNING", "CRITICAL"
-    pub active_sensors: usize,
-    pub stale_sensors: usize,
-    pub sensors_above_action: Vec<String>,
-    pub sensors_above_flood: Vec<String>,
-}
-
-/// Overall basin status
-#[derive(Debug, Serialize)]
-pub struct BasinStatusResponse {
-    pub overall_status: String,  // "NORMAL", "ELEVATED", "FLOOD_WATCH", "FLOOD_WARNING"
-    pub active_zones: Vec<ActiveZoneStatus>,
-    pub backwater_risk: BackwaterRiskResponse,
-    pub upstream_flood_pulse: UpstreamFloodPulseResponse,
-    pub compound_event_risk: String,  // "LOW", "MODERATE", "HIGH"
-    pub last_updated: DateTime<Utc>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct ActiveZoneStatus {
-    pub zone_id: usize,
-    pub zone_name: String,
-    pub status: String,
-    pub lead_time_hours: Option<i64>,
-    pub key_sensors_elevated: Vec<String>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct BackwaterRiskResponse {
-    pub risk_level: String,  // "LOW", "MODERATE", "HIGH", "CRITICAL"
-    pub grafton_stage_ft: Option<f64>,
-    pub lagrange_pool_ft: Option<f64>,
-    pub lagrange_tailwater_ft: Option<f64>,
-    pub pool_tailwater_differential_ft: Option<f64>,
-    pub explanation: String,
-}
-
-#[derive(Debug, Serialize)]
-pub struct UpstreamFloodPulseResponse {
-    pub pulse_detected: bool,
-    pub estimated_arrival_hours: Option<i64>,
-    pub source_zones: Vec<usize>,
-    pub explanation: String,
-}
+// Defined in the flomon-types crate so clients can share them
+pub use flomon_types::api::{
+    ActiveZoneStatus, ApiErrorResponse, BackwaterRiskResponse, BasinStatusResponse, CoordinatesResponse,
+    EmbedSummaryResponse, SensorDetailResponse, UpstreamFloodPulseResponse, ZoneDetailResponse,
+    ZoneListItem, ZoneMetadataResponse, ZoneStatusResponse, ZonesListResponse,
+};
 
 // ============================================================================
 // Main Endpoint Handlers
@@ -167,464 +166,451 @@ pub fn fetch_zones_list(_client: &mut Client) -> Result<ZonesListResponse, Strin
     })
 }
 
-/// Fetch zone detail with all sensor readings
-pub fn fetch_zone_detail(client: &mut Client, zone_id: usize) -> Result<ZoneDetailResponse, String> {
-    let zones_config = zones::load_zones_default()
-        .map_err(|e| format!("Failed to load zones.toml: {}", e))?;
-    
-    let zone = get_zone(&zones_config, zone_id)
-        .ok_or_else(|| format!("Zone {} not found", zone_id))?;
-    
-    let metadata = ZoneMetadata::for_zone(zone_id);
-    
-    // Fetch all recent USGS readings
-    let usgs_readings = fetch_all_recent_readings(client)?;
-    
-    // Group by zone
-    let zone_readings = group_by_zone(usgs_readings, &zones_config);
-    let this_zone_readings = zone_readings.iter()
-        .find(|zr| zr.zone_id == zone_id)
-        .ok_or_else(|| format!("Zone {} readings not found", zone_id))?;
-    
-    // Build sensor details
-    let mut sensors = Vec::new();
-    let mut sensors_above_action = Vec::new();
-    let mut sensors_above_flood = Vec::new();
-    let mut active_count = 0;
-    let mut stale_count = 0;
-    
-    for sensor_data in &this_zone_readings.sensors {
-        let sensor = &sensor_data.sensor;
-        
-        // Extract current reading
-        let (current_value, current_unit, current_timestamp, staleness) = 
-            if let Some(ref readings) = sensor_data.readings {
-                // Prefer stage over discharge for thresholds
-                let stage_reading = readings.stage_ft.as_ref();
-                let discharge_reading = readings.discharge_cfs.as_ref();
-                
-                let reading_opt = stage_reading.or(discharge_reading);
-                
-                if let Some(reading) = reading_opt {
-                    let timestamp = chrono::DateTime::parse_from_rfc3339(&reading.datetime)
-                        .ok()
-                        .map(|dt| dt.with_timezone(&Utc));
-                    
-                    let staleness_min = timestamp.map(|ts| (Utc::now() - ts).num_minutes());
-                    
-                    active_count += 1;
-                    if staleness_min.unwrap_or(9999) > 120 {
-                        stale_count += 1;
-                    }
-                    
-                    (Some(reading.value), Some(reading.unit.clone()), 
-                     Some(reading.datetime.clone()), staleness_min)
-                } else {
-                    stale_count += 1;
-                    (None, None, None, None)
-                }
-            } else {
-                // For CWMS/ASOS sensors, fetch from appropriate tables
-                let (val, unit, ts, stale) = fetch_sensor_reading(client, sensor)?;
-                if val.is_some() {
-                    active_count += 1;
-                    if stale.unwrap_or(9999) > 120 {
-                        stale_count += 1;
-                    }
-                } else {
-                    stale_count += 1;
-                }
-                (val, unit, ts, stale)
-            };
-        
-        // Check thresholds
-        if let (Some(value), Some(action)) = (current_value, sensor.action_stage_ft) {
-            if value >= action {
-                sensors_above_action.push(sensor.primary_id());
-            }
-        }
-        
-        if let (Some(value), Some(flood)) = (current_value, sensor.flood_stage_ft) {
-            if value >= flood {
-                sensors_above_flood.push(sensor.primary_id());
-            }
-        }
-        
-        sensors.push(SensorDetailResponse {
-            sensor_id: sensor.primary_id(),
-            sensor_type: sensor.sensor_type.clone(),
-            role: sensor.role.clone(),
-            location: sensor.location.clone(),
-            coordinates: CoordinatesResponse {
-                lat: sensor.lat,
-                lon: sensor.lon,
-            },
-            source: sensor.source.clone(),
-            current_value,
-            current_unit,
-            current_timestamp,
-            staleness_minutes: staleness,
-            flood_stage_ft: sensor.flood_stage_ft,
-            action_stage_ft: sensor.action_stage_ft,
-            relevance: sensor.relevance.clone(),
-        });
-    }
-    
-    // Determine zone alert level
-    let alert_level = if !sensors_above_flood.is_empty() {
-        "CRITICAL"
-    } else if !sensors_above_action.is_empty() {
-        "WARNING"
-    } else if stale_count > sensors.len() / 2 {
-        "DEGRADED"
-    } else {
-        "NORMAL"
-    };
-    
-    Ok(ZoneDetailResponse {
-        zone_id,
-        zone_name: zone.name.clone(),
-        description: zone.description.clone(),
-        metadata: ZoneMetadataResponse {
-            lead_time_hours_min: metadata.lead_time_hours_min,
-            lead_time_hours_max: metadata.lead_time_hours_max,
-            primary_alert_condition: metadata.primary_alert_condition,
-        },
-        sensors,
-        zone_status: ZoneStatusResponse {
-            alert_level: alert_level.to_string(),
-            active_sensors: active_count,
-            stale_sensors: stale_count,
-            sensors_above_action,
-            sensors_above_flood,
-        },
-        last_updated: Utc::now(),
+// ============================================================================
+// Embeddable Status Widget
+// ============================================================================
+//
+// Served to third-party pages (the neighborhood association's site), so
+// these routes are public by design: no auth, permissive CORS, and long
+// cache lifetimes so a busy embedding page doesn't load the database.
+
+/// Gauge shown when the embed does not name one (Illinois River at Peoria)
+pub const EMBED_DEFAULT_SITE: &str = "05567500";
+
+/// Cache lifetime for the summary JSON, in seconds
+const EMBED_SUMMARY_MAX_AGE: u32 = 300;
+/// Cache lifetime for the severity scheme, which only changes on restart
+const SEVERITY_SCHEME_MAX_AGE: u32 = 3_600;
+/// Cache lifetime for the widget script, in seconds
+const EMBED_SCRIPT_MAX_AGE: u32 = 86_400;
+/// Cache lifetime for stored webcam snapshots, which never change
+const CAM_SNAPSHOT_MAX_AGE: u32 = 86_400;
+
+/// Widget script. Embed with:
+///   <script src="https://HOST/embed/widget.js" data-site="05567500" async></script>
+const EMBED_WIDGET_JS: &str = r##"(function () {
+  var script = document.currentScript;
+  if (!script) { return; }
+  var origin = new URL(script.src).origin;
+  var site = script.getAttribute("data-site") || "";
+  var box = document.createElement("div");
+  box.style.cssText = "font:14px/1.3 sans-serif;display:inline-block;padding:8px 12px;" +
+    "border-radius:6px;color:#fff;background:#757575;min-width:180px";
+  box.textContent = "Loading river status…";
+  script.parentNode.insertBefore(box, script);
+
+  fetch(origin + "/api/embed/summary" + (site ? "?site=" + encodeURIComponent(site) : ""))
+    .then(function (r) { return r.json(); })
+    .then(function (s) {
+      box.style.background = s.color || "#757575";
+      box.textContent = "";
+      var name = document.createElement("div");
+      name.style.fontSize = "12px";
+      name.textContent = s.site_name || "";
+      var stage = document.createElement("div");
+      stage.style.cssText = "font-size:22px;font-weight:bold";
+      stage.textContent = s.stage_ft == null ? "No data"
+        : s.stage_ft.toFixed(2) + " ft " + (s.trend_arrow || "");
+      var status = document.createElement("div");
+      status.textContent = (s.severity_label || s.severity || "").toUpperCase() +
+        (s.observed_at ? " · " + new Date(s.observed_at).toLocaleString() : "");
+      var credit = document.createElement("div");
+      credit.style.cssText = "font-size:10px;opacity:0.85;margin-top:4px";
+      credit.textContent = (s.attribution || []).length ? "Data: " +
+        s.attribution.map(function (a) { return a.provider; }).join(", ") : "";
+      box.appendChild(name);
+      box.appendChild(stage);
+      box.appendChild(status);
+      box.appendChild(credit);
     })
+    .catch(function () { box.textContent = "River status unavailable"; });
+})();
+"##;
+
+/// Handle GET /api/embed/summary
+fn handle_embed_summary(
+    client: &mut Client,
+    scheme: &SeverityScheme,
+    query: &HashMap<String, String>,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let site = query.get("site").map(String::as_str).unwrap_or(EMBED_DEFAULT_SITE);
+    match fetch_embed_summary(client, site, scheme) {
+        Ok(data) => with_public_cache(
+            create_response(200, serde_json::to_value(&data).unwrap()),
+            EMBED_SUMMARY_MAX_AGE,
+        ),
+        Err(e) if e.starts_with("Unknown site") => create_response(404, serde_json::json!({"error": e})),
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
 }
 
-/// Fetch overall basin status
-pub fn fetch_basin_status(client: &mut Client) -> Result<BasinStatusResponse, String> {
-    let _zones_config = zones::load_zones_default()
-        .map_err(|e| format!("Failed to load zones.toml: {}", e))?;
-    
-    let mut active_zones = Vec::new();
-    let mut overall_elevated = false;
-    let mut overall_watch = false;
-    let mut overall_warning = false;
-    
-    // Check each zone for activity
-    for zone_id in 0..=6 {
-        let zone_detail = fetch_zone_detail(client, zone_id)?;
-        
-        let zone_active = match zone_detail.zone_status.alert_level.as_str() {
-            "CRITICAL" => {
-                overall_warning = true;
-                true
-            }
-            "WARNING" => {
-                overall_watch = true;
-                true
-            }
-            "DEGRADED" | "NORMAL" => false,
-            _ => false,
-        };
-        
-        if zone_active || !zone_detail.zone_status.sensors_above_action.is_empty() {
-            overall_elevated = true;
-            let metadata = ZoneMetadata::for_zone(zone_id);
-            
-            active_zones.push(ActiveZoneStatus {
-                zone_id,
-                zone_name: zone_detail.zone_name.clone(),
-                status: zone_detail.zone_status.alert_level.clone(),
-                lead_time_hours: metadata.lead_time_hours_max,
-                key_sensors_elevated: zone_detail.zone_status.sensors_above_action,
-            });
+/// Handle GET /embed/widget.js
+fn handle_embed_widget() -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let response = tiny_http::Response::from_data(EMBED_WIDGET_JS.as_bytes().to_vec())
+        .with_status_code(tiny_http::StatusCode::from(200))
+        .with_header(
+            tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/javascript; charset=utf-8"[..]).unwrap()
+        );
+    with_public_cache(response, EMBED_SCRIPT_MAX_AGE)
+}
+
+/// Handle GET /api/snapshot: the status cache document as written
+fn handle_snapshot(snapshot: Option<&mut SnapshotReader>) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let Some(snapshot) = snapshot else {
+        return create_response(404, serde_json::json!({"error": "Status cache is not configured"}));
+    };
+    match snapshot.raw(Utc::now()) {
+        Some(json) => {
+            let response = tiny_http::Response::from_data(json.to_vec())
+                .with_status_code(tiny_http::StatusCode::from(200))
+                .with_header(tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap());
+            with_public_cache(response, EMBED_SUMMARY_MAX_AGE)
         }
+        None => create_response(503, serde_json::json!({"error": "No current status snapshot"})),
     }
-    
-    // Determine overall status
-    let overall_status = if overall_warning {
-        "FLOOD_WARNING"
-    } else if overall_watch {
-        "FLOOD_WATCH"
-    } else if overall_elevated {
-        "ELEVATED"
-    } else {
-        "NORMAL"
+}
+
+/// Cache lifetime for map layers; revalidated by ETag after that
+const MAP_LAYER_MAX_AGE: u32 = 3_600;
+/// Live GeoJSON changes with every poll
+const GEOJSON_MAX_AGE: u32 = 60;
+
+/// Handle GET /map/{name}.geojson: 304 when the client's copy is current
+fn handle_map_layer(
+    tiles: &[MapTile],
+    name: &str,
+    if_none_match: Option<&str>,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let Some(tile) = tiles.iter().find(|t| t.name == name) else {
+        return create_response(404, serde_json::json!({
+            "error": format!("No map layer {}", name),
+            "layers": tiles.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(),
+        }));
     };
-    
-    // Backwater risk analysis
-    let backwater_risk = analyze_backwater_risk(client)?;
-    
-    // Upstream flood pulse detection
-    let upstream_pulse = detect_upstream_flood_pulse(&active_zones);
-    
-    // Compound event risk
-    let zone_0_active = active_zones.iter().any(|z| z.zone_id == 0);
-    let zone_4_plus_active = active_zones.iter().any(|z| z.zone_id >= 4);
-    
-    let compound_risk = if zone_0_active && zone_4_plus_active {
-        "HIGH"
-    } else if zone_0_active || zone_4_plus_active {
-        "MODERATE"
+    let etag = tiny_http::Header::from_bytes(&b"ETag"[..], tile.etag.as_bytes()).unwrap();
+    let response = if if_none_match.is_some_and(|tags| tile.matches(tags)) {
+        tiny_http::Response::from_data(Vec::new()).with_status_code(tiny_http::StatusCode::from(304))
     } else {
-        "LOW"
+        tiny_http::Response::from_data(tile.body.clone())
+            .with_status_code(tiny_http::StatusCode::from(200))
+            .with_header(tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/geo+json"[..]).unwrap())
     };
-    
-    Ok(BasinStatusResponse {
-        overall_status: overall_status.to_string(),
-        active_zones,
-        backwater_risk,
-        upstream_flood_pulse: upstream_pulse,
-        compound_event_risk: compound_risk.to_string(),
-        last_updated: Utc::now(),
-    })
+    with_public_cache(response.with_header(etag), MAP_LAYER_MAX_AGE)
 }
 
-/// Analyze backwater flood risk
-fn analyze_backwater_risk(client: &mut Client) -> Result<BackwaterRiskResponse, String> {
-    // Fetch key sensors from Zone 0 (Mississippi) and Zone 1 (LaGrange)
-    let grafton_stage = fetch_cwms_stage(client, "Grafton", "GRFI2")?;
-    let lagrange_pool = fetch_cwms_stage(client, "LaGrange", "IL08P")?;
-    let lagrange_tailwater = fetch_cwms_stage(client, "LaGrange", "IL08TW")?;
-    
-    let differential = match (lagrange_pool, lagrange_tailwater) {
-        (Some(pool), Some(tw)) => Some(pool - tw),
-        _ => None,
-    };
-    
-    // Analyze risk level
-    let risk_level = match (grafton_stage, differential) {
-        (Some(grafton), Some(diff)) => {
-            if grafton > 25.0 && diff < 0.5 {
-                "CRITICAL"
-            } else if grafton > 20.0 && diff < 1.0 {
-                "HIGH"
-            } else if grafton > 18.0 || diff < 2.0 {
-                "MODERATE"
-            } else {
-                "LOW"
+/// Handle GET /api/geojson/stations and /api/geojson/zones
+fn handle_geojson(
+    client: &mut Client,
+    scheme: &SeverityScheme,
+    layer: &str,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let now = Utc::now();
+    let result = registry_at(client, None).and_then(|(stations, version)| {
+        let readings = archive::latest_known(client, None, false, now, Duration::days(LATEST_LOOKBACK_DAYS))?;
+        let statuses: Vec<geojson::StationStatus> = stations.iter()
+            .map(|station| geojson::station_status(station, &readings, now))
+            .collect();
+        match layer {
+            "stations" => Ok(geojson::stations_collection(&stations, &statuses, scheme, version, now)),
+            "zones" => {
+                let zones_config = zones::load_zones_default()
+                    .map_err(|e| format!("Failed to load zones.toml: {}", e))?;
+                Ok(geojson::zones_collection(&zones_config, &statuses, scheme, version, now))
             }
+            _ => Err(format!("Unknown GeoJSON layer {}", layer)),
         }
-        _ => "UNKNOWN",
-    };
-    
-    let explanation = format!(
-        "Backwater risk is {} based on Grafton stage ({:.1} ft) and LaGrange pool-tailwater differential ({:.1} ft). \
-         When Grafton exceeds 20ft and LaGrange differential drops below 1ft, Mississippi backwater is dominating Illinois River drainage.",
-        risk_level,
-        grafton_stage.unwrap_or(0.0),
-        differential.unwrap_or(99.0)
-    );
-    
-    Ok(BackwaterRiskResponse {
-        risk_level: risk_level.to_string(),
-        grafton_stage_ft: grafton_stage,
-        lagrange_pool_ft: lagrange_pool,
-        lagrange_tailwater_ft: lagrange_tailwater,
-        pool_tailwater_differential_ft: differential,
-        explanation,
-    })
+    });
+    match result {
+        Ok(collection) => with_public_cache(
+            create_typed_response(200, collection, "application/geo+json"),
+            GEOJSON_MAX_AGE,
+        ),
+        Err(e) if e.starts_with("Unknown GeoJSON layer") => create_response(404, serde_json::json!({
+            "error": e,
+            "layers": ["stations", "zones"],
+        })),
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
 }
 
-/// Detect upstream flood pulse
-fn detect_upstream_flood_pulse(active_zones: &[ActiveZoneStatus]) -> UpstreamFloodPulseResponse {
-    let upstream_active: Vec<usize> = active_zones.iter()
-        .filter(|z| z.zone_id >= 4)  // Zones 4, 5, 6
-        .map(|z| z.zone_id)
-        .collect();
-    
-    let pulse_detected = !upstream_active.is_empty();
-    
-    let estimated_arrival = if upstream_active.contains(&6) {
-        Some(72)  // 3 days from Chicago
-    } else if upstream_active.contains(&5) {
-        Some(48)  // 2 days from Dresden Island
-    } else if upstream_active.contains(&4) {
-        Some(24)  // 1 day from Starved Rock
-    } else {
-        None
+/// Handle GET /cams/snapshot/{id}: a stored webcam image
+fn handle_cam_snapshot(client: &mut Client, id: &str) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let id: i64 = match id.parse() {
+        Ok(id) => id,
+        Err(_) => return create_response(400, serde_json::json!({"error": format!("Invalid snapshot id: {}", id)})),
     };
-    
-    let explanation = if pulse_detected {
-        format!(
-            "Upstream flood pulse detected in zones: {}. Estimated arrival at property in {} hours.",
-            upstream_active.iter().map(|z| z.to_string()).collect::<Vec<_>>().join(", "),
-            estimated_arrival.unwrap_or(0)
-        )
-    } else {
-        "No upstream flood pulse detected in upper basin zones.".to_string()
-    };
-    
-    UpstreamFloodPulseResponse {
-        pulse_detected,
-        estimated_arrival_hours: estimated_arrival,
-        source_zones: upstream_active,
-        explanation,
+    match cams::load_image(client, id) {
+        Ok(Some((content_type, image))) => {
+            let response = tiny_http::Response::from_data(image)
+                .with_status_code(tiny_http::StatusCode::from(200))
+                .with_header(tiny_http::Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes()).unwrap());
+            with_public_cache(response, CAM_SNAPSHOT_MAX_AGE)
+        }
+        Ok(None) => create_response(404, serde_json::json!({"error": format!("Snapshot {} not found", id)})),
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
     }
 }
 
+/// Add cross-origin and public caching headers for embedded content
+fn with_public_cache(
+    response: tiny_http::Response<std::io::Cursor<Vec<u8>>>,
+    max_age: u32,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let cache_control = format!("public, max-age={}", max_age);
+    response
+        .with_header(tiny_http::Header::from_bytes(&b"Cache-Control"[..], cache_control.as_bytes()).unwrap())
+        .with_header(tiny_http::Header::from_bytes(&b"Access-Control-Allow-Origin"[..], &b"*"[..]).unwrap())
+}
+
 // ============================================================================
-// Helper Functions
+// HTTP Server
 // ============================================================================
 
-/// Fetch all recent USGS readings (last 4 hours)
-fn fetch_all_recent_readings(client: &mut Client) -> Result<Vec<GaugeReading>, String> {
-    let rows = client.query(
-        "SELECT DISTINCT ON (site_code, parameter_code)
-            site_code,
-            parameter_code,
-            unit,
-            value,
-            reading_time,
-            qualifier
-         FROM usgs_raw.gauge_readings
-         WHERE reading_time >= NOW() - INTERVAL '4 hours'
-         ORDER BY site_code, parameter_code, reading_time DESC",
-        &[]
-    ).map_err(|e| format!("Failed to fetch recent readings: {}", e))?;
-    
-    let mut readings = Vec::new();
-    
-    for row in rows {
-        let site_code: String = row.get(0);
-        let parameter_code: String = row.get(1);
-        let unit: String = row.get(2);
-        let value: rust_decimal::Decimal = row.get(3);
-        let reading_time: DateTime<Utc> = row.get(4);
-        let qualifier: String = row.get(5);
-        
-        readings.push(GaugeReading {
-            site_code: site_code.clone(),
-            site_name: site_code.clone(),  // Will be enriched later
-            parameter_code,
-            unit,
-            value: value.to_string().parse().unwrap_or(0.0),
-            datetime: reading_time.to_rfc3339(),
-            qualifier,
-        });
-    }
-    
-    Ok(readings)
+/// How the endpoint is exposed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServeOptions {
+    pub port: u16,
+    /// Serve HTTPS on `port` with this certificate (see `tls`)
+    pub tls: Option<TlsFiles>,
+    /// Peers whose X-Forwarded-* headers are believed
+    pub trusted_proxies: Vec<TrustedProxy>,
+    /// Zone DEM grids served at /zone/{zone_id}/inundation
+    pub inundation: Vec<InundationLayer>,
+    /// Token and aliases for POST /manual_readings; disabled when `None`
+    pub manual_readings: Option<ManualSettings>,
+    /// Token for POST /field_obs; disabled when `None`
+    pub field_obs: Option<FieldObsSettings>,
+    /// Zone occupancy for /api/occupancy; POST disabled without a token
+    pub occupancy: Option<OccupancySettings>,
+    /// Token for POST /api/suppressions; the POSTs are disabled when `None`
+    pub suppressions: Option<SuppressionSettings>,
+    /// Severity names and colors used by every response
+    pub severity: SeverityScheme,
+    /// Snapshot served in place of status queries; always queried when `None`
+    pub status_cache: Option<StatusCacheSettings>,
+    /// GeoJSON files precomputed at startup for /map (see `basin_map`)
+    pub map_layers: Vec<MapLayerSettings>,
+    /// Points given an interpolated stage at /api/profile
+    pub profile_points: Vec<ProfilePoint>,
+    /// Client limit and optional token for /api/stream
+    pub stream: StreamSettings,
 }
 
-/// Fetch sensor reading (for CWMS/ASOS sensors)
-fn fetch_sensor_reading(
-    client: &mut Client,
-    sensor: &zones::Sensor
-) -> Result<(Option<f64>, Option<String>, Option<String>, Option<i64>), String> {
-    
-    if sensor.is_cwms() {
-        // Query CWMS timeseries table
-        if let Some(cwms_loc) = &sensor.cwms_location {
-            let rows = client.query(
-                "SELECT value, unit, timestamp
-                 FROM usace.cwms_timeseries
-                 WHERE location_id = $1
-                 ORDER BY timestamp DESC
-                 LIMIT 1",
-                &[cwms_loc]
-            ).map_err(|e| format!("CWMS query failed: {}", e))?;
-            
-            if let Some(row) = rows.first() {
-                let value: rust_decimal::Decimal = row.get(0);
-                let unit: String = row.get(1);
-                let timestamp: DateTime<Utc> = row.get(2);
-                let staleness = (Utc::now() - timestamp).num_minutes();
-                
-                return Ok((
-                    Some(value.to_string().parse().unwrap_or(0.0)),
-                    Some(unit),
-                    Some(timestamp.to_rfc3339()),
-                    Some(staleness)
-                ));
-            }
-        }
-    } else if sensor.is_asos() {
-        // Query ASOS observations table
-        if let Some(station_id) = &sensor.station_id {
-            let rows = client.query(
-                "SELECT precip_1hr_in, observation_time
-                 FROM asos_observations
-                 WHERE station_id = $1
-                 ORDER BY observation_time DESC
-                 LIMIT 1",
-                &[station_id]
-            ).map_err(|e| format!("ASOS query failed: {}", e))?;
-            
-            if let Some(row) = rows.first() {
-                let value_opt: Option<f64> = row.get(0);
-                let timestamp: DateTime<Utc> = row.get(1);
-                let staleness = (Utc::now() - timestamp).num_minutes();
-                
-                if let Some(value) = value_opt {
-                    return Ok((
-                        Some(value),
-                        Some("in".to_string()),
-                        Some(timestamp.to_rfc3339()),
-                        Some(staleness)
-                    ));
-                }
-            }
+impl ServeOptions {
+    /// Plain HTTP on `port`, trusting forwarded headers from loopback only
+    pub fn plain(port: u16) -> Self {
+        Self {
+            port,
+            tls: None,
+            trusted_proxies: default_trusted_proxies(),
+            inundation: Vec::new(),
+            manual_readings: None,
+            field_obs: None,
+            occupancy: None,
+            suppressions: None,
+            severity: SeverityScheme::default(),
+            status_cache: None,
+            map_layers: Vec::new(),
+            profile_points: Vec::new(),
+            stream: StreamSettings::default(),
         }
     }
-    
-    Ok((None, None, None, None))
 }
 
-/// Fetch CWMS stage for a specific location
-fn fetch_cwms_stage(client: &mut Client, location_name: &str, _shef_id: &str) -> Result<Option<f64>, String> {
-    let rows = client.query(
-        "SELECT value
-         FROM usace.cwms_timeseries
-         WHERE location_id LIKE $1
-         ORDER BY timestamp DESC
-         LIMIT 1",
-        &[&format!("%{}%", location_name)]
-    ).map_err(|e| format!("CWMS stage query failed: {}", e))?;
-    
-    if let Some(row) = rows.first() {
-        let value: rust_decimal::Decimal = row.get(0);
-        Ok(Some(value.to_string().parse().unwrap_or(0.0)))
-    } else {
-        Ok(None)
-    }
+/// Start HTTP endpoint server on the specified port
+pub fn start_endpoint_server(port: u16, client: Client) -> Result<(), String> {
+    serve(&ServeOptions::plain(port), client)
 }
 
-// ============================================================================
-// HTTP Server
-// ============================================================================
-
-/// Start HTTP endpoint server on the specified port
-pub fn start_endpoint_server(port: u16, mut client: Client) -> Result<(), String> {
-    let server = tiny_http::Server::http(format!("0.0.0.0:{}", port))
-        .map_err(|e| format!("Failed to start HTTP server: {}", e))?;
+/// Start the endpoint server as described by `options`
+pub fn serve(options: &ServeOptions, mut client: Client) -> Result<(), String> {
+    let public = SocketAddr::from((Ipv4Addr::UNSPECIFIED, options.port));
+    let (server, tls_peers, scheme) = match &options.tls {
+        None => {
+            let server = tiny_http::Server::http(public)
+                .map_err(|e| format!("Failed to start HTTP server: {}", e))?;
+            (server, None, "http")
+        }
+        Some(files) => {
+            // HTTP on an ephemeral loopback port, TLS terminated in front of it
+            let config = tls::load_server_config(files)?;
+            let server = tiny_http::Server::http((Ipv4Addr::LOCALHOST, 0))
+                .map_err(|e| format!("Failed to start HTTP server: {}", e))?;
+            let backend = server.server_addr().to_ip()
+                .ok_or("HTTP server has no TCP address")?;
+            let peers = tls::spawn_terminator(public, backend, config)?;
+            (server, Some(peers), "https")
+        }
+    };
+    
+    // Grids are read once; a missing or malformed grid stops startup
+    let grids = options.inundation.iter()
+        .map(|layer| inundation::load_grid(&layer.grid).map(|grid| (layer.clone(), grid)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut snapshot = options.status_cache.clone().map(SnapshotReader::new);
+    let map_tiles = basin_map::build(&options.map_layers, &stations::load_stations())?;
+    let hub = Arc::new(Hub::new(options.stream.max_clients));
+    stream::spawn_listener(Arc::clone(&hub));
     
-    println!("📡 Zone-based HTTP endpoint listening on http://0.0.0.0:{}", port);
+    println!("📡 Zone-based HTTP endpoint listening on {}://0.0.0.0:{}", scheme, options.port);
     println!("   NEW ZONE-BASED ENDPOINTS:");
     println!("   GET /zones - List all zones with metadata");
-    println!("   GET /zone/{{zone_id}} - Get zone detail (0-6)");
+    println!("   GET /zone/{{zone_id}}?as_of= - Get zone detail (0-6)");
+    println!("   GET /zone/{{zone_id}}/inundation?stage= - Approximate flooded area (GeoJSON)");
     println!("   GET /status - Overall basin flood status");
     println!("   GET /backwater - Backwater flood analysis");
     println!("   GET /health - Service health check");
+    println!("   GET|POST /field_obs - Manual field observations");
+    println!("   GET /history - Readings across hot and archived storage");
+    println!("   GET /api/latest, /api/alerts - Latest readings and active alerts (as_of= for a past instant)");
+    println!("   GET /api/stations, /api/stations/{{site}}/latest, /api/zones, /api/alerts/active - Dashboard API");
+    println!("   GET /api/mode - Event mode and dashboard banner");
+    println!("   GET /api/lag - Upstream travel times per flow regime");
+    println!("   GET /api/profile - Water surface by river mile, interpolated stage at profile points");
+    println!("   GET /api/stream - Live readings and severity transitions (Server-Sent Events)");
+    println!("   GET|POST /api/occupancy - Zone occupancy flags");
+    println!("   GET|POST /manual_readings - Staff gauge readings reported by SMS/email");
+    println!("   GET /embed/widget.js, /api/embed/summary - Public status widget");
+    println!("   GET /api/snapshot - Current conditions and alerts (status cache)");
+    println!("   GET /cams/snapshot/{{id}} - Webcam image captured on a severity change");
+    println!("   GET /map/layers, /map/{{name}}.geojson - Basin map layers ({} precomputed)", map_tiles.len());
+    println!("   GET /api/geojson/stations, /api/geojson/zones - Live station and zone status as GeoJSON");
     println!("   ");
     println!("   DEPRECATED (but still functional):");
     println!("   GET /site/{{site_code}} - Single-site query (use /zone instead)\n");
     
-    for request in server.incoming_requests() {
-        let url = request.url();
+    for mut request in server.incoming_requests() {
+        let client_info = resolve_client(
+            request.remote_addr(),
+            header_value(&request, "X-Forwarded-For"),
+            header_value(&request, "X-Forwarded-Proto"),
+            &options.trusted_proxies,
+            tls_peers.as_ref(),
+        );
+        let direct_proxy = request.remote_addr()
+            .filter(|r| tls_peers.as_ref().and_then(|p| p.client_for(r)).is_none())
+            .is_some_and(|r| options.trusted_proxies.iter().any(|t| t.contains(r.ip())));
+        let request_id = choose_request_id(
+            header_value(&request, "X-Request-Id").filter(|_| direct_proxy),
+            Utc::now(),
+        );
+        let method = request.method().clone();
+        let (path, query) = split_query(request.url());
+        let url = path.as_str();
         
         // Route requests
-        let response = if url == "/health" {
+        let response = if url == "/api/stream" {
+            // Held open on a thread of its own; not a one-shot response
+            match open_stream(&options.stream, &hub, header_value(&request, "Authorization"), &query) {
+                Ok(subscription) => {
+                    logging::info(
+                        logging::DataSource::System,
+                        None,
+                        &format!("[{}] {} {} {} {} -> 200 (stream, {} open)", request_id, client_info,
+                            if client_info.https { "https" } else { "http" }, method, url, hub.len()),
+                    );
+                    std::thread::spawn(move || stream::serve_client(request, subscription, &request_id));
+                    continue;
+                }
+                Err(response) => response,
+            }
+        } else if url == "/field_obs" && *request.method() == tiny_http::Method::Post {
+            let authorization = header_value(&request, "Authorization").map(str::to_string);
+            match read_body(request.as_reader()) {
+                Ok(body) => handle_field_obs_create(&mut client, options.field_obs.as_ref(), authorization.as_deref(), &body),
+                Err(response) => response,
+            }
+        } else if url == "/manual_readings" && *request.method() == tiny_http::Method::Post {
+            let authorization = header_value(&request, "Authorization").map(str::to_string);
+            match read_body(request.as_reader()) {
+                Ok(body) => handle_manual_reading_create(&mut client, options.manual_readings.as_ref(), authorization.as_deref(), &body),
+                Err(response) => response,
+            }
+        } else if url == "/manual_readings" {
+            handle_manual_readings_list(&mut client, &query)
+        } else if url == "/field_obs" {
+            handle_field_obs_list(&mut client, &query)
+        } else if url == "/history" {
+            handle_history(&mut client, &query)
+        } else if url == "/embed/widget.js" {
+            handle_embed_widget()
+        } else if url == "/api/embed/summary" {
+            let site = query.get("site").map(String::as_str).unwrap_or(EMBED_DEFAULT_SITE);
+            match snapshot.as_mut().and_then(|s| s.station(site, Utc::now())) {
+                Some(summary) => with_public_cache(create_response(200, summary), EMBED_SUMMARY_MAX_AGE),
+                None => handle_embed_summary(&mut client, &options.severity, &query),
+            }
+        } else if url == "/api/snapshot" {
+            handle_snapshot(snapshot.as_mut())
+        } else if url == "/api/severity_scheme" {
+            with_public_cache(
+                create_response(200, serde_json::to_value(options.severity.to_response()).unwrap()),
+                SEVERITY_SCHEME_MAX_AGE,
+            )
+        } else if let Some(site) = url.strip_prefix("/api/stations/").and_then(|rest| rest.strip_suffix("/stats")) {
+            handle_station_stats(&mut client, site, &query)
+        } else if let Some(site) = url.strip_prefix("/api/stations/").and_then(|rest| rest.strip_suffix("/quality")) {
+            handle_station_quality(&mut client, site, &query)
+        } else if let Some(route) = DashboardRoute::parse(url, &query) {
+            api::handle(&mut client, route, &query, snapshot.as_mut())
+        } else if let Some(zone_id) = url.strip_prefix("/api/zones/").and_then(|rest| rest.strip_suffix("/status")) {
+            handle_zone_status(&mut client, &options.severity, zone_id)
+        } else if url == "/api/volume" {
+            handle_volume(&mut client, &query)
+        } else if url == "/api/mode" {
+            handle_event_mode(&mut client)
+        } else if url == "/api/occupancy" && *request.method() == tiny_http::Method::Post {
+            let authorization = header_value(&request, "Authorization").map(str::to_string);
+            match read_body(request.as_reader()) {
+                Ok(body) => handle_occupancy_update(&mut client, options.occupancy.as_ref(), authorization.as_deref(), &body),
+                Err(response) => response,
+            }
+        } else if url == "/api/occupancy" {
+            handle_occupancy(&mut client, options.occupancy.as_ref())
+        } else if url == "/api/suppressions" && *request.method() == tiny_http::Method::Post {
+            let authorization = header_value(&request, "Authorization").map(str::to_string);
+            match read_body(request.as_reader()) {
+                Ok(body) => handle_suppression_create(&mut client, options.suppressions.as_ref(), authorization.as_deref(), &body),
+                Err(response) => response,
+            }
+        } else if let Some(id) = url.strip_prefix("/api/suppressions/").and_then(|rest| rest.strip_suffix("/end"))
+            .filter(|_| *request.method() == tiny_http::Method::Post)
+        {
+            let authorization = header_value(&request, "Authorization");
+            handle_suppression_end(&mut client, options.suppressions.as_ref(), authorization, id)
+        } else if url == "/api/suppressions" {
+            handle_suppressions_list(&mut client, &query)
+        } else if url == "/api/expected_response" {
+            handle_expected_response(&mut client)
+        } else if url == "/api/lag" {
+            match lag::load(&mut client) {
+                Ok(estimates) => create_response(200, serde_json::json!({"estimates": estimates})),
+                Err(e) => create_response(500, serde_json::json!({"error": e})),
+            }
+        } else if url == "/api/profile" {
+            handle_profile(&mut client, &options.profile_points, &query)
+        } else if url == "/map/layers" {
+            with_public_cache(create_response(200, basin_map::index(&map_tiles)), MAP_LAYER_MAX_AGE)
+        } else if let Some(name) = url.strip_prefix("/map/").and_then(|rest| rest.strip_suffix(".geojson")) {
+            handle_map_layer(&map_tiles, name, header_value(&request, "If-None-Match"))
+        } else if let Some(layer) = url.strip_prefix("/api/geojson/") {
+            handle_geojson(&mut client, &options.severity, layer)
+        } else if let Some(id) = url.strip_prefix("/cams/snapshot/") {
+            handle_cam_snapshot(&mut client, id)
+        } else if url == "/health" {
             handle_health()
         } else if url == "/zones" {
             handle_zones_list(&mut client)
+        } else if let Some(zone_id) = url.strip_prefix("/zone/").and_then(|rest| rest.strip_suffix("/inundation")) {
+            handle_inundation(&mut client, &grids, &options.severity, zone_id, &query)
         } else if url.starts_with("/zone/") {
             let zone_id_str = url.trim_start_matches("/zone/");
-            handle_zone_detail(&mut client, zone_id_str)
+            handle_zone_detail(&mut client, zone_id_str, &query)
         } else if url == "/status" {
-            handle_basin_status(&mut client)
+            match snapshot.as_mut().and_then(|s| s.basin_status(Utc::now())) {
+                Some(status) => create_response(200, status),
+                None => handle_basin_status(&mut client),
+            }
         } else if url == "/backwater" {
             handle_backwater_analysis(&mut client)
         } else if url.starts_with("/site/") {
@@ -637,16 +623,63 @@ pub fn start_endpoint_server(port: u16, mut client: Client) -> Result<(), String
                     "error": "Not found",
                     "available_endpoints": {
                         "zones": "/zones",
-                        "zone_detail": "/zone/{zone_id}",
+                        "zone_detail": "/zone/{zone_id}?as_of={rfc3339}",
+                        "zone_inundation": "/zone/{zone_id}/inundation?stage={ft}",
                         "basin_status": "/status",
                         "backwater_analysis": "/backwater",
                         "health": "/health",
+                        "field_obs": "/field_obs",
+                        "manual_readings": "/manual_readings?site={site_code}&start={rfc3339}&end={rfc3339}",
+                        "history": "/history?site={site_code}&param={parameter_code}&start={rfc3339}&end={rfc3339}",
+                        "embed_widget": "/embed/widget.js",
+                        "severity_scheme": "/api/severity_scheme",
+                        "embed_summary": "/api/embed/summary?site={site_code}",
+                        "snapshot": "/api/snapshot",
+                        "latest": "/api/latest?site={site_code}&as_of={rfc3339}",
+                        "alerts": "/api/alerts?as_of={rfc3339}",
+                        "stations": "/api/stations?as_of={rfc3339}",
+                        "station_latest": "/api/stations/{site_code}/latest?as_of={rfc3339}",
+                        "api_zones": "/api/zones",
+                        "zone_status": "/api/zones/{zone_id}/status",
+                        "active_alerts": "/api/alerts/active",
+                        "event_mode": "/api/mode",
+                        "occupancy": "/api/occupancy",
+                        "suppressions": "/api/suppressions?all={bool}",
+                        "expected_response": "/api/expected_response",
+                        "lag": "/api/lag",
+                        "profile": "/api/profile?river_mile={mile}",
+                        "stream": "/api/stream?site={site_code},...&token={token}",
+                        "volume": "/api/volume?start={rfc3339}&end={rfc3339} or ?event={event_id}",
+                        "station_stats": "/api/stations/{site_code}/stats?param={parameter_code}&start={rfc3339}&end={rfc3339}",
+                        "station_quality": "/api/stations/{site_code}/quality?start={rfc3339}&end={rfc3339}",
+                        "cam_snapshot": "/cams/snapshot/{id}",
+                        "map_layers": "/map/layers",
+                        "map_layer": "/map/{name}.geojson",
+                        "geojson_stations": "/api/geojson/stations",
+                        "geojson_zones": "/api/geojson/zones",
                         "deprecated_site_query": "/site/{site_code}"
                     }
                 })
             )
         };
         
+        let response = attribute(response, attribution::for_path(url));
+        let (response, error) = stamp_request_id(response, &request_id);
+        let status = response.status_code().0;
+        logging::info(
+            logging::DataSource::System,
+            None,
+            &format!("[{}] {} {} {} {} -> {}", request_id, client_info, if client_info.https { "https" } else { "http" },
+                method, url, status),
+        );
+        if let Some(error) = error.filter(|_| status >= 500) {
+            logging::error(logging::DataSource::System, None,
+                &format!("[{}] {} {} failed with {}: {}", request_id, method, url, status, error));
+            let client_ip = client_info.ip.map(|ip| ip.to_string());
+            if let Err(e) = record_api_error(&mut client, &request_id, &method.to_string(), url, status, &error, client_ip.as_deref()) {
+                eprintln!("[{}] {}", request_id, e);
+            }
+        }
         if let Err(e) = request.respond(response) {
             eprintln!("Failed to send response: {}", e);
         }
@@ -655,6 +688,279 @@ pub fn start_endpoint_server(port: u16, mut client: Client) -> Result<(), String
     Ok(())
 }
 
+// ============================================================================
+// Live stream
+// ============================================================================
+
+/// Admit a client to /api/stream, or the refusal: 401 without the
+/// configured token, 503 while `max_clients` are connected
+fn open_stream(
+    settings: &StreamSettings,
+    hub: &Hub,
+    authorization: Option<&str>,
+    query: &HashMap<String, String>,
+) -> Result<Subscription, tiny_http::Response<std::io::Cursor<Vec<u8>>>> {
+    if !settings.authorized(authorization, query.get("token").map(String::as_str)) {
+        return Err(create_response(401, serde_json::json!({"error": "Missing or invalid stream token"})));
+    }
+    let sites = query.get("site")
+        .map(|s| s.split(',').filter(|s| !s.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default();
+    hub.subscribe(sites).ok_or_else(|| create_response(503, serde_json::json!({
+        "error": format!("Stream is at its limit of {} clients; try again later", settings.max_clients),
+    })))
+}
+
+// ============================================================================
+// Request bodies
+// ============================================================================
+
+/// Largest body a POST route reads; the write routes take small JSON
+/// documents, and an unbounded read would let a client exhaust memory
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+/// A POST body as text, or the error response: 413 past `MAX_BODY_BYTES`,
+/// 400 when it cannot be read
+fn read_body(reader: impl Read) -> Result<String, tiny_http::Response<std::io::Cursor<Vec<u8>>>> {
+    let mut body = String::new();
+    reader.take(MAX_BODY_BYTES + 1).read_to_string(&mut body)
+        .map_err(|e| create_response(400, serde_json::json!({"error": format!("Failed to read body: {}", e)})))?;
+    if body.len() as u64 > MAX_BODY_BYTES {
+        return Err(create_response(413, serde_json::json!({
+            "error": format!("Body is larger than {} bytes", MAX_BODY_BYTES),
+        })));
+    }
+    Ok(body)
+}
+
+// ============================================================================
+// Request IDs
+// ============================================================================
+
+/// Sequence number distinguishing requests within one millisecond
+static REQUEST_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Longest forwarded `X-Request-Id` reused as-is
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// ID for a request: a forwarded ID (from a trusted proxy) when it is short
+/// and plain, so proxy and flomon logs line up; otherwise a new one made of
+/// the time in milliseconds and a sequence number, in hex
+fn choose_request_id(forwarded: Option<&str>, now: DateTime<Utc>) -> String {
+    let plain = |id: &&str| {
+        !id.is_empty()
+            && id.len() <= MAX_REQUEST_ID_LEN
+            && id.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+    };
+    match forwarded.map(str::trim).filter(plain) {
+        Some(id) => id.to_string(),
+        None => {
+            let sequence = REQUEST_SEQUENCE.fetch_add(1, Ordering::Relaxed) & 0xffff;
+            format!("{:012x}-{:04x}", now.timestamp_millis(), sequence)
+        }
+    }
+}
+
+/// Add `attribution` for `providers` to a successful JSON object response
+/// (see `attribution`); anything else is passed through untouched
+fn attribute(
+    response: tiny_http::Response<std::io::Cursor<Vec<u8>>>,
+    providers: &[attribution::Provider],
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let status = response.status_code();
+    let is_json = response.headers().iter()
+        .any(|h| h.field.equiv("Content-Type") && h.value.as_str().starts_with("application/json"));
+    if providers.is_empty() || !is_json || !(200..300).contains(&status.0) {
+        return response;
+    }
+    
+    let headers = response.headers().to_vec();
+    let body = response.into_reader().into_inner();
+    let body = match serde_json::from_slice(&body) {
+        Ok(value @ serde_json::Value::Object(_)) => {
+            serde_json::to_string_pretty(&attribution::attach(value, providers)).unwrap().into_bytes()
+        }
+        _ => body,
+    };
+    let length = body.len();
+    tiny_http::Response::new(status, headers, std::io::Cursor::new(body), Some(length), None)
+}
+
+/// Add the `X-Request-Id` header, and for JSON error responses the
+/// `ApiErrorResponse` fields. Returns the error message of an error
+/// response, for the logs.
+fn stamp_request_id(
+    response: tiny_http::Response<std::io::Cursor<Vec<u8>>>,
+    request_id: &str,
+) -> (tiny_http::Response<std::io::Cursor<Vec<u8>>>, Option<String>) {
+    let id_header = tiny_http::Header::from_bytes(&b"X-Request-Id"[..], request_id.as_bytes()).unwrap();
+    let status = response.status_code();
+    let is_json = response.headers().iter()
+        .any(|h| h.field.equiv("Content-Type") && h.value.as_str().starts_with("application/json"));
+    if status.0 < 400 {
+        return (response.with_header(id_header), None);
+    }
+    
+    let mut headers = response.headers().to_vec();
+    let body = response.into_reader().into_inner();
+    let parsed = if is_json { serde_json::from_slice(&body).ok() } else { None };
+    let (body, error) = match parsed {
+        Some(serde_json::Value::Object(mut fields)) => {
+            let error = fields.get("error").and_then(|e| e.as_str())
+                .unwrap_or(status.default_reason_phrase())
+                .to_string();
+            let stamped = ApiErrorResponse { error: error.clone(), status: status.0, request_id: request_id.to_string() };
+            if let Ok(serde_json::Value::Object(stamped)) = serde_json::to_value(&stamped) {
+                fields.extend(stamped);
+            }
+            (serde_json::to_string_pretty(&fields).unwrap().into_bytes(), error)
+        }
+        // Non-JSON error bodies (rare) are passed through untouched
+        _ => (body, status.default_reason_phrase().to_string()),
+    };
+    headers.push(id_header);
+    let length = body.len();
+    let response = tiny_http::Response::new(status, headers, std::io::Cursor::new(body), Some(length), None);
+    (response, Some(error))
+}
+
+/// Record a 5xx response in `api_errors`
+fn record_api_error(
+    client: &mut Client,
+    request_id: &str,
+    method: &str,
+    path: &str,
+    status: u16,
+    error: &str,
+    client_ip: Option<&str>,
+) -> Result<(), String> {
+    client.execute(
+        "INSERT INTO api_errors (request_id, method, path, status, error, client_ip)
+         VALUES ($1, $2, $3, $4, $5, $6)",
+        &[&request_id, &method, &path, &(status as i32), &error, &client_ip],
+    ).map(|_| ()).map_err(|e| format!("Failed to record API error: {}", e))
+}
+
+// ============================================================================
+// Client address
+// ============================================================================
+
+/// A peer whose forwarded headers are believed: one address or a CIDR block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrustedProxy {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl TrustedProxy {
+    /// Parse "10.0.0.5", "172.16.0.0/12" or "::1"
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (addr, prefix) = match s.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s.trim(), None),
+        };
+        let network: IpAddr = addr.parse()
+            .map_err(|_| format!("invalid trusted proxy address {:?}", s))?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p.parse().ok().filter(|p| *p <= max)
+                .ok_or_else(|| format!("invalid prefix length in trusted proxy {:?}", s))?,
+            None => max,
+        };
+        Ok(Self { network, prefix })
+    }
+
+    /// Whether `ip` falls inside this address or block
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let mask_matches = |a: u128, b: u128, bits: u32| {
+            let shift = bits - self.prefix as u32;
+            shift >= bits || (a >> shift) == (b >> shift)
+        };
+        match (self.network, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => mask_matches(u32::from(net) as u128, u32::from(ip) as u128, 32),
+            (IpAddr::V6(net), IpAddr::V6(ip)) => mask_matches(u128::from(net), u128::from(ip), 128),
+            _ => false,
+        }
+    }
+}
+
+/// Loopback only: a proxy on the same host (e.g. Caddy)
+pub fn default_trusted_proxies() -> Vec<TrustedProxy> {
+    vec![
+        TrustedProxy { network: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 0)), prefix: 8 },
+        TrustedProxy { network: IpAddr::V6(Ipv6Addr::LOCALHOST), prefix: 128 },
+    ]
+}
+
+/// Where a request really came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientInfo {
+    pub ip: Option<IpAddr>,
+    pub https: bool,
+}
+
+impl std::fmt::Display for ClientInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.ip {
+            Some(ip) => write!(f, "{}", ip),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+/// First value of a request header
+fn header_value<'a>(request: &'a tiny_http::Request, name: &'static str) -> Option<&'a str> {
+    request.headers().iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+/// Resolve the client behind `remote`.
+///
+/// A connection from our own TLS terminator maps back to the TLS client.
+/// If the peer is a trusted proxy, `X-Forwarded-For` is walked from the
+/// right (nearest hop) past further trusted proxies; the first untrusted
+/// hop is the client, and `X-Forwarded-Proto` gives the scheme.
+fn resolve_client(
+    remote: Option<&SocketAddr>,
+    forwarded_for: Option<&str>,
+    forwarded_proto: Option<&str>,
+    trusted: &[TrustedProxy],
+    tls_peers: Option<&TlsPeers>,
+) -> ClientInfo {
+    let terminated = remote.and_then(|r| tls_peers.and_then(|p| p.client_for(r)));
+    let mut info = ClientInfo {
+        ip: terminated.or(remote.copied()).map(|a| a.ip()),
+        https: terminated.is_some(),
+    };
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|t| t.contains(ip));
+
+    let Some(peer) = info.ip.filter(|ip| is_trusted(*ip)) else {
+        return info;
+    };
+    let Some(forwarded_for) = forwarded_for else {
+        return info;
+    };
+    let mut client = peer;
+    for hop in forwarded_for.rsplit(',') {
+        match hop.trim().parse::<IpAddr>() {
+            Ok(ip) => {
+                client = ip;
+                if !is_trusted(ip) {
+                    break;
+                }
+            }
+            // Unparseable hop: stop at the last address we could verify
+            Err(_) => break,
+        }
+    }
+    info.ip = Some(client);
+    if let Some(proto) = forwarded_proto.and_then(|p| p.rsplit(',').next()) {
+        info.https = proto.trim().eq_ignore_ascii_case("https");
+    }
+    info
+}
+
 /// Handle /health endpoint
 fn handle_health() -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
     create_response(
@@ -677,7 +983,11 @@ fn handle_zones_list(client: &mut Client) -> tiny_http::Response<std::io::Cursor
 }
 
 /// Handle /zone/{zone_id} endpoint
-fn handle_zone_detail(client: &mut Client, zone_id_str: &str) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+fn handle_zone_detail(
+    client: &mut Client,
+    zone_id_str: &str,
+    query: &HashMap<String, String>,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
     let zone_id: usize = match zone_id_str.parse() {
         Ok(id) if id <= 6 => id,
         _ => return create_response(
@@ -689,12 +999,132 @@ fn handle_zone_detail(client: &mut Client, zone_id_str: &str) -> tiny_http::Resp
         ),
     };
     
-    match fetch_zone_detail(client, zone_id) {
+    let as_of = match as_of_param(query, Utc::now()) {
+        Ok(as_of) => as_of,
+        Err(e) => return create_response(400, serde_json::json!({"error": e})),
+    };
+    
+    match fetch_zone_detail(client, zone_id, as_of) {
         Ok(data) => create_response(200, serde_json::to_value(&data).unwrap()),
         Err(e) => create_response(500, serde_json::json!({"error": e})),
     }
 }
 
+/// Handle GET /api/zones/{zone_id}/status: the status the daemon recorded
+/// for the zone in its last cycle
+fn handle_zone_status(
+    client: &mut Client,
+    scheme: &SeverityScheme,
+    zone_id_str: &str,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let zone_id: usize = match zone_id_str.parse() {
+        Ok(id) if id <= 6 => id,
+        _ => return create_response(400, serde_json::json!({
+            "error": "Invalid zone_id. Must be 0-6.",
+            "valid_zones": [0, 1, 2, 3, 4, 5, 6]
+        })),
+    };
+    let status = match zones::status::latest(client, zone_id) {
+        Ok(Some(status)) => status,
+        Ok(None) => return create_response(404, serde_json::json!({
+            "error": format!("No status recorded for zone {} yet", zone_id),
+        })),
+        Err(e) => return create_response(500, serde_json::json!({"error": e})),
+    };
+    let style = scheme.style_for(status.severity.as_ref(), status.severity.is_none() && status.all_stale());
+    
+    create_response(200, serde_json::json!({
+        "zone_id": zone_id,
+        "name": ZoneMetadata::for_zone(zone_id).name,
+        "computed_at": status.computed_at,
+        "age_minutes": (Utc::now() - status.computed_at).num_minutes(),
+        "severity": style.key,
+        "severity_label": style.label,
+        "severity_color": style.color,
+        "severity_site": status.severity_site,
+        "max_rise_ft_per_hour": status.max_rise_ft_per_hour,
+        "max_rise_site": status.max_rise_site,
+        "sensor_count": status.sensor_count,
+        "reporting_count": status.reporting_count(),
+        "stale_sensors": status.stale_sensors,
+        "precip": status.precip,
+    }))
+}
+
+/// Handle GET /zone/{zone_id}/inundation
+///
+/// Uses `stage` (ft) when given, otherwise the reference gauge's latest stage.
+fn handle_inundation(
+    client: &mut Client,
+    grids: &[(InundationLayer, DemGrid)],
+    scheme: &SeverityScheme,
+    zone_id_str: &str,
+    query: &HashMap<String, String>,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let Some((layer, grid)) = zone_id_str.parse::<usize>().ok()
+        .and_then(|id| grids.iter().find(|(layer, _)| layer.zone_id == id))
+    else {
+        return create_response(404, serde_json::json!({
+            "error": format!("No inundation grid configured for zone {}", zone_id_str),
+            "zones_with_grids": grids.iter().map(|(layer, _)| layer.zone_id).collect::<Vec<_>>(),
+        }));
+    };
+    
+    let (stage_ft, observed_at, source) = match query.get("stage") {
+        Some(stage) => match stage.parse::<f64>() {
+            Ok(stage) if stage.is_finite() => (stage, None, "projected"),
+            _ => return create_response(400, serde_json::json!({"error": format!("Invalid stage: {}", stage)})),
+        },
+        None => match client.query_opt(
+            "SELECT value, reading_time FROM usgs_raw.gauge_readings
+             WHERE site_code = $1 AND parameter_code = '00065'
+             ORDER BY reading_time DESC LIMIT 1",
+            &[&layer.reference_site],
+        ) {
+            Ok(Some(row)) => {
+                let value: rust_decimal::Decimal = row.get(0);
+                let time: DateTime<Utc> = row.get(1);
+                (value.to_string().parse().unwrap_or(0.0), Some(time), "observed")
+            }
+            Ok(None) => return create_response(404, serde_json::json!({
+                "error": format!("No stage readings for {}; pass ?stage=", layer.reference_site),
+            })),
+            Err(e) => return create_response(500, serde_json::json!({"error": format!("Database error: {}", e)})),
+        },
+    };
+    
+    let mut properties = serde_json::Map::new();
+    properties.insert("zone_id".to_string(), serde_json::json!(layer.zone_id));
+    properties.insert("reference_site".to_string(), serde_json::json!(layer.reference_site));
+    properties.insert("stage_ft".to_string(), serde_json::json!(stage_ft));
+    properties.insert("stage_source".to_string(), serde_json::json!(source));
+    properties.insert("observed_at".to_string(), serde_json::json!(observed_at));
+    properties.insert("datum".to_string(), serde_json::json!(layer.datum));
+    // Severity the reference gauge would be at for this stage
+    if let Some(station) = stations::find_station(&layer.reference_site) {
+        let reading = GaugeReading {
+            site_code: station.site_code.clone(),
+            site_name: station.name.clone(),
+            parameter_code: stations::PARAM_STAGE.to_string(),
+            unit: "ft".to_string(),
+            value: stage_ft,
+            datetime: observed_at.unwrap_or_else(Utc::now).fixed_offset(),
+            qualifier: "P".to_string(),
+            original_unit: None,
+        };
+        let style = scheme.style_for(check_station(&station, &reading).map(|a| a.severity).as_ref(), false);
+        properties.insert("severity".to_string(), serde_json::json!(style.key));
+        properties.insert("severity_label".to_string(), serde_json::json!(style.label));
+        properties.insert("severity_color".to_string(), serde_json::json!(style.color));
+    }
+    let feature = inundation::inundation(grid, layer.water_surface_ft(stage_ft)).to_geojson(properties);
+    
+    create_typed_response(200, serde_json::json!({
+        "type": "FeatureCollection",
+        "features": [feature],
+    }), "application/geo+json")
+}
+
 /// Handle /status endpoint
 fn handle_basin_status(client: &mut Client) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
     match fetch_basin_status(client) {
@@ -711,6 +1141,552 @@ fn handle_backwater_analysis(client: &mut Client) -> tiny_http::Response<std::io
     }
 }
 
+/// Longest span /history returns in one request
+const HISTORY_MAX_DAYS: i64 = 366;
+
+/// Handle GET /history
+fn handle_history(client: &mut Client, query: &HashMap<String, String>) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let (site, parameter, start, end) = match history_params(query, Utc::now()) {
+        Ok(params) => params,
+        Err(e) => return create_response(400, serde_json::json!({"error": e})),
+    };
+    let readings = match archive::readings_between(client, &site, &parameter, start, end) {
+        Ok(readings) => readings,
+        Err(e) => return create_response(500, serde_json::json!({"error": e})),
+    };
+    let mut body = serde_json::json!({
+        "site_code": site,
+        "parameter_code": parameter,
+        "start": start,
+        "end": end,
+        "count": readings.len(),
+        "readings": readings.iter().map(|r| serde_json::json!({
+            "datetime": r.datetime,
+            "value": r.value,
+            "unit": r.unit,
+            "qualifier": r.qualifier,
+        })).collect::<Vec<_>>(),
+    });
+    // Manual reports stay separate from the gauge series
+    if query.get("include_manual").is_some_and(|v| v == "true") && parameter == "00065" {
+        match manual_readings::list(client, Some(&site), start, end, None) {
+            Ok(manual) => body["manual_readings"] = serde_json::json!(manual),
+            Err(e) => return create_response(500, serde_json::json!({"error": e})),
+        }
+    }
+    create_response(200, body)
+}
+
+/// Handle GET /api/stations/{site}/stats
+fn handle_station_stats(
+    client: &mut Client,
+    site: &str,
+    query: &HashMap<String, String>,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let Some(station) = stations::find_station(site) else {
+        return create_response(404, serde_json::json!({"error": format!("Unknown site {}", site)}));
+    };
+    let parameter = query.get("param").cloned().unwrap_or_else(|| stations::PARAM_STAGE.to_string());
+    let (start, end) = match time_range(query, Utc::now()) {
+        Ok(range) => range,
+        Err(e) => return create_response(400, serde_json::json!({"error": e})),
+    };
+    let readings = match archive::readings_between(client, site, &parameter, start, end) {
+        Ok(readings) => readings,
+        Err(e) => return create_response(500, serde_json::json!({"error": e})),
+    };
+    let series: Vec<(DateTime<Utc>, f64)> = readings.iter()
+        .map(|r| (r.datetime.with_timezone(&Utc), r.value))
+        .collect();
+    // NWS thresholds are stage levels
+    let levels = match &station.thresholds {
+        Some(thresholds) if parameter == stations::PARAM_STAGE => window_stats::threshold_levels(thresholds),
+        _ => Vec::new(),
+    };
+    
+    create_response(200, serde_json::json!({
+        "site_code": site,
+        "parameter_code": parameter,
+        "unit": readings.first().map(|r| r.unit.clone()),
+        "start": start,
+        "end": end,
+        "stats": window_stats::summarize(&series, &levels),
+    }))
+}
+
+/// Handle GET /api/stations/{site}/quality: how the station's stage and
+/// discharge readings in the window grade, and how many alerts may act on
+fn handle_station_quality(
+    client: &mut Client,
+    site: &str,
+    query: &HashMap<String, String>,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    if stations::find_station(site).is_none() {
+        return create_response(404, serde_json::json!({"error": format!("Unknown site {}", site)}));
+    }
+    let (start, end) = match time_range(query, Utc::now()) {
+        Ok(range) => range,
+        Err(e) => return create_response(400, serde_json::json!({"error": e})),
+    };
+    let mut parameters = serde_json::Map::new();
+    for parameter in [stations::PARAM_STAGE, stations::PARAM_DISCHARGE] {
+        let readings = match archive::readings_between(client, site, parameter, start, end) {
+            Ok(readings) => readings,
+            Err(e) => return create_response(500, serde_json::json!({"error": e})),
+        };
+        let stats = quality::summarize(&readings);
+        let counts: serde_json::Map<String, serde_json::Value> = stats.counts.iter()
+            .map(|(flag, n)| (flag.as_str().to_string(), serde_json::json!(n)))
+            .collect();
+        parameters.insert(parameter.to_string(), serde_json::json!({
+            "total": stats.total,
+            "counts": counts,
+            "alertable": stats.alertable(),
+            "alertable_fraction": stats.alertable_fraction(),
+        }));
+    }
+    
+    create_response(200, serde_json::json!({
+        "site_code": site,
+        "start": start,
+        "end": end,
+        "parameters": parameters,
+    }))
+}
+
+/// Handle GET /api/volume: `event` (a flood_analysis.events ID) sets the
+/// window, otherwise `start`/`end`
+fn handle_volume(client: &mut Client, query: &HashMap<String, String>) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let now = Utc::now();
+    let window = match query.get("event") {
+        Some(id) => match id.parse::<i32>() {
+            Ok(id) => match volume::event_window(client, id, now) {
+                Ok(Some(window)) => Ok(window),
+                Ok(None) => return create_response(404, serde_json::json!({"error": format!("Unknown event {}", id)})),
+                Err(e) => return create_response(500, serde_json::json!({"error": e})),
+            },
+            Err(_) => Err("event must be an integer".to_string()),
+        },
+        None => time_range(query, now),
+    };
+    let (start, end) = match window {
+        Ok(window) => window,
+        Err(e) => return create_response(400, serde_json::json!({"error": e})),
+    };
+    
+    match volume::compute(client, &stations::load_stations(), start, end) {
+        Ok(account) => create_response(200, serde_json::json!({
+            "start": start,
+            "end": end,
+            "volume": account,
+        })),
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
+}
+
+/// Handle GET /api/mode: the daemon's current event mode. Normal, with no
+/// banner, until the first change is recorded.
+fn handle_event_mode(client: &mut Client) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    match event_mode::load_current(client) {
+        Ok(current) => {
+            let (mode, since, reason) = match current {
+                Some((mode, since, reason)) => (mode, Some(since), Some(reason)),
+                None => (Mode::Normal, None, None),
+            };
+            create_response(200, serde_json::json!({
+                "mode": mode,
+                "since": since,
+                "reason": reason,
+                "banner": mode.banner(),
+            }))
+        }
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
+}
+
+/// Handle GET /api/occupancy: each configured zone, its stored flag and
+/// whether alerts for it currently go out at full urgency
+fn handle_occupancy(client: &mut Client, settings: Option<&OccupancySettings>) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let Some(settings) = settings else {
+        return create_response(404, serde_json::json!({"error": "Occupancy is not configured ([occupancy] in flomon.toml)"}));
+    };
+    let flags = match occupancy::load_flags(client) {
+        Ok(flags) => flags,
+        Err(e) => return create_response(500, serde_json::json!({"error": e})),
+    };
+    let flag_map = occupancy::flag_map(&flags);
+    let local = Utc::now().with_timezone(&chrono::Local).naive_local();
+    let zones: Vec<serde_json::Value> = settings.zones.iter()
+        .map(|zone| serde_json::json!({
+            "zone_id": zone.zone_id,
+            "primary": zone.primary,
+            "scheduled": zone.schedule.iter().any(|w| w.contains(local)),
+            "flag": flags.iter().find(|f| f.zone_id == zone.zone_id),
+            "occupied": settings.is_occupied(zone.zone_id, &flag_map, local),
+        }))
+        .collect();
+    create_response(200, serde_json::json!({"zones": zones}))
+}
+
+/// Body of POST /api/occupancy; `occupied: null` clears the flag
+#[derive(Debug, serde::Deserialize)]
+struct OccupancyUpdate {
+    zone_id: usize,
+    occupied: Option<bool>,
+}
+
+/// Handle POST /api/occupancy
+fn handle_occupancy_update(
+    client: &mut Client,
+    settings: Option<&OccupancySettings>,
+    authorization: Option<&str>,
+    body: &str,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let Some(settings) = settings.filter(|s| s.token.is_some()) else {
+        return create_response(404, serde_json::json!({"error": "Occupancy updates are not enabled (occupancy.token in flomon.toml)"}));
+    };
+    if !settings.authorized(authorization) {
+        return create_response(401, serde_json::json!({"error": "Missing or invalid bearer token"}));
+    }
+    let update: OccupancyUpdate = match serde_json::from_str(body) {
+        Ok(u) => u,
+        Err(e) => return create_response(400, serde_json::json!({"error": format!("Invalid occupancy update: {}", e)})),
+    };
+    if settings.zone(update.zone_id).is_none() {
+        return create_response(422, serde_json::json!({"error": format!("Zone {} is not listed under [occupancy]", update.zone_id)}));
+    }
+    match occupancy::set_flag(client, update.zone_id, update.occupied, occupancy::SOURCE_API, Utc::now()) {
+        Ok(()) => {
+            logging::info(logging::DataSource::System, None, &format!(
+                "Zone {} {} via API", update.zone_id,
+                match update.occupied { Some(true) => "occupied", Some(false) => "vacant", None => "back on its schedule" }));
+            create_response(200, serde_json::json!({"zone_id": update.zone_id, "occupied": update.occupied}))
+        }
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
+}
+
+/// Handle GET /api/suppressions: those in effect, or with `all=true` the
+/// latest ones including those that ended
+fn handle_suppressions_list(client: &mut Client, query: &HashMap<String, String>) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let all = query.get("all").is_some_and(|v| v == "true" || v == "1");
+    let result = if all { suppression::recent(client) } else { suppression::active(client, Utc::now()) };
+    match result {
+        Ok(suppressions) => create_response(200, serde_json::json!({"suppressions": suppressions})),
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
+}
+
+/// Handle POST /api/suppressions
+fn handle_suppression_create(
+    client: &mut Client,
+    settings: Option<&SuppressionSettings>,
+    authorization: Option<&str>,
+    body: &str,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let Some(settings) = settings.filter(|s| s.token.is_some()) else {
+        return create_response(404, serde_json::json!({"error": "Suppressions are not enabled (suppressions.token in flomon.toml)"}));
+    };
+    if !settings.authorized(authorization) {
+        return create_response(401, serde_json::json!({"error": "Missing or invalid bearer token"}));
+    }
+    let new: NewSuppression = match serde_json::from_str(body) {
+        Ok(n) => n,
+        Err(e) => return create_response(400, serde_json::json!({"error": format!("Invalid suppression: {}", e)})),
+    };
+    let now = Utc::now();
+    if let Err(e) = new.validate(now) {
+        return create_response(422, serde_json::json!({"error": format!("Invalid suppression: {}", e)}));
+    }
+    match suppression::create(client, &new, now) {
+        Ok(created) => {
+            logging::info(created.source.data_source(), Some(&created.station_id), &format!(
+                "{}{} set via API", created.label(),
+                created.parameter_code.as_ref().map(|p| format!(" for {}", p)).unwrap_or_default()));
+            create_response(201, serde_json::to_value(&created).unwrap())
+        }
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
+}
+
+/// Handle POST /api/suppressions/{id}/end
+fn handle_suppression_end(
+    client: &mut Client,
+    settings: Option<&SuppressionSettings>,
+    authorization: Option<&str>,
+    id: &str,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let Some(settings) = settings.filter(|s| s.token.is_some()) else {
+        return create_response(404, serde_json::json!({"error": "Suppressions are not enabled (suppressions.token in flomon.toml)"}));
+    };
+    if !settings.authorized(authorization) {
+        return create_response(401, serde_json::json!({"error": "Missing or invalid bearer token"}));
+    }
+    let Ok(id) = id.parse::<i64>() else {
+        return create_response(400, serde_json::json!({"error": format!("Invalid suppression id '{}'", id)}));
+    };
+    match suppression::end(client, id, Utc::now()) {
+        Ok(Some(ended)) => {
+            logging::info(ended.source.data_source(), Some(&ended.station_id), &format!("{} ended via API", ended.label()));
+            create_response(200, serde_json::to_value(&ended).unwrap())
+        }
+        Ok(None) => create_response(404, serde_json::json!({"error": format!("No suppression {} in effect", id)})),
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
+}
+
+/// Handle GET /api/expected_response
+fn handle_expected_response(client: &mut Client) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let curves = match precip_response::load_curves(client) {
+        Ok(curves) => curves,
+        Err(e) => return create_response(500, serde_json::json!({"error": e})),
+    };
+    let rows = match client.query(
+        "SELECT scope_id, window_end, total_in, max_in, sample_count
+         FROM asos_precip_rollups_latest
+         WHERE scope = 'basin' AND window_hours = $1
+           AND window_end >= NOW() - INTERVAL '3 hours'",
+        &[&(precip_response::WINDOW_HOURS as i32)],
+    ) {
+        Ok(rows) => rows,
+        Err(e) => return create_response(500, serde_json::json!({"error": format!("Failed to query basin rollups: {}", e)})),
+    };
+    let rollups: Vec<PrecipRollup> = rows.iter()
+        .map(|row| PrecipRollup {
+            scope: RollupScope::Basin,
+            scope_id: row.get(0),
+            window_hours: precip_response::WINDOW_HOURS,
+            window_end: row.get(1),
+            total_in: row.get(2),
+            max_in: row.get(3),
+            sample_count: row.get(4),
+        })
+        .collect();
+    let responses = precip_response::expected_responses(&curves, &rollups);
+    
+    create_response(200, serde_json::json!({
+        "curves": curves.len(),
+        "min_precip_in": precip_response::MIN_PRECIP_IN,
+        "count": responses.len(),
+        "responses": responses,
+    }))
+}
+
+/// Readings older than this don't place a gauge on the profile
+const PROFILE_LOOKBACK_HOURS: i64 = 6;
+
+/// Handle GET /api/profile: the gauged water-surface profile from the
+/// latest stages and an estimate at each configured point, plus at
+/// `river_mile` when given
+fn handle_profile(
+    client: &mut Client,
+    points: &[ProfilePoint],
+    query: &HashMap<String, String>,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let mut points = points.to_vec();
+    if let Some(mile) = query.get("river_mile") {
+        match mile.parse::<f64>() {
+            Ok(river_mile) if river_mile.is_finite() => {
+                points.push(ProfilePoint { name: format!("RM {}", mile), river_mile, datum_ft: None });
+            }
+            _ => return create_response(400, serde_json::json!({"error": "river_mile must be a number"})),
+        }
+    }
+    let latest = match archive::latest_known(client, None, true, Utc::now(), Duration::hours(PROFILE_LOOKBACK_HOURS)) {
+        Ok(readings) => readings,
+        Err(e) => return create_response(500, serde_json::json!({"error": e})),
+    };
+    let latest_stages: HashMap<String, (f64, DateTime<Utc>)> = latest.iter()
+        .map(|r| (r.site_code.clone(), (r.value, r.datetime.with_timezone(&Utc))))
+        .collect();
+    let profile = match profile::build(&stations::load_stations(), &latest_stages) {
+        Ok(profile) => profile,
+        Err(e) => return create_response(500, serde_json::json!({"error": e})),
+    };
+    let (estimates, outside): (Vec<_>, Vec<_>) = points.iter()
+        .map(|point| (point, profile.estimate(point)))
+        .partition(|(_, estimate)| estimate.is_some());
+
+    create_response(200, serde_json::json!({
+        "datum": profile.datum,
+        "gauges": profile.gauges,
+        "estimates": estimates.into_iter().filter_map(|(_, e)| e).collect::<Vec<_>>(),
+        "outside_gauged_reach": outside.into_iter().map(|(p, _)| &p.name).collect::<Vec<_>>(),
+    }))
+}
+
+/// Handle GET /manual_readings
+fn handle_manual_readings_list(client: &mut Client, query: &HashMap<String, String>) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let (start, end) = match time_range(query, Utc::now()) {
+        Ok(params) => params,
+        Err(e) => return create_response(400, serde_json::json!({"error": e})),
+    };
+    let limit = match query.get("limit").map(|l| l.parse::<i64>()).transpose() {
+        Ok(limit) => limit,
+        Err(_) => return create_response(400, serde_json::json!({"error": "limit must be an integer"})),
+    };
+    match manual_readings::list(client, query.get("site").map(String::as_str), start, end, limit) {
+        Ok(readings) => create_response(200, serde_json::json!({
+            "source": manual_readings::SOURCE,
+            "count": readings.len(),
+            "readings": readings,
+        })),
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
+}
+
+/// Handle POST /manual_readings: a message relayed by the SMS/email forwarder
+fn handle_manual_reading_create(
+    client: &mut Client,
+    settings: Option<&ManualSettings>,
+    authorization: Option<&str>,
+    body: &str,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let Some(settings) = settings else {
+        return create_response(404, serde_json::json!({"error": "Manual readings are not enabled ([manual_readings] in flomon.toml)"}));
+    };
+    if !settings.authorized(authorization) {
+        return create_response(401, serde_json::json!({"error": "Missing or invalid bearer token"}));
+    }
+    let message: ForwardedMessage = match serde_json::from_str(body) {
+        Ok(m) => m,
+        Err(e) => return create_response(400, serde_json::json!({"error": format!("Invalid forwarded message: {}", e)})),
+    };
+    if !settings.sender_allowed(&message.from) {
+        logging::warn(logging::DataSource::System, None,
+            &format!("Manual reading from unlisted sender {} rejected", message.from));
+        return create_response(403, serde_json::json!({"error": "Sender is not in manual_readings.allowed_senders"}));
+    }
+    let reading = match manual_readings::parse_message(settings, &message) {
+        Ok(r) => r,
+        Err(e) => return create_response(422, serde_json::json!({"error": e})),
+    };
+    
+    match manual_readings::insert(client, &reading, &message.body) {
+        Ok(id) => {
+            logging::info(logging::DataSource::System, Some(&reading.site_code), &format!(
+                "Manual reading {:.2} ft at {} from {} ({})",
+                reading.stage_ft, reading.reading_time.to_rfc3339(), reading.reporter, reading.channel.as_str(),
+            ));
+            create_response(201, serde_json::json!({"id": id, "reading": reading}))
+        }
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
+}
+
+/// Parse and bound /history query parameters.
+///
+/// `param` defaults to stage, `end` to now and `start` to 7 days before `end`.
+fn history_params(
+    query: &HashMap<String, String>,
+    now: DateTime<Utc>,
+) -> Result<(String, String, DateTime<Utc>, DateTime<Utc>), String> {
+    let site = query.get("site").cloned().ok_or("site is required")?;
+    let parameter = query.get("param").cloned().unwrap_or_else(|| "00065".to_string());
+    let (start, end) = time_range(query, now)?;
+    Ok((site, parameter, start, end))
+}
+
+/// `start`/`end` query parameters: `end` defaults to now, `start` to 7
+/// days before `end`, and the span is bounded by `HISTORY_MAX_DAYS`
+fn time_range(query: &HashMap<String, String>, now: DateTime<Utc>) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    let parse = |key: &str| -> Result<Option<DateTime<Utc>>, String> {
+        query.get(key)
+            .map(|v| DateTime::parse_from_rfc3339(v)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| format!("{} must be RFC 3339: {}", key, e)))
+            .transpose()
+    };
+    let end = parse("end")?.unwrap_or(now);
+    let start = parse("start")?.unwrap_or(end - Duration::days(7));
+    if start > end {
+        return Err("start must be before end".to_string());
+    }
+    if end - start > Duration::days(HISTORY_MAX_DAYS) {
+        return Err(format!("range is limited to {} days", HISTORY_MAX_DAYS));
+    }
+    Ok((start, end))
+}
+
+/// Handle GET /field_obs
+fn handle_field_obs_list(client: &mut Client, query: &HashMap<String, String>) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let filter = match field_obs_filter(query) {
+        Ok(f) => f,
+        Err(e) => return create_response(400, serde_json::json!({"error": e})),
+    };
+    
+    match field_obs::list(client, &filter) {
+        Ok(observations) => create_response(200, serde_json::json!({
+            "count": observations.len(),
+            "observations": observations,
+        })),
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
+}
+
+/// Handle POST /field_obs
+fn handle_field_obs_create(
+    client: &mut Client,
+    settings: Option<&FieldObsSettings>,
+    authorization: Option<&str>,
+    body: &str,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let Some(settings) = settings else {
+        return create_response(404, serde_json::json!({"error": "Recording field observations over HTTP is not enabled ([field_obs] in flomon.toml)"}));
+    };
+    if !settings.authorized(authorization) {
+        return create_response(401, serde_json::json!({"error": "Missing or invalid bearer token"}));
+    }
+    let obs: FieldObservation = match serde_json::from_str(body) {
+        Ok(o) => o,
+        Err(e) => return create_response(400, serde_json::json!({"error": format!("Invalid field observation: {}", e)})),
+    };
+    if let Err(e) = obs.validate() {
+        return create_response(400, serde_json::json!({"error": e}));
+    }
+    
+    match field_obs::insert(client, &obs) {
+        Ok(id) => create_response(201, serde_json::json!({"id": id})),
+        Err(e) => create_response(500, serde_json::json!({"error": e})),
+    }
+}
+
+/// Build a field observation filter from `site`, `event`, `kind`, `limit`
+fn field_obs_filter(query: &HashMap<String, String>) -> Result<FieldObsFilter, String> {
+    let event_id = query.get("event")
+        .map(|v| v.parse().map_err(|_| format!("Invalid event id '{}'", v)))
+        .transpose()?;
+    let kind = query.get("kind")
+        .map(|v| FieldObsKind::parse(v).ok_or_else(|| format!("Unknown kind '{}'", v)))
+        .transpose()?;
+    let limit = query.get("limit")
+        .map(|v| v.parse().map_err(|_| format!("Invalid limit '{}'", v)))
+        .transpose()?;
+    
+    Ok(FieldObsFilter {
+        site_code: query.get("site").cloned(),
+        event_id,
+        kind,
+        limit,
+    })
+}
+
+/// Split "/path?a=1&b=2" into the path and decoded query parameters
+pub(crate) fn split_query(url: &str) -> (String, HashMap<String, String>) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let params = query.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let decode = |s: &str| urlencoding::decode(&s.replace('+', " "))
+                .map(|d| d.into_owned())
+                .unwrap_or_else(|_| s.to_string());
+            (decode(key), decode(value))
+        })
+        .collect();
+    (path.to_string(), params)
+}
+
 /// Handle deprecated /site/{site_code} endpoint
 fn handle_deprecated_site_query(_client: &mut Client, url: &str) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
     create_response(
@@ -734,13 +1710,230 @@ fn handle_deprecated_site_query(_client: &mut Client, url: &str) -> tiny_http::R
 }
 
 /// Create HTTP response with JSON body
-fn create_response(status_code: u16, json: serde_json::Value) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+pub(crate) fn create_response(status_code: u16, json: serde_json::Value) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    create_typed_response(status_code, json, "application/json")
+}
+
+/// `create_response` with a different JSON media type (e.g. `application/geo+json`)
+fn create_typed_response(
+    status_code: u16,
+    json: serde_json::Value,
+    content_type: &str,
+) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
     let body = serde_json::to_string_pretty(&json).unwrap();
     let bytes = body.into_bytes();
     
     tiny_http::Response::from_data(bytes)
         .with_status_code(tiny_http::StatusCode::from(status_code))
         .with_header(
-            tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap()
+            tiny_http::Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes()).unwrap()
         )
 }
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alert::thresholds::FloodSeverity;
+    
+    #[test]
+    fn test_split_query_decodes_params() {
+        let (path, query) = split_query("/field_obs?site=05567500&kind=high_water_mark&notes=a+b%2Fc");
+        assert_eq!(path, "/field_obs");
+        assert_eq!(query.get("site").map(String::as_str), Some("05567500"));
+        assert_eq!(query.get("notes").map(String::as_str), Some("a b/c"));
+        
+        let (path, query) = split_query("/zones");
+        assert_eq!(path, "/zones");
+        assert!(query.is_empty());
+    }
+    
+    #[test]
+    fn test_read_body_is_bounded() {
+        let small = read_body(std::io::Cursor::new(br#"{"kind": "photo"}"#.to_vec()));
+        assert_eq!(small.ok().as_deref(), Some(r#"{"kind": "photo"}"#));
+        
+        let exact = vec![b'x'; MAX_BODY_BYTES as usize];
+        assert!(read_body(std::io::Cursor::new(exact)).is_ok());
+        let oversized = vec![b'x'; MAX_BODY_BYTES as usize + 1];
+        match read_body(std::io::Cursor::new(oversized)) {
+            Err(response) => assert_eq!(response.status_code().0, 413),
+            Ok(_) => panic!("a body over MAX_BODY_BYTES should be refused"),
+        }
+    }
+
+    #[test]
+    fn test_open_stream_checks_token_then_client_limit() {
+        let settings = StreamSettings { max_clients: 1, token: Some("0123456789abcdef".to_string()) };
+        let hub = Hub::new(settings.max_clients);
+        let status = |result: Result<Subscription, tiny_http::Response<std::io::Cursor<Vec<u8>>>>| match result {
+            Ok(_) => 200,
+            Err(response) => response.status_code().0,
+        };
+
+        let (_, query) = split_query("/api/stream?site=05568500");
+        assert_eq!(status(open_stream(&settings, &hub, None, &query)), 401);
+
+        let (_, query) = split_query("/api/stream?site=05568500&token=0123456789abcdef");
+        let first = open_stream(&settings, &hub, None, &query);
+        assert!(first.is_ok());
+        assert_eq!(status(open_stream(&settings, &hub, Some("Bearer 0123456789abcdef"), &query)), 503);
+        drop(first);
+        assert_eq!(status(open_stream(&settings, &hub, Some("Bearer 0123456789abcdef"), &query)), 200);
+    }
+
+    #[test]
+    fn test_field_obs_filter_rejects_bad_values() {
+        let (_, query) = split_query("/field_obs?event=12&kind=hwm&limit=5");
+        let filter = field_obs_filter(&query).unwrap();
+        assert_eq!(filter.event_id, Some(12));
+        assert_eq!(filter.kind, Some(FieldObsKind::HighWaterMark));
+        assert_eq!(filter.limit, Some(5));
+        
+        let (_, query) = split_query("/field_obs?event=latest");
+        assert!(field_obs_filter(&query).is_err());
+        let (_, query) = split_query("/field_obs?kind=rumor");
+        assert!(field_obs_filter(&query).is_err());
+    }
+    
+    #[test]
+    fn test_embed_severity_colors() {
+        let scheme = SeverityScheme::default();
+        assert_eq!(scheme.style_for(None, false).key, "normal");
+        let moderate = scheme.style_for(Some(&FloodSeverity::Moderate), false);
+        assert_eq!((moderate.key.as_str(), moderate.color.as_str()), ("moderate", "#c62828"));
+        // Stale data never shows a reassuring color
+        assert_eq!(scheme.style_for(None, true).key, "stale");
+        assert_eq!(scheme.style_for(Some(&FloodSeverity::Major), true).key, "stale");
+    }
+    
+    #[test]
+    fn test_attribution_added_to_successful_json_objects() {
+        let body = |response: tiny_http::Response<std::io::Cursor<Vec<u8>>>| -> serde_json::Value {
+            serde_json::from_slice(&response.into_reader().into_inner()).unwrap()
+        };
+        let ok = attribute(create_response(200, serde_json::json!({"stage_ft": 17.2})), attribution::for_path("/api/latest"));
+        assert_eq!(body(ok)["attribution"][1]["provider"], "NWS");
+
+        let error = attribute(create_response(404, serde_json::json!({"error": "Unknown site"})), attribution::for_path("/api/latest"));
+        assert!(body(error).get("attribution").is_none());
+        let local = attribute(create_response(200, serde_json::json!({"count": 0})), attribution::for_path("/field_obs"));
+        assert!(body(local).get("attribution").is_none());
+    }
+
+    #[test]
+    fn test_embed_widget_is_public_and_cached() {
+        let response = handle_embed_widget();
+        let header = |name: &'static str| response.headers().iter()
+            .find(|h| h.field.equiv(name))
+            .map(|h| h.value.as_str().to_string());
+        assert_eq!(header("Access-Control-Allow-Origin").as_deref(), Some("*"));
+        assert_eq!(header("Cache-Control").as_deref(), Some("public, max-age=86400"));
+        assert!(header("Content-Type").unwrap().starts_with("application/javascript"));
+    }
+    
+    #[test]
+    fn test_history_params_defaults_and_bounds() {
+        let now: DateTime<Utc> = "2024-05-08T12:00:00Z".parse().unwrap();
+        let query = |pairs: &[(&str, &str)]| pairs.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<String, String>>();
+        
+        let (site, param, start, end) = history_params(&query(&[("site", "05567500")]), now).unwrap();
+        assert_eq!((site.as_str(), param.as_str()), ("05567500", "00065"));
+        assert_eq!(end, now);
+        assert_eq!(end - start, Duration::days(7));
+        
+        assert!(history_params(&query(&[]), now).is_err());
+        assert!(history_params(&query(&[("site", "05567500"), ("start", "2024-05-09T00:00:00Z")]), now).is_err());
+        assert!(history_params(&query(&[("site", "05567500"), ("start", "2020-01-01T00:00:00Z")]), now).is_err());
+    }
+    
+    #[test]
+    fn test_trusted_proxy_parsing_and_cidr() {
+        let docker = TrustedProxy::parse("172.16.0.0/12").unwrap();
+        assert!(docker.contains("172.18.0.2".parse().unwrap()));
+        assert!(!docker.contains("172.32.0.1".parse().unwrap()));
+        assert!(!docker.contains("::1".parse().unwrap()));
+        assert!(TrustedProxy::parse("::1").unwrap().contains("::1".parse().unwrap()));
+        assert!(TrustedProxy::parse("10.0.0.0/33").is_err());
+        assert!(TrustedProxy::parse("caddy").is_err());
+    }
+    
+    #[test]
+    fn test_forwarded_headers_only_from_trusted_proxy() {
+        let trusted = default_trusted_proxies();
+        let caddy: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let stranger: SocketAddr = "198.51.100.9:50000".parse().unwrap();
+        
+        let via_proxy = resolve_client(Some(&caddy), Some("203.0.113.7"), Some("https"), &trusted, None);
+        assert_eq!(via_proxy.ip, Some("203.0.113.7".parse().unwrap()));
+        assert!(via_proxy.https);
+        
+        // Spoofed header straight from the internet is ignored
+        let spoofed = resolve_client(Some(&stranger), Some("10.1.1.1"), Some("https"), &trusted, None);
+        assert_eq!(spoofed.ip, Some(stranger.ip()));
+        assert!(!spoofed.https);
+    }
+    
+    #[test]
+    fn test_forwarded_for_chain_stops_at_first_untrusted_hop() {
+        let trusted = vec![TrustedProxy::parse("127.0.0.1").unwrap(), TrustedProxy::parse("10.0.0.0/8").unwrap()];
+        let peer: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        // Client-supplied "1.2.3.4" is left of the real client and not believed
+        let info = resolve_client(Some(&peer), Some("1.2.3.4, 203.0.113.7, 10.0.0.3"), None, &trusted, None);
+        assert_eq!(info.ip, Some("203.0.113.7".parse().unwrap()));
+        assert_eq!(info.to_string(), "203.0.113.7");
+    }
+    
+    #[test]
+    fn test_request_ids_forwarded_or_generated() {
+        let now = Utc::now();
+        assert_eq!(choose_request_id(Some("caddy-7f3a.91"), now), "caddy-7f3a.91");
+        
+        // Junk or oversized forwarded IDs are replaced
+        let generated = choose_request_id(Some("x\r\nSet-Cookie: a"), now);
+        assert!(generated.starts_with(&format!("{:012x}-", now.timestamp_millis())));
+        assert_ne!(choose_request_id(Some(&"a".repeat(65)), now).len(), 65);
+        assert_ne!(choose_request_id(None, now), choose_request_id(None, now));
+    }
+    
+    #[test]
+    fn test_typed_response_sends_one_content_type() {
+        let response = create_typed_response(200, serde_json::json!({"type": "FeatureCollection"}), "application/geo+json");
+        let content_types: Vec<String> = response.headers().iter()
+            .filter(|h| h.field.equiv("Content-Type"))
+            .map(|h| h.value.to_string())
+            .collect();
+        assert_eq!(content_types, ["application/geo+json"]);
+    }
+    
+    #[test]
+    fn test_error_responses_carry_request_id() {
+        let header = |response: &tiny_http::Response<std::io::Cursor<Vec<u8>>>, name: &'static str| {
+            response.headers().iter().find(|h| h.field.equiv(name)).map(|h| h.value.to_string())
+        };
+        
+        let failed = create_response(500, serde_json::json!({"error": "relation \"usgs_raw.gauge_readings\" does not exist"}));
+        let (failed, error) = stamp_request_id(failed, "abc-1");
+        assert!(error.unwrap().contains("does not exist"));
+        assert_eq!(failed.status_code().0, 500);
+        assert_eq!(header(&failed, "X-Request-Id").as_deref(), Some("abc-1"));
+        assert_eq!(header(&failed, "Content-Type").as_deref(), Some("application/json"));
+        let body: ApiErrorResponse = serde_json::from_slice(&failed.into_reader().into_inner()).unwrap();
+        assert_eq!((body.status, body.request_id.as_str()), (500, "abc-1"));
+        
+        // Extra fields survive
+        let (missing, _) = stamp_request_id(create_response(404, serde_json::json!({"error": "Not found", "available_endpoints": {}})), "abc-2");
+        let body: serde_json::Value = serde_json::from_slice(&missing.into_reader().into_inner()).unwrap();
+        assert!(body["available_endpoints"].is_object());
+        assert_eq!(body["request_id"], "abc-2");
+        
+        let (ok, error) = stamp_request_id(handle_health(), "abc-3");
+        assert_eq!(error, None);
+        assert_eq!(header(&ok, "X-Request-Id").as_deref(), Some("abc-3"));
+    }
+}
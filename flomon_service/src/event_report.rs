@@ -0,0 +1,434 @@
+//! Incident timeline for a catalogued flood event (`flomon event report`).
+//!
+//! After an event closes, the adjuster and the drainage district both want
+//! the same thing: what the river did, when we knew, and what was done.
+//! `load` gathers everything the service recorded about one
+//! `flood_analysis.events` row into a single chronological timeline:
+//!
+//! - stage milestones at the event's gauge: each severity change as the
+//!   alert rules saw it (which is when escalation alerts went out) and the crest
+//! - system-wide event mode changes (`monitor::event_mode`)
+//! - data quality flags raised at the gauge (`data_quality`)
+//! - field observations: high-water marks, crest stakes, photos, sandbagging
+//! - staff gauge readings reported by SMS/email (`manual_readings`)
+//! - webcam snapshots taken on transitions (`cams`)
+//!
+//! The window is the event's start to its end (or now, for an open event),
+//! padded by `PAD_HOURS` on both sides so the first crossing and the return
+//! below action stage are included. Alert acknowledgements and NWS products
+//! are not stored by this service and so do not appear.
+//!
+//! `to_markdown` and `to_html` render the report; both are self-contained
+//! and end with the data providers' credits (see `attribution`).
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+use serde::Serialize;
+
+use crate::alert::thresholds::{self, FloodAlert, FloodSeverity};
+use crate::archive;
+use crate::attribution::Provider;
+use crate::field_obs::{self, FieldObsFilter};
+use crate::manual_readings;
+use crate::model::GaugeReading;
+use crate::stations;
+
+/// Hours added before the event start and after its end
+pub const PAD_HOURS: i64 = 12;
+
+/// Stage parameter
+const STAGE_PARAMETER: &str = "00065";
+
+/// Output format of `flomon event report`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "markdown" | "md" => Some(Format::Markdown),
+            "html" => Some(Format::Html),
+            _ => None,
+        }
+    }
+}
+
+/// The catalogued event a report covers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventSummary {
+    pub id: i32,
+    pub site_code: String,
+    pub station_name: Option<String>,
+    pub start: DateTime<Utc>,
+    pub peak: DateTime<Utc>,
+    /// None while the event is still open
+    pub end: Option<DateTime<Utc>>,
+    pub severity: String,
+    pub peak_stage_ft: Option<f64>,
+}
+
+/// One line of the timeline
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimelineEntry {
+    pub at: DateTime<Utc>,
+    /// Where the entry came from, e.g. "gauge", "field_obs"
+    pub source: &'static str,
+    pub site_code: Option<String>,
+    pub text: String,
+}
+
+/// An event and its timeline, oldest entry first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventReport {
+    pub event: EventSummary,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub entries: Vec<TimelineEntry>,
+    pub generated_at: DateTime<Utc>,
+}
+
+// ============================================================================
+// Timeline assembly
+// ============================================================================
+
+/// Severity changes and the crest in `readings` (oldest first), as the
+/// alert rules in `evaluate` would have seen them
+pub fn stage_milestones(
+    readings: &[GaugeReading],
+    evaluate: impl Fn(&GaugeReading) -> Option<FloodAlert>,
+) -> Vec<TimelineEntry> {
+    let mut entries = Vec::new();
+    let mut current: Option<FloodSeverity> = None;
+    let mut crest: Option<(DateTime<Utc>, &GaugeReading)> = None;
+    for reading in readings {
+        let at = reading.datetime.with_timezone(&Utc);
+        if crest.is_none_or(|(_, c)| reading.value > c.value) {
+            crest = Some((at, reading));
+        }
+        let alert = evaluate(reading);
+        let severity = alert.as_ref().map(|a| a.severity.clone());
+        if severity == current {
+            continue;
+        }
+        let text = match (&alert, &current) {
+            (Some(alert), Some(previous)) if alert.severity > *previous => format!("Escalated: {}", alert.message),
+            (Some(alert), Some(_)) => format!("Receded: {}", alert.message),
+            (Some(alert), None) => format!("Escalated: {}", alert.message),
+            (None, _) => format!("Below action stage: {:.2} ft", reading.value),
+        };
+        entries.push(TimelineEntry { at, source: "gauge", site_code: Some(reading.site_code.clone()), text });
+        current = severity;
+    }
+    if let Some((at, reading)) = crest {
+        entries.push(TimelineEntry {
+            at,
+            source: "gauge",
+            site_code: Some(reading.site_code.clone()),
+            text: format!("Crest: {:.2} ft", reading.value),
+        });
+    }
+    entries
+}
+
+/// Load event `event_id` and everything recorded during it; None when the
+/// event doesn't exist
+pub fn load(client: &mut Client, event_id: i32, now: DateTime<Utc>) -> Result<Option<EventReport>, String> {
+    let Some(row) = client.query_opt(
+        "SELECT site_code, event_start, event_peak, event_end, severity, peak_stage_ft::FLOAT8
+         FROM flood_analysis.events WHERE id = $1",
+        &[&event_id],
+    ).map_err(|e| format!("Failed to look up event {}: {}", event_id, e))? else {
+        return Ok(None);
+    };
+    let site_code: String = row.get(0);
+    let station = stations::find_station(&site_code);
+    let event = EventSummary {
+        id: event_id,
+        station_name: station.as_ref().map(|s| s.name.clone()),
+        site_code,
+        start: row.get(1),
+        peak: row.get(2),
+        end: row.get(3),
+        severity: row.get(4),
+        peak_stage_ft: row.get(5),
+    };
+    let window_start = event.start - Duration::hours(PAD_HOURS);
+    let window_end = (event.end.unwrap_or(now) + Duration::hours(PAD_HOURS)).min(now);
+    let site = event.site_code.as_str();
+
+    let mut entries = Vec::new();
+
+    let readings = archive::readings_between(client, site, STAGE_PARAMETER, window_start, window_end)?;
+    if let Some(station) = &station {
+        entries.extend(stage_milestones(&readings, |r| thresholds::check_station(station, r)));
+    }
+
+    let rows = client.query(
+        "SELECT entered_at, previous_mode, mode, reason FROM usgs_raw.event_mode_history
+         WHERE entered_at BETWEEN $1 AND $2 ORDER BY entered_at, id",
+        &[&window_start, &window_end],
+    ).map_err(|e| format!("Failed to load event mode history: {}", e))?;
+    entries.extend(rows.iter().map(|row| {
+        let (from, to, reason): (String, String, String) = (row.get(1), row.get(2), row.get(3));
+        TimelineEntry { at: row.get(0), source: "event_mode", site_code: None, text: format!("Mode {} -> {}: {}", from, to, reason) }
+    }));
+
+    let rows = client.query(
+        "SELECT event_time, kind, summary, status FROM usgs_raw.data_quality_alerts
+         WHERE site_code = $1 AND event_time BETWEEN $2 AND $3 ORDER BY event_time",
+        &[&site, &window_start, &window_end],
+    ).map_err(|e| format!("Failed to load data quality alerts: {}", e))?;
+    entries.extend(rows.iter().map(|row| {
+        let (kind, summary, status): (String, String, String) = (row.get(1), row.get(2), row.get(3));
+        TimelineEntry {
+            at: row.get(0),
+            source: "data_quality",
+            site_code: Some(event.site_code.clone()),
+            text: format!("Data quality flag {} ({}): {}", kind, status, summary),
+        }
+    }));
+
+    // Observations tagged with the event, plus untagged ones at the gauge in the window
+    let mut observations = field_obs::list(client, &FieldObsFilter { event_id: Some(event_id), limit: Some(1000), ..Default::default() })?;
+    observations.extend(
+        field_obs::list(client, &FieldObsFilter { site_code: Some(event.site_code.clone()), limit: Some(1000), ..Default::default() })?
+            .into_iter()
+            .filter(|o| o.event_id.is_none() && o.observed_at >= window_start && o.observed_at <= window_end),
+    );
+    entries.extend(observations.iter().map(|obs| {
+        let mut text = obs.kind.as_str().replace('_', " ");
+        if let Some(value) = obs.value_ft {
+            text.push_str(format!(" {:.2} ft {}", value, obs.datum.as_deref().unwrap_or("")).trim_end());
+        }
+        for part in [&obs.notes, &obs.media_ref].into_iter().flatten() {
+            text.push_str(&format!("; {}", part));
+        }
+        if let Some(observer) = &obs.observer {
+            text.push_str(&format!(" ({})", observer));
+        }
+        TimelineEntry { at: obs.observed_at, source: "field_obs", site_code: obs.site_code.clone(), text }
+    }));
+
+    entries.extend(manual_readings::list(client, Some(site), window_start, window_end, None)?.into_iter().map(|m| {
+        let mut text = format!("Staff gauge {:.2} ft from {} via {}", m.stage_ft, m.reporter, m.channel.as_str());
+        if let Some(note) = &m.note {
+            text.push_str(&format!(": {}", note));
+        }
+        TimelineEntry { at: m.reading_time, source: "manual", site_code: Some(m.site_code), text }
+    }));
+
+    let rows = client.query(
+        "SELECT captured_at, camera, site_code, severity_from, severity_to, stage_ft
+         FROM flood_analysis.cam_snapshots
+         WHERE event_id = $1 OR (site_code = $2 AND captured_at BETWEEN $3 AND $4)
+         ORDER BY captured_at",
+        &[&event_id, &site, &window_start, &window_end],
+    ).map_err(|e| format!("Failed to load camera snapshots: {}", e))?;
+    entries.extend(rows.iter().map(|row| {
+        let (camera, from, to, stage): (String, String, String, f64) = (row.get(1), row.get(3), row.get(4), row.get(5));
+        TimelineEntry {
+            at: row.get(0),
+            source: "camera",
+            site_code: Some(row.get(2)),
+            text: format!("Snapshot from {} ({} -> {} at {:.2} ft)", camera, from, to, stage),
+        }
+    }));
+
+    entries.sort_by_key(|e| e.at);
+    Ok(Some(EventReport { event, window_start, window_end, entries, generated_at: now }))
+}
+
+// ============================================================================
+// Rendering
+// ============================================================================
+
+impl EventReport {
+    fn title(&self) -> String {
+        let event = &self.event;
+        match &event.station_name {
+            Some(name) => format!("Flood event {}: {} ({})", event.id, name, event.site_code),
+            None => format!("Flood event {}: {}", event.id, event.site_code),
+        }
+    }
+
+    /// Summary lines shown above the timeline
+    fn facts(&self) -> Vec<(&'static str, String)> {
+        let event = &self.event;
+        let time = |t: DateTime<Utc>| t.format("%Y-%m-%d %H:%M UTC").to_string();
+        let peak = match event.peak_stage_ft {
+            Some(stage) => format!("{:.2} ft at {}", stage, time(event.peak)),
+            None => time(event.peak),
+        };
+        vec![
+            ("Severity", event.severity.clone()),
+            ("Start", time(event.start)),
+            ("Peak", peak),
+            ("End", event.end.map(time).unwrap_or_else(|| "ongoing".to_string())),
+            ("Timeline window", format!("{} to {}", time(self.window_start), time(self.window_end))),
+            ("Generated", time(self.generated_at)),
+        ]
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n\n", self.title());
+        for (label, value) in self.facts() {
+            out.push_str(&format!("- **{}:** {}\n", label, value));
+        }
+        out.push_str("\n## Timeline\n\n");
+        if self.entries.is_empty() {
+            out.push_str("Nothing was recorded in this window.\n");
+        } else {
+            out.push_str("| Time (UTC) | Source | Site | Entry |\n|---|---|---|---|\n");
+            for entry in &self.entries {
+                out.push_str(&format!(
+                    "| {} | {} | {} | {} |\n",
+                    entry.at.format("%Y-%m-%d %H:%M"),
+                    entry.source,
+                    entry.site_code.as_deref().unwrap_or(""),
+                    entry.text.replace('|', "\\|"),
+                ));
+            }
+        }
+        out.push_str("\n## Data sources\n\n");
+        for provider in Provider::ALL {
+            out.push_str(&format!("- {} {}\n", provider.text(), provider.url()));
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let title = escape_html(&self.title());
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{0}</title>\n\
+             <style>body{{font-family:sans-serif}}table{{border-collapse:collapse}}\
+             td,th{{border:1px solid #999;padding:2px 6px;text-align:left;vertical-align:top}}</style>\n\
+             </head><body>\n<h1>{0}</h1>\n<ul>\n",
+            title,
+        );
+        for (label, value) in self.facts() {
+            out.push_str(&format!("<li><b>{}:</b> {}</li>\n", label, escape_html(&value)));
+        }
+        out.push_str("</ul>\n<h2>Timeline</h2>\n");
+        if self.entries.is_empty() {
+            out.push_str("<p>Nothing was recorded in this window.</p>\n");
+        } else {
+            out.push_str("<table>\n<tr><th>Time (UTC)</th><th>Source</th><th>Site</th><th>Entry</th></tr>\n");
+            for entry in &self.entries {
+                out.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    entry.at.format("%Y-%m-%d %H:%M"),
+                    entry.source,
+                    escape_html(entry.site_code.as_deref().unwrap_or("")),
+                    escape_html(&entry.text),
+                ));
+            }
+            out.push_str("</table>\n");
+        }
+        out.push_str("<h2>Data sources</h2>\n<ul>\n");
+        for provider in Provider::ALL {
+            out.push_str(&format!("<li>{} <a href=\"{1}\">{1}</a></li>\n", escape_html(provider.text()), provider.url()));
+        }
+        out.push_str("</ul>\n</body></html>\n");
+        out
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Markdown => self.to_markdown(),
+            Format::Html => self.to_html(),
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::FloodThresholds;
+    use chrono::TimeZone;
+
+    fn t(hour: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap() + Duration::hours(hour)
+    }
+
+    fn reading(hour: i64, value: f64) -> GaugeReading {
+        GaugeReading {
+            site_code: "05568500".to_string(),
+            site_name: "Illinois River at Kingston Mines".to_string(),
+            parameter_code: STAGE_PARAMETER.to_string(),
+            unit: "ft".to_string(),
+            value,
+            datetime: t(hour).fixed_offset(),
+            qualifier: "P".to_string(),
+            original_unit: None,
+        }
+    }
+
+    const THRESHOLDS: FloodThresholds = FloodThresholds {
+        action_stage_ft: 14.0,
+        flood_stage_ft: 16.0,
+        moderate_flood_stage_ft: 20.0,
+        major_flood_stage_ft: 24.0,
+    };
+
+    #[test]
+    fn test_milestones_follow_severity_changes_and_crest() {
+        let readings: Vec<GaugeReading> = [13.0, 14.5, 16.2, 17.0, 20.4, 19.1, 15.0, 13.2].iter()
+            .enumerate()
+            .map(|(i, v)| reading(i as i64, *v))
+            .collect();
+        let entries = stage_milestones(&readings, |r| thresholds::check_flood_stage(r, &THRESHOLDS));
+
+        let texts: Vec<&str> = entries.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(texts.len(), 7, "{:?}", texts);
+        assert!(texts[0].starts_with("Escalated") && entries[0].at == t(1));
+        assert!(texts[2].starts_with("Escalated") && texts[2].contains("MODERATE"));
+        assert!(texts[3].starts_with("Receded"));
+        assert_eq!(texts[5], "Below action stage: 13.20 ft");
+        assert_eq!((texts[6], entries[6].at), ("Crest: 20.40 ft", t(4)));
+    }
+
+    #[test]
+    fn test_markdown_and_html_render_timeline() {
+        let report = EventReport {
+            event: EventSummary {
+                id: 7,
+                site_code: "05568500".to_string(),
+                station_name: Some("Illinois River at Kingston Mines".to_string()),
+                start: t(0),
+                peak: t(30),
+                end: Some(t(96)),
+                severity: "moderate".to_string(),
+                peak_stage_ft: Some(20.4),
+            },
+            window_start: t(-12),
+            window_end: t(108),
+            entries: vec![TimelineEntry {
+                at: t(20),
+                source: "field_obs",
+                site_code: None,
+                text: "sandbagging; north levee <Main St | 2nd>".to_string(),
+            }],
+            generated_at: t(200),
+        };
+
+        let markdown = report.render(Format::Markdown);
+        assert!(markdown.starts_with("# Flood event 7: Illinois River at Kingston Mines (05568500)"));
+        assert!(markdown.contains("- **Peak:** 20.40 ft at 2024-05-02 06:00 UTC"));
+        assert!(markdown.contains("| 2024-05-01 20:00 | field_obs |  | sandbagging; north levee <Main St \\| 2nd> |"));
+
+        let html = report.render(Format::Html);
+        assert!(html.contains("north levee &lt;Main St | 2nd&gt;"));
+        assert!(html.ends_with("</body></html>\n"));
+    }
+}
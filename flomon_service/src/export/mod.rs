@@ -0,0 +1,298 @@
+//! Windowed exports of the curated USGS readings for modeling tools.
+//!
+//! `flomon export` lays stage and discharge out on a regular time grid
+//! (station x time per parameter) and writes it as NetCDF (see `netcdf`),
+//! which the HEC-RAS / HEC-HMS pre-processing scripts read directly:
+//!
+//! ```text
+//! dimensions: station = 8, time = 2881, id_strlen = 8, name_strlen = 44
+//! time(time)                minutes since 1970-01-01 00:00:00 UTC
+//! station_id(station, id_strlen), station_name(station, name_strlen)
+//! latitude(station), longitude(station)
+//! stage(station, time)      ft        _FillValue -9999
+//! discharge(station, time)  ft3 s-1   _FillValue -9999
+//! ```
+//!
+//! Each grid time takes the latest reading in the interval ending at it
+//! (`(t - interval, t]`), so a 15-minute grid over 15-minute data is the
+//! data itself and a coarser grid is sampled, not averaged. Slots with no
+//! reading hold the fill value. Readings come from the hot table and the
+//! archive alike (`archive::readings_between`).
+
+pub mod netcdf;
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+
+use crate::archive;
+use crate::attribution::Provider;
+use crate::model::{GaugeReading, PARAM_DISCHARGE, PARAM_STAGE};
+use crate::stations::Station;
+use netcdf::{Attr, Dataset, Values};
+
+/// Value written where a slot has no reading
+pub const FILL_VALUE: f64 = -9999.0;
+
+/// Default grid spacing: the USGS instantaneous-value cadence
+pub const DEFAULT_INTERVAL_MINUTES: i64 = 15;
+
+/// Grids longer than this many slots are refused (a year at 15 minutes
+/// is ~35k)
+pub const MAX_SLOTS: usize = 200_000;
+
+/// Exported parameters: code, variable name, long name, units
+const PARAMETERS: [(&str, &str, &str, &str); 2] = [
+    (PARAM_STAGE, "stage", "gage height", "ft"),
+    (PARAM_DISCHARGE, "discharge", "river discharge", "ft3 s-1"),
+];
+
+/// Time span and spacing of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub interval: Duration,
+}
+
+impl ExportWindow {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>, interval_minutes: i64) -> Result<Self, String> {
+        if interval_minutes <= 0 {
+            return Err("interval must be a positive number of minutes".to_string());
+        }
+        if end < start {
+            return Err("end must not be before start".to_string());
+        }
+        let window = ExportWindow { start, end, interval: Duration::minutes(interval_minutes) };
+        let slots = window.slot_count();
+        if slots > MAX_SLOTS {
+            return Err(format!("{} time slots exceeds the limit of {}; use a wider interval", slots, MAX_SLOTS));
+        }
+        Ok(window)
+    }
+
+    fn slot_count(&self) -> usize {
+        ((self.end - self.start).num_minutes() / self.interval.num_minutes()) as usize + 1
+    }
+
+    /// Grid times from `start` through `end`
+    pub fn times(&self) -> Vec<DateTime<Utc>> {
+        (0..self.slot_count() as i32).map(|i| self.start + self.interval * i).collect()
+    }
+
+    /// Slot whose interval `(t - interval, t]` holds `at`
+    fn slot(&self, at: DateTime<Utc>) -> Option<usize> {
+        if at <= self.start - self.interval || at > self.end {
+            return None;
+        }
+        let since = (at - self.start).num_seconds();
+        let step = self.interval.num_seconds();
+        let slot = if since <= 0 { 0 } else { (since + step - 1) / step };
+        let slot = slot as usize;
+        (slot < self.slot_count()).then_some(slot)
+    }
+}
+
+/// One parameter on the grid, station-major
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridVariable {
+    pub parameter_code: &'static str,
+    pub name: &'static str,
+    pub long_name: &'static str,
+    pub units: &'static str,
+    /// `stations.len() * times.len()` values; `FILL_VALUE` where missing
+    pub values: Vec<f64>,
+}
+
+/// Readings laid out station x time
+#[derive(Debug, Clone)]
+pub struct Grid {
+    pub stations: Vec<Station>,
+    pub times: Vec<DateTime<Utc>>,
+    pub variables: Vec<GridVariable>,
+}
+
+/// Lay out readings on `window`'s grid; `fetch(site, parameter)` returns a
+/// station's readings in the window (padded one interval before `start`)
+pub fn build_grid<F>(stations: &[Station], window: &ExportWindow, mut fetch: F) -> Result<Grid, String>
+where
+    F: FnMut(&str, &str) -> Result<Vec<GaugeReading>, String>,
+{
+    let times = window.times();
+    let mut variables = Vec::new();
+    for (parameter_code, name, long_name, units) in PARAMETERS {
+        let mut values = vec![FILL_VALUE; stations.len() * times.len()];
+        for (row, station) in stations.iter().enumerate() {
+            if !station.expected_parameters.iter().any(|p| p == parameter_code) {
+                continue;
+            }
+            // Latest reading per slot: later readings overwrite earlier ones
+            let mut readings: Vec<(DateTime<Utc>, f64)> = fetch(&station.site_code, parameter_code)?
+                .iter()
+                .map(|r| (r.datetime.with_timezone(&Utc), r.value))
+                .collect();
+            readings.sort_by_key(|(t, _)| *t);
+            for (at, value) in readings {
+                if let Some(slot) = window.slot(at) {
+                    values[row * times.len() + slot] = value;
+                }
+            }
+        }
+        variables.push(GridVariable { parameter_code, name, long_name, units, values });
+    }
+    Ok(Grid { stations: stations.to_vec(), times, variables })
+}
+
+/// Query the readings for `stations` in `window` and lay them out
+pub fn load(client: &mut Client, stations: &[Station], window: &ExportWindow) -> Result<Grid, String> {
+    build_grid(stations, window, |site, parameter| {
+        archive::readings_between(client, site, parameter, window.start - window.interval, window.end)
+    })
+}
+
+/// Encode as CF-1.8 timeSeries NetCDF, crediting USGS as the source
+pub fn to_netcdf(grid: &Grid, now: DateTime<Utc>) -> Result<Vec<u8>, String> {
+    let mut ds = Dataset::new();
+    let id_len = grid.stations.iter().map(|s| s.site_code.len()).max().unwrap_or(0).max(1);
+    let name_len = grid.stations.iter().map(|s| s.name.len()).max().unwrap_or(0).max(1);
+    let station = ds.add_dim("station", grid.stations.len());
+    let time = ds.add_dim("time", grid.times.len());
+    let id_strlen = ds.add_dim("id_strlen", id_len);
+    let name_strlen = ds.add_dim("name_strlen", name_len);
+
+    let text = |s: &str| Attr::Text(s.to_string());
+    ds.add_attr("Conventions", text("CF-1.8"));
+    ds.add_attr("featureType", text("timeSeries"));
+    ds.add_attr("title", text("Illinois River basin gauge readings"));
+    ds.add_attr("institution", text("flomon"));
+    ds.add_attr("source", text(Provider::Usgs.text()));
+    ds.add_attr("license", text(Provider::Usgs.license()));
+    ds.add_attr("references", text(Provider::Usgs.url()));
+    ds.add_attr("history", Attr::Text(format!("{} flomon export", now.format("%Y-%m-%dT%H:%M:%SZ"))));
+    ds.add_attr("time_coverage_start", Attr::Text(grid.times.first().map(|t| t.to_rfc3339()).unwrap_or_default()));
+    ds.add_attr("time_coverage_end", Attr::Text(grid.times.last().map(|t| t.to_rfc3339()).unwrap_or_default()));
+
+    ds.add_var("time", &[time], vec![
+        ("standard_name", text("time")),
+        ("units", text("minutes since 1970-01-01 00:00:00 UTC")),
+        ("calendar", text("standard")),
+    ], Values::Double(grid.times.iter().map(|t| (t.timestamp() / 60) as f64).collect()))?;
+    ds.add_var("station_id", &[station, id_strlen], vec![
+        ("long_name", text("USGS site number")),
+        ("cf_role", text("timeseries_id")),
+    ], Values::Char(fixed_width(grid.stations.iter().map(|s| s.site_code.as_str()), id_len)))?;
+    ds.add_var("station_name", &[station, name_strlen], vec![
+        ("long_name", text("USGS site name")),
+    ], Values::Char(fixed_width(grid.stations.iter().map(|s| s.name.as_str()), name_len)))?;
+    ds.add_var("latitude", &[station], vec![
+        ("standard_name", text("latitude")),
+        ("units", text("degrees_north")),
+    ], Values::Double(grid.stations.iter().map(|s| s.latitude).collect()))?;
+    ds.add_var("longitude", &[station], vec![
+        ("standard_name", text("longitude")),
+        ("units", text("degrees_east")),
+    ], Values::Double(grid.stations.iter().map(|s| s.longitude).collect()))?;
+
+    for variable in &grid.variables {
+        ds.add_var(variable.name, &[station, time], vec![
+            ("long_name", text(variable.long_name)),
+            ("units", text(variable.units)),
+            ("usgs_parameter_code", text(variable.parameter_code)),
+            ("coordinates", text("time latitude longitude station_id")),
+            ("_FillValue", Attr::Double(FILL_VALUE)),
+        ], Values::Double(variable.values.clone()))?;
+    }
+    Ok(ds.to_bytes())
+}
+
+/// Strings as a NUL-padded `[n, width]` char array
+fn fixed_width<'a>(strings: impl Iterator<Item = &'a str>, width: usize) -> Vec<u8> {
+    strings
+        .flat_map(|s| {
+            let mut bytes = s.as_bytes()[..s.len().min(width)].to_vec();
+            bytes.resize(width, 0);
+            bytes
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn t(minutes: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap() + Duration::minutes(minutes)
+    }
+
+    fn reading(site: &str, minutes: i64, value: f64) -> GaugeReading {
+        GaugeReading {
+            site_code: site.to_string(),
+            site_name: site.to_string(),
+            parameter_code: PARAM_STAGE.to_string(),
+            unit: "ft".to_string(),
+            value,
+            datetime: t(minutes).fixed_offset(),
+            qualifier: "P".to_string(),
+            original_unit: None,
+        }
+    }
+
+    fn stations() -> Vec<Station> {
+        let all = crate::stations::load_stations();
+        let stage_only = |code: &str| {
+            let mut s = all.iter().find(|s| s.site_code == code).unwrap().clone();
+            s.expected_parameters = vec![PARAM_STAGE.to_string()];
+            s
+        };
+        vec![stage_only("05568500"), stage_only("05567500")]
+    }
+
+    #[test]
+    fn test_window_slots_take_latest_reading_in_interval() {
+        let window = ExportWindow::new(t(0), t(60), 30).unwrap();
+        assert_eq!(window.times(), vec![t(0), t(30), t(60)]);
+        assert_eq!(window.slot(t(-10)), Some(0));
+        assert_eq!(window.slot(t(-30)), None);
+        assert_eq!(window.slot(t(15)), Some(1));
+        assert_eq!(window.slot(t(30)), Some(1));
+        assert_eq!(window.slot(t(31)), Some(2));
+        assert_eq!(window.slot(t(61)), None);
+
+        assert!(ExportWindow::new(t(0), t(60), 0).is_err());
+        assert!(ExportWindow::new(t(60), t(0), 15).is_err());
+        assert!(ExportWindow::new(t(0), t(60 * 24 * 365 * 10), 15).unwrap_err().contains("wider interval"));
+    }
+
+    #[test]
+    fn test_grid_is_station_major_with_fill_for_gaps() {
+        let window = ExportWindow::new(t(0), t(30), 15).unwrap();
+        let grid = build_grid(&stations(), &window, |site, parameter| {
+            assert_eq!(parameter, PARAM_STAGE, "stations without discharge are not queried for it");
+            Ok(match site {
+                "05568500" => vec![reading(site, 0, 17.0), reading(site, 10, 17.1), reading(site, 15, 17.2)],
+                _ => vec![reading(site, 30, 440.0)],
+            })
+        }).unwrap();
+
+        let stage = &grid.variables[0];
+        assert_eq!(stage.name, "stage");
+        assert_eq!(stage.values, vec![17.0, 17.2, FILL_VALUE, FILL_VALUE, FILL_VALUE, 440.0]);
+        assert!(grid.variables[1].values.iter().all(|v| *v == FILL_VALUE));
+    }
+
+    #[test]
+    fn test_netcdf_carries_cf_metadata_and_attribution() {
+        let window = ExportWindow::new(t(0), t(15), 15).unwrap();
+        let grid = build_grid(&stations(), &window, |site, _| Ok(vec![reading(site, 0, 1.0)])).unwrap();
+        let bytes = to_netcdf(&grid, t(0)).unwrap();
+        let contains = |needle: &str| bytes.windows(needle.len()).any(|w| w == needle.as_bytes());
+        assert_eq!(&bytes[..4], b"CDF\x02");
+        for needle in ["CF-1.8", "timeSeries", "timeseries_id", "05568500", "ft3 s-1", "U.S. Geological Survey"] {
+            assert!(contains(needle), "missing {}", needle);
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! Minimal NetCDF classic writer (64-bit offset format, "CDF-2").
+//!
+//! Only what the readings export needs: fixed-size dimensions (no record
+//! dimension), text and double attributes, and char and double variables.
+//! Everything is big-endian and padded to 4 bytes, as the format requires:
+//!
+//! ```text
+//! 'C' 'D' 'F' 0x02  numrecs=0
+//! NC_DIMENSION n  [name length]...
+//! NC_ATTRIBUTE n  [name type n values]...          (global)
+//! NC_VARIABLE  n  [name ndims dimids attrs type vsize begin]...
+//! variable data at each `begin`, in declaration order
+//! ```
+//!
+//! Files open in netCDF-C, xarray/netCDF4-python and the HEC tools'
+//! pre-processing scripts without a netCDF library on this side.
+
+const MAGIC: &[u8; 4] = b"CDF\x02";
+const NC_DIMENSION: u32 = 0x0A;
+const NC_VARIABLE: u32 = 0x0B;
+const NC_ATTRIBUTE: u32 = 0x0C;
+const NC_CHAR: u32 = 2;
+const NC_DOUBLE: u32 = 6;
+
+/// An attribute value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Attr {
+    Text(String),
+    Double(f64),
+}
+
+/// Values of a variable, row-major over its dimensions
+#[derive(Debug, Clone, PartialEq)]
+pub enum Values {
+    Char(Vec<u8>),
+    Double(Vec<f64>),
+}
+
+impl Values {
+    fn nc_type(&self) -> u32 {
+        match self {
+            Values::Char(_) => NC_CHAR,
+            Values::Double(_) => NC_DOUBLE,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Values::Char(v) => v.len(),
+            Values::Double(v) => v.len(),
+        }
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            Values::Char(bytes) => out.extend_from_slice(bytes),
+            Values::Double(values) => {
+                for v in values {
+                    out.extend_from_slice(&v.to_be_bytes());
+                }
+            }
+        }
+        pad(out);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Variable {
+    name: String,
+    dims: Vec<usize>,
+    attrs: Vec<(String, Attr)>,
+    values: Values,
+}
+
+/// A dataset under construction
+#[derive(Debug, Clone, Default)]
+pub struct Dataset {
+    dims: Vec<(String, usize)>,
+    attrs: Vec<(String, Attr)>,
+    vars: Vec<Variable>,
+}
+
+impl Dataset {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a dimension, returning its id
+    pub fn add_dim(&mut self, name: &str, len: usize) -> usize {
+        self.dims.push((name.to_string(), len));
+        self.dims.len() - 1
+    }
+
+    pub fn add_attr(&mut self, name: &str, value: Attr) {
+        self.attrs.push((name.to_string(), value));
+    }
+
+    /// Add a variable over `dims`; `values` must fill them exactly
+    pub fn add_var(
+        &mut self,
+        name: &str,
+        dims: &[usize],
+        attrs: Vec<(&str, Attr)>,
+        values: Values,
+    ) -> Result<(), String> {
+        let expected: usize = dims.iter()
+            .map(|&d| self.dims.get(d).map(|(_, len)| *len).ok_or_else(|| format!("{}: unknown dimension {}", name, d)))
+            .product::<Result<usize, String>>()?;
+        if values.len() != expected {
+            return Err(format!("{}: {} values for a {}-value shape", name, values.len(), expected));
+        }
+        self.vars.push(Variable {
+            name: name.to_string(),
+            dims: dims.to_vec(),
+            attrs: attrs.into_iter().map(|(n, v)| (n.to_string(), v)).collect(),
+            values,
+        });
+        Ok(())
+    }
+
+    /// The encoded file
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // Offsets are 8 bytes whatever their value, so the header length
+        // can be measured with placeholders first
+        let header_len = self.header(&vec![0; self.vars.len()]).len();
+        let mut begins = Vec::with_capacity(self.vars.len());
+        let mut offset = header_len as u64;
+        for var in &self.vars {
+            begins.push(offset);
+            offset += vsize(&var.values) as u64;
+        }
+
+        let mut out = self.header(&begins);
+        for var in &self.vars {
+            var.values.write(&mut out);
+        }
+        out
+    }
+
+    fn header(&self, begins: &[u64]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        put_u32(&mut out, 0); // numrecs: no record dimension
+
+        if self.dims.is_empty() {
+            put_absent(&mut out);
+        } else {
+            put_u32(&mut out, NC_DIMENSION);
+            put_u32(&mut out, self.dims.len() as u32);
+            for (name, len) in &self.dims {
+                put_name(&mut out, name);
+                put_u32(&mut out, *len as u32);
+            }
+        }
+
+        put_attrs(&mut out, &self.attrs);
+
+        if self.vars.is_empty() {
+            put_absent(&mut out);
+        } else {
+            put_u32(&mut out, NC_VARIABLE);
+            put_u32(&mut out, self.vars.len() as u32);
+            for (var, begin) in self.vars.iter().zip(begins) {
+                put_name(&mut out, &var.name);
+                put_u32(&mut out, var.dims.len() as u32);
+                for dim in &var.dims {
+                    put_u32(&mut out, *dim as u32);
+                }
+                put_attrs(&mut out, &var.attrs);
+                put_u32(&mut out, var.values.nc_type());
+                put_u32(&mut out, u32::try_from(vsize(&var.values)).unwrap_or(u32::MAX));
+                out.extend_from_slice(&begin.to_be_bytes());
+            }
+        }
+        out
+    }
+}
+
+/// Bytes a variable occupies, padded to 4
+fn vsize(values: &Values) -> usize {
+    let raw = match values {
+        Values::Char(v) => v.len(),
+        Values::Double(v) => v.len() * 8,
+    };
+    raw.div_ceil(4) * 4
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_absent(out: &mut Vec<u8>) {
+    put_u32(out, 0);
+    put_u32(out, 0);
+}
+
+fn pad(out: &mut Vec<u8>) {
+    while !out.len().is_multiple_of(4) {
+        out.push(0);
+    }
+}
+
+fn put_name(out: &mut Vec<u8>, name: &str) {
+    put_u32(out, name.len() as u32);
+    out.extend_from_slice(name.as_bytes());
+    pad(out);
+}
+
+fn put_attrs(out: &mut Vec<u8>, attrs: &[(String, Attr)]) {
+    if attrs.is_empty() {
+        put_absent(out);
+        return;
+    }
+    put_u32(out, NC_ATTRIBUTE);
+    put_u32(out, attrs.len() as u32);
+    for (name, value) in attrs {
+        put_name(out, name);
+        match value {
+            Attr::Text(text) => {
+                put_u32(out, NC_CHAR);
+                put_u32(out, text.len() as u32);
+                out.extend_from_slice(text.as_bytes());
+            }
+            Attr::Double(v) => {
+                put_u32(out, NC_DOUBLE);
+                put_u32(out, 1);
+                out.extend_from_slice(&v.to_be_bytes());
+            }
+        }
+        pad(out);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_header_layout_and_data_offsets() {
+        let mut ds = Dataset::new();
+        let station = ds.add_dim("station", 2);
+        let time = ds.add_dim("time", 3);
+        ds.add_attr("Conventions", Attr::Text("CF-1.8".to_string()));
+        ds.add_var("time", &[time], vec![("units", Attr::Text("minutes".to_string()))],
+            Values::Double(vec![0.0, 15.0, 30.0])).unwrap();
+        ds.add_var("stage", &[station, time], vec![("_FillValue", Attr::Double(-9999.0))],
+            Values::Double(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0])).unwrap();
+        let bytes = ds.to_bytes();
+
+        assert_eq!(&bytes[..4], b"CDF\x02");
+        assert_eq!(u32_at(&bytes, 4), 0);
+        assert_eq!(u32_at(&bytes, 8), NC_DIMENSION);
+        assert_eq!(u32_at(&bytes, 12), 2);
+        assert_eq!(u32_at(&bytes, 16), 7); // "station"
+        assert_eq!(&bytes[20..27], b"station");
+        assert_eq!(u32_at(&bytes, 28), 2);
+
+        // Data follows the header: 3 times then the 2x3 stage grid
+        let data_len = 3 * 8 + 6 * 8;
+        let start = bytes.len() - data_len;
+        let stage_first = f64::from_be_bytes(bytes[start + 24..start + 32].try_into().unwrap());
+        assert_eq!(stage_first, 1.0);
+        let last = f64::from_be_bytes(bytes[bytes.len() - 8..].try_into().unwrap());
+        assert_eq!(last, 6.0);
+        // The stage variable's begin offset points at its data
+        let begin = u64::from_be_bytes(bytes[start - 8..start].try_into().unwrap());
+        assert_eq!(begin as usize, start + 24);
+    }
+
+    #[test]
+    fn test_char_data_padded_and_shape_checked() {
+        let mut ds = Dataset::new();
+        let n = ds.add_dim("n", 5);
+        ds.add_var("id", &[n], vec![], Values::Char(b"abcde".to_vec())).unwrap();
+        let bytes = ds.to_bytes();
+        assert_eq!(bytes.len() % 4, 0);
+        assert_eq!(&bytes[bytes.len() - 8..bytes.len() - 3], b"abcde");
+
+        assert!(ds.add_var("bad", &[n], vec![], Values::Double(vec![1.0])).unwrap_err().contains("5-value"));
+        assert!(ds.add_var("bad", &[9], vec![], Values::Double(vec![])).is_err());
+    }
+}
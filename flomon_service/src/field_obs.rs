@@ -0,0 +1,323 @@
+//! Manual field observations: high-water marks, crest stakes, photos, sandbagging
+//!
+//! Post-event documentation (a surveyed high-water mark, a crest stake
+//! reading, a photo of the levee, a note that sandbagging started on a
+//! street) is stored in `flood_analysis.field_obs` next to the sensor data,
+//! tied to a station and optionally to a flood event.
+//!
+//! Entry points:
+//! - `flomon field-obs add|list` (CLI)
+//! - `GET /field_obs`, `POST /field_obs` (HTTP API); the POST needs the
+//!   `[field_obs]` bearer token and is disabled without it
+
+use chrono::{DateTime, Utc};
+use postgres::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::auth;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// `[field_obs]` section of flomon.toml; `POST /field_obs` is disabled
+/// without it
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldObsSettings {
+    /// Shared secret sent as `Authorization: Bearer <token>`
+    pub token: String,
+}
+
+impl FieldObsSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        auth::validate_token("field_obs.token", &self.token)
+    }
+
+    /// Whether `authorization` (the header value) carries the token
+    pub fn authorized(&self, authorization: Option<&str>) -> bool {
+        auth::bearer_authorized(authorization, &self.token)
+    }
+}
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// What was observed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldObsKind {
+    /// Surveyed high-water mark (debris line, mud line, seed line)
+    HighWaterMark,
+    /// Peak reading from a crest stake
+    CrestStake,
+    /// Photo documentation; `media_ref` holds the path or URL
+    Photo,
+    /// Sandbagging or other flood-fighting action
+    Sandbagging,
+}
+
+impl FieldObsKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldObsKind::HighWaterMark => "high_water_mark",
+            FieldObsKind::CrestStake => "crest_stake",
+            FieldObsKind::Photo => "photo",
+            FieldObsKind::Sandbagging => "sandbagging",
+        }
+    }
+
+    /// Parse the stored/CLI form; also accepts the short "hwm"
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "high_water_mark" | "hwm" => Some(FieldObsKind::HighWaterMark),
+            "crest_stake" => Some(FieldObsKind::CrestStake),
+            "photo" => Some(FieldObsKind::Photo),
+            "sandbagging" => Some(FieldObsKind::Sandbagging),
+            _ => None,
+        }
+    }
+
+    /// Kinds that record a water level
+    pub fn requires_value(&self) -> bool {
+        matches!(self, FieldObsKind::HighWaterMark | FieldObsKind::CrestStake)
+    }
+}
+
+/// One manual observation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldObservation {
+    /// Assigned by the database; ignored on insert
+    #[serde(default)]
+    pub id: Option<i64>,
+    pub kind: FieldObsKind,
+    pub observed_at: DateTime<Utc>,
+    /// USGS site code or CWMS location the observation is nearest to
+    pub site_code: Option<String>,
+    /// `flood_analysis.events.id` this documents, if known
+    pub event_id: Option<i32>,
+    /// Water level in feet (HWM elevation or crest stake reading)
+    pub value_ft: Option<f64>,
+    /// Datum of `value_ft`, e.g. "NGVD29", "NAVD88", "gauge"
+    pub datum: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Photo path or URL
+    pub media_ref: Option<String>,
+    pub notes: Option<String>,
+    pub observer: Option<String>,
+}
+
+impl FieldObservation {
+    /// Check required fields for the observation kind
+    pub fn validate(&self) -> Result<(), String> {
+        if self.kind.requires_value() {
+            if self.value_ft.is_none() {
+                return Err(format!("{} requires value_ft", self.kind.as_str()));
+            }
+            if self.datum.is_none() {
+                return Err(format!("{} requires a datum", self.kind.as_str()));
+            }
+        }
+        if self.kind == FieldObsKind::Photo && self.media_ref.is_none() {
+            return Err("photo requires media_ref (path or URL)".to_string());
+        }
+        if self.kind == FieldObsKind::Sandbagging && self.notes.is_none() {
+            return Err("sandbagging requires notes describing the action".to_string());
+        }
+        if self.site_code.is_none() && (self.latitude.is_none() || self.longitude.is_none()) {
+            return Err("observation needs a site_code or latitude/longitude".to_string());
+        }
+        if let (Some(lat), Some(lon)) = (self.latitude, self.longitude)
+            && (!(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon))
+        {
+            return Err(format!("invalid coordinates: {}, {}", lat, lon));
+        }
+        Ok(())
+    }
+}
+
+/// Filter for `list`
+#[derive(Debug, Default, Clone)]
+pub struct FieldObsFilter {
+    pub site_code: Option<String>,
+    pub event_id: Option<i32>,
+    pub kind: Option<FieldObsKind>,
+    pub limit: Option<i64>,
+}
+
+/// Default and maximum rows returned by `list`
+pub const DEFAULT_LIST_LIMIT: i64 = 100;
+const MAX_LIST_LIMIT: i64 = 1000;
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+/// Validate and insert an observation; returns the new id
+pub fn insert(client: &mut Client, obs: &FieldObservation) -> Result<i64, String> {
+    obs.validate()?;
+
+    let row = client.query_one(
+        "INSERT INTO flood_analysis.field_obs
+         (kind, observed_at, site_code, event_id, value_ft, datum,
+          latitude, longitude, media_ref, notes, observer)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+         RETURNING id",
+        &[
+            &obs.kind.as_str(),
+            &obs.observed_at,
+            &obs.site_code,
+            &obs.event_id,
+            &obs.value_ft,
+            &obs.datum,
+            &obs.latitude,
+            &obs.longitude,
+            &obs.media_ref,
+            &obs.notes,
+            &obs.observer,
+        ],
+    ).map_err(|e| format!("Failed to insert field observation: {}", e))?;
+
+    Ok(row.get(0))
+}
+
+/// Observations matching `filter`, newest first
+pub fn list(client: &mut Client, filter: &FieldObsFilter) -> Result<Vec<FieldObservation>, String> {
+    let limit = filter.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+    let kind = filter.kind.map(|k| k.as_str());
+
+    let rows = client.query(
+        "SELECT id, kind, observed_at, site_code, event_id, value_ft, datum,
+                latitude, longitude, media_ref, notes, observer
+         FROM flood_analysis.field_obs
+         WHERE ($1::TEXT IS NULL OR site_code = $1)
+           AND ($2::INTEGER IS NULL OR event_id = $2)
+           AND ($3::TEXT IS NULL OR kind = $3)
+         ORDER BY observed_at DESC
+         LIMIT $4",
+        &[&filter.site_code, &filter.event_id, &kind, &limit],
+    ).map_err(|e| format!("Failed to query field observations: {}", e))?;
+
+    rows.iter()
+        .map(|row| {
+            let kind: String = row.get(1);
+            Ok(FieldObservation {
+                id: Some(row.get(0)),
+                kind: FieldObsKind::parse(&kind)
+                    .ok_or_else(|| format!("Unknown field observation kind '{}'", kind))?,
+                observed_at: row.get(2),
+                site_code: row.get(3),
+                event_id: row.get(4),
+                value_ft: row.get(5),
+                datum: row.get(6),
+                latitude: row.get(7),
+                longitude: row.get(8),
+                media_ref: row.get(9),
+                notes: row.get(10),
+                observer: row.get(11),
+            })
+        })
+        .collect()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hwm() -> FieldObservation {
+        FieldObservation {
+            id: None,
+            kind: FieldObsKind::HighWaterMark,
+            observed_at: "2013-04-23T15:00:00Z".parse().unwrap(),
+            site_code: Some("05567500".to_string()),
+            event_id: None,
+            value_ft: Some(453.4),
+            datum: Some("NGVD29".to_string()),
+            latitude: None,
+            longitude: None,
+            media_ref: None,
+            notes: Some("Debris line on Water St. floodwall".to_string()),
+            observer: Some("county EMA".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_kind_round_trips_through_str() {
+        for kind in [
+            FieldObsKind::HighWaterMark,
+            FieldObsKind::CrestStake,
+            FieldObsKind::Photo,
+            FieldObsKind::Sandbagging,
+        ] {
+            assert_eq!(FieldObsKind::parse(kind.as_str()), Some(kind));
+        }
+        assert_eq!(FieldObsKind::parse("hwm"), Some(FieldObsKind::HighWaterMark));
+        assert_eq!(FieldObsKind::parse("rumor"), None);
+    }
+
+    #[test]
+    fn test_water_level_kinds_require_value_and_datum() {
+        assert!(hwm().validate().is_ok());
+        assert!(FieldObservation { value_ft: None, ..hwm() }.validate().is_err());
+        assert!(FieldObservation { datum: None, ..hwm() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_photo_and_sandbagging_requirements() {
+        let photo = FieldObservation {
+            kind: FieldObsKind::Photo,
+            value_ft: None,
+            datum: None,
+            ..hwm()
+        };
+        assert!(photo.validate().is_err());
+        assert!(FieldObservation { media_ref: Some("photos/2013/water_st.jpg".to_string()), ..photo }
+            .validate().is_ok());
+
+        let sandbagging = FieldObservation { kind: FieldObsKind::Sandbagging, notes: None, ..hwm() };
+        assert!(sandbagging.validate().is_err());
+    }
+
+    #[test]
+    fn test_settings_token() {
+        let settings = FieldObsSettings { token: "0123456789abcdef".to_string() };
+        assert!(settings.validate().is_ok());
+        assert!(settings.authorized(Some("Bearer 0123456789abcdef")));
+        assert!(!settings.authorized(Some("Bearer 0123456789abcdeX")));
+        assert!(!settings.authorized(None));
+        assert!(FieldObsSettings { token: String::new() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_location_required() {
+        let unplaced = FieldObservation { site_code: None, ..hwm() };
+        assert!(unplaced.validate().is_err());
+
+        let placed = FieldObservation { latitude: Some(40.69), longitude: Some(-89.59), ..unplaced };
+        assert!(placed.validate().is_ok());
+
+        let bad = FieldObservation { latitude: Some(140.0), ..placed };
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_deserializes_api_payload() {
+        let json = r#"{
+            "kind": "crest_stake",
+            "observed_at": "2019-06-08T12:00:00Z",
+            "site_code": "05568500",
+            "value_ft": 24.1,
+            "datum": "gauge"
+        }"#;
+        let obs: FieldObservation = serde_json::from_str(json).unwrap();
+        assert_eq!(obs.kind, FieldObsKind::CrestStake);
+        assert_eq!(obs.id, None);
+        assert!(obs.validate().is_ok());
+    }
+}
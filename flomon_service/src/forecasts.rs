@@ -0,0 +1,65 @@
+//! Stage forecasts from every forecasting agency, in one table.
+//!
+//! NWS, the Corps and the Illinois State Water Survey each forecast the
+//! Peoria pool reach. Whatever adapter reads a product normalizes it to
+//! `StageForecast` rows in `flood_analysis.stage_forecasts`
+//! (sql/032_stage_forecasts.sql), keyed by source, station, issue time and
+//! valid time, so a reissued product replaces its earlier values and
+//! products from different agencies sit side by side.
+//!
+//! `flood_analysis.forecast_verification` pairs each forecast value whose
+//! time has passed with the USGS stage observed nearest to it (within 30
+//! minutes), giving the error by source and lead time.
+
+use chrono::{DateTime, Utc};
+use postgres::Client;
+
+/// Agency that issued a forecast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ForecastSource {
+    Nws,
+    Cwms,
+    Isws,
+}
+
+impl ForecastSource {
+    /// Value of the `source` column
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ForecastSource::Nws => "nws",
+            ForecastSource::Cwms => "cwms",
+            ForecastSource::Isws => "isws",
+        }
+    }
+}
+
+/// One forecast stage at one station
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageForecast {
+    pub source: ForecastSource,
+    /// USGS site code of the station forecast
+    pub site_code: String,
+    /// The location as the product names it
+    pub product_location: String,
+    pub issued_at: DateTime<Utc>,
+    pub valid_at: DateTime<Utc>,
+    /// Feet above the station's gauge datum
+    pub stage_ft: f64,
+}
+
+/// Insert or replace forecast values; returns how many rows were written
+pub fn store(client: &mut Client, forecasts: &[StageForecast], fetched_at: DateTime<Utc>) -> Result<usize, String> {
+    let mut written = 0;
+    for f in forecasts {
+        written += client.execute(
+            "INSERT INTO flood_analysis.stage_forecasts
+             (source, site_code, issued_at, valid_at, stage_ft, product_location, fetched_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (source, site_code, issued_at, valid_at) DO UPDATE SET
+                stage_ft = EXCLUDED.stage_ft, product_location = EXCLUDED.product_location,
+                fetched_at = EXCLUDED.fetched_at",
+            &[&f.source.as_str(), &f.site_code, &f.issued_at, &f.valid_at, &f.stage_ft, &f.product_location, &fetched_at],
+        ).map_err(|e| format!("Failed to store {} forecast for {}: {}", f.source.as_str(), f.site_code, e))? as usize;
+    }
+    Ok(written)
+}
@@ -0,0 +1,263 @@
+//! Live GeoJSON for map dashboards.
+//!
+//! `GET /api/geojson/stations` and `GET /api/geojson/zones` answer with
+//! FeatureCollections a Leaflet or Mapbox layer can load directly, styled
+//! from the properties alone:
+//!
+//! - stations: one Point per registry station at its lat/lon, with the
+//!   latest stage and discharge, the severity key, label and color (see
+//!   `alert::scheme`), and the flood thresholds in effect
+//! - zones: one MultiPoint per zone over its sensors' locations, with the
+//!   zone's lead times and the worst severity among its USGS stations
+//!
+//! Unlike the static `/map` layers (see `basin_map`) these are built per
+//! request from the latest readings. The collection carries the registry
+//! version the severities were computed against and when it was built.
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use crate::alert::scheme::SeverityScheme;
+use crate::alert::thresholds::{check_station, FloodSeverity};
+use crate::cli_output::STALE_AFTER_MINUTES;
+use crate::model::{GaugeReading, PARAM_DISCHARGE, PARAM_STAGE};
+use crate::stations::Station;
+use crate::zones::{get_all_zones, ZoneMetadata, ZonesConfig};
+
+/// One station's current condition
+#[derive(Debug, Clone, PartialEq)]
+pub struct StationStatus {
+    pub site_code: String,
+    pub stage_ft: Option<f64>,
+    pub discharge_cfs: Option<f64>,
+    /// Time of the latest stage, else of the latest discharge
+    pub observed_at: Option<DateTime<Utc>>,
+    pub severity: Option<FloodSeverity>,
+    pub stale: bool,
+}
+
+/// Current condition of `station` from its latest readings
+pub fn station_status(station: &Station, latest: &[GaugeReading], now: DateTime<Utc>) -> StationStatus {
+    let newest = |parameter: &str| latest.iter()
+        .filter(|r| r.site_code == station.site_code && r.parameter_code == parameter)
+        .map(|r| (r, r.datetime.with_timezone(&Utc)))
+        .max_by_key(|(_, t)| *t);
+    let stage = newest(PARAM_STAGE);
+    let discharge = newest(PARAM_DISCHARGE);
+    let observed_at = stage.or(discharge).map(|(_, t)| t);
+    StationStatus {
+        site_code: station.site_code.clone(),
+        stage_ft: stage.map(|(r, _)| r.value),
+        discharge_cfs: discharge.map(|(r, _)| r.value),
+        observed_at,
+        severity: stage.and_then(|(r, _)| check_station(station, r)).map(|alert| alert.severity),
+        stale: observed_at.is_none_or(|t| (now - t).num_minutes() > STALE_AFTER_MINUTES as i64),
+    }
+}
+
+fn feature_collection(features: Vec<Value>, registry_version: Option<i32>, now: DateTime<Utc>) -> Value {
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+        "registry_version": registry_version,
+        "generated_at": now,
+    })
+}
+
+/// Severity key, label and color for a condition; no data beats stale
+fn style_properties(scheme: &SeverityScheme, severity: Option<&FloodSeverity>, stale: bool, has_data: bool) -> Value {
+    let style = if has_data { scheme.style_for(severity, stale) } else { &scheme.no_data };
+    json!({"severity": style.key, "severity_label": style.label, "severity_color": style.color})
+}
+
+fn merge(mut properties: Value, extra: Value) -> Value {
+    if let (Some(properties), Value::Object(extra)) = (properties.as_object_mut(), extra) {
+        properties.extend(extra);
+    }
+    properties
+}
+
+/// Point per station, statuses matched by site code
+pub fn stations_collection(
+    stations: &[Station],
+    statuses: &[StationStatus],
+    scheme: &SeverityScheme,
+    registry_version: Option<i32>,
+    now: DateTime<Utc>,
+) -> Value {
+    let features = stations.iter()
+        .map(|station| {
+            let status = statuses.iter().find(|s| s.site_code == station.site_code);
+            let properties = json!({
+                "site_code": station.site_code,
+                "name": station.name,
+                "river_mile": station.river_mile,
+                "stage_ft": status.and_then(|s| s.stage_ft),
+                "discharge_cfs": status.and_then(|s| s.discharge_cfs),
+                "observed_at": status.and_then(|s| s.observed_at),
+                "stale": status.is_none_or(|s| s.stale),
+                "thresholds": station.thresholds.as_ref().map(|t| json!({
+                    "action_stage_ft": t.action_stage_ft,
+                    "flood_stage_ft": t.flood_stage_ft,
+                    "moderate_flood_stage_ft": t.moderate_flood_stage_ft,
+                    "major_flood_stage_ft": t.major_flood_stage_ft,
+                })),
+                "pool_deviation": station.pool_deviation.as_ref().map(|p| json!({
+                    "target_elevation_ft": p.target_elevation_ft,
+                    "action_ft": p.action_ft,
+                    "flood_ft": p.flood_ft,
+                    "moderate_ft": p.moderate_ft,
+                    "major_ft": p.major_ft,
+                })),
+            });
+            let style = style_properties(
+                scheme,
+                status.and_then(|s| s.severity.as_ref()),
+                status.is_none_or(|s| s.stale),
+                status.is_some_and(|s| s.observed_at.is_some()),
+            );
+            json!({
+                "type": "Feature",
+                "id": station.site_code,
+                "geometry": {"type": "Point", "coordinates": [station.longitude, station.latitude]},
+                "properties": merge(properties, style),
+            })
+        })
+        .collect();
+    feature_collection(features, registry_version, now)
+}
+
+/// MultiPoint per zone over its sensors, with the worst severity among
+/// its USGS stations
+pub fn zones_collection(
+    zones: &ZonesConfig,
+    statuses: &[StationStatus],
+    scheme: &SeverityScheme,
+    registry_version: Option<i32>,
+    now: DateTime<Utc>,
+) -> Value {
+    let features = get_all_zones(zones).into_iter()
+        .map(|(zone_id, zone)| {
+            let metadata = ZoneMetadata::for_zone(zone_id);
+            let zone_statuses: Vec<&StationStatus> = zone.usgs_sensors().iter()
+                .filter_map(|sensor| sensor.usgs_id.as_deref())
+                .filter_map(|site| statuses.iter().find(|s| s.site_code == site))
+                .collect();
+            let worst = zone_statuses.iter().filter(|s| !s.stale).filter_map(|s| s.severity.as_ref()).max();
+            let mut above_action: Vec<&str> = zone_statuses.iter()
+                .filter(|s| s.severity.is_some())
+                .map(|s| s.site_code.as_str())
+                .collect();
+            above_action.sort_unstable();
+            above_action.dedup();
+            let has_data = zone_statuses.iter().any(|s| s.observed_at.is_some());
+            let all_stale = zone_statuses.iter().all(|s| s.stale);
+
+            let properties = json!({
+                "zone_id": zone_id,
+                "name": zone.name,
+                "description": zone.description,
+                "lead_time_hours_min": metadata.lead_time_hours_min,
+                "lead_time_hours_max": metadata.lead_time_hours_max,
+                "primary_alert_condition": metadata.primary_alert_condition,
+                "sensor_count": zone.sensors.len(),
+                "stations_above_action": above_action,
+            });
+            let coordinates: Vec<[f64; 2]> = zone.sensors.iter().map(|s| [s.lon, s.lat]).collect();
+            json!({
+                "type": "Feature",
+                "id": zone_id,
+                "geometry": {"type": "MultiPoint", "coordinates": coordinates},
+                "properties": merge(properties, style_properties(scheme, worst, worst.is_none() && all_stale, has_data)),
+            })
+        })
+        .collect();
+    feature_collection(features, registry_version, now)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stations::load_stations;
+    use crate::zones::load_zones_default;
+    use chrono::{Duration, TimeZone};
+
+    fn reading(site_code: &str, parameter_code: &str, value: f64, time: DateTime<Utc>) -> GaugeReading {
+        GaugeReading {
+            site_code: site_code.to_string(),
+            site_name: site_code.to_string(),
+            parameter_code: parameter_code.to_string(),
+            unit: if parameter_code == PARAM_STAGE { "ft" } else { "ft3/s" }.to_string(),
+            value,
+            datetime: time.fixed_offset(),
+            qualifier: "P".to_string(),
+            original_unit: None,
+        }
+    }
+
+    #[test]
+    fn test_station_features_carry_condition_and_thresholds() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let stations = load_stations();
+        let station = stations.iter().find(|s| s.thresholds.is_some()).unwrap();
+        let flood = station.thresholds.as_ref().unwrap().flood_stage_ft;
+        let latest = vec![
+            reading(&station.site_code, PARAM_STAGE, flood + 0.5, now - Duration::minutes(15)),
+            reading(&station.site_code, PARAM_DISCHARGE, 31200.0, now - Duration::minutes(15)),
+        ];
+        let statuses: Vec<StationStatus> = stations.iter().map(|s| station_status(s, &latest, now)).collect();
+        let scheme = SeverityScheme::default();
+
+        let collection = stations_collection(&stations, &statuses, &scheme, Some(7), now);
+        assert_eq!(collection["type"], "FeatureCollection");
+        assert_eq!(collection["registry_version"], 7);
+        let features = collection["features"].as_array().unwrap();
+        assert_eq!(features.len(), stations.len());
+
+        let feature = features.iter().find(|f| f["id"] == station.site_code.as_str()).unwrap();
+        assert_eq!(feature["geometry"]["coordinates"][0], station.longitude);
+        assert_eq!(feature["geometry"]["coordinates"][1], station.latitude);
+        let properties = &feature["properties"];
+        assert_eq!(properties["stage_ft"], flood + 0.5);
+        assert_eq!(properties["discharge_cfs"], 31200.0);
+        assert_eq!(properties["thresholds"]["flood_stage_ft"], flood);
+        assert_ne!(properties["severity"], "normal");
+        assert_eq!(properties["severity_color"], scheme.level(properties["severity"].as_str().unwrap()).unwrap().color);
+
+        // Stations without readings are "no data"
+        let silent = features.iter().find(|f| f["id"] != station.site_code.as_str()).unwrap();
+        assert_eq!(silent["properties"]["severity"], scheme.no_data.key);
+        assert_eq!(silent["properties"]["stale"], true);
+    }
+
+    #[test]
+    fn test_zone_features_take_the_worst_station() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let zones = load_zones_default().unwrap();
+        let stations = load_stations();
+        let (zone_id, zone) = get_all_zones(&zones).into_iter()
+            .find(|(_, z)| z.usgs_sensors().iter().any(|s| {
+                s.usgs_id.as_deref().is_some_and(|id| stations.iter().any(|st| st.site_code == id && st.thresholds.is_some()))
+            }))
+            .unwrap();
+        let station = stations.iter()
+            .find(|st| st.thresholds.is_some() && zone.usgs_sensors().iter().any(|s| s.usgs_id.as_deref() == Some(&st.site_code)))
+            .unwrap();
+        let major = station.thresholds.as_ref().unwrap().major_flood_stage_ft;
+        let latest = vec![reading(&station.site_code, PARAM_STAGE, major + 1.0, now)];
+        let statuses: Vec<StationStatus> = stations.iter().map(|s| station_status(s, &latest, now)).collect();
+
+        let collection = zones_collection(&zones, &statuses, &SeverityScheme::default(), None, now);
+        let feature = collection["features"].as_array().unwrap().iter()
+            .find(|f| f["id"] == zone_id)
+            .unwrap();
+        assert_eq!(feature["geometry"]["type"], "MultiPoint");
+        assert_eq!(feature["geometry"]["coordinates"].as_array().unwrap().len(), zone.sensors.len());
+        assert_eq!(feature["properties"]["severity"], "major");
+        assert_eq!(feature["properties"]["stations_above_action"], json!([station.site_code]));
+    }
+}
@@ -0,0 +1,170 @@
+//! Base URLs of the external data APIs (`[endpoints]` in flomon.toml).
+//!
+//! The source modules build request URLs on these instead of on their own
+//! constants, so a deployment can point them somewhere else: a mirror, a
+//! caching proxy, or the recorded-response fixture server in `testing`.
+//! Every key defaults to the public API:
+//!
+//! ```toml
+//! [endpoints]
+//! usgs_iv = "https://waterservices.usgs.gov/nwis/iv/"
+//! cwms = "http://cache.local:8081/cwms-data"
+//! ```
+//!
+//! Like the retry policy (`retry::set_policy`) the setting is process-wide
+//! and the first `set` wins; `flomon` sets it when it loads flomon.toml.
+//! The NWS alerts feed and MRMS grids have their own `url` settings in
+//! `[nws_alerts]` and `[mrms]`.
+
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+pub const USGS_IV: &str = "https://waterservices.usgs.gov/nwis/iv/";
+pub const USGS_DV: &str = "https://waterservices.usgs.gov/nwis/dv/";
+pub const USGS_STATS: &str = "https://waterservices.usgs.gov/nwis/stat/";
+pub const USGS_RATINGS: &str = "https://waterdata.usgs.gov/nwisweb/get_ratings";
+pub const USGS_MEASUREMENTS: &str = "https://waterdata.usgs.gov/nwis/measurements";
+pub const USGS_PEAK: &str = "https://nwis.waterdata.usgs.gov/nwis/peak";
+pub const CWMS: &str = "https://cwms-data.usace.army.mil/cwms-data";
+pub const IEM: &str = "https://mesonet.agron.iastate.edu";
+pub const NWS: &str = "https://api.weather.gov";
+
+/// Where each API's requests go
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BaseUrls {
+    /// USGS instantaneous values, with the trailing slash
+    pub usgs_iv: String,
+    /// USGS daily values, with the trailing slash
+    pub usgs_dv: String,
+    /// USGS daily statistics, with the trailing slash
+    pub usgs_stats: String,
+    /// USGS expanded rating tables
+    pub usgs_ratings: String,
+    /// USGS field measurements
+    pub usgs_measurements: String,
+    /// USGS annual peak flows
+    pub usgs_peak: String,
+    /// CWMS Data API root, without a trailing slash
+    pub cwms: String,
+    /// IEM root, without a trailing slash
+    pub iem: String,
+    /// api.weather.gov root (station forecast zones), without a trailing slash
+    pub nws: String,
+}
+
+impl Default for BaseUrls {
+    fn default() -> Self {
+        Self {
+            usgs_iv: USGS_IV.to_string(),
+            usgs_dv: USGS_DV.to_string(),
+            usgs_stats: USGS_STATS.to_string(),
+            usgs_ratings: USGS_RATINGS.to_string(),
+            usgs_measurements: USGS_MEASUREMENTS.to_string(),
+            usgs_peak: USGS_PEAK.to_string(),
+            cwms: CWMS.to_string(),
+            iem: IEM.to_string(),
+            nws: NWS.to_string(),
+        }
+    }
+}
+
+impl BaseUrls {
+    /// Every API served from one host, on the public APIs' paths
+    /// (`{root}/nwis/iv/`, `{root}/cwms-data`, ...)
+    pub fn all_at(root: &str) -> Self {
+        let root = root.trim_end_matches('/');
+        Self {
+            usgs_iv: format!("{}/nwis/iv/", root),
+            usgs_dv: format!("{}/nwis/dv/", root),
+            usgs_stats: format!("{}/nwis/stat/", root),
+            usgs_ratings: format!("{}/nwisweb/get_ratings", root),
+            usgs_measurements: format!("{}/nwis/measurements", root),
+            usgs_peak: format!("{}/nwis/peak", root),
+            cwms: format!("{}/cwms-data", root),
+            iem: root.to_string(),
+            nws: root.to_string(),
+        }
+    }
+
+    fn named(&self) -> [(&'static str, &str); 9] {
+        [
+            ("usgs_iv", &self.usgs_iv),
+            ("usgs_dv", &self.usgs_dv),
+            ("usgs_stats", &self.usgs_stats),
+            ("usgs_ratings", &self.usgs_ratings),
+            ("usgs_measurements", &self.usgs_measurements),
+            ("usgs_peak", &self.usgs_peak),
+            ("cwms", &self.cwms),
+            ("iem", &self.iem),
+            ("nws", &self.nws),
+        ]
+    }
+
+    /// Every URL must be http(s) with a host
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, url) in self.named() {
+            let host = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://"));
+            if host.is_none_or(|rest| rest.is_empty() || rest.starts_with('/')) {
+                return Err(format!("endpoints.{} must be an http:// or https:// URL, got {:?}", name, url));
+            }
+        }
+        Ok(())
+    }
+
+    /// Keys pointed away from the public APIs, for the startup log
+    pub fn overridden(&self) -> Vec<&'static str> {
+        let defaults = BaseUrls::default();
+        self.named().into_iter()
+            .zip(defaults.named())
+            .filter(|((_, url), (_, default))| url != default)
+            .map(|((name, _), _)| name)
+            .collect()
+    }
+}
+
+static BASE_URLS: OnceLock<BaseUrls> = OnceLock::new();
+
+/// Set the process-wide base URLs (first call wins)
+pub fn set(urls: BaseUrls) {
+    let _ = BASE_URLS.set(urls);
+}
+
+/// The process-wide base URLs, the public APIs if none were set
+pub fn get() -> BaseUrls {
+    BASE_URLS.get().cloned().unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_section_keeps_public_defaults() {
+        let urls: BaseUrls = toml::from_str("cwms = \"http://cache.local:8081/cwms-data\"").unwrap();
+        assert_eq!(urls.cwms, "http://cache.local:8081/cwms-data");
+        assert_eq!(urls.usgs_iv, USGS_IV);
+        assert_eq!(urls.overridden(), vec!["cwms"]);
+        assert!(urls.validate().is_ok());
+        assert!(BaseUrls::default().overridden().is_empty());
+
+        assert!(toml::from_str::<BaseUrls>("usgs = \"http://x\"").is_err(), "unknown key");
+        let bad = BaseUrls { iem: "mesonet.agron.iastate.edu".to_string(), ..BaseUrls::default() };
+        assert!(bad.validate().unwrap_err().contains("endpoints.iem"));
+        let hostless = BaseUrls { nws: "https:///points".to_string(), ..BaseUrls::default() };
+        assert!(hostless.validate().is_err());
+    }
+
+    #[test]
+    fn test_all_at_uses_the_public_paths() {
+        let urls = BaseUrls::all_at("http://127.0.0.1:4000/");
+        assert_eq!(urls.usgs_iv, "http://127.0.0.1:4000/nwis/iv/");
+        assert_eq!(urls.cwms, "http://127.0.0.1:4000/cwms-data");
+        assert_eq!(urls.iem, "http://127.0.0.1:4000");
+        assert!(urls.validate().is_ok());
+    }
+}
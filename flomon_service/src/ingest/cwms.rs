@@ -1,16 +1,20 @@
-/// USACE CWMS Data API Client
-///
-/// Retrieves timeseries data from Corps Water Management System (CWMS) API
-/// for Mississippi River backwater detection and lock/dam operations monitoring.
-///
-/// API Documentation: https://cwms-data.usace.army.mil/cwms-data/swagger-ui.html
-/// Base URL: https://cwms-data.usace.army.mil/cwms-data/
+//! USACE CWMS Data API Client
+//!
+//! Retrieves timeseries data from Corps Water Management System (CWMS) API
+//! for Mississippi River backwater detection and lock/dam operations monitoring.
+//!
+//! API Documentation: https://cwms-data.usace.army.mil/cwms-data/swagger-ui.html
+//! Base URL: https://cwms-data.usace.army.mil/cwms-data/
 
+use crate::ingest::base_urls;
+use crate::ingest::cwms_quality::CwmsQuality;
+use crate::units;
+use crate::ingest::retry;
+use crate::logging::DataSource;
+use crate::model::IngestError;
 use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::Deserialize;
 
-const CWMS_API_BASE: &str = "https://cwms-data.usace.army.mil/cwms-data";
-
 // ============================================================================
 // CWMS API Request/Response Structures
 // ============================================================================
@@ -74,9 +78,18 @@ pub struct CwmsTimeseries {
     pub timestamp: DateTime<Utc>,
     pub value: f64,
     pub unit: String,
+    /// Unit reported by CWMS when it differed from the canonical `unit`
+    pub original_unit: Option<String>,
     pub quality_code: i32,
 }
 
+impl CwmsTimeseries {
+    /// Decoded `quality_code`
+    pub fn quality(&self) -> CwmsQuality {
+        CwmsQuality::from_code(self.quality_code)
+    }
+}
+
 // ============================================================================
 // API Client
 // ============================================================================
@@ -94,50 +107,57 @@ pub fn fetch_timeseries(
     office_id: &str,
     begin: DateTime<Utc>,
     end: DateTime<Utc>,
-) -> Result<Vec<CwmsTimeseries>, Box<dyn std::error::Error>> {
+) -> Result<Vec<CwmsTimeseries>, IngestError> {
     
-    let url = format!(
+    let url = timeseries_url(timeseries_id, office_id, begin, end);
+    
+    println!("   Fetching: {}", url);
+    
+    let body = retry::get_text_blocking(client, DataSource::Cwms, &url, true)?;
+    
+    parse_timeseries(timeseries_id, &body)
+}
+
+/// Timeseries request URL for `begin..=end`
+pub fn timeseries_url(timeseries_id: &str, office_id: &str, begin: DateTime<Utc>, end: DateTime<Utc>) -> String {
+    format!(
         "{}/timeseries?name={}&office={}&begin={}&end={}",
-        CWMS_API_BASE,
+        base_urls::get().cwms,
         urlencoding::encode(timeseries_id),
         office_id,
         begin.format("%Y-%m-%dT%H:%M:%S"),
         end.format("%Y-%m-%dT%H:%M:%S")
-    );
-    
-    println!("   Fetching: {}", url);
-    
-    let response = client
-        .get(&url)
-        .header("Accept", "application/json")
-        .send()?;
-    
-    if !response.status().is_success() {
-        return Err(format!("CWMS API error: {}", response.status()).into());
-    }
-    
-    let api_response: CwmsTimeseriesResponse = response.json()?;
+    )
+}
+
+/// Parse a timeseries response body into canonical-unit records
+pub fn parse_timeseries(timeseries_id: &str, body: &str) -> Result<Vec<CwmsTimeseries>, IngestError> {
+    let api_response: CwmsTimeseriesResponse = serde_json::from_str(body)
+        .map_err(|e| IngestError::Parse(format!("CWMS timeseries {}: {}", timeseries_id, e)))?;
     
     // Parse timeseries ID to extract components
     let parts: Vec<&str> = timeseries_id.split('.').collect();
-    let location_id = parts.get(0).unwrap_or(&"unknown").to_string();
+    let location_id = parts.first().unwrap_or(&"unknown").to_string();
     let parameter_id = parts.get(1).unwrap_or(&"unknown").to_string();
     
+    let conversion = units::for_cwms(&parameter_id, &api_response.units).map_err(IngestError::Parse)?;
+    
     let mut records = Vec::new();
     
     if let Some(values) = api_response.values {
         for val in values {
             // Convert milliseconds to DateTime
             let timestamp = DateTime::from_timestamp(val.date_time / 1000, 0)
-                .ok_or("Invalid timestamp")?;
+                .ok_or_else(|| IngestError::Parse(format!("invalid timestamp {}", val.date_time)))?;
             
             records.push(CwmsTimeseries {
                 timeseries_id: timeseries_id.to_string(),
                 location_id: location_id.clone(),
                 parameter_id: parameter_id.clone(),
                 timestamp,
-                value: val.value,
-                unit: api_response.units.clone(),
+                value: conversion.apply(val.value),
+                unit: conversion.unit.clone(),
+                original_unit: conversion.original_unit.clone(),
                 quality_code: val.quality,
             });
         }
@@ -152,7 +172,7 @@ pub fn fetch_recent(
     timeseries_id: &str,
     office_id: &str,
     hours: i64,
-) -> Result<Vec<CwmsTimeseries>, Box<dyn std::error::Error>> {
+) -> Result<Vec<CwmsTimeseries>, IngestError> {
     
     let end = Utc::now();
     let begin = end - chrono::Duration::hours(hours);
@@ -167,7 +187,7 @@ pub fn fetch_historical(
     office_id: &str,
     start_date: NaiveDateTime,
     end_date: NaiveDateTime,
-) -> Result<Vec<CwmsTimeseries>, Box<dyn std::error::Error>> {
+) -> Result<Vec<CwmsTimeseries>, IngestError> {
     
     let begin = DateTime::<Utc>::from_naive_utc_and_offset(start_date, Utc);
     let end = DateTime::<Utc>::from_naive_utc_and_offset(end_date, Utc);
@@ -194,33 +214,27 @@ pub fn fetch_historical(
 /// // Returns all timeseries like:
 /// //   Peoria-Pool.Elev.Inst.~1Hour.0.CBT-RAW
 /// //   Peoria-TW.Elev.Inst.~1Hour.0.CBT-RAW
-/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # Ok::<(), flomon_service::model::IngestError>(())
 /// ```
 pub fn discover_timeseries(
     client: &reqwest::blocking::Client,
     office: &str,
     location_pattern: &str,
-) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+) -> Result<Vec<String>, IngestError> {
     
     let url = format!(
         "{}/catalog/TIMESERIES?office={}&like={}&format=json",
-        CWMS_API_BASE,
+        base_urls::get().cwms,
         office,
         urlencoding::encode(location_pattern)
     );
     
     println!("   Querying CWMS catalog: {}", url);
     
-    let response = client
-        .get(&url)
-        .header("Accept", "application/json")
-        .send()?;
-    
-    if !response.status().is_success() {
-        return Err(format!("CWMS catalog API error: {}", response.status()).into());
-    }
+    let body = retry::get_text_blocking(client, DataSource::Cwms, &url, true)?;
     
-    let catalog: CwmsCatalogResponse = response.json()?;
+    let catalog: CwmsCatalogResponse = serde_json::from_str(&body)
+        .map_err(|e| IngestError::Parse(format!("CWMS catalog: {}", e)))?;
     
     let timeseries_ids = catalog.entries
         .unwrap_or_default()
@@ -238,20 +252,8 @@ pub fn discover_pool_elevation(
     client: &reqwest::blocking::Client,
     office: &str,
     location_base: &str,
-) -> Result<Option<String>, Box<dyn std::error::Error>> {
-    
-    let pattern = format!("{}.*", location_base);
-    let all_timeseries = discover_timeseries(client, office, &pattern)?;
-    
-    // Look for pool elevation timeseries
-    // Prioritize: Pool.Elev.Inst > Pool.Elev.Ave > any with "Pool" and "Elev"
-    let pool_elev = all_timeseries.iter()
-        .find(|ts| ts.contains("-Pool.") && ts.contains(".Elev.Inst"))
-        .or_else(|| all_timeseries.iter().find(|ts| ts.contains("-Pool.") && ts.contains(".Elev.")))
-        .or_else(|| all_timeseries.iter().find(|ts| ts.contains("Pool") && ts.contains("Elev")))
-        .cloned();
-    
-    Ok(pool_elev)
+) -> Result<Option<String>, IngestError> {
+    discover_one(client, office, location_base, CwmsParameter::PoolElevation)
 }
 
 /// Discover tailwater elevation timeseries for a location
@@ -259,19 +261,8 @@ pub fn discover_tailwater_elevation(
     client: &reqwest::blocking::Client,
     office: &str,
     location_base: &str,
-) -> Result<Option<String>, Box<dyn std::error::Error>> {
-    
-    let pattern = format!("{}.*", location_base);
-    let all_timeseries = discover_timeseries(client, office, &pattern)?;
-    
-    // Look for tailwater elevation timeseries
-    // Patterns: -TW.Elev, -Tailwater.Elev, TW-*.Elev
-    let tw_elev = all_timeseries.iter()
-        .find(|ts| ts.contains("-TW.") && ts.contains(".Elev.Inst"))
-        .or_else(|| all_timeseries.iter().find(|ts| (ts.contains("-TW.") || ts.contains("TW-") || ts.contains("Tailwater")) && ts.contains(".Elev.")))
-        .cloned();
-    
-    Ok(tw_elev)
+) -> Result<Option<String>, IngestError> {
+    discover_one(client, office, location_base, CwmsParameter::TailwaterElevation)
 }
 
 /// Discover stage timeseries for a river gauge location (not a pool)
@@ -279,18 +270,171 @@ pub fn discover_stage(
     client: &reqwest::blocking::Client,
     office: &str,
     location_base: &str,
-) -> Result<Option<String>, Box<dyn std::error::Error>> {
-    
-    let pattern = format!("{}.*", location_base);
-    let all_timeseries = discover_timeseries(client, office, &pattern)?;
-    
-    // Look for stage timeseries (for river gauges, not pools)
-    let stage = all_timeseries.iter()
-        .find(|ts| ts.contains(".Stage.Inst"))
-        .or_else(|| all_timeseries.iter().find(|ts| ts.contains(".Stage.")))
-        .cloned();
-    
-    Ok(stage)
+) -> Result<Option<String>, IngestError> {
+    discover_one(client, office, location_base, CwmsParameter::Stage)
+}
+
+fn discover_one(
+    client: &reqwest::blocking::Client,
+    office: &str,
+    location_base: &str,
+    parameter: CwmsParameter,
+) -> Result<Option<String>, IngestError> {
+    let all_timeseries = discover_timeseries(client, office, &format!("{}.*", location_base))?;
+    Ok(parameter.select(&all_timeseries))
+}
+
+/// Discover every `wanted` parameter for a location from one catalog query
+pub fn discover_location(
+    client: &reqwest::blocking::Client,
+    office: &str,
+    location_base: &str,
+    wanted: &[CwmsParameter],
+) -> Result<Vec<(CwmsParameter, String)>, IngestError> {
+    let all_timeseries = discover_timeseries(client, office, &format!("{}.*", location_base))?;
+    Ok(wanted.iter()
+        .filter_map(|&parameter| parameter.select(&all_timeseries).map(|id| (parameter, id)))
+        .collect())
+}
+
+// ============================================================================
+// Location Parameters
+// ============================================================================
+
+/// What a discovered timeseries measures at a location. Offices name the
+/// same measurement differently ("Peoria-TW.Elev", "LaGrange.Elev-Tailwater",
+/// "Flow-Out" vs "Flow-Res Out"), so records are stored under the
+/// `parameter_id` of their measurement rather than the one in the id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CwmsParameter {
+    PoolElevation,
+    TailwaterElevation,
+    /// River gauge stage
+    Stage,
+    /// River gauge discharge
+    Discharge,
+    /// Release through a dam
+    Outflow,
+    GateOpening,
+}
+
+impl CwmsParameter {
+    pub const ALL: [CwmsParameter; 6] = [
+        CwmsParameter::PoolElevation,
+        CwmsParameter::TailwaterElevation,
+        CwmsParameter::Stage,
+        CwmsParameter::Discharge,
+        CwmsParameter::Outflow,
+        CwmsParameter::GateOpening,
+    ];
+
+    /// Parameter for a `data_types` entry in usace_stations.toml
+    pub fn from_data_type(data_type: &str) -> Option<Self> {
+        match data_type {
+            "pool_elevation" => Some(CwmsParameter::PoolElevation),
+            "tailwater_elevation" => Some(CwmsParameter::TailwaterElevation),
+            "stage" => Some(CwmsParameter::Stage),
+            "discharge" => Some(CwmsParameter::Discharge),
+            "outflow" => Some(CwmsParameter::Outflow),
+            "gate_opening" => Some(CwmsParameter::GateOpening),
+            _ => None,
+        }
+    }
+
+    /// `parameter_id` stored for this measurement
+    pub fn parameter_id(self) -> &'static str {
+        match self {
+            CwmsParameter::PoolElevation => "Elev",
+            CwmsParameter::TailwaterElevation => "Elev-Tailwater",
+            CwmsParameter::Stage => "Stage",
+            CwmsParameter::Discharge => "Flow",
+            CwmsParameter::Outflow => "Flow-Out",
+            CwmsParameter::GateOpening => "Opening-Gate",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CwmsParameter::PoolElevation => "pool elevation",
+            CwmsParameter::TailwaterElevation => "tailwater elevation",
+            CwmsParameter::Stage => "stage",
+            CwmsParameter::Discharge => "discharge",
+            CwmsParameter::Outflow => "outflow",
+            CwmsParameter::GateOpening => "gate opening",
+        }
+    }
+
+    /// Best timeseries for this parameter in a location's catalog,
+    /// preferring instantaneous values
+    pub fn select(self, catalog: &[String]) -> Option<String> {
+        let find = |matches: &dyn Fn(&str) -> bool| catalog.iter().find(|ts| matches(ts)).cloned();
+        let parameter = |ts: &str| ts.split('.').nth(1).unwrap_or("").to_string();
+        let inst = |ts: &str| ts.split('.').nth(2) == Some("Inst");
+        match self {
+            // Prioritize: Pool.Elev.Inst > Pool.Elev.Ave > any with "Pool" and "Elev"
+            CwmsParameter::PoolElevation => find(&|ts| ts.contains("-Pool.") && ts.contains(".Elev.Inst"))
+                .or_else(|| find(&|ts| ts.contains("-Pool.") && ts.contains(".Elev.")))
+                .or_else(|| find(&|ts| ts.contains("Pool") && ts.contains("Elev"))),
+            // Patterns: -TW.Elev, -Tailwater.Elev, TW-*.Elev
+            CwmsParameter::TailwaterElevation => find(&|ts| ts.contains("-TW.") && ts.contains(".Elev.Inst"))
+                .or_else(|| find(&|ts| (ts.contains("-TW.") || ts.contains("TW-") || ts.contains("Tailwater")) && ts.contains(".Elev"))),
+            CwmsParameter::Stage => find(&|ts| ts.contains(".Stage.Inst"))
+                .or_else(|| find(&|ts| ts.contains(".Stage."))),
+            CwmsParameter::Discharge => find(&|ts| parameter(ts) == "Flow" && inst(ts))
+                .or_else(|| find(&|ts| parameter(ts) == "Flow")),
+            // "Flow-Out", "Flow-Res Out", "Flow-Outflow"
+            CwmsParameter::Outflow => {
+                let outflow = |ts: &str| {
+                    let p = parameter(ts);
+                    p.starts_with("Flow-") && p.contains("Out")
+                };
+                find(&|ts| outflow(ts) && inst(ts)).or_else(|| find(&outflow))
+            }
+            // "Opening", "Opening-Gate", "Opening-Gate Total"
+            CwmsParameter::GateOpening => {
+                let opening = |ts: &str| parameter(ts).starts_with("Opening");
+                find(&|ts| opening(ts) && inst(ts)).or_else(|| find(&opening))
+            }
+        }
+    }
+
+    /// Store `records` under this measurement's `parameter_id`
+    pub fn normalize(self, records: &mut [CwmsTimeseries]) {
+        for record in records {
+            record.parameter_id = self.parameter_id().to_string();
+        }
+    }
+}
+
+/// Everything fetched for one location in a cycle
+#[derive(Debug, Default)]
+pub struct LocationFetch {
+    pub records: Vec<CwmsTimeseries>,
+    /// Series that could not be fetched, with the error
+    pub failures: Vec<(CwmsParameter, String)>,
+}
+
+/// Fetch every discovered timeseries of a location for `begin..=end`,
+/// converted to canonical units and stored under consistent parameter ids.
+/// A failing series is recorded in `failures` and the rest still fetched.
+pub fn fetch_location(
+    client: &reqwest::blocking::Client,
+    series: &[(CwmsParameter, String)],
+    office_id: &str,
+    begin: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> LocationFetch {
+    let mut fetched = LocationFetch::default();
+    for (parameter, timeseries_id) in series {
+        match fetch_timeseries(client, timeseries_id, office_id, begin, end) {
+            Ok(mut records) => {
+                parameter.normalize(&mut records);
+                fetched.records.extend(records);
+            }
+            Err(e) => fetched.failures.push((*parameter, e.to_string())),
+        }
+    }
+    fetched
 }
 
 // ============================================================================
@@ -373,4 +517,49 @@ mod tests {
         assert_eq!(classify_backwater_severity(7.0), "major");
         assert_eq!(classify_backwater_severity(12.0), "extreme");
     }
+
+    #[test]
+    fn test_select_finds_each_parameter_in_one_catalog() {
+        let catalog: Vec<String> = [
+            "Peoria-Pool.Elev.Ave.1Hour.1Hour.CBT-REV",
+            "Peoria-Pool.Elev.Inst.~1Hour.0.CBT-RAW",
+            "Peoria-TW.Elev.Inst.~1Hour.0.CBT-RAW",
+            "Peoria-Pool.Flow-In.Ave.1Hour.1Hour.CBT-REV",
+            "Peoria-Pool.Flow-Res Out.Ave.1Hour.1Hour.CBT-REV",
+            "Peoria-Pool.Opening-Gate Total.Inst.~1Hour.0.CBT-RAW",
+        ].iter().map(|s| s.to_string()).collect();
+
+        let select = |p: CwmsParameter| p.select(&catalog);
+        assert_eq!(select(CwmsParameter::PoolElevation).as_deref(), Some("Peoria-Pool.Elev.Inst.~1Hour.0.CBT-RAW"));
+        assert_eq!(select(CwmsParameter::TailwaterElevation).as_deref(), Some("Peoria-TW.Elev.Inst.~1Hour.0.CBT-RAW"));
+        assert_eq!(select(CwmsParameter::Outflow).as_deref(), Some("Peoria-Pool.Flow-Res Out.Ave.1Hour.1Hour.CBT-REV"));
+        assert_eq!(select(CwmsParameter::GateOpening).as_deref(), Some("Peoria-Pool.Opening-Gate Total.Inst.~1Hour.0.CBT-RAW"));
+        // Inflow is neither a gauge discharge nor a release
+        assert_eq!(select(CwmsParameter::Discharge), None);
+        assert_eq!(select(CwmsParameter::Stage), None);
+
+        let gauge = vec!["Grafton.Flow.Inst.30Minutes.0.RatingCOE".to_string(), "Grafton.Stage.Inst.30Minutes.0.29".to_string()];
+        assert_eq!(CwmsParameter::Discharge.select(&gauge).as_deref(), Some("Grafton.Flow.Inst.30Minutes.0.RatingCOE"));
+        assert_eq!(CwmsParameter::Stage.select(&gauge).as_deref(), Some("Grafton.Stage.Inst.30Minutes.0.29"));
+    }
+
+    #[test]
+    fn test_normalized_records_use_consistent_parameter_ids() {
+        let body = r#"{"name":"LaGrange.Opening-Gate.Inst.~1Hour.0.CBT-RAW","office-id":"MVR","units":"m","values":[[1714564800000,0.5,0]]}"#;
+        let mut records = parse_timeseries("LaGrange.Opening-Gate.Inst.~1Hour.0.CBT-RAW", body).unwrap();
+        CwmsParameter::GateOpening.normalize(&mut records);
+        assert_eq!(records[0].parameter_id, "Opening-Gate");
+        assert_eq!(records[0].unit, "ft");
+        assert!((records[0].value - 1.64042).abs() < 1e-4);
+
+        let body = r#"{"name":"LaGrange.Elev-Tailwater.Inst.~1Hour.0.CBT-RAW","office-id":"MVR","units":"ft","values":[[1714564800000,431.2,0]]}"#;
+        let mut records = parse_timeseries("LaGrange.Elev-Tailwater.Inst.~1Hour.0.CBT-RAW", body).unwrap();
+        CwmsParameter::TailwaterElevation.normalize(&mut records);
+        assert_eq!((records[0].location_id.as_str(), records[0].parameter_id.as_str()), ("LaGrange", "Elev-Tailwater"));
+
+        for data_type in ["pool_elevation", "tailwater_elevation", "stage", "discharge", "outflow", "gate_opening"] {
+            assert!(CwmsParameter::from_data_type(data_type).is_some(), "{}", data_type);
+        }
+        assert_eq!(CwmsParameter::from_data_type("lockage"), None);
+    }
 }
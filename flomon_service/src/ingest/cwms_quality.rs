@@ -0,0 +1,184 @@
+//! CWMS quality code decoding.
+//!
+//! Every CWMS value carries a 32-bit quality word (stored as-is in
+//! `usace.cwms_timeseries.quality_code`). The bits used here:
+//!
+//! ```text
+//! bit  0      screened (0 = never run through validation)
+//! bits 1-4    validity: 1 okay, 2 missing, 3 questionable, 4 rejected
+//! bit  7      value differs from the original reported value
+//! bits 8-10   replacement cause (automatic, interactive, manual, restored)
+//! bits 11-14  replacement method (interpolation, explicit, missing, graphical)
+//! bit  31     protected
+//! ```
+//!
+//! A value with a replacement method was filled in rather than measured;
+//! that is what `estimated` means here. Rejected and missing values are
+//! kept in storage but are not usable for analysis or alerting
+//! (`is_usable`, and `USABLE_SQL` for queries).
+
+const SCREENED: u32 = 1;
+const OKAY: u32 = 1 << 1;
+const MISSING: u32 = 1 << 2;
+const QUESTIONABLE: u32 = 1 << 3;
+const REJECTED: u32 = 1 << 4;
+const DIFFERENT: u32 = 1 << 7;
+const REPLACEMENT_CAUSE_SHIFT: u32 = 8;
+const REPLACEMENT_METHOD_SHIFT: u32 = 11;
+const PROTECTED: u32 = 1 << 31;
+
+/// WHERE-clause fragment keeping rows whose `quality_code` is usable
+/// (neither missing nor rejected; NULL counts as usable)
+pub const USABLE_SQL: &str = "(quality_code IS NULL OR (quality_code & 20) = 0)";
+
+/// Validity bits of a quality code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Validity {
+    /// No validity bit set (unscreened data is usually reported this way)
+    Unknown,
+    Okay,
+    Questionable,
+    Missing,
+    Rejected,
+}
+
+impl Validity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Validity::Unknown => "unknown",
+            Validity::Okay => "okay",
+            Validity::Questionable => "questionable",
+            Validity::Missing => "missing",
+            Validity::Rejected => "rejected",
+        }
+    }
+}
+
+/// How a value was replaced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplacementMethod {
+    None,
+    LinearInterpolation,
+    Explicit,
+    Missing,
+    Graphical,
+    Other(u8),
+}
+
+/// Decoded CWMS quality code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CwmsQuality {
+    pub code: i32,
+    pub screened: bool,
+    pub validity: Validity,
+    /// Value differs from what was originally reported
+    pub modified: bool,
+    pub replacement: ReplacementMethod,
+    pub protected: bool,
+}
+
+impl CwmsQuality {
+    pub fn from_code(code: i32) -> Self {
+        let bits = code as u32;
+        // More than one validity bit should not happen; take the worst
+        let validity = if bits & REJECTED != 0 {
+            Validity::Rejected
+        } else if bits & MISSING != 0 {
+            Validity::Missing
+        } else if bits & QUESTIONABLE != 0 {
+            Validity::Questionable
+        } else if bits & OKAY != 0 {
+            Validity::Okay
+        } else {
+            Validity::Unknown
+        };
+        let replacement = match (bits >> REPLACEMENT_METHOD_SHIFT) & 0xf {
+            0 => ReplacementMethod::None,
+            1 => ReplacementMethod::LinearInterpolation,
+            2 => ReplacementMethod::Explicit,
+            3 => ReplacementMethod::Missing,
+            4 => ReplacementMethod::Graphical,
+            n => ReplacementMethod::Other(n as u8),
+        };
+        CwmsQuality {
+            code,
+            screened: bits & SCREENED != 0,
+            validity,
+            modified: bits & DIFFERENT != 0,
+            replacement,
+            protected: bits & PROTECTED != 0,
+        }
+    }
+
+    pub fn is_rejected(&self) -> bool {
+        self.validity == Validity::Rejected
+    }
+
+    pub fn is_questionable(&self) -> bool {
+        self.validity == Validity::Questionable
+    }
+
+    /// Filled in by a replacement method rather than measured
+    pub fn is_estimated(&self) -> bool {
+        self.replacement != ReplacementMethod::None
+    }
+
+    /// Replacement cause bits (0 = not replaced)
+    pub fn replacement_cause(&self) -> u8 {
+        ((self.code as u32 >> REPLACEMENT_CAUSE_SHIFT) & 0x7) as u8
+    }
+
+    /// Whether the value may feed analysis and alerting
+    pub fn is_usable(&self) -> bool {
+        !matches!(self.validity, Validity::Rejected | Validity::Missing)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_common_codes() {
+        // Unscreened raw data
+        let raw = CwmsQuality::from_code(0);
+        assert!(!raw.screened);
+        assert_eq!(raw.validity, Validity::Unknown);
+        assert!(raw.is_usable());
+
+        // Screened okay (3), questionable (9), rejected (17), missing (5)
+        assert_eq!(CwmsQuality::from_code(3).validity, Validity::Okay);
+        assert!(CwmsQuality::from_code(9).is_questionable());
+        assert!(CwmsQuality::from_code(9).is_usable());
+        assert!(CwmsQuality::from_code(17).is_rejected());
+        assert!(!CwmsQuality::from_code(17).is_usable());
+        assert!(!CwmsQuality::from_code(5).is_usable());
+    }
+
+    #[test]
+    fn test_estimated_and_protected_bits() {
+        // Screened okay, different, automatic cause, linear interpolation
+        let interpolated = CwmsQuality::from_code(3 | 1 << 7 | 1 << 8 | 1 << 11);
+        assert!(interpolated.is_estimated());
+        assert!(interpolated.modified);
+        assert_eq!(interpolated.replacement, ReplacementMethod::LinearInterpolation);
+        assert_eq!(interpolated.replacement_cause(), 1);
+        assert!(interpolated.is_usable());
+
+        // Protected flag is the sign bit
+        let protected = CwmsQuality::from_code(i32::MIN | 3);
+        assert!(protected.protected);
+        assert_eq!(protected.validity, Validity::Okay);
+        assert!(!CwmsQuality::from_code(3).is_estimated());
+    }
+
+    #[test]
+    fn test_usable_sql_matches_decoder() {
+        // The SQL mask covers exactly the bits is_usable rejects on
+        assert!(USABLE_SQL.contains(&(MISSING | REJECTED).to_string()));
+    }
+}
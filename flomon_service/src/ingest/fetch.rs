@@ -0,0 +1,427 @@
+//! Concurrent fetching for the poll cycle (tokio + async reqwest).
+//!
+//! The blocking clients in `usgs`, `cwms` and `iem` fetch one request at a
+//! time, so a CWMS call that hangs for its full timeout held up every USGS
+//! station behind it. Each poll cycle now hands its whole request list to
+//! `fetch_cycle`, which runs every station's fetch as its own task:
+//!
+//! - at most `FetchLimits::max_concurrent` requests are in flight at once
+//!   (one semaphore shared by all sources, so the agencies see a bounded
+//!   request rate)
+//! - each source has its own timeout, counted from when the task gets a
+//!   permit; a timed-out station is an ordinary fetch failure
+//! - results come back keyed by station so the daemon can warehouse and
+//!   evaluate them in its usual order
+//!
+//! In event mode the daemon lists its CRITICAL stations in
+//! `CyclePlan::critical`. Those skip the shared semaphore and take a permit
+//! from a small lane of their own (`FetchLimits::critical_concurrent`), so
+//! the Peoria gauge isn't queued behind a cycle's worth of tributary and
+//! weather requests. The lane is bounded too: at most
+//! `max_concurrent + critical_concurrent` requests are ever in flight.
+//!
+//! Each request is retried on transient failures (503s, dropped
+//! connections) with backoff and jitter inside the source's timeout (see
+//! `retry`). Sources inside a maintenance window (`CyclePlan::gentle`, see
+//! `monitor::maintenance`) skip their in-cycle retries: no backoff retries,
+//! no RDB fallback for USGS, and a CWMS location gives up at its first
+//! failing timeseries.
+//!
+//! URLs and parsing stay in the source modules; this module owns only the
+//! transport. Backfill and the CLI keep using the blocking clients.
+
+use chrono::{Duration as ChronoDuration, Utc};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::ingest::cwms::{self, CwmsParameter, CwmsTimeseries};
+use crate::ingest::iem::{self, AsosObservation};
+use crate::ingest::retry::{self, RetryPolicy};
+use crate::ingest::{usgs, usgs_rdb};
+use crate::logging::{self, DataSource};
+use crate::monitor::maintenance::Source;
+use crate::model::{GaugeReading, IngestError};
+
+/// Requests in flight at once across all sources
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Extra requests in flight for CRITICAL stations in event mode
+pub const DEFAULT_CRITICAL_CONCURRENCY: usize = 2;
+
+/// Default per-source timeouts, seconds. CWMS is the slowest and its
+/// locations take up to three requests each.
+pub const DEFAULT_USGS_TIMEOUT_SECS: u64 = 15;
+pub const DEFAULT_CWMS_TIMEOUT_SECS: u64 = 45;
+pub const DEFAULT_ASOS_TIMEOUT_SECS: u64 = 15;
+
+/// Lookback of each recent-data request, hours
+const RECENT_HOURS: i64 = 4;
+
+/// USGS parameters polled every cycle: discharge and stage
+const USGS_PARAMETERS: [&str; 2] = ["00060", "00065"];
+
+/// Concurrency bound and per-source timeouts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchLimits {
+    pub max_concurrent: usize,
+    /// Permits reserved for `CyclePlan::critical` stations; 0 sends them
+    /// through the shared bound like everything else
+    pub critical_concurrent: usize,
+    pub usgs_timeout: Duration,
+    pub cwms_timeout: Duration,
+    pub asos_timeout: Duration,
+}
+
+impl Default for FetchLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: DEFAULT_CONCURRENCY,
+            critical_concurrent: DEFAULT_CRITICAL_CONCURRENCY,
+            usgs_timeout: Duration::from_secs(DEFAULT_USGS_TIMEOUT_SECS),
+            cwms_timeout: Duration::from_secs(DEFAULT_CWMS_TIMEOUT_SECS),
+            asos_timeout: Duration::from_secs(DEFAULT_ASOS_TIMEOUT_SECS),
+        }
+    }
+}
+
+/// A CWMS location's discovered timeseries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CwmsRequest {
+    pub location: String,
+    pub office: String,
+    /// Each timeseries id with what it measures
+    pub timeseries: Vec<(CwmsParameter, String)>,
+}
+
+/// Everything one poll cycle asks for
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CyclePlan {
+    pub usgs_sites: Vec<String>,
+    pub cwms: Vec<CwmsRequest>,
+    pub asos_stations: Vec<String>,
+    /// Sources in a maintenance window this cycle, fetched without retries
+    pub gentle: Vec<Source>,
+    /// Site codes, CWMS location names and ASOS ids fetched in the
+    /// critical lane (set by the daemon only in event mode)
+    pub critical: Vec<String>,
+}
+
+impl CyclePlan {
+    fn is_gentle(&self, source: Source) -> bool {
+        self.gentle.contains(&source)
+    }
+
+    fn is_critical(&self, station: &str) -> bool {
+        self.critical.iter().any(|s| s == station)
+    }
+}
+
+/// Results of one cycle, keyed by site code, location name and station id
+#[derive(Debug, Default)]
+pub struct CycleResults {
+    pub usgs: HashMap<String, Result<Vec<GaugeReading>, IngestError>>,
+    pub cwms: HashMap<String, Result<Vec<CwmsTimeseries>, IngestError>>,
+    pub asos: HashMap<String, Result<Vec<AsosObservation>, IngestError>>,
+}
+
+/// Runtime for the fetch phase, kept by the daemon across cycles
+pub fn runtime() -> Result<Runtime, String> {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .thread_name("flomon-fetch")
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to start fetch runtime: {}", e))
+}
+
+// ============================================================================
+// Bounded execution
+// ============================================================================
+
+/// Run `jobs` with at most `max_concurrent` at a time, each limited to its
+/// own timeout once it starts. Results come back in completion order; a
+/// job that runs out of time is an `IngestError::Timeout`.
+pub async fn bounded<K, T, F>(jobs: Vec<(K, Duration, F)>, max_concurrent: usize) -> Vec<(K, Result<T, IngestError>)>
+where
+    K: Send + 'static,
+    T: Send + 'static,
+    F: Future<Output = Result<T, IngestError>> + Send + 'static,
+{
+    let jobs = jobs.into_iter().map(|(key, timeout, job)| (key, timeout, false, job)).collect();
+    bounded_with_lane(jobs, max_concurrent, 0).await
+}
+
+/// `bounded`, except jobs flagged critical take their permit from a
+/// separate lane of `critical_concurrent` (when non-zero) and so never wait
+/// behind the rest
+pub async fn bounded_with_lane<K, T, F>(
+    jobs: Vec<(K, Duration, bool, F)>,
+    max_concurrent: usize,
+    critical_concurrent: usize,
+) -> Vec<(K, Result<T, IngestError>)>
+where
+    K: Send + 'static,
+    T: Send + 'static,
+    F: Future<Output = Result<T, IngestError>> + Send + 'static,
+{
+    let shared = Arc::new(Semaphore::new(max_concurrent.max(1)));
+    let lane = Arc::new(Semaphore::new(critical_concurrent));
+    let mut tasks = JoinSet::new();
+    // Critical jobs are spawned first so they also lead when the lane is off
+    let (critical, rest): (Vec<_>, Vec<_>) = jobs.into_iter().partition(|(_, _, critical, _)| *critical);
+    for (key, timeout, critical, job) in critical.into_iter().chain(rest) {
+        let permits = Arc::clone(if critical && critical_concurrent > 0 { &lane } else { &shared });
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await.expect("fetch semaphore is never closed");
+            let result = match tokio::time::timeout(timeout, job).await {
+                Ok(result) => result,
+                Err(_) => Err(IngestError::Timeout(format!("no response within {} s", timeout.as_secs()))),
+            };
+            (key, result)
+        });
+    }
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(result) => results.push(result),
+            Err(e) => logging::error(DataSource::System, None, &format!("Fetch task failed: {}", e)),
+        }
+    }
+    results
+}
+
+// ============================================================================
+// Poll cycle
+// ============================================================================
+
+/// Which station a job is for
+enum Key {
+    Usgs(String),
+    Cwms(String),
+    Asos(String),
+}
+
+/// What a job fetched
+enum Fetched {
+    Usgs(Vec<GaugeReading>),
+    Cwms(Vec<CwmsTimeseries>),
+    Asos(Vec<AsosObservation>),
+}
+
+/// A boxed fetch, so jobs of different sources share one `bounded` call
+pub type Job<T> = std::pin::Pin<Box<dyn Future<Output = Result<T, IngestError>> + Send>>;
+
+/// Fetch every station in `plan` concurrently within `limits`
+pub async fn fetch_cycle(plan: &CyclePlan, limits: FetchLimits) -> CycleResults {
+    let client = reqwest::Client::new();
+    let mut jobs: Vec<(Key, Duration, bool, Job<Fetched>)> = Vec::new();
+    let gentle_usgs = plan.is_gentle(Source::Usgs);
+    for site in &plan.usgs_sites {
+        let (client, site) = (client.clone(), site.clone());
+        jobs.push((Key::Usgs(site.clone()), limits.usgs_timeout, plan.is_critical(&site),
+            Box::pin(async move { usgs_recent(&client, &site, gentle_usgs).await.map(Fetched::Usgs) })));
+    }
+    let gentle_cwms = plan.is_gentle(Source::Cwms);
+    for request in &plan.cwms {
+        let (client, request) = (client.clone(), request.clone());
+        jobs.push((Key::Cwms(request.location.clone()), limits.cwms_timeout, plan.is_critical(&request.location),
+            Box::pin(async move { cwms_recent(&client, &request, gentle_cwms).await.map(Fetched::Cwms) })));
+    }
+    let gentle_asos = plan.is_gentle(Source::Asos);
+    for station in &plan.asos_stations {
+        let (client, station) = (client.clone(), station.clone());
+        jobs.push((Key::Asos(station.clone()), limits.asos_timeout, plan.is_critical(&station),
+            Box::pin(async move { asos_recent(&client, &station, gentle_asos).await.map(Fetched::Asos) })));
+    }
+
+    let mut results = CycleResults::default();
+    for (key, result) in bounded_with_lane(jobs, limits.max_concurrent, limits.critical_concurrent).await {
+        match key {
+            Key::Usgs(site) => {
+                results.usgs.insert(site, result.map(|f| match f {
+                    Fetched::Usgs(readings) => readings,
+                    _ => unreachable!("jobs are keyed by the source they fetch"),
+                }));
+            }
+            Key::Cwms(location) => {
+                results.cwms.insert(location, result.map(|f| match f {
+                    Fetched::Cwms(timeseries) => timeseries,
+                    _ => unreachable!("jobs are keyed by the source they fetch"),
+                }));
+            }
+            Key::Asos(station) => {
+                results.asos.insert(station, result.map(|f| match f {
+                    Fetched::Asos(observations) => observations,
+                    _ => unreachable!("jobs are keyed by the source they fetch"),
+                }));
+            }
+        }
+    }
+    results
+}
+
+/// GET `url` and return the body of a successful response, retrying
+/// transient failures unless `gentle`
+async fn get_text(client: &reqwest::Client, source: DataSource, url: &str, accept_json: bool, gentle: bool) -> Result<String, IngestError> {
+    let policy = if gentle { RetryPolicy::none() } else { retry::policy() };
+    retry::get_text(client, policy, source, url, accept_json).await
+}
+
+/// Latest USGS IV readings for a site: JSON, falling back to RDB unless
+/// USGS says there is no data or the fetch is `gentle`
+async fn usgs_recent(client: &reqwest::Client, site: &str, gentle: bool) -> Result<Vec<GaugeReading>, IngestError> {
+    let period = format!("PT{}H", RECENT_HOURS);
+    let json_url = usgs::build_iv_url(&[site], &USGS_PARAMETERS, &period);
+    let json_err = match get_text(client, DataSource::Usgs, &json_url, false, gentle).await {
+        Ok(body) => match usgs::parse_iv_response(&body) {
+            Ok(readings) => return Ok(readings),
+            Err(e @ IngestError::NoData(_)) => return Err(e),
+            Err(e) => e,
+        },
+        Err(e) => e,
+    };
+    if gentle {
+        return Err(json_err);
+    }
+    logging::warn(DataSource::Usgs, Some(site),
+        &format!("JSON request failed ({}), retrying as RDB", json_err));
+
+    let rdb_url = usgs::build_iv_rdb_url(&[site], &USGS_PARAMETERS, &period);
+    let rdb = get_text(client, DataSource::Usgs, &rdb_url, false, gentle).await
+        .and_then(|body| usgs_rdb::parse_iv_rdb(&body));
+    rdb.map_err(|rdb_err| {
+        logging::warn(DataSource::Usgs, Some(site), &format!("RDB fallback also failed: {}", rdb_err));
+        json_err
+    })
+}
+
+/// Recent values of each of a location's timeseries. A failing timeseries
+/// is logged and skipped; the location fails only if all of them do. A
+/// `gentle` fetch stops at the first failure.
+async fn cwms_recent(client: &reqwest::Client, request: &CwmsRequest, gentle: bool) -> Result<Vec<CwmsTimeseries>, IngestError> {
+    let end = Utc::now();
+    let begin = end - ChronoDuration::hours(RECENT_HOURS);
+    let mut records = Vec::new();
+    let mut last_err = None;
+    for (parameter, timeseries_id) in &request.timeseries {
+        let url = cwms::timeseries_url(timeseries_id, &request.office, begin, end);
+        let fetched = get_text(client, DataSource::Cwms, &url, true, gentle).await
+            .and_then(|body| cwms::parse_timeseries(timeseries_id, &body));
+        match fetched {
+            Ok(mut values) => {
+                parameter.normalize(&mut values);
+                records.extend(values);
+            }
+            Err(e) if gentle => {
+                logging::debug(DataSource::Cwms, Some(&request.location),
+                    &format!("Failed to fetch {}: {}", timeseries_id, e));
+                last_err = Some(e);
+                break;
+            }
+            Err(e) => {
+                logging::warn(DataSource::Cwms, Some(&request.location),
+                    &format!("Failed to fetch {}: {}", timeseries_id, e));
+                last_err = Some(e);
+            }
+        }
+    }
+    match last_err {
+        Some(e) if records.is_empty() => Err(e),
+        _ => Ok(records),
+    }
+}
+
+/// Recent ASOS observations merged with current conditions
+async fn asos_recent(client: &reqwest::Client, station: &str, gentle: bool) -> Result<Vec<AsosObservation>, IngestError> {
+    let end = Utc::now();
+    let begin = end - ChronoDuration::hours(RECENT_HOURS);
+    let body = get_text(client, DataSource::Asos, &iem::asos_url(station, begin, end), false, gentle).await?;
+    let mut observations = iem::parse_asos_csv(&body, station)?;
+
+    // The archive lags real time; current conditions fill the gap
+    let current = get_text(client, DataSource::Asos, &iem::current_url(station), true, gentle).await
+        .and_then(|body| iem::parse_current(&body));
+    match current {
+        Ok(current) => observations.push(current),
+        Err(e) => logging::debug(DataSource::Asos, Some(station),
+            &format!("Current conditions unavailable: {}", e)),
+    }
+    Ok(iem::dedup_observations(observations))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+
+    fn run<T>(future: impl Future<Output = T>) -> T {
+        runtime().unwrap().block_on(future)
+    }
+
+    #[test]
+    fn test_bounded_limits_concurrency() {
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let jobs: Vec<_> = (0..6).map(|i| {
+            let (active, peak) = (Arc::clone(&active), Arc::clone(&peak));
+            (i, Duration::from_secs(5), async move {
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                active.fetch_sub(1, Ordering::SeqCst);
+                Ok::<_, IngestError>(i * 10)
+            })
+        }).collect();
+
+        let mut results = run(bounded(jobs, 2));
+        results.sort_by_key(|(k, _)| *k);
+        assert_eq!(results.len(), 6);
+        assert_eq!(results[5], (5, Ok(50)));
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_critical_job_skips_the_shared_bound() {
+        let slow = || -> Job<u32> { Box::pin(async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Ok(1)
+        }) };
+        let jobs = vec![
+            ("tributary", Duration::from_secs(5), false, slow()),
+            ("peoria", Duration::from_secs(5), true, slow()),
+        ];
+
+        // One shared permit, but the critical job has its own lane
+        let started = Instant::now();
+        let results = run(bounded_with_lane(jobs, 1, 1));
+        assert_eq!(results.len(), 2);
+        assert!(started.elapsed() < Duration::from_millis(390));
+    }
+
+    #[test]
+    fn test_slow_job_times_out_without_holding_up_others() {
+        let jobs: Vec<(&str, Duration, Job<u32>)> = vec![
+            ("cwms", Duration::from_millis(50), Box::pin(async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok(1)
+            })),
+            ("usgs", Duration::from_secs(5), Box::pin(async { Ok(2) })),
+        ];
+
+        let started = Instant::now();
+        let results: HashMap<_, _> = run(bounded(jobs, 4)).into_iter().collect();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(results["usgs"], Ok(2));
+        assert!(matches!(results["cwms"], Err(IngestError::Timeout(_))));
+    }
+}
@@ -0,0 +1,181 @@
+//! USGS surface-water field measurements.
+//!
+//! Hydrographers visit each gauge every six to eight weeks and measure
+//! discharge directly (ADCP or current meter). Every discharge value in the
+//! IV feed is derived from stage via the station's rating; the field
+//! measurements are how USGS checks and shifts that rating. They arrive as
+//! RDB from the measurements service:
+//! https://waterdata.usgs.gov/nwis/measurements?site_no={site}&agency_cd=USGS&format=rdb
+//!
+//! Key fields:
+//! - measurement_nu: measurement number, unique per site
+//! - measurement_dt / tz_cd: local time of the measurement
+//! - q_meas_used_fg: "Yes" when USGS used it to define or shift the rating
+//! - gage_height_va: stage during the measurement, ft
+//! - discharge_va: measured discharge, cfs
+//! - measured_rating_diff: measurement quality (Excellent/Good/Fair/Poor)
+//!
+//! Stored in `usgs_raw.field_measurements`; `analysis::rating_drift` compares
+//! them with the rated discharge at the same time.
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use postgres::Client;
+use std::collections::HashMap;
+
+use crate::ingest::usgs_rdb::tz_offset;
+use crate::ingest::{base_urls, retry};
+use crate::logging::DataSource;
+
+/// How often the daemon re-fetches measurements; sites are visited every
+/// six to eight weeks
+pub const REFRESH_HOURS: i64 = 24;
+
+/// One field measurement
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldMeasurement {
+    pub site_code: String,
+    pub measurement_number: String,
+    pub measured_at: DateTime<Utc>,
+    pub gage_height_ft: Option<f64>,
+    pub discharge_cfs: Option<f64>,
+    /// Measurement quality: Excellent, Good, Fair, Poor or Unspecified
+    pub rating: Option<String>,
+    /// USGS used it for the rating
+    pub used: bool,
+}
+
+impl FieldMeasurement {
+    /// Poor measurements (worse than ±8%) can't resolve rating drift
+    pub fn is_poor(&self) -> bool {
+        self.rating.as_deref() == Some("Poor")
+    }
+}
+
+pub fn measurements_url(site_code: &str) -> String {
+    format!("{}?site_no={}&agency_cd=USGS&format=rdb", base_urls::get().usgs_measurements, site_code)
+}
+
+/// Parse the measurements RDB. Rows without a parseable time are skipped;
+/// a missing required column is an error.
+pub fn parse_rdb(rdb_text: &str) -> Result<Vec<FieldMeasurement>, String> {
+    let mut data_lines = rdb_text.lines()
+        .filter(|line| !line.trim().starts_with('#') && !line.trim().is_empty());
+
+    let header_line = data_lines.next()
+        .ok_or("No header line found in RDB data")?;
+    let col_map: HashMap<&str, usize> = header_line.split('\t')
+        .enumerate()
+        .map(|(idx, name)| (name, idx))
+        .collect();
+    let column = |name: &str| col_map.get(name).copied().ok_or_else(|| format!("Missing {} column", name));
+    let (site_idx, number_idx, datetime_idx) = (column("site_no")?, column("measurement_nu")?, column("measurement_dt")?);
+
+    // Column width/type line
+    data_lines.next().ok_or("No format line found in RDB data")?;
+
+    let mut measurements = Vec::new();
+    for line in data_lines {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let get = |idx: Option<usize>| idx.and_then(|i| fields.get(i)).map(|f| f.trim()).filter(|f| !f.is_empty());
+        let number = |name: &str| get(col_map.get(name).copied()).and_then(|f| f.parse::<f64>().ok());
+
+        let (Some(site_code), Some(measurement_number), Some(datetime)) =
+            (get(Some(site_idx)), get(Some(number_idx)), get(Some(datetime_idx))) else {
+            continue;
+        };
+        let Some(measured_at) = parse_datetime(datetime, get(col_map.get("tz_cd").copied())) else {
+            continue;
+        };
+
+        measurements.push(FieldMeasurement {
+            site_code: site_code.to_string(),
+            measurement_number: measurement_number.to_string(),
+            measured_at,
+            gage_height_ft: number("gage_height_va"),
+            discharge_cfs: number("discharge_va"),
+            rating: get(col_map.get("measured_rating_diff").copied()).map(str::to_string),
+            used: get(col_map.get("q_meas_used_fg").copied()) == Some("Yes"),
+        });
+    }
+    Ok(measurements)
+}
+
+/// Local measurement time to UTC; times without a zone are taken as UTC
+fn parse_datetime(datetime: &str, tz_cd: Option<&str>) -> Option<DateTime<Utc>> {
+    let local = NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M"))
+        .ok()?;
+    match tz_cd {
+        Some(tz) => tz_offset(tz)?.from_local_datetime(&local).single().map(|t| t.with_timezone(&Utc)),
+        None => Some(Utc.from_utc_datetime(&local)),
+    }
+}
+
+/// Fetch and parse one site's measurements
+pub fn fetch(site_code: &str) -> Result<Vec<FieldMeasurement>, String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let body = retry::get_text_blocking(&client, DataSource::Usgs, &measurements_url(site_code), false)
+        .map_err(|e| format!("Failed to fetch field measurements for {}: {}", site_code, e))?;
+    parse_rdb(&body)
+}
+
+/// Insert measurements not already stored; returns how many were new
+pub fn store(client: &mut Client, measurements: &[FieldMeasurement]) -> Result<usize, String> {
+    let mut inserted = 0;
+    for m in measurements {
+        inserted += client.execute(
+            "INSERT INTO usgs_raw.field_measurements
+             (site_code, measurement_number, measured_at, gage_height_ft, discharge_cfs, rating, used)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (site_code, measurement_number) DO NOTHING",
+            &[&m.site_code, &m.measurement_number, &m.measured_at, &m.gage_height_ft,
+              &m.discharge_cfs, &m.rating, &m.used],
+        ).map_err(|e| format!("Failed to store field measurement {} {}: {}", m.site_code, m.measurement_number, e))? as usize;
+    }
+    Ok(inserted)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+# U.S. Geological Survey
+# Surface water measurements
+agency_cd\tsite_no\tmeasurement_nu\tmeasurement_dt\ttz_cd\tq_meas_used_fg\tparty_nm\tgage_height_va\tdischarge_va\tmeasured_rating_diff
+5s\t15s\t6s\t19d\t6s\t3s\t12s\t12s\t12s\t11s
+USGS\t05568500\t512\t2024-04-10 10:15:00\tCDT\tYes\tABC/DEF\t14.62\t31200\tGood
+USGS\t05568500\t513\t2024-05-22 09:40:00\tCDT\tNo\tABC\t\t\tPoor
+USGS\t05568500\t514\tnot a date\tCDT\tYes\tABC\t12.0\t20000\tFair
+";
+
+    #[test]
+    fn test_parse_measurements_rdb() {
+        let measurements = parse_rdb(SAMPLE).unwrap();
+        assert_eq!(measurements.len(), 2);
+
+        let first = &measurements[0];
+        assert_eq!(first.measurement_number, "512");
+        assert_eq!(first.measured_at, Utc.with_ymd_and_hms(2024, 4, 10, 15, 15, 0).unwrap());
+        assert_eq!(first.gage_height_ft, Some(14.62));
+        assert_eq!(first.discharge_cfs, Some(31200.0));
+        assert!(first.used && !first.is_poor());
+
+        let second = &measurements[1];
+        assert_eq!(second.discharge_cfs, None);
+        assert!(!second.used && second.is_poor());
+    }
+
+    #[test]
+    fn test_missing_columns_rejected() {
+        assert!(parse_rdb("agency_cd\tsite_no\n5s\t15s\n").is_err());
+        assert!(parse_rdb("# only comments\n").is_err());
+    }
+}
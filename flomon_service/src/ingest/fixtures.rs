@@ -1,26 +1,26 @@
-///the JSON fixture strings, cfg(test) gated
-/// 
-/// /// Test fixtures: representative JSON payloads from the USGS IV API.
-///
-/// These fixtures are structurally complete but truncated to the minimum
-/// needed to exercise the parser. They reflect the real WaterML-as-JSON
-/// envelope returned by:
-///   https://waterservices.usgs.gov/nwis/iv/?format=json&...
-///
-/// USGS IV response shape:
-///   response.value.timeSeries[]
-///     .sourceInfo.siteCode[0].value  — site number (string)
-///     .sourceInfo.siteName
-///     .variable.variableCode[0].value — parameter code (string)
-///     .variable.unit.unitCode
-///     .variable.noDataValue          — sentinel for missing data (-999999)
-///     .values[0].value[]
-///       .value     — the measurement as a STRING (not a number)
-///       .dateTime  — ISO 8601 with offset
-///       .qualifiers[] — e.g. ["P"] or ["A"]
-///
-/// Note: measurement values are always JSON strings in the USGS response,
-/// even though they represent numbers. Parsers must handle this.
+//!the JSON fixture strings, cfg(test) gated
+//! 
+//! /// Test fixtures: representative JSON payloads from the USGS IV API.
+//!
+//! These fixtures are structurally complete but truncated to the minimum
+//! needed to exercise the parser. They reflect the real WaterML-as-JSON
+//! envelope returned by:
+//!   https://waterservices.usgs.gov/nwis/iv/?format=json&...
+//!
+//! USGS IV response shape:
+//!   response.value.timeSeries[]
+//!     .sourceInfo.siteCode[0].value  — site number (string)
+//!     .sourceInfo.siteName
+//!     .variable.variableCode[0].value — parameter code (string)
+//!     .variable.unit.unitCode
+//!     .variable.noDataValue          — sentinel for missing data (-999999)
+//!     .values[0].value[]
+//!       .value     — the measurement as a STRING (not a number)
+//!       .dateTime  — ISO 8601 with offset
+//!       .qualifiers[] — e.g. ["P"] or ["A"]
+//!
+//! Note: measurement values are always JSON strings in the USGS response,
+//! even though they represent numbers. Parsers must handle this.
 
 /// Single site (Kingston Mines 05568500) with both discharge and stage.
 /// Stage of 18.42 ft is above flood stage (16.0) but below moderate (20.0).
@@ -224,3 +224,61 @@ pub(crate) fn fixture_approved_qualifier_json() -> &'static str {
       }
     }"#
 }
+
+// ---------------------------------------------------------------------------
+// RDB (tab-delimited) fixtures: same requests rendered with format=rdb
+// ---------------------------------------------------------------------------
+
+/// Kingston Mines IV in RDB form. The last row matches
+/// `fixture_kingston_mines_json()`; the 11:45 stage is an "Eqp" status code.
+#[cfg(test)]
+pub(crate) fn fixture_kingston_mines_rdb() -> &'static str {
+    "# ---------------------------------- WARNING ----------------------------------------\n\
+     # Some of the data that you have obtained from this U.S. Geological Survey database\n\
+     # may not have received Director's approval.\n\
+     #\n\
+     # Data for the following 1 site(s) are contained in this file\n\
+     #    USGS 05568500 ILLINOIS RIVER AT KINGSTON MINES, IL\n\
+     # -----------------------------------------------------------------------------------\n\
+     #\n\
+     # Data provided for site 05568500\n\
+     #            TS   parameter     Description\n\
+     #         69928       00060     Discharge, cubic feet per second\n\
+     #         69929       00065     Gage height, feet\n\
+     #\n\
+     agency_cd\tsite_no\tdatetime\ttz_cd\t69928_00060\t69928_00060_cd\t69929_00065\t69929_00065_cd\n\
+     5s\t15s\t20d\t6s\t14n\t10s\t14n\t10s\n\
+     USGS\t05568500\t2024-05-01 11:30\tCDT\t42100\tP\t18.39\tP\n\
+     USGS\t05568500\t2024-05-01 11:45\tCDT\t42200\tP\tEqp\tP\n\
+     USGS\t05568500\t2024-05-01 12:00\tCDT\t42300\tP\t18.42\tP\n"
+}
+
+/// Peoria (stage) and Chillicothe (discharge) as two RDB site blocks.
+/// Peoria's qualifier carries an estimated-value suffix ("A:e").
+#[cfg(test)]
+pub(crate) fn fixture_multi_site_rdb() -> &'static str {
+    "# Data for the following 2 site(s) are contained in this file\n\
+     #    USGS 05567500 ILLINOIS RIVER AT PEORIA, IL\n\
+     #    USGS 05568000 ILLINOIS RIVER AT CHILLICOTHE, IL\n\
+     #\n\
+     # Data provided for site 05567500\n\
+     agency_cd\tsite_no\tdatetime\ttz_cd\t69900_00065\t69900_00065_cd\n\
+     5s\t15s\t20d\t6s\t14n\t10s\n\
+     USGS\t05567500\t2024-01-15 09:15\tCST\t11.80\tA\n\
+     USGS\t05567500\t2024-01-15 09:30\tCST\t11.85\tA:e\n\
+     #\n\
+     # Data provided for site 05568000\n\
+     agency_cd\tsite_no\tdatetime\ttz_cd\t69910_00060\t69910_00060_cd\n\
+     5s\t15s\t20d\t6s\t14n\t10s\n\
+     USGS\t05568000\t2024-01-15 09:30\tCST\t25400\tP\n"
+}
+
+/// Kingston Mines daily means (statistic 00003) in RDB form
+#[cfg(test)]
+pub(crate) fn fixture_daily_values_rdb() -> &'static str {
+    "#    USGS 05568500 ILLINOIS RIVER AT KINGSTON MINES, IL\n\
+     agency_cd\tsite_no\tdatetime\t69928_00060_00003\t69928_00060_00003_cd\t69929_00065_00003\t69929_00065_00003_cd\n\
+     5s\t15s\t20d\t14n\t10s\t14n\t10s\n\
+     USGS\t05568500\t2020-01-01\t18900\tA\t9.12\tA\n\
+     USGS\t05568500\t2020-01-02\t19100\tA\t9.20\tA\n"
+}
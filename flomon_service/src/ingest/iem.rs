@@ -1,16 +1,21 @@
-/// IEM (Iowa Environmental Mesonet) Data API Client
-///
-/// Retrieves ASOS (Automated Surface Observing System) station data from
-/// the Iowa State University Mesonet API for precipitation and meteorological
-/// monitoring relevant to flood prediction.
-///
-/// API Documentation: https://mesonet.agron.iastate.edu/request/download.phtml
-/// Current conditions: https://mesonet.agron.iastate.edu/json/current.py
+//! IEM (Iowa Environmental Mesonet) Data API Client
+//!
+//! Retrieves ASOS (Automated Surface Observing System) station data from
+//! the Iowa State University Mesonet API for precipitation and meteorological
+//! monitoring relevant to flood prediction.
+//!
+//! API Documentation: https://mesonet.agron.iastate.edu/request/download.phtml
+//! Current conditions: https://mesonet.agron.iastate.edu/json/current.py
 
 use chrono::{DateTime, NaiveDateTime, Utc};
+use std::collections::btree_map::{BTreeMap, Entry};
 use serde::Deserialize;
 
-const IEM_BASE_URL: &str = "https://mesonet.agron.iastate.edu";
+use crate::ingest::base_urls;
+use crate::ingest::retry;
+use crate::units::{Precip, WindSpeed};
+use crate::logging::DataSource;
+use crate::model::IngestError;
 
 // ============================================================================
 // IEM API Response Structures
@@ -83,6 +88,24 @@ pub struct AsosObservation {
     pub visibility_mi: Option<f64>,
     pub sky_condition: Option<String>,
     pub weather_codes: Option<String>,
+    /// Humidity as reported by IEM, before QC replaced `relative_humidity`
+    pub relative_humidity_reported: Option<f64>,
+    /// Outcome of `qc_relative_humidity` (None if QC has not run)
+    pub rh_qc_flag: Option<RhQcFlag>,
+}
+
+impl AsosObservation {
+    pub fn wind_speed(&self) -> Option<WindSpeed> {
+        self.wind_speed_knots.map(WindSpeed::from_knots)
+    }
+
+    pub fn wind_gust(&self) -> Option<WindSpeed> {
+        self.wind_gust_knots.map(WindSpeed::from_knots)
+    }
+
+    pub fn precip_1hr(&self) -> Option<Precip> {
+        self.precip_1hr_in.map(Precip::from_in)
+    }
 }
 
 // ============================================================================
@@ -100,28 +123,26 @@ pub struct AsosObservation {
 pub fn fetch_current(
     client: &reqwest::blocking::Client,
     station_id: &str,
-) -> Result<AsosObservation, Box<dyn std::error::Error>> {
-    
-    let url = format!(
-        "{}/json/current.py?station={}",
-        IEM_BASE_URL,
-        station_id
-    );
+) -> Result<AsosObservation, IngestError> {
     
-    let response = client
-        .get(&url)
-        .header("Accept", "application/json")
-        .send()?;
+    let body = retry::get_text_blocking(client, DataSource::Asos, &current_url(station_id), true)?;
     
-    if !response.status().is_success() {
-        return Err(format!("IEM API error: {}", response.status()).into());
-    }
-    
-    let api_response: IemCurrentResponse = response.json()?;
+    parse_current(&body)
+}
+
+/// Current conditions URL for a station
+pub fn current_url(station_id: &str) -> String {
+    format!("{}/json/current.py?station={}", base_urls::get().iem, station_id)
+}
+
+/// Parse a current conditions response body
+pub fn parse_current(body: &str) -> Result<AsosObservation, IngestError> {
+    let api_response: IemCurrentResponse = serde_json::from_str(body)
+        .map_err(|e| IngestError::Parse(format!("IEM current conditions: {}", e)))?;
     
     let obs = api_response.data.into_iter()
         .next()
-        .ok_or("No data returned for station")?;
+        .ok_or_else(|| IngestError::NoData("IEM returned no current observation".to_string()))?;
     
     parse_observation(obs)
 }
@@ -133,14 +154,20 @@ pub fn fetch_recent_precip(
     client: &reqwest::blocking::Client,
     station_id: &str,
     hours: i64,
-) -> Result<Vec<AsosObservation>, Box<dyn std::error::Error>> {
+) -> Result<Vec<AsosObservation>, IngestError> {
     
     let end = Utc::now();
     let begin = end - chrono::Duration::hours(hours);
     
-    let url = format!(
+    let text = retry::get_text_blocking(client, DataSource::Asos, &asos_url(station_id, begin, end), false)?;
+    parse_asos_csv(&text, station_id)
+}
+
+/// ASOS archive request URL (CSV) for `begin..end`
+pub fn asos_url(station_id: &str, begin: DateTime<Utc>, end: DateTime<Utc>) -> String {
+    format!(
         "{}/cgi-bin/request/asos.py?station={}&data=all&year1={}&month1={}&day1={}&hour1={}&year2={}&month2={}&day2={}&hour2={}&tz=UTC&format=onlycomma&latlon=no&elev=no&missing=null&trace=null&direct=no",
-        IEM_BASE_URL,
+        base_urls::get().iem,
         station_id,
         begin.format("%Y"),
         begin.format("%m"),
@@ -150,22 +177,11 @@ pub fn fetch_recent_precip(
         end.format("%m"),
         end.format("%d"),
         end.format("%H")
-    );
-    
-    let response = client
-        .get(&url)
-        .send()?;
-    
-    if !response.status().is_success() {
-        return Err(format!("IEM ASOS API error: {}", response.status()).into());
-    }
-    
-    let text = response.text()?;
-    parse_asos_csv(&text, station_id)
+    )
 }
 
 /// Parse IEM ASOS CSV response
-fn parse_asos_csv(csv: &str, _station_id: &str) -> Result<Vec<AsosObservation>, Box<dyn std::error::Error>> {
+pub fn parse_asos_csv(csv: &str, _station_id: &str) -> Result<Vec<AsosObservation>, IngestError> {
     let mut observations = Vec::new();
     
     for (i, line) in csv.lines().enumerate() {
@@ -190,9 +206,8 @@ fn parse_asos_csv(csv: &str, _station_id: &str) -> Result<Vec<AsosObservation>,
         // Parse timestamp (format: "2026-02-21 19:54")
         let timestamp_str = fields[1];
         let timestamp = NaiveDateTime::parse_from_str(timestamp_str, "%Y-%m-%d %H:%M")
-            .ok()
-            .and_then(|dt| Some(DateTime::from_naive_utc_and_offset(dt, Utc)))
-            .ok_or("Failed to parse timestamp")?;
+            .ok().map(|dt| DateTime::from_naive_utc_and_offset(dt, Utc))
+            .ok_or_else(|| IngestError::Parse(format!("ASOS timestamp {:?}", timestamp_str)))?;
         
         let station_id = fields[0].to_string();
         let temp_f = parse_field(fields[2]);
@@ -201,7 +216,7 @@ fn parse_asos_csv(csv: &str, _station_id: &str) -> Result<Vec<AsosObservation>,
         let wind_direction_deg = parse_field(fields[5]);
         let wind_speed_knots = parse_field(fields[6]);
         let precip_1hr_in = parse_field(fields[7]);
-        let altimeter_in_hg = parse_field(fields[8]);
+        let _altimeter_in_hg = parse_field(fields[8]);
         let pressure_mb = parse_field(fields[9]);
         let visibility_mi = parse_field(fields[10]);
         let wind_gust_knots = parse_field(fields[11]);
@@ -230,7 +245,7 @@ fn parse_asos_csv(csv: &str, _station_id: &str) -> Result<Vec<AsosObservation>,
             Some(fields[20].to_string())
         };
         
-        observations.push(AsosObservation {
+        let mut observation = AsosObservation {
             station_id,
             timestamp,
             temp_f,
@@ -244,19 +259,24 @@ fn parse_asos_csv(csv: &str, _station_id: &str) -> Result<Vec<AsosObservation>,
             visibility_mi,
             sky_condition,
             weather_codes,
-        });
+            relative_humidity_reported: None,
+            rh_qc_flag: None,
+        };
+        qc_relative_humidity(&mut observation);
+        observations.push(observation);
     }
     
     Ok(observations)
 }
 
 /// Parse a single IEM observation into our format
-fn parse_observation(obs: IemObservation) -> Result<AsosObservation, Box<dyn std::error::Error>> {
+fn parse_observation(obs: IemObservation) -> Result<AsosObservation, IngestError> {
     // Parse ISO 8601 timestamp
-    let timestamp = DateTime::parse_from_rfc3339(&obs.valid)?
+    let timestamp = DateTime::parse_from_rfc3339(&obs.valid)
+        .map_err(|e| IngestError::Parse(format!("IEM timestamp {:?}: {}", obs.valid, e)))?
         .with_timezone(&Utc);
     
-    Ok(AsosObservation {
+    let mut observation = AsosObservation {
         station_id: obs.station,
         timestamp,
         temp_f: obs.temp_f,
@@ -270,7 +290,12 @@ fn parse_observation(obs: IemObservation) -> Result<AsosObservation, Box<dyn std
         visibility_mi: obs.vsby,
         sky_condition: obs.skyc1,
         weather_codes: obs.wxcodes,
-    })
+        relative_humidity_reported: None,
+        rh_qc_flag: None,
+    };
+    qc_relative_humidity(&mut observation);
+    
+    Ok(observation)
 }
 
 // ============================================================================
@@ -299,6 +324,160 @@ pub fn calculate_precip_intensity(observations: &[AsosObservation], hours: usize
     Some(total / hours as f64)
 }
 
+// ============================================================================
+// Cross-Endpoint Deduplication
+// ============================================================================
+
+/// Observation times are rounded to this many seconds when matching
+/// records from different endpoints. The archive CSV reports whole minutes
+/// while current conditions can carry seconds for the same METAR.
+pub const DEDUP_ROUND_SECONDS: i64 = 60;
+
+/// Round a timestamp to the nearest `DEDUP_ROUND_SECONDS`
+fn dedup_key_time(timestamp: DateTime<Utc>) -> DateTime<Utc> {
+    let secs = timestamp.timestamp();
+    let rounded = (secs + DEDUP_ROUND_SECONDS / 2).div_euclid(DEDUP_ROUND_SECONDS) * DEDUP_ROUND_SECONDS;
+    DateTime::from_timestamp(rounded, 0).unwrap_or(timestamp)
+}
+
+/// Number of populated measurement fields
+fn richness(obs: &AsosObservation) -> usize {
+    [
+        obs.temp_f,
+        obs.dewpoint_f,
+        obs.relative_humidity,
+        obs.wind_direction_deg,
+        obs.wind_speed_knots,
+        obs.wind_gust_knots,
+        obs.precip_1hr_in,
+        obs.pressure_mb,
+        obs.visibility_mi,
+    ]
+    .iter()
+    .filter(|v| v.is_some())
+    .count()
+        + obs.sky_condition.is_some() as usize
+        + obs.weather_codes.is_some() as usize
+}
+
+/// Collapse near-duplicate observations from the current-conditions and
+/// archive endpoints.
+///
+/// Records are keyed on station + timestamp rounded to the nearest
+/// `DEDUP_ROUND_SECONDS`. For each key the record with the most populated
+/// fields wins (the first one seen on a tie), and its timestamp is set to
+/// the rounded key so the same observation always lands on the same
+/// `(station_id, observation_time)` row. Output is sorted by station, time.
+pub fn dedup_observations(observations: Vec<AsosObservation>) -> Vec<AsosObservation> {
+    let mut by_key: BTreeMap<(String, DateTime<Utc>), AsosObservation> =
+        BTreeMap::new();
+
+    for mut obs in observations {
+        let key_time = dedup_key_time(obs.timestamp);
+        obs.timestamp = key_time;
+        match by_key.entry((obs.station_id.clone(), key_time)) {
+            Entry::Vacant(slot) => {
+                slot.insert(obs);
+            }
+            Entry::Occupied(mut slot) => {
+                if richness(&obs) > richness(slot.get()) {
+                    slot.insert(obs);
+                }
+            }
+        }
+    }
+
+    by_key.into_values().collect()
+}
+
+// ============================================================================
+// Derived Field QC
+// ============================================================================
+
+/// Allowed disagreement between reported and computed RH (percentage points)
+pub const RH_TOLERANCE_PCT: f64 = 5.0;
+
+/// Dewpoint may exceed air temperature by this much (°F) before the pair
+/// is rejected; rounding in METAR reports produces small inversions
+const DEWPOINT_INVERSION_TOLERANCE_F: f64 = 0.5;
+
+/// Result of checking reported relative humidity against temp/dewpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RhQcFlag {
+    /// Reported RH agrees with the computed value
+    Consistent,
+    /// Reported RH was missing; computed value filled in
+    Computed,
+    /// Reported RH disagrees with temp/dewpoint; computed value used
+    Inconsistent,
+    /// Reported RH outside 0-100%; replaced by computed value or dropped
+    OutOfRange,
+    /// Dewpoint above air temperature; RH dropped
+    DewpointAboveTemp,
+    /// No temp/dewpoint pair to check against; reported value kept
+    Unverifiable,
+}
+
+impl RhQcFlag {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RhQcFlag::Consistent => "consistent",
+            RhQcFlag::Computed => "computed",
+            RhQcFlag::Inconsistent => "inconsistent",
+            RhQcFlag::OutOfRange => "out_of_range",
+            RhQcFlag::DewpointAboveTemp => "dewpoint_above_temp",
+            RhQcFlag::Unverifiable => "unverifiable",
+        }
+    }
+}
+
+/// Relative humidity (%) from air temperature and dewpoint (°F).
+///
+/// Magnus approximation with Alduchov-Eskridge coefficients; within 0.4%
+/// of the Goff-Gratch formulation over the range ASOS reports.
+pub fn relative_humidity_from(temp_f: f64, dewpoint_f: f64) -> f64 {
+    let to_c = |f: f64| (f - 32.0) * 5.0 / 9.0;
+    let magnus = |c: f64| (17.625 * c / (243.04 + c)).exp();
+    100.0 * magnus(to_c(dewpoint_f)) / magnus(to_c(temp_f))
+}
+
+/// Recompute RH from temp/dewpoint and prefer the computed value.
+///
+/// The reported value is preserved in `relative_humidity_reported` and the
+/// outcome recorded in `rh_qc_flag`. Returns the flag.
+pub fn qc_relative_humidity(obs: &mut AsosObservation) -> RhQcFlag {
+    let reported = obs.relative_humidity;
+    obs.relative_humidity_reported = reported;
+    let in_range = |rh: f64| (0.0..=100.0).contains(&rh);
+
+    let flag = match (obs.temp_f, obs.dewpoint_f) {
+        (Some(temp), Some(dewpoint)) if dewpoint > temp + DEWPOINT_INVERSION_TOLERANCE_F => {
+            obs.relative_humidity = None;
+            RhQcFlag::DewpointAboveTemp
+        }
+        (Some(temp), Some(dewpoint)) => {
+            let computed = relative_humidity_from(temp, dewpoint).min(100.0);
+            obs.relative_humidity = Some(computed);
+            match reported {
+                None => RhQcFlag::Computed,
+                Some(rh) if !in_range(rh) => RhQcFlag::OutOfRange,
+                Some(rh) if (rh - computed).abs() > RH_TOLERANCE_PCT => RhQcFlag::Inconsistent,
+                Some(_) => RhQcFlag::Consistent,
+            }
+        }
+        _ => match reported {
+            Some(rh) if !in_range(rh) => {
+                obs.relative_humidity = None;
+                RhQcFlag::OutOfRange
+            }
+            _ => RhQcFlag::Unverifiable,
+        },
+    };
+
+    obs.rh_qc_flag = Some(flag);
+    flag
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -324,6 +503,8 @@ mod tests {
                 visibility_mi: Some(10.0),
                 sky_condition: None,
                 weather_codes: None,
+                relative_humidity_reported: None,
+                rh_qc_flag: None,
             },
             AsosObservation {
                 station_id: "KPIA".to_string(),
@@ -339,6 +520,8 @@ mod tests {
                 visibility_mi: Some(8.0),
                 sky_condition: None,
                 weather_codes: None,
+                relative_humidity_reported: None,
+                rh_qc_flag: None,
             },
         ];
         
@@ -362,10 +545,116 @@ mod tests {
                 visibility_mi: None,
                 sky_condition: None,
                 weather_codes: None,
+                relative_humidity_reported: None,
+                rh_qc_flag: None,
             },
         ];
         
         assert!(detect_rainfall_event(&obs, 0.5));
         assert!(!detect_rainfall_event(&obs, 1.0));
     }
+
+    fn humidity_obs(temp_f: Option<f64>, dewpoint_f: Option<f64>, rh: Option<f64>) -> AsosObservation {
+        AsosObservation {
+            station_id: "KPIA".to_string(),
+            timestamp: Utc::now(),
+            temp_f,
+            dewpoint_f,
+            relative_humidity: rh,
+            wind_direction_deg: None,
+            wind_speed_knots: None,
+            wind_gust_knots: None,
+            precip_1hr_in: None,
+            pressure_mb: None,
+            visibility_mi: None,
+            sky_condition: None,
+            weather_codes: None,
+            relative_humidity_reported: None,
+            rh_qc_flag: None,
+        }
+    }
+    
+    #[test]
+    fn test_relative_humidity_from_temp_and_dewpoint() {
+        // 68°F / 50°F is about 52.5% RH
+        assert!((relative_humidity_from(68.0, 50.0) - 52.5).abs() < 0.5);
+        assert!((relative_humidity_from(40.0, 40.0) - 100.0).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_rh_qc_prefers_computed_value() {
+        let mut obs = humidity_obs(Some(68.0), Some(50.0), Some(53.0));
+        assert_eq!(qc_relative_humidity(&mut obs), RhQcFlag::Consistent);
+        assert!((obs.relative_humidity.unwrap() - 52.5).abs() < 0.5);
+        assert_eq!(obs.relative_humidity_reported, Some(53.0));
+        
+        let mut obs = humidity_obs(Some(68.0), Some(50.0), Some(80.0));
+        assert_eq!(qc_relative_humidity(&mut obs), RhQcFlag::Inconsistent);
+        assert!((obs.relative_humidity.unwrap() - 52.5).abs() < 0.5);
+        
+        let mut obs = humidity_obs(Some(68.0), Some(50.0), None);
+        assert_eq!(qc_relative_humidity(&mut obs), RhQcFlag::Computed);
+        assert!(obs.relative_humidity.is_some());
+    }
+    
+    #[test]
+    fn test_rh_qc_out_of_range() {
+        // Saturated air reported as 103%: replaced by computed 100%
+        let mut obs = humidity_obs(Some(40.0), Some(40.0), Some(103.0));
+        assert_eq!(qc_relative_humidity(&mut obs), RhQcFlag::OutOfRange);
+        assert_eq!(obs.relative_humidity, Some(100.0));
+        
+        // No temp/dewpoint to recompute from: bad value is dropped
+        let mut obs = humidity_obs(None, None, Some(104.0));
+        assert_eq!(qc_relative_humidity(&mut obs), RhQcFlag::OutOfRange);
+        assert_eq!(obs.relative_humidity, None);
+        
+        let mut obs = humidity_obs(None, Some(40.0), Some(70.0));
+        assert_eq!(qc_relative_humidity(&mut obs), RhQcFlag::Unverifiable);
+        assert_eq!(obs.relative_humidity, Some(70.0));
+    }
+    
+    #[test]
+    fn test_rh_qc_rejects_dewpoint_above_temp() {
+        let mut obs = humidity_obs(Some(40.0), Some(42.0), Some(100.0));
+        assert_eq!(qc_relative_humidity(&mut obs), RhQcFlag::DewpointAboveTemp);
+        assert_eq!(obs.relative_humidity, None);
+        
+        // Rounding-sized inversion is tolerated and clamped to saturation
+        let mut obs = humidity_obs(Some(40.0), Some(40.4), None);
+        assert_eq!(qc_relative_humidity(&mut obs), RhQcFlag::Computed);
+        assert_eq!(obs.relative_humidity, Some(100.0));
+    }
+
+    #[test]
+    fn test_dedup_merges_near_duplicate_endpoints() {
+        let archive = humidity_obs(Some(68.0), Some(50.0), None);
+        let mut archive = AsosObservation { precip_1hr_in: Some(0.12), ..archive };
+        archive.timestamp = DateTime::parse_from_rfc3339("2024-05-01T11:53:00Z").unwrap().with_timezone(&Utc);
+        
+        // Current-conditions copy of the same METAR, seconds later, no precip
+        let mut current = humidity_obs(Some(68.0), None, None);
+        current.timestamp = DateTime::parse_from_rfc3339("2024-05-01T11:53:12Z").unwrap().with_timezone(&Utc);
+        
+        let merged = dedup_observations(vec![current, archive]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].precip_1hr_in, Some(0.12), "richer archive record wins");
+        assert_eq!(merged[0].timestamp.to_rfc3339(), "2024-05-01T11:53:00+00:00");
+        assert_eq!(calculate_cumulative_precip(&merged), 0.12);
+    }
+    
+    #[test]
+    fn test_dedup_keeps_distinct_observations() {
+        let mut routine = humidity_obs(Some(68.0), Some(50.0), None);
+        routine.timestamp = DateTime::parse_from_rfc3339("2024-05-01T11:53:00Z").unwrap().with_timezone(&Utc);
+        let mut special = routine.clone();
+        special.timestamp = DateTime::parse_from_rfc3339("2024-05-01T11:20:00Z").unwrap().with_timezone(&Utc);
+        let mut other_station = routine.clone();
+        other_station.station_id = "KBMI".to_string();
+        
+        let merged = dedup_observations(vec![routine, special, other_station]);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].station_id, "KBMI");
+        assert!(merged[1].timestamp < merged[2].timestamp);
+    }
 }
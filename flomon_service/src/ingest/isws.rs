@@ -0,0 +1,251 @@
+//! Illinois State Water Survey / IDNR stage products for the Peoria pool reach.
+//!
+//! A third forecast source next to NWS and the Corps, for verification
+//! (see `forecasts`). The product is fetched from the configured URL every
+//! `REFRESH_HOURS` and each row for a configured location is stored in
+//! `flood_analysis.stage_forecasts` with source `isws`:
+//!
+//! ```toml
+//! [isws]
+//! url = "https://example.org/isws/peoria_pool_stages.csv"
+//!
+//! [[isws.locations]]
+//! name = "Peoria"            # location as the product names it
+//! site_code = "05567500"
+//! gauge_datum_ft = 440.0     # product gives elevations; omit for stages
+//! ```
+//!
+//! The product is read as CSV with a header row. Columns are found by
+//! name, case-insensitively: `location`, `issued`, `valid` and `stage`;
+//! others are ignored. Times are RFC 3339 or local time with a zone
+//! abbreviation ("2024-05-01 07:00 CDT"). Rows valid before the product
+//! was issued are the observed stages it repeats and are skipped, as are
+//! missing stages ("M") and locations not configured.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashSet;
+
+use crate::forecasts::{ForecastSource, StageForecast};
+use crate::ingest::{retry, usgs_rdb};
+use crate::logging::DataSource;
+use crate::model::IngestError;
+
+/// How often the daemon re-reads the product
+pub const REFRESH_HOURS: i64 = 6;
+
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// `[isws]` section of flomon.toml
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IswsSettings {
+    /// Stage product for the Peoria pool reach
+    pub url: String,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    pub locations: Vec<IswsLocation>,
+}
+
+/// `[[isws.locations]]` entry: a product location and the station it forecasts
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IswsLocation {
+    pub name: String,
+    pub site_code: String,
+    /// Elevation of the station's gauge zero, when the product gives
+    /// water-surface elevations rather than stages
+    #[serde(default)]
+    pub gauge_datum_ft: Option<f64>,
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+impl IswsSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.url.trim().is_empty() {
+            return Err("isws.url must not be empty".to_string());
+        }
+        if self.timeout_secs == 0 {
+            return Err("isws.timeout_secs must be greater than zero".to_string());
+        }
+        if self.locations.is_empty() {
+            return Err("isws.locations must list at least one location".to_string());
+        }
+        let mut names = HashSet::new();
+        for location in &self.locations {
+            if !names.insert(location.name.to_lowercase()) {
+                return Err(format!("isws.locations: {:?} is listed twice", location.name));
+            }
+            if location.site_code.len() != 8 || !location.site_code.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(format!("isws.locations: {:?} is not an 8-digit USGS site code", location.site_code));
+            }
+        }
+        Ok(())
+    }
+
+    fn location(&self, name: &str) -> Option<&IswsLocation> {
+        self.locations.iter().find(|l| l.name.eq_ignore_ascii_case(name))
+    }
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+/// Forecast rows of the product for the configured locations
+pub fn parse(body: &str, settings: &IswsSettings) -> Result<Vec<StageForecast>, IngestError> {
+    let mut lines = body.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    let header: Vec<String> = lines.next()
+        .ok_or_else(|| IngestError::Parse("ISWS product is empty".to_string()))?
+        .split(',')
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+    let column = |name: &str| header.iter().position(|h| h == name)
+        .ok_or_else(|| IngestError::Parse(format!("ISWS product has no '{}' column", name)));
+    let (location_col, issued_col, valid_col, stage_col) =
+        (column("location")?, column("issued")?, column("valid")?, column("stage")?);
+
+    let mut forecasts = Vec::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let field = |i: usize| fields.get(i).copied()
+            .ok_or_else(|| IngestError::Parse(format!("ISWS row is missing columns: {}", line)));
+        let Some(location) = settings.location(field(location_col)?) else {
+            continue;
+        };
+        let stage = field(stage_col)?;
+        if stage.eq_ignore_ascii_case("M") || stage.is_empty() {
+            continue;
+        }
+        let value: f64 = stage.parse()
+            .map_err(|_| IngestError::Parse(format!("ISWS stage '{}' is not a number", stage)))?;
+        let issued_at = parse_time(field(issued_col)?)?;
+        let valid_at = parse_time(field(valid_col)?)?;
+        if valid_at < issued_at {
+            continue;
+        }
+        forecasts.push(StageForecast {
+            source: ForecastSource::Isws,
+            site_code: location.site_code.clone(),
+            product_location: location.name.clone(),
+            issued_at,
+            valid_at,
+            stage_ft: value - location.gauge_datum_ft.unwrap_or(0.0),
+        });
+    }
+    if forecasts.is_empty() {
+        return Err(IngestError::NoData("ISWS product has no forecasts for the configured locations".to_string()));
+    }
+    Ok(forecasts)
+}
+
+/// RFC 3339, or "YYYY-MM-DD HH:MM" followed by a zone abbreviation
+fn parse_time(value: &str) -> Result<DateTime<Utc>, IngestError> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let invalid = || IngestError::InvalidTimestamp(format!("ISWS time '{}'", value));
+    let (local, zone) = value.rsplit_once(' ').ok_or_else(invalid)?;
+    let local = NaiveDateTime::parse_from_str(local, "%Y-%m-%d %H:%M").map_err(|_| invalid())?;
+    let offset = usgs_rdb::tz_offset(zone).ok_or_else(invalid)?;
+    local.and_local_timezone(offset).single()
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(invalid)
+}
+
+// ============================================================================
+// Fetch
+// ============================================================================
+
+/// The product's current forecasts for the configured locations
+pub fn fetch(settings: &IswsSettings) -> Result<Vec<StageForecast>, String> {
+    let http = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(settings.timeout_secs))
+        .build()
+        .map_err(|e| format!("Failed to build ISWS HTTP client: {}", e))?;
+    let body = retry::get_text_blocking(&http, DataSource::System, &settings.url, false)
+        .map_err(|e| format!("ISWS stage product {}: {}", settings.url, e))?;
+    parse(&body, settings).map_err(|e| format!("ISWS stage product {}: {}", settings.url, e))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRODUCT: &str = "\
+# Illinois River - Peoria pool reach
+Location,Issued,Valid,Stage,Flow
+Peoria,2024-05-01 07:00 CDT,2024-04-30 07:00 CDT,445.1,
+Peoria,2024-05-01 07:00 CDT,2024-05-01 07:00 CDT,445.9,
+Peoria,2024-05-01 07:00 CDT,2024-05-02 07:00 CDT,447.2,
+Peoria,2024-05-01 07:00 CDT,2024-05-03 07:00 CDT,M,
+Havana,2024-05-01 07:00 CDT,2024-05-02 07:00 CDT,14.2,
+Kingston Mines,2024-05-01T12:00:00Z,2024-05-02T12:00:00Z,18.6,
+";
+
+    fn settings() -> IswsSettings {
+        toml::from_str(r#"
+            url = "https://example.org/isws/peoria_pool_stages.csv"
+            [[locations]]
+            name = "Peoria"
+            site_code = "05567500"
+            gauge_datum_ft = 440.0
+            [[locations]]
+            name = "kingston mines"
+            site_code = "05568500"
+        "#).unwrap()
+    }
+
+    #[test]
+    fn test_parse_keeps_configured_forecast_rows() {
+        let forecasts = parse(PRODUCT, &settings()).unwrap();
+        let summary: Vec<(&str, String, f64)> = forecasts.iter()
+            .map(|f| (f.site_code.as_str(), f.valid_at.to_rfc3339(), (f.stage_ft * 10.0).round() / 10.0))
+            .collect();
+        assert_eq!(summary, [
+            ("05567500", "2024-05-01T12:00:00+00:00".to_string(), 5.9),
+            ("05567500", "2024-05-02T12:00:00+00:00".to_string(), 7.2),
+            ("05568500", "2024-05-02T12:00:00+00:00".to_string(), 18.6),
+        ]);
+        assert!(forecasts.iter().all(|f| f.source == ForecastSource::Isws));
+        assert_eq!(forecasts[0].issued_at.to_rfc3339(), "2024-05-01T12:00:00+00:00");
+        assert_eq!(forecasts[2].product_location, "kingston mines");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let settings = settings();
+        assert!(matches!(parse("Location,Issued,Stage\n", &settings), Err(IngestError::Parse(_))));
+        assert!(matches!(parse("Location,Issued,Valid,Stage\nHavana,2024-05-01 07:00 CDT,2024-05-02 07:00 CDT,14.2\n", &settings),
+            Err(IngestError::NoData(_))));
+        assert!(matches!(parse("Location,Issued,Valid,Stage\nPeoria,2024-05-01 07:00 XST,2024-05-02 07:00 CDT,447.2\n", &settings),
+            Err(IngestError::InvalidTimestamp(_))));
+        assert!(matches!(parse("Location,Issued,Valid,Stage\nPeoria,2024-05-01 07:00 CDT,2024-05-02 07:00 CDT,high\n", &settings),
+            Err(IngestError::Parse(_))));
+    }
+
+    #[test]
+    fn test_settings_validated() {
+        assert!(settings().validate().is_ok());
+        let mut twice = settings();
+        twice.locations[1].name = "PEORIA".to_string();
+        assert!(twice.validate().unwrap_err().contains("listed twice"));
+        let mut bad_site = settings();
+        bad_site.locations[0].site_code = "PEOI2".to_string();
+        assert!(bad_site.validate().unwrap_err().contains("site code"));
+        assert!(IswsSettings { locations: Vec::new(), ..settings() }.validate().is_err());
+    }
+}
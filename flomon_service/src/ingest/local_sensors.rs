@@ -0,0 +1,435 @@
+//! Private sensors reporting over MQTT (LoRa gateway or similar)
+//!
+//! An ultrasonic stage sensor at the dock and a tipping-bucket rain gauge
+//! publish to a local MQTT broker. The daemon subscribes to their topics
+//! (see `mqtt`) on its own supervised thread, validates each message, and
+//! stores accepted readings in `community.readings` with
+//! `source = 'local_mqtt'`, next to the agency data but never mixed into
+//! `usgs_raw.gauge_readings`, the rules or the alerts.
+//!
+//! ```toml
+//! [local_sensors]
+//! broker = "192.168.1.20:1883"
+//! username = "flomon"
+//! password = "${MQTT_PASSWORD}"
+//!
+//! [[local_sensors.sensors]]
+//! name = "dock-stage"
+//! topic = "lora/dock/stage"
+//! site_id = "DOCK"
+//! kind = "ultrasonic_stage"
+//! sensor_height_ft = 32.5   # stage reading at the sensor face
+//! distance_unit = "m"
+//! min_ft = 0.0
+//! max_ft = 30.0
+//! max_step_ft = 1.0
+//!
+//! [[local_sensors.sensors]]
+//! name = "yard-rain"
+//! topic = "lora/yard/rain"
+//! site_id = "YARD"
+//! kind = "tipping_bucket"
+//! tip_in = 0.01
+//! ```
+//!
+//! # Payloads
+//! Either a bare number, or a JSON object with `value` and an optional
+//! `time` (RFC 3339 or Unix seconds). Without a time the reading is
+//! stamped on receipt. The ultrasonic value is the distance from the sensor
+//! down to the water; stage is `sensor_height_ft - distance`. The rain
+//! value is the number of tips since the previous message.
+//!
+//! # Validation
+//! A message is rejected (logged, not stored) when it can't be parsed, is
+//! stamped more than `MAX_FUTURE_MINUTES` ahead, puts stage outside
+//! `min_ft..=max_ft`, jumps more than `max_step_ft` from the previous
+//! accepted stage within `STEP_WINDOW_MINUTES`, or reports a negative or
+//! implausible (`max_tips`) tip count.
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+use crate::ingest::mqtt::{self, ConnectOptions, Subscriber};
+use crate::ingest::plugin::{self, CanonicalReading};
+use crate::units::{self, Quantity};
+use crate::logging;
+use crate::shutdown;
+
+/// Value of the `source` column for every local sensor reading
+pub const SOURCE: &str = "local_mqtt";
+
+/// USGS parameter codes used for the stored readings
+pub const STAGE_PARAMETER: &str = "00065";
+pub const PRECIP_PARAMETER: &str = "00045";
+
+/// Reported times further ahead than this are rejected
+const MAX_FUTURE_MINUTES: i64 = 5;
+
+/// Stage step check only applies against a reading this recent
+const STEP_WINDOW_MINUTES: i64 = 60;
+
+/// How long `run` waits for a message before checking for shutdown
+const RECEIVE_SLICE: std::time::Duration = std::time::Duration::from_secs(5);
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// `[local_sensors]` section of flomon.toml
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocalSensorSettings {
+    /// MQTT broker, "host:port"
+    pub broker: String,
+    #[serde(default = "default_client_id")]
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    #[serde(default = "default_keepalive_secs")]
+    pub keepalive_secs: u64,
+    pub sensors: Vec<SensorConfig>,
+}
+
+fn default_client_id() -> String {
+    "flomon".to_string()
+}
+
+fn default_keepalive_secs() -> u64 {
+    60
+}
+
+/// `[[local_sensors.sensors]]` entry
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SensorConfig {
+    pub name: String,
+    /// Exact MQTT topic (no wildcards)
+    pub topic: String,
+    /// Stored as `site_id`
+    pub site_id: String,
+    #[serde(flatten)]
+    pub kind: SensorKind,
+}
+
+/// Sensor type and its calibration
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SensorKind {
+    UltrasonicStage {
+        /// Stage that a zero distance would read, ft
+        sensor_height_ft: f64,
+        /// Unit of the reported distance (any length unit, see `units`)
+        #[serde(default = "default_distance_unit")]
+        distance_unit: String,
+        min_ft: f64,
+        max_ft: f64,
+        max_step_ft: Option<f64>,
+    },
+    TippingBucket {
+        /// Rain per tip, inches
+        tip_in: f64,
+        #[serde(default = "default_max_tips")]
+        max_tips: u32,
+    },
+}
+
+fn default_distance_unit() -> String {
+    "m".to_string()
+}
+
+fn default_max_tips() -> u32 {
+    100
+}
+
+impl LocalSensorSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.broker.contains(':') {
+            return Err(format!("local_sensors.broker must be host:port (got {:?})", self.broker));
+        }
+        if self.keepalive_secs == 0 || self.keepalive_secs > u16::MAX as u64 {
+            return Err("local_sensors.keepalive_secs must be 1-65535".to_string());
+        }
+        if self.sensors.is_empty() {
+            return Err("local_sensors.sensors must list at least one sensor".to_string());
+        }
+        let mut names = HashSet::new();
+        let mut topics = HashSet::new();
+        for sensor in &self.sensors {
+            if !names.insert(sensor.name.as_str()) {
+                return Err(format!("duplicate local sensor name {}", sensor.name));
+            }
+            if !topics.insert(sensor.topic.as_str()) {
+                return Err(format!("local sensors share topic {}", sensor.topic));
+            }
+            if sensor.topic.contains(['#', '+']) {
+                return Err(format!("local sensor {} topic must not contain wildcards", sensor.name));
+            }
+            match &sensor.kind {
+                SensorKind::UltrasonicStage { distance_unit, min_ft, max_ft, .. } => {
+                    units::resolve(Some(Quantity::Length), &sensor.name, distance_unit)?;
+                    if min_ft >= max_ft {
+                        return Err(format!("local sensor {} min_ft must be below max_ft", sensor.name));
+                    }
+                }
+                SensorKind::TippingBucket { tip_in, .. } => {
+                    if *tip_in <= 0.0 {
+                        return Err(format!("local sensor {} tip_in must be positive", sensor.name));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn connect_options(&self) -> ConnectOptions {
+        ConnectOptions {
+            client_id: self.client_id.clone(),
+            username: self.username.clone(),
+            password: self.password.clone(),
+            keepalive: std::time::Duration::from_secs(self.keepalive_secs),
+        }
+    }
+}
+
+// ============================================================================
+// Parsing and validation
+// ============================================================================
+
+/// Value and optional time from a message payload
+fn parse_payload(payload: &[u8]) -> Result<(f64, Option<DateTime<Utc>>), String> {
+    let text = std::str::from_utf8(payload).map_err(|_| "payload is not UTF-8".to_string())?.trim();
+    if let Ok(value) = text.parse::<f64>() {
+        return Ok((value, None));
+    }
+    let json: Value = serde_json::from_str(text).map_err(|_| format!("unrecognized payload {:?}", text))?;
+    let value = json.get("value").and_then(Value::as_f64)
+        .ok_or_else(|| "payload has no numeric value".to_string())?;
+    let time = match json.get("time") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(s)) => Some(DateTime::parse_from_rfc3339(s)
+            .map_err(|e| format!("invalid time {:?}: {}", s, e))?
+            .with_timezone(&Utc)),
+        Some(Value::Number(n)) => Some(n.as_i64()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+            .ok_or_else(|| format!("invalid time {}", n))?),
+        Some(other) => return Err(format!("invalid time {}", other)),
+    };
+    Ok((value, time))
+}
+
+/// Validates messages against each sensor's limits and its previous reading
+pub struct LocalSensors {
+    sensors: HashMap<String, SensorConfig>,
+    last_stage: HashMap<String, (DateTime<Utc>, f64)>,
+}
+
+impl LocalSensors {
+    pub fn new(settings: &LocalSensorSettings) -> Self {
+        Self {
+            sensors: settings.sensors.iter().map(|s| (s.topic.clone(), s.clone())).collect(),
+            last_stage: HashMap::new(),
+        }
+    }
+
+    pub fn topics(&self) -> Vec<String> {
+        let mut topics: Vec<String> = self.sensors.keys().cloned().collect();
+        topics.sort();
+        topics
+    }
+
+    /// Sensor name for `topic`, if one is configured
+    pub fn sensor_name(&self, topic: &str) -> Option<&str> {
+        self.sensors.get(topic).map(|s| s.name.as_str())
+    }
+
+    /// Turn a message into a reading, or explain why it was rejected
+    pub fn accept(&mut self, topic: &str, payload: &[u8], received_at: DateTime<Utc>) -> Result<CanonicalReading, String> {
+        let sensor = self.sensors.get(topic).ok_or_else(|| format!("no sensor for topic {}", topic))?;
+        let (value, time) = parse_payload(payload)?;
+        if !value.is_finite() {
+            return Err(format!("value {} is not finite", value));
+        }
+        let reading_time = time.unwrap_or(received_at);
+        if reading_time > received_at + Duration::minutes(MAX_FUTURE_MINUTES) {
+            return Err(format!("time {} is in the future", reading_time.to_rfc3339()));
+        }
+
+        match &sensor.kind {
+            SensorKind::UltrasonicStage { sensor_height_ft, distance_unit, min_ft, max_ft, max_step_ft } => {
+                let distance_ft = units::resolve(Some(Quantity::Length), &sensor.name, distance_unit)?.apply(value);
+                let stage = sensor_height_ft - distance_ft;
+                if stage < *min_ft || stage > *max_ft {
+                    return Err(format!("stage {:.2} ft outside {:.2}..{:.2} ft", stage, min_ft, max_ft));
+                }
+                if let (Some(step), Some((last_time, last_stage))) = (max_step_ft, self.last_stage.get(topic)) {
+                    let recent = (reading_time - *last_time).num_minutes().abs() <= STEP_WINDOW_MINUTES;
+                    if recent && (stage - last_stage).abs() > *step {
+                        return Err(format!(
+                            "stage {:.2} ft jumped {:+.2} ft from {:.2} ft (max {:.2})",
+                            stage, stage - last_stage, last_stage, step));
+                    }
+                }
+                self.last_stage.insert(topic.to_string(), (reading_time, stage));
+                Ok(CanonicalReading {
+                    site_id: sensor.site_id.clone(),
+                    parameter: STAGE_PARAMETER.to_string(),
+                    reading_time,
+                    value: stage,
+                    unit: "ft".to_string(),
+                })
+            }
+            SensorKind::TippingBucket { tip_in, max_tips } => {
+                if value < 0.0 || value.fract() != 0.0 {
+                    return Err(format!("tip count {} is not a whole non-negative number", value));
+                }
+                if value > *max_tips as f64 {
+                    return Err(format!("{} tips exceeds max_tips {}", value, max_tips));
+                }
+                Ok(CanonicalReading {
+                    site_id: sensor.site_id.clone(),
+                    parameter: PRECIP_PARAMETER.to_string(),
+                    reading_time,
+                    value: value * tip_in,
+                    unit: "in".to_string(),
+                })
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Subscriber loop
+// ============================================================================
+
+/// Subscribe and store readings until shutdown. Returns an error when the
+/// broker connection fails so the supervisor can reconnect with backoff.
+pub fn run(settings: &LocalSensorSettings, client: &mut Client) -> Result<(), String> {
+    let mut sensors = LocalSensors::new(settings);
+    let mut subscriber = Subscriber::connect(&settings.broker, &settings.connect_options(), &sensors.topics(), 1)?;
+    logging::info(logging::DataSource::System, None, &format!(
+        "Subscribed to {} local sensor topic(s) on {}", sensors.topics().len(), settings.broker));
+
+    while !shutdown::requested() {
+        let Some(message) = subscriber.next_message(RECEIVE_SLICE)? else {
+            continue;
+        };
+        handle(&mut sensors, client, &message, Utc::now());
+    }
+    Ok(())
+}
+
+fn handle(sensors: &mut LocalSensors, client: &mut Client, message: &mqtt::Message, received_at: DateTime<Utc>) {
+    let name = sensors.sensor_name(&message.topic).unwrap_or(&message.topic).to_string();
+    let reading = match sensors.accept(&message.topic, &message.payload, received_at) {
+        Ok(reading) => reading,
+        Err(e) => {
+            logging::warn(logging::DataSource::System, Some(&name), &format!("Rejected local sensor message: {}", e));
+            return;
+        }
+    };
+    if let Err(e) = plugin::store_readings(client, plugin::DEFAULT_DESTINATION, SOURCE, std::slice::from_ref(&reading)) {
+        logging::error(logging::DataSource::Database, Some(&name), &e);
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn settings() -> LocalSensorSettings {
+        toml::from_str(r#"
+            broker = "192.168.1.20:1883"
+            [[sensors]]
+            name = "dock-stage"
+            topic = "lora/dock/stage"
+            site_id = "DOCK"
+            kind = "ultrasonic_stage"
+            sensor_height_ft = 32.5
+            distance_unit = "m"
+            min_ft = 0.0
+            max_ft = 30.0
+            max_step_ft = 1.0
+            [[sensors]]
+            name = "yard-rain"
+            topic = "lora/yard/rain"
+            site_id = "YARD"
+            kind = "tipping_bucket"
+            tip_in = 0.01
+        "#).unwrap()
+    }
+
+    fn at(minute: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap() + Duration::minutes(minute)
+    }
+
+    #[test]
+    fn test_ultrasonic_distance_becomes_stage() {
+        let settings = settings();
+        settings.validate().unwrap();
+        let mut sensors = LocalSensors::new(&settings);
+
+        // 4.572 m = 15 ft below the sensor face
+        let reading = sensors.accept("lora/dock/stage", b"4.572", at(0)).unwrap();
+        assert_eq!((reading.site_id.as_str(), reading.parameter.as_str()), ("DOCK", STAGE_PARAMETER));
+        assert!((reading.value - 17.5).abs() < 1e-9);
+        assert_eq!(reading.reading_time, at(0));
+
+        let timed = sensors.accept("lora/dock/stage", br#"{"value": 4.5, "time": "2024-05-01T12:10:00Z"}"#, at(12)).unwrap();
+        assert_eq!(timed.reading_time, at(10));
+    }
+
+    #[test]
+    fn test_implausible_stage_rejected() {
+        let mut sensors = LocalSensors::new(&settings());
+        // Distance beyond the sensor height puts stage below zero
+        assert!(sensors.accept("lora/dock/stage", b"10.5", at(0)).unwrap_err().contains("outside"));
+
+        sensors.accept("lora/dock/stage", b"4.572", at(0)).unwrap();
+        // 0.9 m (~3 ft) in 15 minutes is a reflection off debris, not the river
+        assert!(sensors.accept("lora/dock/stage", b"3.672", at(15)).unwrap_err().contains("jumped"));
+        // The same change two hours later is plausible
+        assert!(sensors.accept("lora/dock/stage", b"3.672", at(135)).is_ok());
+
+        let future = br#"{"value": 4.5, "time": "2024-05-01T13:00:00Z"}"#;
+        assert!(sensors.accept("lora/dock/stage", future, at(0)).unwrap_err().contains("future"));
+        assert!(sensors.accept("lora/dock/stage", b"n/a", at(0)).is_err());
+    }
+
+    #[test]
+    fn test_tipping_bucket_tips_become_inches() {
+        let mut sensors = LocalSensors::new(&settings());
+        let reading = sensors.accept("lora/yard/rain", br#"{"value": 12, "time": 1714564800}"#, at(5)).unwrap();
+        assert_eq!(reading.parameter, PRECIP_PARAMETER);
+        assert!((reading.value - 0.12).abs() < 1e-9);
+        assert_eq!(reading.reading_time, at(0));
+
+        assert!(sensors.accept("lora/yard/rain", b"-1", at(0)).is_err());
+        assert!(sensors.accept("lora/yard/rain", b"2.5", at(0)).is_err());
+        assert!(sensors.accept("lora/yard/rain", b"5000", at(0)).unwrap_err().contains("max_tips"));
+        assert!(sensors.accept("lora/unknown", b"1", at(0)).is_err());
+    }
+
+    #[test]
+    fn test_settings_validation() {
+        let mut bad = settings();
+        bad.sensors[1].topic = "lora/dock/stage".to_string();
+        assert!(bad.validate().unwrap_err().contains("share topic"));
+
+        let mut bad = settings();
+        bad.sensors[0].topic = "lora/#".to_string();
+        assert!(bad.validate().is_err());
+
+        let mut bad = settings();
+        if let SensorKind::UltrasonicStage { distance_unit, .. } = &mut bad.sensors[0].kind {
+            *distance_unit = "furlongs".to_string();
+        }
+        assert!(bad.validate().is_err());
+    }
+}
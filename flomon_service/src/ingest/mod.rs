@@ -1,5 +1,19 @@
+pub mod base_urls;
 pub mod cwms;
+pub mod cwms_quality;
+pub mod fetch;
+pub mod field_measurements;
 pub mod fixtures;
 pub mod iem;
-pub mod peak_flow;
+pub mod isws;
+pub mod local_sensors;
+pub mod mqtt;
+pub mod mrms;
+pub mod nws_alerts;
+pub mod plugin;
+pub mod retry;
+pub mod rating;
 pub mod usgs;
+pub mod usgs_peaks;
+pub mod usgs_rdb;
+pub mod usgs_stats;
@@ -0,0 +1,370 @@
+//! Minimal MQTT 3.1.1 subscriber over plain TCP.
+//!
+//! Just enough of the protocol for `local_sensors`: CONNECT (optional
+//! username/password, clean session), SUBSCRIBE at QoS 0 or 1, receiving
+//! PUBLISH (acknowledging QoS 1), and PINGREQ keepalives. Nothing is ever
+//! published and QoS 2 is not requested. The broker is expected to be on
+//! the local network (a LoRa gateway's Mosquitto, for example), so there is
+//! no TLS.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const PUBACK: u8 = 0x40;
+const SUBSCRIBE: u8 = 0x82;
+const SUBACK: u8 = 0x90;
+const PINGREQ: u8 = 0xc0;
+const PINGRESP: u8 = 0xd0;
+
+/// Time allowed for the rest of a packet once it has started arriving
+const PACKET_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest packet accepted from the broker
+const MAX_PACKET_BYTES: usize = 64 * 1024;
+
+/// Connection options
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectOptions {
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub keepalive: Duration,
+}
+
+/// An application message received on a subscribed topic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub topic: String,
+    pub payload: Vec<u8>,
+}
+
+/// A fixed header type plus its body
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Packet {
+    header: u8,
+    body: Vec<u8>,
+}
+
+// ---------------------------------------------------------------------------
+// Encoding
+// ---------------------------------------------------------------------------
+
+fn encode_remaining_length(mut len: usize, out: &mut Vec<u8>) {
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+}
+
+fn encode_str(s: &str, out: &mut Vec<u8>) {
+    out.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![header];
+    encode_remaining_length(body.len(), &mut out);
+    out.extend_from_slice(body);
+    out
+}
+
+fn connect_packet(options: &ConnectOptions) -> Vec<u8> {
+    let mut body = Vec::new();
+    encode_str("MQTT", &mut body);
+    body.push(4); // protocol level 3.1.1
+    let mut flags = 0x02; // clean session
+    if options.username.is_some() {
+        flags |= 0x80;
+    }
+    if options.password.is_some() {
+        flags |= 0x40;
+    }
+    body.push(flags);
+    body.extend_from_slice(&(options.keepalive.as_secs().min(u16::MAX as u64) as u16).to_be_bytes());
+    encode_str(&options.client_id, &mut body);
+    if let Some(username) = &options.username {
+        encode_str(username, &mut body);
+    }
+    if let Some(password) = &options.password {
+        encode_str(password, &mut body);
+    }
+    packet(CONNECT, &body)
+}
+
+fn subscribe_packet(packet_id: u16, topics: &[String], qos: u8) -> Vec<u8> {
+    let mut body = packet_id.to_be_bytes().to_vec();
+    for topic in topics {
+        encode_str(topic, &mut body);
+        body.push(qos);
+    }
+    packet(SUBSCRIBE, &body)
+}
+
+// ---------------------------------------------------------------------------
+// Decoding
+// ---------------------------------------------------------------------------
+
+fn read_packet(stream: &mut impl Read) -> io::Result<Packet> {
+    let mut header = [0u8; 1];
+    stream.read_exact(&mut header)?;
+    read_packet_body(stream, header[0])
+}
+
+/// Remaining length and body of a packet whose header byte has been read
+fn read_packet_body(stream: &mut impl Read, header: u8) -> io::Result<Packet> {
+    let mut len = 0usize;
+    for shift in 0..4 {
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte)?;
+        len |= ((byte[0] & 0x7f) as usize) << (7 * shift);
+        if byte[0] & 0x80 == 0 {
+            if len > MAX_PACKET_BYTES {
+                return Err(invalid(format!("packet of {} bytes exceeds limit", len)));
+            }
+            let mut body = vec![0u8; len];
+            stream.read_exact(&mut body)?;
+            return Ok(Packet { header, body });
+        }
+    }
+    Err(invalid("malformed remaining length".to_string()))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Topic, payload and (for QoS 1) packet id of a PUBLISH
+fn parse_publish(header: u8, body: &[u8]) -> io::Result<(Message, Option<u16>)> {
+    let topic_len = body.get(..2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+        .ok_or_else(|| invalid("PUBLISH without topic".to_string()))?;
+    let topic = body.get(2..2 + topic_len)
+        .and_then(|t| std::str::from_utf8(t).ok())
+        .ok_or_else(|| invalid("PUBLISH topic is not UTF-8".to_string()))?
+        .to_string();
+    let mut rest = 2 + topic_len;
+    let qos = (header >> 1) & 0x03;
+    let packet_id = if qos > 0 {
+        let id = body.get(rest..rest + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or_else(|| invalid("PUBLISH without packet id".to_string()))?;
+        rest += 2;
+        Some(id)
+    } else {
+        None
+    };
+    Ok((Message { topic, payload: body[rest..].to_vec() }, packet_id))
+}
+
+/// A SUBACK must carry one return code per requested topic; 0x80 is a refusal
+fn check_suback(address: &str, body: &[u8], topics: &[String]) -> Result<(), String> {
+    let codes = body.get(2..).unwrap_or_default();
+    if codes.len() != topics.len() {
+        return Err(format!(
+            "MQTT broker {} acknowledged {} subscriptions, expected {}",
+            address, codes.len(), topics.len(),
+        ));
+    }
+    if let Some(topic) = codes.iter().position(|&code| code == 0x80).and_then(|i| topics.get(i)) {
+        return Err(format!("MQTT broker {} refused subscription to {}", address, topic));
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Client
+// ---------------------------------------------------------------------------
+
+/// A subscribed connection
+pub struct Subscriber {
+    stream: TcpStream,
+    keepalive: Duration,
+    last_sent: Instant,
+    ping_outstanding: bool,
+}
+
+impl Subscriber {
+    /// Connect to `address` ("host:port") and subscribe to `topics` at `qos`
+    pub fn connect(address: &str, options: &ConnectOptions, topics: &[String], qos: u8) -> Result<Self, String> {
+        let mut stream = TcpStream::connect(address)
+            .map_err(|e| format!("Failed to connect to MQTT broker {}: {}", address, e))?;
+        stream.set_read_timeout(Some(PACKET_TIMEOUT)).map_err(|e| e.to_string())?;
+        let io_err = |e: io::Error| format!("MQTT broker {}: {}", address, e);
+
+        stream.write_all(&connect_packet(options)).map_err(io_err)?;
+        let connack = read_packet(&mut stream).map_err(io_err)?;
+        if connack.header != CONNACK || connack.body.len() != 2 {
+            return Err(format!("MQTT broker {} sent {:#04x} instead of CONNACK", address, connack.header));
+        }
+        if connack.body[1] != 0 {
+            return Err(format!("MQTT broker {} refused connection (return code {})", address, connack.body[1]));
+        }
+
+        stream.write_all(&subscribe_packet(1, topics, qos.min(1))).map_err(io_err)?;
+        let suback = read_packet(&mut stream).map_err(io_err)?;
+        if suback.header != SUBACK {
+            return Err(format!("MQTT broker {} sent {:#04x} instead of SUBACK", address, suback.header));
+        }
+        check_suback(address, &suback.body, topics)?;
+
+        Ok(Self { stream, keepalive: options.keepalive, last_sent: Instant::now(), ping_outstanding: false })
+    }
+
+    /// Wait up to `timeout` for the next message, keeping the connection
+    /// alive. `Ok(None)` on timeout; an error means the connection is gone.
+    pub fn next_message(&mut self, timeout: Duration) -> Result<Option<Message>, String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            self.ping_if_due()?;
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            let wait = (deadline - now).min(self.keepalive / 2).max(Duration::from_millis(10));
+            self.stream.set_read_timeout(Some(wait)).map_err(|e| e.to_string())?;
+            let mut header = [0u8; 1];
+            match self.stream.read(&mut header) {
+                Ok(1) => {}
+                Ok(_) => return Err("MQTT broker closed the connection".to_string()),
+                Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => continue,
+                Err(e) => return Err(format!("MQTT connection lost: {}", e)),
+            }
+            // Once a packet has started, read the rest of it without the short timeout
+            self.stream.set_read_timeout(Some(PACKET_TIMEOUT)).map_err(|e| e.to_string())?;
+            let received = read_packet_body(&mut self.stream, header[0])
+                .map_err(|e| format!("MQTT connection lost: {}", e))?;
+            match received.header & 0xf0 {
+                PUBLISH => {
+                    let (message, packet_id) = parse_publish(received.header, &received.body).map_err(|e| e.to_string())?;
+                    if let Some(id) = packet_id {
+                        self.send(&packet(PUBACK, &id.to_be_bytes()))?;
+                    }
+                    return Ok(Some(message));
+                }
+                PINGRESP => self.ping_outstanding = false,
+                _ => {}
+            }
+        }
+    }
+
+    fn ping_if_due(&mut self) -> Result<(), String> {
+        if self.last_sent.elapsed() < self.keepalive {
+            return Ok(());
+        }
+        if self.ping_outstanding {
+            return Err("MQTT broker did not answer keepalive ping".to_string());
+        }
+        self.send(&packet(PINGREQ, &[]))?;
+        self.ping_outstanding = true;
+        Ok(())
+    }
+
+    fn send(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.stream.write_all(bytes).map_err(|e| format!("MQTT write failed: {}", e))?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    fn options() -> ConnectOptions {
+        ConnectOptions {
+            client_id: "flomon".to_string(),
+            username: Some("dock".to_string()),
+            password: Some("secret".to_string()),
+            keepalive: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn test_remaining_length_encoding() {
+        for (len, bytes) in [(0, vec![0x00]), (127, vec![0x7f]), (128, vec![0x80, 0x01]), (16_383, vec![0xff, 0x7f])] {
+            let mut out = Vec::new();
+            encode_remaining_length(len, &mut out);
+            assert_eq!(out, bytes, "length {}", len);
+        }
+        let encoded = packet(PUBLISH, &[7u8; 300]);
+        let decoded = read_packet(&mut encoded.as_slice()).unwrap();
+        assert_eq!(decoded.body.len(), 300);
+    }
+
+    #[test]
+    fn test_connect_packet_layout() {
+        let bytes = connect_packet(&options());
+        assert_eq!(bytes[0], CONNECT);
+        // "MQTT", level 4, flags: username + password + clean session
+        assert_eq!(&bytes[2..10], &[0, 4, b'M', b'Q', b'T', b'T', 4, 0xc2]);
+        assert_eq!(&bytes[10..12], &60u16.to_be_bytes());
+    }
+
+    #[test]
+    fn test_parse_publish_qos0_and_qos1() {
+        let mut body = Vec::new();
+        encode_str("dock/stage", &mut body);
+        body.extend_from_slice(b"1234");
+        let (message, id) = parse_publish(PUBLISH, &body).unwrap();
+        assert_eq!((message.topic.as_str(), message.payload.as_slice(), id), ("dock/stage", &b"1234"[..], None));
+
+        let mut body = Vec::new();
+        encode_str("rain/tips", &mut body);
+        body.extend_from_slice(&42u16.to_be_bytes());
+        body.extend_from_slice(b"3");
+        let (message, id) = parse_publish(PUBLISH | 0x02, &body).unwrap();
+        assert_eq!((message.payload.as_slice(), id), (&b"3"[..], Some(42)));
+    }
+
+    #[test]
+    fn test_suback_needs_one_code_per_topic() {
+        let topics = ["dock/#".to_string(), "rain/#".to_string()];
+        assert!(check_suback("broker", &[0, 1, 1, 0], &topics).is_ok());
+        assert!(check_suback("broker", &[0, 1, 1, 0x80], &topics).unwrap_err().contains("rain/#"));
+        assert!(check_suback("broker", &[0, 1, 1], &topics).unwrap_err().contains("acknowledged 1 subscriptions, expected 2"));
+        assert!(check_suback("broker", &[0, 1, 0, 0, 0x80], &topics).is_err());
+        assert!(check_suback("broker", &[0], &topics).is_err());
+    }
+
+    #[test]
+    fn test_subscribe_and_receive_from_broker() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let broker = std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            assert_eq!(read_packet(&mut socket).unwrap().header, CONNECT);
+            socket.write_all(&packet(CONNACK, &[0, 0])).unwrap();
+            let subscribe = read_packet(&mut socket).unwrap();
+            assert_eq!(subscribe.header, SUBSCRIBE);
+            socket.write_all(&packet(SUBACK, &[0, 1, 1])).unwrap();
+            let mut body = Vec::new();
+            encode_str("dock/stage", &mut body);
+            body.extend_from_slice(&9u16.to_be_bytes());
+            body.extend_from_slice(b"{\"value\": 1830}");
+            socket.write_all(&packet(PUBLISH | 0x02, &body)).unwrap();
+            // The client acknowledges the QoS 1 message
+            read_packet(&mut socket).unwrap()
+        });
+
+        let mut subscriber = Subscriber::connect(&address, &options(), &["dock/#".to_string()], 1).unwrap();
+        let message = subscriber.next_message(Duration::from_secs(5)).unwrap().unwrap();
+        assert_eq!(message.topic, "dock/stage");
+        assert_eq!(broker.join().unwrap(), Packet { header: PUBACK, body: vec![0, 9] });
+    }
+}
@@ -0,0 +1,447 @@
+//! NOAA MRMS radar QPE averaged over the basin and each zone.
+//!
+//! ASOS gauges are 30-60 miles apart and a convective cell can drop two
+//! inches between them without either one seeing it. MRMS (Multi-Radar
+//! Multi-Sensor) merges radar and gauges into a 0.01° national grid; its
+//! hourly multi-sensor QPE (`MultiSensor_QPE_01H_Pass2`) is what this
+//! module summarizes.
+//!
+//! NOAA publishes MRMS as gzipped GRIB2, which this crate does not decode.
+//! `[mrms] url` instead names an ESRI ASCII grid of one hour's QPE in mm,
+//! exported from the GRIB2 by a cron job (`gdal_translate -of AAIGrid
+//! -projwin ...` cropped to the basin keeps it small). The url may be
+//! `http(s)://` or a local path, and may contain chrono `%` fields that
+//! are filled with the hour's end time, UTC:
+//!
+//! ```toml
+//! [mrms]
+//! url = "http://127.0.0.1:8081/mrms/qpe01h_%Y%m%d-%H0000.asc"
+//! latency_minutes = 60      # Pass2 is final about an hour after the hour
+//! lookback_hours = 3        # hours re-checked each cycle for gaps
+//!
+//! [[mrms.zones]]            # optional: replace a zone's default footprint
+//! zone_id = 3
+//! south = 40.35
+//! north = 40.75
+//! west = -89.65
+//! east = -88.45
+//! ```
+//!
+//! Each cycle the daemon fetches any of the last `lookback_hours` complete
+//! hours not yet stored. Each grid is clipped to `BASIN_BBOX`, and the mean
+//! and maximum over the cells whose centres fall in the basin and in each
+//! zone's footprint are stored in `precip_grid_summary`
+//! (sql/033_precip_grid_summary.sql). A zone's default footprint is the
+//! bounding box of its sensors padded by `margin_deg`. Negative values
+//! (MRMS "no coverage" / missing) and NODATA cells are left out.
+
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use postgres::Client;
+use serde::Deserialize;
+
+use crate::analysis::inundation::{self, DemGrid};
+use crate::zones::{self, ZonesConfig};
+
+/// Product label stored with each summary
+pub const PRODUCT: &str = "MultiSensor_QPE_01H_Pass2";
+
+/// `scope_id` of the basin-wide summary
+pub const BASIN_SCOPE_ID: &str = "illinois";
+
+/// Approximate Illinois River basin extent
+pub const BASIN_BBOX: Footprint = Footprint { south: 38.9, north: 42.8, west: -91.2, east: -87.4 };
+
+const MM_PER_INCH: f64 = 25.4;
+
+pub const DEFAULT_LATENCY_MINUTES: i64 = 60;
+pub const DEFAULT_LOOKBACK_HOURS: i64 = 3;
+pub const DEFAULT_MARGIN_DEG: f64 = 0.1;
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// `[mrms]` section of flomon.toml
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MrmsSettings {
+    /// Hourly QPE grid (ESRI ASCII, mm), with chrono `%` fields for the hour end
+    pub url: String,
+    #[serde(default = "default_latency_minutes")]
+    pub latency_minutes: i64,
+    #[serde(default = "default_lookback_hours")]
+    pub lookback_hours: i64,
+    /// Padding around a zone's sensors for its default footprint, degrees
+    #[serde(default = "default_margin_deg")]
+    pub margin_deg: f64,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default)]
+    pub zones: Vec<ZoneFootprint>,
+}
+
+fn default_latency_minutes() -> i64 {
+    DEFAULT_LATENCY_MINUTES
+}
+
+fn default_lookback_hours() -> i64 {
+    DEFAULT_LOOKBACK_HOURS
+}
+
+fn default_margin_deg() -> f64 {
+    DEFAULT_MARGIN_DEG
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+/// `[[mrms.zones]]` entry overriding a zone's footprint
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ZoneFootprint {
+    pub zone_id: usize,
+    pub south: f64,
+    pub north: f64,
+    pub west: f64,
+    pub east: f64,
+}
+
+impl ZoneFootprint {
+    fn footprint(&self) -> Footprint {
+        Footprint { south: self.south, north: self.north, west: self.west, east: self.east }
+    }
+}
+
+impl MrmsSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.url.trim().is_empty() {
+            return Err("mrms.url must not be empty".to_string());
+        }
+        if self.latency_minutes < 0 {
+            return Err("mrms.latency_minutes must not be negative".to_string());
+        }
+        if self.lookback_hours <= 0 {
+            return Err("mrms.lookback_hours must be positive".to_string());
+        }
+        if self.margin_deg.is_nan() || self.margin_deg < 0.0 {
+            return Err("mrms.margin_deg must not be negative".to_string());
+        }
+        for zone in &self.zones {
+            if zone.zone_id > inundation::MAX_ZONE_ID {
+                return Err(format!("mrms zone_id {} must be 0-{}", zone.zone_id, inundation::MAX_ZONE_ID));
+            }
+            if !zone.footprint().is_valid() {
+                return Err(format!("mrms zone {} footprint must have south < north and west < east", zone.zone_id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Grid location for the hour ending at `hour_end`
+    pub fn url_for(&self, hour_end: DateTime<Utc>) -> String {
+        hour_end.format(&self.url).to_string()
+    }
+
+    /// Hours (end times) expected to be available at `now`, oldest first
+    pub fn hours_due(&self, now: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        let available = now - Duration::minutes(self.latency_minutes);
+        let Ok(latest) = available.duration_trunc(Duration::hours(1)) else {
+            return Vec::new();
+        };
+        (0..self.lookback_hours).rev().map(|h| latest - Duration::hours(h)).collect()
+    }
+
+    /// Footprint of every zone: configured, else its sensors' bounding box
+    /// padded by `margin_deg`. Zones without sensors and no override are left out.
+    pub fn zone_footprints(&self, zones: Option<&ZonesConfig>) -> Vec<(usize, Footprint)> {
+        let mut footprints: Vec<(usize, Footprint)> = zones
+            .map(|config| {
+                zones::get_all_zones(config).into_iter()
+                    .filter(|(id, _)| !self.zones.iter().any(|z| z.zone_id == *id))
+                    .filter_map(|(id, zone)| {
+                        Footprint::around(zone.sensors.iter().map(|s| (s.lat, s.lon)), self.margin_deg)
+                            .map(|f| (id, f))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        footprints.extend(self.zones.iter().map(|z| (z.zone_id, z.footprint())));
+        footprints.sort_by_key(|(id, _)| *id);
+        footprints
+    }
+}
+
+// ============================================================================
+// Grid summaries
+// ============================================================================
+
+/// Lat/lon bounding box, degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Footprint {
+    pub south: f64,
+    pub north: f64,
+    pub west: f64,
+    pub east: f64,
+}
+
+impl Footprint {
+    /// Bounding box of `points` (lat, lon) padded by `margin_deg`
+    pub fn around(points: impl IntoIterator<Item = (f64, f64)>, margin_deg: f64) -> Option<Self> {
+        let mut points = points.into_iter();
+        let (lat, lon) = points.next()?;
+        let mut bbox = Footprint { south: lat, north: lat, west: lon, east: lon };
+        for (lat, lon) in points {
+            bbox.south = bbox.south.min(lat);
+            bbox.north = bbox.north.max(lat);
+            bbox.west = bbox.west.min(lon);
+            bbox.east = bbox.east.max(lon);
+        }
+        Some(Footprint {
+            south: bbox.south - margin_deg,
+            north: bbox.north + margin_deg,
+            west: bbox.west - margin_deg,
+            east: bbox.east + margin_deg,
+        })
+    }
+
+    fn is_valid(&self) -> bool {
+        self.south < self.north && self.west < self.east
+    }
+
+    fn contains(&self, lat: f64, lon: f64) -> bool {
+        (self.south..=self.north).contains(&lat) && (self.west..=self.east).contains(&lon)
+    }
+}
+
+/// Where a summary applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Basin,
+    Zone(usize),
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Basin => "basin",
+            Scope::Zone(_) => "zone",
+        }
+    }
+
+    pub fn id(&self) -> String {
+        match self {
+            Scope::Basin => BASIN_SCOPE_ID.to_string(),
+            Scope::Zone(id) => id.to_string(),
+        }
+    }
+}
+
+/// One hour's QPE over one area
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridSummary {
+    pub scope: Scope,
+    pub hour_end: DateTime<Utc>,
+    pub mean_in: f64,
+    pub max_in: f64,
+    /// Cells with a valid value inside the area
+    pub cell_count: i32,
+}
+
+/// Valid QPE values (inches) of cells whose centres fall in `area`
+fn values_in(grid: &DemGrid, area: &Footprint) -> Vec<f64> {
+    let mut values = Vec::new();
+    for row in 0..grid.nrows {
+        let lat = grid.yll + (grid.nrows - row) as f64 * grid.cellsize - grid.cellsize / 2.0;
+        if !(area.south..=area.north).contains(&lat) {
+            continue;
+        }
+        for col in 0..grid.ncols {
+            let lon = grid.xll + (col as f64 + 0.5) * grid.cellsize;
+            if !area.contains(lat, lon) {
+                continue;
+            }
+            if let Some(mm) = grid.elevations[row * grid.ncols + col].filter(|v| *v >= 0.0) {
+                values.push(mm / MM_PER_INCH);
+            }
+        }
+    }
+    values
+}
+
+/// Basin and per-zone summaries of one hourly grid (values in mm). Areas
+/// with no valid cells get no summary.
+pub fn summarize(grid: &DemGrid, hour_end: DateTime<Utc>, zones: &[(usize, Footprint)]) -> Vec<GridSummary> {
+    std::iter::once((Scope::Basin, BASIN_BBOX))
+        .chain(zones.iter().map(|(id, footprint)| (Scope::Zone(*id), *footprint)))
+        .filter_map(|(scope, area)| {
+            let values = values_in(grid, &area);
+            (!values.is_empty()).then(|| GridSummary {
+                scope,
+                hour_end,
+                mean_in: values.iter().sum::<f64>() / values.len() as f64,
+                max_in: values.iter().copied().fold(0.0, f64::max),
+                cell_count: values.len() as i32,
+            })
+        })
+        .collect()
+}
+
+// ============================================================================
+// Fetch and store
+// ============================================================================
+
+/// Read one hour's grid from `settings.url`
+pub fn fetch_grid(settings: &MrmsSettings, hour_end: DateTime<Utc>) -> Result<DemGrid, String> {
+    let location = settings.url_for(hour_end);
+    let text = if location.starts_with("http://") || location.starts_with("https://") {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(settings.timeout_secs))
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+        let response = client.get(&location).send()
+            .map_err(|e| format!("Failed to fetch MRMS grid {}: {}", location, e))?;
+        if !response.status().is_success() {
+            return Err(format!("MRMS grid {} returned HTTP {}", location, response.status()));
+        }
+        response.text().map_err(|e| format!("Failed to read MRMS grid {}: {}", location, e))?
+    } else {
+        std::fs::read_to_string(&location)
+            .map_err(|e| format!("Failed to read MRMS grid {}: {}", location, e))?
+    };
+    inundation::parse_ascii_grid(&text).map_err(|e| format!("{}: {}", location, e))
+}
+
+/// Hours in `hours` that already have a basin summary
+pub fn stored_hours(client: &mut Client, hours: &[DateTime<Utc>]) -> Result<Vec<DateTime<Utc>>, String> {
+    let rows = client.query(
+        "SELECT hour_end FROM precip_grid_summary
+         WHERE scope = 'basin' AND scope_id = $1 AND hour_end = ANY($2)",
+        &[&BASIN_SCOPE_ID, &hours],
+    ).map_err(|e| format!("Failed to read precip_grid_summary: {}", e))?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+/// Basin-wide QPE summed over the hours ending in `(now - hours, now]`;
+/// None when no hour in the window is stored
+pub fn basin_total(client: &mut Client, hours: i64, now: DateTime<Utc>) -> Result<Option<f64>, String> {
+    let row = client.query_one(
+        "SELECT SUM(mean_in) FROM precip_grid_summary
+         WHERE scope = 'basin' AND scope_id = $1 AND hour_end > $2 AND hour_end <= $3",
+        &[&BASIN_SCOPE_ID, &(now - Duration::hours(hours)), &now],
+    ).map_err(|e| format!("Failed to read precip_grid_summary: {}", e))?;
+    Ok(row.get(0))
+}
+
+/// Insert or replace summaries; returns rows written
+pub fn store(client: &mut Client, summaries: &[GridSummary]) -> Result<usize, String> {
+    let mut written = 0;
+    for s in summaries {
+        written += client.execute(
+            "INSERT INTO precip_grid_summary
+             (scope, scope_id, hour_end, mean_in, max_in, cell_count, product)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (scope, scope_id, hour_end) DO UPDATE
+             SET mean_in = EXCLUDED.mean_in,
+                 max_in = EXCLUDED.max_in,
+                 cell_count = EXCLUDED.cell_count,
+                 product = EXCLUDED.product,
+                 computed_at = NOW()",
+            &[&s.scope.as_str(), &s.scope.id(), &s.hour_end, &s.mean_in, &s.max_in, &s.cell_count, &PRODUCT],
+        ).map_err(|e| format!("Failed to store MRMS summary {} {}: {}", s.scope.as_str(), s.scope.id(), e))? as usize;
+    }
+    Ok(written)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn settings() -> MrmsSettings {
+        MrmsSettings {
+            url: "/data/mrms/qpe01h_%Y%m%d-%H0000.asc".to_string(),
+            latency_minutes: DEFAULT_LATENCY_MINUTES,
+            lookback_hours: DEFAULT_LOOKBACK_HOURS,
+            margin_deg: DEFAULT_MARGIN_DEG,
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            zones: Vec::new(),
+        }
+    }
+
+    /// 4 x 4 grid of 0.5° cells covering 40-42N, 90-88W; NW quadrant wet
+    const GRID: &str = "\
+ncols 4
+nrows 4
+xllcorner -90.0
+yllcorner 40.0
+cellsize 0.5
+NODATA_value -999
+25.4 50.8 0 0
+12.7 -3 0 0
+0 0 0 -999
+0 0 0 0
+";
+
+    #[test]
+    fn test_hours_due_respects_latency() {
+        let now = Utc.with_ymd_and_hms(2026, 6, 1, 14, 20, 0).unwrap();
+        let hours = settings().hours_due(now);
+        assert_eq!(hours, vec![
+            Utc.with_ymd_and_hms(2026, 6, 1, 11, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2026, 6, 1, 12, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2026, 6, 1, 13, 0, 0).unwrap(),
+        ]);
+        assert_eq!(settings().url_for(hours[2]), "/data/mrms/qpe01h_20260601-130000.asc");
+    }
+
+    #[test]
+    fn test_summarize_basin_and_zone() {
+        let grid = inundation::parse_ascii_grid(GRID).unwrap();
+        let hour = Utc.with_ymd_and_hms(2026, 6, 1, 13, 0, 0).unwrap();
+        let nw = Footprint { south: 41.0, north: 42.0, west: -90.0, east: -89.0 };
+        let summaries = summarize(&grid, hour, &[(2, nw)]);
+
+        let basin = &summaries[0];
+        assert_eq!(basin.scope, Scope::Basin);
+        assert_eq!(basin.cell_count, 14, "missing (-3) and NODATA cells are left out");
+
+        let zone = &summaries[1];
+        assert_eq!(zone.scope, Scope::Zone(2));
+        assert_eq!(zone.cell_count, 3);
+        assert!((zone.mean_in - (1.0 + 2.0 + 0.5) / 3.0).abs() < 1e-9);
+        assert!((zone.max_in - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_zone_without_cells_has_no_summary() {
+        let grid = inundation::parse_ascii_grid(GRID).unwrap();
+        let hour = Utc.with_ymd_and_hms(2026, 6, 1, 13, 0, 0).unwrap();
+        let elsewhere = Footprint { south: 30.0, north: 31.0, west: -100.0, east: -99.0 };
+        assert_eq!(summarize(&grid, hour, &[(5, elsewhere)]).len(), 1);
+    }
+
+    #[test]
+    fn test_footprint_around_sensors() {
+        let f = Footprint::around([(40.7, -89.6), (40.5, -89.4)], 0.1).unwrap();
+        assert!((f.south - 40.4).abs() < 1e-9 && (f.north - 40.8).abs() < 1e-9);
+        assert!((f.west + 89.7).abs() < 1e-9 && (f.east + 89.3).abs() < 1e-9);
+        assert!(Footprint::around(std::iter::empty(), 0.1).is_none());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(settings().validate().is_ok());
+        let mut bad = settings();
+        bad.zones.push(ZoneFootprint { zone_id: 2, south: 41.0, north: 40.0, west: -90.0, east: -89.0 });
+        assert!(bad.validate().is_err());
+        let mut bad = settings();
+        bad.lookback_hours = 0;
+        assert!(bad.validate().is_err());
+    }
+}
@@ -0,0 +1,361 @@
+//! NWS flood watches and warnings from the api.weather.gov alerts feed.
+//!
+//! The forecasters at NWS Lincoln (ILX) often issue a Flood Watch before
+//! any gauge here has moved, and a Flood Warning for the river carries
+//! their crest forecast. Each cycle the daemon fetches the active alerts
+//! for the configured county zones:
+//!
+//! ```toml
+//! [nws_alerts]
+//! zones = ["ILC143", "ILC179", "ILC203"]   # Peoria, Tazewell, Woodford (default)
+//! ```
+//!
+//! Only flood watches and warnings are kept (Flood Watch, Flood Warning,
+//! Flash Flood Watch/Warning, ...); advisories and statements are not.
+//! They are stored in `nws.active_alerts` (sql/037_nws_active_alerts.sql),
+//! which is replaced by every poll, and each alert is dispatched once, when
+//! first seen. An NWS update is a new alert and goes out again.
+//!
+//! Alerts reach the subscribers of the zones they cover, matched by UGC and
+//! SAME code against `nws_geo`'s stored geography (`flomon geo resolve`).
+//! Until that has been resolved every zone is notified: the feed is
+//! already limited to the counties around the property.
+
+use chrono::{DateTime, Utc};
+use postgres::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+use crate::alert::thresholds::{FloodAlert, FloodSeverity};
+use crate::ingest::retry;
+use crate::logging::DataSource;
+use crate::nws_geo::{self, AlertFilter, CapArea};
+use crate::zones::{self, ZonesConfig};
+
+/// api.weather.gov active alerts endpoint
+pub const ALERTS_URL: &str = "https://api.weather.gov/alerts/active";
+
+/// Peoria, Tazewell and Woodford counties
+pub const DEFAULT_ZONES: [&str; 3] = ["ILC143", "ILC179", "ILC203"];
+
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// `[nws_alerts]` section of flomon.toml
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NwsAlertSettings {
+    /// UGC county ("ILC143") or forecast zone ("ILZ031") codes to poll
+    #[serde(default = "default_zones")]
+    pub zones: Vec<String>,
+    #[serde(default = "default_url")]
+    pub url: String,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_zones() -> Vec<String> {
+    DEFAULT_ZONES.iter().map(|z| z.to_string()).collect()
+}
+
+fn default_url() -> String {
+    ALERTS_URL.to_string()
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+impl Default for NwsAlertSettings {
+    fn default() -> Self {
+        Self { zones: default_zones(), url: default_url(), timeout_secs: default_timeout_secs() }
+    }
+}
+
+impl NwsAlertSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.zones.is_empty() {
+            return Err("nws_alerts.zones must list at least one zone".to_string());
+        }
+        for zone in &self.zones {
+            if !nws_geo::is_forecast_zone(zone) && nws_geo::county_fips_from_ugc(zone).is_none() {
+                return Err(format!("nws_alerts.zones: {:?} is not a UGC code like ILC143 or ILZ031", zone));
+            }
+        }
+        if self.timeout_secs == 0 {
+            return Err("nws_alerts.timeout_secs must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+
+    /// Feed URL for the configured zones
+    pub fn alerts_url(&self) -> String {
+        format!("{}?zone={}", self.url, self.zones.join(","))
+    }
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+/// One flood watch or warning
+#[derive(Debug, Clone, PartialEq)]
+pub struct NwsAlert {
+    /// CAP identifier; a new one for every update
+    pub id: String,
+    /// "Flood Warning", "Flash Flood Watch", ...
+    pub event: String,
+    /// "Alert" or "Update"
+    pub message_type: String,
+    /// CAP severity ("Severe")
+    pub cap_severity: Option<String>,
+    pub urgency: Option<String>,
+    pub headline: Option<String>,
+    pub area_desc: String,
+    pub area: CapArea,
+    pub sent: DateTime<Utc>,
+    pub onset: Option<DateTime<Utc>>,
+    pub expires: Option<DateTime<Utc>>,
+    pub ends: Option<DateTime<Utc>>,
+}
+
+/// Flood watches and warnings; advisories and statements are informational
+pub fn is_flood_watch_or_warning(event: &str) -> bool {
+    event.contains("Flood") && (event.ends_with(" Watch") || event.ends_with(" Warning"))
+}
+
+fn time(value: &Value) -> Option<DateTime<Utc>> {
+    value.as_str()
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Flood watches and warnings in an api.weather.gov alerts FeatureCollection
+pub fn parse(json: &str) -> Result<Vec<NwsAlert>, String> {
+    let doc: Value = serde_json::from_str(json)
+        .map_err(|e| format!("Invalid NWS alerts response: {}", e))?;
+    let features = doc["features"].as_array()
+        .ok_or("NWS alerts response has no features")?;
+
+    let mut alerts = Vec::new();
+    for feature in features {
+        let props = &feature["properties"];
+        let text = |key: &str| props[key].as_str().map(str::to_string);
+        let (Some(id), Some(event), Some(sent)) = (text("id"), text("event"), time(&props["sent"])) else {
+            continue;
+        };
+        let message_type = text("messageType").unwrap_or_else(|| "Alert".to_string());
+        if !is_flood_watch_or_warning(&event) || message_type == "Cancel" {
+            continue;
+        }
+        alerts.push(NwsAlert {
+            id,
+            event,
+            message_type,
+            cap_severity: text("severity"),
+            urgency: text("urgency"),
+            headline: text("headline"),
+            area_desc: text("areaDesc").unwrap_or_default(),
+            area: CapArea::from_feature(feature),
+            sent,
+            onset: time(&props["onset"]),
+            expires: time(&props["expires"]),
+            ends: time(&props["ends"]),
+        });
+    }
+    Ok(alerts)
+}
+
+impl NwsAlert {
+    /// Severity on our scale: a warning means flooding is occurring or
+    /// imminent, a watch that it is possible
+    pub fn flood_severity(&self) -> FloodSeverity {
+        if self.event.ends_with(" Warning") {
+            FloodSeverity::Flood
+        } else {
+            FloodSeverity::Action
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let update = if self.message_type == "Update" { " (update)" } else { "" };
+        match &self.headline {
+            Some(headline) => format!("NWS {}{}: {}", self.event, update, headline),
+            None => format!("NWS {}{} for {}", self.event, update, self.area_desc),
+        }
+    }
+
+    /// The alert as it goes through the notification pipeline
+    pub fn to_flood_alert(&self) -> FloodAlert {
+        FloodAlert {
+            severity: self.flood_severity(),
+            message: self.describe(),
+            registry_version: None,
+        }
+    }
+}
+
+/// Zones to notify of `area`: those the stored geography says it covers,
+/// or every zone when no geography has been resolved
+pub fn zones_affected(filter: &AlertFilter, zones: &ZonesConfig, area: &CapArea) -> Vec<usize> {
+    if filter.stations.is_empty() && filter.zones.is_empty() {
+        return zones::get_all_zones(zones).into_iter().map(|(id, _)| id).collect();
+    }
+    let mut affected: BTreeSet<usize> = filter.zones_affected(area).into_iter().collect();
+    for site_code in filter.stations_affected(area) {
+        affected.extend(zones::zones_for_site(zones, site_code));
+    }
+    affected.into_iter().collect()
+}
+
+// ============================================================================
+// Fetch and storage
+// ============================================================================
+
+/// Active flood watches and warnings for the configured zones
+pub fn fetch(settings: &NwsAlertSettings) -> Result<Vec<NwsAlert>, String> {
+    let http = reqwest::blocking::Client::builder()
+        .user_agent(nws_geo::USER_AGENT)
+        .timeout(std::time::Duration::from_secs(settings.timeout_secs))
+        .build()
+        .map_err(|e| format!("Failed to build NWS HTTP client: {}", e))?;
+    let url = settings.alerts_url();
+    let body = retry::get_text_blocking(&http, DataSource::System, &url, false)
+        .map_err(|e| format!("NWS alerts {}: {}", url, e))?;
+    parse(&body)
+}
+
+/// Replace the stored active alerts with `alerts`; returns the ids not
+/// stored before, which are the ones to dispatch
+pub fn store(client: &mut Client, alerts: &[NwsAlert], fetched_at: DateTime<Utc>) -> Result<Vec<String>, String> {
+    let mut tx = client.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let mut new_ids = Vec::new();
+    for alert in alerts {
+        let inserted: bool = tx.query_one(
+            "INSERT INTO nws.active_alerts
+             (alert_id, event, message_type, cap_severity, urgency, headline, area_desc,
+              ugc_codes, same_codes, sent_at, onset_at, expires_at, ends_at, first_seen_at, last_seen_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $14)
+             ON CONFLICT (alert_id) DO UPDATE
+             SET expires_at = EXCLUDED.expires_at,
+                 ends_at = EXCLUDED.ends_at,
+                 last_seen_at = EXCLUDED.last_seen_at
+             RETURNING (xmax = 0)",
+            &[
+                &alert.id,
+                &alert.event,
+                &alert.message_type,
+                &alert.cap_severity,
+                &alert.urgency,
+                &alert.headline,
+                &alert.area_desc,
+                &alert.area.ugc,
+                &alert.area.same,
+                &alert.sent,
+                &alert.onset,
+                &alert.expires,
+                &alert.ends,
+                &fetched_at,
+            ],
+        ).map_err(|e| format!("Failed to store NWS alert {}: {}", alert.id, e))?.get(0);
+        if inserted {
+            new_ids.push(alert.id.clone());
+        }
+    }
+    let current: Vec<&str> = alerts.iter().map(|a| a.id.as_str()).collect();
+    tx.execute("DELETE FROM nws.active_alerts WHERE alert_id <> ALL($1)", &[&current])
+        .map_err(|e| format!("Failed to clear lapsed NWS alerts: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to commit NWS alerts: {}", e))?;
+    Ok(new_ids)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nws_geo::{Area, Geography};
+    use serde_json::json;
+
+    fn feature(id: &str, event: &str, message_type: &str) -> Value {
+        json!({
+            "type": "Feature",
+            "properties": {
+                "id": id,
+                "event": event,
+                "messageType": message_type,
+                "severity": "Severe",
+                "urgency": "Expected",
+                "headline": format!("{} issued May 1 at 9:12AM CDT by NWS Lincoln IL", event),
+                "areaDesc": "Peoria, IL; Tazewell, IL",
+                "sent": "2024-05-01T09:12:00-05:00",
+                "onset": "2024-05-01T09:12:00-05:00",
+                "expires": "2024-05-02T09:15:00-05:00",
+                "ends": null,
+                "geocode": {"SAME": ["017143", "017179"], "UGC": ["ILC143", "ILC179"]}
+            }
+        })
+    }
+
+    #[test]
+    fn test_parse_keeps_flood_watches_and_warnings() {
+        let body = json!({
+            "type": "FeatureCollection",
+            "features": [
+                feature("urn:oid:1", "Flood Warning", "Alert"),
+                feature("urn:oid:2", "Flash Flood Watch", "Update"),
+                feature("urn:oid:3", "Flood Advisory", "Alert"),
+                feature("urn:oid:4", "Severe Thunderstorm Warning", "Alert"),
+                feature("urn:oid:5", "Flood Warning", "Cancel"),
+            ]
+        }).to_string();
+        let alerts = parse(&body).unwrap();
+        assert_eq!(alerts.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(), ["urn:oid:1", "urn:oid:2"]);
+
+        let warning = &alerts[0];
+        assert_eq!(warning.sent.to_rfc3339(), "2024-05-01T14:12:00+00:00");
+        assert_eq!(warning.ends, None);
+        assert_eq!(warning.area.same, ["017143", "017179"]);
+        assert_eq!(warning.flood_severity(), FloodSeverity::Flood);
+        assert_eq!(alerts[1].flood_severity(), FloodSeverity::Action);
+        assert!(alerts[1].describe().starts_with("NWS Flash Flood Watch (update): "));
+        assert!(parse(r#"{"title": "no features"}"#).is_err());
+    }
+
+    #[test]
+    fn test_settings_default_to_the_three_counties() {
+        let settings: NwsAlertSettings = toml::from_str("").unwrap();
+        assert_eq!(settings, NwsAlertSettings::default());
+        assert_eq!(settings.alerts_url(), "https://api.weather.gov/alerts/active?zone=ILC143,ILC179,ILC203");
+        assert!(settings.validate().is_ok());
+        let bad = NwsAlertSettings { zones: vec!["17143".to_string()], ..settings };
+        assert!(bad.validate().unwrap_err().contains("UGC"));
+    }
+
+    #[test]
+    fn test_zones_affected_from_geography() {
+        let zones = zones::load_zones_default().unwrap();
+        let area = CapArea { ugc: vec!["ILC143".to_string()], same: vec![] };
+
+        let everything = zones_affected(&AlertFilter::default(), &zones, &area);
+        assert_eq!(everything.len(), zones::get_all_zones(&zones).len(), "unresolved geography: every zone");
+
+        let mut filter = AlertFilter::default();
+        filter.stations.insert("05567500".to_string(), Geography {
+            forecast_zone: Some("ILZ031".to_string()),
+            county_fips: Some("17143".to_string()),
+        });
+        let mut far = Area::default();
+        far.add(&Geography { forecast_zone: None, county_fips: Some("17163".to_string()) });
+        filter.zones.insert(6, far);
+        let affected = zones_affected(&filter, &zones, &area);
+        assert_eq!(affected, zones::zones_for_site(&zones, "05567500"));
+        assert!(!affected.contains(&6));
+    }
+}
@@ -0,0 +1,361 @@
+//! Data source plugins for community and private gauges.
+//!
+//! USGS, CWMS and ASOS are wired into the daemon directly. Anything else
+//! (a LoRa staff gauge at a marina, a township's rain bucket) is a
+//! `DataSourcePlugin`: it is configured from its `[[plugins]]` entry,
+//! fetches a raw payload, parses it into `CanonicalReading`s, and names the
+//! table they go to. The daemon polls every configured plugin once per
+//! cycle after the built-in sources, so adding a source never touches the
+//! core loop.
+//!
+//! ```toml
+//! [[plugins]]
+//! kind = "json_http"
+//! name = "marina-lora"
+//!
+//! [plugins.settings]
+//! url = "https://gateway.example.org/api/devices/dock-1/uplinks"
+//! site_id = "MARINA-DOCK-1"
+//! parameter = "stage"
+//! unit = "ft"
+//! items = "/uplinks"
+//! time_field = "received_at"
+//! value_field = "decoded/stage_ft"
+//! ```
+//!
+//! # Registration
+//! `PluginRegistry::builtin()` knows the plugins shipped here. A deployment
+//! with its own plugin builds its binary against this crate, calls
+//! `register(kind, factory)` on the registry, and hands the instantiated
+//! plugins to `Daemon::set_plugins`.
+//!
+//! # Destination
+//! Every destination table has the shape of `community.readings` (see
+//! sql/025_community_readings.sql): `source, site_id, parameter,
+//! reading_time, value, unit`, unique on the first four. Rows are inserted
+//! with `ON CONFLICT DO NOTHING`, so re-fetching overlapping windows is
+//! harmless. Nothing a plugin stores reaches `usgs_raw.gauge_readings`, the
+//! rules or the alerts.
+
+use chrono::{DateTime, Utc};
+use postgres::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+/// Table used when a plugin does not name its own
+pub const DEFAULT_DESTINATION: Destination = Destination { schema: "community", table: "readings" };
+
+/// One reading in the form every plugin produces
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanonicalReading {
+    pub site_id: String,
+    pub parameter: String,
+    pub reading_time: DateTime<Utc>,
+    pub value: f64,
+    pub unit: String,
+}
+
+/// Table a plugin's readings are written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Destination {
+    pub schema: &'static str,
+    pub table: &'static str,
+}
+
+impl Destination {
+    /// `schema.table`, after checking both are plain identifiers
+    pub fn qualified(&self) -> Result<String, String> {
+        for part in [self.schema, self.table] {
+            let plain = !part.is_empty()
+                && part.starts_with(|c: char| c.is_ascii_lowercase())
+                && part.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !plain {
+                return Err(format!("Invalid destination identifier {:?}", part));
+            }
+        }
+        Ok(format!("{}.{}", self.schema, self.table))
+    }
+}
+
+/// A data source the daemon can poll without knowing anything about it
+pub trait DataSourcePlugin: Send {
+    /// Instance name from `[[plugins]]` (stored as the `source` column)
+    fn name(&self) -> &str;
+
+    /// Apply `[plugins.settings]`; called once before the first fetch
+    fn configure(&mut self, settings: &toml::Value) -> Result<(), String>;
+
+    /// Fetch the raw payload
+    fn fetch(&mut self, http: &reqwest::blocking::Client) -> Result<String, String>;
+
+    /// Parse a payload from `fetch` into canonical readings
+    fn parse(&self, payload: &str) -> Result<Vec<CanonicalReading>, String>;
+
+    /// Where readings are stored
+    fn destination(&self) -> Destination {
+        DEFAULT_DESTINATION
+    }
+}
+
+/// Builds an unconfigured plugin instance named `name`
+pub type PluginFactory = fn(name: &str) -> Box<dyn DataSourcePlugin>;
+
+/// `[[plugins]]` entry in flomon.toml
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginConfig {
+    /// Registered plugin kind, e.g. "json_http"
+    pub kind: String,
+    /// Instance name, unique across plugins
+    pub name: String,
+    #[serde(default = "empty_settings")]
+    pub settings: toml::Value,
+}
+
+fn empty_settings() -> toml::Value {
+    toml::Value::Table(toml::value::Table::new())
+}
+
+/// Plugin kinds available to `[[plugins]]`
+pub struct PluginRegistry {
+    factories: BTreeMap<String, PluginFactory>,
+}
+
+impl PluginRegistry {
+    /// Registry with no plugins
+    pub fn empty() -> Self {
+        Self { factories: BTreeMap::new() }
+    }
+
+    /// Registry with the plugins shipped in this crate
+    pub fn builtin() -> Self {
+        let mut registry = Self::empty();
+        registry.register("json_http", |name| Box::new(JsonHttpPlugin::new(name)));
+        registry
+    }
+
+    /// Add (or replace) a plugin kind
+    pub fn register(&mut self, kind: &str, factory: PluginFactory) {
+        self.factories.insert(kind.to_string(), factory);
+    }
+
+    pub fn kinds(&self) -> Vec<&str> {
+        self.factories.keys().map(String::as_str).collect()
+    }
+
+    /// Create and configure a plugin for every entry
+    pub fn instantiate(&self, configs: &[PluginConfig]) -> Result<Vec<Box<dyn DataSourcePlugin>>, String> {
+        let mut names = HashSet::new();
+        configs.iter().map(|config| {
+            if !names.insert(config.name.as_str()) {
+                return Err(format!("duplicate [[plugins]] name {}", config.name));
+            }
+            let factory = self.factories.get(&config.kind).ok_or_else(|| format!(
+                "plugin {} has unknown kind {:?} (known: {})",
+                config.name, config.kind, self.kinds().join(", ")))?;
+            let mut plugin = factory(&config.name);
+            plugin.configure(&config.settings)
+                .map_err(|e| format!("plugin {}: {}", config.name, e))?;
+            plugin.destination().qualified()
+                .map_err(|e| format!("plugin {}: {}", config.name, e))?;
+            Ok(plugin)
+        }).collect()
+    }
+}
+
+/// Insert readings into the plugin's destination; returns rows added
+pub fn store(client: &mut Client, plugin: &dyn DataSourcePlugin, readings: &[CanonicalReading]) -> Result<usize, String> {
+    store_readings(client, plugin.destination(), plugin.name(), readings)
+}
+
+/// Insert readings tagged `source` into `destination`; returns rows added
+pub fn store_readings(
+    client: &mut Client,
+    destination: Destination,
+    source: &str,
+    readings: &[CanonicalReading],
+) -> Result<usize, String> {
+    let table = destination.qualified()?;
+    let sql = format!(
+        "INSERT INTO {} (source, site_id, parameter, reading_time, value, unit)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (source, site_id, parameter, reading_time) DO NOTHING",
+        table);
+    let mut inserted = 0;
+    for r in readings {
+        inserted += client.execute(
+            &sql,
+            &[&source, &r.site_id, &r.parameter, &r.reading_time, &r.value, &r.unit],
+        ).map_err(|e| format!("Failed to insert {} reading for {}: {}", source, r.site_id, e))?;
+    }
+    Ok(inserted as usize)
+}
+
+// ---------------------------------------------------------------------------
+// json_http: a JSON array of readings at a URL
+// ---------------------------------------------------------------------------
+
+/// `[plugins.settings]` for `json_http`. Fields are JSON pointers relative
+/// to each item, without the leading slash ("decoded/stage_ft").
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonHttpSettings {
+    url: String,
+    site_id: String,
+    parameter: String,
+    unit: String,
+    /// JSON pointer to the array of items ("" when the body is the array)
+    #[serde(default)]
+    items: String,
+    time_field: String,
+    value_field: String,
+}
+
+/// Polls a URL that returns a JSON array of timestamped values, such as a
+/// LoRa network server's uplink history
+pub struct JsonHttpPlugin {
+    name: String,
+    settings: Option<JsonHttpSettings>,
+}
+
+impl JsonHttpPlugin {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), settings: None }
+    }
+
+    fn settings(&self) -> Result<&JsonHttpSettings, String> {
+        self.settings.as_ref().ok_or_else(|| format!("plugin {} is not configured", self.name))
+    }
+}
+
+impl DataSourcePlugin for JsonHttpPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn configure(&mut self, settings: &toml::Value) -> Result<(), String> {
+        let settings: JsonHttpSettings = settings.clone().try_into().map_err(|e| format!("{}", e))?;
+        if !(settings.url.starts_with("https://") || settings.url.starts_with("http://")) {
+            return Err(format!("url must be http(s) (got {:?})", settings.url));
+        }
+        self.settings = Some(settings);
+        Ok(())
+    }
+
+    fn fetch(&mut self, http: &reqwest::blocking::Client) -> Result<String, String> {
+        let url = &self.settings()?.url;
+        http.get(url)
+            .send()
+            .and_then(|r| r.error_for_status())
+            .and_then(|r| r.text())
+            .map_err(|e| format!("Failed to fetch {}: {}", url, e))
+    }
+
+    fn parse(&self, payload: &str) -> Result<Vec<CanonicalReading>, String> {
+        let settings = self.settings()?;
+        let body: Value = serde_json::from_str(payload).map_err(|e| format!("Invalid JSON: {}", e))?;
+        let items = body.pointer(&settings.items)
+            .and_then(Value::as_array)
+            .ok_or_else(|| format!("No array at {:?}", settings.items))?;
+
+        let pointer = |field: &str| format!("/{}", field.trim_start_matches('/'));
+        let (time_field, value_field) = (pointer(&settings.time_field), pointer(&settings.value_field));
+        items.iter().enumerate().map(|(i, item)| {
+            let time = item.pointer(&time_field).and_then(Value::as_str)
+                .ok_or_else(|| format!("Item {} has no {}", i, settings.time_field))?;
+            let reading_time = DateTime::parse_from_rfc3339(time)
+                .map_err(|e| format!("Item {} time {:?}: {}", i, time, e))?
+                .with_timezone(&Utc);
+            let value = match item.pointer(&value_field) {
+                Some(Value::Number(n)) => n.as_f64(),
+                Some(Value::String(s)) => s.trim().parse().ok(),
+                _ => None,
+            }.ok_or_else(|| format!("Item {} has no numeric {}", i, settings.value_field))?;
+            Ok(CanonicalReading {
+                site_id: settings.site_id.clone(),
+                parameter: settings.parameter.clone(),
+                reading_time,
+                value,
+                unit: settings.unit.clone(),
+            })
+        }).collect()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marina_config() -> PluginConfig {
+        toml::from_str(r#"
+            kind = "json_http"
+            name = "marina-lora"
+            [settings]
+            url = "https://gateway.example.org/uplinks"
+            site_id = "MARINA-DOCK-1"
+            parameter = "stage"
+            unit = "ft"
+            items = "/uplinks"
+            time_field = "received_at"
+            value_field = "decoded/stage_ft"
+        "#).unwrap()
+    }
+
+    #[test]
+    fn test_json_http_parses_nested_fields() {
+        let plugins = PluginRegistry::builtin().instantiate(&[marina_config()]).unwrap();
+        let payload = r#"{"uplinks": [
+            {"received_at": "2024-05-01T12:00:00Z", "decoded": {"stage_ft": 18.42}},
+            {"received_at": "2024-05-01T07:15:00-05:00", "decoded": {"stage_ft": "18.5"}}
+        ]}"#;
+        let readings = plugins[0].parse(payload).unwrap();
+        assert_eq!(readings.len(), 2);
+        assert_eq!(readings[0].site_id, "MARINA-DOCK-1");
+        assert_eq!(readings[0].value, 18.42);
+        assert_eq!(readings[1].reading_time.to_rfc3339(), "2024-05-01T12:15:00+00:00");
+        assert_eq!(readings[1].value, 18.5);
+        assert_eq!(plugins[0].destination().qualified().unwrap(), "community.readings");
+
+        let missing = r#"{"uplinks": [{"received_at": "2024-05-01T12:00:00Z", "decoded": {}}]}"#;
+        assert!(plugins[0].parse(missing).unwrap_err().contains("decoded/stage_ft"));
+    }
+
+    #[test]
+    fn test_registry_rejects_unknown_kind_duplicates_and_bad_settings() {
+        let registry = PluginRegistry::builtin();
+        let mut unknown = marina_config();
+        unknown.kind = "modbus".to_string();
+        assert!(registry.instantiate(&[unknown]).err().unwrap().contains("known: json_http"));
+
+        assert!(registry.instantiate(&[marina_config(), marina_config()]).err().unwrap().contains("duplicate"));
+
+        let mut bad = marina_config();
+        bad.settings.as_table_mut().unwrap().remove("site_id");
+        assert!(registry.instantiate(&[bad]).is_err());
+    }
+
+    struct Fixed;
+
+    impl DataSourcePlugin for Fixed {
+        fn name(&self) -> &str { "fixed" }
+        fn configure(&mut self, _: &toml::Value) -> Result<(), String> { Ok(()) }
+        fn fetch(&mut self, _: &reqwest::blocking::Client) -> Result<String, String> { Ok(String::new()) }
+        fn parse(&self, _: &str) -> Result<Vec<CanonicalReading>, String> { Ok(Vec::new()) }
+        fn destination(&self) -> Destination {
+            Destination { schema: "community", table: "readings; DROP TABLE x" }
+        }
+    }
+
+    #[test]
+    fn test_registered_plugin_destination_is_checked() {
+        let mut registry = PluginRegistry::empty();
+        registry.register("fixed", |_| Box::new(Fixed));
+        let config = PluginConfig { kind: "fixed".to_string(), name: "f".to_string(), settings: empty_settings() };
+        assert!(registry.instantiate(&[config]).err().unwrap().contains("Invalid destination"));
+    }
+}
@@ -0,0 +1,283 @@
+//! USGS stage-discharge rating tables.
+//!
+//! A station's rating is the curve USGS pushes stage through to report
+//! discharge. With it, discharge can be turned back into stage, which is
+//! what the flood thresholds are written in: a station that reports only
+//! discharge (the Romeoville canal, or any gauge whose stage sensor is
+//! out) still gets a stage estimate for alerting.
+//!
+//! Tables come from the NWIS rating service as expanded, shift-adjusted
+//! RDB (`file_type=exsa`): stage at 0.01 ft steps with the current shifts
+//! applied:
+//! https://waterdata.usgs.gov/nwisweb/get_ratings?site_no={site}&file_type=exsa
+//!
+//! Key fields:
+//! - `# //RATING ID="..."` comment: rating number
+//! - INDEP: stage, ft
+//! - SHIFT: shift applied at that stage, ft (already in DEP)
+//! - DEP: discharge, cfs
+//!
+//! The daemon fetches each discharge station's table every `REFRESH_HOURS`
+//! and caches it in `usgs_raw.rating_tables`, which it falls back to when
+//! the service is down. Conversions interpolate linearly between table
+//! rows and return `None` outside the table's range rather than
+//! extrapolate.
+
+use chrono::{DateTime, Utc};
+use postgres::Client;
+use std::collections::HashMap;
+
+use crate::model::GaugeReading;
+use crate::ingest::{base_urls, retry};
+use crate::logging::DataSource;
+use crate::stations::{PARAM_DISCHARGE, PARAM_STAGE};
+
+/// How often the daemon re-fetches ratings; shifts change after field
+/// measurements, every six to eight weeks
+pub const REFRESH_HOURS: i64 = 24;
+
+/// Qualifier on stage estimated from discharge (NWIS "e": estimated)
+pub const ESTIMATED_QUALIFIER: &str = "e";
+
+/// One station's stage-discharge table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rating {
+    pub site_code: String,
+    /// USGS rating number, when the table names one
+    pub rating_id: Option<String>,
+    /// (stage ft, discharge cfs), stage ascending, discharge non-decreasing
+    points: Vec<(f64, f64)>,
+}
+
+impl Rating {
+    /// Rating from table rows in any order. Needs two distinct stages and
+    /// discharge that never falls as stage rises.
+    pub fn new(site_code: &str, rating_id: Option<String>, mut points: Vec<(f64, f64)>) -> Result<Self, String> {
+        points.retain(|(stage, q)| stage.is_finite() && q.is_finite());
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        points.dedup_by(|b, a| a.0 == b.0);
+        if points.len() < 2 {
+            return Err(format!("Rating for {} has fewer than two points", site_code));
+        }
+        if let Some(w) = points.windows(2).find(|w| w[1].1 < w[0].1) {
+            return Err(format!(
+                "Rating for {} falls from {} to {} cfs between {} and {} ft",
+                site_code, w[0].1, w[1].1, w[0].0, w[1].0));
+        }
+        Ok(Self { site_code: site_code.to_string(), rating_id, points })
+    }
+
+    pub fn points(&self) -> &[(f64, f64)] {
+        &self.points
+    }
+
+    /// Rated discharge at `stage_ft`; `None` outside the table
+    pub fn stage_to_discharge(&self, stage_ft: f64) -> Option<f64> {
+        interpolate(&self.points, stage_ft, |p| p.0, |p| p.1)
+    }
+
+    /// Stage at which the rating gives `discharge_cfs`; `None` outside the
+    /// table. Where the table is flat (same discharge over a range of
+    /// stages) the lowest such stage is returned.
+    pub fn discharge_to_stage(&self, discharge_cfs: f64) -> Option<f64> {
+        interpolate(&self.points, discharge_cfs, |p| p.1, |p| p.0)
+    }
+}
+
+/// Linear interpolation of `to` at `x` along `from` (non-decreasing)
+fn interpolate(
+    points: &[(f64, f64)],
+    x: f64,
+    from: impl Fn(&(f64, f64)) -> f64,
+    to: impl Fn(&(f64, f64)) -> f64,
+) -> Option<f64> {
+    let first = points.first()?;
+    let last = points.last()?;
+    if !x.is_finite() || x < from(first) || x > from(last) {
+        return None;
+    }
+    // First row at or past x; the table is small enough to scan
+    let i = points.iter().position(|p| from(p) >= x)?;
+    if i == 0 || from(&points[i]) == x {
+        return Some(to(&points[i]));
+    }
+    let (a, b) = (&points[i - 1], &points[i]);
+    let t = (x - from(a)) / (from(b) - from(a));
+    Some(to(a) + t * (to(b) - to(a)))
+}
+
+pub fn rating_url(site_code: &str) -> String {
+    format!("{}?site_no={}&file_type=exsa", base_urls::get().usgs_ratings, site_code)
+}
+
+/// Parse an expanded rating RDB. Rows without a stage or discharge are
+/// skipped; a missing INDEP or DEP column is an error.
+pub fn parse_rdb(site_code: &str, rdb_text: &str) -> Result<Rating, String> {
+    let rating_id = rdb_text.lines()
+        .filter_map(|line| line.trim().strip_prefix("#"))
+        .filter_map(|line| line.trim().strip_prefix("//RATING "))
+        .find_map(|line| line.split("ID=\"").nth(1)?.split('"').next().map(str::to_string));
+
+    let mut data_lines = rdb_text.lines()
+        .filter(|line| !line.trim().starts_with('#') && !line.trim().is_empty());
+    let header_line = data_lines.next()
+        .ok_or("No header line found in rating RDB")?;
+    let col_map: HashMap<&str, usize> = header_line.split('\t')
+        .enumerate()
+        .map(|(idx, name)| (name.trim(), idx))
+        .collect();
+    let column = |name: &str| col_map.get(name).copied().ok_or_else(|| format!("Missing {} column", name));
+    let (stage_idx, discharge_idx) = (column("INDEP")?, column("DEP")?);
+
+    // Column width/type line
+    data_lines.next().ok_or("No format line found in rating RDB")?;
+
+    let points = data_lines
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let number = |idx: usize| fields.get(idx).and_then(|f| f.trim().parse::<f64>().ok());
+            Some((number(stage_idx)?, number(discharge_idx)?))
+        })
+        .collect();
+    Rating::new(site_code, rating_id, points)
+}
+
+/// Fetch and parse one site's current rating
+pub fn fetch(site_code: &str) -> Result<Rating, String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let body = retry::get_text_blocking(&client, DataSource::Usgs, &rating_url(site_code), false)
+        .map_err(|e| format!("Failed to fetch rating for {}: {}", site_code, e))?;
+    parse_rdb(site_code, &body)
+}
+
+/// Replace the cached table for the rating's site
+pub fn store(client: &mut Client, rating: &Rating, fetched_at: DateTime<Utc>) -> Result<(), String> {
+    let (stages, discharges): (Vec<f64>, Vec<f64>) = rating.points.iter().copied().unzip();
+    client.execute(
+        "INSERT INTO usgs_raw.rating_tables (site_code, rating_id, stage_ft, discharge_cfs, fetched_at)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (site_code) DO UPDATE SET
+            rating_id = EXCLUDED.rating_id,
+            stage_ft = EXCLUDED.stage_ft,
+            discharge_cfs = EXCLUDED.discharge_cfs,
+            fetched_at = EXCLUDED.fetched_at",
+        &[&rating.site_code, &rating.rating_id, &stages, &discharges, &fetched_at],
+    ).map_err(|e| format!("Failed to store rating for {}: {}", rating.site_code, e))?;
+    Ok(())
+}
+
+/// The cached table for `site_code`, if one was stored
+pub fn load(client: &mut Client, site_code: &str) -> Result<Option<Rating>, String> {
+    let row = client.query_opt(
+        "SELECT rating_id, stage_ft, discharge_cfs FROM usgs_raw.rating_tables WHERE site_code = $1",
+        &[&site_code],
+    ).map_err(|e| format!("Failed to load rating for {}: {}", site_code, e))?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let stages: Vec<f64> = row.get(1);
+    let discharges: Vec<f64> = row.get(2);
+    Rating::new(site_code, row.get(0), stages.into_iter().zip(discharges).collect()).map(Some)
+}
+
+/// Stage estimated through `rating` from the latest discharge in
+/// `readings`, for a batch that has no stage of its own
+pub fn estimate_stage(rating: &Rating, readings: &[GaugeReading]) -> Option<GaugeReading> {
+    if readings.iter().any(|r| r.parameter_code == PARAM_STAGE) {
+        return None;
+    }
+    let discharge = readings.iter()
+        .filter(|r| r.parameter_code == PARAM_DISCHARGE)
+        .max_by_key(|r| r.datetime)?;
+    let stage = rating.discharge_to_stage(discharge.value)?;
+    Some(GaugeReading {
+        parameter_code: PARAM_STAGE.to_string(),
+        unit: "ft".to_string(),
+        value: (stage * 100.0).round() / 100.0,
+        qualifier: ESTIMATED_QUALIFIER.to_string(),
+        original_unit: None,
+        ..discharge.clone()
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+# //UNITED STATES GEOLOGICAL SURVEY       https://water.usgs.gov/
+# //STATION AGENCY=\"USGS \" NUMBER=\"05536890       \"
+# //RATING ID=\"14.0\" TYPE=\"STGQ\" NAME=\"stage-discharge\" AGING=\"Working\"
+INDEP\tSHIFT\tDEP\tSTOR
+16N\t16N\t16N\t1S
+10.00\t0.05\t1200.0\t*
+10.01\t0.05\t1210.0
+10.02\t0.05\t1220.0
+10.03\t0.05\t
+12.00\t0.04\t3000.0
+";
+
+    fn reading(parameter_code: &str, value: f64, datetime: &str) -> GaugeReading {
+        GaugeReading {
+            site_code: "05536890".to_string(),
+            site_name: "Chicago Sanitary & Ship Canal at Romeoville, IL".to_string(),
+            parameter_code: parameter_code.to_string(),
+            unit: if parameter_code == PARAM_STAGE { "ft" } else { "ft3/s" }.to_string(),
+            value,
+            datetime: DateTime::parse_from_rfc3339(datetime).unwrap(),
+            qualifier: "P".to_string(),
+            original_unit: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_expanded_rating_rdb() {
+        let rating = parse_rdb("05536890", SAMPLE).unwrap();
+        assert_eq!(rating.rating_id.as_deref(), Some("14.0"));
+        assert_eq!(rating.points().len(), 4);
+        assert_eq!(rating.points()[3], (12.0, 3000.0));
+        assert!(parse_rdb("05536890", "INDEP\tSHIFT\n16N\t16N\n10.0\t0.0\n").is_err());
+    }
+
+    #[test]
+    fn test_conversions_interpolate_within_the_table() {
+        let rating = parse_rdb("05536890", SAMPLE).unwrap();
+        assert_eq!(rating.stage_to_discharge(10.01), Some(1210.0));
+        // Halfway between the 10.02 and 12.00 ft rows
+        let q = rating.stage_to_discharge(11.01).unwrap();
+        assert!((q - 2110.0).abs() < 1e-6);
+        let stage = rating.discharge_to_stage(q).unwrap();
+        assert!((stage - 11.01).abs() < 1e-9);
+        assert_eq!(rating.discharge_to_stage(1200.0), Some(10.0));
+        assert_eq!(rating.stage_to_discharge(9.99), None);
+        assert_eq!(rating.discharge_to_stage(3500.0), None);
+
+        let flat = Rating::new("x", None, vec![(1.0, 0.0), (2.0, 0.0), (3.0, 50.0)]).unwrap();
+        assert_eq!(flat.discharge_to_stage(0.0), Some(1.0));
+        assert!(Rating::new("x", None, vec![(1.0, 10.0), (2.0, 5.0)]).is_err());
+    }
+
+    #[test]
+    fn test_estimate_stage_from_latest_discharge() {
+        let rating = parse_rdb("05536890", SAMPLE).unwrap();
+        let readings = [
+            reading(PARAM_DISCHARGE, 1200.0, "2026-10-16T11:00:00-05:00"),
+            reading(PARAM_DISCHARGE, 2110.0, "2026-10-16T11:15:00-05:00"),
+        ];
+        let estimate = estimate_stage(&rating, &readings).unwrap();
+        assert_eq!(estimate.parameter_code, PARAM_STAGE);
+        assert_eq!(estimate.value, 11.01);
+        assert_eq!(estimate.qualifier, ESTIMATED_QUALIFIER);
+        assert_eq!(estimate.datetime.to_rfc3339(), "2026-10-16T11:15:00-05:00");
+
+        let with_stage = [readings[1].clone(), reading(PARAM_STAGE, 10.5, "2026-10-16T11:15:00-05:00")];
+        assert!(estimate_stage(&rating, &with_stage).is_none());
+    }
+}
@@ -0,0 +1,326 @@
+//! Retry with exponential backoff and jitter for the ingest HTTP clients.
+//!
+//! USGS and IEM return the odd 503 or drop a connection under load; without
+//! a retry one such failure cost the station its whole cycle. Every ingest
+//! request (the poll cycle in `fetch`, the blocking USGS, CWMS and IEM
+//! clients used by backfill and the CLI) goes through `get_text` /
+//! `get_text_blocking`, which repeat a request while its error is retryable
+//! (`IngestError::is_retryable`: transport failures, timeouts, 408/429
+//! and 5xx):
+//!
+//! ```text
+//! attempt 1 fails -> wait ~initial_delay
+//! attempt 2 fails -> wait ~2 x initial_delay   (capped at max_delay)
+//! ...             -> give up once the next wait would pass max_elapsed
+//! ```
+//!
+//! Each wait is shortened by a random share of up to `jitter`, so stations
+//! that failed together don't all come back in the same instant. The
+//! random unit comes from the caller (`retry_blocking` / `retry` take it as
+//! a parameter, `get_text*` pass `random_unit`), so tests can fix it. A 429
+//! with a `Retry-After` waits at least that long, or gives up if that
+//! would pass `max_elapsed`. Permanent
+//! errors (4xx, no data, parse errors) are returned at once.
+//!
+//! The policy comes from the `retry_*` settings of `[daemon]` in flomon.toml
+//! and is set once at startup (`set_policy`). Sources in a maintenance
+//! window are fetched with `RetryPolicy::none()`.
+
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::logging::{self, DataSource};
+use crate::model::IngestError;
+
+/// Default first wait, milliseconds
+pub const DEFAULT_INITIAL_DELAY_MS: u64 = 500;
+
+/// Default longest single wait, milliseconds
+pub const DEFAULT_MAX_DELAY_MS: u64 = 4_000;
+
+/// Default time after which no further attempt is started, seconds. Kept
+/// under the per-source fetch timeouts so a retried station still reports
+/// its own error rather than a timeout.
+pub const DEFAULT_MAX_ELAPSED_SECS: u64 = 10;
+
+/// Default largest share of a wait removed at random
+pub const DEFAULT_JITTER: f64 = 0.5;
+
+/// Backoff schedule and give-up point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub max_elapsed: Duration,
+    /// 0.0 (exact doubling) to 1.0 (anywhere from zero to the full wait)
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(DEFAULT_INITIAL_DELAY_MS),
+            max_delay: Duration::from_millis(DEFAULT_MAX_DELAY_MS),
+            max_elapsed: Duration::from_secs(DEFAULT_MAX_ELAPSED_SECS),
+            jitter: DEFAULT_JITTER,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A single attempt
+    pub fn none() -> Self {
+        Self { max_elapsed: Duration::ZERO, ..Self::default() }
+    }
+
+    /// Wait after failed attempt `attempt` (1-based); `unit` in `[0, 1)`
+    /// picks the jitter
+    pub fn delay(&self, attempt: u32, unit: f64) -> Duration {
+        let doubled = self.initial_delay.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        doubled.min(self.max_delay).mul_f64(1.0 - self.jitter.clamp(0.0, 1.0) * unit)
+    }
+
+    /// The wait before the next attempt, or None to give up; `unit` picks
+    /// the jitter as in `delay`
+    fn next_wait(&self, err: &IngestError, attempt: u32, elapsed: Duration, unit: f64) -> Option<Duration> {
+        if !err.is_retryable() {
+            return None;
+        }
+        let mut wait = self.delay(attempt, unit);
+        if let IngestError::RateLimited { retry_after: Some(asked) } = err {
+            wait = wait.max(*asked);
+        }
+        (elapsed + wait <= self.max_elapsed).then_some(wait)
+    }
+}
+
+static POLICY: OnceLock<RetryPolicy> = OnceLock::new();
+
+/// Set the process-wide policy (first call wins)
+pub fn set_policy(policy: RetryPolicy) {
+    let _ = POLICY.set(policy);
+}
+
+/// The process-wide policy, the defaults if none was set
+pub fn policy() -> RetryPolicy {
+    POLICY.get().copied().unwrap_or_default()
+}
+
+/// Uniform in `[0, 1)`, from the randomly keyed std hasher
+pub fn random_unit() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    hasher.write_u128(now.as_nanos());
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn log_retry(source: DataSource, url: &str, err: &IngestError, attempt: u32, wait: Duration) {
+    logging::debug(source, None, &format!(
+        "Attempt {} of {} failed ({}); retrying in {} ms", attempt, url, err, wait.as_millis()));
+}
+
+/// Run `op` until it succeeds, fails permanently or `policy` gives up;
+/// returns the last error. `unit` is called once per wait for the jitter
+/// (`random_unit` outside tests).
+pub fn retry_blocking<T>(
+    policy: RetryPolicy,
+    source: DataSource,
+    url: &str,
+    mut unit: impl FnMut() -> f64,
+    mut op: impl FnMut() -> Result<T, IngestError>,
+) -> Result<T, IngestError> {
+    let started = Instant::now();
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(err) => match policy.next_wait(&err, attempt, started.elapsed(), unit()) {
+                Some(wait) => {
+                    log_retry(source, url, &err, attempt, wait);
+                    std::thread::sleep(wait);
+                    attempt += 1;
+                }
+                None => return Err(err),
+            },
+        }
+    }
+}
+
+/// `retry_blocking` for async operations
+pub async fn retry<T, F, Fut>(
+    policy: RetryPolicy,
+    source: DataSource,
+    url: &str,
+    mut unit: impl FnMut() -> f64,
+    mut op: F,
+) -> Result<T, IngestError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, IngestError>>,
+{
+    let started = Instant::now();
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(err) => match policy.next_wait(&err, attempt, started.elapsed(), unit()) {
+                Some(wait) => {
+                    log_retry(source, url, &err, attempt, wait);
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                }
+                None => return Err(err),
+            },
+        }
+    }
+}
+
+fn transport(e: reqwest::Error) -> IngestError {
+    if e.is_timeout() {
+        IngestError::Timeout(e.to_string())
+    } else {
+        IngestError::Transport(e.to_string())
+    }
+}
+
+/// The error for a non-2xx response; 429 carries its `Retry-After` seconds
+fn status_error(status: reqwest::StatusCode, headers: &reqwest::header::HeaderMap) -> IngestError {
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = headers.get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok())
+            .map(Duration::from_secs);
+        return IngestError::RateLimited { retry_after };
+    }
+    IngestError::Http(status.as_u16())
+}
+
+/// GET `url` with the process-wide policy and return the body of a
+/// successful response
+pub fn get_text_blocking(
+    client: &reqwest::blocking::Client,
+    source: DataSource,
+    url: &str,
+    accept_json: bool,
+) -> Result<String, IngestError> {
+    retry_blocking(policy(), source, url, random_unit, || {
+        let mut request = client.get(url);
+        if accept_json {
+            request = request.header("Accept", "application/json");
+        }
+        let response = request.send().map_err(transport)?;
+        if !response.status().is_success() {
+            return Err(status_error(response.status(), response.headers()));
+        }
+        response.text().map_err(transport)
+    })
+}
+
+/// Async `get_text_blocking` under an explicit policy
+pub async fn get_text(
+    client: &reqwest::Client,
+    policy: RetryPolicy,
+    source: DataSource,
+    url: &str,
+    accept_json: bool,
+) -> Result<String, IngestError> {
+    retry(policy, source, url, random_unit, || async {
+        let mut request = client.get(url);
+        if accept_json {
+            request = request.header("Accept", "application/json");
+        }
+        let response = request.send().await.map_err(transport)?;
+        if !response.status().is_success() {
+            return Err(status_error(response.status(), response.headers()));
+        }
+        response.text().await.map_err(transport)
+    }).await
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fast() -> RetryPolicy {
+        RetryPolicy {
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(4),
+            max_elapsed: Duration::from_millis(200),
+            jitter: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_delay_doubles_to_cap_and_jitter_shortens() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(1, 0.0), Duration::from_millis(500));
+        assert_eq!(policy.delay(3, 0.0), Duration::from_millis(2000));
+        assert_eq!(policy.delay(30, 0.0), Duration::from_millis(4000));
+        assert_eq!(policy.delay(1, 0.99), Duration::from_millis(500).mul_f64(1.0 - 0.5 * 0.99));
+        assert!((0..100).map(|_| random_unit()).all(|u| (0.0..1.0).contains(&u)));
+    }
+
+    #[test]
+    fn test_retryable_errors_are_retried_until_success() {
+        let mut calls = 0;
+        let mut units = 0;
+        let result = retry_blocking(fast(), DataSource::Usgs, "test", || { units += 1; 0.5 }, || {
+            calls += 1;
+            if calls < 3 { Err(IngestError::Http(503)) } else { Ok(calls) }
+        });
+        assert_eq!(result, Ok(3));
+        assert_eq!(units, 2, "one jitter draw per wait");
+    }
+
+    #[test]
+    fn test_permanent_errors_and_none_policy_fail_at_once() {
+        let mut calls = 0;
+        let result: Result<(), _> = retry_blocking(fast(), DataSource::Usgs, "test", || 0.0, || {
+            calls += 1;
+            Err(IngestError::Http(404))
+        });
+        assert_eq!((result, calls), (Err(IngestError::Http(404)), 1));
+
+        let mut calls = 0;
+        let result: Result<(), _> = retry_blocking(RetryPolicy::none(), DataSource::Asos, "test", || 0.0, || {
+            calls += 1;
+            Err(IngestError::Transport("connection reset".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_gives_up_after_max_elapsed() {
+        // No jitter: waits of 1, 2, 4 and 4 ms fit in 12 ms, the next does not
+        let policy = RetryPolicy { max_elapsed: Duration::from_millis(12), ..fast() };
+        let mut calls = 0;
+        let started = Instant::now();
+        let result: Result<(), _> = retry_blocking(policy, DataSource::Cwms, "test", || 0.0, || {
+            calls += 1;
+            Err(IngestError::Http(502))
+        });
+        assert_eq!(result, Err(IngestError::Http(502)));
+        assert!((2..=5).contains(&calls), "{} attempts", calls);
+        assert!(started.elapsed() < Duration::from_millis(100));
+        let err = IngestError::Http(502);
+        assert_eq!(policy.next_wait(&err, 4, Duration::from_millis(7), 0.0), Some(Duration::from_millis(4)));
+        assert_eq!(policy.next_wait(&err, 5, Duration::from_millis(11), 0.0), None);
+
+        // A Retry-After beyond max_elapsed is not waited out
+        let mut calls = 0;
+        let throttled = IngestError::RateLimited { retry_after: Some(Duration::from_secs(30)) };
+        let result: Result<(), _> = retry_blocking(fast(), DataSource::Asos, "test", || 0.0, || {
+            calls += 1;
+            Err(IngestError::RateLimited { retry_after: Some(Duration::from_secs(30)) })
+        });
+        assert_eq!((result, calls), (Err(throttled), 1));
+    }
+}
@@ -1,15 +1,18 @@
-/// build_iv_url, parse_iv_response + those tests
-/// When we add NWS or MRMS data sources later, they each get their own file under ingest/ rather than bloating this one.
-/// /// USGS NWIS Instantaneous Values (IV) API client.
-///
-/// Handles URL construction and JSON response parsing for the USGS Water
-/// Services IV endpoint:
-///   https://waterservices.usgs.gov/nwis/iv/
-///
-/// The IV service returns WaterML rendered as JSON. See `fixtures.rs` for
-/// annotated examples of the response structure.
-
-use crate::model::{GaugeReading, NwisError};
+//! build_iv_url, parse_iv_response + those tests
+//! When we add NWS or MRMS data sources later, they each get their own file under ingest/ rather than bloating this one.
+//! /// USGS NWIS Instantaneous Values (IV) API client.
+//!
+//! Handles URL construction and JSON response parsing for the USGS Water
+//! Services IV endpoint:
+//!   https://waterservices.usgs.gov/nwis/iv/
+//!
+//! The IV service returns WaterML rendered as JSON. See `fixtures.rs` for
+//! annotated examples of the response structure.
+
+use crate::ingest::base_urls;
+use crate::units;
+use crate::model::{GaugeReading, IngestError};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Utc};
 use serde::Deserialize;
 
 // ---------------------------------------------------------------------------
@@ -85,9 +88,6 @@ struct ValueEntry {
 // URL construction
 // ---------------------------------------------------------------------------
 
-const IV_BASE_URL: &str = "https://waterservices.usgs.gov/nwis/iv/";
-const DV_BASE_URL: &str = "https://waterservices.usgs.gov/nwis/dv/";
-
 /// Builds a USGS IV API URL for the given site codes, parameter codes,
 /// and ISO 8601 period (e.g. `"PT1H"` for the past hour, `"PT3H"` for
 /// the past three hours).
@@ -108,17 +108,40 @@ const DV_BASE_URL: &str = "https://waterservices.usgs.gov/nwis/dv/";
 /// );
 /// ```
 pub fn build_iv_url(sites: &[&str], param_codes: &[&str], period: &str) -> String {
+    iv_url(sites, param_codes, &format!("period={}", period), "json")
+}
+
+/// Same request as `build_iv_url`, rendered as tab-delimited RDB.
+/// Parse the response with `usgs_rdb::parse_iv_rdb`.
+pub fn build_iv_rdb_url(sites: &[&str], param_codes: &[&str], period: &str) -> String {
+    iv_url(sites, param_codes, &format!("period={}", period), "rdb")
+}
+
+/// Instantaneous values between two dates (YYYY-MM-DD, both inclusive)
+/// instead of a period back from now. Used by the chunked historical
+/// backfill; parse with `parse_iv_response_all`.
+pub fn build_iv_range_url(sites: &[&str], param_codes: &[&str], start_date: &str, end_date: &str) -> String {
+    iv_url(sites, param_codes, &format!("startDT={}&endDT={}", start_date, end_date), "json")
+}
+
+/// Same request as `build_iv_range_url`, rendered as tab-delimited RDB.
+/// Parse the response with `usgs_rdb::parse_iv_rdb_all`.
+pub fn build_iv_range_rdb_url(sites: &[&str], param_codes: &[&str], start_date: &str, end_date: &str) -> String {
+    iv_url(sites, param_codes, &format!("startDT={}&endDT={}", start_date, end_date), "rdb")
+}
+
+/// `window` is the time selection query: `period=...` or `startDT=...&endDT=...`
+fn iv_url(sites: &[&str], param_codes: &[&str], window: &str, format_param: &str) -> String {
     let sites_param = sites.join(",");
     let params_param = param_codes.join(",");
-    let format_param = "json";
     let site_status = "active";
     
     format!(
-        "{}?sites={}&parameterCd={}&period={}&format={}&siteStatus={}",
-        IV_BASE_URL,
+        "{}?sites={}&parameterCd={}&{}&format={}&siteStatus={}",
+        base_urls::get().usgs_iv,
         sites_param,
         params_param,
-        period,
+        window,
         format_param,
         site_status
     )
@@ -148,17 +171,39 @@ pub fn build_dv_url(
     param_codes: &[&str],
     start_date: &str,
     end_date: &str,
+) -> String {
+    dv_url(sites, param_codes, start_date, end_date, "json")
+}
+
+/// Same request as `build_dv_url`, rendered as tab-delimited RDB.
+/// Parse the response with `usgs_rdb::parse_dv_rdb`.
+pub fn build_dv_rdb_url(
+    sites: &[&str],
+    param_codes: &[&str],
+    start_date: &str,
+    end_date: &str,
+) -> String {
+    dv_url(sites, param_codes, start_date, end_date, "rdb")
+}
+
+fn dv_url(
+    sites: &[&str],
+    param_codes: &[&str],
+    start_date: &str,
+    end_date: &str,
+    format_param: &str,
 ) -> String {
     let sites_param = sites.join(",");
     let params_param = param_codes.join(",");
     
     format!(
-        "{}?sites={}&parameterCd={}&startDT={}&endDT={}&format=json",
-        DV_BASE_URL,
+        "{}?sites={}&parameterCd={}&startDT={}&endDT={}&format={}",
+        base_urls::get().usgs_dv,
         sites_param,
         params_param,
         start_date,
-        end_date
+        end_date,
+        format_param
     )
 }
 
@@ -166,21 +211,48 @@ pub fn build_dv_url(
 // Response parsing
 // ---------------------------------------------------------------------------
 
+/// Every qualifier on a value, colon-joined as NWIS writes them in RDB
+/// ("A:e"); "P" when there are none. Interpreted by `quality`.
+pub(crate) fn qualifier_codes(qualifiers: &[String]) -> String {
+    if qualifiers.is_empty() {
+        "P".to_string()
+    } else {
+        qualifiers.join(":")
+    }
+}
+
+/// Parse a WaterML `dateTime`.
+///
+/// Instantaneous values carry an RFC3339 offset, which is kept; daily
+/// values are naive ("2020-01-01T00:00:00.000") or bare dates and are
+/// taken as UTC.
+pub(crate) fn parse_datetime(datetime: &str) -> Result<DateTime<FixedOffset>, IngestError> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(datetime) {
+        return Ok(dt);
+    }
+    let naive = NaiveDateTime::parse_from_str(datetime, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDate::parse_from_str(datetime, "%Y-%m-%d").map(|d| d.and_time(Default::default())))
+        .map_err(|e| IngestError::InvalidTimestamp(format!("'{}': {}", datetime, e)))?;
+    Ok(DateTime::<Utc>::from_naive_utc_and_offset(naive, Utc).fixed_offset())
+}
+
 /// Parses a USGS IV API JSON response body into a flat list of
 /// `GaugeReading`s, one per `timeSeries` entry that contains valid data.
 ///
 /// # Errors
-/// - `NwisError::ParseError` — malformed or unexpected JSON structure.
-/// - `NwisError::NoDataAvailable` — all `timeSeries` entries had either
+/// - `IngestError::Parse` — malformed or unexpected JSON structure.
+/// - `IngestError::InvalidTimestamp` — a value's `dateTime` is not a
+///   timestamp.
+/// - `IngestError::NoData` — all `timeSeries` entries had either
 ///   an empty `value` array or the USGS sentinel value (`-999999`).
-pub fn parse_iv_response(json: &str) -> Result<Vec<GaugeReading>, NwisError> {
+pub fn parse_iv_response(json: &str) -> Result<Vec<GaugeReading>, IngestError> {
     // Parse the JSON into our serde structs
     let response: IvResponse = serde_json::from_str(json)
-        .map_err(|e| NwisError::ParseError(format!("JSON deserialization failed: {}", e)))?;
+        .map_err(|e| IngestError::Parse(format!("JSON deserialization failed: {}", e)))?;
 
     // Check for empty timeSeries array
     if response.value.time_series.is_empty() {
-        return Err(NwisError::NoDataAvailable(
+        return Err(IngestError::NoData(
             "No timeSeries entries in response".to_string(),
         ));
     }
@@ -194,7 +266,7 @@ pub fn parse_iv_response(json: &str) -> Result<Vec<GaugeReading>, NwisError> {
             .source_info
             .site_code
             .first()
-            .ok_or_else(|| NwisError::ParseError("Missing siteCode".to_string()))?
+            .ok_or_else(|| IngestError::Parse("Missing siteCode".to_string()))?
             .value
             .clone();
 
@@ -204,18 +276,19 @@ pub fn parse_iv_response(json: &str) -> Result<Vec<GaugeReading>, NwisError> {
             .variable
             .variable_code
             .first()
-            .ok_or_else(|| NwisError::ParseError("Missing variableCode".to_string()))?
+            .ok_or_else(|| IngestError::Parse("Missing variableCode".to_string()))?
             .value
             .clone();
 
-        let unit = series.variable.unit.unit_code.clone();
+        let conversion = units::for_usgs(&parameter_code, &series.variable.unit.unit_code)
+            .map_err(IngestError::Parse)?;
         let no_data_value = series.variable.no_data_value;
 
         // Get the values array
         let values_wrapper = series
             .values
             .first()
-            .ok_or_else(|| NwisError::ParseError("Missing values array".to_string()))?;
+            .ok_or_else(|| IngestError::Parse("Missing values array".to_string()))?;
 
         // Check for empty value array
         if values_wrapper.value.is_empty() {
@@ -226,42 +299,38 @@ pub fn parse_iv_response(json: &str) -> Result<Vec<GaugeReading>, NwisError> {
         let latest = values_wrapper
             .value
             .last()
-            .ok_or_else(|| NwisError::ParseError("Empty value array".to_string()))?;
+            .ok_or_else(|| IngestError::Parse("Empty value array".to_string()))?;
 
         // Parse the value string to f64
         let value: f64 = latest
             .value
             .parse()
-            .map_err(|e| NwisError::ParseError(format!("Failed to parse value '{}': {}", latest.value, e)))?;
+            .map_err(|e| IngestError::Parse(format!("Failed to parse value '{}': {}", latest.value, e)))?;
 
         // Check for sentinel value
         if (value - no_data_value).abs() < 0.1 {
             continue; // Skip this series, try others
         }
 
-        // Get qualifier, defaulting to "P" if not present
-        let qualifier = latest
-            .qualifiers
-            .first()
-            .map(|s| s.as_str())
-            .unwrap_or("P")
-            .to_string();
+        // All qualifiers, defaulting to "P" if not present
+        let qualifier = qualifier_codes(&latest.qualifiers);
 
         // Create the GaugeReading
         readings.push(GaugeReading {
             site_code,
             site_name,
             parameter_code,
-            unit,
-            value,
-            datetime: latest.date_time.clone(),
+            value: conversion.apply(value),
+            unit: conversion.unit,
+            datetime: parse_datetime(&latest.date_time)?,
             qualifier,
+            original_unit: conversion.original_unit,
         });
     }
 
     // If we didn't collect any valid readings, return NoDataAvailable
     if readings.is_empty() {
-        return Err(NwisError::NoDataAvailable(
+        return Err(IngestError::NoData(
             "All timeSeries entries were empty or contained sentinel values".to_string(),
         ));
     }
@@ -272,12 +341,12 @@ pub fn parse_iv_response(json: &str) -> Result<Vec<GaugeReading>, NwisError> {
 /// Parses a USGS IV API JSON response into ALL readings (not just latest).
 /// Similar to parse_dv_response but for instantaneous values.
 /// Use this for backfilling gaps with high-resolution data.
-pub fn parse_iv_response_all(json: &str) -> Result<Vec<GaugeReading>, NwisError> {
+pub fn parse_iv_response_all(json: &str) -> Result<Vec<GaugeReading>, IngestError> {
     let response: IvResponse = serde_json::from_str(json)
-        .map_err(|e| NwisError::ParseError(format!("JSON deserialization failed: {}", e)))?;
+        .map_err(|e| IngestError::Parse(format!("JSON deserialization failed: {}", e)))?;
 
     if response.value.time_series.is_empty() {
-        return Err(NwisError::NoDataAvailable(
+        return Err(IngestError::NoData(
             "No timeSeries entries in response".to_string(),
         ));
     }
@@ -289,7 +358,7 @@ pub fn parse_iv_response_all(json: &str) -> Result<Vec<GaugeReading>, NwisError>
             .source_info
             .site_code
             .first()
-            .ok_or_else(|| NwisError::ParseError("Missing siteCode".to_string()))?
+            .ok_or_else(|| IngestError::Parse("Missing siteCode".to_string()))?
             .value
             .clone();
 
@@ -299,17 +368,18 @@ pub fn parse_iv_response_all(json: &str) -> Result<Vec<GaugeReading>, NwisError>
             .variable
             .variable_code
             .first()
-            .ok_or_else(|| NwisError::ParseError("Missing variableCode".to_string()))?
+            .ok_or_else(|| IngestError::Parse("Missing variableCode".to_string()))?
             .value
             .clone();
 
-        let unit = series.variable.unit.unit_code.clone();
+        let conversion = units::for_usgs(&parameter_code, &series.variable.unit.unit_code)
+            .map_err(IngestError::Parse)?;
         let no_data_value = series.variable.no_data_value;
 
         let values_wrapper = series
             .values
             .first()
-            .ok_or_else(|| NwisError::ParseError("Missing values array".to_string()))?;
+            .ok_or_else(|| IngestError::Parse("Missing values array".to_string()))?;
 
         if values_wrapper.value.is_empty() {
             continue;
@@ -327,27 +397,23 @@ pub fn parse_iv_response_all(json: &str) -> Result<Vec<GaugeReading>, NwisError>
                 continue;
             }
 
-            let qualifier = entry
-                .qualifiers
-                .first()
-                .map(|s| s.as_str())
-                .unwrap_or("P")
-                .to_string();
+            let qualifier = qualifier_codes(&entry.qualifiers);
 
             all_readings.push(GaugeReading {
                 site_code: site_code.clone(),
                 site_name: site_name.clone(),
                 parameter_code: parameter_code.clone(),
-                unit: unit.clone(),
-                value,
-                datetime: entry.date_time.clone(),
+                unit: conversion.unit.clone(),
+                value: conversion.apply(value),
+                datetime: parse_datetime(&entry.date_time)?,
                 qualifier: qualifier.clone(),
+                original_unit: conversion.original_unit.clone(),
             });
         }
     }
 
     if all_readings.is_empty() {
-        return Err(NwisError::NoDataAvailable(
+        return Err(IngestError::NoData(
             "All timeSeries entries were empty or contained sentinel values".to_string(),
         ));
     }
@@ -362,17 +428,18 @@ pub fn parse_iv_response_all(json: &str) -> Result<Vec<GaugeReading>, NwisError>
 /// per timeSeries, this returns one reading per day for historical analysis.
 ///
 /// # Errors
-/// - `NwisError::ParseError` — malformed or unexpected JSON structure.
-/// - `NwisError::NoDataAvailable` — all `timeSeries` entries had either
+/// - `IngestError::Parse` — malformed or unexpected JSON structure.
+/// - `IngestError::InvalidTimestamp` — a value's `dateTime` is not a date.
+/// - `IngestError::NoData` — all `timeSeries` entries had either
 ///   an empty `value` array or only USGS sentinel values (`-999999`).
-pub fn parse_dv_response(json: &str) -> Result<Vec<GaugeReading>, NwisError> {
+pub fn parse_dv_response(json: &str) -> Result<Vec<GaugeReading>, IngestError> {
     // Parse the JSON into our serde structs (same format as IV)
     let response: IvResponse = serde_json::from_str(json)
-        .map_err(|e| NwisError::ParseError(format!("JSON deserialization failed: {}", e)))?;
+        .map_err(|e| IngestError::Parse(format!("JSON deserialization failed: {}", e)))?;
 
     // Check for empty timeSeries array
     if response.value.time_series.is_empty() {
-        return Err(NwisError::NoDataAvailable(
+        return Err(IngestError::NoData(
             "No timeSeries entries in response".to_string(),
         ));
     }
@@ -386,7 +453,7 @@ pub fn parse_dv_response(json: &str) -> Result<Vec<GaugeReading>, NwisError> {
             .source_info
             .site_code
             .first()
-            .ok_or_else(|| NwisError::ParseError("Missing siteCode".to_string()))?
+            .ok_or_else(|| IngestError::Parse("Missing siteCode".to_string()))?
             .value
             .clone();
 
@@ -396,18 +463,19 @@ pub fn parse_dv_response(json: &str) -> Result<Vec<GaugeReading>, NwisError> {
             .variable
             .variable_code
             .first()
-            .ok_or_else(|| NwisError::ParseError("Missing variableCode".to_string()))?
+            .ok_or_else(|| IngestError::Parse("Missing variableCode".to_string()))?
             .value
             .clone();
 
-        let unit = series.variable.unit.unit_code.clone();
+        let conversion = units::for_usgs(&parameter_code, &series.variable.unit.unit_code)
+            .map_err(IngestError::Parse)?;
         let no_data_value = series.variable.no_data_value;
 
         // Get the values array
         let values_wrapper = series
             .values
             .first()
-            .ok_or_else(|| NwisError::ParseError("Missing values array".to_string()))?;
+            .ok_or_else(|| IngestError::Parse("Missing values array".to_string()))?;
 
         // Check for empty value array
         if values_wrapper.value.is_empty() {
@@ -431,30 +499,26 @@ pub fn parse_dv_response(json: &str) -> Result<Vec<GaugeReading>, NwisError> {
                 continue;
             }
 
-            // Get qualifier, defaulting to "P" if not present
-            let qualifier = entry
-                .qualifiers
-                .first()
-                .map(|s| s.as_str())
-                .unwrap_or("P")
-                .to_string();
+            // All qualifiers, defaulting to "P" if not present
+            let qualifier = qualifier_codes(&entry.qualifiers);
 
             // Create a GaugeReading for each daily value
             all_readings.push(GaugeReading {
                 site_code: site_code.clone(),
                 site_name: site_name.clone(),
                 parameter_code: parameter_code.clone(),
-                unit: unit.clone(),
-                value,
-                datetime: entry.date_time.clone(),
+                unit: conversion.unit.clone(),
+                value: conversion.apply(value),
+                datetime: parse_datetime(&entry.date_time)?,
                 qualifier: qualifier.clone(),
+                original_unit: conversion.original_unit.clone(),
             });
         }
     }
 
     // If we didn't collect any valid readings, return NoDataAvailable
     if all_readings.is_empty() {
-        return Err(NwisError::NoDataAvailable(
+        return Err(IngestError::NoData(
             "All timeSeries entries were empty or contained sentinel values".to_string(),
         ));
     }
@@ -560,6 +624,23 @@ mod tests {
         assert!(url.contains("1940-09-30"), "must support full year range");
     }
 
+    #[test]
+    fn test_rdb_urls_differ_from_json_only_in_format() {
+        let json = build_iv_url(&["05568500"], &["00065"], "PT4H");
+        let rdb = build_iv_rdb_url(&["05568500"], &["00065"], "PT4H");
+        assert_eq!(rdb, json.replace("format=json", "format=rdb"));
+
+        let json = build_dv_url(&["05568500"], &["00060"], "2020-01-01", "2020-12-31");
+        let rdb = build_dv_rdb_url(&["05568500"], &["00060"], "2020-01-01", "2020-12-31");
+        assert_eq!(rdb, json.replace("format=json", "format=rdb"));
+
+        let json = build_iv_range_url(&["05568500"], &["00065"], "2024-05-01", "2024-05-31");
+        let rdb = build_iv_range_rdb_url(&["05568500"], &["00065"], "2024-05-01", "2024-05-31");
+        assert_eq!(rdb, json.replace("format=json", "format=rdb"));
+        assert!(json.starts_with(base_urls::USGS_IV));
+        assert!(json.contains("&startDT=2024-05-01&endDT=2024-05-31&") && !json.contains("period="));
+    }
+
     // --- Parsing: happy path ------------------------------------------------
 
     #[test]
@@ -580,11 +661,7 @@ mod tests {
             discharge.value
         );
         assert_eq!(discharge.qualifier, "P");
-        assert!(
-            discharge.datetime.starts_with("2024-05-01"),
-            "datetime should be parsed correctly, got {}",
-            discharge.datetime
-        );
+        assert_eq!(discharge.datetime.to_rfc3339(), "2024-05-01T12:00:00-05:00");
     }
 
     #[test]
@@ -660,13 +737,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_keeps_every_qualifier() {
+        let json = fixture_approved_qualifier_json().replacen("[\"A\"]", "[\"A\", \"e\"]", 1);
+        let readings = parse_iv_response(&json).expect("fixture should parse");
+        assert_eq!(readings[0].qualifier, "A:e");
+    }
+
     // --- Parsing: error and edge cases --------------------------------------
 
     #[test]
     fn test_parse_empty_value_array_returns_no_data_available() {
         let result = parse_iv_response(fixture_empty_value_array_json());
         assert!(
-            matches!(result, Err(NwisError::NoDataAvailable(_))),
+            matches!(result, Err(IngestError::NoData(_))),
             "empty value array should yield NoDataAvailable, got {:?}",
             result
         );
@@ -678,7 +762,7 @@ mod tests {
         // is present. This must not be stored as a valid reading.
         let result = parse_iv_response(fixture_sentinel_no_data_json());
         assert!(
-            matches!(result, Err(NwisError::NoDataAvailable(_))),
+            matches!(result, Err(IngestError::NoData(_))),
             "sentinel value -999999 should yield NoDataAvailable, got {:?}",
             result
         );
@@ -688,7 +772,7 @@ mod tests {
     fn test_parse_malformed_json_returns_parse_error() {
         let result = parse_iv_response("{ this is not valid json }}}");
         assert!(
-            matches!(result, Err(NwisError::ParseError(_))),
+            matches!(result, Err(IngestError::Parse(_))),
             "malformed JSON should return ParseError, got {:?}",
             result
         );
@@ -698,7 +782,7 @@ mod tests {
     fn test_parse_empty_string_returns_parse_error() {
         let result = parse_iv_response("");
         assert!(
-            matches!(result, Err(NwisError::ParseError(_))),
+            matches!(result, Err(IngestError::Parse(_))),
             "empty input should return ParseError"
         );
     }
@@ -708,7 +792,7 @@ mod tests {
         let json = r#"{ "value": { "timeSeries": [] } }"#;
         let result = parse_iv_response(json);
         assert!(
-            matches!(result, Err(NwisError::NoDataAvailable(_))),
+            matches!(result, Err(IngestError::NoData(_))),
             "empty timeSeries should yield NoDataAvailable"
         );
     }
@@ -737,10 +821,57 @@ mod tests {
         assert!(
             matches!(
                 result,
-                Err(NwisError::ParseError(_)) | Err(NwisError::NoDataAvailable(_))
+                Err(IngestError::Parse(_)) | Err(IngestError::NoData(_))
             ),
             "missing values field should return an error, got {:?}",
             result
         );
     }
+
+    #[test]
+    fn test_parse_normalizes_discharge_unit_and_keeps_original() {
+        let json = r#"{
+          "value": {
+            "timeSeries": [{
+              "sourceInfo": {
+                "siteName": "Test Site",
+                "siteCode": [{ "value": "99999999", "network": "NWIS" }]
+              },
+              "variable": {
+                "variableCode": [{ "value": "00060", "network": "NWIS" }],
+                "variableName": "Streamflow",
+                "unit": { "unitCode": "kcfs" },
+                "noDataValue": -999999.0
+              },
+              "values": [{
+                "value": [{ "value": "42.3", "qualifiers": ["P"], "dateTime": "2024-05-01T12:00:00.000-05:00" }]
+              }]
+            }]
+          }
+        }"#;
+        let readings = parse_iv_response(json).unwrap();
+        assert_eq!(readings[0].unit, "ft3/s");
+        assert_eq!(readings[0].original_unit.as_deref(), Some("kcfs"));
+        assert!((readings[0].value - 42_300.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_parse_invalid_timestamp_is_an_error() {
+        let json = fixture_kingston_mines_json().replace("2024-05-01T12:00:00.000-05:00", "yesterday noon");
+        let result = parse_iv_response(&json);
+        assert!(
+            matches!(result, Err(IngestError::InvalidTimestamp(_))),
+            "unparseable dateTime should return InvalidTimestamp, got {:?}",
+            result
+        );
+        assert!(!IngestError::InvalidTimestamp(String::new()).is_retryable());
+    }
+
+    #[test]
+    fn test_parse_daily_value_datetime_as_utc() {
+        let dv = parse_datetime("2020-01-01T00:00:00.000").unwrap();
+        assert_eq!(dv.to_rfc3339(), "2020-01-01T00:00:00+00:00");
+        assert_eq!(parse_datetime("2020-01-01").unwrap(), dv);
+        assert!(parse_datetime("01/01/2020").is_err());
+    }
 }
@@ -1,21 +1,36 @@
-/// USGS Peak Streamflow Database Parser
-///
-/// Parses annual peak streamflow data from USGS NWIS Peak Streamflow database.
-/// Format: Tab-delimited RDB (Research Data BYte-stream)
-/// Source: https://nwis.waterdata.usgs.gov/{state}/nwis/peak?site_no={site}&agency_cd=USGS&format=rdb
-///
-/// This authoritative historical dataset contains complete annual peak stage and discharge
-/// records spanning 80-110+ years for most Illinois River gauges. Each row represents
-/// the highest water level and flow rate recorded during a water year (Oct 1 - Sep 30).
-///
-/// Used to populate nws.flood_events table with ground-truth historical flood events
-/// for training predictive models and validating alert systems.
+//! USGS annual peak streamflow (the peak-flow file).
+//!
+//! Parses annual peak streamflow data from USGS NWIS Peak Streamflow database.
+//! Format: Tab-delimited RDB (Research Data BYte-stream)
+//! Source: https://nwis.waterdata.usgs.gov/nwis/peak?site_no={site}&agency_cd=USGS&format=rdb
+//!
+//! This authoritative historical dataset contains complete annual peak stage and discharge
+//! records spanning 80-110+ years for most Illinois River gauges. Each row represents
+//! the highest water level and flow rate recorded during a water year (Oct 1 - Sep 30).
+//!
+//! Stored in `usgs_raw.annual_peaks` and refreshed monthly by the daemon;
+//! escalation alerts rank the current stage among the annual peaks on
+//! record ("would rank 6th of 118 annual peaks (1903-2021), highest since
+//! 2013"). `identify_flood_events` turns them into historical flood events
+//! for training predictive models and validating alert systems.
 
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use postgres::Client;
 use std::collections::HashMap;
 
+use crate::ingest::usgs_stats::ordinal_suffix;
+use crate::ingest::{base_urls, retry};
+use crate::logging::DataSource;
+
+/// How often the daemon re-fetches the peak-flow file; USGS adds a water
+/// year's peak once its record is approved
+pub const REFRESH_HOURS: i64 = 24 * 30;
+
+/// Gage height code: "Gage height at different site and(or) datum"
+const GAGE_HT_OTHER_DATUM: &str = "3";
+
 /// Parsed peak flow record from USGS RDB format
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PeakFlowRecord {
     pub site_code: String,
     pub peak_date: NaiveDate,
@@ -24,10 +39,32 @@ pub struct PeakFlowRecord {
     pub peak_qualification_codes: Vec<String>,
     pub gage_height_ft: Option<f64>,
     pub gage_height_qualification_codes: Vec<String>,
-    pub water_year: Option<u16>,
+    /// Water year of `peak_date` (see `water_year`)
+    pub water_year: i32,
     pub alternate_gage_height_ft: Option<f64>,
 }
 
+impl PeakFlowRecord {
+    /// Highest stage of the water year: the alternate gage height when the
+    /// stage peaked apart from the discharge, else the stage at the peak.
+    /// None when neither is reported or the stage was read at another
+    /// site or datum, which would not compare with today's gage.
+    pub fn annual_max_stage(&self) -> Option<f64> {
+        if self.gage_height_qualification_codes.iter().any(|c| c == GAGE_HT_OTHER_DATUM) {
+            return None;
+        }
+        match (self.gage_height_ft, self.alternate_gage_height_ft) {
+            (Some(peak), Some(alternate)) => Some(peak.max(alternate)),
+            (peak, alternate) => peak.or(alternate),
+        }
+    }
+}
+
+/// USGS water year of `date`: Oct 1 2012 - Sep 30 2013 is water year 2013
+pub fn water_year(date: NaiveDate) -> i32 {
+    if date.month() >= 10 { date.year() + 1 } else { date.year() }
+}
+
 /// Flood event derived from peak flow record + threshold comparison
 #[derive(Debug, Clone)]
 pub struct FloodEvent {
@@ -80,6 +117,10 @@ pub struct FloodThresholds {
     pub major_flood_stage_ft: f64,
 }
 
+pub fn peaks_url(site_code: &str) -> String {
+    format!("{}?site_no={}&agency_cd=USGS&format=rdb", base_urls::get().usgs_peak, site_code)
+}
+
 /// Parse USGS Peak Streamflow RDB format
 ///
 /// RDB format structure:
@@ -97,6 +138,9 @@ pub struct FloodThresholds {
 /// - peak_cd: Qualification codes (comma-separated)
 /// - gage_ht: Gage height (feet) - THIS IS THE FLOOD STAGE VALUE
 /// - gage_ht_cd: Gage height qualification codes
+/// - ag_gage_ht: Highest stage of the year, if not at the peak discharge
+///
+/// Historic peaks whose month or day is unknown ("1844-00-00") are skipped.
 ///
 /// # Arguments
 /// * `rdb_text` - Raw RDB format text from USGS API
@@ -137,6 +181,9 @@ pub fn parse_rdb(rdb_text: &str) -> Result<Vec<PeakFlowRecord>, String> {
         let peak_dt_str = fields.get(*col_map.get("peak_dt").ok_or("Missing peak_dt column")?)
             .ok_or("Missing peak_dt value")?;
         
+        if peak_dt_str.trim().ends_with("-00") {
+            continue;
+        }
         let peak_date = NaiveDate::parse_from_str(peak_dt_str, "%Y-%m-%d")
             .map_err(|e| format!("Invalid peak_dt '{}': {}", peak_dt_str, e))?;
         
@@ -172,12 +219,6 @@ pub fn parse_rdb(rdb_text: &str) -> Result<Vec<PeakFlowRecord>, String> {
             .map(|s| s.split(',').map(|c| c.trim().to_string()).collect())
             .unwrap_or_default();
         
-        // Water year (optional)
-        let water_year = col_map.get("year_last_pk")
-            .and_then(|&idx| fields.get(idx))
-            .filter(|s| !s.trim().is_empty())
-            .and_then(|s| s.trim().parse::<u16>().ok());
-        
         // Alternate gage height (max for year if different from peak discharge time)
         let alternate_gage_height_ft = col_map.get("ag_gage_ht")
             .and_then(|&idx| fields.get(idx))
@@ -192,7 +233,7 @@ pub fn parse_rdb(rdb_text: &str) -> Result<Vec<PeakFlowRecord>, String> {
             peak_qualification_codes,
             gage_height_ft,
             gage_height_qualification_codes,
-            water_year,
+            water_year: water_year(peak_date),
             alternate_gage_height_ft,
         });
     }
@@ -200,6 +241,113 @@ pub fn parse_rdb(rdb_text: &str) -> Result<Vec<PeakFlowRecord>, String> {
     Ok(records)
 }
 
+/// Fetch and parse one site's annual peaks
+pub fn fetch(site_code: &str) -> Result<Vec<PeakFlowRecord>, String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let body = retry::get_text_blocking(&client, DataSource::Usgs, &peaks_url(site_code), false)
+        .map_err(|e| format!("Failed to fetch annual peaks for {}: {}", site_code, e))?;
+    parse_rdb(&body)
+}
+
+/// Insert or replace annual peaks, one per site and water year; returns
+/// how many rows were written
+pub fn store(client: &mut Client, peaks: &[PeakFlowRecord], now: DateTime<Utc>) -> Result<usize, String> {
+    let mut written = 0;
+    for p in peaks {
+        written += client.execute(
+            "INSERT INTO usgs_raw.annual_peaks
+             (site_code, water_year, peak_date, peak_time, peak_discharge_cfs, peak_codes,
+              gage_height_ft, gage_height_codes, alternate_gage_height_ft, fetched_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             ON CONFLICT (site_code, water_year) DO UPDATE SET
+                peak_date = EXCLUDED.peak_date, peak_time = EXCLUDED.peak_time,
+                peak_discharge_cfs = EXCLUDED.peak_discharge_cfs, peak_codes = EXCLUDED.peak_codes,
+                gage_height_ft = EXCLUDED.gage_height_ft, gage_height_codes = EXCLUDED.gage_height_codes,
+                alternate_gage_height_ft = EXCLUDED.alternate_gage_height_ft,
+                fetched_at = EXCLUDED.fetched_at",
+            &[&p.site_code, &p.water_year, &p.peak_date, &p.peak_time, &p.peak_discharge_cfs,
+              &p.peak_qualification_codes, &p.gage_height_ft, &p.gage_height_qualification_codes,
+              &p.alternate_gage_height_ft, &now],
+        ).map_err(|e| format!("Failed to store annual peak {} {}: {}", p.site_code, p.water_year, e))? as usize;
+    }
+    Ok(written)
+}
+
+/// Stored annual peaks of `site_code`, oldest water year first
+pub fn load(client: &mut Client, site_code: &str) -> Result<Vec<PeakFlowRecord>, String> {
+    let rows = client.query(
+        "SELECT water_year, peak_date, peak_time, peak_discharge_cfs, peak_codes,
+                gage_height_ft, gage_height_codes, alternate_gage_height_ft
+         FROM usgs_raw.annual_peaks
+         WHERE site_code = $1
+         ORDER BY water_year",
+        &[&site_code],
+    ).map_err(|e| format!("Failed to load annual peaks for {}: {}", site_code, e))?;
+    Ok(rows.iter().map(|row| PeakFlowRecord {
+        site_code: site_code.to_string(),
+        water_year: row.get(0),
+        peak_date: row.get(1),
+        peak_time: row.get(2),
+        peak_discharge_cfs: row.get(3),
+        peak_qualification_codes: row.get(4),
+        gage_height_ft: row.get(5),
+        gage_height_qualification_codes: row.get(6),
+        alternate_gage_height_ft: row.get(7),
+    }).collect())
+}
+
+/// Where a stage would fall among a station's annual peak stages
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageRank {
+    /// 1 when above every annual peak on record
+    pub rank: usize,
+    /// Annual peaks with a comparable stage
+    pub of: usize,
+    pub first_year: i32,
+    pub last_year: i32,
+    /// Latest water year whose peak was as high or higher; None for a record
+    pub highest_since: Option<i32>,
+}
+
+impl StageRank {
+    /// "would rank 6th of 118 annual peaks (1903-2021), highest since 2013"
+    pub fn describe(&self) -> String {
+        let record = format!("{} annual peaks ({}-{})", self.of, self.first_year, self.last_year);
+        match self.highest_since {
+            None => format!("above all {}", record),
+            Some(year) => format!(
+                "would rank {}{} of {}, highest since {}",
+                self.rank, ordinal_suffix(self.rank as u32), record, year,
+            ),
+        }
+    }
+}
+
+/// Rank `stage_ft` among the annual maximum stages in `peaks` (see
+/// `PeakFlowRecord::annual_max_stage`); a tie ranks below the peak it
+/// ties. None without any comparable stage.
+pub fn rank_stage(peaks: &[PeakFlowRecord], stage_ft: f64) -> Option<StageRank> {
+    let stages: Vec<(i32, f64)> = peaks.iter()
+        .filter_map(|p| p.annual_max_stage().map(|stage| (p.water_year, stage)))
+        .collect();
+    let first_year = stages.iter().map(|(year, _)| *year).min()?;
+    let last_year = stages.iter().map(|(year, _)| *year).max()?;
+    let higher: Vec<i32> = stages.iter()
+        .filter(|(_, stage)| *stage >= stage_ft)
+        .map(|(year, _)| *year)
+        .collect();
+    Some(StageRank {
+        rank: higher.len() + 1,
+        of: stages.len(),
+        first_year,
+        last_year,
+        highest_since: higher.iter().max().copied(),
+    })
+}
+
 /// Convert peak flow records to flood events based on thresholds
 ///
 /// Only peaks where gage_height_ft >= flood_stage_ft are considered flood events.
@@ -277,6 +425,54 @@ USGS	05567500	2015-12-29		31400		19.09
         assert_eq!(records[0].gage_height_ft, Some(18.79));
         
         assert_eq!(records[1].gage_height_ft, Some(19.09));
+        // December 2015 belongs to water year 2016
+        assert_eq!(records[1].water_year, 2016);
+    }
+
+    #[test]
+    fn test_historic_peaks_without_a_full_date_are_skipped() {
+        let rdb_data = "agency_cd\tsite_no\tpeak_dt\tpeak_va\tgage_ht\tgage_ht_cd\tag_gage_ht
+5s\t15s\t10d\t8s\t8s\t27s\t8s
+USGS\t05567500\t1844-00-00\t\t27.50\t3\t
+USGS\t05567500\t1943-05-22\t85200\t28.76\t\t
+";
+        let records = parse_rdb(rdb_data).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].water_year, 1943);
+    }
+
+    fn peak(year: i32, stage: f64) -> PeakFlowRecord {
+        PeakFlowRecord {
+            site_code: "05567500".to_string(),
+            peak_date: NaiveDate::from_ymd_opt(year, 5, 1).unwrap(),
+            peak_time: None,
+            peak_discharge_cfs: None,
+            peak_qualification_codes: vec![],
+            gage_height_ft: Some(stage),
+            gage_height_qualification_codes: vec![],
+            water_year: year,
+            alternate_gage_height_ft: None,
+        }
+    }
+
+    #[test]
+    fn test_rank_stage_among_annual_peaks() {
+        let mut moved = peak(1904, 30.0);
+        moved.gage_height_qualification_codes = vec![GAGE_HT_OTHER_DATUM.to_string()];
+        let mut stage_peaked_later = peak(2019, 17.1);
+        stage_peaked_later.alternate_gage_height_ft = Some(20.4);
+        let peaks = vec![moved, peak(1943, 28.7), peak(2013, 29.4), peak(2018, 15.2), stage_peaked_later];
+
+        let rank = rank_stage(&peaks, 20.0).unwrap();
+        assert_eq!((rank.rank, rank.of), (4, 4), "1904 is on another datum");
+        assert_eq!(rank.highest_since, Some(2019));
+        assert_eq!(rank.describe(), "would rank 4th of 4 annual peaks (1943-2019), highest since 2019");
+
+        let record = rank_stage(&peaks, 30.1).unwrap();
+        assert_eq!(record.rank, 1);
+        assert_eq!(record.describe(), "above all 4 annual peaks (1943-2019)");
+
+        assert!(rank_stage(&[], 20.0).is_none());
     }
 
     #[test]
@@ -290,7 +486,7 @@ USGS	05567500	2015-12-29		31400		19.09
                 peak_qualification_codes: vec![],
                 gage_height_ft: Some(18.79),
                 gage_height_qualification_codes: vec![],
-                water_year: None,
+                water_year: 2013,
                 alternate_gage_height_ft: None,
             },
             PeakFlowRecord {
@@ -301,7 +497,7 @@ USGS	05567500	2015-12-29		31400		19.09
                 peak_qualification_codes: vec![],
                 gage_height_ft: Some(17.65),
                 gage_height_qualification_codes: vec![],
-                water_year: None,
+                water_year: 2019,
                 alternate_gage_height_ft: None,
             },
         ];
@@ -324,7 +520,7 @@ USGS	05567500	2015-12-29		31400		19.09
 
     #[test]
     fn test_flood_severity_classification() {
-        let thresholds = FloodThresholds {
+        let _thresholds = FloodThresholds {
             flood_stage_ft: 18.0,
             moderate_flood_stage_ft: 20.0,
             major_flood_stage_ft: 22.0,
@@ -0,0 +1,346 @@
+//! USGS NWIS RDB (tab-delimited) parser for IV and DV responses.
+//!
+//! Fallback for when the JSON (WaterML) rendering of the IV/DV services is
+//! degraded but the RDB rendering of the same request still works:
+//!   https://waterservices.usgs.gov/nwis/iv/?format=rdb&...
+//!
+//! RDB response shape, repeated once per site in multi-site responses:
+//!   # comment block, including one line per site:
+//!   #    USGS 05568500 ILLINOIS RIVER AT KINGSTON MINES, IL
+//!   agency_cd  site_no  datetime  tz_cd  69928_00060  69928_00060_cd  ...
+//!   5s         15s      20d       6s     14n          10s             ...
+//!   USGS       05568500 2024-05-01 12:00  CDT  42300  P  ...
+//!
+//! Value columns are named `{ts_id}_{parameter}` (IV) or
+//! `{ts_id}_{parameter}_{statistic}` (DV), each followed by a `_cd`
+//! qualifier column. Missing values are blank or a text code ("Ice",
+//! "Eqp", "***") rather than the JSON `-999999` sentinel.
+//!
+//! Output is the same `GaugeReading` the JSON parsers produce, with
+//! datetimes resolved to the same offsets, so downstream code can't tell
+//! which format a reading came from.
+
+use crate::units;
+use crate::model::{GaugeReading, IngestError};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone};
+use std::collections::HashMap;
+
+/// USGS sentinel for missing data (not normally present in RDB, but cheap to guard)
+const NO_DATA_VALUE: f64 = -999999.0;
+
+// ---------------------------------------------------------------------------
+// Public parsers
+// ---------------------------------------------------------------------------
+
+/// RDB counterpart of `usgs::parse_iv_response`: the most recent valid
+/// value per site and parameter.
+///
+/// # Errors
+/// - `IngestError::Parse` — body is not RDB (e.g. an HTML error page).
+/// - `IngestError::NoData` — no site blocks, or no valid values.
+pub fn parse_iv_rdb(body: &str) -> Result<Vec<GaugeReading>, IngestError> {
+    let all = parse_rdb(body)?;
+
+    // Rows are chronological within a site block, so the last wins
+    let mut latest: Vec<GaugeReading> = Vec::new();
+    for reading in all {
+        match latest.iter_mut().find(|r| {
+            r.site_code == reading.site_code && r.parameter_code == reading.parameter_code
+        }) {
+            Some(existing) => *existing = reading,
+            None => latest.push(reading),
+        }
+    }
+    Ok(latest)
+}
+
+/// RDB counterpart of `usgs::parse_iv_response_all`: every valid value.
+pub fn parse_iv_rdb_all(body: &str) -> Result<Vec<GaugeReading>, IngestError> {
+    parse_rdb(body)
+}
+
+/// RDB counterpart of `usgs::parse_dv_response`: one reading per day.
+pub fn parse_dv_rdb(body: &str) -> Result<Vec<GaugeReading>, IngestError> {
+    parse_rdb(body)
+}
+
+// ---------------------------------------------------------------------------
+// Implementation
+// ---------------------------------------------------------------------------
+
+/// A value column and its qualifier column within the current site block
+struct ValueColumn {
+    index: usize,
+    qualifier_index: Option<usize>,
+    parameter_code: String,
+}
+
+/// Column layout of the current site block
+struct Header {
+    site_index: usize,
+    datetime_index: usize,
+    tz_index: Option<usize>,
+    values: Vec<ValueColumn>,
+}
+
+fn parse_rdb(body: &str) -> Result<Vec<GaugeReading>, IngestError> {
+    let mut site_names: HashMap<String, String> = HashMap::new();
+    let mut header: Option<Header> = None;
+    let mut expect_format_line = false;
+    let mut saw_header = false;
+    let mut readings = Vec::new();
+
+    for line in body.lines() {
+        if let Some(comment) = line.strip_prefix('#') {
+            if let Some((site, name)) = parse_site_comment(comment) {
+                site_names.insert(site, name);
+            }
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+
+        if fields.first() == Some(&"agency_cd") {
+            header = Some(parse_header(&fields)?);
+            expect_format_line = true;
+            saw_header = true;
+            continue;
+        }
+        if expect_format_line {
+            // Column width/type line, e.g. "5s\t15s\t20d"
+            expect_format_line = false;
+            continue;
+        }
+
+        let header = header.as_ref().ok_or_else(|| {
+            IngestError::Parse("RDB data row before column header".to_string())
+        })?;
+
+        let site_code = field(&fields, header.site_index).to_string();
+        let datetime = parse_datetime(
+            field(&fields, header.datetime_index),
+            header.tz_index.map(|i| field(&fields, i)),
+        )?;
+        let site_name = site_names.get(&site_code).cloned().unwrap_or_default();
+
+        for column in &header.values {
+            let value: f64 = match field(&fields, column.index).parse() {
+                Ok(v) => v,
+                Err(_) => continue, // Blank or a status code such as "Ice"
+            };
+            if (value - NO_DATA_VALUE).abs() < 0.1 {
+                continue;
+            }
+
+            let qualifier = column.qualifier_index
+                .map(|i| field(&fields, i).replace(',', ":"))
+                .filter(|q| !q.is_empty())
+                .unwrap_or_else(|| "P".to_string());
+
+            readings.push(GaugeReading {
+                site_code: site_code.clone(),
+                site_name: site_name.clone(),
+                parameter_code: column.parameter_code.clone(),
+                unit: units::canonical_usgs_unit(&column.parameter_code).to_string(),
+                value,
+                datetime,
+                qualifier,
+                original_unit: None,
+            });
+        }
+    }
+
+    if !saw_header {
+        if body.contains("No sites found") {
+            return Err(IngestError::NoData("No sites in RDB response".to_string()));
+        }
+        return Err(IngestError::Parse("Missing RDB column header".to_string()));
+    }
+    if readings.is_empty() {
+        return Err(IngestError::NoData(
+            "All RDB value columns were empty or non-numeric".to_string(),
+        ));
+    }
+
+    Ok(readings)
+}
+
+fn field<'a>(fields: &[&'a str], index: usize) -> &'a str {
+    fields.get(index).map(|f| f.trim()).unwrap_or("")
+}
+
+fn parse_header(fields: &[&str]) -> Result<Header, IngestError> {
+    let position = |name: &str| fields.iter().position(|f| *f == name);
+
+    let site_index = position("site_no")
+        .ok_or_else(|| IngestError::Parse("RDB header missing site_no".to_string()))?;
+    let datetime_index = position("datetime")
+        .ok_or_else(|| IngestError::Parse("RDB header missing datetime".to_string()))?;
+    let tz_index = position("tz_cd");
+
+    let mut values = Vec::new();
+    for (index, name) in fields.iter().enumerate() {
+        if matches!(*name, "agency_cd" | "site_no" | "datetime" | "tz_cd") || name.ends_with("_cd") {
+            continue;
+        }
+        // "{ts_id}_{parameter}" or "{ts_id}_{parameter}_{statistic}"
+        let parameter_code = match name.split('_').nth(1) {
+            Some(p) if p.len() == 5 && p.chars().all(|c| c.is_ascii_digit()) => p.to_string(),
+            _ => continue,
+        };
+        let qualifier_name = format!("{}_cd", name);
+        values.push(ValueColumn {
+            index,
+            qualifier_index: position(&qualifier_name),
+            parameter_code,
+        });
+    }
+
+    Ok(Header { site_index, datetime_index, tz_index, values })
+}
+
+/// Extract site code and name from a "#    USGS 05568500 NAME" comment line
+fn parse_site_comment(comment: &str) -> Option<(String, String)> {
+    let rest = comment.trim_start().strip_prefix("USGS ")?;
+    let (site, name) = rest.trim_start().split_once(' ')?;
+    if site.len() < 8 || !site.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((site.to_string(), name.trim().to_string()))
+}
+
+/// Parse an RDB datetime to the same timestamp the JSON service gives.
+///
+/// IV rows carry local time plus a `tz_cd` abbreviation
+/// ("2024-05-01 12:00", "CDT" -> 2024-05-01T12:00:00-05:00).
+/// DV rows carry a bare date, taken as UTC midnight as in the JSON
+/// ("2020-01-01" -> 2020-01-01T00:00:00+00:00).
+fn parse_datetime(datetime: &str, tz_cd: Option<&str>) -> Result<DateTime<FixedOffset>, IngestError> {
+    let Some(tz_cd) = tz_cd else {
+        let date = NaiveDate::parse_from_str(datetime, "%Y-%m-%d")
+            .map_err(|e| IngestError::InvalidTimestamp(format!("RDB date '{}': {}", datetime, e)))?;
+        return Ok(date.and_time(Default::default()).and_utc().fixed_offset());
+    };
+
+    let local = NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M")
+        .map_err(|e| IngestError::InvalidTimestamp(format!("RDB datetime '{}': {}", datetime, e)))?;
+    let offset = tz_offset(tz_cd)
+        .ok_or_else(|| IngestError::InvalidTimestamp(format!("unknown RDB time zone '{}'", tz_cd)))?;
+    offset.from_local_datetime(&local).single()
+        .ok_or_else(|| IngestError::InvalidTimestamp(format!("ambiguous RDB datetime '{}'", datetime)))
+}
+
+pub(crate) fn tz_offset(tz_cd: &str) -> Option<FixedOffset> {
+    let hours = match tz_cd {
+        "UTC" | "GMT" => 0,
+        "EST" => -5,
+        "EDT" => -4,
+        "CST" => -6,
+        "CDT" => -5,
+        "MST" => -7,
+        "MDT" => -6,
+        "PST" => -8,
+        "PDT" => -7,
+        _ => return None,
+    };
+    FixedOffset::east_opt(hours * 3600)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingest::fixtures::*;
+    use crate::ingest::usgs::parse_iv_response;
+
+    #[test]
+    fn test_iv_rdb_matches_json_parser() {
+        let mut from_rdb = parse_iv_rdb(fixture_kingston_mines_rdb()).expect("RDB fixture parses");
+        let mut from_json = parse_iv_response(fixture_kingston_mines_json()).expect("JSON fixture parses");
+        from_rdb.sort_by(|a, b| a.parameter_code.cmp(&b.parameter_code));
+        from_json.sort_by(|a, b| a.parameter_code.cmp(&b.parameter_code));
+
+        assert_eq!(from_rdb.len(), from_json.len());
+        for (rdb, json) in from_rdb.iter().zip(&from_json) {
+            assert_eq!(rdb.site_code, json.site_code);
+            assert_eq!(rdb.parameter_code, json.parameter_code);
+            assert_eq!(rdb.unit, json.unit);
+            assert_eq!(rdb.datetime, json.datetime);
+            assert_eq!(rdb.qualifier, json.qualifier);
+            assert!((rdb.value - json.value).abs() < 1e-9);
+        }
+        assert_eq!(from_rdb[0].site_name, "ILLINOIS RIVER AT KINGSTON MINES, IL");
+    }
+
+    #[test]
+    fn test_iv_rdb_all_keeps_every_row_and_skips_status_codes() {
+        let readings = parse_iv_rdb_all(fixture_kingston_mines_rdb()).unwrap();
+        // 3 rows x 2 parameters, minus the "Eqp" stage value
+        assert_eq!(readings.len(), 5);
+        assert!(readings.iter().all(|r| r.value.is_finite()));
+    }
+
+    #[test]
+    fn test_iv_rdb_multi_site_blocks() {
+        let readings = parse_iv_rdb(fixture_multi_site_rdb()).unwrap();
+        let peoria = readings.iter().find(|r| r.site_code == "05567500").expect("Peoria present");
+        let chillicothe = readings.iter().find(|r| r.site_code == "05568000").expect("Chillicothe present");
+
+        assert_eq!(peoria.parameter_code, "00065");
+        assert_eq!(peoria.datetime.to_rfc3339(), "2024-01-15T09:30:00-06:00");
+        assert_eq!(chillicothe.parameter_code, "00060");
+        assert_eq!(chillicothe.site_name, "ILLINOIS RIVER AT CHILLICOTHE, IL");
+    }
+
+    #[test]
+    fn test_iv_rdb_keeps_every_qualifier() {
+        let readings = parse_iv_rdb(fixture_multi_site_rdb()).unwrap();
+        let peoria = readings.iter().find(|r| r.site_code == "05567500").unwrap();
+        // "A:e" (approved, estimated), as the JSON parser joins qualifiers
+        assert_eq!(peoria.qualifier, "A:e");
+    }
+
+    #[test]
+    fn test_dv_rdb_matches_json_datetime_format() {
+        let readings = parse_dv_rdb(fixture_daily_values_rdb()).unwrap();
+        assert_eq!(readings.len(), 4);
+        assert_eq!(readings[0].datetime.to_rfc3339(), "2020-01-01T00:00:00+00:00");
+        assert_eq!(readings[0].parameter_code, "00060");
+        assert_eq!(readings[1].parameter_code, "00065");
+        assert_eq!(readings[1].qualifier, "A");
+    }
+
+    #[test]
+    fn test_rdb_no_sites_returns_no_data_available() {
+        let body = "#\n# No sites found matching all criteria\n#\n";
+        assert!(matches!(parse_iv_rdb(body), Err(IngestError::NoData(_))));
+    }
+
+    #[test]
+    fn test_rdb_unknown_time_zone_is_invalid_timestamp() {
+        let body = "agency_cd\tsite_no\tdatetime\ttz_cd\t1_00065\t1_00065_cd\n\
+                    5s\t15s\t20d\t6s\t14n\t10s\n\
+                    USGS\t05567500\t2024-01-15 09:30\tXST\t14.2\tP\n";
+        assert!(matches!(parse_iv_rdb(body), Err(IngestError::InvalidTimestamp(_))));
+    }
+
+    #[test]
+    fn test_rdb_html_error_page_returns_parse_error() {
+        let body = "<html><body>Service Unavailable</body></html>";
+        assert!(matches!(parse_iv_rdb(body), Err(IngestError::Parse(_))));
+    }
+
+    #[test]
+    fn test_rdb_all_values_missing_returns_no_data_available() {
+        let body = "agency_cd\tsite_no\tdatetime\ttz_cd\t1_00065\t1_00065_cd\n\
+                    5s\t15s\t20d\t6s\t14n\t10s\n\
+                    USGS\t05567500\t2024-01-15 09:30\tCST\tIce\tP\n";
+        assert!(matches!(parse_iv_rdb(body), Err(IngestError::NoData(_))));
+    }
+}
@@ -0,0 +1,327 @@
+//! USGS daily statistics (climatology by day of year).
+//!
+//! For every calendar day USGS publishes the distribution of that day's
+//! mean value over the station's period of record: minimum, mean, maximum
+//! and the 5th through 95th percentiles. Comparing a current value with
+//! the distribution for its date turns "31,200 cfs" into "at the 97th
+//! percentile for May 1". They arrive as RDB from the statistics service:
+//! https://waterservices.usgs.gov/nwis/stat/?format=rdb&sites={site}&statReportType=daily&statTypeCd=all&parameterCd={codes}
+//!
+//! Key fields:
+//! - parameter_cd: 00060 (discharge, cfs) or 00065 (stage, ft)
+//! - month_nu / day_nu: calendar day, Feb 29 included
+//! - begin_yr / end_yr / count_nu: period of record and years with data
+//! - min_va / mean_va / max_va: extremes and mean of the daily means
+//! - p05_va .. p95_va: percentiles of the daily means
+//!
+//! Stored in `usgs_raw.daily_statistics`; the API reports the percentile of
+//! each station's latest discharge and escalation alerts quote it.
+
+use chrono::{DateTime, Datelike, FixedOffset, Utc};
+use postgres::Client;
+use std::collections::HashMap;
+
+use crate::ingest::{base_urls, retry};
+use crate::logging::DataSource;
+
+/// How often the daemon re-fetches statistics; USGS recomputes them when
+/// a water year's record is approved
+pub const REFRESH_HOURS: i64 = 24 * 7;
+
+/// Parameters statistics are fetched for
+pub const PARAMETERS: [&str; 2] = ["00060", "00065"];
+
+/// Percentile columns of the RDB and the percentile each one holds
+const PERCENTILE_COLUMNS: [(&str, f64); 9] = [
+    ("p05_va", 5.0), ("p10_va", 10.0), ("p20_va", 20.0), ("p25_va", 25.0), ("p50_va", 50.0),
+    ("p75_va", 75.0), ("p80_va", 80.0), ("p90_va", 90.0), ("p95_va", 95.0),
+];
+
+/// Statistics of one parameter on one calendar day
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyStat {
+    pub site_code: String,
+    pub parameter_code: String,
+    pub month: u32,
+    pub day: u32,
+    pub begin_year: Option<i32>,
+    pub end_year: Option<i32>,
+    /// Years with data for this day
+    pub count: Option<i32>,
+    pub min: Option<f64>,
+    pub mean: Option<f64>,
+    pub max: Option<f64>,
+    /// (percentile, value), ascending; only those USGS reported
+    pub percentiles: Vec<(f64, f64)>,
+}
+
+impl DailyStat {
+    pub fn median(&self) -> Option<f64> {
+        self.percentiles.iter().find(|(p, _)| *p == 50.0).map(|(_, v)| *v)
+    }
+
+    /// Percentile of `value` among this day's record, interpolated linearly
+    /// between the reported percentiles, with the record minimum taken as
+    /// the 0th and the maximum as the 100th. Values outside the record
+    /// clamp to 0 or 100.
+    pub fn percentile_of(&self, value: f64) -> Option<f64> {
+        let mut points: Vec<(f64, f64)> = Vec::with_capacity(self.percentiles.len() + 2);
+        points.extend(self.min.map(|v| (0.0, v)));
+        points.extend(self.percentiles.iter().copied());
+        points.extend(self.max.map(|v| (100.0, v)));
+        let (&(first_p, first_v), &(last_p, last_v)) = (points.first()?, points.last()?);
+        if value <= first_v {
+            return Some(if self.min.is_some() { 0.0 } else { first_p });
+        }
+        if value >= last_v {
+            return Some(if self.max.is_some() { 100.0 } else { last_p });
+        }
+        points.windows(2)
+            .find(|w| value >= w[0].1 && value <= w[1].1)
+            .map(|w| {
+                let ((p0, v0), (p1, v1)) = (w[0], w[1]);
+                if v1 > v0 { p0 + (p1 - p0) * (value - v0) / (v1 - v0) } else { p1 }
+            })
+    }
+
+    /// "at the 97th percentile for May 1 (1940-2023)"
+    pub fn describe(&self, value: f64) -> Option<String> {
+        let percentile = self.percentile_of(value)?.round() as u32;
+        let date = chrono::NaiveDate::from_ymd_opt(2000, self.month, self.day)?.format("%b %-d");
+        let record = match (self.begin_year, self.end_year) {
+            (Some(begin), Some(end)) => format!(" ({}-{})", begin, end),
+            _ => String::new(),
+        };
+        Some(match percentile {
+            0 => format!("at or below the record low for {}{}", date, record),
+            100 => format!("above the record high for {}{}", date, record),
+            p => format!("at the {}{} percentile for {}{}", p, ordinal_suffix(p), date, record),
+        })
+    }
+}
+
+pub(crate) fn ordinal_suffix(n: u32) -> &'static str {
+    match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+/// Calendar day whose statistics apply at `time`; USGS daily values are
+/// computed over local standard time (Central, UTC-6)
+pub fn stats_day(time: DateTime<Utc>) -> (u32, u32) {
+    let local = time.with_timezone(&FixedOffset::west_opt(6 * 3600).unwrap());
+    (local.month(), local.day())
+}
+
+pub fn stats_url(site_code: &str) -> String {
+    format!(
+        "{}?format=rdb&sites={}&statReportType=daily&statTypeCd=all&parameterCd={}",
+        base_urls::get().usgs_stats, site_code, PARAMETERS.join(","),
+    )
+}
+
+/// Parse the statistics RDB. Rows without a valid calendar day are
+/// skipped; when a site reports several timeseries of one parameter the
+/// one with the longest record is kept. A missing required column is an
+/// error.
+pub fn parse_rdb(rdb_text: &str) -> Result<Vec<DailyStat>, String> {
+    let mut data_lines = rdb_text.lines()
+        .filter(|line| !line.trim().starts_with('#') && !line.trim().is_empty());
+
+    let header_line = data_lines.next()
+        .ok_or("No header line found in RDB data")?;
+    let col_map: HashMap<&str, usize> = header_line.split('\t')
+        .enumerate()
+        .map(|(idx, name)| (name, idx))
+        .collect();
+    let column = |name: &str| col_map.get(name).copied().ok_or_else(|| format!("Missing {} column", name));
+    let (site_idx, parameter_idx, month_idx, day_idx) =
+        (column("site_no")?, column("parameter_cd")?, column("month_nu")?, column("day_nu")?);
+
+    // Column width/type line
+    data_lines.next().ok_or("No format line found in RDB data")?;
+
+    let mut stats: Vec<DailyStat> = Vec::new();
+    let mut index: HashMap<(String, String, u32, u32), usize> = HashMap::new();
+    for line in data_lines {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let get = |idx: Option<usize>| idx.and_then(|i| fields.get(i)).map(|f| f.trim()).filter(|f| !f.is_empty());
+        let number = |name: &str| get(col_map.get(name).copied()).and_then(|f| f.parse::<f64>().ok());
+        let integer = |name: &str| get(col_map.get(name).copied()).and_then(|f| f.parse::<i32>().ok());
+
+        let (Some(site_code), Some(parameter_code)) = (get(Some(site_idx)), get(Some(parameter_idx))) else {
+            continue;
+        };
+        let (Some(month), Some(day)) = (
+            get(Some(month_idx)).and_then(|f| f.parse::<u32>().ok()),
+            get(Some(day_idx)).and_then(|f| f.parse::<u32>().ok()),
+        ) else {
+            continue;
+        };
+        if chrono::NaiveDate::from_ymd_opt(2000, month, day).is_none() {
+            continue;
+        }
+
+        let stat = DailyStat {
+            site_code: site_code.to_string(),
+            parameter_code: parameter_code.to_string(),
+            month,
+            day,
+            begin_year: integer("begin_yr"),
+            end_year: integer("end_yr"),
+            count: integer("count_nu"),
+            min: number("min_va"),
+            mean: number("mean_va"),
+            max: number("max_va"),
+            percentiles: PERCENTILE_COLUMNS.iter()
+                .filter_map(|(name, p)| number(name).map(|v| (*p, v)))
+                .collect(),
+        };
+        let key = (stat.site_code.clone(), stat.parameter_code.clone(), month, day);
+        match index.get(&key) {
+            Some(&i) if stats[i].count >= stat.count => {}
+            Some(&i) => stats[i] = stat,
+            None => {
+                index.insert(key, stats.len());
+                stats.push(stat);
+            }
+        }
+    }
+    Ok(stats)
+}
+
+/// Fetch and parse one site's statistics
+pub fn fetch(site_code: &str) -> Result<Vec<DailyStat>, String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let body = retry::get_text_blocking(&client, DataSource::Usgs, &stats_url(site_code), false)
+        .map_err(|e| format!("Failed to fetch daily statistics for {}: {}", site_code, e))?;
+    parse_rdb(&body)
+}
+
+/// Insert or replace statistics; returns how many rows were written
+pub fn store(client: &mut Client, stats: &[DailyStat], now: DateTime<Utc>) -> Result<usize, String> {
+    let mut written = 0;
+    for s in stats {
+        let (percentiles, values): (Vec<f64>, Vec<f64>) = s.percentiles.iter().copied().unzip();
+        written += client.execute(
+            "INSERT INTO usgs_raw.daily_statistics
+             (site_code, parameter_code, month, day, begin_year, end_year, year_count,
+              min_value, mean_value, max_value, percentiles, percentile_values, fetched_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+             ON CONFLICT (site_code, parameter_code, month, day) DO UPDATE SET
+                begin_year = EXCLUDED.begin_year, end_year = EXCLUDED.end_year,
+                year_count = EXCLUDED.year_count, min_value = EXCLUDED.min_value,
+                mean_value = EXCLUDED.mean_value, max_value = EXCLUDED.max_value,
+                percentiles = EXCLUDED.percentiles, percentile_values = EXCLUDED.percentile_values,
+                fetched_at = EXCLUDED.fetched_at",
+            &[&s.site_code, &s.parameter_code, &(s.month as i16), &(s.day as i16), &s.begin_year,
+              &s.end_year, &s.count, &s.min, &s.mean, &s.max, &percentiles, &values, &now],
+        ).map_err(|e| format!("Failed to store daily statistics {} {} {}/{}: {}",
+            s.site_code, s.parameter_code, s.month, s.day, e))? as usize;
+    }
+    Ok(written)
+}
+
+/// Stored statistics of `parameter_code` at `site_code` for one calendar day
+pub fn load(client: &mut Client, site_code: &str, parameter_code: &str, month: u32, day: u32) -> Result<Option<DailyStat>, String> {
+    let row = client.query_opt(
+        "SELECT begin_year, end_year, year_count, min_value, mean_value, max_value, percentiles, percentile_values
+         FROM usgs_raw.daily_statistics
+         WHERE site_code = $1 AND parameter_code = $2 AND month = $3 AND day = $4",
+        &[&site_code, &parameter_code, &(month as i16), &(day as i16)],
+    ).map_err(|e| format!("Failed to load daily statistics for {}: {}", site_code, e))?;
+    Ok(row.map(|row| {
+        let percentiles: Vec<f64> = row.get(6);
+        let values: Vec<f64> = row.get(7);
+        DailyStat {
+            site_code: site_code.to_string(),
+            parameter_code: parameter_code.to_string(),
+            month,
+            day,
+            begin_year: row.get(0),
+            end_year: row.get(1),
+            count: row.get(2),
+            min: row.get(3),
+            mean: row.get(4),
+            max: row.get(5),
+            percentiles: percentiles.into_iter().zip(values).collect(),
+        }
+    }))
+}
+
+/// Percentile of `value` for its date at `site_code`, if statistics are stored
+pub fn percentile_at(
+    client: &mut Client,
+    site_code: &str,
+    parameter_code: &str,
+    value: f64,
+    time: DateTime<Utc>,
+) -> Result<Option<f64>, String> {
+    let (month, day) = stats_day(time);
+    Ok(load(client, site_code, parameter_code, month, day)?.and_then(|s| s.percentile_of(value)))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const SAMPLE: &str = "\
+# U.S. Geological Survey
+# Daily statistics
+agency_cd\tsite_no\tparameter_cd\tts_id\tloc_web_ds\tmonth_nu\tday_nu\tbegin_yr\tend_yr\tcount_nu\tmax_va_yr\tmax_va\tmin_va_yr\tmin_va\tmean_va\tp05_va\tp10_va\tp20_va\tp25_va\tp50_va\tp75_va\tp80_va\tp90_va\tp95_va
+5s\t15s\t5s\t10n\t15s\t3n\t3n\t6n\t6n\t8n\t6n\t12s\t6n\t12s\t12n\t12s\t12s\t12s\t12s\t12s\t12s\t12s\t12s\t12s
+USGS\t05568500\t00060\t45678\t\t5\t1\t1940\t2023\t84\t2013\t85000\t1964\t4000\t24000\t6000\t8000\t11000\t13000\t21000\t32000\t36000\t44000\t52000
+USGS\t05568500\t00060\t99999\tOld sensor\t5\t1\t1990\t1995\t6\t1993\t60000\t1991\t9000\t20000\t\t\t\t\t19000\t\t\t\t
+USGS\t05568500\t00060\t45678\t\t2\t30\t1940\t2023\t84\t2013\t1\t1964\t1\t1\t1\t1\t1\t1\t1\t1\t1\t1\t1
+USGS\t05568500\t00065\t45679\t\t2\t29\t1990\t2023\t9\t2013\t18.5\t1991\t6.1\t10.0\t\t\t\t\t9.5\t\t\t\t
+";
+
+    #[test]
+    fn test_parse_stats_rdb() {
+        let stats = parse_rdb(SAMPLE).unwrap();
+        assert_eq!(stats.len(), 2, "Feb 30 skipped, shorter duplicate dropped");
+
+        let may1 = &stats[0];
+        assert_eq!((may1.month, may1.day), (5, 1));
+        assert_eq!((may1.begin_year, may1.end_year, may1.count), (Some(1940), Some(2023), Some(84)));
+        assert_eq!(may1.min, Some(4000.0));
+        assert_eq!(may1.median(), Some(21000.0));
+        assert_eq!(may1.percentiles.len(), 9);
+
+        let leap = &stats[1];
+        assert_eq!((leap.parameter_code.as_str(), leap.month, leap.day), ("00065", 2, 29));
+        assert_eq!(leap.percentiles, vec![(50.0, 9.5)]);
+
+        assert!(parse_rdb("agency_cd\tsite_no\n5s\t15s\n").is_err());
+    }
+
+    #[test]
+    fn test_percentile_interpolates_and_clamps() {
+        let may1 = parse_rdb(SAMPLE).unwrap().remove(0);
+        assert_eq!(may1.percentile_of(21000.0), Some(50.0));
+        assert_eq!(may1.percentile_of(48000.0), Some(92.5));
+        assert_eq!(may1.percentile_of(68500.0), Some(97.5));
+        assert_eq!(may1.percentile_of(3000.0), Some(0.0));
+        assert_eq!(may1.percentile_of(90000.0), Some(100.0));
+
+        assert_eq!(may1.describe(48000.0).unwrap(), "at the 93rd percentile for May 1 (1940-2023)");
+        assert_eq!(may1.describe(90000.0).unwrap(), "above the record high for May 1 (1940-2023)");
+        assert_eq!(ordinal_suffix(11), "th");
+        assert_eq!(ordinal_suffix(21), "st");
+
+        // Just after midnight UTC is still the previous day in Illinois
+        assert_eq!(stats_day(Utc.with_ymd_and_hms(2024, 5, 2, 3, 0, 0).unwrap()), (5, 1));
+    }
+}
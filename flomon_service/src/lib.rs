@@ -1,43 +1,149 @@
-/// flomon_service: Peoria Illinois River flood risk monitoring service.
-///
-/// # Module structure
-///
-/// ```text
-/// flomon_service
-/// +-- model       - shared data types (GaugeReading, FloodThresholds, NwisError, ...)
-/// +-- config      - station registry configuration loader (usgs_stations.toml)
-/// +-- stations    - USGS site code registry with NWS flood stage thresholds
-/// +-- zones       - Hydrological zone-based sensor grouping (zones.toml)
-/// +-- usace_locations - USACE/CWMS location registry (usace_stations.toml)
-/// +-- asos_locations - ASOS station registry (iem_asos.toml)
-/// +-- daemon      - main daemon loop (startup, backfill, polling, warehousing)
-/// +-- endpoint    - Zone-based HTTP API for flood monitoring
-/// +-- ingest
-/// |   +-- usgs    - USGS NWIS IV API: URL construction + JSON parsing
-/// |   +-- cwms    - USACE CWMS API: timeseries data retrieval
-/// |   +-- iem     - IEM/ASOS weather data API client
-/// |   +-- fixtures (test only) - representative API response payloads
-/// +-- monitor     - real-time staleness tracking (hybrid DB + in-memory)
-/// +-- alert
-/// |   +-- thresholds - flood stage severity evaluation
-/// |   +-- staleness  - gauge reading freshness checking
-/// +-- analysis
-///     +-- grouping   - organizes flat readings into per-site or per-zone structs
-/// ```
+//! flomon_service: Peoria Illinois River flood risk monitoring service.
+//!
+//! # Module structure
+//!
+//! ```text
+//! flomon_service
+//! +-- model       - shared data types (GaugeReading, FloodThresholds, IngestError, ...)
+//! +-- units       - canonical units per parameter and typed quantities (Stage, Discharge, ...)
+//! +-- config      - station registry configuration loader (usgs_stations.toml)
+//! |   +-- service - consolidated flomon.toml (includes, env interpolation, validation)
+//! +-- stations    - USGS site code registry with NWS flood stage thresholds
+//! +-- datum       - NGVD29/NAVD88/IGLD85 and gauge zero: per-site offsets onto one vertical datum
+//! +-- registry    - registry version snapshots and threshold change audit
+//! +-- threshold_overrides - per-station flood stages from the database, registry as fallback
+//! +-- plot        - terminal sparkline / hydrograph rendering (flomon plot)
+//! +-- cli_output  - stable --json schemas for the informational CLI commands
+//! +-- attribution - USGS/USACE/IEM/NWS credits attached to API responses, exports and the widget
+//! +-- zones       - Hydrological zone-based sensor grouping (zones.toml)
+//! |   +-- status  - per-cycle zone rollup: worst severity, fastest rise, stale sensors, rain
+//! +-- usace_locations - USACE/CWMS location registry (usace_stations.toml)
+//! +-- asos_locations - ASOS station registry (iem_asos.toml)
+//! +-- daemon      - main daemon loop (startup, backfill, polling, warehousing)
+//! +-- backfill    - chunked multi-year USGS DV/IV backfill, resumable from checkpoints
+//! +-- db          - connection setup and schema validation
+//! |   +-- bulk    - binary COPY into staging tables for backfill-sized batches
+//! +-- conditions  - zone detail, basin status and widget summary queries
+//! +-- endpoint    - Zone-based HTTP API for flood monitoring
+//! |   +-- api     - dashboard routes (/api/stations, /api/zones, /api/alerts/active) and their 404s
+//! +-- status_cache - snapshot file of current conditions served without DB queries
+//! +-- tls         - HTTPS termination in front of the endpoint (rustls)
+//! +-- basin_map   - precomputed GeoJSON map layers served with ETags
+//! +-- geojson     - live station/zone FeatureCollections (stage, severity color, thresholds)
+//! +-- cams        - webcam snapshots on severity transitions, linked from alerts
+//! +-- auth        - bearer token length check and comparison for the protected routes
+//! +-- field_obs   - manual high-water marks, crest stakes, photos, sandbagging
+//! +-- event_report - incident timeline for a flood event as Markdown/HTML (flomon event report)
+//! +-- quality     - USGS qualifiers and CWMS quality codes on one scale; what alerts may act on
+//! +-- data_quality - detected data problems (datum shifts) awaiting manual review
+//! +-- manual_readings - staff gauge readings reported by SMS/email (source MANUAL)
+//! +-- nws_geo     - NWS forecast zone / county FIPS per station and zone (CAP alert relevance)
+//! +-- forecasts   - stage forecasts by issuing agency (flood_analysis.stage_forecasts) + verification
+//! +-- archive     - compressed long-term reading archive + hot/archive union queries
+//! +-- export      - station x time gridded reading exports (flomon export)
+//! |   +-- netcdf  - minimal NetCDF classic (CDF-2) writer
+//! +-- cwms_archive - delta-encoded (run-length) CWMS storage + series reconstruction
+//! +-- testing (feature "testing") - fixture server with recorded USGS/CWMS/IEM responses
+//! +-- ingest
+//! |   +-- base_urls - USGS/CWMS/IEM/NWS API roots ([endpoints]): mirrors, proxies, the fixture server
+//! |   +-- usgs    - USGS NWIS IV API: URL construction + JSON parsing
+//! |   +-- usgs_rdb - USGS NWIS RDB (tab-delimited) fallback parser
+//! |   +-- usgs_peaks - USGS annual peak flows and stages: where a stage ranks in the record
+//! |   +-- usgs_stats - USGS daily statistics: percentile of the current value for its date
+//! |   +-- cwms    - USACE CWMS API: timeseries data retrieval
+//! |   +-- cwms_quality - CWMS quality bitfield (screened/questionable/rejected/estimated)
+//! |   +-- iem     - IEM/ASOS weather data API client
+//! |   +-- mrms    - MRMS radar QPE grids averaged per basin and zone (precip_grid_summary)
+//! |   +-- nws_alerts - api.weather.gov flood watches/warnings for the local counties (nws.active_alerts)
+//! |   +-- isws    - ISWS/IDNR Peoria pool stage product, a forecast source for verification
+//! |   +-- fetch   - concurrent poll-cycle fetching (tokio): bounded concurrency, per-source timeouts
+//! |   +-- retry   - exponential backoff with jitter for every ingest HTTP request
+//! |   +-- rating  - USGS stage-discharge ratings: stage_to_discharge / discharge_to_stage
+//! |   +-- plugin  - DataSourcePlugin trait + registry for community/private sources
+//! |   +-- local_sensors - private dock stage / rain gauge over MQTT (source local_mqtt)
+//! |   +-- mqtt    - minimal MQTT 3.1.1 subscriber (QoS 0/1)
+//! |   +-- fixtures (test only) - representative API response payloads
+//! +-- stream      - live readings/transitions: daemon NOTIFY -> server LISTEN -> SSE clients
+//! +-- shutdown    - SIGINT/SIGTERM flag checked by the daemon loop
+//! +-- supervisor  - panic capture and restart-with-backoff for long-running tasks
+//! +-- monitor     - real-time staleness tracking (hybrid DB + in-memory)
+//! |   +-- adaptive - poll-interval tightening while stations are elevated (audited)
+//! |   +-- cadence  - learned per-feed reporting cadence; overdue-reading alerts
+//! |   +-- catchup  - rate-limited, oldest-first backfill after daemon downtime
+//! |   +-- event_mode - system-wide Normal/Watch/Event/Recovery state driving polling and the banner
+//! |   +-- schedule - per-feed next-poll times at each feed's own interval, tightened during floods
+//! |   +-- suppression - stations/sensors down for maintenance until a time: expected failures, no alerts
+//! +-- alert
+//! |   +-- thresholds - flood stage severity evaluation
+//! |   +-- transitions - per-station severity level changes
+//! |   +-- staleness  - gauge reading freshness checking
+//! |   +-- subscriptions - per-zone notification recipients and severity floors
+//! |   +-- queue      - pending deliveries: shutdown drain + redelivery at start
+//! |   +-- dispatch   - Notifier channels (email, webhook, ntfy) chosen by recipient, per-channel severity floor
+//! |   +-- matrix     - delivery to a Matrix room (`matrix` recipients)
+//! |   +-- occupancy  - per-zone occupancy (schedule, API, MQTT presence); quiet alerts for empty zones
+//! |   +-- scheme     - configurable severity names, colors and order for all outputs
+//! |   +-- precip_alarm - basin/station rainfall alarm that fires before the rivers respond
+//! |   +-- outlook    - "flooding likely within N h" from rain, upstream rises and lag estimates
+//! +-- analysis
+//!     +-- anomaly    - median/MAD robust z-score flagging suspect readings at ingest
+//!     +-- backwater  - pool/tailwater head and Grafton stage: backwater reaching Peoria
+//!     +-- events     - flood event segmentation (action stage -> crest -> recession)
+//!     +-- grouping   - organizes flat readings into per-site or per-zone structs
+//!     +-- freeboard  - critical property elevation minus water surface
+//!     +-- frequency  - Log-Pearson III fit to the annual peaks: "~10-year flow"
+//!     +-- inundation - flooded cells of a per-zone DEM grid as GeoJSON
+//!     +-- lag        - upstream travel time per flow regime from stage cross-correlation
+//!     +-- precip     - rolling 1/3/6/24/72h precipitation per station and basin
+//!     +-- profile    - water surface interpolated by river mile between gauges
+//!     +-- slope      - Peoria-Kingston Mines water-surface slope (backwater signature)
+//!     +-- nowcast    - 60-120 min radar extrapolation rainfall per sub-basin
+//! ```
 
 /// Public modules
 pub mod alert;
 pub mod analysis;
+pub mod api;
+pub mod archive;
 pub mod asos_locations;
+pub mod attribution;
+pub mod auth;
+pub mod backfill;
+pub mod basin_map;
+pub mod cams;
+pub mod cli_output;
+pub mod conditions;
 pub mod config;
+pub mod cwms_archive;
 pub mod daemon;
 pub mod db;
 pub mod endpoint;
+pub mod event_report;
+pub mod export;
+pub mod field_obs;
+pub mod forecasts;
+pub mod geojson;
+pub mod data_quality;
+pub mod datum;
 pub mod ingest;
 pub mod logging;
+pub mod manual_readings;
 pub mod model;
 pub mod monitor;
+pub mod nws_geo;
+pub mod plot;
+pub mod quality;
+pub mod registry;
+pub mod shutdown;
 pub mod stations;
+pub mod status_cache;
+pub mod stream;
+pub mod supervisor;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod threshold_overrides;
+pub mod tls;
+pub mod units;
 pub mod usace_locations;
 pub mod verify;
 pub mod zones;
@@ -1,15 +1,31 @@
-/// Structured logging for flood monitoring service
-///
-/// Provides context-rich logging with site/location identifiers,
-/// timestamps, and severity levels. Supports both console output
-/// and file-based logging for daemon operations.
+//! Structured logging for flood monitoring service
+//!
+//! Provides context-rich logging with site/location identifiers,
+//! timestamps, and severity levels. Supports both console output
+//! and file-based logging for daemon operations.
+//!
+//! With [`LogFormat::Json`] every entry, on the console and in the file,
+//! is one JSON object per line for Loki or Elasticsearch:
+//!
+//! ```text
+//! {"fields":{"error":"HTTP 503","failure_type":"UNEXPECTED","operation":"Poll"},"level":"ERROR",
+//!  "message":"Poll failed [UNEXPECTED]: HTTP 503","site_id":"05568500",
+//!  "source":"USGS","timestamp":"2024-04-20T12:00:00.000Z"}
+//! ```
+//!
+//! `site_id` is null and `fields` empty when an entry has none.
+//! The daemon's `--dry-run` report (`[dry-run] ...` lines) is the one
+//! thing still printed as plain text to stdout.
 
-use chrono::Utc;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::Value;
 use std::fmt;
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::sync::Mutex;
 
+use crate::model::IngestError;
+
 // ---------------------------------------------------------------------------
 // Log Levels
 // ---------------------------------------------------------------------------
@@ -33,11 +49,36 @@ impl fmt::Display for LogLevel {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Output Format
+// ---------------------------------------------------------------------------
+
+/// How entries are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Console lines for people, timestamped lines in the file
+    #[default]
+    Text,
+    /// One JSON object per line, on the console and in the file
+    Json,
+}
+
+impl LogFormat {
+    /// "text" or "json" (`FLOMON_LOG_FORMAT`)
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("Unknown log format {:?} (text or json)", s)),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Data Source Types
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DataSource {
     Usgs,
     Cwms,
@@ -96,26 +137,42 @@ pub struct Logger {
     log_file: Option<String>,
     /// Whether to include timestamps in console output
     console_timestamps: bool,
+    format: LogFormat,
 }
 
 impl Logger {
     /// Initialize the global logger
-    pub fn init(min_level: LogLevel, log_file: Option<String>, console_timestamps: bool) {
+    pub fn init(min_level: LogLevel, log_file: Option<String>, console_timestamps: bool, format: LogFormat) {
         let logger = Logger {
             min_level,
             log_file,
             console_timestamps,
+            format,
         };
         
         *LOGGER.lock().unwrap() = Some(logger);
     }
     
-    /// Log a message with the global logger
-    fn log(&self, level: LogLevel, source: &DataSource, site_id: Option<&str>, message: &str) {
+    /// Log a message with the global logger. `fields` only appear in the
+    /// JSON format; text messages already spell them out.
+    fn log(&self, level: LogLevel, source: &DataSource, site_id: Option<&str>, message: &str, fields: &[(&str, Value)]) {
         if level < self.min_level {
             return;
         }
         
+        if self.format == LogFormat::Json {
+            let entry = json_entry(Utc::now(), level, source, site_id, message, fields);
+            match level {
+                LogLevel::Error | LogLevel::Warning => eprintln!("{}", entry),
+                LogLevel::Info | LogLevel::Debug => println!("{}", entry),
+            }
+            if let Some(ref path) = self.log_file
+                && let Err(e) = Self::append_to_file(path, &entry) {
+                eprintln!("Failed to write to log file {}: {}", path, e);
+            }
+            return;
+        }
+        
         let timestamp = Utc::now().format("%Y-%m-%d %H:%M:%S UTC");
         
         // Format the log entry
@@ -148,10 +205,9 @@ impl Logger {
         }
         
         // File output
-        if let Some(ref path) = self.log_file {
-            if let Err(e) = Self::append_to_file(path, &log_entry) {
-                eprintln!("Failed to write to log file {}: {}", path, e);
-            }
+        if let Some(ref path) = self.log_file
+            && let Err(e) = Self::append_to_file(path, &log_entry) {
+            eprintln!("Failed to write to log file {}: {}", path, e);
         }
     }
     
@@ -165,90 +221,102 @@ impl Logger {
     }
 }
 
+/// One JSON log line
+fn json_entry(
+    timestamp: DateTime<Utc>,
+    level: LogLevel,
+    source: &DataSource,
+    site_id: Option<&str>,
+    message: &str,
+    fields: &[(&str, Value)],
+) -> String {
+    let fields: serde_json::Map<String, Value> = fields.iter()
+        .map(|(key, value)| (key.to_string(), value.clone()))
+        .collect();
+    serde_json::json!({
+        "timestamp": timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+        "level": level.to_string(),
+        "source": source.to_string(),
+        "site_id": site_id,
+        "message": message,
+        "fields": fields,
+    }).to_string()
+}
+
 // ---------------------------------------------------------------------------
 // Public Logging Functions
 // ---------------------------------------------------------------------------
 
 /// Initialize the global logger
-pub fn init_logger(min_level: LogLevel, log_file: Option<&str>, console_timestamps: bool) {
-    Logger::init(min_level, log_file.map(String::from), console_timestamps);
+pub fn init_logger(min_level: LogLevel, log_file: Option<&str>, console_timestamps: bool, format: LogFormat) {
+    Logger::init(min_level, log_file.map(String::from), console_timestamps, format);
 }
 
-/// Log a general informational message
-pub fn info(source: DataSource, site_id: Option<&str>, message: &str) {
+/// Log a message with extra key/values for the JSON format
+pub fn log_fields(level: LogLevel, source: DataSource, site_id: Option<&str>, message: &str, fields: &[(&str, Value)]) {
     if let Some(logger) = LOGGER.lock().unwrap().as_ref() {
-        logger.log(LogLevel::Info, &source, site_id, message);
+        logger.log(level, &source, site_id, message, fields);
     }
 }
 
+/// Log a general informational message
+pub fn info(source: DataSource, site_id: Option<&str>, message: &str) {
+    log_fields(LogLevel::Info, source, site_id, message, &[]);
+}
+
 /// Log a warning message
 pub fn warn(source: DataSource, site_id: Option<&str>, message: &str) {
-    if let Some(logger) = LOGGER.lock().unwrap().as_ref() {
-        logger.log(LogLevel::Warning, &source, site_id, message);
-    }
+    log_fields(LogLevel::Warning, source, site_id, message, &[]);
 }
 
 /// Log an error message
 pub fn error(source: DataSource, site_id: Option<&str>, message: &str) {
-    if let Some(logger) = LOGGER.lock().unwrap().as_ref() {
-        logger.log(LogLevel::Error, &source, site_id, message);
-    }
+    log_fields(LogLevel::Error, source, site_id, message, &[]);
 }
 
 /// Log a debug message
 pub fn debug(source: DataSource, site_id: Option<&str>, message: &str) {
-    if let Some(logger) = LOGGER.lock().unwrap().as_ref() {
-        logger.log(LogLevel::Debug, &source, site_id, message);
-    }
+    log_fields(LogLevel::Debug, source, site_id, message, &[]);
 }
 
 // ---------------------------------------------------------------------------
 // Failure Classification Helpers
 // ---------------------------------------------------------------------------
 
-/// Classify a USGS station failure based on the error type and context
-pub fn classify_usgs_failure(_site_code: &str, error_message: &str) -> FailureType {
-    // Check for known patterns that indicate expected failures
-    
-    // Empty timeSeries or sentinel values often means station is offline
-    if error_message.contains("empty or contained sentinel values")
-        || error_message.contains("No timeSeries entries") {
-        // Some stations are known to be offline or seasonal
-        // This could be expanded with a database of known-offline stations
-        FailureType::Unknown
-    }
-    // HTTP errors might indicate service issues
-    else if error_message.contains("HTTP error") {
-        FailureType::Unexpected
-    }
-    // Parse errors suggest API changes or bugs
-    else if error_message.contains("Parse error") {
-        FailureType::Unexpected
-    }
-    else {
-        FailureType::Unknown
+/// Classify a USGS station failure by the kind of error
+pub fn classify_usgs_failure(_site_code: &str, err: &IngestError) -> FailureType {
+    match err {
+        // Empty timeSeries or sentinel values often means station is offline.
+        // Some stations are known to be offline or seasonal; this could be
+        // expanded with a database of known-offline stations
+        IngestError::NoData(_) => FailureType::Unknown,
+        // HTTP errors might indicate service issues; parse errors suggest
+        // API changes or bugs
+        IngestError::Http(_) | IngestError::RateLimited { .. } | IngestError::Parse(_)
+        | IngestError::InvalidTimestamp(_) => FailureType::Unexpected,
+        IngestError::Timeout(_) | IngestError::Transport(_) => FailureType::Unknown,
     }
 }
 
 /// Classify a CWMS location failure
-pub fn classify_cwms_failure(_location_id: &str, error_message: &str) -> FailureType {
-    if error_message.contains("HTTP") || error_message.contains("timeout") {
-        FailureType::Unexpected
-    } else if error_message.contains("No data") {
-        FailureType::Unknown
-    } else {
-        FailureType::Unknown
+pub fn classify_cwms_failure(_location_id: &str, err: &IngestError) -> FailureType {
+    // "No data" responses fall through to Unknown: the location may simply
+    // not be reporting right now.
+    match err {
+        IngestError::Http(_) | IngestError::RateLimited { .. } | IngestError::Timeout(_) => FailureType::Unexpected,
+        IngestError::NoData(_) | IngestError::Parse(_) | IngestError::InvalidTimestamp(_)
+        | IngestError::Transport(_) => FailureType::Unknown,
     }
 }
 
 /// Classify an ASOS station failure
-pub fn classify_asos_failure(_station_id: &str, error_message: &str) -> FailureType {
-    if error_message.contains("HTTP") || error_message.contains("timeout") {
-        FailureType::Unexpected
-    } else if error_message.contains("No data") {
-        FailureType::Unknown
-    } else {
-        FailureType::Unknown
+pub fn classify_asos_failure(_station_id: &str, err: &IngestError) -> FailureType {
+    // "No data" responses fall through to Unknown: the location may simply
+    // not be reporting right now.
+    match err {
+        IngestError::Http(_) | IngestError::RateLimited { .. } | IngestError::Timeout(_) => FailureType::Unexpected,
+        IngestError::NoData(_) | IngestError::Parse(_) | IngestError::InvalidTimestamp(_)
+        | IngestError::Transport(_) => FailureType::Unknown,
     }
 }
 
@@ -256,61 +324,55 @@ pub fn classify_asos_failure(_station_id: &str, error_message: &str) -> FailureT
 // Structured Failure Logging
 // ---------------------------------------------------------------------------
 
+/// Log `err` at the level of its classification; errors that are not an
+/// `IngestError` (database, I/O) are Unknown
+fn log_failure(
+    source: DataSource,
+    site_id: &str,
+    operation: &str,
+    err: &(dyn std::error::Error + 'static),
+    classify: fn(&str, &IngestError) -> FailureType,
+) {
+    let failure_type = err.downcast_ref::<IngestError>()
+        .map_or(FailureType::Unknown, |e| classify(site_id, e));
+    log_classified_failure(source, site_id, operation, failure_type, &err.to_string());
+}
+
 /// Log a data source failure with automatic classification
-pub fn log_usgs_failure(site_code: &str, operation: &str, err: &dyn std::error::Error) {
-    let error_msg = err.to_string();
-    let failure_type = classify_usgs_failure(site_code, &error_msg);
-    
-    let message = format!(
-        "{} failed [{}]: {}",
-        operation,
-        failure_type,
-        error_msg
-    );
-    
-    match failure_type {
-        FailureType::Expected => debug(DataSource::Usgs, Some(site_code), &message),
-        FailureType::Unexpected => error(DataSource::Usgs, Some(site_code), &message),
-        FailureType::Unknown => warn(DataSource::Usgs, Some(site_code), &message),
-    }
+pub fn log_usgs_failure(site_code: &str, operation: &str, err: &(dyn std::error::Error + 'static)) {
+    log_failure(DataSource::Usgs, site_code, operation, err, classify_usgs_failure);
 }
 
 /// Log a CWMS failure with classification
-pub fn log_cwms_failure(location_id: &str, operation: &str, err: &dyn std::error::Error) {
-    let error_msg = err.to_string();
-    let failure_type = classify_cwms_failure(location_id, &error_msg);
-    
-    let message = format!(
-        "{} failed [{}]: {}",
-        operation,
-        failure_type,
-        error_msg
-    );
-    
-    match failure_type {
-        FailureType::Expected => debug(DataSource::Cwms, Some(location_id), &message),
-        FailureType::Unexpected => error(DataSource::Cwms, Some(location_id), &message),
-        FailureType::Unknown => warn(DataSource::Cwms, Some(location_id), &message),
-    }
+pub fn log_cwms_failure(location_id: &str, operation: &str, err: &(dyn std::error::Error + 'static)) {
+    log_failure(DataSource::Cwms, location_id, operation, err, classify_cwms_failure);
 }
 
 /// Log an ASOS failure with classification
-pub fn log_asos_failure(station_id: &str, operation: &str, err: &dyn std::error::Error) {
-    let error_msg = err.to_string();
-    let failure_type = classify_asos_failure(station_id, &error_msg);
-    
-    let message = format!(
-        "{} failed [{}]: {}",
-        operation,
-        failure_type,
-        error_msg
-    );
-    
-    match failure_type {
-        FailureType::Expected => debug(DataSource::Asos, Some(station_id), &message),
-        FailureType::Unexpected => error(DataSource::Asos, Some(station_id), &message),
-        FailureType::Unknown => warn(DataSource::Asos, Some(station_id), &message),
-    }
+pub fn log_asos_failure(station_id: &str, operation: &str, err: &(dyn std::error::Error + 'static)) {
+    log_failure(DataSource::Asos, station_id, operation, err, classify_asos_failure);
+}
+
+/// Log a failure the caller has already classified, e.g. one inside a
+/// maintenance window (see `monitor::maintenance`)
+pub fn log_classified_failure(
+    source: DataSource,
+    site_id: &str,
+    operation: &str,
+    failure_type: FailureType,
+    error_msg: &str,
+) {
+    let message = format!("{} failed [{}]: {}", operation, failure_type, error_msg);
+    let level = match failure_type {
+        FailureType::Expected => LogLevel::Debug,
+        FailureType::Unexpected => LogLevel::Error,
+        FailureType::Unknown => LogLevel::Warning,
+    };
+    log_fields(level, source, Some(site_id), &message, &[
+        ("operation", Value::from(operation)),
+        ("failure_type", Value::from(failure_type.to_string())),
+        ("error", Value::from(error_msg)),
+    ]);
 }
 
 // ---------------------------------------------------------------------------
@@ -326,13 +388,18 @@ pub fn log_backfill_summary(source: DataSource, total: usize, successful: usize,
         failed
     );
     
-    if failed == 0 {
-        info(source, None, &message);
+    let level = if failed == 0 {
+        LogLevel::Info
     } else if successful == 0 {
-        error(source, None, &message);
+        LogLevel::Error
     } else {
-        warn(source, None, &message);
-    }
+        LogLevel::Warning
+    };
+    log_fields(level, source, None, &message, &[
+        ("total", Value::from(total)),
+        ("successful", Value::from(successful)),
+        ("failed", Value::from(failed)),
+    ]);
 }
 
 #[cfg(test)]
@@ -348,12 +415,36 @@ mod tests {
     
     #[test]
     fn test_failure_classification() {
-        let empty_series_error = "No data available for site: No timeSeries entries in response";
-        let result = classify_usgs_failure("05568500", empty_series_error);
-        assert_eq!(result, FailureType::Unknown);
-        
-        let http_error = "HTTP error: 500";
-        let result = classify_usgs_failure("05568500", http_error);
-        assert_eq!(result, FailureType::Unexpected);
+        let empty_series = IngestError::NoData("No timeSeries entries in response".to_string());
+        assert_eq!(classify_usgs_failure("05568500", &empty_series), FailureType::Unknown);
+        assert_eq!(classify_usgs_failure("05568500", &IngestError::Http(500)), FailureType::Unexpected);
+        assert_eq!(classify_usgs_failure("05568500", &IngestError::Parse("bad".to_string())), FailureType::Unexpected);
+
+        let timeout = IngestError::Timeout("operation timed out".to_string());
+        assert_eq!(classify_cwms_failure("Peoria-Pool", &timeout), FailureType::Unexpected);
+        assert_eq!(classify_cwms_failure("Peoria-Pool", &empty_series), FailureType::Unknown);
+        assert_eq!(classify_asos_failure("KPIA", &IngestError::RateLimited { retry_after: None }), FailureType::Unexpected);
+    }
+
+    #[test]
+    fn test_json_entry_is_one_object_per_line() {
+        let time = DateTime::parse_from_rfc3339("2024-04-20T12:00:00Z").unwrap().with_timezone(&Utc);
+        let line = json_entry(time, LogLevel::Warning, &DataSource::Usgs, Some("05568500"),
+            "Poll failed:\nHTTP 503", &[("operation", Value::from("Poll"))]);
+        assert!(!line.contains('\n'));
+        let entry: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(entry["timestamp"], "2024-04-20T12:00:00.000Z");
+        assert_eq!(entry["level"], "WARN");
+        assert_eq!(entry["source"], "USGS");
+        assert_eq!(entry["site_id"], "05568500");
+        assert_eq!(entry["message"], "Poll failed:\nHTTP 503");
+        assert_eq!(entry["fields"]["operation"], "Poll");
+
+        let bare: Value = serde_json::from_str(&json_entry(time, LogLevel::Info, &DataSource::System, None, "ok", &[])).unwrap();
+        assert!(bare["site_id"].is_null());
+        assert_eq!(bare["fields"], serde_json::json!({}));
+
+        assert_eq!(LogFormat::parse("JSON").unwrap(), LogFormat::Json);
+        assert!(LogFormat::parse("logfmt").is_err());
     }
 }
@@ -10,45 +10,463 @@
 //! Complex statistical analysis and regression modeling are handled
 //! by external Python scripts that read from the curated database.
 //!
-//! Usage:
-//!   cargo run --release -- verify          # Verify data source configuration
-//!   cargo run --release -- --endpoint 8080 # Start daemon with HTTP endpoint on port 8080
+//! Ingestion and the HTTP API can run as separate processes sharing the
+//! same database, so the API can be restarted or scaled independently of
+//! the polling loop and a crash in one does not take down the other.
+//!
+//! Usage (`flomon --help` and `flomon <command> --help` list every option):
+//!   flomon verify                # Verify data source configuration
+//!   flomon status                # Basin flood status
+//!   flomon alerts list           # Stations at or above action stage
+//!   flomon alerts rules          # Composite rule counters (active and shadow)
+//!   flomon station show SITE     # Registry entry + latest readings
+//!   (add --json to informational commands for a stable JSON document)
+//!   flomon migrate [--status] [--baseline N]  # Apply embedded SQL migrations
+//!   flomon ingest                # Backfill + continuous polling (no HTTP)
+//!   flomon ingest --dry-run      # One cycle, printing would-be inserts
+//!   flomon ingest --source usgs --once  # One cycle of one source, then exit
+//!   flomon backfill --site 05568500 --start 2013-01-01  # Fill a station's history
+//!   flomon serve [--port PORT]   # HTTP API only (default port 8080)
+//!   flomon --endpoint 8080       # Combined: polling loop + HTTP endpoint
+//!   flomon field-obs add|list    # Record or list manual field observations
+//!   flomon data-quality list|review  # Datum shifts and other data problems to review
+//!   flomon subscribe add|remove|list  # Manage zone notification subscriptions
+//!   flomon archive [--days N] [--dry-run]  # Move old readings to the archive
+//!   flomon archive --cwms [--days N] [--dry-run]  # Delta-encode old CWMS rows
+//!   flomon registry history [--site CODE]  # Station registry / threshold change log
+//!   flomon plot SITE [--hours N] [--width N]  # Terminal stage hydrograph
+//!   flomon event detect|list    # Flood events segmented from stage history
+//!   flomon lag fit|show          # Upstream travel times to Kingston Mines per flow regime
+//!   flomon export --start T --end T --out FILE  # Gridded readings as NetCDF
+//!   flomon geo resolve [--dry-run] | geo show  # NWS zone/county mapping for alert relevance
+//!
+//! Configuration:
+//!   flomon.toml (or $FLOMON_CONFIG) - consolidated service configuration;
+//!   when absent, the individual station TOML files and DATABASE_URL are used
 //!
 //! Environment:
 //!   DATABASE_URL - PostgreSQL connection string
 
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use flomon_service::alert::subscriptions;
+use flomon_service::archive;
+use flomon_service::cli_output;
+use flomon_service::alert::occupancy;
+use flomon_service::alert::thresholds::FloodSeverity;
+use flomon_service::config::{self, ServiceConfig};
 use flomon_service::daemon::Daemon;
+use flomon_service::data_quality;
 use flomon_service::endpoint;
-use flomon_service::logging::{self, LogLevel};
+use flomon_service::event_report;
+use flomon_service::field_obs;
+use flomon_service::ingest::local_sensors;
+use flomon_service::logging::{self, LogFormat, LogLevel};
+use flomon_service::monitor::maintenance;
+use flomon_service::nws_geo;
+use flomon_service::plot;
+use flomon_service::registry;
+use flomon_service::supervisor;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 
+/// Default port for `flomon serve`
+const DEFAULT_SERVE_PORT: u16 = 8080;
+
+/// Commands that accept `--json` (see `cli_output`)
+const JSON_COMMANDS: &[&str] = &["verify", "status", "alerts", "station", "registry", "field-obs", "data-quality", "subscribe"];
+
+/// Illinois River flood monitoring service
+#[derive(Debug, Parser)]
+#[command(name = "flomon", version, about)]
+struct Cli {
+    /// Print one JSON document on stdout (verify, status, alerts, station,
+    /// registry, field-obs, data-quality, subscribe)
+    #[arg(long, global = true)]
+    json: bool,
+    
+    /// Without a command: run the polling loop with the HTTP endpoint on PORT
+    #[arg(long, value_name = "PORT")]
+    endpoint: Option<u16>,
+    
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Verify data source configuration and write verification_report.json
+    Verify,
+    /// Basin flood status
+    Status,
+    /// Active alerts and composite rule counters
+    Alerts {
+        #[command(subcommand)]
+        command: AlertsCommand,
+    },
+    /// Station registry entries
+    Station {
+        #[command(subcommand)]
+        command: StationCommand,
+    },
+    /// Apply pending embedded SQL migrations
+    Migrate(MigrateArgs),
+    /// Run backfill and the polling loop only (no HTTP)
+    Ingest(IngestOptions),
+    /// Fetch a USGS station's history over a date range, resumable
+    Backfill(BackfillArgs),
+    /// Run the HTTP API only
+    Serve {
+        /// Listen port [default: flomon.toml endpoint port, else 8080]
+        #[arg(long)]
+        port: Option<u16>,
+    },
+    /// Record or list manual field observations
+    FieldObs {
+        #[command(subcommand)]
+        command: FieldObsCommand,
+    },
+    /// Datum shifts and other data problems awaiting review
+    DataQuality {
+        #[command(subcommand)]
+        command: DataQualityCommand,
+    },
+    /// Manage zone notification subscriptions
+    Subscribe {
+        #[command(subcommand)]
+        command: SubscribeCommand,
+    },
+    /// Move old readings to the archive, or delta-encode old CWMS rows
+    Archive(ArchiveArgs),
+    /// Station registry and threshold change log
+    Registry {
+        #[command(subcommand)]
+        command: RegistryCommand,
+    },
+    /// Stage hydrograph in the terminal
+    Plot(PlotArgs),
+    /// Flood events segmented from stage history
+    Event {
+        #[command(subcommand)]
+        command: EventCommand,
+    },
+    /// Upstream travel times per flow regime
+    Lag {
+        #[command(subcommand)]
+        command: LagCommand,
+    },
+    /// Stage and discharge per station on a regular grid as CF NetCDF
+    Export(ExportArgs),
+    /// NWS forecast zone / county mapping used to filter CAP alerts
+    Geo {
+        #[command(subcommand)]
+        command: GeoCommand,
+    },
+}
+
+impl Command {
+    /// Whether `--json` applies (`JSON_COMMANDS`)
+    fn supports_json(&self) -> bool {
+        matches!(
+            self,
+            Command::Verify
+                | Command::Status
+                | Command::Alerts { .. }
+                | Command::Station { .. }
+                | Command::Registry { .. }
+                | Command::FieldObs { .. }
+                | Command::DataQuality { .. }
+                | Command::Subscribe { .. }
+        )
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum AlertsCommand {
+    /// Stations currently at or above action stage
+    List,
+    /// Evaluations, matches and firings per [[alert_rules]] rule
+    Rules,
+}
+
+#[derive(Debug, Subcommand)]
+enum StationCommand {
+    /// Registry entry, thresholds, and latest readings
+    Show { site: String },
+}
+
+#[derive(Debug, Args)]
+struct MigrateArgs {
+    /// List embedded migrations and whether each is applied
+    #[arg(long, conflicts_with = "baseline")]
+    status: bool,
+    /// Record migrations through N as applied (databases set up by hand)
+    #[arg(long, value_name = "N")]
+    baseline: Option<i32>,
+}
+
+/// `flomon ingest` switches
+#[derive(Debug, Clone, Copy, Default, Args)]
+struct IngestOptions {
+    /// One cycle, printing would-be writes
+    #[arg(long)]
+    dry_run: bool,
+    /// One cycle, then exit
+    #[arg(long)]
+    once: bool,
+    /// Poll only this source (usgs, cwms or asos)
+    #[arg(long, value_parser = parse_source)]
+    source: Option<maintenance::Source>,
+}
+
+#[derive(Debug, Args)]
+struct BackfillArgs {
+    /// USGS site code
+    #[arg(long)]
+    site: String,
+    /// First day (YYYY-MM-DD or RFC 3339)
+    #[arg(long, value_parser = parse_date)]
+    start: DateTime<Utc>,
+    /// Last day (YYYY-MM-DD or RFC 3339) [default: now]
+    #[arg(long, value_parser = parse_date)]
+    end: Option<DateTime<Utc>>,
+    /// Print would-be writes; no checkpoints recorded
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum FieldObsCommand {
+    /// Record an observation
+    Add {
+        /// high_water_mark (hwm), crest_stake, photo, sandbagging
+        #[arg(long, value_parser = parse_field_obs_kind)]
+        kind: field_obs::FieldObsKind,
+        /// Observation time (RFC 3339)
+        #[arg(long, value_parser = parse_time)]
+        time: DateTime<Utc>,
+        #[arg(long)]
+        site: Option<String>,
+        /// Flood event id
+        #[arg(long)]
+        event: Option<i32>,
+        /// Water level in feet (with --datum)
+        #[arg(long)]
+        value: Option<f64>,
+        #[arg(long)]
+        datum: Option<String>,
+        #[arg(long)]
+        lat: Option<f64>,
+        #[arg(long)]
+        lon: Option<f64>,
+        /// Photo path or URL
+        #[arg(long)]
+        media: Option<String>,
+        #[arg(long)]
+        notes: Option<String>,
+        #[arg(long)]
+        observer: Option<String>,
+    },
+    /// List observations, newest first
+    List {
+        #[arg(long)]
+        site: Option<String>,
+        #[arg(long)]
+        event: Option<i32>,
+        #[arg(long, value_parser = parse_field_obs_kind)]
+        kind: Option<field_obs::FieldObsKind>,
+        #[arg(long)]
+        limit: Option<i64>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum DataQualityCommand {
+    /// List data-quality alerts
+    List {
+        /// open, confirmed or dismissed
+        #[arg(long, value_parser = parse_review_status)]
+        status: Option<data_quality::Status>,
+        #[arg(long)]
+        limit: Option<i64>,
+    },
+    /// Confirm or dismiss an alert
+    Review {
+        id: i64,
+        /// confirmed or dismissed
+        #[arg(value_parser = parse_review_status)]
+        status: data_quality::Status,
+        /// Reviewer name
+        #[arg(long)]
+        by: String,
+        #[arg(long)]
+        note: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SubscribeCommand {
+    /// Subscribe a recipient to one or more zones
+    Add {
+        /// Channel name, channel:address, or email address
+        #[arg(long)]
+        recipient: String,
+        /// Zone ids, e.g. 2,3
+        #[arg(long, required = true, value_delimiter = ',')]
+        zones: Vec<usize>,
+        /// action, flood, moderate or major
+        #[arg(long, value_parser = parse_severity, default_value = "flood")]
+        min_severity: FloodSeverity,
+    },
+    /// Remove a recipient's subscriptions (all zones unless --zone)
+    Remove {
+        #[arg(long)]
+        recipient: String,
+        #[arg(long)]
+        zone: Option<usize>,
+    },
+    /// Stored and configured subscriptions
+    List,
+}
+
+#[derive(Debug, Args)]
+struct ArchiveArgs {
+    /// Delta-encode CWMS rows instead of archiving USGS readings
+    #[arg(long)]
+    cwms: bool,
+    /// Hot retention window [default: 365, or 30 with --cwms]
+    #[arg(long, value_parser = clap::value_parser!(i64).range(1..))]
+    days: Option<i64>,
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum RegistryCommand {
+    /// Registry and threshold change log
+    History {
+        #[arg(long)]
+        site: Option<String>,
+    },
+}
+
+#[derive(Debug, Args)]
+struct PlotArgs {
+    site: String,
+    #[arg(long, default_value_t = 72, value_parser = clap::value_parser!(i64).range(1..))]
+    hours: i64,
+    #[arg(long, default_value_t = plot::DEFAULT_WIDTH as i64, value_parser = clap::value_parser!(i64).range(1..))]
+    width: i64,
+}
+
+#[derive(Debug, Subcommand)]
+enum EventCommand {
+    /// Incident timeline for a flood event
+    Report {
+        id: i32,
+        /// markdown or html
+        #[arg(long, value_parser = parse_report_format, default_value = "markdown")]
+        format: event_report::Format,
+        /// Write to FILE instead of stdout
+        #[arg(long, value_name = "FILE")]
+        out: Option<String>,
+    },
+    /// Find flood events in stored stage history
+    Detect {
+        #[arg(long)]
+        site: Option<String>,
+        #[arg(long, default_value_t = 365, value_parser = clap::value_parser!(i64).range(1..))]
+        days: i64,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Stored flood events with crest and duration
+    List {
+        #[arg(long)]
+        site: Option<String>,
+        #[arg(long, default_value_t = 365 * 10, value_parser = clap::value_parser!(i64).range(1..))]
+        days: i64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum LagCommand {
+    /// Cross-correlate upstream stage rises against a downstream gauge
+    Fit {
+        /// Downstream site (default Kingston Mines)
+        #[arg(long, default_value = "05568500")]
+        downstream: String,
+        #[arg(long, default_value_t = 365, value_parser = clap::value_parser!(i64).range(1..))]
+        days: i64,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Stored upstream travel times per flow regime
+    Show,
+}
+
+#[derive(Debug, Args)]
+struct ExportArgs {
+    /// Grid start (RFC 3339)
+    #[arg(long, value_parser = parse_time)]
+    start: DateTime<Utc>,
+    /// Grid end (RFC 3339)
+    #[arg(long, value_parser = parse_time)]
+    end: DateTime<Utc>,
+    #[arg(long, value_name = "FILE")]
+    out: String,
+    /// Grid spacing in minutes
+    #[arg(long, default_value_t = flomon_service::export::DEFAULT_INTERVAL_MINUTES)]
+    interval: i64,
+    /// Site codes to include, e.g. 05568500,05567500 [default: all]
+    #[arg(long, value_delimiter = ',')]
+    sites: Vec<String>,
+    #[arg(long, value_enum, default_value = "netcdf")]
+    format: ExportFormat,
+}
+
+/// `flomon export --format`
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    #[value(alias = "nc")]
+    Netcdf,
+}
+
+#[derive(Debug, Subcommand)]
+enum GeoCommand {
+    /// Look up and store NWS forecast zone / county per station and zone
+    Resolve {
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Stored NWS geography used to filter CAP alerts
+    Show,
+}
+
 fn main() {
-    println!("🌊 Flood Monitoring Service");
-    println!("============================\n");
+    // Parse first so `--help` and `--version` print nothing else.
+    // With `--json`, stdout carries only the document.
+    let cli = Cli::parse();
+    let json = cli.json;
     
-    // Parse command-line arguments early to check for verify command
-    let args: Vec<String> = env::args().collect();
+    if cli.endpoint.is_some() && cli.command.is_some() {
+        Cli::command()
+            .error(ErrorKind::ArgumentConflict, "--endpoint is only valid without a command (use `serve --port`)")
+            .exit();
+    }
+    if json && !cli.command.as_ref().is_some_and(Command::supports_json) {
+        eprintln!("❌ --json is supported by: {}", JSON_COMMANDS.join(", "));
+        std::process::exit(1);
+    }
+    if !json {
+        println!("🌊 Flood Monitoring Service");
+        println!("============================\n");
+    }
     
     // Check for verify command (runs without daemon initialization)
-    if args.len() > 1 && args[1] == "verify" {
-        println!("🔍 Running data source verification...\n");
-        
-        match flomon_service::verify::run_full_verification() {
-            Ok(report) => {
-                flomon_service::verify::print_summary(&report);
-                
-                // Save JSON report
-                let report_json = serde_json::to_string_pretty(&report).unwrap();
-                std::fs::write("verification_report.json", report_json).unwrap();
-                println!("\n📄 Detailed report saved to: verification_report.json");
-                
-                std::process::exit(0);
-            }
-            Err(e) => {
-                eprintln!("❌ Verification failed: {}", e);
-                std::process::exit(1);
-            }
-        }
+    if let Some(Command::Verify) = cli.command {
+        run_verify(json);
     }
     
     // Initialize logging system
@@ -56,150 +474,453 @@ fn main() {
     let log_file = "./flomon_service.log";
     let log_level = LogLevel::Info;  // Change to Debug for verbose output
     let console_timestamps = false;  // Clean console output, timestamps in file
+    // FLOMON_LOG_FORMAT=json: one JSON object per line for log shippers
+    let log_format = match env::var("FLOMON_LOG_FORMAT") {
+        Ok(format) => LogFormat::parse(&format).unwrap_or_else(|e| {
+            eprintln!("❌ FLOMON_LOG_FORMAT: {}", e);
+            std::process::exit(1);
+        }),
+        Err(_) => LogFormat::default(),
+    };
     
-    logging::init_logger(log_level, Some(log_file), console_timestamps);
-    println!("📝 Logging to {}\n", log_file);
+    logging::init_logger(log_level, Some(log_file), console_timestamps, log_format);
+    if !json {
+        println!("📝 Logging to {}\n", log_file);
+    }
     
-    // Parse remaining command-line arguments
-    let mut endpoint_port: Option<u16> = None;
+    let service_config = load_service_config(json);
     
-    let mut i = 1;
-    while i < args.len() {
-        match args[i].as_str() {
-            "--endpoint" => {
-                if i + 1 < args.len() {
-                    endpoint_port = args[i + 1].parse().ok();
-                    i += 2;
-                } else {
-                    eprintln!("Error: --endpoint requires a port number");
-                    std::process::exit(1);
-                }
+    match cli.command {
+        Some(Command::Verify) => unreachable!("handled before logging"),
+        Some(Command::Ingest(options)) => run_ingest(None, service_config.as_ref(), options),
+        Some(Command::Backfill(args)) => run_backfill(args, service_config.as_ref()),
+        Some(Command::Migrate(args)) => run_migrate(args),
+        Some(Command::Status) => run_status(json),
+        Some(Command::Alerts { command }) => run_alerts(command, json),
+        Some(Command::Station { command: StationCommand::Show { site } }) => run_station(&site, json),
+        Some(Command::FieldObs { command }) => run_field_obs(command, json),
+        Some(Command::DataQuality { command }) => run_data_quality(command, json),
+        Some(Command::Subscribe { command }) => run_subscribe(command, service_config.as_ref(), json),
+        Some(Command::Archive(args)) => run_archive(args),
+        Some(Command::Registry { command: RegistryCommand::History { site } }) => run_registry(site.as_deref(), json),
+        Some(Command::Plot(args)) => run_plot(args),
+        Some(Command::Event { command }) => run_event(command),
+        Some(Command::Lag { command }) => run_lag(command),
+        Some(Command::Export(args)) => run_export(args),
+        Some(Command::Geo { command }) => run_geo(command),
+        Some(Command::Serve { port }) => {
+            let configured_port = service_config.as_ref()
+                .map(|c| c.endpoint.port)
+                .unwrap_or(DEFAULT_SERVE_PORT);
+            run_serve(&serve_options(service_config.as_ref(), port.unwrap_or(configured_port)));
+        }
+        None => {
+            // Legacy combined mode: polling loop with optional in-process endpoint
+            run_ingest(cli.endpoint, service_config.as_ref(), IngestOptions::default());
+        }
+    }
+}
+
+/// `--source` value
+fn parse_source(value: &str) -> Result<maintenance::Source, String> {
+    match value {
+        "usgs" => Ok(maintenance::Source::Usgs),
+        "cwms" => Ok(maintenance::Source::Cwms),
+        "asos" => Ok(maintenance::Source::Asos),
+        _ => Err("expected usgs, cwms or asos".to_string()),
+    }
+}
+
+/// RFC 3339 time
+fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| format!("expected an RFC 3339 time: {}", e))
+}
+
+/// YYYY-MM-DD (midnight UTC) or RFC 3339 time
+fn parse_date(value: &str) -> Result<DateTime<Utc>, String> {
+    parse_time(value).or_else(|_| chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|d| d.and_time(chrono::NaiveTime::MIN).and_utc())
+        .map_err(|_| "expected YYYY-MM-DD or an RFC 3339 time".to_string()))
+}
+
+fn parse_field_obs_kind(value: &str) -> Result<field_obs::FieldObsKind, String> {
+    field_obs::FieldObsKind::parse(value)
+        .ok_or_else(|| "expected high_water_mark (hwm), crest_stake, photo or sandbagging".to_string())
+}
+
+fn parse_review_status(value: &str) -> Result<data_quality::Status, String> {
+    data_quality::Status::parse(value).ok_or_else(|| "expected open, confirmed or dismissed".to_string())
+}
+
+fn parse_severity(value: &str) -> Result<FloodSeverity, String> {
+    FloodSeverity::parse(value).ok_or_else(|| "expected action, flood, moderate or major".to_string())
+}
+
+fn parse_report_format(value: &str) -> Result<event_report::Format, String> {
+    event_report::Format::parse(value).ok_or_else(|| "expected markdown or html".to_string())
+}
+
+/// Endpoint serving options: TLS and trusted proxies from flomon.toml when
+/// present, otherwise plain HTTP trusting only loopback proxies
+fn serve_options(service_config: Option<&ServiceConfig>, port: u16) -> endpoint::ServeOptions {
+    match service_config {
+        Some(config) => config.serve_options(port).unwrap_or_else(|e| {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }),
+        None => endpoint::ServeOptions::plain(port),
+    }
+}
+
+/// Load flomon.toml if present and apply its database URL.
+///
+/// Returns `None` (legacy per-file configuration) when the file does not
+/// exist; exits the process if it exists but is invalid. `quiet` keeps
+/// stdout clean for `--json`.
+fn load_service_config(quiet: bool) -> Option<ServiceConfig> {
+    let path = config::service::default_path();
+    if !path.exists() {
+        if !quiet {
+            println!("ℹ️  {} not found, using individual TOML files and DATABASE_URL\n", path.display());
+        }
+        return None;
+    }
+    
+    match config::service::load(&path) {
+        Ok(service_config) => {
+            if !quiet {
+                println!("⚙️  Loaded configuration from {}\n", path.display());
             }
-            _ => {
-                eprintln!("Unknown argument: {}", args[i]);
-                eprintln!("Usage:");
-                eprintln!("  {} verify           - Verify data source configuration", args[0]);
-                eprintln!("  {} --endpoint PORT  - Start monitoring daemon", args[0]);
-                std::process::exit(1);
+            if let Some(url) = &service_config.database.url {
+                flomon_service::db::set_database_url(url.clone());
             }
+            flomon_service::db::set_auto_migrate(service_config.database.auto_migrate);
+            let overridden = service_config.endpoints.overridden();
+            if !overridden.is_empty() && !quiet {
+                println!("🔀 API endpoints overridden: {}\n", overridden.join(", "));
+            }
+            flomon_service::ingest::base_urls::set(service_config.endpoints.clone());
+            Some(service_config)
+        }
+        Err(e) => {
+            eprintln!("❌ {}\n", e);
+            std::process::exit(1);
         }
     }
+}
+
+/// `flomon backfill --site CODE --start DATE [--end DATE] [--dry-run]`:
+/// fetch and store one station's USGS readings over a date range, in
+/// chunks; rerunning an interrupted backfill resumes it (see `backfill`)
+fn run_backfill(args: BackfillArgs, service_config: Option<&ServiceConfig>) -> ! {
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
+    let BackfillArgs { site, start, end, dry_run } = args;
+    let site = &site;
+    let end = end.unwrap_or_else(Utc::now);
     
-    // Create daemon with default configuration
-    let mut daemon = Daemon::new();
-    
-    // Initialize: validate database and load stations
-    println!("📊 Initializing daemon...");
-    if let Err(e) = daemon.initialize() {
-        eprintln!("\n❌ Initialization failed: {}\n", e);
-        eprintln!("Run setup validation: ./scripts/validate_db_setup.sh\n");
+    let mut daemon = match service_config {
+        Some(service_config) => Daemon::from_service_config(service_config),
+        None => Daemon::new(),
+    };
+    daemon.set_dry_run(dry_run);
+    daemon.initialize().unwrap_or_else(|e| fail(format!("Initialization failed: {}", e)));
+    if !daemon.get_stations().iter().any(|s| &s.site_code == site) {
+        fail(format!("Unknown site {}", site));
+    }
+    flomon_service::shutdown::install();
+    println!("📥 Backfilling {} from {} to {}", site, start.format("%Y-%m-%d"), end.format("%Y-%m-%d"));
+    let report = daemon.backfill_range(site, start, end)
+        .unwrap_or_else(|e| fail(format!("Backfill failed: {}", e)));
+    if dry_run {
+        println!("✓ {} (rows that would be written; no checkpoints recorded)", report.summary());
+    } else {
+        println!("✓ {}", report.summary());
+    }
+    if !report.is_complete() {
+        eprintln!("   Run the same command again to resume");
         std::process::exit(1);
     }
-    println!("✓ Daemon initialized\n");
+    std::process::exit(0);
+}
+
+/// `flomon field-obs add|list`: record or list manual field observations
+fn run_field_obs(command: FieldObsCommand, json: bool) -> ! {
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
     
-    // Check for stale data and backfill if needed
-    println!("📋 Checking data freshness...");
-    let mut backfill_needed = Vec::new();
-    
-    // Collect station codes first to avoid borrow checker issues
-    let station_codes: Vec<String> = daemon.get_stations()
-        .iter()
-        .map(|s| s.site_code.clone())
-        .collect();
-    
-    for site_code in &station_codes {
-        match daemon.check_staleness(site_code) {
-            Ok(None) => {
-                println!("   {} - No data found (needs backfill)", site_code);
-                backfill_needed.push(site_code.clone());
-            }
-            Ok(Some(staleness)) => {
-                let hours = staleness.num_hours();
-                if hours > 2 {
-                    println!("   {} - Data is {} hours old (stale)", site_code, hours);
-                    backfill_needed.push(site_code.clone());
-                } else {
-                    println!("   {} - Data is fresh ({} min old)", site_code, staleness.num_minutes());
+    let mut client = flomon_service::db::connect_simple()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+    
+    match command {
+        FieldObsCommand::Add { kind, time, site, event, value, datum, lat, lon, media, notes, observer } => {
+            let obs = field_obs::FieldObservation {
+                id: None,
+                kind,
+                observed_at: time,
+                site_code: site,
+                event_id: event,
+                value_ft: value,
+                datum,
+                latitude: lat,
+                longitude: lon,
+                media_ref: media,
+                notes,
+                observer,
+            };
+            
+            match field_obs::insert(&mut client, &obs) {
+                Ok(id) => {
+                    println!("✓ Recorded {} observation #{}", kind.as_str(), id);
+                    std::process::exit(0);
                 }
+                Err(e) => fail(e),
             }
-            Err(e) => {
-                eprintln!("   {} - Error checking staleness: {}", site_code, e);
+        }
+        FieldObsCommand::List { site, event, kind, limit } => {
+            let filter = field_obs::FieldObsFilter {
+                site_code: site,
+                event_id: event,
+                kind,
+                limit,
+            };
+            
+            match field_obs::list(&mut client, &filter) {
+                Ok(observations) if json => {
+                    println!("{}", cli_output::to_json("field_obs", &observations));
+                    std::process::exit(0);
+                }
+                Ok(observations) => {
+                    for obs in &observations {
+                        println!(
+                            "#{:<5} {} {:<15} {:<10} {} {}",
+                            obs.id.unwrap_or_default(),
+                            obs.observed_at.format("%Y-%m-%d %H:%M"),
+                            obs.kind.as_str(),
+                            obs.site_code.as_deref().unwrap_or("-"),
+                            obs.value_ft
+                                .map(|v| format!("{:.2} ft {}", v, obs.datum.as_deref().unwrap_or("")))
+                                .unwrap_or_default(),
+                            obs.notes.as_deref().or(obs.media_ref.as_deref()).unwrap_or(""),
+                        );
+                    }
+                    println!("{} observation(s)", observations.len());
+                    std::process::exit(0);
+                }
+                Err(e) => fail(e),
             }
         }
     }
+}
+
+/// `flomon data-quality list|review`: data problems awaiting manual review
+fn run_data_quality(command: DataQualityCommand, json: bool) -> ! {
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
+    
+    let mut client = flomon_service::db::connect_simple()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
     
-    // Run backfill for stations that need it
-    if !backfill_needed.is_empty() {
-        println!("\n📥 Backfilling {} USGS stations...", backfill_needed.len());
-        for site_code in &backfill_needed {
-            match daemon.backfill_station(site_code) {
-                Ok(count) => println!("   ✓ {} - Inserted {} readings", site_code, count),
-                Err(e) => eprintln!("   ✗ {} - Backfill failed: {}", site_code, e),
+    match command {
+        DataQualityCommand::List { status, limit } => {
+            match data_quality::list(&mut client, status, limit) {
+                Ok(alerts) if json => {
+                    println!("{}", cli_output::to_json("data_quality", &alerts));
+                    std::process::exit(0);
+                }
+                Ok(alerts) => {
+                    for alert in &alerts {
+                        println!("#{:<5} {:<9} {:<10} {}", alert.id, alert.status.as_str(), alert.site_code, alert.summary);
+                        if let (Some(by), Some(at)) = (&alert.reviewed_by, alert.reviewed_at) {
+                            println!("       reviewed by {} at {}{}", by, at.format("%Y-%m-%d %H:%M"),
+                                alert.review_note.as_deref().map(|n| format!(": {}", n)).unwrap_or_default());
+                        }
+                    }
+                    println!("{} alert(s)", alerts.len());
+                    std::process::exit(0);
+                }
+                Err(e) => fail(e),
+            }
+        }
+        DataQualityCommand::Review { id, status, by, note } => {
+            match data_quality::review(&mut client, id, status, &by, note.as_deref(), chrono::Utc::now()) {
+                Ok(()) => {
+                    println!("✓ Data-quality alert #{} {}", id, status.as_str());
+                    std::process::exit(0);
+                }
+                Err(e) => fail(e),
+            }
+        }
+    }
+}
+
+/// `flomon verify`: check configured data sources and write a JSON report
+fn run_verify(json: bool) -> ! {
+    if json {
+        match flomon_service::verify::run_verification(&mut std::io::stderr()) {
+            Ok(report) => {
+                println!("{}", cli_output::to_json("verify", &report));
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("❌ Verification failed: {}", e);
+                std::process::exit(1);
             }
         }
-        println!();
     }
     
-    // Check CWMS locations for stale data
-    println!("📋 Checking CWMS data freshness...");
-    let mut cwms_backfill_needed = Vec::new();
+    println!("🔍 Running data source verification...\n");
     
-    // Collect CWMS locations (clone to avoid borrow checker issues)
-    let cwms_locations: Vec<_> = daemon.get_cwms_locations().to_vec();
+    match flomon_service::verify::run_full_verification() {
+        Ok(report) => {
+            flomon_service::verify::print_summary(&report);
+            
+            // Save JSON report
+            let report_json = serde_json::to_string_pretty(&report).unwrap();
+            std::fs::write("verification_report.json", report_json).unwrap();
+            println!("\n📄 Detailed report saved to: verification_report.json");
+            
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("❌ Verification failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `flomon serve`: run the HTTP API against the shared database.
+///
+/// Does not poll any upstream source; data is whatever the ingest
+/// process has warehoused.
+fn run_serve(options: &endpoint::ServeOptions) {
+    println!("🚀 Starting HTTP endpoint server (serve mode)...");
     
-    for location in &cwms_locations {
-        // Skip locations without discovered timeseries
-        if location.discovered_timeseries.is_none() {
-            println!("   {} - Skipped (no timeseries discovered)", location.name);
-            continue;
+    let client = match flomon_service::db::connect_with_validation() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("\n❌ Failed to connect to database: {}\n", e);
+            eprintln!("Run setup validation: ./scripts/validate_db_setup.sh\n");
+            std::process::exit(1);
         }
-        
-        match daemon.check_cwms_staleness(&location.cwms_location) {
-            Ok(None) => {
-                println!("   {} - No data found (needs backfill)", location.name);
-                cwms_backfill_needed.push(location.clone());
-            }
-            Ok(Some(staleness)) => {
-                let hours = staleness.num_hours();
-                if hours > 2 {
-                    println!("   {} - Data is {} hours old (stale)", location.name, hours);
-                    cwms_backfill_needed.push(location.clone());
-                } else {
-                    println!("   {} - Data is fresh ({} min old)", location.name, staleness.num_minutes());
-                }
-            }
-            Err(e) => {
-                eprintln!("   {} - Error checking staleness: {}", location.name, e);
+    };
+    
+    // The first run uses the validated connection; restarts reconnect
+    let mut client = Some(client);
+    supervisor::supervise("endpoint", supervisor::Backoff::default(), || {
+        let client = match client.take() {
+            Some(client) => client,
+            None => flomon_service::db::connect_simple().map_err(|e| e.to_string())?,
+        };
+        endpoint::serve(options, client)
+    });
+}
+
+/// `flomon ingest`: initialize, catch up on feeds left with a hole by
+/// downtime (see `monitor::catchup`), then poll forever.
+///
+/// When `endpoint_port` is set (legacy `--endpoint` mode) the HTTP API is
+/// also started in a background thread of this process.
+///
+/// With `dry_run`, catch-up and a single poll cycle run with every write
+/// replaced by a printed `[dry-run]` line, then the process exits. With
+/// `once`, a single real cycle runs and queued notifications are drained
+/// (see `Daemon::run_once`) before exiting.
+fn run_ingest(endpoint_port: Option<u16>, service_config: Option<&ServiceConfig>, options: IngestOptions) {
+    let IngestOptions { dry_run, once, source } = options;
+    // Create daemon from flomon.toml, or with default configuration
+    let mut daemon = match service_config {
+        Some(service_config) => Daemon::from_service_config(service_config),
+        None => Daemon::new(),
+    };
+    daemon.set_dry_run(dry_run);
+    daemon.set_source(source);
+    if dry_run {
+        println!("🧪 Dry run: fetching and parsing only, no database writes\n");
+    }
+    
+    // Initialize: validate database and load stations
+    println!("📊 Initializing daemon...");
+    if let Err(e) = daemon.initialize() {
+        eprintln!("\n❌ Initialization failed: {}\n", e);
+        eprintln!("Run setup validation: ./scripts/validate_db_setup.sh\n");
+        std::process::exit(1);
+    }
+    println!("✓ Daemon initialized\n");
+    
+    // Ctrl+C during catch-up stops it between feeds
+    flomon_service::shutdown::install();
+    
+    // Backfill whatever downtime left missing before polling resumes
+    println!("📋 Checking data freshness...");
+    match daemon.catch_up() {
+        Ok(report) if report.sources.is_empty() => println!("   All feeds are fresh\n"),
+        Ok(report) => {
+            let verb = if dry_run { "would recover" } else { "recovered" };
+            println!("✓ Catch-up {} {} readings", verb, report.total_readings());
+            for line in report.summary(chrono::Utc::now()) {
+                println!("   {}", line);
             }
+            println!();
         }
+        Err(e) => eprintln!("   ✗ Catch-up failed: {}\n", e),
+    }
+    if flomon_service::shutdown::requested() {
+        println!("🛑 Shutdown requested during catch-up");
+        std::process::exit(0);
     }
     
-    // Run backfill for CWMS locations that need it
-    if !cwms_backfill_needed.is_empty() {
-        println!("\n📥 Backfilling {} CWMS locations...", cwms_backfill_needed.len());
-        for location in &cwms_backfill_needed {
-            match daemon.backfill_cwms_location(location) {
-                Ok(count) => println!("   ✓ {} - Inserted {} readings", location.name, count),
-                Err(e) => eprintln!("   ✗ {} - Backfill failed: {}", location.name, e),
+    if dry_run || once {
+        println!("🔄 {} poll cycle...", if dry_run { "Dry-run" } else { "Single" });
+        let polled = if dry_run { daemon.poll_all_stations() } else { daemon.run_once() };
+        match polled {
+            Ok(results) if dry_run => {
+                let total: usize = results.values().sum();
+                println!("\n✓ Dry run complete: {} rows would be written", total);
+                std::process::exit(0);
+            }
+            Ok(results) => {
+                let total: usize = results.values().sum();
+                println!("\n✓ Poll complete: {} rows written from {} feeds", total, results.len());
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("\n❌ Poll failed: {}", e);
+                std::process::exit(1);
             }
         }
-        println!();
     }
     
     // Start HTTP endpoint if requested (in background thread)
     if let Some(port) = endpoint_port {
         println!("🚀 Starting HTTP endpoint server...");
+        let options = serve_options(service_config, port);
         
         // Get a new database connection for the endpoint
         match flomon_service::db::connect_with_validation() {
             Ok(client) => {
-                // Spawn endpoint server in background thread
-                std::thread::spawn(move || {
-                    if let Err(e) = endpoint::start_endpoint_server(port, client) {
-                        eprintln!("❌ Endpoint server error: {}", e);
-                    }
+                let scheme = if options.tls.is_some() { "https" } else { "http" };
+                // Spawn endpoint server in a supervised background thread
+                let mut client = Some(client);
+                let spawned = supervisor::spawn("endpoint", supervisor::Backoff::default(), move || {
+                    let client = match client.take() {
+                        Some(client) => client,
+                        None => flomon_service::db::connect_simple().map_err(|e| e.to_string())?,
+                    };
+                    endpoint::serve(&options, client)
                 });
-                println!("   Endpoint running on http://0.0.0.0:{}\n", port);
+                match spawned {
+                    Ok(_) => println!("   Endpoint running on {}://0.0.0.0:{}\n", scheme, port),
+                    Err(e) => eprintln!("❌ Failed to start endpoint thread: {}\n", e),
+                }
             }
             Err(e) => {
                 eprintln!("❌ Failed to connect to database for endpoint: {}", e);
@@ -208,16 +929,678 @@ fn main() {
         }
     }
     
+    // Subscribe to private MQTT sensors if configured (in background thread)
+    if let Some(local) = service_config.and_then(|c| c.local_sensors.clone()) {
+        let broker = local.broker.clone();
+        let spawned = supervisor::spawn("local_sensors", supervisor::Backoff::default(), move || {
+            let mut client = flomon_service::db::connect_simple().map_err(|e| e.to_string())?;
+            local_sensors::run(&local, &mut client)
+        });
+        match spawned {
+            Ok(_) => println!("📡 Local sensors: subscribing on {}\n", broker),
+            Err(e) => eprintln!("❌ Failed to start local sensor thread: {}\n", e),
+        }
+    }
+    
+    // Follow presence sensors for zone occupancy on the same broker
+    let presence = service_config.and_then(|c| Some((c.local_sensors.clone()?, c.occupancy.clone()?)));
+    if let Some((local, occupancy)) = presence.filter(|(_, o)| !o.topics().is_empty()) {
+        let topics = occupancy.topics().len();
+        let spawned = supervisor::spawn("occupancy", supervisor::Backoff::default(), move || {
+            let mut client = flomon_service::db::connect_simple().map_err(|e| e.to_string())?;
+            // A second session needs its own client id or the broker drops the first
+            let mut options = local.connect_options();
+            options.client_id.push_str("-presence");
+            occupancy::run(&local.broker, &options, &occupancy, &mut client)
+        });
+        match spawned {
+            Ok(_) => println!("🏠 Occupancy: following {} presence topic(s)\n", topics),
+            Err(e) => eprintln!("❌ Failed to start occupancy thread: {}\n", e),
+        }
+    }
+    
     // Run the main monitoring loop
     println!("🔄 Starting continuous monitoring loop...");
     println!("   Poll interval: 15 minutes");
     println!("   Monitoring {} USGS stations + {} CWMS locations", 
             daemon.get_stations().len(), daemon.get_cwms_locations().len());
-    println!("   Press Ctrl+C to stop\n");
+    println!("   Press Ctrl+C to stop (again to skip the notification drain)\n");
+    
+    // A panic or error in a poll cycle restarts the loop rather than the process
+    supervisor::supervise("daemon", supervisor::Backoff::default(), || {
+        daemon.run().map_err(|e| e.to_string())
+    });
+}
+
+/// `flomon archive`: move readings past the hot retention window into the
+/// compressed archive, or with `--cwms` delta-encode old CWMS rows. Safe
+/// to run repeatedly (e.g. nightly from cron).
+fn run_archive(args: ArchiveArgs) -> ! {
+    let ArchiveArgs { cwms, days, dry_run } = args;
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
+    let days = match days {
+        Some(days) => days,
+        None if cwms => flomon_service::cwms_archive::HOT_RETENTION_DAYS,
+        None => archive::HOT_RETENTION_DAYS,
+    };
+    
+    let mut client = flomon_service::db::connect_simple()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+    
+    if cwms {
+        match flomon_service::cwms_archive::compact(&mut client, chrono::Utc::now(), days, dry_run) {
+            Ok(stats) => {
+                let verb = if dry_run { "Would compact" } else { "Compacted" };
+                println!(
+                    "✓ {} {} CWMS rows from {} timeseries into {} runs (before {})",
+                    verb, stats.rows, stats.series, stats.runs, stats.cutoff.format("%Y-%m-%d")
+                );
+                std::process::exit(0);
+            }
+            Err(e) => fail(e),
+        }
+    }
+    
+    match archive::archive_readings(&mut client, chrono::Utc::now(), days, dry_run) {
+        Ok(stats) => {
+            let verb = if dry_run { "Would archive" } else { "Archived" };
+            println!(
+                "✓ {} {} readings into {} day rows (before {})",
+                verb, stats.readings, stats.days, stats.cutoff.format("%Y-%m-%d")
+            );
+            std::process::exit(0);
+        }
+        Err(e) => fail(e),
+    }
+}
+
+/// `flomon migrate [--status] [--baseline N]`: apply or inspect the
+/// embedded SQL migrations (see `db::migrate`)
+fn run_migrate(args: MigrateArgs) -> ! {
+    use flomon_service::db::{self, migrate};
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
+    
+    let mut client = db::connect_with_validation()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+    
+    match args {
+        MigrateArgs { status: false, baseline: None } => {
+            let applied = db::migrate(&mut client).unwrap_or_else(|e| fail(e.to_string()));
+            for migration in &applied {
+                println!("✓ Applied {:03}_{}", migration.version, migration.name);
+            }
+            println!("{} migration(s) applied, schema at version {}",
+                applied.len(), migrate::MIGRATIONS.last().map_or(0, |m| m.version));
+        }
+        MigrateArgs { status: true, .. } => {
+            let applied = migrate::applied_versions(&mut client).unwrap_or_else(|e| fail(e.to_string()));
+            for migration in migrate::MIGRATIONS {
+                let mark = if applied.contains(&migration.version) { "applied" } else { "pending" };
+                println!("{:03} {:<28} {}", migration.version, migration.name, mark);
+            }
+        }
+        MigrateArgs { baseline: Some(through), .. } => {
+            let recorded = migrate::baseline(&mut client, through).unwrap_or_else(|e| fail(e.to_string()));
+            println!("✓ Recorded {} migration(s) through {:03} as applied", recorded, through);
+        }
+    }
+    std::process::exit(0);
+}
+
+/// `flomon geo resolve|show`: NWS forecast zone and county FIPS of every
+/// station and zone, used to keep only geographically relevant CAP alerts.
+/// Codes pinned in usgs_stations.toml are kept; the rest are looked up from
+/// coordinates on api.weather.gov.
+fn run_geo(command: GeoCommand) -> ! {
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
     
-    if let Err(e) = daemon.run() {
-        eprintln!("\n❌ Daemon error: {}", e);
+    match command {
+        GeoCommand::Resolve { dry_run } => {
+            let stations = flomon_service::stations::load_stations();
+            let zones = flomon_service::zones::load_zones_default()
+                .unwrap_or_else(|e| fail(format!("Failed to load zones.toml: {}", e)));
+            let http = reqwest::blocking::Client::builder()
+                .timeout(std::time::Duration::from_secs(15))
+                .build()
+                .unwrap_or_else(|e| fail(format!("Failed to build HTTP client: {}", e)));
+            // Zone sensors often share a site; look each coordinate up once
+            let mut lookups: HashMap<String, Result<nws_geo::Geography, String>> = HashMap::new();
+            let mut lookup = |lat: f64, lon: f64| {
+                lookups.entry(format!("{:.4},{:.4}", lat, lon))
+                    .or_insert_with(|| nws_geo::fetch_point(&http, lat, lon))
+                    .clone()
+            };
+            
+            let mut filter = nws_geo::AlertFilter::default();
+            let mut sources = BTreeMap::new();
+            for station in &stations {
+                let configured = nws_geo::configured(station).unwrap_or_else(|e| fail(e));
+                let (geography, source) = if configured.is_complete() {
+                    (configured, "config")
+                } else {
+                    match lookup(station.latitude, station.longitude) {
+                        Ok(looked_up) => {
+                            let (merged, conflict) = nws_geo::complete(configured, looked_up);
+                            if let Some(conflict) = conflict {
+                                eprintln!("⚠️  {}: {}", station.site_code, conflict);
+                            }
+                            (merged, "api")
+                        }
+                        Err(e) => {
+                            eprintln!("⚠️  {}: {}", station.site_code, e);
+                            (configured, "config")
+                        }
+                    }
+                };
+                sources.insert(station.site_code.clone(), source);
+                filter.stations.insert(station.site_code.clone(), geography);
+            }
+            for (zone_id, zone) in flomon_service::zones::get_all_zones(&zones) {
+                let mut area = nws_geo::Area::default();
+                for sensor in &zone.sensors {
+                    let known = sensor.usgs_id.as_ref().and_then(|id| filter.stations.get(id));
+                    match known.cloned().map_or_else(|| lookup(sensor.lat, sensor.lon), Ok) {
+                        Ok(geography) => area.add(&geography),
+                        Err(e) => eprintln!("⚠️  zone {} sensor {}: {}", zone_id, sensor.primary_id(), e),
+                    }
+                }
+                filter.zones.insert(zone_id, area);
+            }
+            
+            print_geography(&filter);
+            if dry_run {
+                println!("(dry run: nothing stored)");
+                std::process::exit(0);
+            }
+            let mut client = flomon_service::db::connect_simple()
+                .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+            nws_geo::store(&mut client, &filter, &sources, chrono::Utc::now()).unwrap_or_else(|e| fail(e));
+            println!("✓ Stored NWS geography for {} stations and {} zones", filter.stations.len(), filter.zones.len());
+            std::process::exit(0);
+        }
+        GeoCommand::Show => {
+            let mut client = flomon_service::db::connect_simple()
+                .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+            let filter = nws_geo::load_filter(&mut client).unwrap_or_else(|e| fail(e));
+            if filter.stations.is_empty() {
+                println!("No NWS geography stored yet (run `flomon geo resolve`)");
+            } else {
+                print_geography(&filter);
+            }
+            std::process::exit(0);
+        }
+    }
+}
+
+/// Station and zone NWS codes, one line each
+fn print_geography(filter: &nws_geo::AlertFilter) {
+    for (site_code, geography) in &filter.stations {
+        println!(
+            "{:<10} zone {:<7} county {} {}",
+            site_code,
+            geography.forecast_zone.as_deref().unwrap_or("?"),
+            geography.county_fips.as_deref().unwrap_or("?"),
+            geography.county_ugc().map(|ugc| format!("({})", ugc)).unwrap_or_default(),
+        );
+    }
+    let join = |codes: &std::collections::BTreeSet<String>| codes.iter().cloned().collect::<Vec<_>>().join(", ");
+    for (zone_id, area) in &filter.zones {
+        println!("Zone {}: forecast zones [{}], counties [{}]", zone_id, join(&area.forecast_zones), join(&area.county_fips));
+    }
+}
+
+/// `flomon event report ID`: chronological incident timeline of a
+/// catalogued flood event, to stdout or `--out FILE`; `event detect` and
+/// `event list` are handled by `run_event_detect` / `run_event_list`
+fn run_event(command: EventCommand) -> ! {
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
         std::process::exit(1);
+    };
+    let (event_id, format, out) = match command {
+        EventCommand::Report { id, format, out } => (id, format, out),
+        EventCommand::Detect { site, days, dry_run } => run_event_detect(site.as_deref(), days, dry_run),
+        EventCommand::List { site, days } => run_event_list(site.as_deref(), days),
+    };
+    
+    let mut client = flomon_service::db::connect_simple()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+    let report = match event_report::load(&mut client, event_id, chrono::Utc::now()) {
+        Ok(Some(report)) => report,
+        Ok(None) => fail(format!("No flood event {}", event_id)),
+        Err(e) => fail(e),
+    };
+    if report.event.end.is_none() {
+        eprintln!("⚠️  Event {} is still open; the timeline runs to now", event_id);
+    }
+    let rendered = report.render(format);
+    match &out {
+        Some(path) => {
+            std::fs::write(path, rendered).unwrap_or_else(|e| fail(format!("Failed to write {}: {}", path, e)));
+            eprintln!("✓ Wrote {} timeline entries to {}", report.entries.len(), path);
+        }
+        None => print!("{}", rendered),
+    }
+    std::process::exit(0);
+}
+
+/// `flomon event detect [--site CODE] [--days N] [--dry-run]`: segment
+/// stored stage history into flood events (see `analysis::events`)
+fn run_event_detect(site: Option<&str>, days: i64, dry_run: bool) -> ! {
+    use flomon_service::analysis::events;
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
+    let mut stations = flomon_service::stations::load_stations();
+    if let Some(site) = site {
+        stations.retain(|s| s.site_code == site);
+        if stations.is_empty() {
+            fail(format!("Unknown site {}", site));
+        }
+    }
+    
+    let mut client = flomon_service::db::connect_simple()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::days(days);
+    let (mut found, mut written) = (0, 0);
+    for station in stations.iter().filter(|s| s.thresholds.is_some()) {
+        let detected = events::scan(&mut client, station, start, end).unwrap_or_else(|e| fail(e));
+        for event in &detected {
+            println!("{} {:<8} start {}  crest {:.2} ft at {}  {}",
+                event.site_code, event.severity, event.start.format("%Y-%m-%d %H:%M"),
+                event.crest_stage_ft, event.crest_time.format("%Y-%m-%d %H:%M"),
+                event.duration_hours().map_or("ongoing".to_string(), |h| format!("{:.0} h", h)));
+        }
+        found += detected.len();
+        if !dry_run && let Some(thresholds) = &station.thresholds {
+            written += events::store(&mut client, &detected, thresholds).unwrap_or_else(|e| fail(e));
+        }
+    }
+    if dry_run {
+        println!("{} event(s) found in the last {} days (dry run, nothing stored)", found, days);
+    } else {
+        println!("✓ {} event(s) found in the last {} days, {} stored", found, days, written);
     }
+    std::process::exit(0);
 }
 
+/// `flomon event list [--site CODE] [--days N]`: stored flood events,
+/// detected and catalogued
+fn run_event_list(site: Option<&str>, days: i64) -> ! {
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
+    let mut client = flomon_service::db::connect_simple()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+    let since = chrono::Utc::now() - chrono::Duration::days(days);
+    let events = flomon_service::analysis::events::list(&mut client, site, since)
+        .unwrap_or_else(|e| fail(e));
+    if events.is_empty() {
+        println!("No flood events in the last {} days", days);
+    }
+    for event in &events {
+        println!("{:>5} {} {:<8} {}  crest {} at {}  {}  {}",
+            event.id, event.site_code, event.severity, event.start.format("%Y-%m-%d %H:%M"),
+            event.crest_stage_ft.map_or("-".to_string(), |c| format!("{:.2} ft", c)),
+            event.crest_time.format("%Y-%m-%d %H:%M"),
+            event.duration_hours.map_or("ongoing".to_string(), |h| format!("{:.0} h", h)),
+            event.detected_by.as_deref().unwrap_or("catalogued"));
+    }
+    std::process::exit(0);
+}
+
+/// `flomon lag fit|show`: upstream travel times fitted from stored stage
+/// history (see `analysis::lag`)
+fn run_lag(command: LagCommand) -> ! {
+    use flomon_service::analysis::lag;
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
+    let print = |estimates: &[lag::LagEstimate], stations: &HashMap<String, flomon_service::stations::Station>| {
+        for e in estimates {
+            let registry = stations.get(&e.upstream_site)
+                .map_or(String::new(), |s| format!("  (registry {:.0} h to Peoria)", s.travel_time_to_peoria_hours));
+            println!("{} -> {} {:<8} {:>3} h  r = {:.2}  n = {}{}",
+                e.upstream_site, e.downstream_site, e.regime.as_str(), e.lag_hours, e.correlation, e.pairs, registry);
+        }
+    };
+    let stations = flomon_service::stations::load_stations_map();
+    let (downstream_site, days, dry_run) = match command {
+        LagCommand::Fit { downstream, days, dry_run } => (downstream, days, dry_run),
+        LagCommand::Show => {
+            let mut client = flomon_service::db::connect_simple()
+                .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+            let estimates = lag::load(&mut client).unwrap_or_else(|e| fail(e));
+            if estimates.is_empty() {
+                println!("No lag estimates stored; run `lag fit`");
+            }
+            print(&estimates, &stations);
+            std::process::exit(0);
+        }
+    };
+    
+    let downstream = stations.get(&downstream_site).unwrap_or_else(|| fail(format!("Unknown site {}", downstream_site)));
+    let upstream: Vec<_> = lag::upstream_of(stations.values(), downstream).into_iter().cloned().collect();
+    
+    let mut client = flomon_service::db::connect_simple()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::days(days);
+    let estimates = lag::fit_stored(&mut client, &upstream, downstream, start, end).unwrap_or_else(|e| fail(e));
+    print(&estimates, &stations);
+    if dry_run {
+        println!("{} estimate(s) from {} upstream gauge(s) (dry run, nothing stored)", estimates.len(), upstream.len());
+    } else {
+        let written = lag::store(&mut client, &estimates, start, end).unwrap_or_else(|e| fail(e));
+        println!("✓ {} estimate(s) from {} upstream gauge(s) stored", written, upstream.len());
+    }
+    std::process::exit(0);
+}
+
+/// `flomon export`: stage and discharge on a station x time grid as
+/// NetCDF for the HEC-RAS / HEC-HMS pre-processing scripts (see `export`)
+fn run_export(args: ExportArgs) -> ! {
+    use flomon_service::export;
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
+    let ExportArgs { start, end, out, interval, sites, format: ExportFormat::Netcdf } = args;
+    let out = &out;
+    let window = export::ExportWindow::new(start, end, interval).unwrap_or_else(|e| fail(e));
+    
+    let mut stations = flomon_service::stations::load_stations();
+    if !sites.is_empty() {
+        let wanted: Vec<&str> = sites.iter().map(|s| s.trim()).collect();
+        if let Some(unknown) = wanted.iter().find(|w| !stations.iter().any(|s| s.site_code == **w)) {
+            fail(format!("Unknown site {}", unknown));
+        }
+        stations.retain(|s| wanted.contains(&s.site_code.as_str()));
+    }
+    
+    let mut client = flomon_service::db::connect_simple()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+    let grid = export::load(&mut client, &stations, &window).unwrap_or_else(|e| fail(e));
+    let bytes = export::to_netcdf(&grid, chrono::Utc::now()).unwrap_or_else(|e| fail(e));
+    std::fs::write(out, &bytes).unwrap_or_else(|e| fail(format!("Failed to write {}: {}", out, e)));
+    eprintln!("✓ Wrote {} stations x {} times ({} KiB) to {}",
+        grid.stations.len(), grid.times.len(), bytes.len() / 1024, out);
+    std::process::exit(0);
+}
+
+/// `flomon plot SITE`: stage hydrograph with threshold lines in the terminal
+fn run_plot(args: PlotArgs) -> ! {
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
+    let PlotArgs { site: site_code, hours, width } = args;
+    let width = width as usize;
+    
+    let station = flomon_service::stations::find_station(&site_code);
+    let mut client = flomon_service::db::connect_simple()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+    
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::hours(hours);
+    let readings = archive::readings_between(&mut client, &site_code, "00065", start, end)
+        .unwrap_or_else(|e| fail(e));
+    if readings.is_empty() {
+        fail(format!("No stage readings for {} in the last {} hours", site_code, hours));
+    }
+    let values: Vec<f64> = readings.iter().map(|r| r.value).collect();
+    let latest = readings.last().unwrap();
+    
+    let name = station.as_ref().map(|s| s.name.as_str()).unwrap_or(site_code.as_str());
+    let (low, high) = values.iter().fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    println!("{} ({}) stage, last {} hours", name, site_code, hours);
+    println!("Latest {:.2} ft at {}   low {:.2}   high {:.2}", latest.value, latest.datetime, low, high);
+    println!("{}\n", plot::sparkline(&plot::resample_max(&values, width)));
+    
+    let thresholds = station.as_ref().and_then(|s| s.thresholds.as_ref());
+    print!("{}", plot::render_chart(&values, thresholds, width, plot::DEFAULT_HEIGHT));
+    print!("{}", plot::time_axis(start, end, width.min(values.len())));
+    std::process::exit(0);
+}
+
+/// `flomon status`: overall basin status and zones above normal
+fn run_status(json: bool) -> ! {
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
+    let mut client = flomon_service::db::connect_simple()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+    let status = flomon_service::conditions::fetch_basin_status(&mut client).unwrap_or_else(|e| fail(e));
+    
+    if json {
+        println!("{}", cli_output::to_json("status", &status));
+        std::process::exit(0);
+    }
+    
+    println!("Basin status:     {}", status.overall_status);
+    println!("Backwater risk:   {} ({})", status.backwater_risk.risk_level, status.backwater_risk.explanation);
+    println!("Upstream pulse:   {}", status.upstream_flood_pulse.explanation);
+    println!("Compound risk:    {}", status.compound_event_risk);
+    for zone in &status.active_zones {
+        println!(
+            "  zone {} {:<28} {:<10} {}",
+            zone.zone_id,
+            zone.zone_name,
+            zone.status,
+            zone.key_sensors_elevated.join(", "),
+        );
+    }
+    println!("Updated {}", status.last_updated.format("%Y-%m-%d %H:%M UTC"));
+    std::process::exit(0);
+}
+
+/// `flomon alerts list`: stations currently at or above action stage;
+/// `flomon alerts rules`: stored counters of the composite alert rules
+fn run_alerts(command: AlertsCommand, json: bool) -> ! {
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
+    
+    let mut client = flomon_service::db::connect_simple()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+    if let AlertsCommand::Rules = command {
+        let stats = flomon_service::alert::rules::load_stats(&mut client).unwrap_or_else(|e| fail(e));
+        if json {
+            println!("{}", cli_output::to_json("alert_rules", &stats));
+            std::process::exit(0);
+        }
+        for rule in &stats {
+            println!(
+                "{:<24} {:<6} {:>7} evaluated {:>5} matched {:>4} fired  last fired {}",
+                rule.rule_name,
+                rule.mode,
+                rule.evaluations,
+                rule.matches,
+                rule.firings,
+                rule.last_fired_at.map(|t| t.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_else(|| "never".to_string()),
+            );
+        }
+        println!("{} rule(s)", stats.len());
+        std::process::exit(0);
+    }
+    let latest = cli_output::latest_readings(&mut client, None, true).unwrap_or_else(|e| fail(e));
+    let version = registry::current_version(&mut client).unwrap_or_else(|e| fail(e));
+    let now = chrono::Utc::now();
+    let mut alerts = cli_output::active_alerts(
+        &flomon_service::stations::load_stations(),
+        &latest,
+        version,
+        now,
+    );
+    for alert in &mut alerts {
+        alert.snapshots = flomon_service::cams::recent_snapshot_links(
+            &mut client, &alert.site_code, flomon_service::cams::recent_since(now),
+        ).unwrap_or_default();
+    }
+    
+    if json {
+        println!("{}", cli_output::to_json("alerts", &alerts));
+        std::process::exit(0);
+    }
+    for alert in &alerts {
+        println!(
+            "{:<8} {:<10} {:>6.2} ft  {}{}",
+            alert.severity,
+            alert.site_code,
+            alert.stage_ft,
+            alert.site_name,
+            if alert.stale { "  (stale)" } else { "" },
+        );
+        for link in &alert.snapshots {
+            println!("         snapshot {}", link);
+        }
+    }
+    println!("{} active alert(s)", alerts.len());
+    std::process::exit(0);
+}
+
+/// `flomon station show SITE`: registry entry, thresholds, latest readings
+fn run_station(site: &str, json: bool) -> ! {
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
+    let station = flomon_service::stations::find_station(site)
+        .unwrap_or_else(|| fail(format!("Unknown station {}", site)));
+    
+    let mut client = flomon_service::db::connect_simple()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+    let latest = cli_output::latest_readings(&mut client, Some(site), false).unwrap_or_else(|e| fail(e));
+    let detail = cli_output::station_detail(&station, &latest, chrono::Utc::now());
+    
+    if json {
+        println!("{}", cli_output::to_json("station", &detail));
+        std::process::exit(0);
+    }
+    println!("{} {}", detail.site_code, detail.name);
+    println!("  {}", detail.description);
+    println!("  Location: {:.4}, {:.4}", detail.latitude, detail.longitude);
+    if let Some(t) = &detail.thresholds {
+        println!(
+            "  Thresholds: action {:.1} / flood {:.1} / moderate {:.1} / major {:.1} ft",
+            t.action_ft, t.flood_ft, t.moderate_ft, t.major_ft,
+        );
+    }
+    if let Some(severity) = &detail.severity {
+        println!("  Current severity: {}", severity);
+    }
+    for reading in &detail.latest {
+        println!(
+            "  {} {:>10.2} {:<6} {} ({} min ago{})",
+            reading.parameter_code,
+            reading.value,
+            reading.unit,
+            reading.reading_time.format("%Y-%m-%d %H:%M"),
+            reading.age_minutes,
+            if reading.stale { ", stale" } else { "" },
+        );
+    }
+    std::process::exit(0);
+}
+
+/// `flomon registry history`: print the station registry change log
+fn run_registry(site: Option<&str>, json: bool) -> ! {
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
+    
+    let mut client = flomon_service::db::connect_simple()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+    
+    let records = registry::history(&mut client, site).unwrap_or_else(|e| fail(e));
+    if json {
+        let changes: Vec<cli_output::RegistryChangeJson> = records.iter().map(Into::into).collect();
+        println!("{}", cli_output::to_json("registry_history", &changes));
+        std::process::exit(0);
+    }
+    for record in &records {
+        println!(
+            "v{:<4} {} {:<10} {:<8} {:<24} {} -> {}",
+            record.version,
+            record.changed_at.format("%Y-%m-%d %H:%M"),
+            record.changed_by,
+            record.change.site_code,
+            record.change.field,
+            record.change.old_value.as_deref().unwrap_or("(none)"),
+            record.change.new_value.as_deref().unwrap_or("(none)"),
+        );
+    }
+    match registry::current_version(&mut client).unwrap_or_else(|e| fail(e)) {
+        Some(version) => println!("{} change(s); current registry version v{}", records.len(), version),
+        None => println!("No registry versions recorded yet (recorded when the daemon starts)"),
+    }
+    std::process::exit(0);
+}
+
+/// `flomon subscribe add|remove|list`: manage zone notification subscriptions
+fn run_subscribe(command: SubscribeCommand, service_config: Option<&ServiceConfig>, json: bool) -> ! {
+    let fail = |msg: String| -> ! {
+        eprintln!("❌ {}", msg);
+        std::process::exit(1);
+    };
+    
+    let mut client = flomon_service::db::connect_simple()
+        .unwrap_or_else(|e| fail(format!("Failed to connect to database: {}", e)));
+    
+    match command {
+        SubscribeCommand::Add { recipient, zones, min_severity } => {
+            for zone_id in zones {
+                let sub = subscriptions::Subscription {
+                    recipient: recipient.clone(),
+                    zone_id,
+                    min_severity: min_severity.clone(),
+                };
+                subscriptions::upsert(&mut client, &sub).unwrap_or_else(|e| fail(e));
+                println!("✓ {} subscribed to zone {} at {} and above", recipient, zone_id, min_severity.as_str());
+            }
+            std::process::exit(0);
+        }
+        SubscribeCommand::Remove { recipient, zone } => {
+            let removed = subscriptions::remove(&mut client, &recipient, zone).unwrap_or_else(|e| fail(e));
+            println!("✓ Removed {} subscription(s) for {}", removed, recipient);
+            std::process::exit(0);
+        }
+        SubscribeCommand::List => {
+            let stored = subscriptions::load_stored(&mut client).unwrap_or_else(|e| fail(e));
+            let configured = service_config.map(|c| c.subscriptions.as_slice()).unwrap_or(&[]);
+            let mut merged = subscriptions::merge(configured, stored.clone());
+            merged.sort_by(|a, b| (a.zone_id, &a.recipient).cmp(&(b.zone_id, &b.recipient)));
+            let source = |sub: &subscriptions::Subscription| if stored.contains(sub) { "db" } else { "config" };
+            
+            if json {
+                let list: Vec<cli_output::SubscriptionJson> = merged.iter()
+                    .map(|sub| cli_output::SubscriptionJson::new(sub, source(sub)))
+                    .collect();
+                println!("{}", cli_output::to_json("subscriptions", &list));
+                std::process::exit(0);
+            }
+            for sub in &merged {
+                let source = source(sub);
+                println!("zone {}  {:<8} {:<6} {}", sub.zone_id, sub.min_severity.as_str(), source, sub.recipient);
+            }
+            println!("{} subscription(s)", merged.len());
+            std::process::exit(0);
+        }
+    }
+}
@@ -0,0 +1,377 @@
+//! Manual staff-gauge readings reported by SMS or email
+//!
+//! When the gauges or their telemetry are down, people near the river text
+//! or email staff gauge readings. An SMS/email forwarder POSTs each message
+//! to `/manual_readings` with a shared token; the body is parsed and stored
+//! in `usgs_raw.manual_readings` with `source = 'MANUAL'`.
+//!
+//! Manual readings live in their own table, so the daemon, the rules and
+//! every analysis that reads `usgs_raw.gauge_readings` never see them. The
+//! dashboard asks for them explicitly: `GET /manual_readings`, or
+//! `GET /history?include_manual=true`.
+//!
+//! # Message format
+//! ```text
+//! [STAGE] <site> <value>[ft] [<time>] [note...]
+//! 05567500 18.4
+//! PEORIA 18.4ft 2:30pm water over the bike path
+//! stage kingston 21.05 14:30
+//! ```
+//! `<site>` is a USGS site code or an alias from `[manual_readings.aliases]`.
+//! `<time>` is the local time on the staff gauge (`14:30`, `2:30pm`), taken
+//! in the forwarder's time zone on the day the message arrived (the day
+//! before if that would put it in the future). Without it the reading is
+//! stamped with the time the message was received.
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveTime, Utc};
+use postgres::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::auth;
+
+/// Value of the `source` column for every manual reading
+pub const SOURCE: &str = "MANUAL";
+
+/// Plausible stage range, ft
+const MIN_STAGE_FT: f64 = -20.0;
+const MAX_STAGE_FT: f64 = 100.0;
+
+/// How far after receipt a reported time may fall before it is read as yesterday
+const CLOCK_SKEW_MINUTES: i64 = 10;
+
+/// Help text returned when a message cannot be parsed
+pub const FORMAT_HELP: &str = "expected: [STAGE] <site> <value>[ft] [HH:MM|h:mmam/pm] [note]";
+
+/// Default and maximum rows returned by `list`
+pub const DEFAULT_LIST_LIMIT: i64 = 100;
+const MAX_LIST_LIMIT: i64 = 1000;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// `[manual_readings]` section of flomon.toml; the POST endpoint is
+/// disabled without it
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ManualSettings {
+    /// Shared secret the forwarder sends as `Authorization: Bearer <token>`
+    pub token: String,
+    /// Short names accepted in place of a site code, e.g. PEORIA = "05567500"
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+    /// Sender addresses/numbers accepted; empty accepts any sender
+    #[serde(default)]
+    pub allowed_senders: Vec<String>,
+}
+
+impl ManualSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        auth::validate_token("manual_readings.token", &self.token)
+    }
+
+    /// Whether `authorization` (the header value) carries the token
+    pub fn authorized(&self, authorization: Option<&str>) -> bool {
+        auth::bearer_authorized(authorization, &self.token)
+    }
+
+    pub fn sender_allowed(&self, sender: &str) -> bool {
+        self.allowed_senders.is_empty()
+            || self.allowed_senders.iter().any(|s| s.eq_ignore_ascii_case(sender.trim()))
+    }
+
+    /// Site code for a code or alias (case-insensitive)
+    fn resolve_site(&self, site: &str) -> Option<String> {
+        if site.len() >= 8 && site.chars().all(|c| c.is_ascii_digit()) {
+            return Some(site.to_string());
+        }
+        self.aliases.iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(site))
+            .map(|(_, code)| code.clone())
+    }
+}
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// How the report arrived
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    Sms,
+    Email,
+}
+
+impl Channel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::Sms => "sms",
+            Channel::Email => "email",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "sms" => Some(Channel::Sms),
+            "email" => Some(Channel::Email),
+            _ => None,
+        }
+    }
+}
+
+/// A message as POSTed by the SMS/email forwarder
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ForwardedMessage {
+    pub channel: Channel,
+    /// Phone number or email address
+    pub from: String,
+    /// When the forwarder received it, with the local UTC offset
+    pub received_at: DateTime<FixedOffset>,
+    pub body: String,
+}
+
+/// One manual stage reading
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ManualReading {
+    /// Assigned by the database
+    pub id: Option<i64>,
+    pub site_code: String,
+    pub stage_ft: f64,
+    pub reading_time: DateTime<Utc>,
+    pub received_at: DateTime<Utc>,
+    pub channel: Channel,
+    pub reporter: String,
+    pub note: Option<String>,
+    /// Always "MANUAL"
+    pub source: String,
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+/// Parse a forwarded message into a reading
+pub fn parse_message(settings: &ManualSettings, message: &ForwardedMessage) -> Result<ManualReading, String> {
+    let tokens: Vec<&str> = message.body.split_whitespace().collect();
+    let mut rest = tokens.as_slice();
+    if rest.first().is_some_and(|t| t.eq_ignore_ascii_case("stage")) {
+        rest = &rest[1..];
+    }
+
+    let [site, value, tail @ ..] = rest else {
+        return Err(format!("missing site or value; {}", FORMAT_HELP));
+    };
+    let site_code = settings.resolve_site(site)
+        .ok_or_else(|| format!("unknown site '{}'; {}", site, FORMAT_HELP))?;
+
+    let lower = value.to_ascii_lowercase();
+    let stage_ft: f64 = lower.strip_suffix("ft").unwrap_or(&lower).parse()
+        .map_err(|_| format!("'{}' is not a stage in feet; {}", value, FORMAT_HELP))?;
+    if !(MIN_STAGE_FT..=MAX_STAGE_FT).contains(&stage_ft) {
+        return Err(format!("stage {} ft is outside {}..{} ft", stage_ft, MIN_STAGE_FT, MAX_STAGE_FT));
+    }
+    rest = tail;
+    if rest.first().is_some_and(|t| t.eq_ignore_ascii_case("ft")) {
+        rest = &rest[1..];
+    }
+
+    // "14:30", "2:30pm" or "2:30 pm"
+    let mut reading_time = message.received_at;
+    let spaced = match rest {
+        [time, meridiem, ..] if meridiem.eq_ignore_ascii_case("am") || meridiem.eq_ignore_ascii_case("pm") =>
+            parse_clock(&format!("{}{}", time, meridiem)).map(|t| (t, 2)),
+        _ => None,
+    };
+    let clock = spaced.or_else(|| rest.first().and_then(|t| parse_clock(t)).map(|t| (t, 1)));
+    if let Some((time, used)) = clock {
+        reading_time = on_day_received(message.received_at, time);
+        rest = &rest[used..];
+    }
+
+    let note = rest.join(" ");
+    Ok(ManualReading {
+        id: None,
+        site_code,
+        stage_ft,
+        reading_time: reading_time.with_timezone(&Utc),
+        received_at: message.received_at.with_timezone(&Utc),
+        channel: message.channel,
+        reporter: message.from.trim().to_string(),
+        note: (!note.is_empty()).then_some(note),
+        source: SOURCE.to_string(),
+    })
+}
+
+/// "14:30", "2:30pm", "2:30PM"
+fn parse_clock(token: &str) -> Option<NaiveTime> {
+    let lower = token.to_ascii_lowercase();
+    let (clock, pm) = match (lower.strip_suffix("pm"), lower.strip_suffix("am")) {
+        (Some(clock), _) => (clock.to_string(), Some(true)),
+        (_, Some(clock)) => (clock.to_string(), Some(false)),
+        _ => (lower, None),
+    };
+    let (hour, minute) = clock.split_once(':')?;
+    let (mut hour, minute): (u32, u32) = (hour.parse().ok()?, minute.parse().ok()?);
+    if let Some(pm) = pm {
+        if !(1..=12).contains(&hour) {
+            return None;
+        }
+        hour = hour % 12 + if pm { 12 } else { 0 };
+    }
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+/// `time` on the local day `received_at` falls on, or the day before if
+/// that is later than the message
+fn on_day_received(received_at: DateTime<FixedOffset>, time: NaiveTime) -> DateTime<FixedOffset> {
+    let offset = *received_at.offset();
+    let candidate = received_at.date_naive().and_time(time).and_local_timezone(offset).unwrap();
+    if candidate > received_at + Duration::minutes(CLOCK_SKEW_MINUTES) {
+        candidate - Duration::days(1)
+    } else {
+        candidate
+    }
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+/// Insert a reading; returns the new id
+pub fn insert(client: &mut Client, reading: &ManualReading, raw_message: &str) -> Result<i64, String> {
+    let row = client.query_one(
+        "INSERT INTO usgs_raw.manual_readings
+         (site_code, stage_ft, reading_time, received_at, channel, reporter, note, raw_message)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING id",
+        &[
+            &reading.site_code,
+            &reading.stage_ft,
+            &reading.reading_time,
+            &reading.received_at,
+            &reading.channel.as_str(),
+            &reading.reporter,
+            &reading.note,
+            &raw_message,
+        ],
+    ).map_err(|e| format!("Failed to insert manual reading: {}", e))?;
+    Ok(row.get(0))
+}
+
+/// Readings for `site_code` (all sites if `None`) in [start, end], newest first
+pub fn list(
+    client: &mut Client,
+    site_code: Option<&str>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    limit: Option<i64>,
+) -> Result<Vec<ManualReading>, String> {
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+    let rows = client.query(
+        "SELECT id, site_code, stage_ft, reading_time, received_at, channel, reporter, note, source
+         FROM usgs_raw.manual_readings
+         WHERE ($1::TEXT IS NULL OR site_code = $1)
+           AND reading_time BETWEEN $2 AND $3
+         ORDER BY reading_time DESC
+         LIMIT $4",
+        &[&site_code, &start, &end, &limit],
+    ).map_err(|e| format!("Failed to query manual readings: {}", e))?;
+
+    rows.iter()
+        .map(|row| {
+            let channel: String = row.get(5);
+            Ok(ManualReading {
+                id: Some(row.get(0)),
+                site_code: row.get(1),
+                stage_ft: row.get(2),
+                reading_time: row.get(3),
+                received_at: row.get(4),
+                channel: Channel::parse(&channel)
+                    .ok_or_else(|| format!("Unknown manual reading channel '{}'", channel))?,
+                reporter: row.get(6),
+                note: row.get(7),
+                source: row.get(8),
+            })
+        })
+        .collect()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> ManualSettings {
+        ManualSettings {
+            token: "0123456789abcdef".to_string(),
+            aliases: BTreeMap::from([
+                ("PEORIA".to_string(), "05567500".to_string()),
+                ("kingston".to_string(), "05568500".to_string()),
+            ]),
+            allowed_senders: vec!["+13095550100".to_string()],
+        }
+    }
+
+    fn sms(body: &str) -> ForwardedMessage {
+        ForwardedMessage {
+            channel: Channel::Sms,
+            from: "+13095550100".to_string(),
+            received_at: "2024-05-01T14:35:00-05:00".parse().unwrap(),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_bare_reading_uses_receipt_time() {
+        let reading = parse_message(&settings(), &sms("05567500 18.4")).unwrap();
+        assert_eq!(reading.site_code, "05567500");
+        assert_eq!(reading.stage_ft, 18.4);
+        assert_eq!(reading.reading_time, "2024-05-01T19:35:00Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(reading.note, None);
+        assert_eq!(reading.source, "MANUAL");
+    }
+
+    #[test]
+    fn test_alias_time_and_note() {
+        let reading = parse_message(&settings(), &sms("peoria 18.4ft 2:30pm water over the bike path")).unwrap();
+        assert_eq!(reading.site_code, "05567500");
+        assert_eq!(reading.reading_time, "2024-05-01T19:30:00Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(reading.note.as_deref(), Some("water over the bike path"));
+
+        let spaced = parse_message(&settings(), &sms("STAGE Kingston 21.05 ft 2:30 pm")).unwrap();
+        assert_eq!(spaced.site_code, "05568500");
+        assert_eq!(spaced.reading_time, reading.reading_time);
+        assert_eq!(spaced.note, None);
+    }
+
+    #[test]
+    fn test_time_after_receipt_is_yesterday() {
+        let reading = parse_message(&settings(), &sms("05567500 18.1 23:45")).unwrap();
+        assert_eq!(reading.reading_time, "2024-05-01T04:45:00Z".parse::<DateTime<Utc>>().unwrap());
+    }
+
+    #[test]
+    fn test_malformed_messages_are_rejected_with_help() {
+        for body in ["", "havana 12.0", "05567500 high", "05567500 250"] {
+            let err = parse_message(&settings(), &sms(body)).unwrap_err();
+            assert!(!err.is_empty(), "{}", body);
+        }
+        assert!(parse_message(&settings(), &sms("05567500")).unwrap_err().contains(FORMAT_HELP));
+    }
+
+    #[test]
+    fn test_token_and_sender_checks() {
+        let s = settings();
+        assert!(s.authorized(Some("Bearer 0123456789abcdef")));
+        assert!(!s.authorized(Some("Bearer 0123456789abcdeX")));
+        assert!(!s.authorized(Some("0123456789abcdef")));
+        assert!(!s.authorized(None));
+        assert!(s.sender_allowed("+13095550100"));
+        assert!(!s.sender_allowed("+13095550199"));
+        assert!(ManualSettings { token: "short".to_string(), ..s }.validate().is_err());
+    }
+}
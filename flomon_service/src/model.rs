@@ -1,12 +1,18 @@
-/// GaugeReading, SiteReadings, FloodThresholds, NwisError
-/// core data structures and error handling
-/// 
-/// maybe also the "is this a valid site code" test, since that's pretty fundamental to the data model
-/// the site code format test (test_peoria_basin_site_codes_are_valid_format) is validating the shape of our data identifiers.
-/// Core data types for the Peoria flood monitoring service.
-///
-/// This module defines the shared domain model imported by all other modules.
-/// It contains no logic, no I/O, and no external dependencies — only types.
+//! GaugeReading, SiteReadings, FloodThresholds, IngestError
+//! core data structures and error handling
+//! 
+//! maybe also the "is this a valid site code" test, since that's pretty fundamental to the data model
+//! the site code format test (test_peoria_basin_site_codes_are_valid_format) is validating the shape of our data identifiers.
+//! Core data types for the Peoria flood monitoring service.
+//!
+//! This module defines the shared domain model imported by all other modules.
+//! It has no I/O and no external dependencies. Readings keep the plain
+//! `value`/`unit` pair they are stored with; `GaugeReading::stage` and
+//! `discharge` view the value as a typed quantity from `crate::units`.
+
+use crate::units::{self, Discharge, Quantity, Stage};
+use chrono::{DateTime, FixedOffset};
+use std::time::Duration;
 
 // ---------------------------------------------------------------------------
 // Parameter codes
@@ -34,8 +40,29 @@ pub struct GaugeReading {
     pub parameter_code: String,
     pub unit: String,
     pub value: f64,
-    pub datetime: String,   // ISO 8601, e.g. "2024-05-01T12:00:00.000-05:00"
+    /// Observation time with the offset the source reported it in
+    /// (e.g. 2024-05-01T12:00:00-05:00); parsed once at ingest.
+    pub datetime: DateTime<FixedOffset>,
     pub qualifier: String,  // "P" = provisional, "A" = approved
+    /// Unit as reported by the source when it differed from the canonical
+    /// `unit` and the value was converted (see `units`).
+    pub original_unit: Option<String>,
+}
+
+impl GaugeReading {
+    /// The value as a stage, if this is a stage or elevation parameter
+    /// in a length unit
+    pub fn stage(&self) -> Option<Stage> {
+        (units::usgs_quantity(&self.parameter_code) == Some(Quantity::Length))
+            .then(|| Stage::in_unit(self.value, &self.unit))?
+    }
+
+    /// The value as a discharge, if this is a discharge parameter in a
+    /// flow unit
+    pub fn discharge(&self) -> Option<Discharge> {
+        (units::usgs_quantity(&self.parameter_code) == Some(Quantity::Flow))
+            .then(|| Discharge::in_unit(self.value, &self.unit))?
+    }
 }
 
 /// Both available readings for a single site, grouped for convenient access.
@@ -69,38 +96,100 @@ pub struct FloodThresholds {
     pub major_flood_stage_ft: f64,
 }
 
+impl FloodThresholds {
+    pub fn action(&self) -> Stage {
+        Stage::from_ft(self.action_stage_ft)
+    }
+
+    pub fn flood(&self) -> Stage {
+        Stage::from_ft(self.flood_stage_ft)
+    }
+
+    pub fn moderate(&self) -> Stage {
+        Stage::from_ft(self.moderate_flood_stage_ft)
+    }
+
+    pub fn major(&self) -> Stage {
+        Stage::from_ft(self.major_flood_stage_ft)
+    }
+}
+
+/// Alert levels for a regulated pool gauge, in feet above (+) or below (-)
+/// the target pool elevation instead of absolute stage.
+///
+/// Pool elevation is stage + `gauge_datum_ft`; both it and the target are
+/// NGVD29 (a gauge zero on another datum is converted when the station is
+/// loaded, see `datum`). Levels ascend: action < flood < moderate < major.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolDeviationThresholds {
+    /// CWMS pool location whose target applies (zones.toml `cwms_location`)
+    pub pool_location: String,
+    pub target_elevation_ft: f64,
+    pub gauge_datum_ft: f64,
+    pub action_ft: f64,
+    pub flood_ft: f64,
+    pub moderate_ft: f64,
+    pub major_ft: f64,
+}
+
 // ---------------------------------------------------------------------------
 // Error types
 // ---------------------------------------------------------------------------
 
-/// Errors that can arise when fetching or processing USGS NWIS data.
+/// Errors from the ingest clients (USGS, CWMS, IEM).
+///
+/// One enum for every source, so retries (`ingest::retry`) and failure
+/// logging (`logging::classify_*_failure`) match on the kind of failure
+/// instead of on the message.
 #[derive(Debug, PartialEq)]
-pub enum NwisError {
-    /// Non-2xx HTTP response from the USGS API.
-    HttpError(u16),
+pub enum IngestError {
+    /// Non-2xx HTTP response other than 429.
+    Http(u16),
+    /// No response within the client's timeout.
+    Timeout(String),
+    /// The request did not complete: connection refused or reset, or a
+    /// body cut off mid-read.
+    Transport(String),
+    /// HTTP 429, with the wait the source asked for (`Retry-After`).
+    RateLimited { retry_after: Option<Duration> },
     /// The response body could not be deserialized.
-    ParseError(String),
-    /// The requested site code was not found in the response.
-    SiteNotFound(String),
-    /// The site was found but contained no usable data values
-    /// (empty array or sentinel -999999).
-    NoDataAvailable(String),
-    /// A reading exists but is older than the configured freshness threshold.
-    StaleData { site: String, age_minutes: u64 },
+    Parse(String),
+    /// A value's timestamp could not be parsed.
+    InvalidTimestamp(String),
+    /// The source answered but had no usable values (empty series,
+    /// sentinel -999999).
+    NoData(String),
+}
+
+impl IngestError {
+    /// Whether the same request may succeed if repeated shortly: transport
+    /// failures, timeouts, throttling and server-side errors. Anything the
+    /// source answered definitively (bad request, no data) is permanent.
+    /// See `ingest::retry`.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            IngestError::Transport(_) | IngestError::Timeout(_) | IngestError::RateLimited { .. } => true,
+            IngestError::Http(code) => matches!(code, 408 | 500 | 502 | 503 | 504),
+            IngestError::Parse(_) | IngestError::InvalidTimestamp(_) | IngestError::NoData(_) => false,
+        }
+    }
 }
 
-impl std::fmt::Display for NwisError {
+impl std::fmt::Display for IngestError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            NwisError::HttpError(code) => write!(f, "HTTP error: {}", code),
-            NwisError::ParseError(msg) => write!(f, "Parse error: {}", msg),
-            NwisError::SiteNotFound(site) => write!(f, "Site not found: {}", site),
-            NwisError::NoDataAvailable(site) => write!(f, "No data available for site: {}", site),
-            NwisError::StaleData { site, age_minutes } => {
-                write!(f, "Stale data for site {}: {} minutes old", site, age_minutes)
+            IngestError::Http(code) => write!(f, "HTTP error: {}", code),
+            IngestError::Timeout(msg) => write!(f, "Request timed out: {}", msg),
+            IngestError::Transport(msg) => write!(f, "Request failed: {}", msg),
+            IngestError::RateLimited { retry_after: Some(wait) } => {
+                write!(f, "Rate limited (HTTP 429), retry after {} s", wait.as_secs())
             }
+            IngestError::RateLimited { retry_after: None } => write!(f, "Rate limited (HTTP 429)"),
+            IngestError::Parse(msg) => write!(f, "Parse error: {}", msg),
+            IngestError::InvalidTimestamp(value) => write!(f, "Invalid timestamp: {}", value),
+            IngestError::NoData(what) => write!(f, "No data available for site: {}", what),
         }
     }
 }
 
-impl std::error::Error for NwisError {}
+impl std::error::Error for IngestError {}
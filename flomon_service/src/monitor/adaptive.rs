@@ -0,0 +1,262 @@
+//! Adaptive polling: tighten the poll interval while a river is up.
+//!
+//! After each USGS poll the daemon hands the latest stage for each station
+//! to `AdaptiveScheduler::observe`. A station is polled at the elevated
+//! interval while its stage is at or above action stage, or while it is
+//! rising faster than `RAPID_RISE_FT_PER_HOUR` (computed from the previous
+//! poll's stage). Once neither holds it returns to the base interval. The
+//! daemon sleeps for the shortest interval any station currently needs.
+//!
+//! While the system is in Watch or Event mode (see `monitor::event_mode`)
+//! the daemon polls every station at the elevated interval regardless.
+//!
+//! Every change of interval is a `PollingDecision`: it is logged and
+//! written to `usgs_raw.polling_decisions` with the trigger, the stage and
+//! rise rate that caused it, and the interval that took effect, so the
+//! cadence during an event can be reconstructed afterwards.
+
+use chrono::{DateTime, Utc};
+use postgres::Client;
+use std::collections::HashMap;
+
+use crate::model::FloodThresholds;
+
+/// Default elevated poll interval, minutes
+pub const DEFAULT_ELEVATED_MINUTES: u64 = 5;
+
+/// Stage rise rate that tightens polling on its own, ft/hour
+pub const RAPID_RISE_FT_PER_HOUR: f64 = 0.5;
+
+/// Why a station's interval changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// Stage reached action stage
+    ActionStage,
+    /// Stage below action stage but rising quickly
+    RapidRise,
+    /// Neither condition holds any more
+    Receded,
+}
+
+impl Trigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Trigger::ActionStage => "action_stage",
+            Trigger::RapidRise => "rapid_rise",
+            Trigger::Receded => "receded",
+        }
+    }
+}
+
+/// One change of a station's poll interval
+#[derive(Debug, Clone, PartialEq)]
+pub struct PollingDecision {
+    pub site_code: String,
+    pub decided_at: DateTime<Utc>,
+    pub trigger: Trigger,
+    pub previous_minutes: u64,
+    pub interval_minutes: u64,
+    pub stage_ft: f64,
+    pub action_stage_ft: Option<f64>,
+    /// None on a station's first observation
+    pub rise_rate_ft_per_hour: Option<f64>,
+}
+
+impl PollingDecision {
+    /// Human-readable reasoning, used for the log line and stored with the row
+    pub fn reason(&self) -> String {
+        let rate = self.rise_rate_ft_per_hour
+            .map(|r| format!("{:+.2} ft/h", r))
+            .unwrap_or_else(|| "rate unknown".to_string());
+        let condition = match (self.trigger, self.action_stage_ft) {
+            (Trigger::ActionStage, Some(action)) =>
+                format!("stage {:.2} ft >= action {:.2} ft ({})", self.stage_ft, action, rate),
+            (Trigger::RapidRise, _) =>
+                format!("rising {} >= {:.2} ft/h at {:.2} ft", rate, RAPID_RISE_FT_PER_HOUR, self.stage_ft),
+            _ => format!("stage {:.2} ft below action, {}", self.stage_ft, rate),
+        };
+        format!(
+            "{}: {} -> {} min ({})",
+            self.trigger.as_str(), self.previous_minutes, self.interval_minutes, condition,
+        )
+    }
+}
+
+/// Per-station interval state
+#[derive(Debug)]
+pub struct AdaptiveScheduler {
+    base_minutes: u64,
+    elevated_minutes: u64,
+    intervals: HashMap<String, u64>,
+    triggers: HashMap<String, Trigger>,
+    last_stage: HashMap<String, (DateTime<Utc>, f64)>,
+}
+
+impl AdaptiveScheduler {
+    pub fn new(base_minutes: u64, elevated_minutes: u64) -> Self {
+        Self {
+            base_minutes,
+            elevated_minutes: elevated_minutes.min(base_minutes),
+            intervals: HashMap::new(),
+            triggers: HashMap::new(),
+            last_stage: HashMap::new(),
+        }
+    }
+
+    /// Current interval for `site_code`
+    pub fn interval_for(&self, site_code: &str) -> u64 {
+        self.intervals.get(site_code).copied().unwrap_or(self.base_minutes)
+    }
+
+    /// Shortest interval any station needs (what the daemon sleeps for)
+    pub fn effective_interval(&self) -> u64 {
+        self.intervals.values().copied().min().unwrap_or(self.base_minutes).min(self.base_minutes)
+    }
+
+    /// Stations whose latest observation fired `trigger`, sorted
+    pub fn sites_with(&self, trigger: Trigger) -> Vec<String> {
+        let mut sites: Vec<String> = self.triggers.iter()
+            .filter(|(_, t)| **t == trigger)
+            .map(|(site, _)| site.clone())
+            .collect();
+        sites.sort();
+        sites
+    }
+
+    /// Record the latest stage for a station; returns a decision when its
+    /// interval changes
+    pub fn observe(
+        &mut self,
+        site_code: &str,
+        stage_ft: f64,
+        reading_time: DateTime<Utc>,
+        thresholds: Option<&FloodThresholds>,
+        now: DateTime<Utc>,
+    ) -> Option<PollingDecision> {
+        let rise_rate = self.last_stage.get(site_code)
+            .filter(|(previous_time, _)| reading_time > *previous_time)
+            .map(|(previous_time, previous_stage)| {
+                let hours = (reading_time - *previous_time).num_seconds() as f64 / 3600.0;
+                (stage_ft - previous_stage) / hours
+            });
+        if self.last_stage.get(site_code).is_none_or(|(t, _)| reading_time >= *t) {
+            self.last_stage.insert(site_code.to_string(), (reading_time, stage_ft));
+        }
+
+        let action = thresholds.map(|t| t.action_stage_ft);
+        let trigger = if action.is_some_and(|a| stage_ft >= a) {
+            Trigger::ActionStage
+        } else if rise_rate.is_some_and(|r| r >= RAPID_RISE_FT_PER_HOUR) {
+            Trigger::RapidRise
+        } else {
+            Trigger::Receded
+        };
+        let interval = match trigger {
+            Trigger::ActionStage | Trigger::RapidRise => self.elevated_minutes,
+            Trigger::Receded => self.base_minutes,
+        };
+
+        let previous = self.interval_for(site_code);
+        self.triggers.insert(site_code.to_string(), trigger);
+        self.intervals.insert(site_code.to_string(), interval);
+        (interval != previous).then(|| PollingDecision {
+            site_code: site_code.to_string(),
+            decided_at: now,
+            trigger,
+            previous_minutes: previous,
+            interval_minutes: interval,
+            stage_ft,
+            action_stage_ft: action,
+            rise_rate_ft_per_hour: rise_rate,
+        })
+    }
+}
+
+/// Write a decision to `usgs_raw.polling_decisions`
+pub fn record(client: &mut Client, decision: &PollingDecision, effective_minutes: u64) -> Result<(), String> {
+    client.execute(
+        "INSERT INTO usgs_raw.polling_decisions
+         (site_code, decided_at, trigger, previous_minutes, interval_minutes,
+          effective_minutes, stage_ft, action_stage_ft, rise_rate_ft_per_hour, reason)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        &[
+            &decision.site_code,
+            &decision.decided_at,
+            &decision.trigger.as_str(),
+            &(decision.previous_minutes as i32),
+            &(decision.interval_minutes as i32),
+            &(effective_minutes as i32),
+            &decision.stage_ft,
+            &decision.action_stage_ft,
+            &decision.rise_rate_ft_per_hour,
+            &decision.reason(),
+        ],
+    ).map_err(|e| format!("Failed to record polling decision for {}: {}", decision.site_code, e))?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn thresholds() -> FloodThresholds {
+        FloodThresholds {
+            action_stage_ft: 14.0,
+            flood_stage_ft: 16.0,
+            moderate_flood_stage_ft: 20.0,
+            major_flood_stage_ft: 24.0,
+        }
+    }
+
+    fn t(minute: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap() + chrono::Duration::minutes(minute)
+    }
+
+    #[test]
+    fn test_action_stage_tightens_then_recedes() {
+        let mut scheduler = AdaptiveScheduler::new(15, 5);
+        let th = thresholds();
+
+        assert_eq!(scheduler.observe("05568500", 12.0, t(0), Some(&th), t(0)), None);
+
+        let tighten = scheduler.observe("05568500", 14.2, t(15), Some(&th), t(15)).unwrap();
+        assert_eq!(tighten.trigger, Trigger::ActionStage);
+        assert_eq!((tighten.previous_minutes, tighten.interval_minutes), (15, 5));
+        assert_eq!(scheduler.effective_interval(), 5);
+
+        // Still above action: no new decision
+        assert_eq!(scheduler.observe("05568500", 14.3, t(20), Some(&th), t(20)), None);
+
+        let relax = scheduler.observe("05568500", 13.9, t(25), Some(&th), t(25)).unwrap();
+        assert_eq!(relax.trigger, Trigger::Receded);
+        assert_eq!(relax.interval_minutes, 15);
+        assert_eq!(scheduler.effective_interval(), 15);
+    }
+
+    #[test]
+    fn test_rapid_rise_below_action_stage() {
+        let mut scheduler = AdaptiveScheduler::new(15, 5);
+        scheduler.observe("05567500", 8.0, t(0), Some(&thresholds()), t(0));
+
+        // +0.3 ft in 15 minutes = 1.2 ft/h
+        let decision = scheduler.observe("05567500", 8.3, t(15), Some(&thresholds()), t(15)).unwrap();
+        assert_eq!(decision.trigger, Trigger::RapidRise);
+        assert!((decision.rise_rate_ft_per_hour.unwrap() - 1.2).abs() < 1e-9);
+        assert!(decision.reason().starts_with("rapid_rise: 15 -> 5 min"));
+        assert_eq!(scheduler.sites_with(Trigger::RapidRise), ["05567500"]);
+    }
+
+    #[test]
+    fn test_repeated_reading_has_no_rate() {
+        let mut scheduler = AdaptiveScheduler::new(15, 5);
+        scheduler.observe("05568000", 8.0, t(0), None, t(0));
+        // USGS has not published a newer value yet
+        assert_eq!(scheduler.observe("05568000", 8.0, t(0), None, t(15)), None);
+        assert_eq!(scheduler.interval_for("05568000"), 15);
+    }
+}
@@ -0,0 +1,323 @@
+//! Predictive staleness: learn when each feed's next reading is due.
+//!
+//! A single staleness threshold doesn't fit every feed: USGS gauges report
+//! every 15 minutes (often delivered in hourly satellite batches), ASOS
+//! stations hourly. Instead of a fixed age limit, the daemon hands each
+//! feed's latest reading time to `CadenceTracker::observe` after every
+//! poll. Whenever a newer reading shows up, the age the previous one had
+//! reached is recorded as a gap. The typical gap and its jitter are the
+//! median and scaled MAD of the last `MAX_SAMPLES` gaps, and a feed is
+//! overdue once its latest reading is older than
+//!
+//! ```text
+//! typical + OVERDUE_SIGMA * jitter
+//! ```
+//!
+//! Median/MAD keep an outage's long gap from widening the band for the
+//! next one (see `analysis::anomaly`). Jitter is floored so a perfectly
+//! regular feed isn't declared overdue a minute late. Until a feed has
+//! `MIN_SAMPLES` gaps the tracker falls back to the fixed threshold it was
+//! built with. History is in memory only and is relearned after a restart.
+//!
+//! The number of jitters allowed follows the event mode (see
+//! `monitor::event_mode`): `OVERDUE_SIGMA` normally, fewer during an event.
+
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::analysis::anomaly::{MAD_TO_SIGMA, mad, median};
+
+/// Jitters past the typical gap before a reading is overdue
+pub const OVERDUE_SIGMA: f64 = 3.0;
+
+/// Gaps needed before the learned cadence replaces the fixed threshold
+pub const MIN_SAMPLES: usize = 8;
+
+/// Gaps kept per feed
+pub const MAX_SAMPLES: usize = 96;
+
+/// Smallest jitter used, minutes
+const MIN_JITTER_MINUTES: f64 = 2.0;
+
+/// Smallest jitter used, as a share of the typical gap
+const MIN_JITTER_FRACTION: f64 = 0.1;
+
+/// A feed's learned reporting cadence
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cadence {
+    /// Median gap between readings, minutes
+    pub typical_minutes: f64,
+    /// Scaled MAD of the gaps (after flooring), minutes
+    pub jitter_minutes: f64,
+    pub samples: usize,
+}
+
+impl Cadence {
+    /// Age after which the latest reading is overdue, minutes
+    pub fn overdue_after_minutes(&self) -> f64 {
+        self.overdue_after_minutes_at(OVERDUE_SIGMA)
+    }
+
+    /// Same, allowing `sigma` jitters instead of `OVERDUE_SIGMA`
+    pub fn overdue_after_minutes_at(&self, sigma: f64) -> f64 {
+        self.typical_minutes + sigma * self.jitter_minutes
+    }
+
+    /// How many jitters past the typical gap `age_minutes` is
+    pub fn sigma_late(&self, age_minutes: f64) -> f64 {
+        (age_minutes - self.typical_minutes) / self.jitter_minutes
+    }
+}
+
+/// A change in whether a feed is overdue
+#[derive(Debug, Clone, PartialEq)]
+pub enum StalenessChange {
+    Overdue {
+        feed: String,
+        latest_reading: DateTime<Utc>,
+        age_minutes: f64,
+        limit_minutes: f64,
+        /// None while the fixed threshold is still in use
+        cadence: Option<Cadence>,
+    },
+    Resumed {
+        feed: String,
+        latest_reading: DateTime<Utc>,
+    },
+}
+
+impl StalenessChange {
+    pub fn feed(&self) -> &str {
+        match self {
+            StalenessChange::Overdue { feed, .. } | StalenessChange::Resumed { feed, .. } => feed,
+        }
+    }
+
+    /// Human-readable line for the log
+    pub fn describe(&self) -> String {
+        match self {
+            StalenessChange::Overdue { latest_reading, age_minutes, limit_minutes, cadence, .. } => {
+                let basis = match cadence {
+                    Some(c) => format!(
+                        "typical {:.0} ± {:.0} min, {:.1}σ late",
+                        c.typical_minutes, c.jitter_minutes, c.sigma_late(*age_minutes)
+                    ),
+                    None => format!("fixed limit {:.0} min; cadence not learned yet", limit_minutes),
+                };
+                format!(
+                    "Reading overdue: latest {} is {:.0} min old ({})",
+                    latest_reading.to_rfc3339(), age_minutes, basis
+                )
+            }
+            StalenessChange::Resumed { latest_reading, .. } => {
+                format!("Readings resumed: latest {}", latest_reading.to_rfc3339())
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FeedHistory {
+    latest: DateTime<Utc>,
+    gaps: VecDeque<f64>,
+}
+
+/// Per-feed cadence and overdue state
+#[derive(Debug)]
+pub struct CadenceTracker {
+    fallback_minutes: u64,
+    overdue_sigma: f64,
+    feeds: HashMap<String, FeedHistory>,
+    overdue: HashSet<String>,
+}
+
+impl CadenceTracker {
+    /// `fallback_minutes` is the fixed age limit used until a feed's
+    /// cadence has been learned
+    pub fn new(fallback_minutes: u64) -> Self {
+        Self {
+            fallback_minutes,
+            overdue_sigma: OVERDUE_SIGMA,
+            feeds: HashMap::new(),
+            overdue: HashSet::new(),
+        }
+    }
+
+    /// Jitters past the typical gap before a learned feed is overdue
+    pub fn set_overdue_sigma(&mut self, sigma: f64) {
+        self.overdue_sigma = sigma;
+    }
+
+    /// Record the latest reading time a poll returned for `feed`
+    pub fn observe(&mut self, feed: &str, latest_reading: DateTime<Utc>, now: DateTime<Utc>) {
+        let Some(history) = self.feeds.get_mut(feed) else {
+            self.feeds.insert(feed.to_string(), FeedHistory { latest: latest_reading, gaps: VecDeque::new() });
+            return;
+        };
+        if latest_reading <= history.latest {
+            return;
+        }
+        if history.gaps.len() == MAX_SAMPLES {
+            history.gaps.pop_front();
+        }
+        history.gaps.push_back(minutes(now - history.latest));
+        history.latest = latest_reading;
+    }
+
+    /// Learned cadence, once `MIN_SAMPLES` gaps have been seen
+    pub fn cadence(&self, feed: &str) -> Option<Cadence> {
+        let history = self.feeds.get(feed)?;
+        if history.gaps.len() < MIN_SAMPLES {
+            return None;
+        }
+        let mut gaps: Vec<f64> = history.gaps.iter().copied().collect();
+        let typical = median(&mut gaps)?;
+        let floor = MIN_JITTER_MINUTES.max(typical * MIN_JITTER_FRACTION);
+        let jitter = (mad(&gaps, typical)? * MAD_TO_SIGMA).max(floor);
+        Some(Cadence { typical_minutes: typical, jitter_minutes: jitter, samples: gaps.len() })
+    }
+
+    /// Age limit currently applied to `feed`, minutes
+    pub fn limit_minutes(&self, feed: &str) -> f64 {
+        self.cadence(feed)
+            .map(|c| c.overdue_after_minutes_at(self.overdue_sigma))
+            .unwrap_or(self.fallback_minutes as f64)
+    }
+
+    /// Compare `feed`'s latest reading age with its limit; returns a change
+    /// when it becomes overdue or resumes. Feeds never observed are skipped.
+    pub fn check(&mut self, feed: &str, now: DateTime<Utc>) -> Option<StalenessChange> {
+        let latest_reading = self.feeds.get(feed)?.latest;
+        let age_minutes = minutes(now - latest_reading);
+        let limit_minutes = self.limit_minutes(feed);
+        let overdue = age_minutes > limit_minutes;
+
+        if overdue == self.overdue.contains(feed) {
+            return None;
+        }
+        if overdue {
+            self.overdue.insert(feed.to_string());
+            Some(StalenessChange::Overdue {
+                feed: feed.to_string(),
+                latest_reading,
+                age_minutes,
+                limit_minutes,
+                cadence: self.cadence(feed),
+            })
+        } else {
+            self.overdue.remove(feed);
+            Some(StalenessChange::Resumed { feed: feed.to_string(), latest_reading })
+        }
+    }
+
+    /// `check` every observed feed, in feed order
+    pub fn check_all(&mut self, now: DateTime<Utc>) -> Vec<StalenessChange> {
+        let mut feeds: Vec<String> = self.feeds.keys().cloned().collect();
+        feeds.sort();
+        feeds.iter().filter_map(|feed| self.check(feed, now)).collect()
+    }
+
+    pub fn is_overdue(&self, feed: &str) -> bool {
+        self.overdue.contains(feed)
+    }
+}
+
+fn minutes(d: chrono::Duration) -> f64 {
+    d.num_seconds() as f64 / 60.0
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn t(minute: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap() + Duration::minutes(minute)
+    }
+
+    /// Feed a reading every `interval` minutes, seen `latency` minutes later
+    fn learn(tracker: &mut CadenceTracker, feed: &str, interval: i64, latency: i64, count: i64) -> DateTime<Utc> {
+        for i in 0..count {
+            tracker.observe(feed, t(i * interval), t(i * interval + latency));
+        }
+        t((count - 1) * interval)
+    }
+
+    #[test]
+    fn test_fifteen_minute_and_hourly_feeds_learn_their_own_limits() {
+        let mut tracker = CadenceTracker::new(60);
+        // Both feeds' latest reading is at 11:00
+        learn(&mut tracker, "05567500", 15, 0, 45);
+        learn(&mut tracker, "PIA", 60, 5, 12);
+
+        let usgs = tracker.cadence("05567500").unwrap();
+        assert_eq!(usgs.typical_minutes, 15.0);
+        // Regular feed: jitter is floored at 2 minutes
+        assert_eq!(usgs.overdue_after_minutes(), 21.0);
+
+        let asos = tracker.cadence("PIA").unwrap();
+        assert_eq!(asos.typical_minutes, 65.0);
+        assert!((asos.overdue_after_minutes() - 84.5).abs() < 1e-9);
+
+        // 30 minutes without a reading: overdue for USGS, normal for ASOS
+        let changes = tracker.check_all(t(11 * 60 + 30));
+        assert_eq!(changes.len(), 1);
+        match &changes[0] {
+            StalenessChange::Overdue { feed, age_minutes, cadence: Some(c), .. } => {
+                assert_eq!(feed, "05567500");
+                assert!((c.sigma_late(*age_minutes) - 7.5).abs() < 1e-9);
+            }
+            other => panic!("expected overdue with a learned cadence, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_fixed_threshold_until_cadence_learned() {
+        let mut tracker = CadenceTracker::new(60);
+        let last = learn(&mut tracker, "05568500", 15, 0, MIN_SAMPLES as i64);
+        assert!(tracker.cadence("05568500").is_none());
+        assert_eq!(tracker.limit_minutes("05568500"), 60.0);
+
+        assert!(tracker.check("05568500", last + Duration::minutes(45)).is_none());
+        let change = tracker.check("05568500", last + Duration::minutes(61)).unwrap();
+        assert!(change.describe().contains("cadence not learned yet"));
+        assert!(tracker.check("UNSEEN", last).is_none());
+    }
+
+    #[test]
+    fn test_outage_reported_once_and_does_not_widen_the_band() {
+        let mut tracker = CadenceTracker::new(60);
+        let last = learn(&mut tracker, "05567500", 15, 0, 20);
+
+        assert!(matches!(tracker.check("05567500", last + Duration::minutes(25)), Some(StalenessChange::Overdue { .. })));
+        assert!(tracker.check("05567500", last + Duration::minutes(40)).is_none());
+        assert!(tracker.is_overdue("05567500"));
+
+        // Readings resume after a 4-hour gap
+        let resumed = last + Duration::minutes(240);
+        tracker.observe("05567500", resumed, resumed);
+        let change = tracker.check("05567500", resumed).unwrap();
+        assert_eq!(change, StalenessChange::Resumed { feed: "05567500".to_string(), latest_reading: resumed });
+
+        // The outage gap is one sample among many; the limit is unchanged
+        assert_eq!(tracker.limit_minutes("05567500"), 21.0);
+        // Tighter during an event
+        tracker.set_overdue_sigma(2.0);
+        assert_eq!(tracker.limit_minutes("05567500"), 19.0);
+    }
+
+    #[test]
+    fn test_repeated_or_older_reading_is_not_a_gap() {
+        let mut tracker = CadenceTracker::new(60);
+        tracker.observe("05568000", t(0), t(0));
+        tracker.observe("05568000", t(0), t(15));
+        tracker.observe("05568000", t(-15), t(30));
+        assert!(tracker.feeds["05568000"].gaps.is_empty());
+        tracker.observe("05568000", t(15), t(45));
+        assert_eq!(tracker.feeds["05568000"].gaps, [45.0]);
+    }
+}
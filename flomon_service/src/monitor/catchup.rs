@@ -0,0 +1,249 @@
+//! Catch-up after daemon downtime.
+//!
+//! When the daemon starts after hours or days down, every feed has a hole
+//! between its latest stored reading and now. Before normal polling
+//! resumes, the daemon plans one backfill task per feed whose hole is
+//! longer than `GAP_THRESHOLD_MINUTES`, runs them oldest hole first, and
+//! reports what each source recovered, so the downtime is visible in the
+//! log instead of a silent gap in the charts.
+//!
+//! A USGS hole is requested as an ISO 8601 period back from now sized to
+//! the hole (`iv_period`): hours for a hole under a day, whole days
+//! otherwise, always rounded up so the window reaches the latest stored
+//! reading.
+//!
+//! Upstream APIs are shared, public services: `RateLimiter` spaces
+//! requests to the same source by `min_request_interval` (USGS and CWMS
+//! are queried once per task, IEM asks for gentler use). A shutdown request
+//! stops the catch-up between tasks; whatever is left is picked up on the
+//! next start.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Holes shorter than this are left to the first poll cycle
+pub const GAP_THRESHOLD_MINUTES: i64 = 120;
+
+/// Longest ASOS window requested; IEM serves at most a few weeks per call
+pub const MAX_ASOS_CATCHUP_DAYS: i64 = 14;
+
+/// ASOS window for a station with no observations stored (covers the
+/// 72-hour precipitation rollup)
+pub const NEW_ASOS_CATCHUP_DAYS: i64 = 3;
+
+/// Upstream a backfill task queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Source {
+    Usgs,
+    Cwms,
+    Asos,
+}
+
+impl Source {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Source::Usgs => "USGS",
+            Source::Cwms => "CWMS",
+            Source::Asos => "ASOS",
+        }
+    }
+
+    /// Minimum spacing between two requests to this source
+    pub fn min_request_interval(&self) -> std::time::Duration {
+        match self {
+            Source::Usgs | Source::Cwms => std::time::Duration::from_secs(2),
+            Source::Asos => std::time::Duration::from_secs(5),
+        }
+    }
+}
+
+/// One feed to backfill
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatchUpTask {
+    pub source: Source,
+    /// Site code, CWMS location ID or ASOS station ID
+    pub feed: String,
+    /// Latest stored reading; None when the feed has no data at all
+    pub latest: Option<DateTime<Utc>>,
+}
+
+impl CatchUpTask {
+    /// Whole days to request to cover the hole (at least one)
+    pub fn days(&self, now: DateTime<Utc>) -> i64 {
+        match self.latest {
+            Some(latest) => ((now - latest).num_hours() + 23) / 24,
+            None => 0,
+        }
+        .max(1)
+    }
+}
+
+/// ISO 8601 period covering `gap` back from now, for the USGS IV API:
+/// "PT5H" for 4 h 10 min, "P2D" for 36 h
+pub fn iv_period(gap: Duration) -> String {
+    let minutes = gap.num_minutes().max(1);
+    if minutes < 24 * 60 {
+        format!("PT{}H", (minutes + 59) / 60)
+    } else {
+        format!("P{}D", (minutes + 24 * 60 - 1) / (24 * 60))
+    }
+}
+
+/// Backfill tasks for feeds whose hole exceeds `GAP_THRESHOLD_MINUTES`,
+/// oldest hole first (feeds with no data at all lead)
+pub fn plan(feeds: Vec<(Source, String, Option<DateTime<Utc>>)>, now: DateTime<Utc>) -> Vec<CatchUpTask> {
+    let mut tasks: Vec<CatchUpTask> = feeds.into_iter()
+        .filter(|(_, _, latest)| latest.is_none_or(|t| now - t > Duration::minutes(GAP_THRESHOLD_MINUTES)))
+        .map(|(source, feed, latest)| CatchUpTask { source, feed, latest })
+        .collect();
+    tasks.sort_by(|a, b| a.latest.cmp(&b.latest).then(a.source.cmp(&b.source)).then(a.feed.cmp(&b.feed)));
+    tasks
+}
+
+/// Spaces requests to the same source
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    last_request: HashMap<Source, Instant>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How long to wait before the next request to `source` at `now`
+    pub fn wait_before(&self, source: Source, now: Instant) -> std::time::Duration {
+        self.last_request.get(&source)
+            .map(|last| (*last + source.min_request_interval()).saturating_duration_since(now))
+            .unwrap_or_default()
+    }
+
+    pub fn record(&mut self, source: Source, at: Instant) {
+        self.last_request.insert(source, at);
+    }
+}
+
+/// What one source recovered
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceRecovery {
+    pub feeds: usize,
+    pub failed: usize,
+    pub readings: usize,
+    /// Start of the oldest hole among feeds that had data
+    pub oldest_gap: Option<DateTime<Utc>>,
+}
+
+/// Outcome of a catch-up run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CatchUpReport {
+    pub sources: HashMap<Source, SourceRecovery>,
+    /// Tasks not run because a shutdown was requested
+    pub skipped: usize,
+}
+
+impl CatchUpReport {
+    pub fn record(&mut self, task: &CatchUpTask, result: Result<usize, String>) {
+        let recovery = self.sources.entry(task.source).or_default();
+        recovery.feeds += 1;
+        match result {
+            Ok(readings) => recovery.readings += readings,
+            Err(_) => recovery.failed += 1,
+        }
+        if let Some(latest) = task.latest {
+            recovery.oldest_gap = Some(recovery.oldest_gap.map_or(latest, |t| t.min(latest)));
+        }
+    }
+
+    pub fn total_readings(&self) -> usize {
+        self.sources.values().map(|r| r.readings).sum()
+    }
+
+    /// One line per source, in source order
+    pub fn summary(&self, now: DateTime<Utc>) -> Vec<String> {
+        let mut sources: Vec<_> = self.sources.iter().collect();
+        sources.sort_by_key(|(source, _)| **source);
+        let mut lines: Vec<String> = sources.into_iter()
+            .map(|(source, r)| {
+                let window = r.oldest_gap
+                    .map(|t| format!(" covering {} h since {}", (now - t).num_hours(), t.format("%Y-%m-%d %H:%M UTC")))
+                    .unwrap_or_default();
+                format!("{}: {} readings recovered for {} feed(s){}{}", source.as_str(), r.readings, r.feeds, window,
+                    if r.failed > 0 { format!(", {} failed", r.failed) } else { String::new() })
+            })
+            .collect();
+        if self.skipped > 0 {
+            lines.push(format!("{} feed(s) left for the next start (shutdown requested)", self.skipped));
+        }
+        lines
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 3, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_plan_skips_fresh_feeds_and_orders_oldest_first() {
+        let tasks = plan(vec![
+            (Source::Usgs, "05568500".into(), Some(now() - Duration::hours(30))),
+            (Source::Asos, "KPIA".into(), Some(now() - Duration::minutes(50))),
+            (Source::Cwms, "LaGrange-Pool".into(), Some(now() - Duration::days(2))),
+            (Source::Usgs, "05568580".into(), None),
+        ], now());
+        let feeds: Vec<&str> = tasks.iter().map(|t| t.feed.as_str()).collect();
+        assert_eq!(feeds, ["05568580", "LaGrange-Pool", "05568500"]);
+        assert_eq!(tasks[2].days(now()), 2);
+        assert_eq!(tasks[0].days(now()), 1);
+    }
+
+    #[test]
+    fn test_iv_period_covers_the_whole_hole() {
+        assert_eq!(iv_period(Duration::minutes(250)), "PT5H");
+        assert_eq!(iv_period(Duration::hours(3)), "PT3H");
+        assert_eq!(iv_period(Duration::minutes(23 * 60 + 1)), "PT24H");
+        assert_eq!(iv_period(Duration::hours(36)), "P2D");
+        assert_eq!(iv_period(Duration::days(120)), "P120D");
+        assert_eq!(iv_period(Duration::zero()), "PT1H");
+    }
+
+    #[test]
+    fn test_rate_limiter_spaces_requests_per_source() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new();
+        assert_eq!(limiter.wait_before(Source::Asos, start), std::time::Duration::ZERO);
+        limiter.record(Source::Asos, start);
+        let later = start + std::time::Duration::from_secs(1);
+        assert_eq!(limiter.wait_before(Source::Asos, later), std::time::Duration::from_secs(4));
+        assert_eq!(limiter.wait_before(Source::Usgs, later), std::time::Duration::ZERO);
+        assert_eq!(limiter.wait_before(Source::Asos, start + std::time::Duration::from_secs(9)), std::time::Duration::ZERO);
+    }
+
+    #[test]
+    fn test_report_summarizes_per_source() {
+        let mut report = CatchUpReport::default();
+        let usgs = |feed: &str, hours| CatchUpTask {
+            source: Source::Usgs, feed: feed.into(), latest: Some(now() - Duration::hours(hours)),
+        };
+        report.record(&usgs("05568500", 30), Ok(240));
+        report.record(&usgs("05568580", 40), Err("timeout".into()));
+        report.record(&CatchUpTask { source: Source::Asos, feed: "KPIA".into(), latest: None }, Ok(12));
+        report.skipped = 1;
+
+        assert_eq!(report.total_readings(), 252);
+        assert_eq!(report.summary(now()), [
+            "USGS: 240 readings recovered for 2 feed(s) covering 40 h since 2024-05-01 20:00 UTC, 1 failed",
+            "ASOS: 12 readings recovered for 1 feed(s)",
+            "1 feed(s) left for the next start (shutdown requested)",
+        ]);
+    }
+}
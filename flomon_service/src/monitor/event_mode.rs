@@ -0,0 +1,291 @@
+//! System-wide event mode: Normal → Watch → Event → Recovery.
+//!
+//! Polling, staleness checks and the dashboard used to each decide for
+//! themselves whether a flood was on. The daemon now collects the inputs
+//! once per cycle into `Signals` and hands them to `EventMode::update`;
+//! everything else reads the one resulting `Mode`.
+//!
+//! - **Watch**: any station at action stage, any adaptive-polling trigger
+//!   (rapid rise, see `monitor::adaptive`), or an NWS flood watch.
+//! - **Event**: any station at flood stage or above, or an NWS flood
+//!   warning. Entered from any mode.
+//! - **Recovery**: an event's signals have dropped below flood stage. The
+//!   river is still high and falling; a renewed rise goes straight back to
+//!   Event. Recovery ends (to Normal) only after `RECOVERY_QUIET_HOURS`
+//!   without any Watch signal.
+//! - Watch likewise returns to Normal only after `WATCH_QUIET_HOURS`
+//!   quiet, so a stage hovering at action stage doesn't flap the banner.
+//!
+//! The mode tightens the whole system while it lasts: Watch and Event poll
+//! every station at the elevated interval (`Mode::elevated_polling`) and
+//! declare feeds overdue sooner (`Mode::overdue_sigma`). Each change is
+//! logged and written to `usgs_raw.event_mode_history`, which the API's
+//! banner (`GET /api/mode`) reads and the daemon restores on restart.
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+use serde::Serialize;
+
+use crate::alert::thresholds::FloodSeverity;
+use crate::alert::transitions::severity_label;
+use crate::monitor::cadence::OVERDUE_SIGMA;
+
+/// Quiet time before Watch returns to Normal
+pub const WATCH_QUIET_HOURS: i64 = 6;
+
+/// Quiet time before Recovery returns to Normal
+pub const RECOVERY_QUIET_HOURS: i64 = 24;
+
+/// System-wide state, in the order an event passes through it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    Normal,
+    Watch,
+    Event,
+    Recovery,
+}
+
+impl Mode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mode::Normal => "normal",
+            Mode::Watch => "watch",
+            Mode::Event => "event",
+            Mode::Recovery => "recovery",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "normal" => Some(Mode::Normal),
+            "watch" => Some(Mode::Watch),
+            "event" => Some(Mode::Event),
+            "recovery" => Some(Mode::Recovery),
+            _ => None,
+        }
+    }
+
+    /// Dashboard banner text; None in Normal (no banner)
+    pub fn banner(&self) -> Option<&'static str> {
+        match self {
+            Mode::Normal => None,
+            Mode::Watch => Some("Flood watch: rivers rising, monitoring closely"),
+            Mode::Event => Some("Flood event in progress"),
+            Mode::Recovery => Some("Flood receding: conditions recovering"),
+        }
+    }
+
+    /// Poll every station at the elevated interval
+    pub fn elevated_polling(&self) -> bool {
+        matches!(self, Mode::Watch | Mode::Event)
+    }
+
+    /// Jitters past a feed's typical gap before it is overdue (see
+    /// `monitor::cadence`): a silent gauge matters sooner during an event
+    pub fn overdue_sigma(&self) -> f64 {
+        match self {
+            Mode::Event => 2.0,
+            Mode::Watch => 2.5,
+            Mode::Normal | Mode::Recovery => OVERDUE_SIGMA,
+        }
+    }
+}
+
+/// One cycle's inputs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Signals {
+    /// Highest severity across stations (`None`: all below action stage)
+    pub highest_severity: Option<FloodSeverity>,
+    /// Station whose severity that is
+    pub highest_site: Option<String>,
+    /// Stations polled at the elevated interval for a rapid rise
+    pub rapid_rise_sites: Vec<String>,
+    /// Active NWS flood warning / watch products covering the basin
+    pub nws_warning: bool,
+    pub nws_watch: bool,
+}
+
+impl Signals {
+    /// Mode these signals call for on their own, with the reason
+    fn target(&self) -> (Mode, String) {
+        let severity = self.highest_severity.as_ref();
+        let site = self.highest_site.as_deref().unwrap_or("?");
+        if severity.is_some_and(|s| *s >= FloodSeverity::Flood) {
+            (Mode::Event, format!("{} at {} stage", site, severity_label(severity)))
+        } else if self.nws_warning {
+            (Mode::Event, "NWS flood warning in effect".to_string())
+        } else if severity.is_some() {
+            (Mode::Watch, format!("{} at action stage", site))
+        } else if !self.rapid_rise_sites.is_empty() {
+            (Mode::Watch, format!("rapid rise at {}", self.rapid_rise_sites.join(", ")))
+        } else if self.nws_watch {
+            (Mode::Watch, "NWS flood watch in effect".to_string())
+        } else {
+            (Mode::Normal, "no watch signals".to_string())
+        }
+    }
+}
+
+/// A change of mode
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModeChange {
+    pub from: Mode,
+    pub to: Mode,
+    pub at: DateTime<Utc>,
+    pub reason: String,
+}
+
+impl ModeChange {
+    pub fn describe(&self) -> String {
+        format!("Event mode {} -> {}: {}", self.from.as_str(), self.to.as_str(), self.reason)
+    }
+}
+
+/// Current mode and how long it has been quiet
+#[derive(Debug)]
+pub struct EventMode {
+    mode: Mode,
+    since: DateTime<Utc>,
+    quiet_since: Option<DateTime<Utc>>,
+}
+
+impl EventMode {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self::resume(Mode::Normal, now)
+    }
+
+    /// Continue from a stored mode (after a restart)
+    pub fn resume(mode: Mode, since: DateTime<Utc>) -> Self {
+        Self { mode, since, quiet_since: None }
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn since(&self) -> DateTime<Utc> {
+        self.since
+    }
+
+    /// Apply one cycle's signals; returns the change, if any
+    pub fn update(&mut self, signals: &Signals, now: DateTime<Utc>) -> Option<ModeChange> {
+        let (target, reason) = signals.target();
+        if target == Mode::Normal {
+            self.quiet_since.get_or_insert(now);
+        } else {
+            self.quiet_since = None;
+        }
+        let quiet_for = |hours: i64| self.quiet_since.is_some_and(|t| now - t >= Duration::hours(hours));
+
+        let next = match (self.mode, target) {
+            (_, Mode::Event) => Mode::Event,
+            (Mode::Event, _) => Mode::Recovery,
+            (Mode::Normal, Mode::Watch) => Mode::Watch,
+            (Mode::Watch, Mode::Normal) if quiet_for(WATCH_QUIET_HOURS) => Mode::Normal,
+            (Mode::Recovery, Mode::Normal) if quiet_for(RECOVERY_QUIET_HOURS) => Mode::Normal,
+            (current, _) => current,
+        };
+        if next == self.mode {
+            return None;
+        }
+
+        let reason = match next {
+            Mode::Recovery => format!("below flood stage ({})", reason),
+            Mode::Normal => format!("quiet since {}", self.quiet_since.unwrap_or(now).to_rfc3339()),
+            _ => reason,
+        };
+        let change = ModeChange { from: self.mode, to: next, at: now, reason };
+        self.mode = next;
+        self.since = now;
+        Some(change)
+    }
+}
+
+/// Write a change to `usgs_raw.event_mode_history`
+pub fn record(client: &mut Client, change: &ModeChange) -> Result<(), String> {
+    client.execute(
+        "INSERT INTO usgs_raw.event_mode_history (mode, previous_mode, entered_at, reason)
+         VALUES ($1, $2, $3, $4)",
+        &[&change.to.as_str(), &change.from.as_str(), &change.at, &change.reason],
+    ).map_err(|e| format!("Failed to record event mode change: {}", e))?;
+    Ok(())
+}
+
+/// Most recently entered mode, when and why; None before the first change
+pub fn load_current(client: &mut Client) -> Result<Option<(Mode, DateTime<Utc>, String)>, String> {
+    let row = client.query_opt(
+        "SELECT mode, entered_at, reason FROM usgs_raw.event_mode_history
+         ORDER BY entered_at DESC, id DESC LIMIT 1",
+        &[],
+    ).map_err(|e| format!("Failed to load event mode: {}", e))?;
+    Ok(row.and_then(|row| {
+        let mode: String = row.get(0);
+        Mode::parse(&mode).map(|mode| (mode, row.get(1), row.get(2)))
+    }))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn t(hour: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap() + Duration::hours(hour)
+    }
+
+    fn at(severity: Option<FloodSeverity>) -> Signals {
+        Signals {
+            highest_site: severity.as_ref().map(|_| "05568500".to_string()),
+            highest_severity: severity,
+            ..Signals::default()
+        }
+    }
+
+    #[test]
+    fn test_full_event_cycle() {
+        let mut mode = EventMode::new(t(0));
+        let change = mode.update(&at(Some(FloodSeverity::Action)), t(1)).unwrap();
+        assert_eq!((change.from, change.to), (Mode::Normal, Mode::Watch));
+        assert_eq!(change.reason, "05568500 at action stage");
+
+        assert_eq!(mode.update(&at(Some(FloodSeverity::Moderate)), t(2)).unwrap().to, Mode::Event);
+        assert_eq!(mode.update(&at(Some(FloodSeverity::Flood)), t(3)), None);
+        // Below flood stage: recovering, not back to Watch
+        assert_eq!(mode.update(&at(Some(FloodSeverity::Action)), t(4)).unwrap().to, Mode::Recovery);
+        assert_eq!(mode.update(&at(None), t(5)), None);
+        // Action stage again resets the quiet clock
+        assert_eq!(mode.update(&at(Some(FloodSeverity::Action)), t(6)), None);
+        assert_eq!(mode.update(&at(None), t(7)), None);
+        assert_eq!(mode.update(&at(None), t(30)), None);
+        let change = mode.update(&at(None), t(31)).unwrap();
+        assert_eq!((change.from, change.to), (Mode::Recovery, Mode::Normal));
+        assert_eq!(mode.since(), t(31));
+    }
+
+    #[test]
+    fn test_watch_holds_until_quiet_and_nws_products_count() {
+        let mut mode = EventMode::new(t(0));
+        let rising = Signals { rapid_rise_sites: vec!["05567500".to_string()], ..Signals::default() };
+        assert_eq!(mode.update(&rising, t(0)).unwrap().reason, "rapid rise at 05567500");
+        assert_eq!(mode.update(&at(None), t(1)), None);
+        assert_eq!(mode.update(&at(None), t(6)), None);
+        assert_eq!(mode.update(&at(None), t(7)).unwrap().to, Mode::Normal);
+
+        let warning = Signals { nws_warning: true, ..Signals::default() };
+        assert_eq!(mode.update(&warning, t(8)).unwrap().to, Mode::Event);
+    }
+
+    #[test]
+    fn test_mode_policy() {
+        assert!(Mode::Event.elevated_polling() && Mode::Watch.elevated_polling());
+        assert!(!Mode::Recovery.elevated_polling());
+        assert!(Mode::Event.overdue_sigma() < Mode::Normal.overdue_sigma());
+        assert_eq!(Mode::Normal.banner(), None);
+        assert_eq!(Mode::parse(Mode::Recovery.as_str()), Some(Mode::Recovery));
+    }
+}
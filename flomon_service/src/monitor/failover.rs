@@ -0,0 +1,323 @@
+//! Failover readiness of the warm standby.
+//!
+//! A standby box only helps if it is ready when the primary fails: its
+//! Postgres replica must be streaming and close behind, and its daemon must
+//! be running. With `[failover]` configured, every daemon writes a
+//! heartbeat to `daemon_heartbeats` each poll cycle (instance name, host,
+//! dispatch role), and the leader (see `alert::leader`) checks:
+//!
+//! - at least `min_standbys` other daemons have a heartbeat newer than
+//!   `heartbeat_stale_minutes`
+//! - with `expect_replica`, the primary's `pg_stat_replication` lists a
+//!   streaming replica whose replay lag is under `max_replication_lag_seconds`
+//!   (the database role needs `pg_monitor` to see lag columns)
+//!
+//! Each problem is logged at ERROR when it appears and at INFO when it
+//! clears, not every cycle.
+//!
+//! ```toml
+//! [failover]
+//! min_standbys = 1
+//! heartbeat_stale_minutes = 45
+//! expect_replica = true
+//! max_replication_lag_seconds = 300
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::alert::leader::Role;
+
+/// Default replay lag beyond which the replica is not ready
+pub const DEFAULT_MAX_REPLICATION_LAG_SECONDS: f64 = 300.0;
+
+/// Default heartbeat age beyond which a standby daemon counts as down
+/// (three default poll cycles)
+pub const DEFAULT_HEARTBEAT_STALE_MINUTES: i64 = 45;
+
+/// `[failover]` section of flomon.toml
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FailoverSettings {
+    /// This daemon's name in `daemon_heartbeats` (default: `$FLOMON_INSTANCE`, else the host name)
+    pub instance: Option<String>,
+    pub min_standbys: usize,
+    pub heartbeat_stale_minutes: i64,
+    pub expect_replica: bool,
+    pub max_replication_lag_seconds: f64,
+}
+
+impl Default for FailoverSettings {
+    fn default() -> Self {
+        Self {
+            instance: None,
+            min_standbys: 1,
+            heartbeat_stale_minutes: DEFAULT_HEARTBEAT_STALE_MINUTES,
+            expect_replica: true,
+            max_replication_lag_seconds: DEFAULT_MAX_REPLICATION_LAG_SECONDS,
+        }
+    }
+}
+
+impl FailoverSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.heartbeat_stale_minutes <= 0 {
+            return Err("failover.heartbeat_stale_minutes must be positive".to_string());
+        }
+        if self.max_replication_lag_seconds.is_nan() || self.max_replication_lag_seconds <= 0.0 {
+            return Err("failover.max_replication_lag_seconds must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    /// Name this daemon writes its heartbeat under
+    pub fn instance_name(&self) -> String {
+        self.instance.clone()
+            .or_else(|| std::env::var("FLOMON_INSTANCE").ok())
+            .unwrap_or_else(host_name)
+    }
+}
+
+/// Host name from `$HOSTNAME` or /etc/hostname
+pub fn host_name() -> String {
+    std::env::var("HOSTNAME").ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Latest heartbeat of one daemon
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heartbeat {
+    pub instance: String,
+    pub host: String,
+    pub role: String,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// One row of the primary's `pg_stat_replication`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replica {
+    pub name: String,
+    pub state: String,
+    /// None when the role cannot see lag or nothing has been replayed yet
+    pub replay_lag_seconds: Option<f64>,
+}
+
+/// Something that would make a failover fail or lose data
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReadinessIssue {
+    /// Fewer live standby daemons than `min_standbys`
+    StandbysMissing { live: usize, required: usize },
+    /// A standby daemon that has stopped writing heartbeats
+    StandbySilent { instance: String, last_seen: DateTime<Utc> },
+    /// No streaming replica attached to the primary
+    NoReplica,
+    ReplicaNotStreaming { name: String, state: String },
+    ReplicaLagging { name: String, lag_seconds: f64 },
+}
+
+impl ReadinessIssue {
+    /// Stable identity, so a lag that grows is one ongoing issue
+    pub fn key(&self) -> String {
+        match self {
+            ReadinessIssue::StandbysMissing { .. } => "standbys".to_string(),
+            ReadinessIssue::StandbySilent { instance, .. } => format!("silent:{}", instance),
+            ReadinessIssue::NoReplica => "replica".to_string(),
+            ReadinessIssue::ReplicaNotStreaming { name, .. } => format!("state:{}", name),
+            ReadinessIssue::ReplicaLagging { name, .. } => format!("lag:{}", name),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ReadinessIssue::StandbysMissing { live, required } =>
+                format!("{} live standby daemon(s), {} required", live, required),
+            ReadinessIssue::StandbySilent { instance, last_seen } =>
+                format!("Standby daemon {} silent since {}", instance, last_seen.format("%Y-%m-%d %H:%M UTC")),
+            ReadinessIssue::NoReplica => "No streaming replica attached to the primary database".to_string(),
+            ReadinessIssue::ReplicaNotStreaming { name, state } =>
+                format!("Replica {} is {} rather than streaming", name, state),
+            ReadinessIssue::ReplicaLagging { name, lag_seconds } =>
+                format!("Replica {} is {:.0} s behind the primary", name, lag_seconds),
+        }
+    }
+}
+
+/// Readiness problems seen by `own_instance`. `replicas` is None when the
+/// database is itself a replica (nothing to check from there).
+pub fn assess(
+    settings: &FailoverSettings,
+    own_instance: &str,
+    heartbeats: &[Heartbeat],
+    replicas: Option<&[Replica]>,
+    now: DateTime<Utc>,
+) -> Vec<ReadinessIssue> {
+    let mut issues = Vec::new();
+    let stale_after = Duration::minutes(settings.heartbeat_stale_minutes);
+
+    let others: Vec<&Heartbeat> = heartbeats.iter().filter(|h| h.instance != own_instance).collect();
+    let live = others.iter().filter(|h| now - h.last_seen <= stale_after).count();
+    for silent in others.iter().filter(|h| now - h.last_seen > stale_after) {
+        issues.push(ReadinessIssue::StandbySilent { instance: silent.instance.clone(), last_seen: silent.last_seen });
+    }
+    if live < settings.min_standbys {
+        issues.push(ReadinessIssue::StandbysMissing { live, required: settings.min_standbys });
+    }
+
+    if settings.expect_replica && let Some(replicas) = replicas {
+        if replicas.is_empty() {
+            issues.push(ReadinessIssue::NoReplica);
+        }
+        for replica in replicas {
+            if replica.state != "streaming" {
+                issues.push(ReadinessIssue::ReplicaNotStreaming { name: replica.name.clone(), state: replica.state.clone() });
+            } else if let Some(lag) = replica.replay_lag_seconds.filter(|lag| *lag > settings.max_replication_lag_seconds) {
+                issues.push(ReadinessIssue::ReplicaLagging { name: replica.name.clone(), lag_seconds: lag });
+            }
+        }
+    }
+    issues
+}
+
+/// Reports issues when they appear and when they clear
+#[derive(Debug, Default)]
+pub struct ReadinessTracker {
+    open: HashMap<String, ReadinessIssue>,
+}
+
+impl ReadinessTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// (newly appeared issues, issues that cleared as last seen)
+    pub fn update(&mut self, issues: &[ReadinessIssue]) -> (Vec<ReadinessIssue>, Vec<ReadinessIssue>) {
+        let current: HashMap<String, ReadinessIssue> = issues.iter().map(|i| (i.key(), i.clone())).collect();
+        let opened = issues.iter().filter(|i| !self.open.contains_key(&i.key())).cloned().collect();
+        let mut cleared: Vec<(String, ReadinessIssue)> = self.open.drain()
+            .filter(|(key, _)| !current.contains_key(key))
+            .collect();
+        cleared.sort_by(|a, b| a.0.cmp(&b.0));
+        self.open = current;
+        (opened, cleared.into_iter().map(|(_, issue)| issue).collect())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Database
+// ---------------------------------------------------------------------------
+
+/// Record that this daemon is alive
+pub fn write_heartbeat(client: &mut Client, instance: &str, role: Role, registry_version: Option<i32>) -> Result<(), String> {
+    client.execute(
+        "INSERT INTO daemon_heartbeats (instance, host, role, registry_version, started_at, last_seen)
+         VALUES ($1, $2, $3, $4, NOW(), NOW())
+         ON CONFLICT (instance) DO UPDATE SET
+            host = EXCLUDED.host,
+            role = EXCLUDED.role,
+            registry_version = EXCLUDED.registry_version,
+            last_seen = EXCLUDED.last_seen",
+        &[&instance, &host_name(), &role.as_str(), &registry_version],
+    ).map_err(|e| format!("Failed to write daemon heartbeat: {}", e))?;
+    Ok(())
+}
+
+pub fn load_heartbeats(client: &mut Client) -> Result<Vec<Heartbeat>, String> {
+    let rows = client.query(
+        "SELECT instance, host, role, last_seen FROM daemon_heartbeats ORDER BY instance",
+        &[],
+    ).map_err(|e| format!("Failed to read daemon heartbeats: {}", e))?;
+    Ok(rows.iter()
+        .map(|row| Heartbeat { instance: row.get(0), host: row.get(1), role: row.get(2), last_seen: row.get(3) })
+        .collect())
+}
+
+/// Replicas attached to this database, or None when it is itself a replica
+pub fn load_replicas(client: &mut Client) -> Result<Option<Vec<Replica>>, String> {
+    let in_recovery: bool = client.query_one("SELECT pg_is_in_recovery()", &[])
+        .map_err(|e| format!("Failed to check recovery state: {}", e))?
+        .get(0);
+    if in_recovery {
+        return Ok(None);
+    }
+    let rows = client.query(
+        "SELECT COALESCE(application_name, ''), COALESCE(state, 'unknown'),
+                EXTRACT(EPOCH FROM replay_lag)::FLOAT8
+         FROM pg_stat_replication
+         ORDER BY application_name",
+        &[],
+    ).map_err(|e| format!("Failed to read pg_stat_replication: {}", e))?;
+    Ok(Some(rows.iter()
+        .map(|row| Replica { name: row.get(0), state: row.get(1), replay_lag_seconds: row.get(2) })
+        .collect()))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()
+    }
+
+    fn beat(instance: &str, minutes_ago: i64) -> Heartbeat {
+        Heartbeat {
+            instance: instance.to_string(),
+            host: instance.to_string(),
+            role: "standby".to_string(),
+            last_seen: now() - Duration::minutes(minutes_ago),
+        }
+    }
+
+    fn replica(state: &str, lag: Option<f64>) -> Replica {
+        Replica { name: "standby1".to_string(), state: state.to_string(), replay_lag_seconds: lag }
+    }
+
+    #[test]
+    fn test_ready_pair_has_no_issues() {
+        let settings = FailoverSettings::default();
+        let issues = assess(&settings, "primary", &[beat("primary", 0), beat("standby", 10)],
+            Some(&[replica("streaming", Some(4.0))]), now());
+        assert!(issues.is_empty());
+        // Checked from the replica side: nothing to say about replication
+        assert!(assess(&settings, "primary", &[beat("standby", 10)], None, now()).is_empty());
+    }
+
+    #[test]
+    fn test_silent_standby_and_lagging_replica() {
+        let settings = FailoverSettings::default();
+        let issues = assess(&settings, "primary", &[beat("primary", 0), beat("standby", 90)],
+            Some(&[replica("streaming", Some(900.0))]), now());
+        let described: Vec<String> = issues.iter().map(ReadinessIssue::describe).collect();
+        assert_eq!(described, [
+            "Standby daemon standby silent since 2024-05-01 10:30 UTC",
+            "0 live standby daemon(s), 1 required",
+            "Replica standby1 is 900 s behind the primary",
+        ]);
+
+        let issues = assess(&settings, "primary", &[beat("standby", 1)], Some(&[replica("catchup", None)]), now());
+        assert_eq!(issues, [ReadinessIssue::ReplicaNotStreaming { name: "standby1".into(), state: "catchup".into() }]);
+        assert_eq!(assess(&settings, "primary", &[beat("standby", 1)], Some(&[]), now()), [ReadinessIssue::NoReplica]);
+    }
+
+    #[test]
+    fn test_tracker_reports_appearance_and_clearing_once() {
+        let mut tracker = ReadinessTracker::new();
+        let lagging = |lag| ReadinessIssue::ReplicaLagging { name: "standby1".into(), lag_seconds: lag };
+
+        let (opened, cleared) = tracker.update(&[lagging(400.0)]);
+        assert_eq!((opened.len(), cleared.len()), (1, 0));
+        // Same issue, worse: not reported again
+        assert_eq!(tracker.update(&[lagging(800.0)]), (Vec::new(), Vec::new()));
+        assert_eq!(tracker.update(&[]), (Vec::new(), vec![lagging(800.0)]));
+    }
+}
@@ -0,0 +1,171 @@
+//! Low-expectation polling windows for upstream maintenance.
+//!
+//! NWIS goes through nightly maintenance during which requests time out or
+//! come back empty, and every station failing every cycle buried real
+//! problems in the failure log. A `[[maintenance_windows]]` entry marks a
+//! daily period for one source during which:
+//!
+//! - poll failures are classified `Expected` and logged at DEBUG instead
+//!   of going to stderr
+//! - retries are gentler: USGS skips the RDB fallback request and a CWMS
+//!   location stops at its first failing timeseries (see `ingest::fetch`)
+//!
+//! Failures still count toward `monitoring_state.consecutive_failures`, so
+//! staleness alerting is unchanged. Times are UTC, so a window doesn't
+//! shift with the host's time zone; a window whose `end` is not after
+//! `start` runs past midnight. `days` defaults to every day. One station
+//! down for days is a suppression instead (see `monitor::suppression`).
+//!
+//! ```toml
+//! [[maintenance_windows]]
+//! source = "usgs"
+//! start = "04:00"
+//! end = "06:00"
+//! reason = "NWIS nightly maintenance"
+//!
+//! [[maintenance_windows]]
+//! source = "cwms"
+//! days = ["sun"]
+//! start = "10:00"
+//! end = "14:00"
+//! ```
+
+use chrono::{DateTime, Datelike, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::logging::DataSource;
+
+/// Polled source a window applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Usgs,
+    Cwms,
+    Asos,
+}
+
+impl Source {
+    /// Log source for failures of this feed
+    pub fn data_source(self) -> DataSource {
+        match self {
+            Source::Usgs => DataSource::Usgs,
+            Source::Cwms => DataSource::Cwms,
+            Source::Asos => DataSource::Asos,
+        }
+    }
+}
+
+/// `[[maintenance_windows]]` entry: `start`..`end` (HH:MM UTC) on each of `days`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MaintenanceWindow {
+    pub source: Source,
+    /// "mon" through "sun"; every day when empty
+    #[serde(default)]
+    pub days: Vec<String>,
+    pub start: String,
+    pub end: String,
+    /// Shown in the log lines of failures inside the window
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+fn parse_day(day: &str) -> Result<Weekday, String> {
+    day.parse::<Weekday>().map_err(|_| format!("unknown day '{}' (use mon..sun)", day))
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| format!("invalid time '{}' (use HH:MM)", time))
+}
+
+impl MaintenanceWindow {
+    pub fn validate(&self) -> Result<(), String> {
+        for day in &self.days {
+            parse_day(day).map_err(|e| format!("maintenance window: {}", e))?;
+        }
+        let start = parse_time(&self.start).map_err(|e| format!("maintenance window: {}", e))?;
+        let end = parse_time(&self.end).map_err(|e| format!("maintenance window: {}", e))?;
+        if start == end {
+            return Err(format!("maintenance window {}-{} is empty", self.start, self.end));
+        }
+        Ok(())
+    }
+
+    /// Whether `at` falls inside the window. Unparseable windows (rejected
+    /// by `validate`) never match.
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let (Ok(start), Ok(end)) = (parse_time(&self.start), parse_time(&self.end)) else {
+            return false;
+        };
+        let time = at.time();
+        let weekday = at.weekday();
+        let on = |day: Weekday| {
+            self.days.is_empty() || self.days.iter().filter_map(|d| parse_day(d).ok()).any(|d| d == day)
+        };
+        if end > start {
+            on(weekday) && time >= start && time < end
+        } else {
+            (on(weekday) && time >= start) || (on(weekday.pred()) && time < end)
+        }
+    }
+
+    /// Short description for log lines
+    pub fn label(&self) -> String {
+        match &self.reason {
+            Some(reason) => format!("{} ({}-{} UTC)", reason, self.start, self.end),
+            None => format!("maintenance window {}-{} UTC", self.start, self.end),
+        }
+    }
+}
+
+/// The first of `windows` for `source` covering `at`
+pub fn active(windows: &[MaintenanceWindow], source: Source, at: DateTime<Utc>) -> Option<&MaintenanceWindow> {
+    windows.iter().find(|w| w.source == source && w.contains(at))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn window(source: Source, days: &[&str], start: &str, end: &str) -> MaintenanceWindow {
+        MaintenanceWindow {
+            source,
+            days: days.iter().map(|d| d.to_string()).collect(),
+            start: start.to_string(),
+            end: end.to_string(),
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn test_window_past_midnight_applies_to_next_morning() {
+        // 2026-10-16 is a Friday
+        let w = window(Source::Usgs, &["fri"], "23:00", "02:00");
+        assert!(w.contains(Utc.with_ymd_and_hms(2026, 10, 16, 23, 30, 0).unwrap()));
+        assert!(w.contains(Utc.with_ymd_and_hms(2026, 10, 17, 1, 59, 0).unwrap()));
+        assert!(!w.contains(Utc.with_ymd_and_hms(2026, 10, 17, 2, 0, 0).unwrap()));
+        assert!(!w.contains(Utc.with_ymd_and_hms(2026, 10, 16, 1, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_active_matches_source_and_time() {
+        let windows = vec![window(Source::Usgs, &[], "04:00", "06:00")];
+        let at = Utc.with_ymd_and_hms(2026, 10, 16, 5, 0, 0).unwrap();
+        assert!(active(&windows, Source::Usgs, at).is_some());
+        assert!(active(&windows, Source::Cwms, at).is_none());
+        assert!(active(&windows, Source::Usgs, at + chrono::Duration::hours(2)).is_none());
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_windows() {
+        assert!(window(Source::Usgs, &[], "04:00", "06:00").validate().is_ok());
+        assert!(window(Source::Usgs, &["someday"], "04:00", "06:00").validate().is_err());
+        assert!(window(Source::Usgs, &[], "4am", "06:00").validate().is_err());
+        assert!(window(Source::Usgs, &[], "04:00", "04:00").validate().is_err());
+    }
+}
@@ -1,31 +1,40 @@
-/// Real-time monitoring service with database-backed staleness tracking.
-///
-/// ## Architecture: Hybrid Database + In-Memory
-///
-/// **Database (source of truth):**
-/// - `monitoring_state` table tracks polling state per station
-/// - `station_health` view provides current health dashboard
-/// - Survives service restarts
-/// - Queryable for historical analysis
-///
-/// **In-Memory (performance):**
-/// - Cache of latest readings to avoid DB queries on every check
-/// - Quick staleness checks without hitting database
-/// - Refreshed from DB on service startup
-/// - Updated on each successful poll
-///
-/// **Flow:**
-/// 1. Service polls USGS API every 15 minutes
-/// 2. Store new readings in `gauge_readings` table
-/// 3. Update `monitoring_state` via `update_monitoring_state()` function
-/// 4. Update in-memory cache from DB
-/// 5. Staleness checks use cache (fall back to DB if needed)
-///
-/// This approach provides:
-/// - Persistence (database survives crashes)
-/// - Performance (in-memory for hot path)
-/// - Auditability (DB tracks state changes over time)
-/// - Simplicity (no dual state files to keep in sync)
+//! Real-time monitoring service with database-backed staleness tracking.
+//!
+//! ## Architecture: Hybrid Database + In-Memory
+//!
+//! **Database (source of truth):**
+//! - `monitoring_state` table tracks polling state per station
+//! - `station_health` view provides current health dashboard
+//! - Survives service restarts
+//! - Queryable for historical analysis
+//!
+//! **In-Memory (performance):**
+//! - Cache of latest readings to avoid DB queries on every check
+//! - Quick staleness checks without hitting database
+//! - Refreshed from DB on service startup
+//! - Updated on each successful poll
+//!
+//! **Flow:**
+//! 1. Service polls USGS API every 15 minutes
+//! 2. Store new readings in `gauge_readings` table
+//! 3. Update `monitoring_state` via `update_monitoring_state()` function
+//! 4. Update in-memory cache from DB
+//! 5. Staleness checks use cache (fall back to DB if needed)
+//!
+//! This approach provides:
+//! - Persistence (database survives crashes)
+//! - Performance (in-memory for hot path)
+//! - Auditability (DB tracks state changes over time)
+//! - Simplicity (no dual state files to keep in sync)
+
+pub mod adaptive;
+pub mod cadence;
+pub mod catchup;
+pub mod event_mode;
+pub mod failover;
+pub mod maintenance;
+pub mod schedule;
+pub mod suppression;
 
 use crate::model::GaugeReading;
 use chrono::{DateTime, Utc};
@@ -75,6 +84,12 @@ pub struct MonitoringCache {
     last_refresh: DateTime<Utc>,
 }
 
+impl Default for MonitoringCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MonitoringCache {
     pub fn new() -> Self {
         Self {
@@ -128,11 +143,10 @@ impl MonitoringCache {
 
     /// Check if data is stale using cached threshold.
     pub fn is_stale(&self, site_code: &str, parameter_code: &str, now: DateTime<Utc>) -> bool {
-        if let Some(cached) = self.get(site_code, parameter_code) {
-            if let Some(reading_time) = cached.latest_reading_time {
-                let age_minutes = (now - reading_time).num_minutes();
-                return age_minutes > cached.staleness_threshold_minutes as i64;
-            }
+        if let Some(cached) = self.get(site_code, parameter_code)
+            && let Some(reading_time) = cached.latest_reading_time {
+            let age_minutes = (now - reading_time).num_minutes();
+            return age_minutes > cached.staleness_threshold_minutes as i64;
         }
         true // Unknown stations are stale by default
     }
@@ -162,16 +176,11 @@ pub fn record_poll_result(
     let latest = readings
         .iter()
         .filter(|r| r.site_code == site_code && r.parameter_code == parameter_code)
-        .max_by_key(|r| &r.datetime);
-
-    let (latest_time, latest_value) = if let Some(reading) = latest {
-        // Parse datetime from string
-        let dt = chrono::DateTime::parse_from_rfc3339(&reading.datetime)
-            .map(|dt| dt.with_timezone(&Utc))
-            .ok();
-        (dt, Some(reading.value))
-    } else {
-        (None, None)
+        .max_by_key(|r| r.datetime);
+
+    let (latest_time, latest_value) = match latest {
+        Some(reading) => (Some(reading.datetime.with_timezone(&Utc)), Some(reading.value)),
+        None => (None, None),
     };
 
     // Call database function to update state
@@ -241,35 +250,35 @@ pub struct StationHealthRow {
 // Example Real-Time Service Loop
 // ---------------------------------------------------------------------------
 
-/// Example main monitoring loop demonstrating hybrid approach.
-/// 
-/// This would run continuously in main.rs:
-/// ```no_run
-/// loop {
-///     // 1. Poll USGS API
-///     let readings = fetch_latest_from_usgs()?;
-///     
-///     // 2. Store in database
-///     store_readings(&mut db, &readings)?;
-///     
-///     // 3. Update monitoring state
-///     for station in stations {
-///         record_poll_result(&mut db, &station.site_code, "00060", true, &readings)?;
-///     }
-///     
-///     // 4. Refresh in-memory cache
-///     cache.refresh_from_db(&mut db)?;
-///     
-///     // 5. Check for alerts
-///     let unhealthy = cache.unhealthy_stations();
-///     if !unhealthy.is_empty() {
-///         send_alerts(&unhealthy)?;
-///     }
-///     
-///     // 6. Sleep until next poll
-///     sleep(Duration::from_secs(15 * 60));
-/// }
-/// ```
+// Example main monitoring loop demonstrating hybrid approach.
+// 
+// This would run continuously in main.rs:
+// ```no_run
+// loop {
+//     // 1. Poll USGS API
+//     let readings = fetch_latest_from_usgs()?;
+//     
+//     // 2. Store in database
+//     store_readings(&mut db, &readings)?;
+//     
+//     // 3. Update monitoring state
+//     for station in stations {
+//         record_poll_result(&mut db, &station.site_code, "00060", true, &readings)?;
+//     }
+//     
+//     // 4. Refresh in-memory cache
+//     cache.refresh_from_db(&mut db)?;
+//     
+//     // 5. Check for alerts
+//     let unhealthy = cache.unhealthy_stations();
+//     if !unhealthy.is_empty() {
+//         send_alerts(&unhealthy)?;
+//     }
+//     
+//     // 6. Sleep until next poll
+//     sleep(Duration::from_secs(15 * 60));
+// }
+// ```
 
 #[cfg(test)]
 mod tests {
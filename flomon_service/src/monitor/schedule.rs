@@ -0,0 +1,129 @@
+//! Per-feed poll scheduling.
+//!
+//! Each USGS station, CWMS location and ASOS station keeps its own next
+//! poll time. A cycle fetches only the feeds that are due and then
+//! schedules each one again at its own interval; the daemon sleeps until
+//! the earliest next poll. Intervals are:
+//!
+//! - USGS: the adaptive interval (see `monitor::adaptive`)
+//! - CWMS and ASOS: the interval of the location's monitoring priority
+//!   (15 minutes for CRITICAL up to daily for LOW)
+//!
+//! While any station is at or above action stage, or the system is in
+//! Watch or Event mode, polling is tightened: every USGS station is polled
+//! at the elevated interval and no CWMS or ASOS feed waits longer than the
+//! base poll interval.
+//!
+//! A feed never polled is due at once, so a fresh daemon (and every
+//! `ingest --once`) polls everything on its first cycle.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+use crate::monitor::maintenance::Source;
+
+/// Feeds due within this many seconds are polled with the current cycle
+/// rather than waking the daemon again moments later
+pub const DUE_TOLERANCE_SECS: i64 = 30;
+
+/// Next poll time of every feed polled so far
+#[derive(Debug, Default)]
+pub struct PollSchedule {
+    next_poll: HashMap<(Source, String), DateTime<Utc>>,
+}
+
+impl PollSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `id` should be polled in a cycle starting at `now`
+    pub fn is_due(&self, source: Source, id: &str, now: DateTime<Utc>) -> bool {
+        self.next_poll.get(&(source, id.to_string()))
+            .is_none_or(|next| *next <= now + Duration::seconds(DUE_TOLERANCE_SECS))
+    }
+
+    /// Record a poll of `id` in the cycle that started at `now`, successful
+    /// or not, and schedule the next one `interval_minutes` later
+    pub fn polled(&mut self, source: Source, id: &str, now: DateTime<Utc>, interval_minutes: u64) {
+        self.next_poll.insert((source, id.to_string()), now + Duration::minutes(interval_minutes as i64));
+    }
+
+    /// Bring `id` forward so it is polled no later than `at`
+    pub fn advance(&mut self, source: Source, id: &str, at: DateTime<Utc>) {
+        if let Some(next) = self.next_poll.get_mut(&(source, id.to_string())) {
+            *next = (*next).min(at);
+        }
+    }
+
+    /// Earliest scheduled poll of any feed
+    pub fn next_due(&self) -> Option<DateTime<Utc>> {
+        self.next_poll.values().min().copied()
+    }
+
+    pub fn next_poll(&self, source: Source, id: &str) -> Option<DateTime<Utc>> {
+        self.next_poll.get(&(source, id.to_string())).copied()
+    }
+}
+
+/// A CWMS or ASOS feed's interval: its priority interval, capped at the
+/// base poll interval while polling is tightened
+pub fn priority_interval(priority_minutes: u64, base_minutes: u64, tightened: bool) -> u64 {
+    if tightened {
+        priority_minutes.min(base_minutes)
+    } else {
+        priority_minutes
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn t(minute: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap() + Duration::minutes(minute)
+    }
+
+    #[test]
+    fn test_feeds_keep_independent_next_polls() {
+        let mut schedule = PollSchedule::new();
+        assert!(schedule.is_due(Source::Asos, "KPIA", t(0)), "never polled");
+        assert_eq!(schedule.next_due(), None);
+
+        schedule.polled(Source::Usgs, "05568500", t(0), 15);
+        schedule.polled(Source::Asos, "KPIA", t(0), 60);
+        schedule.polled(Source::Cwms, "Grafton", t(0), 360);
+        assert_eq!(schedule.next_due(), Some(t(15)));
+
+        // The USGS station is due a few seconds early; the others are not
+        let wake = t(15) - Duration::seconds(5);
+        assert!(schedule.is_due(Source::Usgs, "05568500", wake));
+        assert!(!schedule.is_due(Source::Asos, "KPIA", wake));
+        assert!(!schedule.is_due(Source::Cwms, "Grafton", t(300)));
+        // Same id under another source is a different feed
+        assert!(schedule.is_due(Source::Cwms, "KPIA", wake));
+
+        schedule.polled(Source::Usgs, "05568500", t(15), 15);
+        assert_eq!(schedule.next_due(), Some(t(30)));
+    }
+
+    #[test]
+    fn test_tightening_brings_slow_feeds_forward() {
+        assert_eq!(priority_interval(1440, 15, false), 1440);
+        assert_eq!(priority_interval(1440, 15, true), 15);
+        assert_eq!(priority_interval(15, 30, true), 15, "never loosened");
+
+        let mut schedule = PollSchedule::new();
+        schedule.polled(Source::Cwms, "Grafton", t(0), 360);
+        schedule.advance(Source::Cwms, "Grafton", t(15));
+        assert_eq!(schedule.next_poll(Source::Cwms, "Grafton"), Some(t(15)));
+        // Never pushed back
+        schedule.advance(Source::Cwms, "Grafton", t(60));
+        assert_eq!(schedule.next_poll(Source::Cwms, "Grafton"), Some(t(15)));
+    }
+}
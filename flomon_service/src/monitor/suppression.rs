@@ -0,0 +1,315 @@
+//! Per-station suppressions: "down until <time> because <reason>".
+//!
+//! Maintenance windows (`monitor::maintenance`) cover a whole source on a
+//! daily schedule. A station pulled for a gauge-house rebuild, or one
+//! sensor of it sent off for calibration, is down for days at one site
+//! instead, and every cycle logged its failures and alerted on its
+//! silence. A row in `station_suppressions`, written through
+//! `POST /api/suppressions`, marks a station (or one parameter of it, a
+//! "sensor") down until a time. Until then the daemon:
+//!
+//! - classifies the station's poll failures `Expected` (logged at DEBUG)
+//! - skips its stale-data and overdue-reading alerts
+//! - leaves the suppressed sensor's readings out of severity, so a
+//!   technician's test readings don't raise or clear a flood alert
+//!
+//! Failures still count toward `monitoring_state.consecutive_failures`,
+//! as inside a maintenance window. A suppression ends at `until`, or early
+//! through `POST /api/suppressions/{id}/end`; ended rows are kept as a
+//! record. The daemon re-reads the active ones every cycle.
+//!
+//! ```toml
+//! [suppressions]
+//! token = "${SUPPRESSION_TOKEN}"   # bearer token for the POST routes
+//! ```
+
+use chrono::{DateTime, Utc};
+use postgres::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::auth;
+use crate::monitor::maintenance::Source;
+
+/// Most rows `GET /api/suppressions?all=true` returns
+pub const LIST_LIMIT: i64 = 200;
+
+/// `[suppressions]` section of flomon.toml
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SuppressionSettings {
+    /// Shared secret for the POST routes; they are disabled without it
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+impl SuppressionSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(token) = &self.token {
+            auth::validate_token("suppressions.token", token)?;
+        }
+        Ok(())
+    }
+
+    /// Whether `authorization` (the header value) carries the token
+    pub fn authorized(&self, authorization: Option<&str>) -> bool {
+        self.token.as_deref().is_some_and(|expected| auth::bearer_authorized(authorization, expected))
+    }
+}
+
+/// Body of `POST /api/suppressions`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NewSuppression {
+    pub source: Source,
+    /// USGS site code, CWMS location name or ASOS station id, as polled
+    pub station_id: String,
+    /// Only this parameter (e.g. "00060"); the whole station when absent
+    #[serde(default)]
+    pub parameter_code: Option<String>,
+    pub until: DateTime<Utc>,
+    pub reason: String,
+    /// Who set it, for the record
+    #[serde(default)]
+    pub created_by: Option<String>,
+}
+
+impl NewSuppression {
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<(), String> {
+        if self.station_id.trim().is_empty() {
+            return Err("station_id is empty".to_string());
+        }
+        if self.parameter_code.as_ref().is_some_and(|p| p.trim().is_empty()) {
+            return Err("parameter_code is empty (omit it to suppress the whole station)".to_string());
+        }
+        if self.reason.trim().is_empty() {
+            return Err("reason is empty".to_string());
+        }
+        if self.until <= now {
+            return Err(format!("until {} is not in the future", self.until.to_rfc3339()));
+        }
+        Ok(())
+    }
+}
+
+/// One row of `station_suppressions`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Suppression {
+    pub id: i64,
+    pub source: Source,
+    pub station_id: String,
+    /// None for the whole station
+    pub parameter_code: Option<String>,
+    pub until: DateTime<Utc>,
+    pub reason: String,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Set when ended before `until`
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+impl Suppression {
+    pub fn is_active(&self, at: DateTime<Utc>) -> bool {
+        self.ended_at.is_none() && self.created_at <= at && at < self.until
+    }
+
+    /// Whether this suppression covers `parameter_code` at the station
+    /// (`None`: the station as a whole) at `at`. A station-wide
+    /// suppression covers every parameter; a sensor's covers only itself.
+    pub fn covers(&self, source: Source, station_id: &str, parameter_code: Option<&str>, at: DateTime<Utc>) -> bool {
+        self.source == source
+            && self.station_id.eq_ignore_ascii_case(station_id)
+            && match (&self.parameter_code, parameter_code) {
+                (None, _) => true,
+                (Some(suppressed), Some(parameter)) => suppressed == parameter,
+                (Some(_), None) => false,
+            }
+            && self.is_active(at)
+    }
+
+    /// Short description for log lines, e.g.
+    /// "suppression #4 (gauge house rebuild, until 2026-10-20 18:00 UTC)"
+    pub fn label(&self) -> String {
+        format!("suppression #{} ({}, until {})", self.id, self.reason, self.until.format("%Y-%m-%d %H:%M UTC"))
+    }
+}
+
+/// The first of `suppressions` covering the station or sensor at `at`
+pub fn find<'a>(
+    suppressions: &'a [Suppression],
+    source: Source,
+    station_id: &str,
+    parameter_code: Option<&str>,
+    at: DateTime<Utc>,
+) -> Option<&'a Suppression> {
+    suppressions.iter().find(|s| s.covers(source, station_id, parameter_code, at))
+}
+
+fn source_name(source: Source) -> &'static str {
+    match source {
+        Source::Usgs => "usgs",
+        Source::Cwms => "cwms",
+        Source::Asos => "asos",
+    }
+}
+
+fn parse_source(name: &str) -> Result<Source, String> {
+    match name {
+        "usgs" => Ok(Source::Usgs),
+        "cwms" => Ok(Source::Cwms),
+        "asos" => Ok(Source::Asos),
+        other => Err(format!("Unknown suppression source '{}'", other)),
+    }
+}
+
+const COLUMNS: &str = "id, source, station_id, parameter_code, until, reason, created_by, created_at, ended_at";
+
+fn from_row(row: &postgres::Row) -> Result<Suppression, String> {
+    let source: String = row.get(1);
+    Ok(Suppression {
+        id: row.get(0),
+        source: parse_source(&source)?,
+        station_id: row.get(2),
+        parameter_code: row.get(3),
+        until: row.get(4),
+        reason: row.get(5),
+        created_by: row.get(6),
+        created_at: row.get(7),
+        ended_at: row.get(8),
+    })
+}
+
+/// Store a validated suppression starting at `now`
+pub fn create(client: &mut Client, new: &NewSuppression, now: DateTime<Utc>) -> Result<Suppression, String> {
+    let row = client.query_one(
+        &format!(
+            "INSERT INTO station_suppressions (source, station_id, parameter_code, until, reason, created_by, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING {}",
+            COLUMNS,
+        ),
+        &[&source_name(new.source), &new.station_id.trim(), &new.parameter_code.as_deref().map(str::trim),
+          &new.until, &new.reason.trim(), &new.created_by, &now],
+    ).map_err(|e| format!("Failed to store suppression: {}", e))?;
+    from_row(&row)
+}
+
+/// End suppression `id` at `now`; None if there is no such suppression
+/// still in effect
+pub fn end(client: &mut Client, id: i64, now: DateTime<Utc>) -> Result<Option<Suppression>, String> {
+    let row = client.query_opt(
+        &format!(
+            "UPDATE station_suppressions SET ended_at = $2
+             WHERE id = $1 AND ended_at IS NULL AND until > $2
+             RETURNING {}",
+            COLUMNS,
+        ),
+        &[&id, &now],
+    ).map_err(|e| format!("Failed to end suppression {}: {}", id, e))?;
+    row.as_ref().map(from_row).transpose()
+}
+
+/// Suppressions in effect at `now`, soonest to end first
+pub fn active(client: &mut Client, now: DateTime<Utc>) -> Result<Vec<Suppression>, String> {
+    let rows = client.query(
+        &format!(
+            "SELECT {} FROM station_suppressions
+             WHERE ended_at IS NULL AND created_at <= $1 AND until > $1
+             ORDER BY until, id",
+            COLUMNS,
+        ),
+        &[&now],
+    ).map_err(|e| format!("Failed to load suppressions: {}", e))?;
+    rows.iter().map(from_row).collect()
+}
+
+/// The latest `LIST_LIMIT` suppressions, ended ones included, newest first
+pub fn recent(client: &mut Client) -> Result<Vec<Suppression>, String> {
+    let rows = client.query(
+        &format!("SELECT {} FROM station_suppressions ORDER BY created_at DESC, id DESC LIMIT $1", COLUMNS),
+        &[&LIST_LIMIT],
+    ).map_err(|e| format!("Failed to load suppressions: {}", e))?;
+    rows.iter().map(from_row).collect()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap()
+    }
+
+    fn suppression(station_id: &str, parameter_code: Option<&str>) -> Suppression {
+        Suppression {
+            id: 4,
+            source: Source::Usgs,
+            station_id: station_id.to_string(),
+            parameter_code: parameter_code.map(str::to_string),
+            until: now() + Duration::days(3),
+            reason: "gauge house rebuild".to_string(),
+            created_by: None,
+            created_at: now() - Duration::hours(1),
+            ended_at: None,
+        }
+    }
+
+    #[test]
+    fn test_station_and_sensor_coverage() {
+        let station = suppression("05567500", None);
+        assert!(station.covers(Source::Usgs, "05567500", None, now()));
+        assert!(station.covers(Source::Usgs, "05567500", Some("00065"), now()));
+        assert!(!station.covers(Source::Cwms, "05567500", None, now()));
+        assert!(!station.covers(Source::Usgs, "05568500", None, now()));
+
+        let sensor = suppression("05567500", Some("00060"));
+        assert!(sensor.covers(Source::Usgs, "05567500", Some("00060"), now()));
+        assert!(!sensor.covers(Source::Usgs, "05567500", Some("00065"), now()));
+        assert!(!sensor.covers(Source::Usgs, "05567500", None, now()), "one sensor is not the station");
+
+        let list = [sensor, station];
+        assert_eq!(find(&list, Source::Usgs, "05567500", None, now()).map(|s| s.parameter_code.clone()), Some(None));
+        assert_eq!(
+            list[1].label(),
+            "suppression #4 (gauge house rebuild, until 2026-10-19 12:00 UTC)",
+        );
+    }
+
+    #[test]
+    fn test_suppression_lapses_at_until_or_when_ended() {
+        let mut s = suppression("KPIA", None);
+        s.source = Source::Asos;
+        assert!(s.covers(Source::Asos, "kpia", None, now()));
+        assert!(!s.covers(Source::Asos, "KPIA", None, s.until));
+        assert!(!s.covers(Source::Asos, "KPIA", None, s.created_at - Duration::minutes(1)));
+        s.ended_at = Some(now());
+        assert!(!s.is_active(now()));
+    }
+
+    #[test]
+    fn test_new_suppression_validation() {
+        let body = r#"{"source": "cwms", "station_id": "Peoria", "until": "2026-10-20T18:00:00Z", "reason": "dam gate work"}"#;
+        let new: NewSuppression = serde_json::from_str(body).unwrap();
+        assert_eq!(new.source, Source::Cwms);
+        assert!(new.validate(now()).is_ok());
+        assert!(new.validate(new.until).unwrap_err().contains("not in the future"));
+
+        let blank = NewSuppression { reason: " ".to_string(), ..new.clone() };
+        assert!(blank.validate(now()).is_err());
+        let empty_sensor = NewSuppression { parameter_code: Some(String::new()), ..new };
+        assert!(empty_sensor.validate(now()).is_err());
+        assert!(serde_json::from_str::<NewSuppression>(r#"{"source": "nws", "station_id": "x", "until": "2026-10-20T18:00:00Z", "reason": "r"}"#).is_err());
+
+        let settings = SuppressionSettings { token: Some("s3cret-s3cret-s3cret".to_string()) };
+        assert!(settings.validate().is_ok());
+        assert!(settings.authorized(Some("Bearer s3cret-s3cret-s3cret")));
+        assert!(!settings.authorized(Some("Bearer wrong")));
+        assert!(!SuppressionSettings { token: None }.authorized(Some("Bearer s3cret-s3cret-s3cret")));
+        assert!(SuppressionSettings { token: Some(String::new()) }.validate().is_err(), "an unset ${{SUPPRESSION_TOKEN}}");
+        assert!(SuppressionSettings { token: Some("s3cret".to_string()) }.validate().is_err());
+    }
+}
@@ -0,0 +1,424 @@
+//! NWS forecast zone and county mapping for alert geo-relevance.
+//!
+//! NWS CAP alerts (api.weather.gov/alerts) say where they apply with UGC
+//! codes (forecast zones like `ILZ031`, counties like `ILC143`) and SAME
+//! codes (`0` + the 5-digit county FIPS, `017143`). A Flash Flood Warning
+//! for a county 100 miles off the basin is noise here, so ingested alerts
+//! are kept only when one of their codes matches a monitored station or a
+//! zone sensor.
+//!
+//! Each station's county FIPS (and optionally its forecast zone) may be set
+//! in usgs_stations.toml; anything missing is looked up from the station's
+//! coordinates with the api.weather.gov points endpoint. Zones get the
+//! union of their sensors' areas. `flomon geo resolve` does the lookups and
+//! stores the result in `nws.station_geography` / `nws.zone_geography`;
+//! `load_filter` reads it back for alert ingestion.
+
+use chrono::{DateTime, Utc};
+use postgres::Client;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::ingest::base_urls;
+use crate::stations::Station;
+
+/// api.weather.gov asks clients to identify themselves
+pub const USER_AGENT: &str = "flomon (Illinois River flood monitoring)";
+
+/// State FIPS prefixes for the UGC state abbreviations around the basin
+const STATE_FIPS: &[(&str, &str)] = &[
+    ("IL", "17"),
+    ("IN", "18"),
+    ("IA", "19"),
+    ("MO", "29"),
+    ("WI", "55"),
+];
+
+// ============================================================================
+// Codes
+// ============================================================================
+
+/// NWS forecast zone and county of one point
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Geography {
+    /// UGC forecast zone, e.g. "ILZ031"
+    pub forecast_zone: Option<String>,
+    /// 5-digit county FIPS, e.g. "17143"
+    pub county_fips: Option<String>,
+}
+
+impl Geography {
+    /// County UGC code ("ILC143") for `county_fips`
+    pub fn county_ugc(&self) -> Option<String> {
+        self.county_fips.as_deref().and_then(county_ugc_from_fips)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.forecast_zone.is_some() && self.county_fips.is_some()
+    }
+}
+
+/// "ILZ031" style forecast zone code
+pub fn is_forecast_zone(code: &str) -> bool {
+    let b = code.as_bytes();
+    b.len() == 6
+        && b[..2].iter().all(u8::is_ascii_uppercase)
+        && b[2] == b'Z'
+        && b[3..].iter().all(u8::is_ascii_digit)
+}
+
+/// 5-digit county FIPS
+pub fn is_county_fips(code: &str) -> bool {
+    code.len() == 5 && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// "ILC143" -> "17143"; `None` for other code types or states
+pub fn county_fips_from_ugc(ugc: &str) -> Option<String> {
+    if ugc.len() != 6 || ugc.as_bytes()[2] != b'C' || !ugc[3..].bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let state = STATE_FIPS.iter().find(|(abbr, _)| *abbr == &ugc[..2])?.1;
+    Some(format!("{}{}", state, &ugc[3..]))
+}
+
+/// "17143" -> "ILC143"
+pub fn county_ugc_from_fips(fips: &str) -> Option<String> {
+    if !is_county_fips(fips) {
+        return None;
+    }
+    let state = STATE_FIPS.iter().find(|(_, code)| *code == &fips[..2])?.0;
+    Some(format!("{}C{}", state, &fips[2..]))
+}
+
+/// "017143" (SAME) -> "17143"
+pub fn county_fips_from_same(same: &str) -> Option<&str> {
+    (same.len() == 6 && same.bytes().all(|b| b.is_ascii_digit())).then(|| &same[1..])
+}
+
+// ============================================================================
+// Lookup
+// ============================================================================
+
+/// api.weather.gov points endpoint for a coordinate
+pub fn points_url(latitude: f64, longitude: f64) -> String {
+    format!("{}/points/{:.4},{:.4}", base_urls::get().nws, latitude, longitude)
+}
+
+/// Forecast zone and county from a points response; both are given as
+/// zone URLs ending in the UGC code
+pub fn parse_points(json: &str) -> Result<Geography, String> {
+    let doc: Value = serde_json::from_str(json)
+        .map_err(|e| format!("Invalid points response: {}", e))?;
+    let ugc = |key: &str| {
+        doc["properties"][key].as_str()
+            .and_then(|url| url.rsplit('/').next())
+            .map(str::to_string)
+            .ok_or_else(|| format!("Points response has no {}", key))
+    };
+    let forecast_zone = ugc("forecastZone")?;
+    let county = ugc("county")?;
+    if !is_forecast_zone(&forecast_zone) {
+        return Err(format!("Unexpected forecast zone {:?}", forecast_zone));
+    }
+    let county_fips = county_fips_from_ugc(&county)
+        .ok_or_else(|| format!("Unexpected county zone {:?}", county))?;
+    Ok(Geography { forecast_zone: Some(forecast_zone), county_fips: Some(county_fips) })
+}
+
+/// Look up the forecast zone and county of a coordinate
+pub fn fetch_point(http: &reqwest::blocking::Client, latitude: f64, longitude: f64) -> Result<Geography, String> {
+    let url = points_url(latitude, longitude);
+    let response = http.get(&url)
+        .header(reqwest::header::USER_AGENT, USER_AGENT)
+        .header(reqwest::header::ACCEPT, "application/geo+json")
+        .send()
+        .map_err(|e| format!("Points lookup {} failed: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Points lookup {} returned HTTP {}", url, response.status()));
+    }
+    let body = response.text().map_err(|e| format!("Points lookup {} body read failed: {}", url, e))?;
+    parse_points(&body)
+}
+
+/// Configured codes of a station, checked for format
+pub fn configured(station: &Station) -> Result<Geography, String> {
+    if let Some(zone) = station.nws_zone.as_deref().filter(|z| !is_forecast_zone(z)) {
+        return Err(format!("{}: nws_zone {:?} is not a forecast zone code like ILZ031", station.site_code, zone));
+    }
+    if let Some(fips) = station.county_fips.as_deref().filter(|f| county_ugc_from_fips(f).is_none()) {
+        return Err(format!("{}: county_fips {:?} is not a 5-digit FIPS in a basin state", station.site_code, fips));
+    }
+    Ok(Geography { forecast_zone: station.nws_zone.clone(), county_fips: station.county_fips.clone() })
+}
+
+/// Fill codes missing from `configured` with a points lookup. Configured
+/// values win; a mismatching county from the lookup is reported.
+pub fn complete(configured: Geography, looked_up: Geography) -> (Geography, Option<String>) {
+    let conflict = match (&configured.county_fips, &looked_up.county_fips) {
+        (Some(a), Some(b)) if a != b => Some(format!("configured county {} but coordinates are in {}", a, b)),
+        _ => None,
+    };
+    let merged = Geography {
+        forecast_zone: configured.forecast_zone.or(looked_up.forecast_zone),
+        county_fips: configured.county_fips.or(looked_up.county_fips),
+    };
+    (merged, conflict)
+}
+
+// ============================================================================
+// Filtering
+// ============================================================================
+
+/// Set of forecast zones and counties
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Area {
+    pub forecast_zones: BTreeSet<String>,
+    pub county_fips: BTreeSet<String>,
+}
+
+impl Area {
+    pub fn add(&mut self, geography: &Geography) {
+        self.forecast_zones.extend(geography.forecast_zone.clone());
+        self.county_fips.extend(geography.county_fips.clone());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.forecast_zones.is_empty() && self.county_fips.is_empty()
+    }
+
+    /// True if the alert names one of our forecast zones or counties, by
+    /// UGC or by SAME code
+    pub fn matches(&self, alert: &CapArea) -> bool {
+        alert.ugc.iter().any(|ugc| {
+            self.forecast_zones.contains(ugc)
+                || county_fips_from_ugc(ugc).is_some_and(|fips| self.county_fips.contains(&fips))
+        }) || alert.same.iter().any(|same| {
+            county_fips_from_same(same).is_some_and(|fips| self.county_fips.contains(fips))
+        })
+    }
+}
+
+/// Geocodes of one CAP alert
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapArea {
+    pub ugc: Vec<String>,
+    pub same: Vec<String>,
+}
+
+impl CapArea {
+    /// `properties.geocode` of an api.weather.gov alert feature
+    pub fn from_feature(feature: &Value) -> Self {
+        let codes = |key: &str| -> Vec<String> {
+            feature["properties"]["geocode"][key].as_array()
+                .map(|a| a.iter().filter_map(Value::as_str).map(str::to_string).collect())
+                .unwrap_or_default()
+        };
+        CapArea { ugc: codes("UGC"), same: codes("SAME") }
+    }
+}
+
+/// Station and zone areas used to keep only relevant alerts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AlertFilter {
+    pub stations: BTreeMap<String, Geography>,
+    pub zones: BTreeMap<usize, Area>,
+}
+
+impl AlertFilter {
+    /// Union of every station and zone area
+    pub fn basin(&self) -> Area {
+        let mut area = Area::default();
+        for geography in self.stations.values() {
+            area.add(geography);
+        }
+        for zone in self.zones.values() {
+            area.forecast_zones.extend(zone.forecast_zones.iter().cloned());
+            area.county_fips.extend(zone.county_fips.iter().cloned());
+        }
+        area
+    }
+
+    /// True if the alert covers any monitored station or zone
+    pub fn relevant(&self, alert: &CapArea) -> bool {
+        self.basin().matches(alert)
+    }
+
+    /// Stations whose forecast zone or county the alert covers
+    pub fn stations_affected(&self, alert: &CapArea) -> Vec<&str> {
+        self.stations.iter()
+            .filter(|(_, geography)| {
+                let mut area = Area::default();
+                area.add(geography);
+                area.matches(alert)
+            })
+            .map(|(site_code, _)| site_code.as_str())
+            .collect()
+    }
+
+    /// Zones the alert covers
+    pub fn zones_affected(&self, alert: &CapArea) -> Vec<usize> {
+        self.zones.iter()
+            .filter(|(_, area)| area.matches(alert))
+            .map(|(zone_id, _)| *zone_id)
+            .collect()
+    }
+
+    /// Alert features (api.weather.gov FeatureCollection) that are relevant
+    pub fn relevant_features<'a>(&self, features: &'a [Value]) -> Vec<&'a Value> {
+        let basin = self.basin();
+        features.iter().filter(|f| basin.matches(&CapArea::from_feature(f))).collect()
+    }
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+/// Replace the stored mapping. `sources` says per station whether the
+/// codes came from "config" or "api".
+pub fn store(
+    client: &mut Client,
+    filter: &AlertFilter,
+    sources: &BTreeMap<String, &str>,
+    resolved_at: DateTime<Utc>,
+) -> Result<(), String> {
+    let mut tx = client.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.batch_execute("DELETE FROM nws.station_geography; DELETE FROM nws.zone_geography;")
+        .map_err(|e| format!("Failed to clear NWS geography: {}", e))?;
+    for (site_code, geography) in &filter.stations {
+        tx.execute(
+            "INSERT INTO nws.station_geography
+             (site_code, forecast_zone, county_fips, county_ugc, source, resolved_at)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            &[
+                site_code,
+                &geography.forecast_zone,
+                &geography.county_fips,
+                &geography.county_ugc(),
+                &sources.get(site_code).copied().unwrap_or("config"),
+                &resolved_at,
+            ],
+        ).map_err(|e| format!("Failed to store geography for {}: {}", site_code, e))?;
+    }
+    for (zone_id, area) in &filter.zones {
+        let zones: Vec<String> = area.forecast_zones.iter().cloned().collect();
+        let counties: Vec<String> = area.county_fips.iter().cloned().collect();
+        tx.execute(
+            "INSERT INTO nws.zone_geography (zone_id, forecast_zones, county_fips, resolved_at)
+             VALUES ($1, $2, $3, $4)",
+            &[&(*zone_id as i32), &zones, &counties, &resolved_at],
+        ).map_err(|e| format!("Failed to store geography for zone {}: {}", zone_id, e))?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit NWS geography: {}", e))
+}
+
+/// Stored mapping, for filtering ingested alerts
+pub fn load_filter(client: &mut Client) -> Result<AlertFilter, String> {
+    let mut filter = AlertFilter::default();
+    let rows = client.query(
+        "SELECT site_code, forecast_zone, county_fips FROM nws.station_geography",
+        &[],
+    ).map_err(|e| format!("Failed to load station geography: {}", e))?;
+    for row in rows {
+        filter.stations.insert(row.get(0), Geography { forecast_zone: row.get(1), county_fips: row.get(2) });
+    }
+    let rows = client.query(
+        "SELECT zone_id, forecast_zones, county_fips FROM nws.zone_geography",
+        &[],
+    ).map_err(|e| format!("Failed to load zone geography: {}", e))?;
+    for row in rows {
+        let zone_id: i32 = row.get(0);
+        let zones: Vec<String> = row.get(1);
+        let counties: Vec<String> = row.get(2);
+        filter.zones.insert(zone_id as usize, Area {
+            forecast_zones: zones.into_iter().collect(),
+            county_fips: counties.into_iter().collect(),
+        });
+    }
+    Ok(filter)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn peoria() -> Geography {
+        Geography { forecast_zone: Some("ILZ031".to_string()), county_fips: Some("17143".to_string()) }
+    }
+
+    #[test]
+    fn test_code_conversions() {
+        assert_eq!(county_fips_from_ugc("ILC143").as_deref(), Some("17143"));
+        assert_eq!(county_fips_from_ugc("ILZ031"), None);
+        assert_eq!(county_fips_from_ugc("TXC001"), None);
+        assert_eq!(county_ugc_from_fips("17179").as_deref(), Some("ILC179"));
+        assert_eq!(county_fips_from_same("017143"), Some("17143"));
+        assert!(is_forecast_zone("ILZ031"));
+        assert!(!is_forecast_zone("ILC143"));
+        assert_eq!(peoria().county_ugc().as_deref(), Some("ILC143"));
+    }
+
+    #[test]
+    fn test_parse_points_response() {
+        let body = json!({
+            "properties": {
+                "forecastZone": "https://api.weather.gov/zones/forecast/ILZ031",
+                "county": "https://api.weather.gov/zones/county/ILC143",
+                "cwa": "ILX"
+            }
+        }).to_string();
+        assert_eq!(parse_points(&body).unwrap(), peoria());
+        assert!(parse_points(r#"{"properties": {}}"#).is_err());
+    }
+
+    #[test]
+    fn test_configured_values_win_over_lookup() {
+        let configured = Geography { forecast_zone: None, county_fips: Some("17143".to_string()) };
+        let looked_up = Geography { forecast_zone: Some("ILZ031".to_string()), county_fips: Some("17179".to_string()) };
+        let (merged, conflict) = complete(configured, looked_up);
+        assert_eq!(merged, peoria());
+        assert!(conflict.unwrap().contains("17179"));
+    }
+
+    #[test]
+    fn test_only_alerts_for_our_counties_are_relevant() {
+        let mut filter = AlertFilter::default();
+        filter.stations.insert("05567500".to_string(), peoria());
+        let mut zone = Area::default();
+        zone.add(&Geography { forecast_zone: None, county_fips: Some("17057".to_string()) });
+        filter.zones.insert(4, zone);
+
+        // County-based warning by SAME code only
+        let peoria_ffw = CapArea { ugc: vec![], same: vec!["017143".to_string()] };
+        // Zone-based watch covering Fulton County's zone and another
+        let fulton = CapArea { ugc: vec!["ILC057".to_string(), "ILZ045".to_string()], same: vec![] };
+        // Flash Flood Warning for a county far outside the basin
+        let far = CapArea { ugc: vec!["ILC163".to_string()], same: vec!["017163".to_string()] };
+
+        assert!(filter.relevant(&peoria_ffw));
+        assert_eq!(filter.stations_affected(&peoria_ffw), vec!["05567500"]);
+        assert!(filter.relevant(&fulton));
+        assert_eq!(filter.zones_affected(&fulton), vec![4]);
+        assert!(!filter.relevant(&far));
+        assert!(!filter.relevant(&CapArea::default()));
+    }
+
+    #[test]
+    fn test_relevant_features_from_alert_collection() {
+        let mut filter = AlertFilter::default();
+        filter.stations.insert("05567500".to_string(), peoria());
+        let features = vec![
+            json!({"properties": {"event": "Flood Warning", "geocode": {"UGC": ["ILC143"], "SAME": ["017143"]}}}),
+            json!({"properties": {"event": "Flash Flood Warning", "geocode": {"UGC": ["ILC163"], "SAME": ["017163"]}}}),
+            json!({"properties": {"event": "Flood Watch", "geocode": {"UGC": ["ILZ031", "ILZ032"]}}}),
+        ];
+        let kept = filter.relevant_features(&features);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[1]["properties"]["event"], "Flood Watch");
+    }
+}
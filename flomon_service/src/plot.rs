@@ -0,0 +1,189 @@
+//! Terminal hydrograph rendering for `flomon plot`.
+//!
+//! For quick checks over SSH when the dashboard is unreachable:
+//!
+//! ```text
+//! flomon plot 05568500 --hours 72
+//! ```
+//!
+//! draws a block-character chart of stage with the NWS threshold lines
+//! overlaid, plus a one-line sparkline. Pure rendering lives here; the CLI
+//! fetches readings through `archive::readings_between`.
+
+use chrono::{DateTime, Utc};
+
+use crate::model::FloodThresholds;
+
+/// Eighth-block levels, lowest to highest
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Default chart size in terminal cells
+pub const DEFAULT_WIDTH: usize = 72;
+pub const DEFAULT_HEIGHT: usize = 12;
+
+/// One-line sparkline of `values`, one character per value
+pub fn sparkline(values: &[f64]) -> String {
+    let (min, max) = bounds(values.iter().copied());
+    let span = max - min;
+    values.iter()
+        .map(|v| {
+            if span <= f64::EPSILON {
+                return BLOCKS[3];
+            }
+            let level = ((v - min) / span * (BLOCKS.len() - 1) as f64).round() as usize;
+            BLOCKS[level.min(BLOCKS.len() - 1)]
+        })
+        .collect()
+}
+
+/// Min and max of an iterator (0, 0 when empty)
+fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    if min.is_finite() { (min, max) } else { (0.0, 0.0) }
+}
+
+/// Resample to `width` columns, keeping the highest value in each bucket so
+/// a short crest is never averaged away
+pub fn resample_max(values: &[f64], width: usize) -> Vec<f64> {
+    if values.len() <= width || width == 0 {
+        return values.to_vec();
+    }
+    (0..width)
+        .map(|col| {
+            let start = col * values.len() / width;
+            let end = ((col + 1) * values.len() / width).max(start + 1);
+            values[start..end].iter().copied().fold(f64::NEG_INFINITY, f64::max)
+        })
+        .collect()
+}
+
+/// Threshold lines to draw: action and flood always, the higher stages only
+/// when the data gets within reach of them
+fn threshold_lines(thresholds: &FloodThresholds, data_max: f64) -> Vec<(&'static str, f64)> {
+    let mut lines = vec![
+        ("action", thresholds.action_stage_ft),
+        ("flood", thresholds.flood_stage_ft),
+    ];
+    if data_max >= thresholds.flood_stage_ft {
+        lines.push(("moderate", thresholds.moderate_flood_stage_ft));
+    }
+    if data_max >= thresholds.moderate_flood_stage_ft {
+        lines.push(("major", thresholds.major_flood_stage_ft));
+    }
+    lines
+}
+
+/// Render a stage chart `height` rows tall.
+///
+/// Each row is labeled with the stage at its top edge; threshold lines are
+/// drawn with `┄` where no bar covers them and named at the right margin.
+pub fn render_chart(values: &[f64], thresholds: Option<&FloodThresholds>, width: usize, height: usize) -> String {
+    if values.is_empty() || height == 0 {
+        return String::from("(no data)\n");
+    }
+    let columns = resample_max(values, width);
+    let (data_min, data_max) = bounds(columns.iter().copied());
+    let lines = thresholds.map(|t| threshold_lines(t, data_max)).unwrap_or_default();
+
+    let (mut lo, mut hi) = bounds(columns.iter().copied().chain(lines.iter().map(|(_, v)| *v)));
+    lo = lo.min(data_min);
+    if hi - lo < 1.0 {
+        // Keep flat series readable: at least one foot of range
+        let mid = (hi + lo) / 2.0;
+        lo = mid - 0.5;
+        hi = mid + 0.5;
+    }
+    let row_height = (hi - lo) / height as f64;
+
+    let mut out = String::new();
+    for row in (0..height).rev() {
+        let bottom = lo + row as f64 * row_height;
+        let top = bottom + row_height;
+        let marker = lines.iter().find(|(_, v)| *v >= bottom && (*v < top || (row == height - 1 && *v <= top)));
+
+        out.push_str(&format!("{:>7.2} │", top));
+        for &v in &columns {
+            let cell = if v >= top {
+                '█'
+            } else if v > bottom {
+                let eighths = ((v - bottom) / row_height * 8.0).ceil() as usize;
+                BLOCKS[eighths.clamp(1, 8) - 1]
+            } else if marker.is_some() {
+                '┄'
+            } else {
+                ' '
+            };
+            out.push(cell);
+        }
+        if let Some((name, value)) = marker {
+            out.push_str(&format!(" {} {:.1}", name, value));
+        }
+        out.push('\n');
+    }
+    out.push_str(&format!("{:>7} └{}\n", "", "─".repeat(columns.len())));
+    out
+}
+
+/// Axis line with start and end times under a chart `columns` wide
+pub fn time_axis(start: DateTime<Utc>, end: DateTime<Utc>, columns: usize) -> String {
+    let left = start.format("%m-%d %H:%MZ").to_string();
+    let right = end.format("%m-%d %H:%MZ").to_string();
+    let gap = columns.saturating_sub(left.len() + right.len()).max(1);
+    format!("{:>7}  {}{}{}\n", "", left, " ".repeat(gap), right)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kingston_mines() -> FloodThresholds {
+        FloodThresholds {
+            action_stage_ft: 14.0,
+            flood_stage_ft: 16.0,
+            moderate_flood_stage_ft: 20.0,
+            major_flood_stage_ft: 24.0,
+        }
+    }
+
+    #[test]
+    fn test_sparkline_spans_lowest_to_highest_block() {
+        let line = sparkline(&[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(line.chars().count(), 4);
+        assert!(line.starts_with('▁'));
+        assert!(line.ends_with('█'));
+        // Flat series renders mid-height rather than dividing by zero
+        assert_eq!(sparkline(&[5.0, 5.0]), "▄▄");
+    }
+
+    #[test]
+    fn test_resample_keeps_crest() {
+        let mut values = vec![10.0; 100];
+        values[57] = 19.3;
+        let columns = resample_max(&values, 10);
+        assert_eq!(columns.len(), 10);
+        assert!(columns.contains(&19.3));
+    }
+
+    #[test]
+    fn test_chart_marks_thresholds_within_range() {
+        let values: Vec<f64> = (0..48).map(|i| 13.0 + i as f64 * 0.1).collect();
+        let chart = render_chart(&values, Some(&kingston_mines()), 48, 10);
+        assert_eq!(chart.lines().count(), 11);
+        assert!(chart.contains("action 14.0"));
+        assert!(chart.contains("flood 16.0"));
+        // Data peaked at 17.7 ft: major stage is out of reach and not drawn
+        assert!(!chart.contains("major"));
+        assert!(chart.contains('█'));
+    }
+
+    #[test]
+    fn test_chart_without_data_or_thresholds() {
+        assert_eq!(render_chart(&[], None, 10, 5), "(no data)\n");
+        let chart = render_chart(&[3.2, 3.2, 3.2], None, 10, 4);
+        assert_eq!(chart.lines().count(), 5);
+    }
+}
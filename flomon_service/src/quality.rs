@@ -0,0 +1,222 @@
+//! Reading quality across sources.
+//!
+//! Each source grades its values its own way, and the codes are stored as
+//! received:
+//!
+//! - USGS: the NWIS qualifiers on each value, kept colon-joined in
+//!   `usgs_raw.gauge_readings.qualifier` ("P", "A", "P:e", "Ice", ...)
+//! - CWMS: the quality word in `usace.cwms_timeseries.quality_code`
+//!   (decoded by `ingest::cwms_quality`)
+//!
+//! [`QualityFlag`] puts both on one scale. The alert layer only acts on
+//! alertable readings ([`QualityFlag::is_alertable`]): an estimated value
+//! is the agency's fill-in for a gap, and an ice-affected stage reads high
+//! because of the ice, not the flow, so neither should raise or clear an
+//! alert. Provisional data is what real-time alerting runs on and stays
+//! alertable.
+//!
+//! Stage the daemon estimates itself from a measured discharge through the
+//! USGS rating (`ingest::rating`) is marked "e" as well, but is derived
+//! from an alertable discharge and is evaluated deliberately; see
+//! `Daemon::track_severity`.
+//!
+//! Entry points:
+//! - `GET /api/stations/{site}/quality?start=&end=` (per-station counts)
+
+use std::fmt;
+
+use crate::ingest::cwms_quality::{CwmsQuality, Validity};
+use crate::model::GaugeReading;
+
+/// Common quality grade, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum QualityFlag {
+    /// Reviewed and approved by the agency
+    Approved,
+    /// Real-time data not yet reviewed
+    Provisional,
+    /// Suspect but measured (USGS backwater, CWMS questionable)
+    Questionable,
+    /// Filled in or computed rather than measured
+    Estimated,
+    /// Affected by ice at the gauge
+    IceAffected,
+    /// Not a usable value (equipment malfunction, CWMS rejected or missing)
+    Rejected,
+}
+
+/// Every flag, best first
+pub const ALL_FLAGS: [QualityFlag; 6] = [
+    QualityFlag::Approved,
+    QualityFlag::Provisional,
+    QualityFlag::Questionable,
+    QualityFlag::Estimated,
+    QualityFlag::IceAffected,
+    QualityFlag::Rejected,
+];
+
+impl QualityFlag {
+    /// Worst grade among the NWIS qualifiers on a value. Codes are
+    /// separated by ':' or ','; unrecognised codes are ignored, and a value
+    /// without an approval code is provisional.
+    pub fn from_usgs(qualifier: &str) -> Self {
+        qualifier.split([':', ','])
+            .map(str::trim)
+            .filter_map(|code| match code {
+                "A" => Some(QualityFlag::Approved),
+                "P" => Some(QualityFlag::Provisional),
+                "Bkw" | "<" | ">" => Some(QualityFlag::Questionable),
+                "e" | "E" => Some(QualityFlag::Estimated),
+                "Ice" => Some(QualityFlag::IceAffected),
+                "Eqp" | "Mnt" | "Dis" | "Fld" | "Ssn" | "Pr" | "Rat" | "***" => Some(QualityFlag::Rejected),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(QualityFlag::Provisional)
+    }
+
+    /// Grade of a CWMS quality word; unscreened data counts as provisional
+    pub fn from_cwms(code: i32) -> Self {
+        let quality = CwmsQuality::from_code(code);
+        match quality.validity {
+            Validity::Missing | Validity::Rejected => QualityFlag::Rejected,
+            _ if quality.is_estimated() => QualityFlag::Estimated,
+            Validity::Questionable => QualityFlag::Questionable,
+            Validity::Okay if quality.screened => QualityFlag::Approved,
+            Validity::Okay | Validity::Unknown => QualityFlag::Provisional,
+        }
+    }
+
+    /// Grade of a stored reading
+    pub fn of(reading: &GaugeReading) -> Self {
+        Self::from_usgs(&reading.qualifier)
+    }
+
+    /// Whether alerts should act on a value of this grade
+    pub fn is_alertable(&self) -> bool {
+        !matches!(self, QualityFlag::Estimated | QualityFlag::IceAffected | QualityFlag::Rejected)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QualityFlag::Approved => "approved",
+            QualityFlag::Provisional => "provisional",
+            QualityFlag::Questionable => "questionable",
+            QualityFlag::Estimated => "estimated",
+            QualityFlag::IceAffected => "ice_affected",
+            QualityFlag::Rejected => "rejected",
+        }
+    }
+}
+
+impl fmt::Display for QualityFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Readings in `readings` the alert layer should act on
+pub fn alertable(readings: &[GaugeReading]) -> Vec<GaugeReading> {
+    readings.iter()
+        .filter(|r| QualityFlag::of(r).is_alertable())
+        .cloned()
+        .collect()
+}
+
+/// How a station's readings grade over a period
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityStats {
+    pub total: usize,
+    /// Count per flag, in [`ALL_FLAGS`] order (zeros included)
+    pub counts: Vec<(QualityFlag, usize)>,
+}
+
+impl QualityStats {
+    pub fn count(&self, flag: QualityFlag) -> usize {
+        self.counts.iter().find(|(f, _)| *f == flag).map_or(0, |(_, n)| *n)
+    }
+
+    /// Readings the alert layer would act on
+    pub fn alertable(&self) -> usize {
+        self.counts.iter().filter(|(f, _)| f.is_alertable()).map(|(_, n)| n).sum()
+    }
+
+    /// Share of readings that are alertable; None without readings
+    pub fn alertable_fraction(&self) -> Option<f64> {
+        (self.total > 0).then(|| self.alertable() as f64 / self.total as f64)
+    }
+}
+
+/// Quality counts over `readings`
+pub fn summarize(readings: &[GaugeReading]) -> QualityStats {
+    let flags: Vec<QualityFlag> = readings.iter().map(QualityFlag::of).collect();
+    QualityStats {
+        total: flags.len(),
+        counts: ALL_FLAGS.iter()
+            .map(|flag| (*flag, flags.iter().filter(|f| *f == flag).count()))
+            .collect(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stage(qualifier: &str) -> GaugeReading {
+        GaugeReading {
+            site_code: "05567500".to_string(),
+            site_name: "ILLINOIS RIVER AT PEORIA, IL".to_string(),
+            parameter_code: "00065".to_string(),
+            unit: "ft".to_string(),
+            value: 18.2,
+            datetime: chrono::DateTime::parse_from_rfc3339("2024-01-15T12:00:00.000-06:00").unwrap(),
+            qualifier: qualifier.to_string(),
+            original_unit: None,
+        }
+    }
+
+    #[test]
+    fn test_usgs_qualifiers_take_the_worst_code() {
+        assert_eq!(QualityFlag::from_usgs("A"), QualityFlag::Approved);
+        assert_eq!(QualityFlag::from_usgs("P"), QualityFlag::Provisional);
+        assert_eq!(QualityFlag::from_usgs("A:e"), QualityFlag::Estimated);
+        assert_eq!(QualityFlag::from_usgs("P,Ice"), QualityFlag::IceAffected);
+        assert_eq!(QualityFlag::from_usgs("P:Eqp"), QualityFlag::Rejected);
+        assert_eq!(QualityFlag::from_usgs("P:Bkw"), QualityFlag::Questionable);
+        assert_eq!(QualityFlag::from_usgs(""), QualityFlag::Provisional);
+        assert_eq!(QualityFlag::from_usgs("A:Zz"), QualityFlag::Approved, "unknown codes are ignored");
+
+        assert!(QualityFlag::from_usgs("P:Bkw").is_alertable());
+        assert!(!QualityFlag::from_usgs("A:e").is_alertable());
+        assert!(!QualityFlag::from_usgs("Ice").is_alertable());
+    }
+
+    #[test]
+    fn test_cwms_quality_words_map_onto_flags() {
+        assert_eq!(QualityFlag::from_cwms(0), QualityFlag::Provisional, "unscreened");
+        assert_eq!(QualityFlag::from_cwms(0b11), QualityFlag::Approved, "screened okay");
+        assert_eq!(QualityFlag::from_cwms(0b1001), QualityFlag::Questionable);
+        assert_eq!(QualityFlag::from_cwms(0b10001), QualityFlag::Rejected);
+        assert_eq!(QualityFlag::from_cwms(0b101), QualityFlag::Rejected, "missing");
+        // Screened okay, replaced by linear interpolation
+        assert_eq!(QualityFlag::from_cwms(0b11 | (1 << 11)), QualityFlag::Estimated);
+    }
+
+    #[test]
+    fn test_summary_counts_every_flag() {
+        let readings = vec![stage("A"), stage("P"), stage("P"), stage("P:e"), stage("Ice")];
+        let stats = summarize(&readings);
+        assert_eq!(stats.total, 5);
+        assert_eq!(stats.counts.len(), ALL_FLAGS.len());
+        assert_eq!(stats.count(QualityFlag::Provisional), 2);
+        assert_eq!(stats.count(QualityFlag::Rejected), 0);
+        assert_eq!(stats.alertable(), 3);
+        assert_eq!(stats.alertable_fraction(), Some(0.6));
+        assert_eq!(alertable(&readings).len(), 3);
+        assert_eq!(summarize(&[]).alertable_fraction(), None);
+    }
+}
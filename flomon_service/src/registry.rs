@@ -0,0 +1,401 @@
+//! Station registry versioning and change audit.
+//!
+//! Thresholds get revised (NWS re-surveys a gauge, a station is added or
+//! retired), and after an event the first review question is "which
+//! thresholds were in effect when that alert fired?". Every time the daemon
+//! starts with a registry that differs from the last recorded one, a new
+//! version is written to `registry_versions` with the full snapshot, and
+//! each changed field is written to `registry_changes` with who and when.
+//! Threshold overrides from the database (see `threshold_overrides`) are
+//! part of the recorded registry, credited to whoever wrote them.
+//!
+//! Alerts carry the version in effect (`FloodAlert::registry_version`), and
+//! the status API reports it alongside the computed severities.
+//!
+//! `flomon registry history [--site CODE]` prints the change log.
+//! `version_at`/`stations_at` rebuild the registry as it stood at a past
+//! instant, for `as_of` queries.
+
+use chrono::{DateTime, Utc};
+use postgres::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::model::{FloodThresholds, PoolDeviationThresholds};
+use crate::stations::Station;
+
+// ============================================================================
+// Snapshot
+// ============================================================================
+
+/// Audited fields of one station
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StationSnapshot {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub action_stage_ft: Option<f64>,
+    pub flood_stage_ft: Option<f64>,
+    pub moderate_flood_stage_ft: Option<f64>,
+    pub major_flood_stage_ft: Option<f64>,
+    pub expected_parameters: Vec<String>,
+    /// Pool gauges: target elevation and [action, flood, moderate, major]
+    /// deviations (absent from snapshots recorded before they existed)
+    #[serde(default)]
+    pub pool_target_elevation_ft: Option<f64>,
+    #[serde(default)]
+    pub pool_deviation_ft: Option<[f64; 4]>,
+}
+
+/// Registry contents keyed by site code (ordered, so serialization is stable)
+pub type RegistrySnapshot = BTreeMap<String, StationSnapshot>;
+
+/// Snapshot of the audited registry fields
+pub fn snapshot(stations: &[Station]) -> RegistrySnapshot {
+    stations.iter()
+        .map(|s| {
+            let t = s.thresholds.as_ref();
+            let pool = s.pool_deviation.as_ref();
+            (s.site_code.clone(), StationSnapshot {
+                name: s.name.clone(),
+                latitude: s.latitude,
+                longitude: s.longitude,
+                action_stage_ft: t.map(|t| t.action_stage_ft),
+                flood_stage_ft: t.map(|t| t.flood_stage_ft),
+                moderate_flood_stage_ft: t.map(|t| t.moderate_flood_stage_ft),
+                major_flood_stage_ft: t.map(|t| t.major_flood_stage_ft),
+                expected_parameters: s.expected_parameters.clone(),
+                pool_target_elevation_ft: pool.map(|p| p.target_elevation_ft),
+                pool_deviation_ft: pool.map(|p| [p.action_ft, p.flood_ft, p.moderate_ft, p.major_ft]),
+            })
+        })
+        .collect()
+}
+
+/// Stable fingerprint of a snapshot (FNV-1a over its JSON form)
+pub fn fingerprint(snapshot: &RegistrySnapshot) -> String {
+    let json = serde_json::to_string(snapshot).unwrap_or_default();
+    let hash = json.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+// ============================================================================
+// Diff
+// ============================================================================
+
+/// One changed field; `None` on the old side means added, on the new side removed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryChange {
+    pub site_code: String,
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+/// (site_code, field) -> rendered value
+fn flatten(snapshot: &RegistrySnapshot) -> BTreeMap<(String, String), String> {
+    let mut fields = BTreeMap::new();
+    for (site, s) in snapshot {
+        let mut put = |field: &str, value: String| {
+            fields.insert((site.clone(), field.to_string()), value);
+        };
+        put("name", s.name.clone());
+        put("latitude", s.latitude.to_string());
+        put("longitude", s.longitude.to_string());
+        let stages = [
+            ("action_stage_ft", s.action_stage_ft),
+            ("flood_stage_ft", s.flood_stage_ft),
+            ("moderate_flood_stage_ft", s.moderate_flood_stage_ft),
+            ("major_flood_stage_ft", s.major_flood_stage_ft),
+        ];
+        let [pool_action, pool_flood, pool_moderate, pool_major] = s.pool_deviation_ft.map_or([None; 4], |d| d.map(Some));
+        let stages = stages.into_iter().chain([
+            ("pool_target_elevation_ft", s.pool_target_elevation_ft),
+            ("pool_action_ft", pool_action),
+            ("pool_flood_ft", pool_flood),
+            ("pool_moderate_ft", pool_moderate),
+            ("pool_major_ft", pool_major),
+        ]);
+        for (field, value) in stages {
+            if let Some(v) = value {
+                put(field, v.to_string());
+            }
+        }
+        put("expected_parameters", s.expected_parameters.join(","));
+    }
+    fields
+}
+
+/// Field-level changes from `old` to `new`, ordered by site then field
+pub fn diff(old: &RegistrySnapshot, new: &RegistrySnapshot) -> Vec<RegistryChange> {
+    let old_fields = flatten(old);
+    let new_fields = flatten(new);
+    let mut keys: Vec<&(String, String)> = old_fields.keys().chain(new_fields.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let old_value = old_fields.get(key);
+            let new_value = new_fields.get(key);
+            (old_value != new_value).then(|| RegistryChange {
+                site_code: key.0.clone(),
+                field: key.1.clone(),
+                old_value: old_value.cloned(),
+                new_value: new_value.cloned(),
+            })
+        })
+        .collect()
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+/// Registry version recorded (or matched) at startup
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedVersion {
+    pub version: i32,
+    /// False when the registry matched the latest stored version
+    pub created: bool,
+    pub changes: Vec<RegistryChange>,
+}
+
+/// Who to credit for a registry change: `$FLOMON_CHANGED_BY`, else `$USER`
+pub fn changed_by() -> String {
+    std::env::var("FLOMON_CHANGED_BY")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Record `stations` as a new version if it differs from the latest stored one
+pub fn record_version(client: &mut Client, stations: &[Station], changed_by: &str) -> Result<RecordedVersion, String> {
+    let current = snapshot(stations);
+    let current_fingerprint = fingerprint(&current);
+
+    let latest = client.query_opt(
+        "SELECT version, fingerprint, snapshot::TEXT FROM registry_versions
+         ORDER BY version DESC LIMIT 1",
+        &[],
+    ).map_err(|e| format!("Failed to read registry version: {}", e))?;
+
+    let previous = match latest {
+        Some(row) => {
+            let version: i32 = row.get(0);
+            let stored_fingerprint: String = row.get(1);
+            if stored_fingerprint == current_fingerprint {
+                return Ok(RecordedVersion { version, created: false, changes: Vec::new() });
+            }
+            let json: String = row.get(2);
+            serde_json::from_str(&json)
+                .map_err(|e| format!("Stored registry snapshot v{} is unreadable: {}", version, e))?
+        }
+        None => RegistrySnapshot::new(),
+    };
+    let changes = diff(&previous, &current);
+
+    let snapshot_json = serde_json::to_string(&current)
+        .map_err(|e| format!("Failed to serialize registry: {}", e))?;
+
+    let mut tx = client.transaction()
+        .map_err(|e| format!("Failed to start registry transaction: {}", e))?;
+    let version: i32 = tx.query_one(
+        "INSERT INTO registry_versions (fingerprint, snapshot, station_count, changed_by)
+         VALUES ($1, $2::TEXT::JSONB, $3, $4)
+         RETURNING version",
+        &[&current_fingerprint, &snapshot_json, &(current.len() as i32), &changed_by],
+    ).map_err(|e| format!("Failed to record registry version: {}", e))?.get(0);
+
+    for change in &changes {
+        tx.execute(
+            "INSERT INTO registry_changes (version, site_code, field, old_value, new_value, changed_by)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            &[&version, &change.site_code, &change.field, &change.old_value, &change.new_value, &changed_by],
+        ).map_err(|e| format!("Failed to record registry change: {}", e))?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit registry version: {}", e))?;
+
+    Ok(RecordedVersion { version, created: true, changes })
+}
+
+/// Latest recorded registry version, if any
+pub fn current_version(client: &mut Client) -> Result<Option<i32>, String> {
+    client.query_one("SELECT MAX(version) FROM registry_versions", &[])
+        .map(|row| row.get(0))
+        .map_err(|e| format!("Failed to read registry version: {}", e))
+}
+
+/// Version in effect at `at` (the newest recorded at or before it) and its
+/// snapshot, if any was recorded by then
+pub fn version_at(client: &mut Client, at: DateTime<Utc>) -> Result<Option<(i32, RegistrySnapshot)>, String> {
+    let row = client.query_opt(
+        "SELECT version, snapshot::TEXT FROM registry_versions
+         WHERE created_at <= $1
+         ORDER BY version DESC LIMIT 1",
+        &[&at],
+    ).map_err(|e| format!("Failed to read registry version: {}", e))?;
+
+    row.map(|row| {
+        let version: i32 = row.get(0);
+        let json: String = row.get(1);
+        serde_json::from_str(&json)
+            .map(|snapshot| (version, snapshot))
+            .map_err(|e| format!("Stored registry snapshot v{} is unreadable: {}", version, e))
+    }).transpose()
+}
+
+/// `stations` as they stood in `snapshot`: stations absent from it are
+/// dropped, and names and thresholds are taken from it
+pub fn stations_at(stations: &[Station], snapshot: &RegistrySnapshot) -> Vec<Station> {
+    stations.iter()
+        .filter_map(|station| {
+            let then = snapshot.get(&station.site_code)?;
+            let mut station = station.clone();
+            station.name = then.name.clone();
+            station.thresholds = match (then.action_stage_ft, then.flood_stage_ft, then.moderate_flood_stage_ft, then.major_flood_stage_ft) {
+                (Some(action), Some(flood), Some(moderate), Some(major)) => Some(FloodThresholds {
+                    action_stage_ft: action,
+                    flood_stage_ft: flood,
+                    moderate_flood_stage_ft: moderate,
+                    major_flood_stage_ft: major,
+                }),
+                _ => None,
+            };
+            station.pool_deviation = match (station.pool_deviation.take(), then.pool_target_elevation_ft, then.pool_deviation_ft) {
+                (Some(pool), Some(target), Some([action, flood, moderate, major])) => Some(PoolDeviationThresholds {
+                    target_elevation_ft: target,
+                    action_ft: action,
+                    flood_ft: flood,
+                    moderate_ft: moderate,
+                    major_ft: major,
+                    ..pool
+                }),
+                _ => None,
+            };
+            Some(station)
+        })
+        .collect()
+}
+
+/// A stored change with its version and time
+#[derive(Debug, Clone)]
+pub struct ChangeRecord {
+    pub version: i32,
+    pub changed_at: DateTime<Utc>,
+    pub changed_by: String,
+    pub change: RegistryChange,
+}
+
+/// Change log, newest first, optionally for one site
+pub fn history(client: &mut Client, site_code: Option<&str>) -> Result<Vec<ChangeRecord>, String> {
+    let rows = client.query(
+        "SELECT version, changed_at, changed_by, site_code, field, old_value, new_value
+         FROM registry_changes
+         WHERE ($1::TEXT IS NULL OR site_code = $1)
+         ORDER BY version DESC, site_code, field",
+        &[&site_code],
+    ).map_err(|e| format!("Failed to read registry changes: {}", e))?;
+
+    Ok(rows.iter()
+        .map(|row| ChangeRecord {
+            version: row.get(0),
+            changed_at: row.get(1),
+            changed_by: row.get(2),
+            change: RegistryChange {
+                site_code: row.get(3),
+                field: row.get(4),
+                old_value: row.get(5),
+                new_value: row.get(6),
+            },
+        })
+        .collect())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stations::load_stations;
+
+    #[test]
+    fn test_fingerprint_is_stable_and_sensitive() {
+        let stations = load_stations();
+        let a = snapshot(&stations);
+        assert_eq!(fingerprint(&a), fingerprint(&snapshot(&stations)));
+
+        let mut b = a.clone();
+        b.get_mut("05567500").unwrap().flood_stage_ft = Some(18.5);
+        assert_ne!(fingerprint(&a), fingerprint(&b));
+    }
+
+    #[test]
+    fn test_diff_reports_threshold_revision() {
+        let old = snapshot(&load_stations());
+        let mut new = old.clone();
+        let peoria = new.get_mut("05567500").unwrap();
+        let before = peoria.flood_stage_ft.unwrap();
+        peoria.flood_stage_ft = Some(before + 0.5);
+
+        let changes = diff(&old, &new);
+        assert_eq!(changes, vec![RegistryChange {
+            site_code: "05567500".to_string(),
+            field: "flood_stage_ft".to_string(),
+            old_value: Some(before.to_string()),
+            new_value: Some((before + 0.5).to_string()),
+        }]);
+    }
+
+    #[test]
+    fn test_diff_reports_added_and_removed_stations() {
+        let full = snapshot(&load_stations());
+        let mut fewer = full.clone();
+        let removed = fewer.remove("05567500").unwrap();
+
+        let changes = diff(&full, &fewer);
+        assert!(changes.iter().all(|c| c.site_code == "05567500" && c.new_value.is_none()));
+        assert!(changes.iter().any(|c| c.field == "name" && c.old_value.as_deref() == Some(removed.name.as_str())));
+
+        let added = diff(&RegistrySnapshot::new(), &full);
+        assert!(added.iter().all(|c| c.old_value.is_none()));
+        assert!(diff(&full, &full).is_empty());
+    }
+
+    #[test]
+    fn test_pool_definition_is_audited() {
+        let current = snapshot(&load_stations());
+        // A snapshot stored before pool definitions existed has no pool keys
+        let mut json = serde_json::to_value(&current).unwrap();
+        for station in json.as_object_mut().unwrap().values_mut() {
+            let fields = station.as_object_mut().unwrap();
+            fields.remove("pool_target_elevation_ft");
+            fields.remove("pool_deviation_ft");
+        }
+        let stored: RegistrySnapshot = serde_json::from_value(json).unwrap();
+
+        let changes = diff(&stored, &current);
+        assert!(changes.iter().all(|c| c.site_code == "05567500" && c.old_value.is_none()));
+        assert!(changes.iter().any(|c| c.field == "pool_target_elevation_ft" && c.new_value.as_deref() == Some("447")));
+        assert!(changes.iter().any(|c| c.field == "pool_flood_ft"));
+    }
+
+    #[test]
+    fn test_stations_at_uses_snapshot_thresholds() {
+        let stations = load_stations();
+        let mut then = snapshot(&stations);
+        then.get_mut("05567500").unwrap().flood_stage_ft = Some(16.0);
+        let removed = then.keys().find(|code| *code != "05567500").unwrap().clone();
+        then.remove(&removed);
+
+        let past = stations_at(&stations, &then);
+        assert_eq!(past.len(), stations.len() - 1);
+        assert!(past.iter().all(|s| s.site_code != removed));
+        let peoria = past.iter().find(|s| s.site_code == "05567500").unwrap();
+        assert_eq!(peoria.thresholds.as_ref().unwrap().flood_stage_ft, 16.0);
+        assert_eq!(snapshot(&past).get("05567500"), then.get("05567500"));
+    }
+}
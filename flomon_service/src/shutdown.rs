@@ -0,0 +1,62 @@
+//! Graceful shutdown on SIGINT/SIGTERM.
+//!
+//! `install()` replaces the default handlers with one that only sets a
+//! flag; the daemon loop checks `requested()` between polls and sleeps
+//! through `sleep()` so a stop request is noticed within a second. It then
+//! drains the notification queue (see `alert::queue`) before exiting.
+//!
+//! A second signal while the drain is running exits immediately.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Exit status for a forced exit (128 + SIGINT)
+const FORCED_EXIT_STATUS: i32 = 130;
+
+#[cfg(unix)]
+extern "C" fn handle_signal(_signal: libc::c_int) {
+    if REQUESTED.swap(true, Ordering::SeqCst) {
+        // Only async-signal-safe calls here
+        unsafe { libc::_exit(FORCED_EXIT_STATUS) };
+    }
+}
+
+/// Install the SIGINT/SIGTERM handlers (no-op off Unix, where Ctrl+C
+/// still terminates immediately)
+pub fn install() {
+    #[cfg(unix)]
+    unsafe {
+        let handler = handle_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+/// Whether a shutdown has been requested
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Request shutdown from inside the process
+pub fn request() {
+    REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Sleep for `duration`, waking early on a shutdown request.
+///
+/// Returns false if interrupted.
+pub fn sleep(duration: Duration) -> bool {
+    let end = Instant::now() + duration;
+    loop {
+        if requested() {
+            return false;
+        }
+        let now = Instant::now();
+        if now >= end {
+            return true;
+        }
+        std::thread::sleep((end - now).min(Duration::from_secs(1)));
+    }
+}
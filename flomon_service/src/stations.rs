@@ -1,23 +1,44 @@
-///site code registry, the "valid format" test lives here
-/// a map of site codes to metadata (name, location, which parameters are available, NWS flood thresholds). 
-/// The site code format test moves here because it's really testing the registry, not the parser. This module will grow as we add more stations and associate threshold data with each one.
-/// Station registry for the Peoria basin flood monitoring service.
-///
-/// Defines the canonical list of USGS gauge stations monitored by this
-/// service, along with their metadata and NWS flood stage thresholds.
-/// This is the single source of truth for site codes — all other modules
-/// should reference stations from here rather than hardcoding site codes.
-///
-/// ## Configuration-Based Registry
-/// 
-/// Station metadata is now loaded from `usgs_stations.toml` configuration file,
-/// allowing threshold updates and station additions without recompilation.
-/// Use `load_stations()` to get the runtime station list, or
-/// `load_stations_map()` for O(1) lookups by site code.
+//!site code registry, the "valid format" test lives here
+//! a map of site codes to metadata (name, location, which parameters are available, NWS flood thresholds). 
+//! The site code format test moves here because it's really testing the registry, not the parser. This module will grow as we add more stations and associate threshold data with each one.
+//! Station registry for the Peoria basin flood monitoring service.
+//!
+//! Defines the canonical list of USGS gauge stations monitored by this
+//! service, along with their metadata and NWS flood stage thresholds.
+//! This is the single source of truth for site codes — all other modules
+//! should reference stations from here rather than hardcoding site codes.
+//!
+//! ## Configuration-Based Registry
+//! 
+//! Station metadata is now loaded from `usgs_stations.toml` configuration file,
+//! allowing threshold updates and station additions without recompilation.
+//! Use `load_stations()` to get the runtime station list, or
+//! `load_stations_map()` for O(1) lookups by site code.
+//!
+//! The usgs_stations.toml shipped with the service is compiled in as the
+//! built-in registry. `load_from_file()` merges a registry file over it:
+//! entries replace the built-in station with the same site code and new
+//! site codes are added, so a deployment's file only needs the gauges it
+//! adds or changes. Site-code format and threshold ordering are checked at
+//! load time rather than when the first reading arrives.
+//!
+//! ## Runtime reload
+//! The daemon holds a `RegistryWatcher` and checks it every poll cycle;
+//! when a watched file's modification time changes the registry is reloaded
+//! and, if it validates, replaces the running one. An invalid edit is
+//! logged and the previous registry stays in service.
 
 use crate::config;
-use crate::model::FloodThresholds;
-use std::collections::HashMap;
+use crate::config::ServiceConfig;
+use crate::datum::{self, SiteDatum, VerticalDatum};
+use crate::model::{FloodThresholds, PoolDeviationThresholds};
+use crate::zones;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Registry file read by `load_stations()`, relative to the working directory
+pub const DEFAULT_REGISTRY_PATH: &str = "usgs_stations.toml";
 
 // ---------------------------------------------------------------------------
 // USGS parameter codes (re-exported here for use in URL construction)
@@ -59,19 +80,140 @@ pub struct Station {
     pub distance_direction: String,
     /// Average travel time for flood wave to reach Peoria, in hours.
     pub travel_time_to_peoria_hours: f64,
+    /// Illinois Waterway river mile (miles above the Mississippi at
+    /// Grafton); main-stem gauges only. Places the gauge on the
+    /// interpolation profile (see `analysis::profile`).
+    pub river_mile: Option<f64>,
+    /// Elevation of zero stage, in feet (stage + datum = water surface elevation).
+    pub gauge_datum_ft: Option<f64>,
+    /// Vertical datum of `gauge_datum_ft` ("NGVD29", "NAVD88").
+    pub gauge_datum: Option<String>,
+    /// Feet added to an elevation in each datum to give NGVD29 at this
+    /// site (see `datum`).
+    pub to_ngvd29_ft: Vec<(VerticalDatum, f64)>,
+    /// NWS forecast zone (UGC, "ILZ031"), if pinned in the registry.
+    pub nws_zone: Option<String>,
+    /// 5-digit county FIPS, if pinned in the registry.
+    pub county_fips: Option<String>,
+    /// Regulated pool gauges: alert levels relative to the target pool.
+    /// When set, alerting uses this instead of `thresholds`.
+    pub pool_deviation: Option<PoolDeviationThresholds>,
+    /// CRITICAL priority: in event mode, fetched in the reserved lane
+    /// instead of waiting behind the shared concurrency bound.
+    pub critical: bool,
 }
 
 /// Loads all monitored stations from usgs_stations.toml configuration.
 ///
 /// This is the primary way to access the station registry. Use this
 /// instead of a static global to allow runtime configuration updates.
+/// Without a usgs_stations.toml in the working directory the built-in
+/// registry is used.
 ///
 /// # Panics
-/// Panics if usgs_stations.toml is missing or malformed.
+/// Panics if usgs_stations.toml is malformed or fails validation (see
+/// `load_from_file`).
 pub fn load_stations() -> Vec<Station> {
-    config::load_config()
+    let path = Path::new(DEFAULT_REGISTRY_PATH);
+    if !path.exists() {
+        return builtin_stations();
+    }
+    load_from_file(path).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// The registry compiled into the binary.
+///
+/// # Panics
+/// Panics if a built-in `pool_deviation` entry cannot be resolved; the
+/// document itself is checked by the tests.
+pub fn builtin_stations() -> Vec<Station> {
+    config::parse_registry(config::BUILTIN_REGISTRY)
+        .expect("built-in usgs_stations.toml parses")
         .into_iter()
-        .map(|cfg| Station {
+        .map(Station::from)
+        .collect()
+}
+
+/// Reads a usgs_stations.toml-shaped file, validates its entries and
+/// merges them over the built-in registry (see `merge`).
+pub fn load_from_file(path: &Path) -> Result<Vec<Station>, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let overrides = config::parse_registry(&contents)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?
+        .into_iter()
+        .map(Station::from_config)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    validate(&overrides).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(merge(builtin_stations(), overrides))
+}
+
+/// `defaults` with each of `overrides` replacing the station of the same
+/// site code in place, and the rest appended in their file order
+pub fn merge(defaults: Vec<Station>, overrides: Vec<Station>) -> Vec<Station> {
+    let mut merged = defaults;
+    for station in overrides {
+        match merged.iter_mut().find(|s| s.site_code == station.site_code) {
+            Some(existing) => *existing = station,
+            None => merged.push(station),
+        }
+    }
+    merged
+}
+
+/// Checks site codes are 8-15 digit USGS codes and unique, and that flood
+/// thresholds ascend action <= flood <= moderate <= major
+pub fn validate(stations: &[Station]) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for station in stations {
+        let code = &station.site_code;
+        if !(8..=15).contains(&code.len()) || !code.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("site_code {:?} is not an 8-15 digit USGS site number", code));
+        }
+        if !seen.insert(code.as_str()) {
+            return Err(format!("duplicate site_code {}", code));
+        }
+        if let Some(t) = &station.thresholds {
+            let ordered = t.action_stage_ft <= t.flood_stage_ft
+                && t.flood_stage_ft <= t.moderate_flood_stage_ft
+                && t.moderate_flood_stage_ft <= t.major_flood_stage_ft;
+            if !ordered {
+                return Err(format!("{}: thresholds must ascend action <= flood <= moderate <= major", code));
+            }
+        }
+        if station.river_mile.is_some_and(|mile| !mile.is_finite() || mile < 0.0) {
+            return Err(format!("{}: river_mile must be zero or more", code));
+        }
+    }
+    Ok(())
+}
+
+impl From<config::StationConfig> for Station {
+    /// # Panics
+    /// Panics if a `pool_deviation` entry cannot be resolved (see
+    /// `Station::from_config`).
+    fn from(cfg: config::StationConfig) -> Self {
+        Station::from_config(cfg).unwrap_or_else(|e| panic!("usgs_stations.toml: {}", e))
+    }
+}
+
+impl Station {
+    /// Build a station from its registry entry, resolving `pool_deviation`
+    /// against zones.toml when the entry has no target of its own
+    pub fn from_config(cfg: config::StationConfig) -> Result<Self, String> {
+        let pool_deviation = cfg.pool_deviation.as_ref().map(|pool| {
+            resolve_pool_deviation(&cfg, pool, || {
+                let zones = zones::load_zones_default().ok()?;
+                zones::pool_target(&zones, &pool.pool_location)
+            })
+        }).transpose()?;
+        if let Some(datum) = &cfg.gauge_datum {
+            VerticalDatum::parse(datum).map_err(|e| format!("{}: gauge_datum: {}", cfg.site_code, e))?;
+        }
+        let to_ngvd29_ft = datum::parse_offsets(&cfg.to_ngvd29_ft)
+            .map_err(|e| format!("{}: {}", cfg.site_code, e))?;
+        Ok(Station {
             site_code: cfg.site_code,
             name: cfg.name,
             description: cfg.description,
@@ -81,11 +223,143 @@ pub fn load_stations() -> Vec<Station> {
             expected_parameters: cfg.expected_parameters,
             distance_from_peoria_miles: cfg.distance_from_peoria_miles,
             distance_direction: cfg.distance_direction,
+            river_mile: cfg.river_mile,
             travel_time_to_peoria_hours: cfg.travel_time_to_peoria_hours,
+            gauge_datum_ft: cfg.gauge_datum_ft,
+            gauge_datum: cfg.gauge_datum,
+            to_ngvd29_ft,
+            nws_zone: cfg.nws_zone,
+            county_fips: cfg.county_fips,
+            pool_deviation,
+            critical: cfg.critical,
         })
+    }
+
+    /// Gauge zero and datum offsets for converting this station's stage to
+    /// elevations; None without a `gauge_datum`
+    pub fn site_datum(&self) -> Option<SiteDatum> {
+        Some(SiteDatum {
+            datum: VerticalDatum::parse(self.gauge_datum.as_deref()?).ok()?,
+            gauge_zero_ft: self.gauge_datum_ft,
+            to_ngvd29_ft: self.to_ngvd29_ft.clone(),
+        })
+    }
+}
+
+/// Where a running registry came from, and how to read it again
+enum RegistrySource {
+    /// A usgs_stations.toml-shaped file (see `load_from_file`)
+    StationFile(PathBuf),
+    /// flomon.toml with its includes
+    ServiceConfig(PathBuf),
+}
+
+/// Notices edits to the registry's files so the daemon can reload it.
+///
+/// Watches modification times only; an edit that keeps the mtime (or a
+/// file replaced by one with an older mtime) is not seen until the next
+/// change.
+pub struct RegistryWatcher {
+    source: RegistrySource,
+    files: Vec<PathBuf>,
+    modified: Vec<Option<SystemTime>>,
+}
+
+impl RegistryWatcher {
+    /// Watch a standalone usgs_stations.toml-shaped file
+    pub fn station_file(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self::new(RegistrySource::StationFile(path.clone()), vec![path])
+    }
+
+    /// Watch flomon.toml and every file it included
+    pub fn service_config(config: &ServiceConfig) -> Self {
+        Self::new(
+            RegistrySource::ServiceConfig(config.source_path.clone()),
+            config.source_files.clone(),
+        )
+    }
+
+    fn new(source: RegistrySource, files: Vec<PathBuf>) -> Self {
+        let modified = modification_times(&files);
+        Self { source, files, modified }
+    }
+
+    /// `None` while no watched file has changed since the last call;
+    /// otherwise the freshly loaded and validated registry, or why it
+    /// could not be used
+    pub fn poll(&mut self) -> Option<Result<Vec<Station>, String>> {
+        let modified = modification_times(&self.files);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        Some(self.load())
+    }
+
+    fn load(&self) -> Result<Vec<Station>, String> {
+        let stations = match &self.source {
+            RegistrySource::StationFile(path) => load_from_file(path)?,
+            RegistrySource::ServiceConfig(path) => {
+                let config = config::service::load(path).map_err(|e| e.to_string())?;
+                config.station.into_iter()
+                    .map(Station::from_config)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("{}: {}", path.display(), e))?
+            }
+        };
+        validate(&stations)?;
+        if stations.is_empty() {
+            return Err("registry has no stations".to_string());
+        }
+        Ok(stations)
+    }
+}
+
+fn modification_times(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files.iter()
+        .map(|f| std::fs::metadata(f).and_then(|m| m.modified()).ok())
         .collect()
 }
 
+/// Pool deviation levels of a station, with the target taken from the
+/// config or else from `zone_target` (zones.toml)
+fn resolve_pool_deviation(
+    cfg: &config::StationConfig,
+    pool: &config::PoolDeviationConfig,
+    zone_target: impl FnOnce() -> Option<f64>,
+) -> Result<PoolDeviationThresholds, String> {
+    let site = &cfg.site_code;
+    let gauge_zero_ft = cfg.gauge_datum_ft
+        .ok_or_else(|| format!("{}: pool_deviation needs gauge_datum_ft", site))?;
+    let site_datum = SiteDatum {
+        datum: VerticalDatum::parse(cfg.gauge_datum.as_deref().unwrap_or_default())
+            .map_err(|e| format!("{}: pool_deviation needs a gauge_datum: {}", site, e))?,
+        gauge_zero_ft: Some(gauge_zero_ft),
+        to_ngvd29_ft: datum::parse_offsets(&cfg.to_ngvd29_ft).map_err(|e| format!("{}: {}", site, e))?,
+    };
+    // Pool targets are NGVD29, so the deviation is taken on NGVD29
+    let gauge_datum_ft = site_datum.convert(gauge_zero_ft, site_datum.datum, datum::COMMON)
+        .ok_or_else(|| format!(
+            "{}: pool_deviation needs gauge_datum = \"NGVD29\" or a to_ngvd29_ft offset for {} (pool targets are NGVD29)",
+            site, site_datum.datum,
+        ))?;
+    let target_elevation_ft = pool.target_elevation_ft.or_else(zone_target)
+        .ok_or_else(|| format!("{}: no target pool elevation for {}", site, pool.pool_location))?;
+    if !(pool.action_ft < pool.flood_ft && pool.flood_ft < pool.moderate_ft && pool.moderate_ft < pool.major_ft) {
+        return Err(format!("{}: pool_deviation levels must ascend action < flood < moderate < major", site));
+    }
+    Ok(PoolDeviationThresholds {
+        pool_location: pool.pool_location.clone(),
+        target_elevation_ft,
+        gauge_datum_ft,
+        action_ft: pool.action_ft,
+        flood_ft: pool.flood_ft,
+        moderate_ft: pool.moderate_ft,
+        major_ft: pool.major_ft,
+    })
+}
+
 /// Loads stations into a HashMap keyed by site code for O(1) lookups.
 ///
 /// Useful when processing large volumes of gauge readings where
@@ -255,6 +529,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_peoria_pool_alerts_on_deviation_from_zone_target() {
+        let peoria = find_station("05567500").expect("Peoria should be in registry");
+        let pool = peoria.pool_deviation.expect("Peoria pool should use pool deviation");
+        assert_eq!(pool.pool_location, "Peoria-Pool");
+        // From zones.toml pool_target_ft_ngvd29
+        assert_eq!(pool.target_elevation_ft, 447.0);
+        assert!(find_station("05568500").unwrap().pool_deviation.is_none());
+    }
+
+    #[test]
+    fn test_pool_deviation_requires_ngvd29_datum_and_target() {
+        let mut cfg = config::load_config().into_iter()
+            .find(|s| s.site_code == "05567500")
+            .unwrap();
+        let pool = cfg.pool_deviation.clone().unwrap();
+        assert!(resolve_pool_deviation(&cfg, &pool, || None).is_err());
+        assert_eq!(resolve_pool_deviation(&cfg, &pool, || Some(446.5)).unwrap().target_elevation_ft, 446.5);
+
+        cfg.gauge_datum = Some("NAVD88".to_string());
+        assert!(resolve_pool_deviation(&cfg, &pool, || Some(447.0)).unwrap_err().contains("NGVD29"));
+
+        // A NAVD88 gauge zero with its offset is moved onto NGVD29
+        cfg.gauge_datum_ft = Some(428.7);
+        cfg.to_ngvd29_ft.insert("NAVD88".to_string(), 0.3);
+        let converted = resolve_pool_deviation(&cfg, &pool, || Some(447.0)).unwrap();
+        assert!((converted.gauge_datum_ft - 429.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_parameter_codes_are_valid_and_distinct() {
         assert_eq!(PARAM_DISCHARGE.len(), 5);
@@ -298,6 +601,79 @@ mod tests {
         assert!(station_has_parameter("05568500", PARAM_STAGE));
         assert!(!station_has_parameter("00000000", PARAM_DISCHARGE)); // non-existent station
     }
+
+    fn write_registry(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("flomon_stations_{}_{}.toml", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    const NEW_GAUGE: &str = r#"
+[[station]]
+site_code = "05563800"
+name = "Illinois River at Pekin, IL"
+description = "Tributary"
+latitude = 40.49
+longitude = -90.34
+distance_from_peoria_miles = 40.0
+distance_direction = "tributary"
+travel_time_to_peoria_hours = 24.0
+expected_parameters = ["00060"]
+"#;
+
+    #[test]
+    fn test_builtin_registry_matches_shipped_file() {
+        let builtin = builtin_stations();
+        assert!(validate(&builtin).is_ok());
+        let codes: Vec<_> = builtin.iter().map(|s| s.site_code.clone()).collect();
+        let shipped: Vec<_> = config::load_config().into_iter().map(|s| s.site_code).collect();
+        assert_eq!(codes, shipped);
+    }
+
+    #[test]
+    fn test_load_from_file_merges_over_builtin() {
+        let kingston = NEW_GAUGE
+            .replace("05563800", "05568500")
+            .replace("Illinois River at Pekin, IL", "Kingston Mines (renamed)");
+        let path = write_registry("merge", &format!("{}{}", kingston, NEW_GAUGE));
+        let stations = load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let builtin = builtin_stations();
+        assert_eq!(stations.len(), builtin.len() + 1);
+        let position = builtin.iter().position(|s| s.site_code == "05568500").unwrap();
+        assert_eq!(stations[position].name, "Kingston Mines (renamed)");
+        assert_eq!(stations.last().unwrap().site_code, "05563800");
+    }
+
+    #[test]
+    fn test_load_from_file_rejects_bad_codes_and_thresholds() {
+        let path = write_registry("bad_code", &NEW_GAUGE.replace("05563800", "5570000"));
+        let err = load_from_file(&path).unwrap_err();
+        std::fs::remove_file(&path).ok();
+        assert!(err.contains("8-15 digit"), "{}", err);
+
+        let unordered = format!("{}\n[station.thresholds]\naction_stage_ft = 20.0\nflood_stage_ft = 16.0\n\
+            moderate_flood_stage_ft = 22.0\nmajor_flood_stage_ft = 24.0\ndescription = \"x\"\n", NEW_GAUGE);
+        let path = write_registry("bad_thresholds", &unordered);
+        let err = load_from_file(&path).unwrap_err();
+        std::fs::remove_file(&path).ok();
+        assert!(err.contains("thresholds must ascend"), "{}", err);
+    }
+
+    #[test]
+    fn test_watcher_reloads_only_after_a_change() {
+        let path = write_registry("watch", NEW_GAUGE);
+        let mut watcher = RegistryWatcher::station_file(&path);
+        assert!(watcher.poll().is_none());
+
+        std::fs::write(&path, NEW_GAUGE.replace("05563800", "0557")).unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(5)).unwrap();
+        assert!(watcher.poll().unwrap().is_err());
+        assert!(watcher.poll().is_none(), "an unchanged bad file is not retried");
+        std::fs::remove_file(&path).ok();
+    }
 }
 
 // ---------------------------------------------------------------------------
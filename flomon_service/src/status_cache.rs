@@ -0,0 +1,340 @@
+//! Status snapshot file served without database queries.
+//!
+//! On the Pi deployment every status page load ran the basin status,
+//! alert and widget queries against PostgreSQL on the SD card. With a
+//! `[status_cache]` section the daemon instead writes one compact snapshot
+//! of current conditions each poll cycle, after warehousing:
+//!
+//! - `basin_status`: what `GET /status` returns
+//! - `alerts`: what `GET /api/alerts` returns (live view)
+//! - `stations`: the `GET /api/embed/summary` document for each station
+//!
+//! The endpoint reads the file and answers those routes, and the public
+//! `GET /api/snapshot` (the whole document), from its copy in memory. A snapshot older than `max_age_minutes`, or a missing or
+//! damaged file, falls back to the database queries, except for
+//! `/api/snapshot`, which answers 503.
+//!
+//! The default path is on tmpfs (`/dev/shm`), so refreshing it each cycle
+//! never reaches the card. The file is written to a temporary name and
+//! renamed over the old one; readers notice the new file by its
+//! modification time and size and read it again. The snapshot is a few
+//! kilobytes, so rereading it once per cycle costs nothing.
+//!
+//! ```toml
+//! [status_cache]
+//! path = "/dev/shm/flomon_status.snap"
+//! max_age_minutes = 45
+//! ```
+//!
+//! File layout: `MAGIC`, the JSON length as a little-endian u64, then the
+//! JSON document.
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::Client;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::alert::scheme::SeverityScheme;
+use crate::archive;
+use crate::cams;
+use crate::cli_output;
+use crate::conditions;
+use crate::logging;
+use crate::registry;
+use crate::stations::Station;
+
+/// First bytes of every snapshot file
+pub const MAGIC: &[u8; 8] = b"FLOSNAP1";
+/// Magic plus the JSON length
+const HEADER_LEN: usize = 16;
+
+pub const DEFAULT_PATH: &str = "/dev/shm/flomon_status.snap";
+pub const DEFAULT_MAX_AGE_MINUTES: i64 = 45;
+
+/// How far back alerts look for a station's latest stage (as `/api/alerts`)
+const ALERT_LOOKBACK_DAYS: i64 = 7;
+
+fn default_path() -> PathBuf {
+    PathBuf::from(DEFAULT_PATH)
+}
+
+fn default_max_age_minutes() -> i64 {
+    DEFAULT_MAX_AGE_MINUTES
+}
+
+/// `[status_cache]` section of flomon.toml
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StatusCacheSettings {
+    /// Snapshot file; keep it on tmpfs
+    #[serde(default = "default_path")]
+    pub path: PathBuf,
+    /// Older snapshots are ignored and the database is queried instead
+    #[serde(default = "default_max_age_minutes")]
+    pub max_age_minutes: i64,
+}
+
+impl StatusCacheSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.path.as_os_str().is_empty() {
+            return Err("status_cache.path must not be empty".to_string());
+        }
+        if self.max_age_minutes <= 0 {
+            return Err("status_cache.max_age_minutes must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Everything the status page and public snapshot show
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatusSnapshot {
+    pub generated_at: DateTime<Utc>,
+    pub basin_status: serde_json::Value,
+    pub alerts: serde_json::Value,
+    /// Widget summary per site code
+    pub stations: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Query current conditions for a snapshot of `stations`
+pub fn build(
+    client: &mut Client,
+    stations: &[Station],
+    scheme: &SeverityScheme,
+    now: DateTime<Utc>,
+) -> Result<StatusSnapshot, String> {
+    let basin_status = conditions::fetch_basin_status(client)?;
+
+    let version = registry::current_version(client)?;
+    let stages = archive::latest_known(client, None, true, now, Duration::days(ALERT_LOOKBACK_DAYS))?;
+    let mut alerts = cli_output::active_alerts(stations, &stages, version, now);
+    for alert in &mut alerts {
+        alert.snapshots = cams::recent_snapshot_links(client, &alert.site_code, cams::recent_since(now))
+            .unwrap_or_default();
+    }
+
+    let mut summaries = serde_json::Map::new();
+    for station in stations {
+        let summary = conditions::fetch_embed_summary(client, &station.site_code, scheme)?;
+        summaries.insert(station.site_code.clone(), to_value(&summary)?);
+    }
+
+    Ok(StatusSnapshot {
+        generated_at: now,
+        basin_status: to_value(&basin_status)?,
+        alerts: to_value(&cli_output::envelope("alerts", alerts, now))?,
+        stations: summaries,
+    })
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<serde_json::Value, String> {
+    serde_json::to_value(value).map_err(|e| format!("Failed to serialize status snapshot: {}", e))
+}
+
+/// Replace the snapshot at `path`; returns the bytes written
+pub fn write(path: &Path, snapshot: &StatusSnapshot) -> Result<usize, String> {
+    let json = serde_json::to_vec(snapshot)
+        .map_err(|e| format!("Failed to serialize status snapshot: {}", e))?;
+    let mut bytes = Vec::with_capacity(HEADER_LEN + json.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&(json.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&json);
+
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    // Readers must never map a half-written file
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, &bytes).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))?;
+    Ok(bytes.len())
+}
+
+// ============================================================================
+// Reading
+// ============================================================================
+
+/// Identity of a snapshot file version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Version {
+    modified: SystemTime,
+    len: u64,
+}
+
+/// A read, validated snapshot
+struct Loaded {
+    version: Version,
+    bytes: Vec<u8>,
+    generated_at: DateTime<Utc>,
+    document: serde_json::Value,
+}
+
+impl Loaded {
+    /// The JSON document after the header
+    fn json(&self) -> &[u8] {
+        &self.bytes[HEADER_LEN..]
+    }
+}
+
+/// Endpoint side of the cache: reads the current snapshot file and rereads
+/// it when the daemon replaces it
+pub struct SnapshotReader {
+    settings: StatusCacheSettings,
+    loaded: Option<Loaded>,
+}
+
+impl SnapshotReader {
+    pub fn new(settings: StatusCacheSettings) -> Self {
+        Self { settings, loaded: None }
+    }
+
+    /// The snapshot JSON, if a fresh one exists
+    pub fn raw(&mut self, now: DateTime<Utc>) -> Option<&[u8]> {
+        self.fresh(now).map(Loaded::json)
+    }
+
+    /// The `/status` document from a fresh snapshot
+    pub fn basin_status(&mut self, now: DateTime<Utc>) -> Option<serde_json::Value> {
+        self.fresh(now).map(|l| l.document["basin_status"].clone())
+    }
+
+    /// The live `/api/alerts` document from a fresh snapshot
+    pub fn alerts(&mut self, now: DateTime<Utc>) -> Option<serde_json::Value> {
+        self.fresh(now).map(|l| l.document["alerts"].clone())
+    }
+
+    /// The widget summary for `site_code` from a fresh snapshot
+    pub fn station(&mut self, site_code: &str, now: DateTime<Utc>) -> Option<serde_json::Value> {
+        self.fresh(now).and_then(|l| l.document["stations"].get(site_code).cloned())
+    }
+
+    /// Current snapshot when it is no older than `max_age_minutes`
+    fn fresh(&mut self, now: DateTime<Utc>) -> Option<&Loaded> {
+        // Warn once when a snapshot that was being served goes away
+        if let Err(e) = self.refresh()
+            && self.loaded.take().is_some()
+        {
+            logging::warn(logging::DataSource::System, None,
+                &format!("Status snapshot {} unusable: {}", self.settings.path.display(), e));
+        }
+        let max_age = Duration::minutes(self.settings.max_age_minutes);
+        self.loaded.as_ref().filter(|l| now - l.generated_at <= max_age)
+    }
+
+    /// Reread the file if it changed since it was last read
+    fn refresh(&mut self) -> Result<(), String> {
+        let meta = fs::metadata(&self.settings.path).map_err(|e| e.to_string())?;
+        let version = Version {
+            modified: meta.modified().map_err(|e| e.to_string())?,
+            len: meta.len(),
+        };
+        if self.loaded.as_ref().is_some_and(|l| l.version == version) {
+            return Ok(());
+        }
+        let bytes = fs::read(&self.settings.path).map_err(|e| e.to_string())?;
+        self.loaded = Some(load(bytes, version)?);
+        Ok(())
+    }
+}
+
+fn load(bytes: Vec<u8>, version: Version) -> Result<Loaded, String> {
+    let len = bytes.len();
+    if len < HEADER_LEN {
+        return Err("file shorter than its header".to_string());
+    }
+    if &bytes[..MAGIC.len()] != MAGIC {
+        return Err("not a status snapshot".to_string());
+    }
+    let mut declared = [0u8; 8];
+    declared.copy_from_slice(&bytes[MAGIC.len()..HEADER_LEN]);
+    if u64::from_le_bytes(declared) != (len - HEADER_LEN) as u64 {
+        return Err("length does not match header".to_string());
+    }
+    let document: serde_json::Value = serde_json::from_slice(&bytes[HEADER_LEN..])
+        .map_err(|e| format!("invalid JSON: {}", e))?;
+    let generated_at = document["generated_at"].as_str()
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|t| t.with_timezone(&Utc))
+        .ok_or("missing generated_at")?;
+    Ok(Loaded { version, bytes, generated_at, document })
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn settings(name: &str) -> StatusCacheSettings {
+        let dir = std::env::temp_dir().join(format!("flomon_status_cache_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        StatusCacheSettings { path: dir.join("status.snap"), max_age_minutes: 45 }
+    }
+
+    fn snapshot(at: DateTime<Utc>, stage: f64) -> StatusSnapshot {
+        let mut stations = serde_json::Map::new();
+        stations.insert("05567500".to_string(), serde_json::json!({"site_code": "05567500", "stage_ft": stage}));
+        StatusSnapshot {
+            generated_at: at,
+            basin_status: serde_json::json!({"overall_status": "NORMAL"}),
+            alerts: serde_json::json!({"schema": "flomon.alerts", "data": []}),
+            stations,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_and_staleness() {
+        let settings = settings("round_trip");
+        let at = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        write(&settings.path, &snapshot(at, 14.2)).unwrap();
+
+        let mut reader = SnapshotReader::new(settings.clone());
+        let now = at + Duration::minutes(10);
+        assert_eq!(reader.basin_status(now).unwrap()["overall_status"], "NORMAL");
+        assert_eq!(reader.station("05567500", now).unwrap()["stage_ft"], 14.2);
+        assert!(reader.station("05568500", now).is_none());
+        let raw: serde_json::Value = serde_json::from_slice(reader.raw(now).unwrap()).unwrap();
+        assert_eq!(raw["alerts"]["schema"], "flomon.alerts");
+
+        assert!(reader.basin_status(at + Duration::minutes(46)).is_none());
+        let _ = fs::remove_dir_all(settings.path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_replaced_snapshot_is_reread() {
+        let settings = settings("reread");
+        let at = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        write(&settings.path, &snapshot(at, 14.2)).unwrap();
+        let mut reader = SnapshotReader::new(settings.clone());
+        assert_eq!(reader.station("05567500", at).unwrap()["stage_ft"], 14.2);
+
+        write(&settings.path, &snapshot(at + Duration::minutes(15), 15.75)).unwrap();
+        let now = at + Duration::minutes(15);
+        assert_eq!(reader.station("05567500", now).unwrap()["stage_ft"], 15.75);
+        let _ = fs::remove_dir_all(settings.path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_damaged_or_missing_file_is_ignored() {
+        let settings = settings("damaged");
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let mut reader = SnapshotReader::new(settings.clone());
+        assert!(reader.raw(now).is_none());
+
+        write(&settings.path, &snapshot(now, 14.2)).unwrap();
+        let mut bytes = fs::read(&settings.path).unwrap();
+        bytes.truncate(bytes.len() - 5);
+        fs::write(&settings.path, &bytes).unwrap();
+        assert!(reader.raw(now).is_none());
+
+        fs::write(&settings.path, b"not a snapshot at all").unwrap();
+        assert!(reader.raw(now).is_none());
+        let _ = fs::remove_dir_all(settings.path.parent().unwrap());
+    }
+}
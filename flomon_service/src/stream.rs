@@ -0,0 +1,402 @@
+//! Live stream of new readings and severity transitions (GET /api/stream).
+//!
+//! The daemon and the API server are separate processes that share only
+//! the database, so the daemon announces what it writes with PostgreSQL
+//! `NOTIFY` on `CHANNEL` (`publish`): the latest reading of each parameter
+//! for every station whose poll stored new rows, and every severity
+//! transition. The server `LISTEN`s on a connection of its own
+//! (`spawn_listener`) and fans each notification out to the connected
+//! clients through a `Hub`.
+//!
+//! Clients receive Server-Sent Events, one per notification, which the
+//! dashboard reads with a plain `EventSource`:
+//!
+//! ```text
+//! event: reading
+//! data: {"event":"reading","site_code":"05568500","parameter_code":"00065",...}
+//!
+//! event: transition
+//! data: {"event":"transition","site_code":"05568500","from":"action","to":"flood",...}
+//! ```
+//!
+//! `?site=05568500,05567500` limits a client to those stations. A comment
+//! line is sent every `HEARTBEAT_SECS` so proxies keep the connection open
+//! and a closed one is noticed. A client that falls `QUEUE_DEPTH` events
+//! behind is dropped; `EventSource` reconnects by itself.
+//!
+//! Each client holds a server thread, so `[stream] max_clients` caps how
+//! many are connected at once; past it the server answers 503. With
+//! `[stream] token` set, clients must present it as a bearer token or, since
+//! `EventSource` cannot send headers, as `?token=`.
+
+use postgres::Client;
+use postgres::fallible_iterator::FallibleIterator;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::alert::transitions::{severity_label, Transition};
+use crate::db;
+use crate::logging::{self, DataSource};
+use crate::auth;
+use crate::model::GaugeReading;
+
+/// PostgreSQL notification channel
+pub const CHANNEL: &str = "flomon_stream";
+
+/// Seconds between keep-alive comments
+pub const HEARTBEAT_SECS: u64 = 15;
+
+/// Events a client may have waiting before it is dropped
+pub const QUEUE_DEPTH: usize = 256;
+
+/// Seconds before the listener reconnects after losing the database
+const RECONNECT_SECS: u64 = 10;
+
+/// Clients connected at once when `[stream] max_clients` is not set
+pub const DEFAULT_MAX_CLIENTS: usize = 64;
+
+/// `[stream]` section of flomon.toml
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreamSettings {
+    /// Clients connected at once; later ones get 503
+    #[serde(default = "default_max_clients")]
+    pub max_clients: usize,
+    /// Shared secret clients must present; the stream is open without it
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+impl Default for StreamSettings {
+    fn default() -> Self {
+        Self { max_clients: DEFAULT_MAX_CLIENTS, token: None }
+    }
+}
+
+fn default_max_clients() -> usize {
+    DEFAULT_MAX_CLIENTS
+}
+
+impl StreamSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_clients == 0 {
+            return Err("stream.max_clients must be positive".to_string());
+        }
+        if let Some(token) = &self.token {
+            auth::validate_token("stream.token", token)?;
+        }
+        Ok(())
+    }
+
+    /// Whether a client may connect: always without a token, otherwise when
+    /// `authorization` (the header value) or `query_token` (`?token=`)
+    /// carries it
+    pub fn authorized(&self, authorization: Option<&str>, query_token: Option<&str>) -> bool {
+        let Some(expected) = self.token.as_deref() else {
+            return true;
+        };
+        let from_query = query_token.map(|token| format!("Bearer {}", token));
+        auth::bearer_authorized(authorization.or(from_query.as_deref()), expected)
+    }
+}
+
+/// One streamed event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Reading {
+        site_code: String,
+        parameter_code: String,
+        value: f64,
+        unit: String,
+        /// As reported by the source
+        reading_time: String,
+        qualifier: String,
+    },
+    Transition {
+        site_code: String,
+        /// Severity name, "normal" below action stage
+        from: String,
+        to: String,
+        escalation: bool,
+        stage_ft: f64,
+        reading_time: String,
+        /// Event crest so far, on de-escalations
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        crest_ft: Option<f64>,
+        /// Minutes since the event began, on de-escalations
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        event_minutes: Option<i64>,
+    },
+}
+
+impl StreamEvent {
+    pub fn reading(reading: &GaugeReading) -> Self {
+        StreamEvent::Reading {
+            site_code: reading.site_code.clone(),
+            parameter_code: reading.parameter_code.clone(),
+            value: reading.value,
+            unit: reading.unit.clone(),
+            reading_time: reading.datetime.to_rfc3339(),
+            qualifier: reading.qualifier.clone(),
+        }
+    }
+
+    pub fn transition(transition: &Transition) -> Self {
+        StreamEvent::Transition {
+            site_code: transition.site_code.clone(),
+            from: severity_label(transition.from.as_ref()).to_string(),
+            to: severity_label(transition.to.as_ref()).to_string(),
+            escalation: transition.is_escalation(),
+            stage_ft: transition.stage_ft,
+            reading_time: transition.reading_time.to_rfc3339(),
+            crest_ft: transition.crest.as_ref().map(|crest| crest.stage_ft),
+            event_minutes: transition.event_duration().map(|d| d.num_minutes()),
+        }
+    }
+
+    /// SSE event name
+    pub fn name(&self) -> &'static str {
+        match self {
+            StreamEvent::Reading { .. } => "reading",
+            StreamEvent::Transition { .. } => "transition",
+        }
+    }
+
+    pub fn site_code(&self) -> &str {
+        match self {
+            StreamEvent::Reading { site_code, .. } | StreamEvent::Transition { site_code, .. } => site_code,
+        }
+    }
+
+    /// The event as one SSE message
+    pub fn frame(&self) -> String {
+        format!("event: {}\ndata: {}\n\n", self.name(), serde_json::to_string(self).unwrap())
+    }
+}
+
+/// Announce `events` to every listening server
+pub fn publish(client: &mut Client, events: &[StreamEvent]) -> Result<(), String> {
+    for event in events {
+        let payload = serde_json::to_string(event).unwrap();
+        client.execute("SELECT pg_notify($1, $2)", &[&CHANNEL, &payload])
+            .map_err(|e| format!("Failed to publish {} event for {}: {}", event.name(), event.site_code(), e))?;
+    }
+    Ok(())
+}
+
+struct Subscriber {
+    /// Stations wanted; every station when empty
+    sites: Vec<String>,
+    events: SyncSender<StreamEvent>,
+    /// Shared with the client's `Subscription`; unique once it is gone
+    slot: Arc<()>,
+}
+
+/// A client's place on the hub, given up when dropped
+pub struct Subscription {
+    pub events: Receiver<StreamEvent>,
+    _slot: Arc<()>,
+}
+
+/// Connected stream clients
+pub struct Hub {
+    subscribers: Mutex<Vec<Subscriber>>,
+    max_clients: usize,
+}
+
+impl Hub {
+    pub fn new(max_clients: usize) -> Self {
+        Self { subscribers: Mutex::new(Vec::new()), max_clients }
+    }
+
+    /// Register a client for events about `sites` (every station when
+    /// empty); `None` while `max_clients` are already connected
+    pub fn subscribe(&self, sites: Vec<String>) -> Option<Subscription> {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|subscriber| Arc::strong_count(&subscriber.slot) > 1);
+        if subscribers.len() >= self.max_clients {
+            return None;
+        }
+        let (events, receiver) = mpsc::sync_channel(QUEUE_DEPTH);
+        let slot = Arc::new(());
+        subscribers.push(Subscriber { sites, events, slot: Arc::clone(&slot) });
+        Some(Subscription { events: receiver, _slot: slot })
+    }
+
+    /// Queue `event` for every interested client, dropping clients that
+    /// have disconnected or fallen `QUEUE_DEPTH` behind
+    pub fn broadcast(&self, event: &StreamEvent) {
+        self.subscribers.lock().unwrap().retain(|subscriber| {
+            if !subscriber.sites.is_empty() && !subscriber.sites.iter().any(|s| s == event.site_code()) {
+                return true;
+            }
+            match subscriber.events.try_send(event.clone()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+    }
+
+    /// Clients still connected
+    pub fn len(&self) -> usize {
+        self.subscribers.lock().unwrap().iter()
+            .filter(|subscriber| Arc::strong_count(&subscriber.slot) > 1)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// `LISTEN` for daemon events in the background and broadcast them to
+/// `hub`, reconnecting whenever the database connection is lost
+pub fn spawn_listener(hub: Arc<Hub>) {
+    std::thread::spawn(move || loop {
+        if let Err(e) = listen(&hub) {
+            logging::warn(DataSource::Database, None, &format!("Live stream listener: {}; reconnecting", e));
+        }
+        std::thread::sleep(Duration::from_secs(RECONNECT_SECS));
+    });
+}
+
+fn listen(hub: &Hub) -> Result<(), String> {
+    let mut client = db::connect_simple().map_err(|e| e.to_string())?;
+    client.batch_execute(&format!("LISTEN {}", CHANNEL)).map_err(|e| e.to_string())?;
+    let mut notifications = client.notifications();
+    let mut incoming = notifications.blocking_iter();
+    while let Some(notification) = incoming.next().map_err(|e| e.to_string())? {
+        match serde_json::from_str::<StreamEvent>(notification.payload()) {
+            Ok(event) => hub.broadcast(&event),
+            Err(e) => logging::debug(DataSource::System, None, &format!("Ignoring stream payload: {}", e)),
+        }
+    }
+    Err("notification stream ended".to_string())
+}
+
+/// Answer `request` with an event stream fed by `subscription`, until the
+/// client goes away or is dropped by the hub. Blocks; run it on its own thread.
+pub fn serve_client(request: tiny_http::Request, subscription: Subscription, request_id: &str) {
+    let events = &subscription.events;
+    // Written by hand: tiny_http's chunked encoder holds 8 KiB before sending
+    let mut writer = request.into_writer();
+    let head = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\
+         Connection: close\r\nAccess-Control-Allow-Origin: *\r\nX-Request-Id: {}\r\n\r\n\
+         retry: 5000\n\n",
+        request_id,
+    );
+    if writer.write_all(head.as_bytes()).and_then(|_| writer.flush()).is_err() {
+        return;
+    }
+    loop {
+        let frame = match events.recv_timeout(Duration::from_secs(HEARTBEAT_SECS)) {
+            Ok(event) => event.frame(),
+            Err(RecvTimeoutError::Timeout) => ": keep-alive\n\n".to_string(),
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        if writer.write_all(frame.as_bytes()).and_then(|_| writer.flush()).is_err() {
+            return;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alert::thresholds::FloodSeverity;
+    use chrono::{TimeZone, Utc};
+
+    fn reading(site_code: &str) -> StreamEvent {
+        StreamEvent::Reading {
+            site_code: site_code.to_string(),
+            parameter_code: "00065".to_string(),
+            value: 17.42,
+            unit: "ft".to_string(),
+            reading_time: "2024-05-01T12:00:00.000-05:00".to_string(),
+            qualifier: "P".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_frames_round_trip_through_the_payload() {
+        let transition = StreamEvent::transition(&Transition {
+            site_code: "05568500".to_string(),
+            from: Some(FloodSeverity::Action),
+            to: Some(FloodSeverity::Flood),
+            stage_ft: 20.1,
+            reading_time: Utc.with_ymd_and_hms(2024, 5, 1, 17, 0, 0).unwrap(),
+            crest: None,
+        });
+        let frame = transition.frame();
+        assert!(frame.starts_with("event: transition\ndata: {\"event\":\"transition\",\"site_code\":\"05568500\",\"from\":\"action\",\"to\":\"flood\",\"escalation\":true"));
+        assert!(frame.ends_with("}\n\n") && frame.matches('\n').count() == 3, "one data line");
+
+        for event in [transition, reading("05567500")] {
+            let payload = serde_json::to_string(&event).unwrap();
+            assert_eq!(serde_json::from_str::<StreamEvent>(&payload).unwrap(), event);
+        }
+    }
+
+    #[test]
+    fn test_hub_filters_by_site_and_drops_gone_or_slow_clients() {
+        let hub = Hub::new(DEFAULT_MAX_CLIENTS);
+        let all = hub.subscribe(Vec::new()).unwrap();
+        let peoria = hub.subscribe(vec!["05567500".to_string()]).unwrap();
+        let gone = hub.subscribe(Vec::new()).unwrap();
+        drop(gone);
+
+        hub.broadcast(&reading("05568500"));
+        hub.broadcast(&reading("05567500"));
+        assert_eq!(hub.len(), 2);
+        assert_eq!(all.events.try_iter().map(|e| e.site_code().to_string()).collect::<Vec<_>>(), ["05568500", "05567500"]);
+        assert_eq!(peoria.events.try_iter().count(), 1);
+
+        // Never reads: dropped once its queue is full, though still connected
+        let slow = hub.subscribe(Vec::new()).unwrap();
+        for _ in 0..=QUEUE_DEPTH {
+            hub.broadcast(&reading("05568500"));
+            all.events.try_iter().for_each(drop);
+        }
+        assert_eq!(slow.events.try_iter().count(), QUEUE_DEPTH);
+        assert_eq!(hub.len(), 2);
+    }
+
+    #[test]
+    fn test_hub_refuses_clients_past_max_until_one_leaves() {
+        let hub = Hub::new(2);
+        let first = hub.subscribe(Vec::new()).unwrap();
+        let _second = hub.subscribe(Vec::new()).unwrap();
+        assert!(hub.subscribe(Vec::new()).is_none());
+
+        // No broadcast needed to notice the client has gone
+        drop(first);
+        assert_eq!(hub.len(), 1);
+        assert!(hub.subscribe(Vec::new()).is_some());
+    }
+
+    #[test]
+    fn test_settings_token() {
+        let open = StreamSettings::default();
+        assert!(open.validate().is_ok());
+        assert!(open.authorized(None, None));
+
+        let settings = StreamSettings { token: Some("0123456789abcdef".to_string()), ..open };
+        assert!(settings.validate().is_ok());
+        assert!(settings.authorized(Some("Bearer 0123456789abcdef"), None));
+        assert!(settings.authorized(None, Some("0123456789abcdef")));
+        assert!(!settings.authorized(None, None));
+        assert!(!settings.authorized(Some("Bearer wrong"), Some("0123456789abcdef")));
+
+        assert!(StreamSettings { token: Some("short".to_string()), ..StreamSettings::default() }.validate().is_err());
+        assert!(StreamSettings { max_clients: 0, token: None }.validate().is_err());
+    }
+}
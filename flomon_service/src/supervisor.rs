@@ -0,0 +1,162 @@
+//! Restart long-running tasks that panic or fail.
+//!
+//! The daemon loop and the HTTP endpoint each run under `supervise` (or
+//! `spawn`, on their own thread). A panic is caught and logged with the task
+//! name and panic message; the task is then restarted after a backoff that
+//! doubles on each consecutive failure, up to `Backoff::max`. A task that
+//! stayed up for `Backoff::healthy_after` before failing starts again from
+//! the initial delay, so one crash a day does not creep towards the cap.
+//!
+//! A task that returns `Ok(())` has finished and is not restarted, and no
+//! restart happens once a shutdown has been requested (see `shutdown`).
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::logging;
+use crate::shutdown;
+
+/// Restart delays for a supervised task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    /// Uptime after which a failure counts as the first in a new series
+    pub healthy_after: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(300),
+            healthy_after: Duration::from_secs(600),
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay before restart number `failures` (1-based) in a series
+    pub fn delay(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+}
+
+/// How one run of a task ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskExit {
+    Finished,
+    Failed(String),
+    Panicked(String),
+}
+
+/// Run `task` once, converting a panic into `TaskExit::Panicked`
+pub fn run_once(task: &mut dyn FnMut() -> Result<(), String>) -> TaskExit {
+    match panic::catch_unwind(AssertUnwindSafe(task)) {
+        Ok(Ok(())) => TaskExit::Finished,
+        Ok(Err(e)) => TaskExit::Failed(e),
+        Err(payload) => TaskExit::Panicked(panic_message(payload.as_ref())),
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload.downcast_ref::<&str>().map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string())
+}
+
+/// Run `task` on the current thread until it finishes or shutdown is
+/// requested, restarting it after failures and panics.
+///
+/// Returns the number of restarts.
+pub fn supervise(name: &str, backoff: Backoff, mut task: impl FnMut() -> Result<(), String>) -> u32 {
+    let mut restarts = 0;
+    let mut failures = 0;
+    loop {
+        let started = Instant::now();
+        let detail = match run_once(&mut task) {
+            TaskExit::Finished => return restarts,
+            TaskExit::Failed(e) => format!("failed: {}", e),
+            TaskExit::Panicked(msg) => format!("panicked: {}", msg),
+        };
+
+        failures = if started.elapsed() >= backoff.healthy_after { 1 } else { failures + 1 };
+        if shutdown::requested() {
+            logging::error(logging::DataSource::System, None,
+                &format!("Task '{}' {} (shutting down, not restarting)", name, detail));
+            return restarts;
+        }
+
+        let delay = backoff.delay(failures);
+        logging::error(logging::DataSource::System, None, &format!(
+            "Task '{}' {} after {}s up; restart #{} in {}s",
+            name, detail, started.elapsed().as_secs(), restarts + 1, delay.as_secs_f64(),
+        ));
+        if !shutdown::sleep(delay) {
+            return restarts;
+        }
+        restarts += 1;
+    }
+}
+
+/// `supervise` on a new thread named after the task
+pub fn spawn(
+    name: &str,
+    backoff: Backoff,
+    task: impl FnMut() -> Result<(), String> + Send + 'static,
+) -> std::io::Result<JoinHandle<u32>> {
+    let task_name = name.to_string();
+    thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || supervise(&task_name, backoff, task))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fast() -> Backoff {
+        Backoff {
+            initial: Duration::from_millis(1),
+            max: Duration::from_millis(4),
+            healthy_after: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_to_cap() {
+        let backoff = Backoff::default();
+        assert_eq!(backoff.delay(1), Duration::from_secs(1));
+        assert_eq!(backoff.delay(4), Duration::from_secs(8));
+        assert_eq!(backoff.delay(40), Duration::from_secs(300));
+    }
+
+    #[test]
+    fn test_panicking_task_is_restarted() {
+        let mut runs = 0;
+        let restarts = supervise("flaky", fast(), || {
+            runs += 1;
+            match runs {
+                1 => panic!("mqtt client lost its socket"),
+                2 => Err("connection refused".to_string()),
+                _ => Ok(()),
+            }
+        });
+        assert_eq!(runs, 3);
+        assert_eq!(restarts, 2);
+    }
+
+    #[test]
+    fn test_panic_message_is_captured() {
+        let exit = run_once(&mut || panic!("bad reading {}", 42));
+        assert_eq!(exit, TaskExit::Panicked("bad reading 42".to_string()));
+        assert_eq!(run_once(&mut || Ok(())), TaskExit::Finished);
+    }
+}
@@ -0,0 +1,173 @@
+//! Per-station flood threshold overrides from the database.
+//!
+//! The registry (usgs_stations.toml) holds the NWS flood stages. Thresholds
+//! refined by FloML's segmented regression, or set by hand for a site the
+//! NWS stages don't fit, go in `flood_analysis.thresholds` instead, so they
+//! can change without a deploy. Every column left NULL falls back to the
+//! registry value, and each row records who or what wrote it
+//! (`updated_by`, e.g. "floml segmented-regression v3" or an operator).
+//!
+//! The daemon re-reads the table every cycle and applies it on top of the
+//! registry; an override that changes the effective thresholds is recorded
+//! as a new registry version (see `registry`), so "which thresholds were
+//! in effect when that alert fired?" still has an answer. An override that
+//! would leave a station's thresholds out of order, or names a station not
+//! in the registry, is logged and ignored.
+
+use chrono::{DateTime, Utc};
+use postgres::Client;
+
+use crate::model::FloodThresholds;
+use crate::stations::{self, Station};
+
+/// One row of `flood_analysis.thresholds`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThresholdOverride {
+    pub site_code: String,
+    pub action_stage_ft: Option<f64>,
+    pub flood_stage_ft: Option<f64>,
+    pub moderate_flood_stage_ft: Option<f64>,
+    pub major_flood_stage_ft: Option<f64>,
+    /// Who or what set the override
+    pub updated_by: String,
+    pub updated_at: DateTime<Utc>,
+    pub reason: Option<String>,
+}
+
+impl ThresholdOverride {
+    /// The station's thresholds with this override applied; a station
+    /// without registry thresholds needs all four levels overridden
+    pub fn merge(&self, registry: Option<&FloodThresholds>) -> Option<FloodThresholds> {
+        Some(FloodThresholds {
+            action_stage_ft: self.action_stage_ft.or(registry.map(|t| t.action_stage_ft))?,
+            flood_stage_ft: self.flood_stage_ft.or(registry.map(|t| t.flood_stage_ft))?,
+            moderate_flood_stage_ft: self.moderate_flood_stage_ft.or(registry.map(|t| t.moderate_flood_stage_ft))?,
+            major_flood_stage_ft: self.major_flood_stage_ft.or(registry.map(|t| t.major_flood_stage_ft))?,
+        })
+    }
+}
+
+/// Every stored override, by site code
+pub fn load(client: &mut Client) -> Result<Vec<ThresholdOverride>, String> {
+    let rows = client.query(
+        "SELECT site_code, action_stage_ft, flood_stage_ft, moderate_flood_stage_ft, major_flood_stage_ft,
+                updated_by, updated_at, reason
+         FROM flood_analysis.thresholds
+         ORDER BY site_code",
+        &[],
+    ).map_err(|e| format!("Failed to load threshold overrides: {}", e))?;
+    Ok(rows.iter()
+        .map(|row| ThresholdOverride {
+            site_code: row.get(0),
+            action_stage_ft: row.get(1),
+            flood_stage_ft: row.get(2),
+            moderate_flood_stage_ft: row.get(3),
+            major_flood_stage_ft: row.get(4),
+            updated_by: row.get(5),
+            updated_at: row.get(6),
+            reason: row.get(7),
+        })
+        .collect())
+}
+
+/// `registry` with `overrides` applied, and why each rejected override was
+/// ignored
+pub fn apply(registry: &[Station], overrides: &[ThresholdOverride]) -> (Vec<Station>, Vec<String>) {
+    let mut stations = registry.to_vec();
+    let mut rejected = Vec::new();
+    for o in overrides {
+        let Some(station) = stations.iter_mut().find(|s| s.site_code == o.site_code) else {
+            rejected.push(format!("{}: threshold override for a station not in the registry", o.site_code));
+            continue;
+        };
+        let Some(thresholds) = o.merge(station.thresholds.as_ref()) else {
+            rejected.push(format!("{}: station has no registry thresholds, so its override must set all four levels", o.site_code));
+            continue;
+        };
+        let candidate = Station { thresholds: Some(thresholds), ..station.clone() };
+        match stations::validate(std::slice::from_ref(&candidate)) {
+            Ok(()) => *station = candidate,
+            Err(e) => rejected.push(format!("threshold override by {} ignored: {}", o.updated_by, e)),
+        }
+    }
+    (stations, rejected)
+}
+
+/// Who changed the effective registry when `overrides` replaced `previous`,
+/// for the registry change audit
+pub fn changed_by(previous: &[ThresholdOverride], overrides: &[ThresholdOverride]) -> String {
+    let mut writers: Vec<&str> = overrides.iter()
+        .filter(|o| !previous.contains(o))
+        .map(|o| o.updated_by.as_str())
+        .collect();
+    writers.sort_unstable();
+    writers.dedup();
+    if writers.is_empty() {
+        "threshold override removed".to_string()
+    } else {
+        format!("threshold override ({})", writers.join(", "))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn override_for(site_code: &str, action: Option<f64>, flood: Option<f64>) -> ThresholdOverride {
+        ThresholdOverride {
+            site_code: site_code.to_string(),
+            action_stage_ft: action,
+            flood_stage_ft: flood,
+            moderate_flood_stage_ft: None,
+            major_flood_stage_ft: None,
+            updated_by: "floml".to_string(),
+            updated_at: Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn test_overrides_fall_back_to_registry_values() {
+        let registry = stations::load_stations();
+        let station = registry.iter().find(|s| s.thresholds.is_some()).unwrap();
+        let t = station.thresholds.as_ref().unwrap();
+        let lowered = override_for(&station.site_code, Some(t.action_stage_ft - 0.5), None);
+
+        let (stations, rejected) = apply(&registry, std::slice::from_ref(&lowered));
+        assert!(rejected.is_empty(), "{:?}", rejected);
+        let applied = stations.iter().find(|s| s.site_code == station.site_code).unwrap();
+        let applied = applied.thresholds.as_ref().unwrap();
+        assert_eq!(applied.action_stage_ft, t.action_stage_ft - 0.5);
+        assert_eq!(applied.flood_stage_ft, t.flood_stage_ft, "registry fallback");
+        assert_eq!(stations.len(), registry.len());
+
+        assert_eq!(changed_by(&[], std::slice::from_ref(&lowered)), "threshold override (floml)");
+        assert_eq!(changed_by(std::slice::from_ref(&lowered), &[]), "threshold override removed");
+    }
+
+    #[test]
+    fn test_invalid_overrides_are_rejected() {
+        let registry = stations::load_stations();
+        let station = registry.iter().find(|s| s.thresholds.is_some()).unwrap();
+        let t = station.thresholds.as_ref().unwrap();
+        let unordered = override_for(&station.site_code, Some(t.flood_stage_ft + 1.0), None);
+        let unknown = override_for("99999999", Some(10.0), Some(12.0));
+
+        let (stations, rejected) = apply(&registry, &[unordered, unknown]);
+        assert_eq!(rejected.len(), 2);
+        assert!(rejected[0].contains("must ascend"), "{}", rejected[0]);
+        assert!(rejected[1].contains("not in the registry"));
+        let kept = stations.iter().find(|s| s.site_code == station.site_code).unwrap();
+        assert_eq!(kept.thresholds.as_ref().unwrap().action_stage_ft, t.action_stage_ft);
+
+        if let Some(bare) = registry.iter().find(|s| s.thresholds.is_none()) {
+            let partial = override_for(&bare.site_code, Some(10.0), Some(12.0));
+            assert!(apply(&registry, &[partial]).1[0].contains("all four levels"));
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Native TLS for the HTTP endpoint.
+//!
+//! tiny_http only accepts plain TCP, so HTTPS is terminated here: the
+//! public port is served by a rustls acceptor that forwards each decrypted
+//! connection to the HTTP server listening on a loopback port. Each
+//! forwarded connection's loopback source port is recorded in `TlsPeers`,
+//! which is how the endpoint recovers the real client address (and knows
+//! the request arrived over HTTPS) without rewriting the HTTP stream.
+//!
+//! Configured with `tls_cert` / `tls_key` (PEM) under `[endpoint]`. When
+//! running behind Caddy or another reverse proxy, leave TLS to the proxy
+//! and list it in `trusted_proxies` instead.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+
+/// How long one side may wait for data before the other side is checked
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Connections with no traffic in either direction for this long are closed
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Certificate chain and private key, both PEM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsFiles {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+/// Client address for each live forwarded connection, keyed by the
+/// loopback port the connection arrives at the HTTP server from
+#[derive(Debug, Clone, Default)]
+pub struct TlsPeers {
+    peers: Arc<Mutex<HashMap<u16, SocketAddr>>>,
+}
+
+impl TlsPeers {
+    /// Client behind the forwarded connection from `local` (the HTTP
+    /// server's view of the remote address), if it is one of ours
+    pub fn client_for(&self, local: &SocketAddr) -> Option<SocketAddr> {
+        if !local.ip().is_loopback() {
+            return None;
+        }
+        self.peers.lock().ok()?.get(&local.port()).copied()
+    }
+
+    fn insert(&self, port: u16, client: SocketAddr) {
+        if let Ok(mut peers) = self.peers.lock() {
+            peers.insert(port, client);
+        }
+    }
+
+    fn remove(&self, port: u16) {
+        if let Ok(mut peers) = self.peers.lock() {
+            peers.remove(&port);
+        }
+    }
+}
+
+/// Build a rustls server configuration from PEM files
+pub fn load_server_config(files: &TlsFiles) -> Result<Arc<ServerConfig>, String> {
+    let open = |path: &Path| {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+    };
+
+    let certs: Vec<rustls::Certificate> = rustls_pemfile::certs(&mut open(&files.cert)?)
+        .map_err(|e| format!("Failed to read certificates from {}: {}", files.cert.display(), e))?
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect();
+    if certs.is_empty() {
+        return Err(format!("No certificates found in {}", files.cert.display()));
+    }
+
+    let key = rustls_pemfile::read_all(&mut open(&files.key)?)
+        .map_err(|e| format!("Failed to read private key from {}: {}", files.key.display(), e))?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(rustls::PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| format!("No private key found in {}", files.key.display()))?;
+
+    ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map(Arc::new)
+        .map_err(|e| format!("Invalid TLS certificate/key: {}", e))
+}
+
+/// Accept TLS on `public`, forwarding each connection to `backend`.
+///
+/// Binds before returning (so a port conflict is reported to the caller);
+/// the accept loop runs on a background thread, one thread per connection.
+pub fn spawn_terminator(public: SocketAddr, backend: SocketAddr, config: Arc<ServerConfig>) -> Result<TlsPeers, String> {
+    let listener = TcpListener::bind(public)
+        .map_err(|e| format!("Failed to bind TLS listener on {}: {}", public, e))?;
+    let peers = TlsPeers::default();
+    let accept_peers = peers.clone();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let config = config.clone();
+            let peers = accept_peers.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = forward(stream, backend, config, &peers) {
+                    eprintln!("TLS connection error: {}", e);
+                }
+            });
+        }
+    });
+    Ok(peers)
+}
+
+/// Relay one client connection to the backend until either side closes
+fn forward(client: TcpStream, backend: SocketAddr, config: Arc<ServerConfig>, peers: &TlsPeers) -> io::Result<()> {
+    let client_addr = client.peer_addr()?;
+    let connection = ServerConnection::new(config).map_err(io::Error::other)?;
+    let mut upstream = TcpStream::connect(backend)?;
+    let upstream_port = upstream.local_addr()?.port();
+    peers.insert(upstream_port, client_addr);
+
+    client.set_read_timeout(Some(POLL_INTERVAL))?;
+    upstream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut tls = StreamOwned::new(connection, client);
+    let result = relay(&mut tls, &mut upstream);
+
+    peers.remove(upstream_port);
+    let _ = upstream.shutdown(Shutdown::Both);
+    let _ = tls.sock.shutdown(Shutdown::Both);
+    result
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
+
+/// Copy in both directions, alternating short blocking reads
+fn relay(tls: &mut StreamOwned<ServerConnection, TcpStream>, upstream: &mut TcpStream) -> io::Result<()> {
+    let mut buf = [0u8; 16 * 1024];
+    let mut last_traffic = Instant::now();
+    loop {
+        match tls.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            // Client hung up without close_notify
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Ok(n) => {
+                upstream.write_all(&buf[..n])?;
+                last_traffic = Instant::now();
+            }
+            Err(e) if is_timeout(&e) => {}
+            Err(e) => return Err(e),
+        }
+        match upstream.read(&mut buf) {
+            Ok(0) => {
+                tls.conn.send_close_notify();
+                return tls.flush();
+            }
+            Ok(n) => {
+                tls.write_all(&buf[..n])?;
+                tls.flush()?;
+                last_traffic = Instant::now();
+            }
+            Err(e) if is_timeout(&e) => {}
+            Err(e) => return Err(e),
+        }
+        if last_traffic.elapsed() > IDLE_TIMEOUT {
+            return Ok(());
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peer_lookup_only_for_loopback() {
+        let peers = TlsPeers::default();
+        let client: SocketAddr = "203.0.113.7:51234".parse().unwrap();
+        peers.insert(40000, client);
+
+        assert_eq!(peers.client_for(&"127.0.0.1:40000".parse().unwrap()), Some(client));
+        assert_eq!(peers.client_for(&"192.0.2.1:40000".parse().unwrap()), None);
+
+        peers.remove(40000);
+        assert_eq!(peers.client_for(&"127.0.0.1:40000".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn test_missing_files_are_reported() {
+        let files = TlsFiles {
+            cert: PathBuf::from("/nonexistent/flomon.crt"),
+            key: PathBuf::from("/nonexistent/flomon.key"),
+        };
+        let err = load_server_config(&files).unwrap_err();
+        assert!(err.contains("/nonexistent/flomon.crt"));
+    }
+}
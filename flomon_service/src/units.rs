@@ -0,0 +1,401 @@
+//! Per-parameter unit normalization applied at ingest.
+//!
+//! USGS usually reports discharge as `ft3/s`, but older series and some
+//! daily-value products spell it `cfs` or `ft^3/s`; CWMS reports stage and
+//! elevation in feet or meters depending on the timeseries version. Parsers
+//! resolve a `UnitConversion` once per series and apply it to every value,
+//! so everything downstream sees one canonical unit per parameter:
+//!
+//! | Quantity    | Canonical | Accepted                                   |
+//! |-------------|-----------|--------------------------------------------|
+//! | Length      | `ft`      | ft, feet, m, meters                        |
+//! | Flow        | `ft3/s`   | ft3/s, cfs, ft^3/s, kcfs, m3/s, cms        |
+//! | Depth       | `in`      | in, inches, mm, cm                         |
+//! | Temperature | `deg C`   | deg C, degC, C, deg F, degF, F             |
+//! | Speed       | `kt`      | kt, knots, kts, mph, m/s                   |
+//!
+//! When the source spelling differs from the canonical one, the source
+//! unit is kept in `original_unit` (and stored alongside the reading).
+//! Parameters with no canonical unit pass through unchanged. A known
+//! parameter in an unrecognized unit is an error rather than a guess.
+//!
+//! # Typed quantities
+//! `Stage`, `Discharge`, `Precip` and `WindSpeed` wrap a value in its
+//! canonical unit. They are built only through a named unit (`from_m`,
+//! `from_cms`, ...) or a unit label (`in_unit`), and compare and subtract
+//! only with their own kind, so a discharge can never be checked against
+//! a stage threshold or a metric stage against one in feet.
+
+/// Feet per meter
+pub const FT_PER_M: f64 = 3.280_839_895;
+
+/// Cubic feet per second per cubic meter per second
+pub const CFS_PER_CMS: f64 = 35.314_666_72;
+
+/// Millimeters per inch
+pub const MM_PER_IN: f64 = 25.4;
+
+/// Miles per hour per knot
+pub const MPH_PER_KNOT: f64 = 1.150_779_448;
+
+/// Meters per second per knot
+pub const MPS_PER_KNOT: f64 = 0.514_444_444;
+
+/// Physical quantity a parameter measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    Length,
+    Flow,
+    Depth,
+    Temperature,
+    Speed,
+}
+
+impl Quantity {
+    /// Unit every value of this quantity is stored in
+    pub fn canonical_unit(&self) -> &'static str {
+        match self {
+            Quantity::Length => "ft",
+            Quantity::Flow => "ft3/s",
+            Quantity::Depth => "in",
+            Quantity::Temperature => "deg C",
+            Quantity::Speed => "kt",
+        }
+    }
+
+    /// `value` in `unit` converted to the canonical unit, if recognized
+    pub fn to_canonical(&self, value: f64, unit: &str) -> Option<f64> {
+        self.conversion_from(unit).map(|(factor, offset)| value * factor + offset)
+    }
+
+    /// (factor, offset) taking `unit` to the canonical unit, if recognized
+    fn conversion_from(&self, unit: &str) -> Option<(f64, f64)> {
+        let unit = unit.trim().to_ascii_lowercase();
+        let linear = |factor: f64| Some((factor, 0.0));
+        match (self, unit.as_str()) {
+            (Quantity::Length, "ft" | "feet" | "foot") => linear(1.0),
+            (Quantity::Length, "m" | "meters" | "metres" | "meter" | "metre") => linear(FT_PER_M),
+            (Quantity::Flow, "ft3/s" | "cfs" | "ft^3/s" | "ft3/sec") => linear(1.0),
+            (Quantity::Flow, "kcfs") => linear(1000.0),
+            (Quantity::Flow, "m3/s" | "cms" | "m^3/s") => linear(CFS_PER_CMS),
+            (Quantity::Depth, "in" | "inches" | "inch") => linear(1.0),
+            (Quantity::Depth, "mm") => linear(1.0 / MM_PER_IN),
+            (Quantity::Depth, "cm") => linear(10.0 / MM_PER_IN),
+            (Quantity::Temperature, "deg c" | "degc" | "c") => linear(1.0),
+            (Quantity::Temperature, "deg f" | "degf" | "f") => Some((5.0 / 9.0, -32.0 * 5.0 / 9.0)),
+            (Quantity::Speed, "kt" | "kts" | "knots" | "knot") => linear(1.0),
+            (Quantity::Speed, "mph") => linear(1.0 / MPH_PER_KNOT),
+            (Quantity::Speed, "m/s" | "mps") => linear(1.0 / MPS_PER_KNOT),
+            _ => None,
+        }
+    }
+}
+
+/// Quantity for a USGS parameter code
+pub fn usgs_quantity(parameter_code: &str) -> Option<Quantity> {
+    match parameter_code {
+        "00065" | "62614" | "62615" => Some(Quantity::Length),
+        "00060" => Some(Quantity::Flow),
+        "00045" => Some(Quantity::Depth),
+        "00010" => Some(Quantity::Temperature),
+        _ => None,
+    }
+}
+
+/// Quantity for a CWMS parameter ("Stage", "Elev-Pool", "Flow-Out", ...)
+pub fn cwms_quantity(parameter_id: &str) -> Option<Quantity> {
+    match parameter_id.split('-').next().unwrap_or(parameter_id) {
+        "Stage" | "Elev" | "Opening" => Some(Quantity::Length),
+        "Flow" => Some(Quantity::Flow),
+        "Precip" => Some(Quantity::Depth),
+        "Temp" => Some(Quantity::Temperature),
+        _ => None,
+    }
+}
+
+/// Canonical unit for a USGS parameter code, or "" when there is none
+pub fn canonical_usgs_unit(parameter_code: &str) -> &'static str {
+    usgs_quantity(parameter_code).map(|q| q.canonical_unit()).unwrap_or("")
+}
+
+/// Conversion for one series, resolved once and applied to each value
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitConversion {
+    factor: f64,
+    offset: f64,
+    /// Unit of converted values
+    pub unit: String,
+    /// Source unit when it differs from `unit`
+    pub original_unit: Option<String>,
+}
+
+impl UnitConversion {
+    /// Convert one value
+    pub fn apply(&self, value: f64) -> f64 {
+        value * self.factor + self.offset
+    }
+}
+
+/// Resolve the conversion from `source_unit` for a parameter's quantity.
+///
+/// `parameter` is only used in the error message.
+pub fn resolve(quantity: Option<Quantity>, parameter: &str, source_unit: &str) -> Result<UnitConversion, String> {
+    let Some(quantity) = quantity else {
+        return Ok(UnitConversion {
+            factor: 1.0,
+            offset: 0.0,
+            unit: source_unit.to_string(),
+            original_unit: None,
+        });
+    };
+
+    let (factor, offset) = quantity.conversion_from(source_unit)
+        .ok_or_else(|| format!("Unrecognized unit '{}' for parameter {}", source_unit, parameter))?;
+    let canonical = quantity.canonical_unit();
+    Ok(UnitConversion {
+        factor,
+        offset,
+        unit: canonical.to_string(),
+        original_unit: (source_unit != canonical).then(|| source_unit.to_string()),
+    })
+}
+
+/// Conversion for a USGS series
+pub fn for_usgs(parameter_code: &str, source_unit: &str) -> Result<UnitConversion, String> {
+    resolve(usgs_quantity(parameter_code), parameter_code, source_unit)
+}
+
+/// Conversion for a CWMS timeseries
+pub fn for_cwms(parameter_id: &str, source_unit: &str) -> Result<UnitConversion, String> {
+    resolve(cwms_quantity(parameter_id), parameter_id, source_unit)
+}
+
+// ---------------------------------------------------------------------------
+// Typed quantities
+// ---------------------------------------------------------------------------
+
+/// A value of one `Quantity` held in its canonical unit
+macro_rules! typed_quantity {
+    ($(#[$doc:meta])* $name:ident, $quantity:expr, $canonical:ident) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+        pub struct $name(f64);
+
+        impl $name {
+            pub const QUANTITY: Quantity = $quantity;
+
+            /// From a value in the canonical unit
+            pub const fn $canonical(value: f64) -> Self {
+                $name(value)
+            }
+
+            /// From a value labelled with any accepted spelling of a unit
+            /// of this quantity; `None` for any other unit
+            pub fn in_unit(value: f64, unit: &str) -> Option<Self> {
+                Self::QUANTITY.to_canonical(value, unit).map($name)
+            }
+
+            /// Value in the canonical unit
+            pub fn value(self) -> f64 {
+                self.0
+            }
+        }
+
+        impl std::ops::Add for $name {
+            type Output = $name;
+            fn add(self, other: $name) -> $name {
+                $name(self.0 + other.0)
+            }
+        }
+
+        impl std::ops::Sub for $name {
+            type Output = $name;
+            fn sub(self, other: $name) -> $name {
+                $name(self.0 - other.0)
+            }
+        }
+
+        /// Value and canonical unit; a precision applies to the value
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match f.precision() {
+                    Some(precision) => write!(f, "{:.*} {}", precision, self.0, Self::QUANTITY.canonical_unit()),
+                    None => write!(f, "{} {}", self.0, Self::QUANTITY.canonical_unit()),
+                }
+            }
+        }
+    };
+}
+
+typed_quantity!(
+    /// River stage or water-surface elevation, in feet
+    Stage, Quantity::Length, from_ft
+);
+
+typed_quantity!(
+    /// Streamflow, in cubic feet per second
+    Discharge, Quantity::Flow, from_cfs
+);
+
+typed_quantity!(
+    /// Precipitation depth, in inches
+    Precip, Quantity::Depth, from_in
+);
+
+typed_quantity!(
+    /// Wind speed, in knots
+    WindSpeed, Quantity::Speed, from_knots
+);
+
+impl Stage {
+    pub fn from_m(m: f64) -> Self {
+        Stage(m * FT_PER_M)
+    }
+
+    pub fn ft(self) -> f64 {
+        self.0
+    }
+
+    pub fn m(self) -> f64 {
+        self.0 / FT_PER_M
+    }
+}
+
+impl Discharge {
+    pub fn from_cms(cms: f64) -> Self {
+        Discharge(cms * CFS_PER_CMS)
+    }
+
+    pub fn cfs(self) -> f64 {
+        self.0
+    }
+
+    pub fn cms(self) -> f64 {
+        self.0 / CFS_PER_CMS
+    }
+}
+
+impl Precip {
+    pub fn from_mm(mm: f64) -> Self {
+        Precip(mm / MM_PER_IN)
+    }
+
+    pub fn inches(self) -> f64 {
+        self.0
+    }
+
+    pub fn mm(self) -> f64 {
+        self.0 * MM_PER_IN
+    }
+}
+
+impl WindSpeed {
+    pub fn from_mph(mph: f64) -> Self {
+        WindSpeed(mph / MPH_PER_KNOT)
+    }
+
+    pub fn from_mps(mps: f64) -> Self {
+        WindSpeed(mps / MPS_PER_KNOT)
+    }
+
+    pub fn knots(self) -> f64 {
+        self.0
+    }
+
+    pub fn mph(self) -> f64 {
+        self.0 * MPH_PER_KNOT
+    }
+
+    pub fn mps(self) -> f64 {
+        self.0 * MPS_PER_KNOT
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn test_canonical_units_pass_through() {
+        let c = for_usgs("00060", "ft3/s").unwrap();
+        assert_eq!(c.unit, "ft3/s");
+        assert_eq!(c.original_unit, None);
+        assert_eq!(c.apply(12_400.0), 12_400.0);
+    }
+
+    #[test]
+    fn test_discharge_spellings_and_metric() {
+        let cfs = for_usgs("00060", "cfs").unwrap();
+        assert_eq!(cfs.unit, "ft3/s");
+        assert_eq!(cfs.original_unit.as_deref(), Some("cfs"));
+        assert!(close(cfs.apply(100.0), 100.0));
+
+        assert!(close(for_usgs("00060", "kcfs").unwrap().apply(12.4), 12_400.0));
+        assert!(close(for_cwms("Flow-Out", "cms").unwrap().apply(1.0), 35.31466672));
+    }
+
+    #[test]
+    fn test_cwms_meters_become_feet() {
+        let c = for_cwms("Elev", "m").unwrap();
+        assert_eq!(c.unit, "ft");
+        assert_eq!(c.original_unit.as_deref(), Some("m"));
+        // LaGrange pool, 440 ft NGVD29
+        assert!(close(c.apply(134.112), 440.0));
+    }
+
+    #[test]
+    fn test_temperature_uses_offset() {
+        let c = for_usgs("00010", "deg F").unwrap();
+        assert!(close(c.apply(212.0), 100.0));
+        assert!(close(c.apply(32.0), 0.0));
+    }
+
+    #[test]
+    fn test_unknown_parameter_passes_through_unknown_unit_fails() {
+        let c = for_usgs("99999", "furlongs").unwrap();
+        assert_eq!(c.unit, "furlongs");
+        assert_eq!(c.original_unit, None);
+
+        assert!(for_usgs("00065", "furlongs").is_err());
+    }
+
+    #[test]
+    fn test_typed_quantities_convert_and_round_trip() {
+        assert!(close(Stage::from_m(134.112).ft(), 440.0));
+        assert!(close(Stage::from_ft(440.0).m(), 134.112));
+        assert!(close(Discharge::from_cms(1.0).cfs(), 35.31466672));
+        assert!(close(Discharge::from_cfs(35.31466672).cms(), 1.0));
+        assert!(close(Precip::from_mm(25.4).inches(), 1.0));
+        assert!(close(Precip::from_in(2.0).mm(), 50.8));
+        assert!(close(WindSpeed::from_knots(10.0).mph(), 11.50779448));
+        assert!(close(WindSpeed::from_mps(5.144444).knots(), 10.0));
+        assert!(close(WindSpeed::from_mph(11.50779448).mps(), 5.14444444));
+    }
+
+    #[test]
+    fn test_typed_quantities_from_labels_compare_in_one_unit() {
+        let metric = Stage::in_unit(6.1, "m").unwrap();
+        assert!(metric > Stage::from_ft(20.0) && metric < Stage::from_ft(20.1));
+        assert_eq!(Stage::in_unit(20.0, "ft3/s"), None, "a discharge label is not a stage");
+        assert_eq!(Discharge::in_unit(12.4, "kcfs"), Some(Discharge::from_cfs(12_400.0)));
+        assert_eq!(WindSpeed::in_unit(10.0, "kts"), Some(WindSpeed::from_knots(10.0)));
+
+        assert_eq!(format!("{:.2}", Stage::from_ft(18.0) - Stage::from_ft(0.5)), "17.50 ft");
+        assert_eq!(format!("{}", Discharge::from_cfs(100.0) + Discharge::from_cfs(20.0)), "120 ft3/s");
+    }
+
+    #[test]
+    fn test_canonical_usgs_unit() {
+        assert_eq!(canonical_usgs_unit("00065"), "ft");
+        assert_eq!(canonical_usgs_unit("00045"), "in");
+        assert_eq!(canonical_usgs_unit("99999"), "");
+    }
+}
@@ -1,16 +1,18 @@
-/// USACE/CWMS Location Registry
-///
-/// Loads USACE Corps Water Management System (CWMS) locations from configuration
-/// file, providing metadata for lock/dam pool elevations, Mississippi River stages,
-/// and other USACE-managed monitoring points.
-///
-/// ## Configuration File
-/// 
-/// Location metadata is loaded from `usace_stations.toml`, allowing updates to timeseries
-/// IDs, relevance notes, and monitoring priorities without recompilation.
+//! USACE/CWMS Location Registry
+//!
+//! Loads USACE Corps Water Management System (CWMS) locations from configuration
+//! file, providing metadata for lock/dam pool elevations, Mississippi River stages,
+//! and other USACE-managed monitoring points.
+//!
+//! ## Configuration File
+//! 
+//! Location metadata is loaded from `usace_stations.toml`, allowing updates to timeseries
+//! IDs, relevance notes, and monitoring priorities without recompilation.
 
+use crate::datum::{self, SiteDatum, VerticalDatum};
+use crate::ingest::cwms::CwmsParameter;
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 
 // ---------------------------------------------------------------------------
@@ -25,9 +27,10 @@ struct UsaceConfig {
 }
 
 /// USACE station configuration from TOML
-#[derive(Debug, Deserialize)]
-struct UsaceStationConfig {
+#[derive(Debug, Clone, Deserialize)]
+pub struct UsaceStationConfig {
     shef_id: Option<String>,
+    #[allow(dead_code)] // Present in TOML; not yet used at runtime
     shef_pool_id: Option<String>,
     cwms_location: Option<String>,
     office: String,
@@ -35,7 +38,13 @@ struct UsaceStationConfig {
     river_mile: Option<f64>,
     river_mile_above_ohio: Option<f64>,
     pool_elevation_target_ft_ngvd29: Option<f64>,
+    #[allow(dead_code)] // Present in TOML; not yet used at runtime
     datum_note: Option<String>,
+    /// Datum of the location's elevations; NGVD29 when absent
+    elevation_datum: Option<String>,
+    /// Feet added to an elevation in each datum to give NGVD29
+    #[serde(default)]
+    to_ngvd29_ft: BTreeMap<String, f64>,
     data_types: Vec<String>,
     relevance: String,
     flood_note: Option<String>,
@@ -66,6 +75,9 @@ pub struct UsaceLocation {
     /// Pool elevation target (NGVD29 datum, for lock/dam pools)
     pub pool_target_ft: Option<f64>,
     
+    /// Datum of the reported elevations and the offsets to NGVD29
+    pub datum: SiteDatum,
+    
     /// Data types available (pool_elevation, tailwater_elevation, stage, discharge, etc.)
     pub data_types: Vec<String>,
     
@@ -83,12 +95,47 @@ pub struct UsaceLocation {
 }
 
 /// Timeseries IDs discovered from CWMS catalog at runtime
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct DiscoveredTimeseries {
     pub pool_elevation: Option<String>,
     pub tailwater_elevation: Option<String>,
     pub stage: Option<String>,
     pub discharge: Option<String>,
+    pub outflow: Option<String>,
+    pub gate_opening: Option<String>,
+}
+
+impl DiscoveredTimeseries {
+    pub fn set(&mut self, parameter: CwmsParameter, timeseries_id: String) {
+        let slot = match parameter {
+            CwmsParameter::PoolElevation => &mut self.pool_elevation,
+            CwmsParameter::TailwaterElevation => &mut self.tailwater_elevation,
+            CwmsParameter::Stage => &mut self.stage,
+            CwmsParameter::Discharge => &mut self.discharge,
+            CwmsParameter::Outflow => &mut self.outflow,
+            CwmsParameter::GateOpening => &mut self.gate_opening,
+        };
+        *slot = Some(timeseries_id);
+    }
+
+    /// Every discovered timeseries with what it measures
+    pub fn series(&self) -> Vec<(CwmsParameter, String)> {
+        [
+            (CwmsParameter::PoolElevation, &self.pool_elevation),
+            (CwmsParameter::TailwaterElevation, &self.tailwater_elevation),
+            (CwmsParameter::Stage, &self.stage),
+            (CwmsParameter::Discharge, &self.discharge),
+            (CwmsParameter::Outflow, &self.outflow),
+            (CwmsParameter::GateOpening, &self.gate_opening),
+        ]
+        .into_iter()
+        .filter_map(|(parameter, id)| id.clone().map(|id| (parameter, id)))
+        .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.series().is_empty()
+    }
 }
 
 /// Monitoring priority for polling frequency
@@ -118,35 +165,70 @@ pub fn load_locations() -> Result<Vec<UsaceLocation>, String> {
     let config: UsaceConfig = toml::from_str(&content)
         .map_err(|e| format!("Failed to parse {}: {}", config_path, e))?;
     
-    let locations = config.usace_stations
+    config.usace_stations
         .into_iter()
         .map(|station| {
-            // Determine priority before moving relevance
-            let priority = determine_priority(&station.relevance);
-            
-            UsaceLocation {
-                shef_id: station.shef_id,
-                cwms_location: station.cwms_location.unwrap_or_else(|| {
-                    // If no cwms_location specified, derive from name
-                    station.name.split(" at ").last()
-                        .unwrap_or(&station.name)
-                        .replace(", ", "-")
-                        .replace(" ", "-")
-                }),
-                office: station.office,
-                name: station.name,
-                river_mile: station.river_mile.or(station.river_mile_above_ohio),
-                pool_target_ft: station.pool_elevation_target_ft_ngvd29,
-                data_types: station.data_types,
-                relevance: station.relevance,
-                flood_notes: station.flood_note,
-                priority,
-                discovered_timeseries: None, // Will be populated by discover_timeseries_ids()
-            }
+            station.site_datum().map_err(|e| format!("{}: {}", config_path, e))?;
+            Ok(UsaceLocation::from(station))
         })
-        .collect();
+        .collect()
+}
+
+impl UsaceStationConfig {
+    /// Datum of this location's elevations (`elevation_datum`, default
+    /// NGVD29) with its `to_ngvd29_ft` offsets
+    pub fn site_datum(&self) -> Result<SiteDatum, String> {
+        let site = self.cwms_location.as_deref().unwrap_or(&self.name);
+        let datum = self.elevation_datum.as_deref()
+            .map(VerticalDatum::parse)
+            .transpose()
+            .map_err(|e| format!("{}: elevation_datum: {}", site, e))?
+            .unwrap_or(datum::COMMON);
+        let to_ngvd29_ft = datum::parse_offsets(&self.to_ngvd29_ft).map_err(|e| format!("{}: {}", site, e))?;
+        if datum != datum::COMMON && !to_ngvd29_ft.iter().any(|(d, _)| *d == datum) {
+            return Err(format!("{}: elevations are {} but to_ngvd29_ft has no {} offset", site, datum, datum));
+        }
+        Ok(SiteDatum { datum, gauge_zero_ft: None, to_ngvd29_ft })
+    }
+}
+
+impl UsaceLocation {
+    /// An elevation reported at this location, on NGVD29
+    pub fn elevation_ngvd29(&self, elevation_ft: f64) -> Option<f64> {
+        self.datum.convert(elevation_ft, self.datum.datum, datum::COMMON)
+    }
+}
+
+impl From<UsaceStationConfig> for UsaceLocation {
+    /// # Panics
+    /// Panics if the datum entries are invalid (see
+    /// `UsaceStationConfig::site_datum`); loaders check them first.
+    fn from(station: UsaceStationConfig) -> Self {
+        // Determine priority before moving relevance
+        let priority = determine_priority(&station.relevance);
+        let datum = station.site_datum().unwrap_or_else(|e| panic!("usace_stations.toml: {}", e));
     
-    Ok(locations)
+        UsaceLocation {
+            shef_id: station.shef_id,
+            cwms_location: station.cwms_location.unwrap_or_else(|| {
+                // If no cwms_location specified, derive from name
+                station.name.split(" at ").last()
+                    .unwrap_or(&station.name)
+                    .replace(", ", "-")
+                    .replace(" ", "-")
+            }),
+            office: station.office,
+            name: station.name,
+            river_mile: station.river_mile.or(station.river_mile_above_ohio),
+            pool_target_ft: station.pool_elevation_target_ft_ngvd29,
+            datum,
+            data_types: station.data_types,
+            relevance: station.relevance,
+            flood_notes: station.flood_note,
+            priority,
+            discovered_timeseries: None, // Will be populated by discover_timeseries_ids()
+        }
+    }
 }
 
 /// Load locations as a HashMap for O(1) lookups by CWMS location name
@@ -224,51 +306,20 @@ pub fn discover_timeseries_ids(
 ) -> Result<DiscoveredTimeseries, String> {
     use crate::ingest::cwms;
     
-    let data_types = &location.data_types;
-    let mut discovered = DiscoveredTimeseries {
-        pool_elevation: None,
-        tailwater_elevation: None,
-        stage: None,
-        discharge: None,
-    };
-    
-    // Discover pool elevation if needed
-    if data_types.contains(&"pool_elevation".to_string()) {
-        discovered.pool_elevation = cwms::discover_pool_elevation(
-            client,
-            &location.office,
-            &location.cwms_location
-        ).map_err(|e| format!("Failed to discover pool elevation: {}", e))?;
-        
-        if let Some(ref ts_id) = discovered.pool_elevation {
-            println!("      Discovered pool elevation: {}", ts_id);
-        }
-    }
-    
-    // Discover tailwater elevation if needed
-    if data_types.contains(&"tailwater_elevation".to_string()) {
-        discovered.tailwater_elevation = cwms::discover_tailwater_elevation(
-            client,
-            &location.office,
-            &location.cwms_location
-        ).map_err(|e| format!("Failed to discover tailwater elevation: {}", e))?;
-        
-        if let Some(ref ts_id) = discovered.tailwater_elevation {
-            println!("      Discovered tailwater elevation: {}", ts_id);
-        }
+    let wanted: Vec<CwmsParameter> = CwmsParameter::ALL.into_iter()
+        .filter(|p| location.data_types.iter().any(|d| CwmsParameter::from_data_type(d) == Some(*p)))
+        .collect();
+    let mut discovered = DiscoveredTimeseries::default();
+    if wanted.is_empty() {
+        return Ok(discovered);
     }
     
-    // Discover stage if needed (for river gauges, not pools)
-    if data_types.contains(&"stage".to_string()) {
-        discovered.stage = cwms::discover_stage(
-            client,
-            &location.office,
-            &location.cwms_location
-        ).map_err(|e| format!("Failed to discover stage: {}", e))?;
-        
-        if let Some(ref ts_id) = discovered.stage {
-            println!("      Discovered stage: {}", ts_id);
-        }
+    // One catalog query covers every parameter of the location
+    let found = cwms::discover_location(client, &location.office, &location.cwms_location, &wanted)
+        .map_err(|e| format!("Failed to discover timeseries: {}", e))?;
+    for (parameter, ts_id) in found {
+        println!("      Discovered {}: {}", parameter.label(), ts_id);
+        discovered.set(parameter, ts_id);
     }
     
     Ok(discovered)
@@ -282,10 +333,7 @@ pub fn update_with_discovered_timeseries(
     let discovered = discover_timeseries_ids(client, location)?;
     
     // Check if we found at least one timeseries
-    if discovered.pool_elevation.is_none() 
-        && discovered.tailwater_elevation.is_none() 
-        && discovered.stage.is_none() 
-        && discovered.discharge.is_none() {
+    if discovered.is_empty() {
         return Err(format!("No timeseries found for location: {}", location.name));
     }
     
@@ -366,6 +414,14 @@ mod tests {
         assert_eq!(peoria.office, "MVR");
         assert!(peoria.pool_target_ft.is_some());
         assert!(peoria.priority == MonitoringPriority::Critical);
+        assert_eq!(peoria.elevation_ngvd29(447.0), Some(447.0));
+        
+        // Lockport reports on IGLD85
+        let lockport = locations.iter()
+            .find(|loc| loc.cwms_location == "Lockport-Pool")
+            .expect("Lockport location not found");
+        assert_eq!(lockport.datum.datum, VerticalDatum::Igld85);
+        assert!((lockport.elevation_ngvd29(578.0).unwrap() - 579.3).abs() < 1e-9);
     }
     
     #[test]
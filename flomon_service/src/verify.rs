@@ -8,8 +8,19 @@
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
+use std::io::Write;
 use std::time::Duration;
 
+/// Progress output is best effort: a closed pipe must not fail verification
+macro_rules! progress_line {
+    ($out:expr, $($arg:tt)*) => {{ let _ = writeln!($out, $($arg)*); }};
+}
+
+/// Progress item awaiting its result on the same line
+macro_rules! progress_item {
+    ($out:expr, $($arg:tt)*) => {{ let _ = write!($out, $($arg)*); let _ = $out.flush(); }};
+}
+
 // ============================================================================
 // Verification Results
 // ============================================================================
@@ -131,7 +142,7 @@ pub fn verify_usgs_station(
                                     .get("variable")
                                     .and_then(|v| v.get("variableCode"))
                                     .and_then(|vc| vc.as_array())
-                                    .and_then(|arr| arr.get(0))
+                                    .and_then(|arr| arr.first())
                                     .and_then(|code| code.get("value"))
                                     .and_then(|val| val.as_str())
                                 {
@@ -169,15 +180,11 @@ pub fn verify_usgs_station(
     }
 
     // Test 2: Check peak flow availability
-    let peak_url = format!(
-        "https://nwis.waterdata.usgs.gov/nwis/peak?site_no={}&agency_cd=USGS&format=rdb",
-        site_code
-    );
+    let peak_url = crate::ingest::usgs_peaks::peaks_url(site_code);
     
-    if let Ok(response) = client.get(&peak_url).timeout(Duration::from_secs(10)).send() {
-        if response.status().is_success() {
-            result.peak_flow_available = true;
-        }
+    if let Ok(response) = client.get(&peak_url).timeout(Duration::from_secs(10)).send()
+        && response.status().is_success() {
+        result.peak_flow_available = true;
     }
 
     // Determine missing parameters
@@ -234,24 +241,15 @@ pub fn verify_cwms_location(
                     let end = Utc::now();
                     let begin = end - chrono::Duration::hours(24);
                     
-                    let data_url = format!(
-                        "https://cwms-data.usace.army.mil/cwms-data/timeseries?name={}&office={}&begin={}&end={}",
-                        first_ts,
-                        office,
-                        begin.format("%Y-%m-%dT%H:%M:%S"),
-                        end.format("%Y-%m-%dT%H:%M:%S")
-                    );
-
-                    if let Ok(response) = client.get(&data_url).timeout(Duration::from_secs(10)).send() {
-                        if response.status().is_success() {
-                            if let Ok(ts_data) = response.json::<crate::ingest::cwms::CwmsTimeseriesResponse>() {
-                                if let Some(values) = ts_data.values {
-                                    result.sample_data_count = values.len();
-                                    if result.sample_data_count > 0 {
-                                        result.sample_data_available = true;
-                                    }
-                                }
-                            }
+                    let data_url = crate::ingest::cwms::timeseries_url(first_ts, office, begin, end);
+
+                    if let Ok(response) = client.get(&data_url).timeout(Duration::from_secs(10)).send()
+                        && response.status().is_success()
+                        && let Ok(ts_data) = response.json::<crate::ingest::cwms::CwmsTimeseriesResponse>()
+                        && let Some(values) = ts_data.values {
+                        result.sample_data_count = values.len();
+                        if result.sample_data_count > 0 {
+                            result.sample_data_available = true;
                         }
                     }
                 }
@@ -338,6 +336,14 @@ pub fn verify_asos_station(
 // ============================================================================
 
 pub fn run_full_verification() -> Result<VerificationReport, Box<dyn Error>> {
+    run_verification(&mut std::io::stdout())
+}
+
+/// Run all verifications, writing progress lines to `progress`.
+///
+/// `flomon verify --json` sends progress to stderr so stdout carries only
+/// the report.
+pub fn run_verification(progress: &mut dyn Write) -> Result<VerificationReport, Box<dyn Error>> {
     let client = reqwest::blocking::Client::builder()
         .timeout(Duration::from_secs(30))
         .build()?;
@@ -361,12 +367,12 @@ pub fn run_full_verification() -> Result<VerificationReport, Box<dyn Error>> {
     };
 
     // Load and verify USGS stations
-    println!("🔍 Verifying USGS stations...");
+    progress_line!(progress, "🔍 Verifying USGS stations...");
     let usgs_stations = crate::stations::load_stations();
     report.summary.usgs_total = usgs_stations.len();
     
     for station in usgs_stations {
-        print!("  {} ... ", station.site_code);
+        progress_item!(progress, "  {} ... ", station.site_code);
         let result = verify_usgs_station(
             &client,
             &station.site_code,
@@ -376,15 +382,15 @@ pub fn run_full_verification() -> Result<VerificationReport, Box<dyn Error>> {
         
         match result.status {
             VerificationStatus::Success => {
-                println!("✓ OK ({} readings)", result.sample_data_count);
+                progress_line!(progress, "✓ OK ({} readings)", result.sample_data_count);
                 report.summary.usgs_working += 1;
             }
             VerificationStatus::PartialSuccess => {
-                println!("⚠ Partial (missing: {:?})", result.parameters_missing);
+                progress_line!(progress, "⚠ Partial (missing: {:?})", result.parameters_missing);
                 report.summary.usgs_working += 1;
             }
             VerificationStatus::Failed => {
-                println!("✗ FAILED: {}", result.error_message.as_deref().unwrap_or("Unknown"));
+                progress_line!(progress, "✗ FAILED: {}", result.error_message.as_deref().unwrap_or("Unknown"));
                 report.summary.usgs_failed += 1;
             }
         }
@@ -393,13 +399,13 @@ pub fn run_full_verification() -> Result<VerificationReport, Box<dyn Error>> {
     }
 
     // Load and verify CWMS locations
-    println!("\n🔍 Verifying CWMS locations...");
+    progress_line!(progress, "\n🔍 Verifying CWMS locations...");
     match crate::usace_locations::load_locations() {
         Ok(cwms_locations) => {
             report.summary.cwms_total = cwms_locations.len();
             
             for location in cwms_locations {
-                print!("  {} ... ", location.name);
+                progress_item!(progress, "  {} ... ", location.name);
                 let result = verify_cwms_location(
                     &client,
                     &location.name,
@@ -409,17 +415,17 @@ pub fn run_full_verification() -> Result<VerificationReport, Box<dyn Error>> {
                 
                 match result.status {
                     VerificationStatus::Success => {
-                        println!("✓ OK ({} timeseries, {} data points)", 
+                        progress_line!(progress, "✓ OK ({} timeseries, {} data points)", 
                             result.timeseries_discovered.len(), result.sample_data_count);
                         report.summary.cwms_working += 1;
                     }
                     VerificationStatus::PartialSuccess => {
-                        println!("⚠ Catalog found but no data ({} timeseries)", 
+                        progress_line!(progress, "⚠ Catalog found but no data ({} timeseries)", 
                             result.timeseries_discovered.len());
                         report.summary.cwms_working += 1;
                     }
                     VerificationStatus::Failed => {
-                        println!("✗ FAILED: {}", result.error_message.as_deref().unwrap_or("Unknown"));
+                        progress_line!(progress, "✗ FAILED: {}", result.error_message.as_deref().unwrap_or("Unknown"));
                         report.summary.cwms_failed += 1;
                     }
                 }
@@ -428,18 +434,18 @@ pub fn run_full_verification() -> Result<VerificationReport, Box<dyn Error>> {
             }
         }
         Err(e) => {
-            println!("⚠ Warning: Could not load CWMS configuration: {}", e);
+            progress_line!(progress, "⚠ Warning: Could not load CWMS configuration: {}", e);
         }
     }
 
     // Load and verify ASOS stations
-    println!("\n🔍 Verifying ASOS stations...");
+    progress_line!(progress, "\n🔍 Verifying ASOS stations...");
     match crate::asos_locations::load_locations("./iem_asos.toml") {
         Ok(asos_stations) => {
             report.summary.asos_total = asos_stations.len();
             
             for station in asos_stations {
-                print!("  {} ... ", station.station_id);
+                progress_item!(progress, "  {} ... ", station.station_id);
                 let result = verify_asos_station(
                     &client,
                     &station.station_id,
@@ -448,15 +454,15 @@ pub fn run_full_verification() -> Result<VerificationReport, Box<dyn Error>> {
                 
                 match result.status {
                     VerificationStatus::Success => {
-                        println!("✓ OK ({} observations)", result.sample_data_count);
+                        progress_line!(progress, "✓ OK ({} observations)", result.sample_data_count);
                         report.summary.asos_working += 1;
                     }
                     VerificationStatus::PartialSuccess => {
-                        println!("⚠ Responsive but no data");
+                        progress_line!(progress, "⚠ Responsive but no data");
                         report.summary.asos_working += 1;
                     }
                     VerificationStatus::Failed => {
-                        println!("✗ FAILED: {}", result.error_message.as_deref().unwrap_or("Unknown"));
+                        progress_line!(progress, "✗ FAILED: {}", result.error_message.as_deref().unwrap_or("Unknown"));
                         report.summary.asos_failed += 1;
                     }
                 }
@@ -465,7 +471,7 @@ pub fn run_full_verification() -> Result<VerificationReport, Box<dyn Error>> {
             }
         }
         Err(e) => {
-            println!("⚠ Warning: Could not load ASOS configuration: {}", e);
+            progress_line!(progress, "⚠ Warning: Could not load ASOS configuration: {}", e);
         }
     }
 
@@ -1,7 +1,10 @@
-/// Zone-based sensor grouping from zones.toml
-///
-/// Organizes sensors into hydrologically meaningful geographic zones
-/// with lead times and flood forecasting context.
+//! Zone-based sensor grouping from zones.toml
+//!
+//! Organizes sensors into hydrologically meaningful geographic zones
+//! with lead times and flood forecasting context. `status` rolls each
+//! zone's sensors up into one per-cycle state.
+
+pub mod status;
 
 use serde::Deserialize;
 use std::fs;
@@ -12,13 +15,13 @@ use std::path::Path;
 // ============================================================================
 
 /// Root zones configuration
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 pub struct ZonesConfig {
     pub zones: ZoneCollection,
 }
 
 /// Collection of all zones
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 pub struct ZoneCollection {
     pub zone_0: Zone,
     pub zone_1: Zone,
@@ -171,8 +174,24 @@ pub fn get_all_zones(config: &ZonesConfig) -> Vec<(usize, &Zone)> {
     ]
 }
 
+/// Target pool elevation (ft NGVD29) of the sensor with `cwms_location`
+pub fn pool_target(config: &ZonesConfig, cwms_location: &str) -> Option<f64> {
+    get_all_zones(config).into_iter()
+        .flat_map(|(_, zone)| zone.sensors.iter())
+        .filter(|s| s.cwms_location.as_deref() == Some(cwms_location))
+        .find_map(|s| s.pool_target_ft_ngvd29)
+}
+
+/// Zones with a sensor on USGS site `site_code`
+pub fn zones_for_site(config: &ZonesConfig, site_code: &str) -> Vec<usize> {
+    get_all_zones(config).into_iter()
+        .filter(|(_, zone)| zone.sensors.iter().any(|s| s.usgs_id.as_deref() == Some(site_code)))
+        .map(|(id, _)| id)
+        .collect()
+}
+
 /// Get a specific zone by ID
-pub fn get_zone<'a>(config: &'a ZonesConfig, zone_id: usize) -> Option<&'a Zone> {
+pub fn get_zone(config: &ZonesConfig, zone_id: usize) -> Option<&Zone> {
     match zone_id {
         0 => Some(&config.zones.zone_0),
         1 => Some(&config.zones.zone_1),
@@ -299,4 +318,14 @@ mod tests {
         assert!(sensor.is_usgs());
         assert!(!sensor.is_cwms());
     }
+
+    #[test]
+    fn test_zones_for_site() {
+        let config = load_zones_default().unwrap();
+        let zones = zones_for_site(&config, "05568500");
+        assert!(!zones.is_empty());
+        assert!(zones.iter().all(|&id| get_zone(&config, id).unwrap().sensors.iter()
+            .any(|s| s.usgs_id.as_deref() == Some("05568500"))));
+        assert!(zones_for_site(&config, "00000000").is_empty());
+    }
 }
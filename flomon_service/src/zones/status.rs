@@ -0,0 +1,382 @@
+//! Per-zone status rollup.
+//!
+//! Each cycle the daemon reduces a zone's sensors (zones.toml) to one
+//! [`ZoneStatus`]:
+//!
+//! - severity: the worst current severity among its USGS stations, as the
+//!   alert layer tracks it (`alert::transitions`); stale stations don't count
+//! - rise: the fastest stage rise among its USGS stations over the last
+//!   `RISE_WINDOW_HOURS`
+//! - staleness: which of its USGS, CWMS and ASOS sensors have nothing newer
+//!   than `STALE_AFTER_MINUTES`
+//! - precipitation: the mean and wettest total of its ASOS stations for
+//!   each rollup window (`analysis::precip`)
+//!
+//! Rows go to `flood_analysis.zone_status` (sql/044_zone_status.sql);
+//! `GET /api/zones/{id}/status` serves the latest one.
+//!
+//! zones.toml names sensors the way the agencies' station pages do, which
+//! is not always how the feeds key them: ASOS "PIA" reports as "KPIA", and
+//! a CWMS "Peoria-TW" sensor's values are stored under location "Peoria"
+//! (`Peoria.Elev-Tailwater...`). [`CycleState::observed`] accepts both.
+
+use chrono::{DateTime, Utc};
+use postgres::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::alert::thresholds::FloodSeverity;
+use crate::analysis::precip::{PrecipRollup, RollupScope};
+use crate::zones::{Sensor, Zone};
+
+/// Window the stage rise rate is measured over, hours
+pub const RISE_WINDOW_HOURS: i64 = 3;
+
+/// Age past which a sensor counts as stale. Longer than the stations'
+/// own staleness limit: hourly CWMS and ASOS values routinely arrive most
+/// of an hour after they were taken.
+pub const STALE_AFTER_MINUTES: i64 = 120;
+
+/// What the daemon knows about every sensor at the end of a cycle
+#[derive(Debug, Clone, Default)]
+pub struct CycleState {
+    /// Latest reading time per USGS site
+    pub usgs_observed: HashMap<String, DateTime<Utc>>,
+    /// Latest value time per CWMS location ID
+    pub cwms_observed: HashMap<String, DateTime<Utc>>,
+    /// Latest observation time per ASOS station ID ("KPIA")
+    pub asos_observed: HashMap<String, DateTime<Utc>>,
+    /// Current severity per USGS site; sites below action stage are absent
+    pub severities: HashMap<String, FloodSeverity>,
+    /// Stage rise rate per USGS site over `RISE_WINDOW_HOURS`, ft/hour
+    pub rise_rates: HashMap<String, f64>,
+    /// Station precipitation rollups ending this cycle
+    pub precip: Vec<PrecipRollup>,
+}
+
+impl CycleState {
+    /// Latest observation of `sensor` from whichever feed it is on
+    pub fn observed(&self, sensor: &Sensor) -> Option<DateTime<Utc>> {
+        if let Some(site) = sensor.usgs_id.as_deref() {
+            return self.usgs_observed.get(site).copied();
+        }
+        if let Some(location) = sensor.cwms_location.as_deref() {
+            return self.cwms_observed.iter()
+                .filter(|(id, _)| cwms_matches(location, id))
+                .map(|(_, t)| *t)
+                .max();
+        }
+        if sensor.is_asos() {
+            let station = sensor.station_id.as_deref()?;
+            return self.asos_observed.iter()
+                .filter(|(id, _)| asos_matches(station, id))
+                .map(|(_, t)| *t)
+                .max();
+        }
+        None
+    }
+}
+
+/// Whether stored CWMS location `location_id` holds values of the zone
+/// sensor `location` ("Peoria-TW" is stored under "Peoria")
+fn cwms_matches(location: &str, location_id: &str) -> bool {
+    location == location_id
+        || location.strip_prefix(location_id).is_some_and(|rest| rest.starts_with('-'))
+}
+
+/// Whether ASOS station `station_id` ("KPIA") is zone sensor `station` ("PIA")
+fn asos_matches(station: &str, station_id: &str) -> bool {
+    station_id == station || station_id.strip_prefix('K') == Some(station)
+}
+
+/// Precipitation over one rollup window across a zone's ASOS stations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZonePrecip {
+    pub window_hours: i64,
+    /// Mean of the station totals, in
+    pub mean_in: f64,
+    /// Wettest station total, in
+    pub max_in: f64,
+    pub station_count: usize,
+}
+
+/// One zone's state at the end of a cycle
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoneStatus {
+    pub zone_id: usize,
+    pub computed_at: DateTime<Utc>,
+    /// Worst severity among the zone's fresh USGS stations
+    pub severity: Option<FloodSeverity>,
+    pub severity_site: Option<String>,
+    /// Fastest stage rise among the zone's fresh USGS stations, ft/hour
+    /// (negative when every station is falling)
+    pub max_rise_ft_per_hour: Option<f64>,
+    pub max_rise_site: Option<String>,
+    /// USGS, CWMS and ASOS sensors in the zone
+    pub sensor_count: usize,
+    /// Of those, the ones with nothing newer than `STALE_AFTER_MINUTES`
+    pub stale_sensors: Vec<String>,
+    /// Only windows some station has data for
+    pub precip: Vec<ZonePrecip>,
+}
+
+impl ZoneStatus {
+    pub fn reporting_count(&self) -> usize {
+        self.sensor_count - self.stale_sensors.len()
+    }
+
+    /// Whether no sensor in the zone is reporting
+    pub fn all_stale(&self) -> bool {
+        self.sensor_count > 0 && self.stale_sensors.len() == self.sensor_count
+    }
+
+    pub fn precip_for(&self, window_hours: i64) -> Option<&ZonePrecip> {
+        self.precip.iter().find(|p| p.window_hours == window_hours)
+    }
+}
+
+/// Stage rise over `readings` (time, stage ft) in ft/hour, from the
+/// first to the last reading; None unless they span at least 15 minutes
+pub fn rise_rate(readings: &[(DateTime<Utc>, f64)]) -> Option<f64> {
+    let first = readings.iter().min_by_key(|(t, _)| *t)?;
+    let last = readings.iter().max_by_key(|(t, _)| *t)?;
+    let minutes = (last.0 - first.0).num_minutes();
+    (minutes >= 15).then(|| (last.1 - first.1) / (minutes as f64 / 60.0))
+}
+
+/// Roll `zone`'s sensors up into its status at `now`
+pub fn rollup(zone_id: usize, zone: &Zone, state: &CycleState, now: DateTime<Utc>) -> ZoneStatus {
+    let fresh = |sensor: &Sensor| state.observed(sensor)
+        .is_some_and(|t| (now - t).num_minutes() <= STALE_AFTER_MINUTES);
+
+    let monitored: Vec<&Sensor> = zone.sensors.iter()
+        .filter(|s| s.is_usgs() || s.cwms_location.is_some() || s.is_asos())
+        .collect();
+    let mut stale_sensors: Vec<String> = monitored.iter()
+        .filter(|s| !fresh(s))
+        .map(|s| s.primary_id())
+        .collect();
+    stale_sensors.sort();
+    stale_sensors.dedup();
+
+    let mut fresh_sites: Vec<&str> = monitored.iter()
+        .filter(|s| fresh(s))
+        .filter_map(|s| s.usgs_id.as_deref())
+        .collect();
+    fresh_sites.sort_unstable();
+    fresh_sites.dedup();
+
+    let worst = fresh_sites.iter()
+        .filter_map(|site| state.severities.get(*site).map(|severity| (*site, severity)))
+        .max_by(|a, b| a.1.cmp(b.1));
+    let fastest = fresh_sites.iter()
+        .filter_map(|site| state.rise_rates.get(*site).map(|rate| (*site, *rate)))
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+
+    let mut windows: Vec<i64> = state.precip.iter().map(|r| r.window_hours).collect();
+    windows.sort_unstable();
+    windows.dedup();
+    let stations: Vec<&str> = zone.asos_sensors().iter().filter_map(|s| s.station_id.as_deref()).collect();
+    let precip = windows.into_iter()
+        .filter_map(|window_hours| {
+            let totals: Vec<f64> = state.precip.iter()
+                .filter(|r| r.scope == RollupScope::Station && r.window_hours == window_hours)
+                .filter(|r| stations.iter().any(|s| asos_matches(s, &r.scope_id)))
+                .map(|r| r.total_in)
+                .collect();
+            (!totals.is_empty()).then(|| ZonePrecip {
+                window_hours,
+                mean_in: totals.iter().sum::<f64>() / totals.len() as f64,
+                max_in: totals.iter().copied().fold(f64::MIN, f64::max),
+                station_count: totals.len(),
+            })
+        })
+        .collect();
+
+    ZoneStatus {
+        zone_id,
+        computed_at: now,
+        severity: worst.map(|(_, severity)| severity.clone()),
+        severity_site: worst.map(|(site, _)| site.to_string()),
+        max_rise_ft_per_hour: fastest.map(|(_, rate)| rate),
+        max_rise_site: fastest.map(|(site, _)| site.to_string()),
+        sensor_count: monitored.len(),
+        stale_sensors,
+        precip,
+    }
+}
+
+/// Write a status to `flood_analysis.zone_status`
+pub fn store(client: &mut Client, status: &ZoneStatus) -> Result<usize, String> {
+    let precip = serde_json::to_string(&status.precip).map_err(|e| e.to_string())?;
+    client.execute(
+        "INSERT INTO flood_analysis.zone_status
+         (zone_id, computed_at, severity, severity_site, max_rise_ft_per_hour, max_rise_site,
+          sensor_count, stale_sensors, precip)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9::TEXT::JSONB)
+         ON CONFLICT (zone_id, computed_at) DO NOTHING",
+        &[
+            &(status.zone_id as i16),
+            &status.computed_at,
+            &status.severity.as_ref().map(|s| s.as_str()),
+            &status.severity_site,
+            &status.max_rise_ft_per_hour,
+            &status.max_rise_site,
+            &(status.sensor_count as i32),
+            &status.stale_sensors,
+            &precip,
+        ],
+    ).map(|n| n as usize)
+        .map_err(|e| format!("Failed to store zone {} status: {}", status.zone_id, e))
+}
+
+/// Most recent stored status of a zone
+pub fn latest(client: &mut Client, zone_id: usize) -> Result<Option<ZoneStatus>, String> {
+    let row = client.query_opt(
+        "SELECT computed_at, severity, severity_site, max_rise_ft_per_hour, max_rise_site,
+                sensor_count, stale_sensors, precip::TEXT
+         FROM flood_analysis.zone_status
+         WHERE zone_id = $1
+         ORDER BY computed_at DESC LIMIT 1",
+        &[&(zone_id as i16)],
+    ).map_err(|e| format!("Failed to load zone {} status: {}", zone_id, e))?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let severity: Option<String> = row.get(1);
+    let precip: String = row.get(7);
+    Ok(Some(ZoneStatus {
+        zone_id,
+        computed_at: row.get(0),
+        severity: severity.as_deref().and_then(FloodSeverity::parse),
+        severity_site: row.get(2),
+        max_rise_ft_per_hour: row.get(3),
+        max_rise_site: row.get(4),
+        sensor_count: row.get::<_, i32>(5) as usize,
+        stale_sensors: row.get(6),
+        precip: serde_json::from_str(&precip)
+            .map_err(|e| format!("Zone {} status has unreadable precip: {}", zone_id, e))?,
+    }))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn sensor(usgs: Option<&str>, cwms: Option<&str>, asos: Option<&str>) -> Sensor {
+        Sensor {
+            sensor_id: None,
+            usgs_id: usgs.map(str::to_string),
+            station_id: asos.map(str::to_string),
+            cwms_location: cwms.map(str::to_string),
+            shef_id: None,
+            source: if asos.is_some() { "IEM/ASOS" } else if cwms.is_some() { "USACE/MVR" } else { "USGS" }.to_string(),
+            sensor_type: "stage".to_string(),
+            role: "direct".to_string(),
+            location: "Test".to_string(),
+            lat: 40.7,
+            lon: -89.6,
+            relevance: "Test sensor".to_string(),
+            pool_target_ft_ngvd29: None,
+            flood_stage_ft: None,
+            action_stage_ft: None,
+            moderate_flood_ft: None,
+            major_flood_ft: None,
+            datum_note: None,
+        }
+    }
+
+    fn zone() -> Zone {
+        Zone {
+            name: "Upper Peoria Lake".to_string(),
+            description: "Test zone".to_string(),
+            sensors: vec![
+                sensor(Some("05567500"), None, None),
+                sensor(Some("05568500"), None, None),
+                sensor(Some("05568580"), None, None),
+                sensor(None, Some("Peoria-TW"), None),
+                sensor(None, None, Some("PIA")),
+                sensor(None, None, Some("BMI")),
+            ],
+        }
+    }
+
+    fn rollup_24h(station: &str, total_in: f64, now: DateTime<Utc>) -> PrecipRollup {
+        PrecipRollup {
+            scope: RollupScope::Station,
+            scope_id: station.to_string(),
+            window_hours: 24,
+            window_end: now,
+            total_in,
+            max_in: total_in,
+            sample_count: 20,
+        }
+    }
+
+    #[test]
+    fn test_rollup_takes_worst_fresh_severity_and_fastest_rise() {
+        let now = Utc.with_ymd_and_hms(2024, 4, 20, 12, 0, 0).unwrap();
+        let recent = now - Duration::minutes(20);
+        let state = CycleState {
+            usgs_observed: HashMap::from([
+                ("05567500".to_string(), recent),
+                ("05568500".to_string(), recent),
+                // Major but five hours old: reported stale, not as the zone's severity
+                ("05568580".to_string(), now - Duration::hours(5)),
+            ]),
+            cwms_observed: HashMap::from([("Peoria".to_string(), now - Duration::minutes(70))]),
+            asos_observed: HashMap::from([("KPIA".to_string(), recent)]),
+            severities: HashMap::from([
+                ("05567500".to_string(), FloodSeverity::Action),
+                ("05568500".to_string(), FloodSeverity::Flood),
+                ("05568580".to_string(), FloodSeverity::Major),
+            ]),
+            rise_rates: HashMap::from([
+                ("05567500".to_string(), 0.4),
+                ("05568500".to_string(), 0.1),
+                ("05568580".to_string(), 2.0),
+            ]),
+            precip: vec![rollup_24h("KPIA", 1.2, now), rollup_24h("KBMI", 0.4, now), rollup_24h("KSPI", 3.0, now)],
+        };
+
+        let status = rollup(2, &zone(), &state, now);
+        assert_eq!(status.severity, Some(FloodSeverity::Flood));
+        assert_eq!(status.severity_site.as_deref(), Some("05568500"));
+        assert_eq!(status.max_rise_ft_per_hour, Some(0.4));
+        assert_eq!(status.max_rise_site.as_deref(), Some("05567500"));
+        assert_eq!(status.sensor_count, 6);
+        assert_eq!(status.stale_sensors, vec!["05568580".to_string(), "BMI".to_string()]);
+        assert_eq!(status.reporting_count(), 4);
+        assert!(!status.all_stale());
+
+        // KSPI is not in the zone
+        let day = status.precip_for(24).unwrap();
+        assert_eq!(day.station_count, 2);
+        assert!((day.mean_in - 0.8).abs() < 1e-9);
+        assert!((day.max_in - 1.2).abs() < 1e-9);
+        assert!(status.precip_for(1).is_none());
+
+        let silent = rollup(2, &zone(), &CycleState::default(), now);
+        assert!(silent.all_stale());
+        assert_eq!(silent.severity, None);
+        assert!(silent.precip.is_empty());
+    }
+
+    #[test]
+    fn test_rise_rate_needs_a_quarter_hour() {
+        let t0 = Utc.with_ymd_and_hms(2024, 4, 20, 9, 0, 0).unwrap();
+        let readings = vec![(t0 + Duration::hours(2), 19.0), (t0, 18.0), (t0 + Duration::hours(1), 18.6)];
+        assert!((rise_rate(&readings).unwrap() - 0.5).abs() < 1e-9);
+        assert_eq!(rise_rate(&readings[..1]), None);
+        assert_eq!(rise_rate(&[(t0, 18.0), (t0 + Duration::minutes(10), 18.2)]), None);
+
+        assert!(cwms_matches("Starved-Rock-Pool", "Starved-Rock"));
+        assert!(!cwms_matches("Peoria-Pool", "Peoria-TW"));
+        assert!(asos_matches("PIA", "KPIA") && !asos_matches("PIA", "KPIB"));
+    }
+}
@@ -0,0 +1,296 @@
+//! Historical event replay regression suite
+//!
+//! Replays canonical recorded events through the same steps the daemon uses
+//! (USGS/CWMS parsing, flood stage evaluation, staleness checks, backwater
+//! detection) and asserts on the exact sequence of alerts and projections.
+//! A refactor that changes alert behavior for any of these events fails here.
+//!
+//! Fixtures live in tests/data/events/ and are reconstructions of:
+//! 1. April 2013 record flood (Henry, Peoria, Kingston Mines; 3-hourly)
+//! 2. A flash Mackinaw River rise feeding Kingston Mines (hourly)
+//! 3. June 2019 Mississippi backwater at LaGrange (CWMS, 6-hourly)
+//! 4. A Peoria sensor outage with a sentinel on restoration (15-minute)
+//!
+//! No network or database required:
+//!   cargo test --test event_replay
+//!
+//! When a change intentionally alters alert behavior, update the expected
+//! sequences below in the same commit so the diff documents the change.
+
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use flomon_service::alert::stalenesses::is_stale_at;
+use flomon_service::alert::thresholds::{check_station, FloodSeverity};
+use flomon_service::ingest::{cwms, usgs};
+use flomon_service::model::GaugeReading;
+use flomon_service::stations::{self, Station};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+const STAGE_PARAM: &str = "00065";
+
+/// Tolerance for "projection matched observation" checks
+const PROJECTION_TOLERANCE_HOURS: i64 = 24;
+
+// ---------------------------------------------------------------------------
+// Replay harness
+// ---------------------------------------------------------------------------
+
+fn load_fixture(name: &str) -> String {
+    let path = format!("{}/tests/data/events/{}", env!("CARGO_MANIFEST_DIR"), name);
+    fs::read_to_string(&path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path, e))
+}
+
+fn reading_time(reading: &GaugeReading) -> DateTime<Utc> {
+    reading.datetime.with_timezone(&Utc)
+}
+
+/// Local (CDT) timestamp label used in the expected sequences
+fn label(t: DateTime<Utc>) -> String {
+    let cdt = FixedOffset::west_opt(5 * 3600).unwrap();
+    t.with_timezone(&cdt).format("%Y-%m-%d %H:%M").to_string()
+}
+
+fn severity_label(severity: &Option<FloodSeverity>) -> &'static str {
+    match severity {
+        None => "below action",
+        Some(FloodSeverity::Action) => "Action",
+        Some(FloodSeverity::Flood) => "Flood",
+        Some(FloodSeverity::Moderate) => "Moderate",
+        Some(FloodSeverity::Major) => "Major",
+    }
+}
+
+fn at_or_above_flood(severity: &Option<FloodSeverity>) -> bool {
+    matches!(
+        severity,
+        Some(FloodSeverity::Flood | FloodSeverity::Moderate | FloodSeverity::Major)
+    )
+}
+
+/// Per-site state carried between replay ticks
+#[derive(Default)]
+struct SiteState {
+    severity: Option<FloodSeverity>,
+    stale: bool,
+}
+
+/// Parse a USGS IV fixture and keep stage readings in time order
+fn stage_readings(fixture: &str) -> Vec<GaugeReading> {
+    let mut readings: Vec<GaugeReading> = usgs::parse_iv_response_all(&load_fixture(fixture))
+        .expect("fixture parses")
+        .into_iter()
+        .filter(|r| r.parameter_code == STAGE_PARAM)
+        .collect();
+    readings.sort_by_key(reading_time);
+    readings
+}
+
+/// Step a clock across the readings and log every alert transition.
+///
+/// At each tick, every site is evaluated on its latest reading at or before
+/// the tick. Logged events:
+/// - severity changes from `check_station` against usgs_stations.toml
+/// - for upstream stations first reaching flood stage, the projected
+///   arrival at Peoria (tick + `travel_time_to_peoria_hours`)
+/// - STALE / FRESH transitions when `staleness_minutes` is set
+fn replay_gauges(
+    readings: &[GaugeReading],
+    tick: Duration,
+    staleness_minutes: Option<u64>,
+) -> Vec<String> {
+    let registry: HashMap<String, Station> = stations::load_stations_map();
+    let start = reading_time(readings.first().expect("fixture has readings"));
+    let end = reading_time(readings.last().unwrap());
+
+    let mut states: BTreeMap<&str, SiteState> = BTreeMap::new();
+    let mut log = Vec::new();
+
+    let mut now = start;
+    while now <= end {
+        let mut latest: BTreeMap<&str, &GaugeReading> = BTreeMap::new();
+        for reading in readings.iter().take_while(|r| reading_time(r) <= now) {
+            latest.insert(reading.site_code.as_str(), reading);
+        }
+
+        for (site, reading) in latest {
+            let station = registry.get(site).expect("fixture site is in usgs_stations.toml");
+            let state = states.entry(site).or_default();
+
+            let severity = check_station(station, reading).map(|alert| alert.severity);
+
+            if severity != state.severity {
+                log.push(format!("{} {} {}", label(now), site, severity_label(&severity)));
+
+                if station.travel_time_to_peoria_hours > 0.0
+                    && at_or_above_flood(&severity)
+                    && !at_or_above_flood(&state.severity)
+                {
+                    let arrival = now + Duration::minutes((station.travel_time_to_peoria_hours * 60.0) as i64);
+                    log.push(format!("{} {} projects Peoria {}", label(now), site, label(arrival)));
+                }
+                state.severity = severity;
+            }
+
+            if let Some(max_age) = staleness_minutes {
+                // Parse failures count as stale (fail-safe)
+                let stale = is_stale_at(reading, max_age, now);
+                if stale != state.stale {
+                    log.push(format!("{} {} {}", label(now), site, if stale { "STALE" } else { "FRESH" }));
+                    state.stale = stale;
+                }
+            }
+        }
+
+        now += tick;
+    }
+
+    log
+}
+
+/// Replay LaGrange pool/tailwater and Grafton stage from a CWMS fixture.
+///
+/// Logs loss/restoration of hydraulic control at LaGrange and changes in
+/// backwater severity (tailwater minus pool).
+fn replay_backwater(fixture: &str) -> Vec<String> {
+    let responses: Vec<cwms::CwmsTimeseriesResponse> =
+        serde_json::from_str(&load_fixture(fixture)).expect("fixture parses");
+
+    let series = |prefix: &str| -> BTreeMap<i64, f64> {
+        responses.iter()
+            .find(|r| r.name.starts_with(prefix))
+            .unwrap_or_else(|| panic!("fixture missing {}", prefix))
+            .values.as_ref().unwrap()
+            .iter()
+            .map(|v| (v.date_time, v.value))
+            .collect()
+    };
+    let grafton = series("Grafton.Stage");
+    let pool = series("LaGrange-Pool.Elev");
+    let tailwater = series("LaGrange-TW.Elev");
+
+    let mut control_lost = false;
+    let mut severity = "none";
+    let mut log = Vec::new();
+
+    for (ms, pool_ft) in &pool {
+        let tw_ft = tailwater[ms];
+        let now = DateTime::from_timestamp(ms / 1000, 0).unwrap();
+
+        let lost = cwms::detect_hydraulic_control_loss(*pool_ft, tw_ft, 0.5);
+        if lost != control_lost {
+            log.push(format!(
+                "{} LaGrange control {} (Grafton {:.1} ft)",
+                label(now), if lost { "LOST" } else { "RESTORED" }, grafton[ms]
+            ));
+            control_lost = lost;
+        }
+
+        let current = cwms::classify_backwater_severity(tw_ft - pool_ft);
+        if current != severity {
+            log.push(format!("{} backwater {}", label(now), current));
+            severity = current;
+        }
+    }
+
+    log
+}
+
+// ---------------------------------------------------------------------------
+// Events
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_2013_record_flood_alert_sequence() {
+    let readings = stage_readings("flood_2013_record.json");
+    let log = replay_gauges(&readings, Duration::hours(3), None);
+
+    assert_eq!(log, vec![
+        "2013-04-18 15:00 05557000 Action",
+        "2013-04-19 03:00 05557000 Flood",
+        "2013-04-19 03:00 05557000 projects Peoria 2013-04-19 21:00",
+        "2013-04-19 12:00 05568500 Action",
+        "2013-04-19 18:00 05557000 Moderate",
+        // Peoria pool alerts on deviation above the 447.0 ft target pool
+        "2013-04-19 21:00 05567500 Action",
+        "2013-04-20 06:00 05557000 Major",
+        "2013-04-20 06:00 05567500 Flood",
+        "2013-04-20 06:00 05568500 Flood",
+        "2013-04-20 15:00 05567500 Moderate",
+        "2013-04-21 03:00 05567500 Major",
+        "2013-04-21 09:00 05568500 Moderate",
+        "2013-04-22 15:00 05568500 Major",
+        "2013-04-24 18:00 05557000 Moderate",
+        "2013-04-25 09:00 05568500 Moderate",
+        "2013-04-26 06:00 05557000 Flood",
+        "2013-04-27 15:00 05567500 Moderate",
+        "2013-04-28 00:00 05568500 Flood",
+    ]);
+}
+
+#[test]
+fn test_2013_record_flood_projection_matches_peoria() {
+    let readings = stage_readings("flood_2013_record.json");
+    let log = replay_gauges(&readings, Duration::hours(3), None);
+
+    let parse = |s: &str| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+    let projected = log.iter()
+        .find_map(|e| e.split(" 05557000 projects Peoria ").nth(1))
+        .map(parse)
+        .expect("Henry projects a Peoria arrival");
+    let observed = log.iter()
+        .find(|e| e.ends_with(" 05567500 Flood"))
+        .map(|e| parse(&e[..16]))
+        .expect("Peoria reaches flood stage");
+
+    let error_hours = (projected - observed).num_hours().abs();
+    assert!(
+        error_hours <= PROJECTION_TOLERANCE_HOURS,
+        "Henry projected Peoria flood at {}, observed {} ({}h apart)", projected, observed, error_hours
+    );
+}
+
+#[test]
+fn test_mackinaw_flash_rise_alert_sequence() {
+    let readings = stage_readings("mackinaw_flash_rise.json");
+    let log = replay_gauges(&readings, Duration::hours(1), None);
+
+    // The Mackinaw gauge has no NWS thresholds, so only the downstream
+    // main-stem response at Kingston Mines alerts.
+    assert!(log.iter().all(|e| !e.contains("05568580")));
+    assert_eq!(log, vec![
+        "2013-04-18 12:00 05568500 Action",
+        "2013-04-18 18:00 05568500 Flood",
+        "2013-04-19 14:00 05568500 Action",
+    ]);
+}
+
+#[test]
+fn test_backwater_2019_sequence() {
+    let log = replay_backwater("backwater_2019.json");
+
+    assert_eq!(log, vec![
+        "2019-06-02 19:00 LaGrange control LOST (Grafton 26.8 ft)",
+        "2019-06-03 07:00 backwater minor",
+        "2019-06-04 01:00 backwater moderate",
+        "2019-06-05 07:00 backwater major",
+        "2019-06-08 13:00 backwater moderate",
+        "2019-06-09 19:00 backwater minor",
+        "2019-06-10 13:00 backwater none",
+    ]);
+}
+
+#[test]
+fn test_peoria_sensor_outage_staleness_sequence() {
+    let readings = stage_readings("peoria_sensor_outage.json");
+    let log = replay_gauges(&readings, Duration::minutes(15), Some(60));
+
+    // Last reading before the gap is 03:00; 04:00 is exactly 60 min (not
+    // stale), 04:15 is the first stale tick. The 06:45 sentinel is dropped
+    // by the parser, so freshness returns with the 07:00 reading. The pool
+    // never rises 1 ft above target, so there are no severity alerts.
+
+    assert_eq!(log, vec![
+        "2019-05-01 04:15 05567500 STALE",
+        "2019-05-01 07:00 05567500 FRESH",
+    ]);
+}
@@ -0,0 +1,94 @@
+//! Hermetic ingest tests against the recorded-response fixture server
+//!
+//! Tests verify, without network access:
+//! 1. The poll cycle fetches and parses USGS and ASOS data (`fetch_cycle`)
+//! 2. The blocking CWMS and IEM clients parse recorded responses
+//! 3. Empty and malformed responses surface as errors, not panics or data
+//!
+//! Requires the "testing" feature (see `flomon_service::testing`).
+//!
+//! Run with: cargo test --features testing --test fixture_ingest
+
+use flomon_service::ingest::fetch::{self, CyclePlan, FetchLimits};
+use flomon_service::ingest::{cwms, iem};
+use flomon_service::model::IngestError;
+use flomon_service::stations;
+use flomon_service::testing::{FixtureServer, Scenario};
+
+fn poll(plan: CyclePlan) -> fetch::CycleResults {
+    FixtureServer::shared();
+    fetch::runtime().unwrap().block_on(fetch::fetch_cycle(&plan, FetchLimits::default()))
+}
+
+#[test]
+fn test_poll_cycle_reads_recorded_usgs_responses() {
+    let plan = CyclePlan {
+        usgs_sites: Scenario::ALL.iter().map(|s| s.usgs_site().to_string()).collect(),
+        ..Default::default()
+    };
+    let results = poll(plan);
+
+    let normal = results.usgs[Scenario::Normal.usgs_site()].as_ref().unwrap();
+    assert_eq!(normal.len(), 2, "latest discharge and stage");
+    let stage = normal.iter().find(|r| r.parameter_code == stations::PARAM_STAGE).unwrap();
+    assert_eq!(stage.value, 9.82);
+
+    let flood = results.usgs[Scenario::Flood.usgs_site()].as_ref().unwrap();
+    let peoria = stations::find_station(Scenario::Flood.usgs_site()).unwrap();
+    let flood_stage = peoria.thresholds.as_ref().unwrap().flood_stage_ft;
+    assert!(flood[0].value > flood_stage, "{} ft should be above flood stage {}", flood[0].value, flood_stage);
+
+    let empty = results.usgs[Scenario::Empty.usgs_site()].as_ref().unwrap_err();
+    assert!(matches!(empty, IngestError::NoData(_)), "{}", empty);
+    // The JSON is cut off and the fixture server has no RDB to fall back on
+    assert!(results.usgs[Scenario::Malformed.usgs_site()].is_err());
+}
+
+#[test]
+fn test_poll_cycle_reads_recorded_asos_responses() {
+    let plan = CyclePlan {
+        asos_stations: vec![Scenario::Normal.asos_station().to_string(), Scenario::Flood.asos_station().to_string()],
+        ..Default::default()
+    };
+    let results = poll(plan);
+
+    let normal = results.asos[Scenario::Normal.asos_station()].as_ref().unwrap();
+    assert_eq!(normal.len(), 3);
+    assert_eq!(iem::calculate_cumulative_precip(normal), 0.0);
+    let storm = results.asos[Scenario::Flood.asos_station()].as_ref().unwrap();
+    assert!(iem::detect_rainfall_event(storm, 1.0));
+}
+
+#[test]
+fn test_blocking_clients_read_recorded_cwms_and_iem_responses() {
+    FixtureServer::shared();
+    let client = reqwest::blocking::Client::new();
+
+    let pool = cwms::fetch_recent(&client, Scenario::Normal.cwms_timeseries(), "MVR", 4).unwrap();
+    assert_eq!(pool.len(), 4);
+    assert_eq!(pool[0].location_id, "Peoria-Pool");
+    let grafton = cwms::fetch_recent(&client, Scenario::Flood.cwms_timeseries(), "MVS", 4).unwrap();
+    assert!(grafton.iter().all(|v| v.value > 35.0 && v.quality().is_usable()));
+    assert!(cwms::fetch_recent(&client, Scenario::Empty.cwms_timeseries(), "MVR", 4).unwrap().is_empty());
+    assert!(matches!(
+        cwms::fetch_recent(&client, Scenario::Malformed.cwms_timeseries(), "MVR", 4),
+        Err(IngestError::Parse(_))
+    ));
+
+    assert!(iem::fetch_recent_precip(&client, Scenario::Empty.asos_station(), 4).unwrap().is_empty());
+    assert!(matches!(
+        iem::fetch_recent_precip(&client, Scenario::Malformed.asos_station(), 4),
+        Err(IngestError::Parse(_))
+    ));
+}
+
+#[test]
+fn test_unrecorded_requests_are_not_found() {
+    let fixtures = FixtureServer::shared();
+    let client = reqwest::blocking::Client::new();
+    assert!(matches!(
+        cwms::fetch_recent(&client, "Nowhere.Stage.Inst.15Minutes.0.Ccp-Rev", "MVR", 4),
+        Err(IngestError::Http(404))
+    ));
+    assert!(fixtures.requests_to("/cwms-data/") > 0);
+}
@@ -14,7 +14,7 @@
 /// Run with: cargo test --test peak_flow_integration -- --test-threads=1
 
 use flomon_service::config::load_config;
-use flomon_service::ingest::peak_flow::{
+use flomon_service::ingest::usgs_peaks::{
     parse_rdb, identify_flood_events, FloodThresholds, FloodSeverity,
 };
 
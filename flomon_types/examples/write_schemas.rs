@@ -0,0 +1,20 @@
+//! Regenerate the checked-in JSON Schemas from the `JsonSchema` derives.
+//!
+//! ```text
+//! cargo run --example write_schemas
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("schemas");
+    for (file, schema) in [
+        ("api.schema.json", flomon_types::api_schema()),
+        ("alert-webhook.schema.json", flomon_types::alert_webhook_schema()),
+    ] {
+        let path = dir.join(file);
+        fs::write(&path, schema).unwrap_or_else(|e| panic!("Failed to write {}: {}", path.display(), e));
+        println!("wrote {}", path.display());
+    }
+}
@@ -0,0 +1,174 @@
+//! HTTP API response bodies.
+//!
+//! One struct per endpoint payload, serialized as-is by `flomon serve`.
+//! Field comments list the values a string field can take.
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// List of all zones with metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ZonesListResponse {
+    pub zones: Vec<ZoneListItem>,
+    pub system_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ZoneListItem {
+    pub zone_id: usize,
+    pub name: String,
+    pub lead_time_hours_min: Option<i64>,
+    pub lead_time_hours_max: Option<i64>,
+    pub primary_alert_condition: String,
+    pub sensor_count: usize,
+}
+
+/// Zone detail with all sensor readings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ZoneDetailResponse {
+    pub zone_id: usize,
+    pub zone_name: String,
+    pub description: String,
+    pub metadata: ZoneMetadataResponse,
+    pub sensors: Vec<SensorDetailResponse>,
+    pub zone_status: ZoneStatusResponse,
+    pub last_updated: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ZoneMetadataResponse {
+    pub lead_time_hours_min: Option<i64>,
+    pub lead_time_hours_max: Option<i64>,
+    pub primary_alert_condition: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SensorDetailResponse {
+    pub sensor_id: String,
+    pub sensor_type: String,
+    pub role: String,
+    pub location: String,
+    pub coordinates: CoordinatesResponse,
+    pub source: String,
+    
+    // Current readings
+    pub current_value: Option<f64>,
+    pub current_unit: Option<String>,
+    pub current_timestamp: Option<String>,
+    pub staleness_minutes: Option<i64>,
+    
+    // Thresholds (if applicable)
+    pub flood_stage_ft: Option<f64>,
+    pub action_stage_ft: Option<f64>,
+    
+    // Relevance explanation
+    pub relevance: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CoordinatesResponse {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ZoneStatusResponse {
+    pub alert_level: String,  // "NORMAL", "WATCH", "WARNING", "CRITICAL"
+    pub active_sensors: usize,
+    pub stale_sensors: usize,
+    pub sensors_above_action: Vec<String>,
+    pub sensors_above_flood: Vec<String>,
+}
+
+/// Overall basin status
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BasinStatusResponse {
+    pub overall_status: String,  // "NORMAL", "ELEVATED", "FLOOD_WATCH", "FLOOD_WARNING"
+    pub active_zones: Vec<ActiveZoneStatus>,
+    pub backwater_risk: BackwaterRiskResponse,
+    pub upstream_flood_pulse: UpstreamFloodPulseResponse,
+    pub compound_event_risk: String,  // "LOW", "MODERATE", "HIGH"
+    pub last_updated: DateTime<Utc>,
+    /// Station registry version whose thresholds produced these statuses
+    pub registry_version: Option<i32>,
+}
+
+/// Body of every 4xx/5xx JSON response. Some endpoints add fields (the
+/// 404 lists `available_endpoints`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ApiErrorResponse {
+    pub error: String,
+    pub status: u16,
+    /// Also sent as the `X-Request-Id` header and written to the logs
+    pub request_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ActiveZoneStatus {
+    pub zone_id: usize,
+    pub zone_name: String,
+    pub status: String,
+    pub lead_time_hours: Option<i64>,
+    pub key_sensors_elevated: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BackwaterRiskResponse {
+    pub risk_level: String,  // "LOW", "MODERATE", "HIGH", "CRITICAL"
+    pub grafton_stage_ft: Option<f64>,
+    pub lagrange_pool_ft: Option<f64>,
+    pub lagrange_tailwater_ft: Option<f64>,
+    pub pool_tailwater_differential_ft: Option<f64>,
+    /// Peoria-Kingston Mines water-surface slope (ft/mile), if current
+    pub stage_slope_ft_per_mile: Option<f64>,
+    /// "steepening", "steady" or "flattening"
+    pub stage_slope_trend: Option<String>,
+    /// "LOW", "MEDIUM", "HIGH": how well the slope agrees with pool/tailwater
+    pub confidence: String,
+    pub explanation: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct UpstreamFloodPulseResponse {
+    pub pulse_detected: bool,
+    pub estimated_arrival_hours: Option<i64>,
+    pub source_zones: Vec<usize>,
+    pub explanation: String,
+}
+
+/// Status for the embeddable widget
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct EmbedSummaryResponse {
+    pub site_code: String,
+    pub site_name: String,
+    pub stage_ft: Option<f64>,
+    pub severity: String,      // Severity key: "normal", "action", "flood", "moderate", "major", "stale", "no_data"
+    pub color: String,         // Hex color for the severity
+    pub severity_label: String, // Display name for the severity
+    pub trend: String,         // "rising", "falling", "steady", "unknown"
+    pub trend_arrow: String,
+    pub change_ft: Option<f64>,
+    pub observed_at: Option<DateTime<Utc>>,
+    pub registry_version: Option<i32>,
+}
+
+/// Display name and color for one severity key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SeverityStyle {
+    pub key: String,           // "action", "flood", ...; what other responses carry as `severity`
+    pub label: String,
+    pub color: String,         // Hex color, "#rrggbb"
+}
+
+/// The severity taxonomy every display should use (/api/severity_scheme)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SeveritySchemeResponse {
+    /// Flood levels, least to most severe
+    pub levels: Vec<SeverityStyle>,
+    /// Below the lowest level
+    pub normal: SeverityStyle,
+    /// Latest reading too old to classify
+    pub stale: SeverityStyle,
+    pub no_data: SeverityStyle,
+}
@@ -0,0 +1,129 @@
+//! flomon-types: the JSON shapes flomon exposes to other programs.
+//!
+//! Depend on this crate (serde + chrono only) to consume the flomon HTTP
+//! API or receive its alert webhooks without pulling in the service's
+//! database and HTTP client stack.
+//!
+//! ```text
+//! flomon_types
+//! +-- api     - HTTP API response bodies (/zones, /zone/{id}, /status, /backwater, /api/embed/summary,
+//! |             /api/severity_scheme, errors)
+//! +-- webhook - alert webhook payload
+//! ```
+//!
+//! # Schemas
+//! Every type derives `JsonSchema`; [`api_schema`] and
+//! [`alert_webhook_schema`] generate JSON Schema (draft 2020-12) documents
+//! from them, with each struct a `$defs` entry of the same name. Optional
+//! values are serialized as `null`, never omitted, so every property is
+//! `required`. The generated documents are checked in under `schemas/`
+//! (also exposed as [`API_SCHEMA`] and [`ALERT_WEBHOOK_SCHEMA`]) for
+//! consumers that do not build Rust; regenerate them after changing a type:
+//!
+//! ```text
+//! cargo run --example write_schemas
+//! ```
+//!
+//! # Stability
+//! Within a [`SCHEMA_VERSION`] fields are only added, never renamed or
+//! removed; consumers should ignore properties they do not know.
+
+pub mod api;
+pub mod webhook;
+
+use schemars::generate::SchemaSettings;
+use schemars::SchemaGenerator;
+
+/// Version of the shapes in this crate
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema for the `api` types (generated by [`api_schema`])
+pub const API_SCHEMA: &str = include_str!("../schemas/api.schema.json");
+
+/// JSON Schema for `webhook::AlertWebhookPayload` (generated by [`alert_webhook_schema`])
+pub const ALERT_WEBHOOK_SCHEMA: &str = include_str!("../schemas/alert-webhook.schema.json");
+
+/// Draft 2020-12 generator describing what flomon serializes
+fn generator() -> SchemaGenerator {
+    SchemaSettings::draft2020_12().for_serialize().into_generator()
+}
+
+/// Pretty-printed document holding the generator's `$defs`, optionally
+/// with `root` as the top-level `$ref`
+fn document(title: &str, mut generator: SchemaGenerator, root: Option<&str>) -> String {
+    let mut doc = serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": title,
+        "$defs": generator.take_definitions(true),
+    });
+    if let Some(root) = root {
+        doc["$ref"] = format!("#/$defs/{}", root).into();
+    }
+    serde_json::to_string_pretty(&doc).expect("schema serializes") + "\n"
+}
+
+/// Generate the contents of `schemas/api.schema.json`
+pub fn api_schema() -> String {
+    let mut generator = generator();
+    generator.subschema_for::<api::ZonesListResponse>();
+    generator.subschema_for::<api::ZoneDetailResponse>();
+    generator.subschema_for::<api::BasinStatusResponse>();
+    generator.subschema_for::<api::EmbedSummaryResponse>();
+    generator.subschema_for::<api::SeveritySchemeResponse>();
+    generator.subschema_for::<api::ApiErrorResponse>();
+    document("flomon HTTP API responses", generator, None)
+}
+
+/// Generate the contents of `schemas/alert-webhook.schema.json`
+pub fn alert_webhook_schema() -> String {
+    let mut generator = generator();
+    generator.subschema_for::<webhook::AlertWebhookPayload>();
+    document("flomon alert webhook payload", generator, Some("AlertWebhookPayload"))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::webhook::AlertWebhookPayload;
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use serde_json::Value;
+
+    #[test]
+    fn test_checked_in_schemas_are_up_to_date() {
+        let stale = "is out of date; run `cargo run --example write_schemas`";
+        assert!(API_SCHEMA == api_schema(), "schemas/api.schema.json {}", stale);
+        assert!(ALERT_WEBHOOK_SCHEMA == alert_webhook_schema(), "schemas/alert-webhook.schema.json {}", stale);
+    }
+
+    #[test]
+    fn test_every_property_is_required() {
+        for schema in [api_schema(), alert_webhook_schema()] {
+            let schema: Value = serde_json::from_str(&schema).unwrap();
+            for (name, def) in schema["$defs"].as_object().unwrap() {
+                let props: Vec<&String> = def["properties"].as_object().unwrap().keys().collect();
+                let mut required: Vec<&str> = def["required"].as_array().unwrap()
+                    .iter().map(|v| v.as_str().unwrap()).collect();
+                required.sort();
+                assert_eq!(props, required, "{}: every property must be required", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_webhook_payload_round_trips() {
+        let mut payload = AlertWebhookPayload::new(
+            "flood",
+            "Kingston Mines at 16.4 ft (flood stage 16.0 ft)",
+            Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+        );
+        payload.site_code = Some("05568500".to_string());
+
+        let json = serde_json::to_string(&payload).unwrap();
+        assert!(json.contains("\"schema\":\"flomon.alert\""));
+        assert_eq!(serde_json::from_str::<AlertWebhookPayload>(&json).unwrap(), payload);
+    }
+}
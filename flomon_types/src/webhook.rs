@@ -0,0 +1,47 @@
+//! Alert webhook payload.
+//!
+//! POSTed as JSON to each configured webhook when an alert is delivered.
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::SCHEMA_VERSION;
+
+/// Value of `AlertWebhookPayload::schema`
+pub const ALERT_SCHEMA: &str = "flomon.alert";
+
+/// Body of an alert webhook
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AlertWebhookPayload {
+    /// Always "flomon.alert"
+    pub schema: String,
+    pub schema_version: u32,
+    /// Gauge the alert is about, if it concerns a single station
+    pub site_code: Option<String>,
+    pub site_name: Option<String>,
+    /// "action", "flood", "moderate", "major"
+    pub severity: String,
+    pub stage_ft: Option<f64>,
+    pub message: String,
+    /// Station registry version the alert was evaluated against
+    pub registry_version: Option<i32>,
+    pub issued_at: DateTime<Utc>,
+}
+
+impl AlertWebhookPayload {
+    /// Payload with the schema fields filled in and no station details
+    pub fn new(severity: &str, message: &str, issued_at: DateTime<Utc>) -> Self {
+        Self {
+            schema: ALERT_SCHEMA.to_string(),
+            schema_version: SCHEMA_VERSION,
+            site_code: None,
+            site_name: None,
+            severity: severity.to_string(),
+            stage_ft: None,
+            message: message.to_string(),
+            registry_version: None,
+            issued_at,
+        }
+    }
+}